@@ -363,6 +363,9 @@ pub fn build_submitted_transactions() -> String {
                         .unwrap();
 
                         write!(buf, "<td rowspan='{}'>", rowspan).unwrap();
+                        if tx.requests.is_empty() {
+                            write!(buf, "UTXO consolidation").unwrap();
+                        }
                         for req in &tx.requests {
                             write!(
                                 buf,
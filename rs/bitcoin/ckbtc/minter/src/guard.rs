@@ -112,6 +112,29 @@ impl Drop for DistributeKytFeeGuard {
     }
 }
 
+#[must_use]
+pub struct ConsolidateUtxosGuard(());
+
+impl ConsolidateUtxosGuard {
+    pub fn new() -> Option<Self> {
+        mutate_state(|s| {
+            if s.is_consolidating_utxos {
+                return None;
+            }
+            s.is_consolidating_utxos = true;
+            Some(ConsolidateUtxosGuard(()))
+        })
+    }
+}
+
+impl Drop for ConsolidateUtxosGuard {
+    fn drop(&mut self) {
+        mutate_state(|s| {
+            s.is_consolidating_utxos = false;
+        });
+    }
+}
+
 pub fn balance_update_guard(p: Principal) -> Result<Guard<PendingBalanceUpdates>, GuardError> {
     Guard::new(p)
 }
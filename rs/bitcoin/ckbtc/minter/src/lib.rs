@@ -58,6 +58,27 @@ pub const MIN_RELAY_FEE_PER_VBYTE: MillisatoshiPerByte = 1_000;
 /// The minimum time the minter should wait before replacing a stuck transaction.
 pub const MIN_RESUBMISSION_DELAY: Duration = Duration::from_secs(24 * 60 * 60);
 
+/// The minter consolidates its UTXOs when it holds more than this number of available UTXOs.
+pub const UTXOS_COUNT_CONSOLIDATION_THRESHOLD: usize = 1_000;
+
+/// The maximum number of UTXOs that the minter merges in a single consolidation transaction.
+pub const MAX_UTXOS_PER_CONSOLIDATION: usize = 100;
+
+/// The minter consolidates UTXOs only if the median fee does not exceed this value.
+pub const MAX_CONSOLIDATION_FEE_PER_VBYTE: MillisatoshiPerByte = 10_000;
+
+/// Having a sequence number lower than (0xffffffff - 1) signals the use of replacement by fee.
+/// It allows us to increase the fee of a transaction already sent to the mempool.
+/// The rbf option is used in `finalize_requests`.
+/// https://github.com/bitcoin/bips/blob/master/bip-0125.mediawiki
+const SEQUENCE_RBF_ENABLED: u32 = 0xfffffffd;
+
+/// The default dustRelayFee is 3 sat/vB,
+/// which translates to a dust threshold of 546 satoshi for P2PKH outputs.
+/// The threshold for other types is lower,
+/// so we simply use 546 satoshi as the minimum amount per output.
+const MIN_OUTPUT_AMOUNT: u64 = 546;
+
 /// The maximum memo size of a transaction on the ckBTC ledger.
 /// The ckBTC minter requires at least 69 bytes, we choose 80
 /// to have some room for future modifications.
@@ -603,8 +624,6 @@ async fn finalize_requests() {
     let key_name = state::read_state(|s| s.ecdsa_key_name.clone());
 
     for (old_txid, submitted_tx) in maybe_finalized_transactions {
-        let mut utxos: BTreeSet<_> = submitted_tx.used_utxos.iter().cloned().collect();
-
         let tx_fee_per_vbyte = match submitted_tx.fee_per_vbyte {
             Some(prev_fee) => {
                 // Ensure that the fee is at least min relay fee higher than the previous
@@ -614,40 +633,75 @@ async fn finalize_requests() {
             None => fee_per_vbyte,
         };
 
-        let outputs = submitted_tx
-            .requests
-            .iter()
-            .map(|req| (req.address.clone(), req.amount))
-            .collect();
-
-        let (unsigned_tx, change_output, used_utxos) = match build_unsigned_transaction(
-            &mut utxos,
-            outputs,
-            main_address.clone(),
-            tx_fee_per_vbyte,
-        ) {
-            Ok(tx) => tx,
-            // If it's impossible to build a new transaction, the fees probably became too high.
-            // Let's ignore this transaction and wait for fees to go down.
-            Err(err) => {
+        let (unsigned_tx, change_output, used_utxos) = if submitted_tx.requests.is_empty() {
+            // A consolidation transaction: it does not serve any request, so there is no rush
+            // to replace it while the fees are high.
+            if fee_per_vbyte > MAX_CONSOLIDATION_FEE_PER_VBYTE {
                 log!(
                     P1,
-                    "[finalize_requests]: failed to rebuild stuck transaction {}: {:?}",
+                    "[finalize_requests]: postponing the replacement of consolidation transaction {} until the fees go down",
                     &submitted_tx.txid,
-                    err
                 );
                 continue;
             }
+            match build_consolidation_transaction(
+                &submitted_tx.used_utxos,
+                main_address.clone(),
+                tx_fee_per_vbyte,
+            ) {
+                Ok((unsigned_tx, change_output)) => {
+                    (unsigned_tx, change_output, submitted_tx.used_utxos.clone())
+                }
+                Err(err) => {
+                    log!(
+                        P1,
+                        "[finalize_requests]: failed to rebuild stuck consolidation transaction {}: {:?}",
+                        &submitted_tx.txid,
+                        err
+                    );
+                    continue;
+                }
+            }
+        } else {
+            let mut utxos: BTreeSet<_> = submitted_tx.used_utxos.iter().cloned().collect();
+
+            let outputs = submitted_tx
+                .requests
+                .iter()
+                .map(|req| (req.address.clone(), req.amount))
+                .collect();
+
+            let (unsigned_tx, change_output, used_utxos) = match build_unsigned_transaction(
+                &mut utxos,
+                outputs,
+                main_address.clone(),
+                tx_fee_per_vbyte,
+            ) {
+                Ok(tx) => tx,
+                // If it's impossible to build a new transaction, the fees probably became too high.
+                // Let's ignore this transaction and wait for fees to go down.
+                Err(err) => {
+                    log!(
+                        P1,
+                        "[finalize_requests]: failed to rebuild stuck transaction {}: {:?}",
+                        &submitted_tx.txid,
+                        err
+                    );
+                    continue;
+                }
+            };
+
+            assert!(
+                utxos.is_empty(),
+                "build_unsigned_transaction didn't use all inputs"
+            );
+            assert_eq!(used_utxos.len(), submitted_tx.used_utxos.len());
+
+            (unsigned_tx, change_output, used_utxos)
         };
 
         let outpoint_account = state::read_state(|s| filter_output_accounts(s, &unsigned_tx));
 
-        assert!(
-            utxos.is_empty(),
-            "build_unsigned_transaction didn't use all inputs"
-        );
-        assert_eq!(used_utxos.len(), submitted_tx.used_utxos.len());
-
         let new_txid = unsigned_tx.txid();
 
         let maybe_signed_tx = sign_transaction(
@@ -715,6 +769,158 @@ async fn finalize_requests() {
     }
 }
 
+/// Merges the smallest UTXOs of the minter into a single output to its main address.
+///
+/// The minter consolidates UTXOs only if it holds many of them and the fees are low,
+/// so that later withdrawals need fewer inputs. At most one consolidation
+/// transaction is pending at any time. If the transaction gets stuck,
+/// `finalize_requests` replaces it like any other minter transaction.
+async fn consolidate_utxos() {
+    if let Err(reason) = state::read_state(|s| s.mode.is_consolidation_available()) {
+        log!(
+            P1,
+            "[consolidate_utxos]: skipping consolidation: {}",
+            reason
+        );
+        return;
+    }
+
+    let should_consolidate = state::read_state(|s| {
+        s.available_utxos.len() > UTXOS_COUNT_CONSOLIDATION_THRESHOLD
+            && !s
+                .submitted_transactions
+                .iter()
+                .any(|tx| tx.requests.is_empty())
+    });
+    if !should_consolidate {
+        return;
+    }
+
+    let fee_per_vbyte = match estimate_fee_per_vbyte().await {
+        Some(fee) => fee,
+        None => return,
+    };
+    if fee_per_vbyte > MAX_CONSOLIDATION_FEE_PER_VBYTE {
+        log!(
+            P1,
+            "[consolidate_utxos]: skipping consolidation, the fee of {} millisatoshi/vbyte is too high",
+            fee_per_vbyte
+        );
+        return;
+    }
+
+    let main_account = Account {
+        owner: ic_cdk::id(),
+        subaccount: None,
+    };
+
+    let ecdsa_public_key = updates::get_btc_address::init_ecdsa_public_key().await;
+    let main_address = address::account_to_bitcoin_address(&ecdsa_public_key, &main_account);
+
+    let maybe_sign_request = state::mutate_state(|s| {
+        let utxos = select_utxos_to_consolidate(
+            &mut s.available_utxos,
+            MAX_UTXOS_PER_CONSOLIDATION,
+            fee_per_vbyte,
+        );
+        if utxos.is_empty() {
+            log!(
+                P1,
+                "[consolidate_utxos]: not enough UTXOs worth more than their spending cost at {} millisatoshi/vbyte",
+                fee_per_vbyte
+            );
+            return None;
+        }
+        match build_consolidation_transaction(&utxos, main_address, fee_per_vbyte) {
+            Ok((unsigned_tx, change_output)) => Some(SignTxRequest {
+                key_name: s.ecdsa_key_name.clone(),
+                ecdsa_public_key,
+                change_output,
+                outpoint_account: filter_output_accounts(s, &unsigned_tx),
+                network: s.btc_network,
+                unsigned_tx,
+                requests: vec![],
+                utxos,
+            }),
+            Err(err) => {
+                log!(
+                    P1,
+                    "[consolidate_utxos]: failed to build a consolidation transaction: {:?}",
+                    err
+                );
+                for utxo in utxos {
+                    assert!(s.available_utxos.insert(utxo));
+                }
+                None
+            }
+        }
+    });
+
+    let req = match maybe_sign_request {
+        Some(req) => req,
+        None => return,
+    };
+
+    // This guard ensures that we return the UTXOs back to the state if the
+    // signing or sending a transaction fails or panics.
+    let utxos_guard = guard(req.utxos, |utxos| undo_sign_request(vec![], utxos));
+
+    let txid = req.unsigned_tx.txid();
+
+    let signed_tx = match sign_transaction(
+        req.key_name,
+        &req.ecdsa_public_key,
+        &req.outpoint_account,
+        req.unsigned_tx,
+    )
+    .await
+    {
+        Ok(signed_tx) => signed_tx,
+        Err(err) => {
+            log!(
+                P0,
+                "[consolidate_utxos]: failed to sign a BTC transaction: {}",
+                err
+            );
+            return;
+        }
+    };
+
+    match management::send_transaction(&signed_tx, req.network).await {
+        Ok(()) => {
+            log!(
+                P0,
+                "[consolidate_utxos]: sent transaction {} consolidating {} UTXOs",
+                &txid,
+                utxos_guard.len(),
+            );
+
+            let used_utxos = ScopeGuard::into_inner(utxos_guard);
+
+            state::mutate_state(|s| {
+                state::audit::sent_transaction(
+                    s,
+                    state::SubmittedBtcTransaction {
+                        requests: vec![],
+                        txid,
+                        used_utxos,
+                        change_output: Some(req.change_output),
+                        submitted_at: ic_cdk::api::time(),
+                        fee_per_vbyte: Some(fee_per_vbyte),
+                    },
+                );
+            });
+        }
+        Err(err) => {
+            log!(
+                P0,
+                "[consolidate_utxos]: failed to send a bitcoin transaction: {}",
+                err
+            );
+        }
+    }
+}
+
 /// Builds the minimal OutPoint -> Account map required to sign a transaction.
 fn filter_output_accounts(
     state: &state::CkBtcMinterState,
@@ -778,6 +984,39 @@ fn greedy(target: u64, available_utxos: &mut BTreeSet<Utxo>) -> Vec<Utxo> {
     solution
 }
 
+/// Removes at most `max_utxos` UTXOs with the smallest values from the available set
+/// and returns them.
+///
+/// UTXOs worth less than the fee required to spend them at `fee_per_vbyte` are never
+/// selected. If the selected UTXOs cannot pay for a consolidation transaction, the
+/// function leaves the available set untouched and returns an empty vector.
+fn select_utxos_to_consolidate(
+    available_utxos: &mut BTreeSet<Utxo>,
+    max_utxos: usize,
+    fee_per_vbyte: u64,
+) -> Vec<Utxo> {
+    let input_cost = (tx_vsize_estimate(1, 0) - tx_vsize_estimate(0, 0)) * fee_per_vbyte / 1000;
+    let overhead_cost = tx_vsize_estimate(0, 1) * fee_per_vbyte / 1000;
+
+    let mut solution: Vec<Utxo> = available_utxos
+        .iter()
+        .filter(|u| u.value > input_cost)
+        .cloned()
+        .collect();
+    solution.sort_by_key(|u| u.value);
+    solution.truncate(max_utxos);
+
+    let surplus = solution.iter().map(|u| u.value - input_cost).sum::<u64>();
+    if solution.len() < 2 || surplus < overhead_cost + MIN_OUTPUT_AMOUNT {
+        return vec![];
+    }
+
+    for utxo in solution.iter() {
+        assert!(available_utxos.remove(utxo));
+    }
+    solution
+}
+
 /// Gathers ECDSA signatures for all the inputs in the specified unsigned
 /// transaction.
 ///
@@ -913,12 +1152,6 @@ pub fn build_unsigned_transaction(
 ) -> Result<(tx::UnsignedTransaction, state::ChangeOutput, Vec<Utxo>), BuildTxError> {
     assert!(!outputs.is_empty());
 
-    /// Having a sequence number lower than (0xffffffff - 1) signals the use of replacement by fee.
-    /// It allows us to increase the fee of a transaction already sent to the mempool.
-    /// The rbf option is used in `resubmit_retrieve_btc`.
-    /// https://github.com/bitcoin/bips/blob/master/bip-0125.mediawiki
-    const SEQUENCE_RBF_ENABLED: u32 = 0xfffffffd;
-
     let amount = outputs.iter().map(|(_, amount)| amount).sum::<u64>();
 
     let input_utxos = greedy(amount, minter_utxos);
@@ -987,11 +1220,6 @@ pub fn build_unsigned_transaction(
     }
 
     let fee_shares = distribute(fee + minter_fee, outputs.len() as u64);
-    // The default dustRelayFee is 3 sat/vB,
-    // which translates to a dust threshold of 546 satoshi for P2PKH outputs.
-    // The threshold for other types is lower,
-    // so we simply use 546 satoshi as the minimum amount per output.
-    const MIN_OUTPUT_AMOUNT: u64 = 546;
 
     for (output, fee_share) in unsigned_tx.outputs.iter_mut().zip(fee_shares.iter()) {
         if output.address != main_address {
@@ -1017,6 +1245,55 @@ pub fn build_unsigned_transaction(
     ))
 }
 
+/// Builds a transaction that merges the specified minter UTXOs into a single
+/// output to the minter main address. The minter pays the fee.
+///
+/// # Panics
+///
+/// This function panics if the `input_utxos` slice is empty as it indicates a bug
+/// in the caller's code.
+pub fn build_consolidation_transaction(
+    input_utxos: &[Utxo],
+    main_address: BitcoinAddress,
+    fee_per_vbyte: u64,
+) -> Result<(tx::UnsignedTransaction, state::ChangeOutput), BuildTxError> {
+    assert!(!input_utxos.is_empty());
+
+    let inputs_value = input_utxos.iter().map(|u| u.value).sum::<u64>();
+
+    let mut unsigned_tx = tx::UnsignedTransaction {
+        inputs: input_utxos
+            .iter()
+            .map(|utxo| tx::UnsignedInput {
+                previous_output: utxo.outpoint.clone(),
+                value: utxo.value,
+                sequence: SEQUENCE_RBF_ENABLED,
+            })
+            .collect(),
+        outputs: vec![tx::TxOut {
+            address: main_address,
+            value: inputs_value,
+        }],
+        lock_time: 0,
+    };
+
+    let tx_vsize = fake_sign(&unsigned_tx).vsize();
+    let fee = (tx_vsize as u64 * fee_per_vbyte) / 1000;
+
+    if fee + MIN_OUTPUT_AMOUNT > inputs_value {
+        return Err(BuildTxError::AmountTooLow);
+    }
+
+    unsigned_tx.outputs[0].value = inputs_value - fee;
+
+    let change_output = state::ChangeOutput {
+        vout: 0,
+        value: inputs_value - fee,
+    };
+
+    Ok((unsigned_tx, change_output))
+}
+
 /// Distributes an amount across the specified number of shares as fairly as
 /// possible.
 ///
@@ -1150,6 +1427,22 @@ pub fn timer() {
                 schedule_after(FEE_ESTIMATE_DELAY, TaskType::RefreshFeePercentiles);
             });
         }
+        TaskType::ConsolidateUtxos => {
+            ic_cdk::spawn(async {
+                let _guard = match crate::guard::ConsolidateUtxosGuard::new() {
+                    Some(guard) => guard,
+                    None => return,
+                };
+
+                const CONSOLIDATION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+                let _enqueue_followup_guard = guard((), |_| {
+                    schedule_after(CONSOLIDATION_INTERVAL, TaskType::ConsolidateUtxos)
+                });
+
+                consolidate_utxos().await;
+            });
+        }
         TaskType::DistributeKytFee => {
             ic_cdk::spawn(async {
                 let _guard = match crate::guard::DistributeKytFeeGuard::new() {
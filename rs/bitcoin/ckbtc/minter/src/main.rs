@@ -9,7 +9,7 @@ use ic_ckbtc_minter::lifecycle::{self, init::MinterArg};
 use ic_ckbtc_minter::metrics::encode_metrics;
 use ic_ckbtc_minter::queries::{EstimateFeeArg, RetrieveBtcStatusRequest, WithdrawalFee};
 use ic_ckbtc_minter::state::{
-    read_state, BtcRetrievalStatusV2, RetrieveBtcStatus, RetrieveBtcStatusV2,
+    read_state, BtcRetrievalStatusV2, RetrieveBtcStatus, RetrieveBtcStatusV2, WithdrawalSummary,
 };
 use ic_ckbtc_minter::tasks::{schedule_now, TaskType};
 use ic_ckbtc_minter::updates::retrieve_btc::{
@@ -23,7 +23,7 @@ use ic_ckbtc_minter::updates::{
 };
 use ic_ckbtc_minter::MinterInfo;
 use ic_ckbtc_minter::{
-    state::eventlog::{Event, GetEventsArg},
+    state::eventlog::{Event, GetEventsArg, GetFilteredEventsArg, GetFilteredEventsResult},
     storage, {Log, LogEntry, Priority},
 };
 use icrc_ledger_types::icrc1::account::Account;
@@ -38,6 +38,7 @@ fn init(args: MinterArg) {
             schedule_now(TaskType::ProcessLogic);
             schedule_now(TaskType::RefreshFeePercentiles);
             schedule_now(TaskType::DistributeKytFee);
+            schedule_now(TaskType::ConsolidateUtxos);
 
             #[cfg(feature = "self_check")]
             ok_or_die(check_invariants())
@@ -130,6 +131,7 @@ fn post_upgrade(minter_arg: Option<MinterArg>) {
     schedule_now(TaskType::ProcessLogic);
     schedule_now(TaskType::RefreshFeePercentiles);
     schedule_now(TaskType::DistributeKytFee);
+    schedule_now(TaskType::ConsolidateUtxos);
 }
 
 #[candid_method(update)]
@@ -180,6 +182,16 @@ fn retrieve_btc_status_v2_by_account(target: Option<Account>) -> Vec<BtcRetrieva
     read_state(|s| s.retrieve_btc_status_v2_by_account(target))
 }
 
+#[candid_method(query)]
+#[query]
+fn retrieve_btc_summary_by_account(target: Option<Account>) -> WithdrawalSummary {
+    let account = target.unwrap_or(Account {
+        owner: ic_cdk::caller(),
+        subaccount: None,
+    });
+    read_state(|s| s.retrieve_btc_summary(&account))
+}
+
 #[candid_method(update)]
 #[update]
 async fn update_balance(args: UpdateBalanceArgs) -> Result<Vec<UtxoStatus>, UpdateBalanceError> {
@@ -315,6 +327,26 @@ fn get_events(args: GetEventsArg) -> Vec<Event> {
         .collect()
 }
 
+#[candid_method(query)]
+#[query]
+fn get_filtered_events(args: GetFilteredEventsArg) -> GetFilteredEventsResult {
+    const MAX_EVENTS_PER_QUERY: usize = 2000;
+    const MAX_SCANNED_EVENTS_PER_QUERY: usize = 100_000;
+
+    let account_block_indices = match &args.filter.account {
+        Some(account) => read_state(|s| s.retrieve_btc_block_indices(account)),
+        None => Default::default(),
+    };
+    ic_ckbtc_minter::state::eventlog::filter_events(
+        storage::events(),
+        args.start,
+        MAX_EVENTS_PER_QUERY.min(args.length as usize),
+        MAX_SCANNED_EVENTS_PER_QUERY,
+        &args.filter,
+        &account_block_indices,
+    )
+}
+
 #[cfg(feature = "self_check")]
 #[query]
 fn self_check() -> Result<(), String> {
@@ -110,6 +110,12 @@ pub fn encode_metrics(
         "Total number of burned tokens.",
     )?;
 
+    metrics.encode_counter(
+        "ckbtc_minter_consolidation_fees_paid",
+        state::read_state(|s| s.consolidation_fees_paid) as f64,
+        "Total amount of BTC (in satoshi) paid in fees for confirmed UTXO consolidation transactions.",
+    )?;
+
     metrics.encode_gauge(
         "ckbtc_minter_min_retrievable_amount",
         state::read_state(|s| s.retrieve_btc_min_amount) as f64,
@@ -179,6 +179,27 @@ pub enum RetrieveBtcStatusV2 {
     WillReimburse(ReimburseDepositTask),
 }
 
+/// The aggregated state of all retrieve_btc requests of an account.
+/// Each field lists the block indices of the burn transactions of the
+/// requests in that state.
+#[derive(CandidType, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct WithdrawalSummary {
+    /// Requests that the minter has not yet sent to the Bitcoin network.
+    pub pending: Vec<u64>,
+    /// Requests whose transaction awaits confirmations.
+    pub submitted: Vec<u64>,
+    /// Requests whose transaction the minter considers final.
+    pub confirmed: Vec<u64>,
+    /// Requests with an amount too low to be satisfied.
+    pub amount_too_low: Vec<u64>,
+    /// Requests that the minter will reimburse.
+    pub reimbursement_scheduled: Vec<u64>,
+    /// Requests that the minter reimbursed.
+    pub reimbursed: Vec<u64>,
+    /// Requests for which the minter has no data anymore.
+    pub unknown: Vec<u64>,
+}
+
 /// Controls which operations the minter can perform.
 #[derive(candid::CandidType, Clone, Debug, PartialEq, Eq, serde::Deserialize, Serialize)]
 pub enum Mode {
@@ -213,6 +234,17 @@ impl Mode {
         }
     }
 
+    /// Returns Ok if the minter can consolidate its UTXOs.
+    pub fn is_consolidation_available(&self) -> Result<(), String> {
+        match self {
+            Self::GeneralAvailability | Self::DepositsRestrictedTo(_) => Ok(()),
+            Self::ReadOnly => Err("the minter is in read-only mode".to_string()),
+            Self::RestrictedTo(_) => {
+                Err("access to the minter is temporarily restricted".to_string())
+            }
+        }
+    }
+
     /// Returns Ok if the specified principal can convert ckBTC to BTC.
     pub fn is_withdrawal_available_for(&self, p: &Principal) -> Result<(), String> {
         match self {
@@ -326,6 +358,10 @@ pub struct CkBtcMinterState {
     /// The total amount of ckBTC burned.
     pub tokens_burned: u64,
 
+    /// The total amount of BTC (in satoshi) the minter paid in fees for
+    /// confirmed UTXO consolidation transactions.
+    pub consolidation_fees_paid: u64,
+
     /// The CanisterId of the ckBTC Ledger.
     pub ledger_id: CanisterId,
 
@@ -359,6 +395,9 @@ pub struct CkBtcMinterState {
     #[serde(skip)]
     pub is_distributing_fee: bool,
 
+    #[serde(skip)]
+    pub is_consolidating_utxos: bool,
+
     /// The mode in which the minter runs.
     pub mode: Mode,
 
@@ -625,6 +664,39 @@ impl CkBtcMinterState {
         result
     }
 
+    /// Returns the block indices of the retrieve_btc requests of the account.
+    pub fn retrieve_btc_block_indices(&self, account: &Account) -> BTreeSet<u64> {
+        self.retrieve_btc_account_to_block_indices
+            .get(account)
+            .map(|indices| indices.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Groups the retrieve_btc requests of the account by their status.
+    pub fn retrieve_btc_summary(&self, account: &Account) -> WithdrawalSummary {
+        let mut summary = WithdrawalSummary::default();
+        for &block_index in self
+            .retrieve_btc_account_to_block_indices
+            .get(account)
+            .into_iter()
+            .flatten()
+        {
+            let bucket = match self.retrieve_btc_status_v2(block_index) {
+                RetrieveBtcStatusV2::Pending
+                | RetrieveBtcStatusV2::Signing
+                | RetrieveBtcStatusV2::Sending { .. } => &mut summary.pending,
+                RetrieveBtcStatusV2::Submitted { .. } => &mut summary.submitted,
+                RetrieveBtcStatusV2::Confirmed { .. } => &mut summary.confirmed,
+                RetrieveBtcStatusV2::AmountTooLow => &mut summary.amount_too_low,
+                RetrieveBtcStatusV2::WillReimburse(_) => &mut summary.reimbursement_scheduled,
+                RetrieveBtcStatusV2::Reimbursed(_) => &mut summary.reimbursed,
+                RetrieveBtcStatusV2::Unknown => &mut summary.unknown,
+            };
+            bucket.push(block_index);
+        }
+        summary
+    }
+
     pub fn retrieve_btc_status_v2(&self, block_index: u64) -> RetrieveBtcStatusV2 {
         if let Some(reimbursement) = self.pending_reimbursements.get(&block_index) {
             return RetrieveBtcStatusV2::WillReimburse(reimbursement.clone());
@@ -752,6 +824,22 @@ impl CkBtcMinterState {
         }
     }
 
+    /// Returns the Bitcoin fee of the specified transaction if it is a pending
+    /// transaction consolidating the minter's UTXOs.
+    pub(crate) fn consolidation_fee(&self, txid: &Txid) -> Option<u64> {
+        let tx = self
+            .submitted_transactions
+            .iter()
+            .chain(self.stuck_transactions.iter())
+            .find(|tx| &tx.txid == txid)?;
+        if !tx.requests.is_empty() {
+            return None;
+        }
+        let inputs_value = tx.used_utxos.iter().map(|u| u.value).sum::<u64>();
+        let change_value = tx.change_output.as_ref().map_or(0, |out| out.value);
+        Some(inputs_value.saturating_sub(change_value))
+    }
+
     pub(crate) fn finalize_transaction(&mut self, txid: &Txid) {
         let finalized_tx = if let Some(pos) = self
             .submitted_transactions
@@ -1135,6 +1223,12 @@ impl CkBtcMinterState {
             "owed_kyt_amount does not match"
         );
 
+        ensure_eq!(
+            self.consolidation_fees_paid,
+            other.consolidation_fees_paid,
+            "consolidation_fees_paid does not match"
+        );
+
         ensure_eq!(
             self.kyt_principal,
             other.kyt_principal,
@@ -1216,6 +1310,7 @@ impl From<InitArgs> for CkBtcMinterState {
             finalized_requests_count: 0,
             tokens_minted: 0,
             tokens_burned: 0,
+            consolidation_fees_paid: 0,
             ledger_id: args.ledger_id,
             kyt_principal: args.kyt_principal,
             available_utxos: Default::default(),
@@ -1224,6 +1319,7 @@ impl From<InitArgs> for CkBtcMinterState {
             finalized_utxos: Default::default(),
             is_timer_running: false,
             is_distributing_fee: false,
+            is_consolidating_utxos: false,
             mode: args.mode,
             last_fee_per_vbyte: vec![1; 100],
             kyt_fee: args
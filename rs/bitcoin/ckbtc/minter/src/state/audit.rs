@@ -67,7 +67,12 @@ pub fn sent_transaction(state: &mut CkBtcMinterState, tx: SubmittedBtcTransactio
 
 pub fn confirm_transaction(state: &mut CkBtcMinterState, txid: &Txid) {
     record_event(&Event::ConfirmedBtcTransaction { txid: *txid });
+    let consolidation_fee = state.consolidation_fee(txid);
     state.finalize_transaction(txid);
+    if let Some(fee) = consolidation_fee {
+        record_event(&Event::PaidConsolidationFee { txid: *txid, fee });
+        state.consolidation_fees_paid += fee;
+    }
 }
 
 pub fn mark_utxo_checked(
@@ -9,6 +9,7 @@ use candid::Principal;
 use ic_btc_interface::{Txid, Utxo};
 use icrc_ledger_types::icrc1::account::Account;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 #[derive(candid::CandidType, Deserialize)]
 pub struct GetEventsArg {
@@ -16,6 +17,126 @@ pub struct GetEventsArg {
     pub length: u64,
 }
 
+#[derive(candid::CandidType, Deserialize)]
+pub struct GetFilteredEventsArg {
+    /// The position in the event log at which the minter starts looking for matching events.
+    pub start: u64,
+    /// The maximum number of matching events to return.
+    pub length: u64,
+    pub filter: EventFilter,
+}
+
+#[derive(candid::CandidType, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct GetFilteredEventsResult {
+    pub events: Vec<IndexedEvent>,
+    /// The position from which the caller should continue the search, if the minter
+    /// stopped before reaching the end of the event log.
+    pub next_start: Option<u64>,
+}
+
+/// An event together with its position in the event log.
+#[derive(candid::CandidType, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct IndexedEvent {
+    pub index: u64,
+    pub event: Event,
+}
+
+/// The kind of an [Event], ignoring its payload.
+#[derive(candid::CandidType, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub enum EventType {
+    #[serde(rename = "init")]
+    Init,
+    #[serde(rename = "upgrade")]
+    Upgrade,
+    #[serde(rename = "received_utxos")]
+    ReceivedUtxos,
+    #[serde(rename = "accepted_retrieve_btc_request")]
+    AcceptedRetrieveBtcRequest,
+    #[serde(rename = "removed_retrieve_btc_request")]
+    RemovedRetrieveBtcRequest,
+    #[serde(rename = "sent_transaction")]
+    SentBtcTransaction,
+    #[serde(rename = "replaced_transaction")]
+    ReplacedBtcTransaction,
+    #[serde(rename = "confirmed_transaction")]
+    ConfirmedBtcTransaction,
+    #[serde(rename = "checked_utxo")]
+    CheckedUtxo,
+    #[serde(rename = "ignored_utxo")]
+    IgnoredUtxo,
+    #[serde(rename = "distributed_kyt_fee")]
+    DistributedKytFee,
+    #[serde(rename = "retrieve_btc_kyt_failed")]
+    RetrieveBtcKytFailed,
+    #[serde(rename = "schedule_deposit_reimbursement")]
+    ScheduleDepositReimbursement,
+    #[serde(rename = "reimbursed_failed_deposit")]
+    ReimbursedFailedDeposit,
+    #[serde(rename = "paid_consolidation_fee")]
+    PaidConsolidationFee,
+}
+
+/// Selects events from the event log. An event matches the filter if it matches
+/// all the specified criteria.
+#[derive(candid::CandidType, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct EventFilter {
+    /// Only events of one of these types.
+    pub event_types: Option<Vec<EventType>>,
+    /// Only events involving this account, either directly or through one of
+    /// the account's retrieve_btc requests.
+    pub account: Option<Account>,
+    /// Only events referring to this ledger block index.
+    pub block_index: Option<u64>,
+    /// Only events with a timestamp (in nanoseconds since the UNIX epoch) at
+    /// or after this time. Events without a timestamp never match.
+    pub from_timestamp: Option<u64>,
+    /// Only events with a timestamp (in nanoseconds since the UNIX epoch)
+    /// strictly before this time. Events without a timestamp never match.
+    pub to_timestamp: Option<u64>,
+}
+
+impl EventFilter {
+    /// Returns true if the event matches the filter.
+    ///
+    /// `account_block_indices` are the block indices of the retrieve_btc
+    /// requests of the filtered account, if any.
+    pub fn matches(&self, event: &Event, account_block_indices: &BTreeSet<u64>) -> bool {
+        if let Some(event_types) = &self.event_types {
+            if !event_types.contains(&event.event_type()) {
+                return false;
+            }
+        }
+        let block_indices = event.block_indices();
+        if let Some(block_index) = self.block_index {
+            if !block_indices.contains(&block_index) {
+                return false;
+            }
+        }
+        if let Some(account) = &self.account {
+            let involves_account = event.account().as_ref() == Some(account)
+                || block_indices
+                    .iter()
+                    .any(|index| account_block_indices.contains(index));
+            if !involves_account {
+                return false;
+            }
+        }
+        if self.from_timestamp.is_some() || self.to_timestamp.is_some() {
+            match event.timestamp() {
+                Some(ts) => {
+                    if self.from_timestamp.map_or(false, |from| ts < from)
+                        || self.to_timestamp.map_or(false, |to| ts >= to)
+                    {
+                        return false;
+                    }
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+}
+
 #[derive(candid::CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Event {
     /// Indicates the minter initialization with the specified arguments.  Must be
@@ -59,6 +180,7 @@ pub enum Event {
 
     /// Indicates that the minter sent out a new transaction to the Bitcoin
     /// network.
+    /// A transaction without requests consolidates the minter's UTXOs.
     #[serde(rename = "sent_transaction")]
     SentBtcTransaction {
         /// Block indices of retrieve_btc requests that caused the transaction.
@@ -177,6 +299,124 @@ pub enum Event {
         /// The mint block on the ledger.
         mint_block_index: u64,
     },
+
+    /// Indicates that a transaction consolidating the minter's UTXOs was
+    /// confirmed and the minter paid its Bitcoin fee out of the BTC reserve.
+    #[serde(rename = "paid_consolidation_fee")]
+    PaidConsolidationFee {
+        /// The Txid of the confirmed consolidation transaction.
+        #[serde(rename = "txid")]
+        txid: Txid,
+        /// The fee (in satoshi) paid for the transaction.
+        #[serde(rename = "fee")]
+        fee: u64,
+    },
+}
+
+impl Event {
+    pub fn event_type(&self) -> EventType {
+        match self {
+            Event::Init(_) => EventType::Init,
+            Event::Upgrade(_) => EventType::Upgrade,
+            Event::ReceivedUtxos { .. } => EventType::ReceivedUtxos,
+            Event::AcceptedRetrieveBtcRequest(_) => EventType::AcceptedRetrieveBtcRequest,
+            Event::RemovedRetrieveBtcRequest { .. } => EventType::RemovedRetrieveBtcRequest,
+            Event::SentBtcTransaction { .. } => EventType::SentBtcTransaction,
+            Event::ReplacedBtcTransaction { .. } => EventType::ReplacedBtcTransaction,
+            Event::ConfirmedBtcTransaction { .. } => EventType::ConfirmedBtcTransaction,
+            Event::CheckedUtxo { .. } => EventType::CheckedUtxo,
+            Event::IgnoredUtxo { .. } => EventType::IgnoredUtxo,
+            Event::DistributedKytFee { .. } => EventType::DistributedKytFee,
+            Event::RetrieveBtcKytFailed { .. } => EventType::RetrieveBtcKytFailed,
+            Event::ScheduleDepositReimbursement { .. } => EventType::ScheduleDepositReimbursement,
+            Event::ReimbursedFailedDeposit { .. } => EventType::ReimbursedFailedDeposit,
+            Event::PaidConsolidationFee { .. } => EventType::PaidConsolidationFee,
+        }
+    }
+
+    /// Returns the ledger block indices that the event refers to.
+    pub fn block_indices(&self) -> Vec<u64> {
+        match self {
+            Event::ReceivedUtxos { mint_txid, .. } => mint_txid.iter().cloned().collect(),
+            Event::AcceptedRetrieveBtcRequest(request) => vec![request.block_index],
+            Event::RemovedRetrieveBtcRequest { block_index }
+            | Event::DistributedKytFee { block_index, .. }
+            | Event::RetrieveBtcKytFailed { block_index, .. } => vec![*block_index],
+            Event::SentBtcTransaction {
+                request_block_indices,
+                ..
+            } => request_block_indices.clone(),
+            Event::ScheduleDepositReimbursement {
+                burn_block_index, ..
+            } => vec![*burn_block_index],
+            Event::ReimbursedFailedDeposit {
+                burn_block_index,
+                mint_block_index,
+            } => vec![*burn_block_index, *mint_block_index],
+            Event::Init(_)
+            | Event::Upgrade(_)
+            | Event::ReplacedBtcTransaction { .. }
+            | Event::ConfirmedBtcTransaction { .. }
+            | Event::CheckedUtxo { .. }
+            | Event::IgnoredUtxo { .. }
+            | Event::PaidConsolidationFee { .. } => vec![],
+        }
+    }
+
+    /// Returns the account that the event explicitly refers to, if any.
+    pub fn account(&self) -> Option<Account> {
+        match self {
+            Event::ReceivedUtxos { to_account, .. } => Some(*to_account),
+            Event::AcceptedRetrieveBtcRequest(request) => request.reimbursement_account,
+            Event::RetrieveBtcKytFailed { owner, .. } => Some(Account {
+                owner: *owner,
+                subaccount: None,
+            }),
+            Event::ScheduleDepositReimbursement { account, .. } => Some(*account),
+            _ => None,
+        }
+    }
+
+    /// Returns the IC time recorded in the event, if any.
+    pub fn timestamp(&self) -> Option<u64> {
+        match self {
+            Event::AcceptedRetrieveBtcRequest(request) => Some(request.received_at),
+            Event::SentBtcTransaction { submitted_at, .. }
+            | Event::ReplacedBtcTransaction { submitted_at, .. } => Some(*submitted_at),
+            _ => None,
+        }
+    }
+}
+
+/// Returns at most `length` events matching the filter, starting the search
+/// at position `start` and scanning at most `max_scanned` events.
+pub fn filter_events(
+    events: impl Iterator<Item = Event>,
+    start: u64,
+    length: usize,
+    max_scanned: usize,
+    filter: &EventFilter,
+    account_block_indices: &BTreeSet<u64>,
+) -> GetFilteredEventsResult {
+    let mut result = GetFilteredEventsResult {
+        events: vec![],
+        next_start: None,
+    };
+    let mut events = (start..).zip(events.skip(start as usize)).peekable();
+    let mut scanned = 0;
+    while result.events.len() < length && scanned < max_scanned {
+        match events.next() {
+            Some((index, event)) => {
+                scanned += 1;
+                if filter.matches(&event, account_block_indices) {
+                    result.events.push(IndexedEvent { index, event });
+                }
+            }
+            None => return result,
+        }
+    }
+    result.next_start = events.peek().map(|(index, _)| *index);
+    result
 }
 
 #[derive(Debug)]
@@ -369,6 +609,9 @@ pub fn replay(mut events: impl Iterator<Item = Event>) -> Result<CkBtcMinterStat
                     },
                 );
             }
+            Event::PaidConsolidationFee { fee, .. } => {
+                state.consolidation_fees_paid += fee;
+            }
         }
     }
 
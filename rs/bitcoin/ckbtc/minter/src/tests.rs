@@ -1,11 +1,13 @@
 use crate::MINTER_FEE_CONSTANT;
 use crate::{
-    address::BitcoinAddress, build_unsigned_transaction, estimate_fee, fake_sign, greedy,
-    signature::EncodedSignature, tx, BuildTxError,
+    address::BitcoinAddress, build_consolidation_transaction, build_unsigned_transaction,
+    estimate_fee, fake_sign, greedy, select_utxos_to_consolidate, signature::EncodedSignature, tx,
+    BuildTxError,
 };
 use crate::{
     lifecycle::init::InitArgs,
     state::{
+        eventlog::{filter_events, Event, EventFilter, EventType},
         ChangeOutput, CkBtcMinterState, Mode, RetrieveBtcRequest, RetrieveBtcStatus,
         SubmittedBtcTransaction,
     },
@@ -297,6 +299,227 @@ fn test_no_dust_outputs() {
     assert_eq!(available_utxos.len(), 1);
 }
 
+#[test]
+fn should_select_smallest_utxos_to_consolidate() {
+    let mut available_utxos: BTreeSet<_> = [50_000, 10_000, 40_000, 20_000, 30_000]
+        .into_iter()
+        .map(dummy_utxo_from_value)
+        .collect();
+
+    let selected = select_utxos_to_consolidate(&mut available_utxos, 3, 1_000);
+
+    assert_eq!(
+        selected.iter().map(|u| u.value).collect::<Vec<_>>(),
+        vec![10_000, 20_000, 30_000]
+    );
+    assert_eq!(
+        available_utxos
+            .iter()
+            .map(|u| u.value)
+            .collect::<BTreeSet<_>>(),
+        BTreeSet::from([40_000, 50_000])
+    );
+}
+
+#[test]
+fn should_not_select_utxos_worth_less_than_their_spending_cost() {
+    // Spending an input costs 68 vbytes * 10 sat/vbyte = 680 satoshi.
+    let mut available_utxos: BTreeSet<_> = [100, 680, 681, 20_000, 30_000, 40_000]
+        .into_iter()
+        .map(dummy_utxo_from_value)
+        .collect();
+
+    let selected = select_utxos_to_consolidate(&mut available_utxos, 3, 10_000);
+
+    assert_eq!(
+        selected.iter().map(|u| u.value).collect::<Vec<_>>(),
+        vec![681, 20_000, 30_000]
+    );
+    assert_eq!(
+        available_utxos
+            .iter()
+            .map(|u| u.value)
+            .collect::<BTreeSet<_>>(),
+        BTreeSet::from([100, 680, 40_000])
+    );
+}
+
+#[test]
+fn should_not_select_utxos_if_they_cannot_pay_for_consolidation() {
+    // Only the UTXOs worth 681 to 700 satoshi exceed their spending cost,
+    // and their surplus does not cover the transaction overhead.
+    let mut available_utxos: BTreeSet<_> = (1..=700).map(dummy_utxo_from_value).collect();
+
+    assert_eq!(
+        select_utxos_to_consolidate(&mut available_utxos, 100, 10_000),
+        vec![]
+    );
+    assert_eq!(available_utxos.len(), 700);
+}
+
+#[test]
+fn should_not_consolidate_dust() {
+    let main_address = BitcoinAddress::P2wpkhV0([0; 20]);
+    let utxos: Vec<_> = (1..=10).map(dummy_utxo_from_value).collect();
+
+    assert_eq!(
+        build_consolidation_transaction(&utxos, main_address, 10_000),
+        Err(BuildTxError::AmountTooLow)
+    );
+}
+
+#[test]
+fn should_compute_consolidation_fee_only_for_consolidation_transactions() {
+    let mut state = CkBtcMinterState::from(InitArgs {
+        btc_network: Network::Regtest.into(),
+        ecdsa_key_name: "".to_string(),
+        retrieve_btc_min_amount: 100_000,
+        ledger_id: CanisterId::from_u64(42),
+        max_time_in_queue_nanos: 0,
+        min_confirmations: None,
+        mode: Mode::GeneralAvailability,
+        kyt_fee: None,
+        kyt_principal: None,
+    });
+    let main_address = BitcoinAddress::P2wpkhV0([0; 20]);
+    let utxos: Vec<_> = [100_000, 200_000, 300_000]
+        .into_iter()
+        .map(dummy_utxo_from_value)
+        .collect();
+
+    let (unsigned_tx, change_output) =
+        build_consolidation_transaction(&utxos, main_address, 1_000).unwrap();
+    let txid = unsigned_tx.txid();
+    let expected_fee = 600_000 - change_output.value;
+    state.push_submitted_transaction(SubmittedBtcTransaction {
+        requests: vec![],
+        txid,
+        used_utxos: utxos,
+        change_output: Some(change_output),
+        submitted_at: 0,
+        fee_per_vbyte: Some(1_000),
+    });
+
+    assert_eq!(state.consolidation_fee(&txid), Some(expected_fee));
+    assert_eq!(state.consolidation_fee(&Txid::from([1; 32])), None);
+}
+
+#[test]
+fn should_consolidate_only_if_withdrawals_are_open_to_everyone() {
+    let p = Principal::management_canister();
+    assert!(Mode::GeneralAvailability
+        .is_consolidation_available()
+        .is_ok());
+    assert!(Mode::DepositsRestrictedTo(vec![p])
+        .is_consolidation_available()
+        .is_ok());
+    assert!(Mode::RestrictedTo(vec![p])
+        .is_consolidation_available()
+        .is_err());
+    assert!(Mode::ReadOnly.is_consolidation_available().is_err());
+}
+
+fn retrieve_btc_events() -> Vec<Event> {
+    let request = |block_index: u64, received_at: u64| {
+        Event::AcceptedRetrieveBtcRequest(RetrieveBtcRequest {
+            amount: 100_000,
+            address: BitcoinAddress::P2wpkhV0([0; 20]),
+            block_index,
+            received_at,
+            kyt_provider: None,
+            reimbursement_account: None,
+        })
+    };
+    vec![
+        request(1, 10),
+        request(2, 20),
+        Event::SentBtcTransaction {
+            request_block_indices: vec![1, 2],
+            txid: [1; 32].into(),
+            utxos: vec![],
+            change_output: None,
+            submitted_at: 30,
+            fee_per_vbyte: None,
+        },
+        Event::RemovedRetrieveBtcRequest { block_index: 3 },
+        Event::ConfirmedBtcTransaction {
+            txid: [1; 32].into(),
+        },
+    ]
+}
+
+#[test]
+fn should_filter_events_by_type_and_block_index() {
+    let events = retrieve_btc_events();
+    let no_account = BTreeSet::new();
+
+    let filter = EventFilter {
+        event_types: Some(vec![EventType::AcceptedRetrieveBtcRequest]),
+        ..Default::default()
+    };
+    let result = filter_events(events.clone().into_iter(), 0, 10, 100, &filter, &no_account);
+    assert_eq!(
+        result.events.iter().map(|e| e.index).collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+    assert_eq!(result.next_start, None);
+
+    let filter = EventFilter {
+        block_index: Some(2),
+        ..Default::default()
+    };
+    let result = filter_events(events.into_iter(), 0, 10, 100, &filter, &no_account);
+    assert_eq!(
+        result.events.iter().map(|e| e.index).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+}
+
+#[test]
+fn should_filter_events_by_account_and_time() {
+    let events = retrieve_btc_events();
+
+    let filter = EventFilter {
+        account: Some(Account {
+            owner: Principal::management_canister(),
+            subaccount: None,
+        }),
+        from_timestamp: Some(20),
+        to_timestamp: Some(31),
+        ..Default::default()
+    };
+    let result = filter_events(
+        events.into_iter(),
+        0,
+        10,
+        100,
+        &filter,
+        &BTreeSet::from([1]),
+    );
+    assert_eq!(
+        result.events.iter().map(|e| e.index).collect::<Vec<_>>(),
+        vec![2]
+    );
+}
+
+#[test]
+fn should_resume_filtered_events_search() {
+    let events = retrieve_btc_events();
+    let filter = EventFilter::default();
+    let no_account = BTreeSet::new();
+
+    let result = filter_events(events.clone().into_iter(), 1, 2, 100, &filter, &no_account);
+    assert_eq!(
+        result.events.iter().map(|e| e.index).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(result.next_start, Some(3));
+
+    let result = filter_events(events.into_iter(), 3, 10, 1, &filter, &no_account);
+    assert_eq!(result.events.len(), 1);
+    assert_eq!(result.next_start, Some(4));
+}
+
 #[test]
 fn blocklist_is_sorted() {
     use crate::blocklist::BTC_ADDRESS_BLOCKLIST;
@@ -714,6 +937,37 @@ proptest! {
         prop_assert_eq!(change_output, ChangeOutput { vout: 1, value: inputs_value - target + minter_fee });
     }
 
+    #[test]
+    fn build_consolidation_tx_merges_utxos(
+        utxos in btree_set(arb_utxo(100_000..1_000_000_000u64), 1..20),
+        fee_per_vbyte in 1000..10_000u64,
+        main_pkhash in uniform20(any::<u8>()),
+    ) {
+        let utxos: Vec<_> = utxos.into_iter().collect();
+        let main_address = BitcoinAddress::P2wpkhV0(main_pkhash);
+        let inputs_value = utxos.iter().map(|u| u.value).sum::<u64>();
+
+        let (unsigned_tx, change_output) = build_consolidation_transaction(
+            &utxos,
+            main_address.clone(),
+            fee_per_vbyte,
+        )
+        .expect("failed to build a consolidation transaction");
+
+        prop_assert_eq!(
+            unsigned_tx.inputs.iter().map(|input| input.previous_output.clone()).collect::<Vec<_>>(),
+            utxos.iter().map(|u| u.outpoint.clone()).collect::<Vec<_>>()
+        );
+        prop_assert_eq!(&unsigned_tx.outputs, &vec![tx::TxOut {
+            address: main_address,
+            value: change_output.value,
+        }]);
+        prop_assert_eq!(change_output.vout, 0);
+
+        let fee = inputs_value - change_output.value;
+        prop_assert_eq!(fee, fake_sign(&unsigned_tx).vsize() as u64 * fee_per_vbyte / 1000);
+    }
+
     #[test]
     fn build_tx_does_not_modify_utxos_on_error(
         mut utxos in btree_set(arb_utxo(5_000u64..1_000_000_000), 1..20),
@@ -199,6 +199,20 @@ impl ConfigValidate for ConfigOptional {
                 );
             }
         }
+        // Profiling Wasm functions writes files and slows down execution, so
+        // it is only available to local and test replicas configured in code.
+        if let Some(hypervisor) = &self.hypervisor {
+            if hypervisor
+                .embedders_config
+                .wasm_function_profile_dir
+                .is_some()
+            {
+                return Err(
+                    "Inside Config::hypervisor wasm_function_profile_dir must not be set."
+                        .to_string(),
+                );
+            }
+        }
         Ok(self)
     }
 }
@@ -243,4 +257,23 @@ mod tests {
             "/tmp/ic_crypto"
         );
     }
+
+    #[test]
+    fn validate_rejects_wasm_function_profile_dir() {
+        let config = ConfigOptional {
+            hypervisor: Some(HypervisorConfig::default()),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        let mut hypervisor = HypervisorConfig::default();
+        hypervisor.embedders_config.wasm_function_profile_dir =
+            Some(PathBuf::from("/tmp/profiles"));
+        let config = ConfigOptional {
+            hypervisor: Some(hypervisor),
+            ..Default::default()
+        };
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("wasm_function_profile_dir"), "{}", err);
+    }
 }
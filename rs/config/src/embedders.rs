@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 
 use ic_base_types::NumBytes;
 use ic_registry_subnet_type::SubnetType;
@@ -169,6 +169,14 @@ pub struct Config {
     /// If this flag is enabled, then execution of a slice will produce a log
     /// entry with the number of executed instructions and the duration.
     pub trace_execution: FlagStatus,
+
+    /// If set, canister modules are instrumented to count the instructions
+    /// executed in each Wasm function and the hypervisor appends the resulting
+    /// profiles in the folded stack format to `<dir>/<canister_id>.folded`.
+    /// Only local and test replicas configured in code (`StateMachine` and
+    /// PocketIC) can set it: replica configuration files that set it are
+    /// rejected.
+    pub wasm_function_profile_dir: Option<PathBuf>,
 }
 
 impl Config {
@@ -194,6 +202,7 @@ impl Config {
             subnet_type: SubnetType::Application,
             dirty_page_overhead: NumInstructions::new(0),
             trace_execution: FlagStatus::Disabled,
+            wasm_function_profile_dir: None,
         }
     }
 }
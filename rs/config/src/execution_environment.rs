@@ -5,7 +5,7 @@ use ic_types::{
     Cycles, NumBytes, NumInstructions, MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
 use serde::{Deserialize, Serialize};
-use std::{str::FromStr, time::Duration};
+use std::{path::PathBuf, str::FromStr, time::Duration};
 
 const MIB: u64 = 1024 * 1024;
 const GIB: u64 = MIB * 1024;
@@ -100,6 +100,23 @@ const QUERY_SCHEDULING_TIME_SLICE_PER_CANISTER: Duration = Duration::from_millis
 /// executions and user errors.
 const QUERY_CACHE_CAPACITY: NumBytes = NumBytes::new(100 * MIB);
 
+/// The upper limit on the staleness window a canister can opt in to.
+///
+/// Within the window, a cache entry is served even though the batch time or
+/// the canister balance have changed since the entry was created.
+const QUERY_CACHE_MAX_STALENESS: Duration = Duration::from_secs(60);
+
+/// The instruction limit of `install_code` messages of canisters that opted in
+/// to the extended limit.
+///
+/// Paused executions are not persisted in checkpoints: they are aborted at
+/// each checkpoint. Executions with the extended limit therefore only start in
+/// the first round after a checkpoint and are rejected if they are still
+/// running at the next one. The limit is chosen such that the execution can
+/// finish with 2B instruction slices within a single checkpoint interval.
+const MAX_INSTRUCTIONS_PER_EXTENDED_INSTALL_CODE: NumInstructions =
+    NumInstructions::new(600_000_000_000);
+
 // The ID of the Bitcoin testnet canister.
 pub const BITCOIN_TESTNET_CANISTER_ID: &str = "g4xu7-jiaaa-aaaan-aaaaq-cai";
 
@@ -116,6 +133,9 @@ const BITCOIN_MAINNET_SOFT_LAUNCH_CANISTER_ID: &str = "gsvzx-syaaa-aaaan-aaabq-c
 /// The capacity of the Wasm compilation cache.
 pub const MAX_COMPILATION_CACHE_SIZE: NumBytes = NumBytes::new(10 * GIB);
 
+/// The capacity of the disk-backed tier of the Wasm compilation cache.
+pub const MAX_COMPILATION_CACHE_DISK_SIZE: NumBytes = NumBytes::new(20 * GIB);
+
 /// Maximum number of controllers allowed in a request (specified in the interface spec).
 pub const MAX_ALLOWED_CONTROLLERS_COUNT: usize = 10;
 
@@ -218,15 +238,30 @@ pub struct Config {
     /// Indicates whether composite queries are available or not.
     pub composite_queries: FlagStatus,
 
+    /// Indicates whether calls from update methods to query methods of
+    /// canisters on the same subnet are executed synchronously within the
+    /// round, with the instructions charged to the caller.
+    pub replicated_query_calls: FlagStatus,
+
     /// Indicates whether replica side query caching is enabled.
     pub query_caching: FlagStatus,
 
     /// Query cache capacity in bytes
     pub query_cache_capacity: NumBytes,
 
+    /// The upper limit on the canister-defined query cache staleness window.
+    pub query_cache_max_staleness: Duration,
+
     /// The capacity of the Wasm compilation cache.
     pub max_compilation_cache_size: NumBytes,
 
+    /// The directory of the disk-backed tier of the Wasm compilation cache.
+    /// The tier is disabled if no directory is set.
+    pub compilation_cache_dir: Option<PathBuf>,
+
+    /// The capacity of the disk-backed tier of the Wasm compilation cache.
+    pub max_compilation_cache_disk_size: NumBytes,
+
     /// Indicate whether query stats should be collected or not.
     pub query_stats_aggregation: FlagStatus,
 
@@ -235,6 +270,15 @@ pub struct Config {
 
     /// The duration a stop_canister has to stop the canister before timing out.
     pub stop_canister_timeout_duration: Duration,
+
+    /// The instruction limit of `install_code` messages of canisters that
+    /// opted in to the extended limit. It applies only if deterministic time
+    /// slicing is enabled. Such executions must start and finish within the
+    /// same checkpoint interval.
+    pub max_instructions_per_extended_install_code: NumInstructions,
+
+    /// Retention limits of the audit changes in canister history.
+    pub canister_history: CanisterHistoryConfig,
 }
 
 impl Default for Config {
@@ -295,12 +339,48 @@ impl Default for Config {
                 mainnet_canister_id: Some(bitcoin_mainnet_canister_id),
             },
             composite_queries: FlagStatus::Enabled,
+            replicated_query_calls: FlagStatus::Disabled,
             query_caching: FlagStatus::Enabled,
             query_cache_capacity: QUERY_CACHE_CAPACITY,
+            query_cache_max_staleness: QUERY_CACHE_MAX_STALENESS,
             max_compilation_cache_size: MAX_COMPILATION_CACHE_SIZE,
+            compilation_cache_dir: None,
+            max_compilation_cache_disk_size: MAX_COMPILATION_CACHE_DISK_SIZE,
             query_stats_aggregation: FlagStatus::Disabled,
             wasm_chunk_store: FlagStatus::Disabled,
             stop_canister_timeout_duration: STOP_CANISTER_TIMEOUT_DURATION,
+            max_instructions_per_extended_install_code: MAX_INSTRUCTIONS_PER_EXTENDED_INSTALL_CODE,
+            canister_history: CanisterHistoryConfig::default(),
+        }
+    }
+}
+
+/// Retention limits of the audit changes recorded in canister history, i.e.,
+/// the changes that are only returned by `canister_history`. When the limit
+/// of a kind is reached, the oldest change of that kind is dropped.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default)]
+pub struct CanisterHistoryConfig {
+    /// The maximum number of retained settings changes.
+    pub max_settings_changes: u64,
+
+    /// The maximum number of retained cycles deposits.
+    pub max_cycles_deposits: u64,
+
+    /// The maximum number of retained cycles withdrawals.
+    pub max_cycles_withdrawals: u64,
+
+    /// Cycles deposits and withdrawals below this amount are not recorded.
+    pub cycles_threshold: Cycles,
+}
+
+impl Default for CanisterHistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_settings_changes: 20,
+            max_cycles_deposits: 20,
+            max_cycles_withdrawals: 20,
+            cycles_threshold: Cycles::new(1_000_000_000_000),
         }
     }
 }
@@ -141,6 +141,15 @@ const SYSTEM_SUBNET_DIRTY_PAGE_OVERHEAD: NumInstructions = NumInstructions::new(
 /// all subnet types.
 const ACCUMULATED_PRIORITY_RESET_INTERVAL: ExecutionRound = ExecutionRound::new(24 * 3600);
 
+/// The number of rounds after which a canister with pending responses in its
+/// input queues becomes eligible for the slot the scheduler reserves every
+/// round for canisters with overdue responses.
+///
+/// With a round every second, responses wait ~20 seconds on a saturated
+/// subnet before the canister gets scheduled preferentially, plus one round
+/// per canister whose responses became overdue earlier.
+const MAX_RESPONSE_WAIT_ROUNDS: ExecutionRound = ExecutionRound::new(20);
+
 /// The default value of the reserved balance limit for the case when the
 /// canister doesn't have it set in the settings.
 const DEFAULT_RESERVED_BALANCE_LIMIT: Cycles = Cycles::new(5 * T);
@@ -240,6 +249,10 @@ pub struct SchedulerConfig {
     /// Accumulated priority reset interval, rounds.
     pub accumulated_priority_reset_interval: ExecutionRound,
 
+    /// Maximum number of rounds responses may wait in the input queues of a
+    /// canister before the canister gets scheduled preferentially.
+    pub max_response_wait_rounds: ExecutionRound,
+
     /// Number of instructions to count when uploading a chunk to the wasm store.
     pub upload_wasm_chunk_instructions: NumInstructions,
 }
@@ -267,6 +280,7 @@ impl SchedulerConfig {
             install_code_rate_limit: MAX_INSTRUCTIONS_PER_SLICE,
             dirty_page_overhead: DEFAULT_DIRTY_PAGE_OVERHEAD,
             accumulated_priority_reset_interval: ACCUMULATED_PRIORITY_RESET_INTERVAL,
+            max_response_wait_rounds: MAX_RESPONSE_WAIT_ROUNDS,
             upload_wasm_chunk_instructions: DEFAULT_UPLOAD_CHUNK_INSTRUCTIONS,
         }
     }
@@ -303,6 +317,7 @@ impl SchedulerConfig {
             install_code_rate_limit: NumInstructions::from(1_000_000_000_000_000),
             dirty_page_overhead: SYSTEM_SUBNET_DIRTY_PAGE_OVERHEAD,
             accumulated_priority_reset_interval: ACCUMULATED_PRIORITY_RESET_INTERVAL,
+            max_response_wait_rounds: MAX_RESPONSE_WAIT_ROUNDS,
             upload_wasm_chunk_instructions: NumInstructions::from(0),
         }
     }
@@ -332,6 +347,7 @@ impl SchedulerConfig {
             install_code_rate_limit: MAX_INSTRUCTIONS_PER_SLICE,
             dirty_page_overhead: DEFAULT_DIRTY_PAGE_OVERHEAD,
             accumulated_priority_reset_interval: ACCUMULATED_PRIORITY_RESET_INTERVAL,
+            max_response_wait_rounds: MAX_RESPONSE_WAIT_ROUNDS,
             upload_wasm_chunk_instructions: DEFAULT_UPLOAD_CHUNK_INSTRUCTIONS,
         }
     }
@@ -354,6 +370,9 @@ pub struct CyclesAccountManagerConfig {
     /// Fee for creating canisters on a subnet
     pub canister_creation_fee: Cycles,
 
+    /// Fee for migrating a canister from this subnet to another subnet.
+    pub canister_migration_fee: Cycles,
+
     /// Fee for every update message executed
     pub update_message_execution_fee: Cycles,
 
@@ -387,6 +406,9 @@ pub struct CyclesAccountManagerConfig {
     /// How often to charge canisters for memory and compute allocations.
     pub duration_between_allocation_charges: Duration,
 
+    /// The minimum time between two automatic top-ups of the same canister.
+    pub duration_between_auto_top_ups: Duration,
+
     /// Amount to charge for an ECDSA signature.
     pub ecdsa_signature_fee: Cycles,
 
@@ -424,6 +446,7 @@ impl CyclesAccountManagerConfig {
         Self {
             reference_subnet_size: DEFAULT_REFERENCE_SUBNET_SIZE,
             canister_creation_fee: Cycles::new(100_000_000_000),
+            canister_migration_fee: Cycles::new(100_000_000_000),
             compute_percent_allocated_per_second_fee: Cycles::new(10_000_000),
 
             // The following fields are set based on a thought experiment where
@@ -438,6 +461,7 @@ impl CyclesAccountManagerConfig {
             // 4 SDR per GiB per year => 4e12 Cycles per year
             gib_storage_per_second_fee: Cycles::new(127_000),
             duration_between_allocation_charges: Duration::from_secs(10),
+            duration_between_auto_top_ups: Duration::from_secs(600),
             ecdsa_signature_fee: ECDSA_SIGNATURE_FEE,
             http_request_linear_baseline_fee: Cycles::new(3_000_000),
             http_request_quadratic_baseline_fee: Cycles::new(60_000),
@@ -455,6 +479,7 @@ impl CyclesAccountManagerConfig {
         Self {
             reference_subnet_size: DEFAULT_REFERENCE_SUBNET_SIZE,
             canister_creation_fee: Cycles::new(0),
+            canister_migration_fee: Cycles::new(0),
             compute_percent_allocated_per_second_fee: Cycles::new(0),
             update_message_execution_fee: Cycles::new(0),
             ten_update_instructions_execution_fee: Cycles::new(0),
@@ -464,6 +489,7 @@ impl CyclesAccountManagerConfig {
             ingress_byte_reception_fee: Cycles::new(0),
             gib_storage_per_second_fee: Cycles::new(0),
             duration_between_allocation_charges: Duration::from_secs(10),
+            duration_between_auto_top_ups: Duration::from_secs(600),
             // The ECDSA signature fee is the fee charged when creating a
             // signature on this subnet. The request likely came from a
             // different subnet which is not a system subnet. There is an
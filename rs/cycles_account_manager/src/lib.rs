@@ -21,12 +21,14 @@ use ic_logger::{error, info, ReplicaLogger};
 use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    canister_state::system_state::CyclesUseCase, CanisterState, SystemState,
+    canister_state::system_state::{AutoTopUp, CyclesUseCase},
+    CanisterState, SystemState,
 };
 use ic_types::{
     canister_http::MAX_CANISTER_HTTP_RESPONSE_BYTES,
     messages::{Request, Response, SignedIngressContent, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES},
     CanisterId, ComputeAllocation, Cycles, MemoryAllocation, NumBytes, NumInstructions, SubnetId,
+    Time,
 };
 use prometheus::IntCounter;
 use serde::{Deserialize, Serialize};
@@ -236,6 +238,11 @@ impl CyclesAccountManager {
         self.scale_cost(self.config.canister_creation_fee, subnet_size)
     }
 
+    /// Returns the fee for migrating a canister to another subnet in [`Cycles`].
+    pub fn canister_migration_fee(&self, subnet_size: usize) -> Cycles {
+        self.scale_cost(self.config.canister_migration_fee, subnet_size)
+    }
+
     /// Returns the fee for receiving an ingress message in [`Cycles`].
     pub fn ingress_message_received_fee(&self, subnet_size: usize) -> Cycles {
         self.scale_cost(self.config.ingress_message_reception_fee, subnet_size)
@@ -621,6 +628,64 @@ impl CyclesAccountManager {
         self.config.duration_between_allocation_charges
     }
 
+    /// The minimum time between two automatic top-ups of the same canister.
+    pub fn duration_between_auto_top_ups(&self) -> Duration {
+        self.config.duration_between_auto_top_ups
+    }
+
+    /// Returns the automatic top-up settings of the canister if a top-up is
+    /// due at `now`, i.e. if the balance of the canister fell below the
+    /// configured threshold and the last top-up attempt happened at least
+    /// `duration_between_auto_top_ups` ago.
+    pub fn auto_top_up_due(&self, system_state: &SystemState, now: Time) -> Option<AutoTopUp> {
+        let auto_top_up = system_state.auto_top_up?;
+        if system_state.balance() >= auto_top_up.threshold {
+            return None;
+        }
+        if now < system_state.time_of_last_auto_top_up + self.duration_between_auto_top_ups() {
+            return None;
+        }
+        Some(auto_top_up)
+    }
+
+    /// Transfers `amount` cycles from `funding_canister` to `canister` as
+    /// part of an automatic top-up.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CanisterOutOfCyclesError` if the transfer would push the
+    /// funding canister below its freezing threshold.
+    pub fn transfer_auto_top_up(
+        &self,
+        funding_canister: &mut CanisterState,
+        canister: &mut CanisterState,
+        amount: Cycles,
+        subnet_size: usize,
+    ) -> Result<(), CanisterOutOfCyclesError> {
+        let threshold = self.freeze_threshold_cycles(
+            funding_canister.system_state.freeze_threshold,
+            funding_canister.memory_allocation(),
+            funding_canister.memory_usage(),
+            funding_canister.message_memory_usage(),
+            funding_canister.compute_allocation(),
+            subnet_size,
+            funding_canister.system_state.reserved_balance(),
+        );
+        self.verify_cycles_balance_with_threshold(
+            funding_canister.canister_id(),
+            funding_canister.system_state.balance(),
+            amount,
+            threshold,
+        )?;
+        funding_canister
+            .system_state
+            .remove_cycles(amount, CyclesUseCase::NonConsumed);
+        canister
+            .system_state
+            .add_cycles(amount, CyclesUseCase::NonConsumed);
+        Ok(())
+    }
+
     /// Amount to charge for an ECDSA signature.
     pub fn ecdsa_signature_fee(&self, subnet_size: usize) -> Cycles {
         self.scale_cost(self.config.ecdsa_signature_fee, subnet_size)
@@ -1,33 +1,94 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
 
 use crate::SerializedModule;
+use ic_config::embedders::Config as EmbeddersConfig;
 use ic_interfaces::execution_environment::HypervisorResult;
+use ic_logger::{warn, ReplicaLogger};
 use ic_types::NumBytes;
 use ic_utils_lru_cache::LruCache;
 use ic_wasm_types::{CanisterModule, WasmHash};
 
+mod disk_tier;
+
+use disk_tier::DiskTierHandle;
+
+type MemoryTier = LruCache<WasmHash, HypervisorResult<Arc<SerializedModule>>>;
+
 /// Stores the serialized modules of wasm code that has already been compiled so
 /// that it can be used again without recompiling.
+///
+/// The cache optionally has a disk-backed tier that keeps successfully
+/// compiled modules across replica restarts. The disk tier loads the
+/// persisted modules into the in-memory tier in the background after a
+/// restart, so that a module can be sent to a sandbox process in its
+/// serialized form instead of being recompiled. Lookups never touch the disk.
 pub struct CompilationCache {
-    cache: Mutex<LruCache<WasmHash, HypervisorResult<Arc<SerializedModule>>>>,
+    cache: Arc<Mutex<MemoryTier>>,
+    disk_tier: Option<DiskTierHandle>,
 }
 
 impl CompilationCache {
     pub fn new(capacity: NumBytes) -> Self {
         Self {
-            cache: Mutex::new(LruCache::new(capacity)),
+            cache: Arc::new(Mutex::new(LruCache::new(capacity))),
+            disk_tier: None,
         }
     }
 
+    /// Creates a cache with a disk-backed tier stored under `dir` that
+    /// occupies at most `disk_capacity` bytes. The modules stored on disk are
+    /// valid only for the given embedder configuration.
+    ///
+    /// Falls back to an in-memory cache if the disk tier cannot be opened.
+    /// The directory must be empty or previously created by the cache.
+    pub fn new_with_disk_tier(
+        capacity: NumBytes,
+        dir: &Path,
+        disk_capacity: NumBytes,
+        embedder_config: &EmbeddersConfig,
+        log: ReplicaLogger,
+    ) -> Self {
+        let cache = Arc::new(Mutex::new(LruCache::new(capacity)));
+        let disk_tier = match DiskTierHandle::start(
+            dir,
+            disk_capacity,
+            embedder_config,
+            Arc::clone(&cache),
+            capacity,
+            log.clone(),
+        ) {
+            Ok(disk_tier) => Some(disk_tier),
+            Err(err) => {
+                warn!(
+                    log,
+                    "Failed to start the compilation cache disk tier for {}: {}",
+                    dir.display(),
+                    err
+                );
+                None
+            }
+        };
+        Self { cache, disk_tier }
+    }
+
     pub fn insert(
         &self,
         canister_module: &CanisterModule,
         serialized_module: HypervisorResult<Arc<SerializedModule>>,
     ) {
+        let wasm_hash = WasmHash::from(canister_module);
+        // Compilation errors are cheap to reproduce, so only successfully
+        // compiled modules are persisted.
+        if let (Some(disk_tier), Ok(serialized_module)) = (&self.disk_tier, &serialized_module) {
+            disk_tier.insert(wasm_hash.clone(), Arc::clone(serialized_module));
+        }
         self.cache
             .lock()
             .unwrap()
-            .push(WasmHash::from(canister_module), serialized_module);
+            .push(wasm_hash, serialized_module);
     }
 
     pub fn get(
@@ -43,6 +104,18 @@ impl CompilationCache {
 
     #[doc(hidden)]
     pub fn clear_for_testing(&self) {
-        self.cache.lock().unwrap().clear()
+        if let Some(disk_tier) = &self.disk_tier {
+            disk_tier.clear();
+        }
+        self.cache.lock().unwrap().clear();
+    }
+
+    /// Waits until the disk tier finished loading the persisted modules and
+    /// storing the inserted ones.
+    #[doc(hidden)]
+    pub fn sync_disk_tier_for_testing(&self) {
+        if let Some(disk_tier) = &self.disk_tier {
+            disk_tier.sync();
+        }
     }
 }
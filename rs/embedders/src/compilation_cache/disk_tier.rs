@@ -0,0 +1,466 @@
+//! The disk-backed tier of the compilation cache.
+//!
+//! Serialized modules are stored in files named after the hash of the
+//! uninstrumented Wasm binary, in a directory named after the hash of the
+//! embedder configuration:
+//!
+//! ```text
+//! <root>
+//! ├── ic_compilation_cache
+//! └── <embedder config hash>
+//!     ├── <wasm hash>
+//!     └── ...
+//! ```
+//!
+//! Each file consists of a magic header, the SHA-256 checksum of the payload
+//! and the payload, which is the `bincode`-encoded `SerializedModule`. Files
+//! with a wrong header or checksum are removed when they are read.
+//!
+//! Since a change of the embedder configuration or of the Wasmtime version
+//! changes the configuration hash, modules compiled by an older replica are
+//! never loaded. The directories of other configurations are removed when
+//! the tier is opened. The tier only uses a root that is empty or contains
+//! the `ic_compilation_cache` marker file, and only removes entries whose
+//! names it could have created, so that a misconfigured root is never wiped.
+//!
+//! All file system operations and checksum computations run on a dedicated
+//! thread, see [`DiskTierHandle`], so that they never block execution.
+
+use std::{
+    collections::BTreeSet,
+    ffi::OsStr,
+    fs,
+    hash::Hash,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::SystemTime,
+};
+
+use ic_config::embedders::Config as EmbeddersConfig;
+use ic_crypto_sha2::Sha256;
+use ic_logger::{warn, ReplicaLogger};
+use ic_types::{CountBytes, NumBytes};
+use ic_utils_lru_cache::LruCache;
+use ic_wasm_types::WasmHash;
+
+use super::MemoryTier;
+use crate::{SerializedModule, WasmtimeEmbedder};
+
+/// The header of every cache file. Bump the version when the file format or
+/// the encoding of `SerializedModule` changes.
+const FILE_MAGIC: &[u8; 8] = b"iccache1";
+
+const CHECKSUM_LEN: usize = 32;
+
+const TMP_FILE_EXTENSION: &str = "tmp";
+
+/// The file that marks a directory as the root of the disk tier.
+const MARKER_FILE_NAME: &str = "ic_compilation_cache";
+
+/// The maximum number of modules waiting to be written to disk. Modules
+/// inserted while the queue is full are not persisted.
+const MAX_PENDING_WRITES: usize = 100;
+
+/// The size of a cache file.
+struct DiskEntry {
+    size: usize,
+}
+
+impl CountBytes for DiskEntry {
+    fn count_bytes(&self) -> usize {
+        self.size
+    }
+}
+
+enum Request {
+    Insert(WasmHash, Arc<SerializedModule>),
+    Clear(mpsc::Sender<()>),
+    Sync(mpsc::Sender<()>),
+}
+
+/// Runs the disk tier on a dedicated thread.
+///
+/// The thread opens the tier, loads the most recently used modules into the
+/// in-memory tier and then persists the inserted modules in the background.
+/// Lookups only consult the in-memory tier.
+pub(super) struct DiskTierHandle {
+    sender: Option<mpsc::SyncSender<Request>>,
+    thread: Option<JoinHandle<()>>,
+    log: ReplicaLogger,
+}
+
+impl DiskTierHandle {
+    pub(super) fn start(
+        root: &Path,
+        capacity: NumBytes,
+        embedder_config: &EmbeddersConfig,
+        memory_tier: Arc<Mutex<MemoryTier>>,
+        memory_capacity: NumBytes,
+        log: ReplicaLogger,
+    ) -> io::Result<Self> {
+        let (sender, receiver) = mpsc::sync_channel(MAX_PENDING_WRITES);
+        let root = root.to_path_buf();
+        let embedder_config = embedder_config.clone();
+        let thread_log = log.clone();
+        let thread = std::thread::Builder::new()
+            .name("CompilationCacheDiskTier".to_string())
+            .spawn(move || {
+                let disk_tier =
+                    match DiskTier::open(&root, capacity, &embedder_config, thread_log.clone()) {
+                        Ok(disk_tier) => disk_tier,
+                        Err(err) => {
+                            // Dropping the receiver turns all further requests into no-ops.
+                            warn!(
+                                thread_log,
+                                "Failed to open the compilation cache in {}: {}",
+                                root.display(),
+                                err
+                            );
+                            return;
+                        }
+                    };
+                disk_tier.load_into(&memory_tier, memory_capacity);
+                for request in receiver {
+                    match request {
+                        Request::Insert(wasm_hash, serialized_module) => {
+                            disk_tier.insert(&wasm_hash, &serialized_module)
+                        }
+                        Request::Clear(done) => {
+                            disk_tier.clear();
+                            let _ = done.send(());
+                        }
+                        Request::Sync(done) => {
+                            let _ = done.send(());
+                        }
+                    }
+                }
+            })?;
+        Ok(Self {
+            sender: Some(sender),
+            thread: Some(thread),
+            log,
+        })
+    }
+
+    /// Schedules the module to be stored on disk. Never blocks: the module is
+    /// dropped if too many writes are pending.
+    pub(super) fn insert(&self, wasm_hash: WasmHash, serialized_module: Arc<SerializedModule>) {
+        if let Some(sender) = &self.sender {
+            if let Err(mpsc::TrySendError::Full(_)) =
+                sender.try_send(Request::Insert(wasm_hash, serialized_module))
+            {
+                warn!(
+                    self.log,
+                    "Too many pending compilation cache writes, not persisting a module"
+                );
+            }
+        }
+    }
+
+    /// Removes all files of the current configuration and waits until they
+    /// are gone.
+    pub(super) fn clear(&self) {
+        self.request_and_wait(Request::Clear);
+    }
+
+    /// Waits until the tier is loaded and all pending writes are done.
+    pub(super) fn sync(&self) {
+        self.request_and_wait(Request::Sync);
+    }
+
+    fn request_and_wait(&self, request: impl FnOnce(mpsc::Sender<()>) -> Request) {
+        if let Some(sender) = &self.sender {
+            let (done_sender, done_receiver) = mpsc::channel();
+            if sender.send(request(done_sender)).is_ok() {
+                let _ = done_receiver.recv();
+            }
+        }
+    }
+}
+
+impl Drop for DiskTierHandle {
+    fn drop(&mut self) {
+        // Closing the channel stops the thread once it finished the pending writes.
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct DiskTier {
+    /// The directory of the current embedder configuration.
+    dir: PathBuf,
+    /// Tracks the files in `dir` to keep their total size under the capacity.
+    index: Mutex<LruCache<WasmHash, DiskEntry>>,
+    /// The indexed modules, from the most recently to the least recently
+    /// modified one, at the time the tier was opened.
+    initial_entries: Vec<(WasmHash, usize)>,
+    capacity: NumBytes,
+    /// Used to generate unique names of temporary files.
+    next_tmp_id: AtomicU64,
+    log: ReplicaLogger,
+}
+
+impl DiskTier {
+    /// Opens the disk tier under `root` for the given embedder configuration.
+    /// Removes the files of other configurations and the leftovers of
+    /// interrupted writes, and indexes the remaining files from the oldest to
+    /// the most recently modified one.
+    ///
+    /// Fails if `root` is neither empty nor marked as a compilation cache.
+    fn open(
+        root: &Path,
+        capacity: NumBytes,
+        embedder_config: &EmbeddersConfig,
+        log: ReplicaLogger,
+    ) -> io::Result<Self> {
+        let config_hash = hex::encode(embedder_config_hash(embedder_config)?);
+        fs::create_dir_all(root)?;
+        let marker = root.join(MARKER_FILE_NAME);
+        if !marker.is_file() {
+            if fs::read_dir(root)?.next().is_some() {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!(
+                        "the directory is not empty and does not contain the {} marker file",
+                        MARKER_FILE_NAME
+                    ),
+                ));
+            }
+            fs::write(&marker, FILE_MAGIC)?;
+        }
+        for entry in fs::read_dir(root)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if name != config_hash.as_str() && is_hash_name(&name) && entry.file_type()?.is_dir() {
+                fs::remove_dir_all(entry.path())?;
+            }
+        }
+        let dir = root.join(&config_hash);
+        fs::create_dir_all(&dir)?;
+
+        let mut files = vec![];
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            match wasm_hash_from_path(&path) {
+                Some(wasm_hash) => {
+                    let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                    files.push((modified, wasm_hash, metadata.len() as usize));
+                }
+                None if is_tmp_file(&path) => fs::remove_file(&path)?,
+                None => {}
+            }
+        }
+        files.sort_by_key(|(modified, ..)| *modified);
+
+        let mut index = LruCache::new(capacity);
+        let mut evicted_hashes = BTreeSet::new();
+        for (_, wasm_hash, size) in files.iter() {
+            for (evicted, _) in index.push(wasm_hash.clone(), DiskEntry { size: *size }) {
+                let _ = fs::remove_file(file_path(&dir, &evicted));
+                evicted_hashes.insert(evicted);
+            }
+        }
+        let initial_entries = files
+            .into_iter()
+            .rev()
+            .filter(|(_, wasm_hash, _)| !evicted_hashes.contains(wasm_hash))
+            .map(|(_, wasm_hash, size)| (wasm_hash, size))
+            .collect();
+
+        Ok(Self {
+            dir,
+            index: Mutex::new(index),
+            initial_entries,
+            capacity,
+            next_tmp_id: AtomicU64::new(0),
+            log,
+        })
+    }
+
+    /// Loads the most recently used modules into the in-memory tier, as long
+    /// as their total size does not exceed `memory_capacity`. Modules already
+    /// present in the in-memory tier are left as they are.
+    fn load_into(&self, memory_tier: &Mutex<MemoryTier>, memory_capacity: NumBytes) {
+        let mut total_size = 0;
+        let mut to_load = vec![];
+        for (wasm_hash, size) in self.initial_entries.iter() {
+            total_size += size;
+            if total_size as u64 > memory_capacity.get() {
+                break;
+            }
+            to_load.push(wasm_hash);
+        }
+        // Push the least recently used modules first, so that the in-memory
+        // tier preserves the order of the files.
+        for wasm_hash in to_load.into_iter().rev() {
+            if let Some(serialized_module) = self.get(wasm_hash) {
+                let mut memory_tier = memory_tier.lock().unwrap();
+                if memory_tier.get(wasm_hash).is_none() {
+                    memory_tier.push(wasm_hash.clone(), Ok(Arc::new(serialized_module)));
+                }
+            }
+        }
+    }
+
+    /// Returns the module stored for the given Wasm hash if its file exists
+    /// and passes the integrity checks.
+    fn get(&self, wasm_hash: &WasmHash) -> Option<SerializedModule> {
+        self.index.lock().unwrap().get(wasm_hash)?;
+        let path = file_path(&self.dir, wasm_hash);
+        match read_module(&path) {
+            Ok(serialized_module) => Some(serialized_module),
+            Err(err) => {
+                warn!(
+                    self.log,
+                    "Removing invalid compilation cache file {}: {}",
+                    path.display(),
+                    err
+                );
+                self.index.lock().unwrap().pop(wasm_hash);
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    /// Stores the module for the given Wasm hash and removes the least
+    /// recently used files if the total size exceeds the capacity. Modules
+    /// that do not fit into the capacity on their own are not stored.
+    fn insert(&self, wasm_hash: &WasmHash, serialized_module: &SerializedModule) {
+        if let Err(err) = self.try_insert(wasm_hash, serialized_module) {
+            warn!(
+                self.log,
+                "Failed to store module {} in the compilation cache: {}",
+                hex::encode(wasm_hash.to_slice()),
+                err
+            );
+        }
+    }
+
+    fn try_insert(
+        &self,
+        wasm_hash: &WasmHash,
+        serialized_module: &SerializedModule,
+    ) -> io::Result<()> {
+        let payload = bincode::serialize(serialized_module)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let size = FILE_MAGIC.len() + CHECKSUM_LEN + payload.len();
+        if (wasm_hash.count_bytes() + size) as u64 > self.capacity.get() {
+            return Ok(());
+        }
+
+        // Write to a temporary file first, so that readers never observe a
+        // partially written file.
+        let tmp_id = self.next_tmp_id.fetch_add(1, Ordering::Relaxed);
+        let tmp_path = self.dir.join(format!("{}.{}", tmp_id, TMP_FILE_EXTENSION));
+        let path = file_path(&self.dir, wasm_hash);
+        let mut contents = Vec::with_capacity(size);
+        contents.extend_from_slice(FILE_MAGIC);
+        contents.extend_from_slice(&Sha256::hash(&payload));
+        contents.extend_from_slice(&payload);
+        fs::write(&tmp_path, &contents)
+            .and_then(|()| fs::rename(&tmp_path, &path))
+            .map_err(|err| {
+                let _ = fs::remove_file(&tmp_path);
+                err
+            })?;
+
+        let evicted = self
+            .index
+            .lock()
+            .unwrap()
+            .push(wasm_hash.clone(), DiskEntry { size });
+        for (evicted_hash, _) in evicted {
+            // The entry replaced by this insertion shares the file with it.
+            if &evicted_hash != wasm_hash {
+                let _ = fs::remove_file(file_path(&self.dir, &evicted_hash));
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&self) {
+        let mut index = self.index.lock().unwrap();
+        if let Ok(entries) = fs::read_dir(&self.dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if wasm_hash_from_path(&path).is_some() || is_tmp_file(&path) {
+                    let _ = fs::remove_file(path);
+                }
+            }
+        }
+        index.clear();
+    }
+}
+
+/// Computes the hash of everything that affects the compiled code: the
+/// embedder configuration and the Wasmtime settings that determine whether a
+/// serialized module can be loaded by the engine.
+fn embedder_config_hash(embedder_config: &EmbeddersConfig) -> io::Result<[u8; 32]> {
+    let engine = wasmtime::Engine::new(&WasmtimeEmbedder::wasmtime_execution_config(
+        embedder_config,
+    ))
+    .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+    let mut hasher = Sha256::new();
+    hasher.write(FILE_MAGIC);
+    hasher.write(format!("{:?}", embedder_config).as_bytes());
+    engine.precompile_compatibility_hash().hash(&mut hasher);
+    Ok(hasher.finish())
+}
+
+fn file_path(dir: &Path, wasm_hash: &WasmHash) -> PathBuf {
+    dir.join(hex::encode(wasm_hash.to_slice()))
+}
+
+/// Returns true if the name is a hex-encoded 32-byte hash, i.e. a name the
+/// tier uses for configuration directories and module files.
+fn is_hash_name(name: &OsStr) -> bool {
+    name.to_str()
+        .and_then(|name| hex::decode(name).ok())
+        .map_or(false, |bytes| bytes.len() == 32)
+}
+
+fn wasm_hash_from_path(path: &Path) -> Option<WasmHash> {
+    let name = path.file_name()?.to_str()?;
+    let bytes: [u8; 32] = hex::decode(name).ok()?.try_into().ok()?;
+    Some(WasmHash::from(bytes))
+}
+
+/// Returns true if the path is a temporary file left over by an interrupted
+/// write, i.e. `<number>.tmp`.
+fn is_tmp_file(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new(TMP_FILE_EXTENSION))
+        && path
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .map_or(false, |stem| stem.parse::<u64>().is_ok())
+}
+
+fn read_module(path: &Path) -> io::Result<SerializedModule> {
+    let invalid_data = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    let contents = fs::read(path)?;
+    if contents.len() < FILE_MAGIC.len() + CHECKSUM_LEN {
+        return Err(invalid_data("file is truncated"));
+    }
+    let (magic, rest) = contents.split_at(FILE_MAGIC.len());
+    let (checksum, payload) = rest.split_at(CHECKSUM_LEN);
+    if magic != FILE_MAGIC {
+        return Err(invalid_data("unexpected file header"));
+    }
+    if checksum != Sha256::hash(payload) {
+        return Err(invalid_data("checksum mismatch"));
+    }
+    bincode::deserialize(payload).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
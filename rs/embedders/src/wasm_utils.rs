@@ -4,6 +4,7 @@ use std::{
 };
 
 use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::flag_status::FlagStatus;
 use ic_interfaces::execution_environment::HypervisorResult;
 use ic_replicated_state::{
     canister_state::{execution_state::WasmMetadata, WASM_PAGE_SIZE_IN_BYTES},
@@ -209,6 +210,10 @@ fn validate_and_instrument(
         config.metering_type,
         config.subnet_type,
         config.dirty_page_overhead,
+        match config.wasm_function_profile_dir {
+            Some(_) => FlagStatus::Enabled,
+            None => FlagStatus::Disabled,
+        },
     )?;
     Ok((wasm_validation_details, instrumentation_output))
 }
@@ -109,6 +109,25 @@
 //! (memory (export "stable_memory_bytemap") i32 (i64.const STABLE_BYTEMAP_SIZE) (i64.const STABLE_BYTEMAP_SIZE))
 //! ```
 //!
+//! # Function profiling
+//!
+//! On local and test replicas the instrumentation can optionally add one
+//! mutable `i64` global per function defined in the original module. Next to
+//! every static instruction counter decrementation the same cost is added to
+//! the global of the enclosing function:
+//!
+//! ```wasm
+//! global.get P
+//! i64.const 8
+//! i64.add
+//! global.set P
+//! ```
+//!
+//! The globals are exported as `canister profile_instructions <index> <name>`
+//! where the name comes from the `name` custom section if it is present. These
+//! globals are neither metered nor persisted, and the profile does not include
+//! the dynamic costs of bulk memory instructions.
+//!
 
 use super::system_api_replacements::replacement_functions;
 use super::validation::API_VERSION_IC0;
@@ -129,8 +148,8 @@ use crate::wasmtime_embedder::{
 };
 use ic_wasm_transform::{self, Global, Module};
 use wasmparser::{
-    BlockType, Export, ExternalKind, FuncType, GlobalType, Import, MemoryType, Operator,
-    StructuralType, SubType, TypeRef, ValType,
+    BlockType, Export, ExternalKind, FuncType, GlobalType, Import, MemoryType, Name,
+    NameSectionReader, Operator, StructuralType, SubType, TypeRef, ValType,
 };
 
 use std::collections::BTreeMap;
@@ -465,6 +484,7 @@ const TABLE_STR: &str = "table";
 pub(crate) const INSTRUCTIONS_COUNTER_GLOBAL_NAME: &str = "canister counter_instructions";
 pub(crate) const DIRTY_PAGES_COUNTER_GLOBAL_NAME: &str = "canister counter_dirty_pages";
 pub(crate) const ACCESSED_PAGES_COUNTER_GLOBAL_NAME: &str = "canister counter_accessed_pages";
+pub(crate) const FUNCTION_PROFILE_GLOBAL_PREFIX: &str = "canister profile_instructions ";
 const CANISTER_START_STR: &str = "canister_start";
 
 /// There is one byte for each OS page in the wasm heap.
@@ -672,7 +692,14 @@ pub(super) fn instrument(
     metering_type: MeteringType,
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
+    function_profiling: FlagStatus,
 ) -> Result<InstrumentationOutput, WasmInstrumentationError> {
+    // The names of the profiled functions have to be determined before the
+    // helper functions are injected because they shift the function indices.
+    let profile_strs: Vec<String> = match function_profiling {
+        FlagStatus::Enabled => profiled_function_export_names(&module),
+        FlagStatus::Disabled => Vec::new(),
+    };
     let stable_memory_index;
     let mut module = inject_helper_functions(module, wasm_native_stable_memory);
     module = export_table(module);
@@ -729,9 +756,28 @@ pub(super) fn instrument(
         module.start = None;
     }
 
+    // The profile globals are placed after the globals added in
+    // `export_additional_symbols`.
+    let first_profile_global_ix = num_globals
+        + match wasm_native_stable_memory {
+            FlagStatus::Enabled => 3,
+            FlagStatus::Disabled => 1,
+        };
+
     // inject instructions counter decrementation
-    for func_body in &mut module.code_sections {
-        inject_metering(&mut func_body.instructions, &special_indices, metering_type);
+    let mut num_profiling_instructions = 0;
+    for (func_ix, func_body) in module.code_sections.iter_mut().enumerate() {
+        let profile_counter_ix = if func_ix < profile_strs.len() {
+            Some(first_profile_global_ix + func_ix as u32)
+        } else {
+            None
+        };
+        num_profiling_instructions += inject_metering(
+            &mut func_body.instructions,
+            &special_indices,
+            metering_type,
+            profile_counter_ix,
+        );
     }
 
     // Collect all the function types of the locally defined functions inside the
@@ -766,6 +812,7 @@ pub(super) fn instrument(
     }
 
     module = export_additional_symbols(module, &special_indices, wasm_native_stable_memory);
+    module = export_function_profile_globals(module, first_profile_global_ix, &profile_strs);
 
     if wasm_native_stable_memory == FlagStatus::Enabled {
         replace_system_api_functions(
@@ -813,9 +860,12 @@ pub(super) fn instrument(
     for body in &module.code_sections {
         wasm_instruction_count += body.instructions.len() as u64;
     }
-    for glob in &module.globals {
+    // The profile globals are the last globals of the module.
+    for glob in &module.globals[..module.globals.len() - profile_strs.len()] {
         wasm_instruction_count += glob.init_expr.len() as u64;
     }
+    // Profiling must not change the compilation cost of the module.
+    wasm_instruction_count -= num_profiling_instructions;
 
     let result = module.encode().map_err(|err| {
         WasmInstrumentationError::WasmSerializeError(WasmError::new(err.to_string()))
@@ -1172,11 +1222,15 @@ impl InjectionPoint {
 // - we insert a function call before each dynamic cost instruction which
 //   performs an overflow check and then decrements the counter by the value at
 //   the top of the stack.
+//
+// If a profile counter is given, the static costs are also added to it. Returns
+// the number of instructions injected for profiling.
 fn inject_metering(
     code: &mut Vec<Operator>,
     export_data_module: &SpecialIndices,
     metering_type: MeteringType,
-) {
+    profile_counter_ix: Option<u32>,
+) -> u64 {
     let points = match metering_type {
         MeteringType::Old => injections_old(code),
         MeteringType::None => Vec::new(),
@@ -1193,6 +1247,7 @@ fn inject_metering(
     let orig_elems = code;
     let mut elems: Vec<Operator> = Vec::new();
     let mut last_injection_position = 0;
+    let mut num_profiling_instructions = 0;
 
     use Operator::*;
 
@@ -1210,6 +1265,16 @@ fn inject_metering(
                         global_index: export_data_module.instructions_counter_ix,
                     },
                 ]);
+                if let Some(global_index) = profile_counter_ix {
+                    let profiling = [
+                        GlobalGet { global_index },
+                        I64Const { value: cost as i64 },
+                        I64Add,
+                        GlobalSet { global_index },
+                    ];
+                    num_profiling_instructions += profiling.len() as u64;
+                    elems.extend_from_slice(&profiling);
+                }
                 if scope == Scope::ReentrantBlockStart {
                     elems.extend_from_slice(&[
                         GlobalGet {
@@ -1244,6 +1309,7 @@ fn inject_metering(
     }
     elems.extend_from_slice(&orig_elems[last_injection_position..]);
     *orig_elems = elems;
+    num_profiling_instructions
 }
 
 // This function adds mem barrier writes, assuming that arguments
@@ -1799,3 +1865,63 @@ fn export_mutable_globals<'a>(
 
     module
 }
+
+// Returns the export names of the profile globals, one for each function
+// defined in the module. Functions without an entry in the `name` custom
+// section are named after their index.
+fn profiled_function_export_names(module: &Module<'_>) -> Vec<String> {
+    let num_imported_functions = module
+        .imports
+        .iter()
+        .filter(|i| matches!(i.ty, TypeRef::Func(_)))
+        .count() as u32;
+
+    let mut names = BTreeMap::new();
+    for (section_name, data) in &module.custom_sections {
+        if *section_name != "name" {
+            continue;
+        }
+        // A malformed name section only makes the profile less readable.
+        for name in NameSectionReader::new(data, 0).flatten() {
+            if let Name::Function(map) = name {
+                for naming in map.into_iter().flatten() {
+                    if let Some(ix) = naming.index.checked_sub(num_imported_functions) {
+                        names.insert(ix, naming.name.replace(';', ":"));
+                    }
+                }
+            }
+        }
+    }
+
+    (0..module.code_sections.len() as u32)
+        .map(|ix| {
+            let name = names
+                .remove(&ix)
+                .unwrap_or_else(|| format!("func_{}", ix + num_imported_functions));
+            format!("{}{} {}", FUNCTION_PROFILE_GLOBAL_PREFIX, ix, name)
+        })
+        .collect()
+}
+
+// Adds and exports one profile global for each of the given export names.
+fn export_function_profile_globals<'a>(
+    mut module: Module<'a>,
+    first_profile_global_ix: u32,
+    export_names: &'a [String],
+) -> Module<'a> {
+    for (ix, name) in export_names.iter().enumerate() {
+        module.globals.push(Global {
+            ty: GlobalType {
+                content_type: ValType::I64,
+                mutable: true,
+            },
+            init_expr: vec![Operator::I64Const { value: 0 }, Operator::End],
+        });
+        module.exports.push(Export {
+            name: name.as_str(),
+            kind: ExternalKind::Global,
+            index: first_profile_global_ix + ix as u32,
+        });
+    }
+    module
+}
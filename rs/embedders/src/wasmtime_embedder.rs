@@ -38,7 +38,7 @@ use signal_stack::WasmtimeSignalStack;
 
 use crate::wasm_utils::instrumentation::{
     ACCESSED_PAGES_COUNTER_GLOBAL_NAME, DIRTY_PAGES_COUNTER_GLOBAL_NAME,
-    INSTRUCTIONS_COUNTER_GLOBAL_NAME,
+    FUNCTION_PROFILE_GLOBAL_PREFIX, INSTRUCTIONS_COUNTER_GLOBAL_NAME,
 };
 use crate::{
     serialized_module::SerializedModuleBytes, wasm_utils::validation::wasmtime_validation_config,
@@ -127,7 +127,10 @@ fn get_exported_globals<T>(
     instance
         .exports(store)
         .filter_map(|e| {
-            if globals_to_ignore.contains(&e.name()) {
+            // Profile globals are never persisted.
+            if globals_to_ignore.contains(&e.name())
+                || e.name().starts_with(FUNCTION_PROFILE_GLOBAL_PREFIX)
+            {
                 None
             } else {
                 e.into_global()
@@ -1135,4 +1138,28 @@ impl WasmtimeInstance {
     pub fn get_stats(&self) -> InstanceStats {
         self.instance_stats.clone()
     }
+
+    /// Returns the number of instructions executed in each Wasm function since
+    /// the instance was created. Only functions that executed at least one
+    /// instruction are included, and the result is empty if the module was not
+    /// instrumented for function profiling.
+    pub fn function_profile(&mut self) -> Vec<(String, u64)> {
+        let profile_globals: Vec<_> = self
+            .instance
+            .exports(&mut self.store)
+            .filter_map(|e| {
+                let name = e.name().strip_prefix(FUNCTION_PROFILE_GLOBAL_PREFIX)?;
+                // The export name is `<index> <function name>`.
+                let (_, function_name) = name.split_once(' ')?;
+                Some((function_name.to_string(), e.into_global()?))
+            })
+            .collect();
+        profile_globals
+            .into_iter()
+            .filter_map(|(name, global)| match global.get(&mut self.store).i64() {
+                Some(count) if count > 0 => Some((name, count as u64)),
+                _ => None,
+            })
+            .collect()
+    }
 }
@@ -669,6 +669,15 @@ pub(crate) fn syscalls(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "call_replicated_query", {
+            move |mut caller: Caller<'_, StoreData>| {
+                charge_for_cpu(&mut caller, overhead!(CALL_REPLICATED_QUERY, metering_type))?;
+                with_system_api(&mut caller, |s| s.ic0_call_replicated_query())
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "call_cycles_add", {
             move |mut caller: Caller<'_, StoreData>, amount: u64| {
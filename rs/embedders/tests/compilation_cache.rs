@@ -0,0 +1,247 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use ic_config::embedders::Config as EmbeddersConfig;
+use ic_embedders::{wasm_utils::compile, CompilationCache, SerializedModule, WasmtimeEmbedder};
+use ic_logger::replica_logger::no_op_logger;
+use ic_types::NumBytes;
+use ic_wasm_types::{BinaryEncodedWasm, CanisterModule};
+
+const MEMORY_CAPACITY: NumBytes = NumBytes::new(1 << 30);
+const DISK_CAPACITY: NumBytes = NumBytes::new(1 << 30);
+const MARKER_FILE_NAME: &str = "ic_compilation_cache";
+
+fn canister_module(constant: i32) -> CanisterModule {
+    let wat = format!(
+        r#"
+        (module
+            (func $run (export "canister_update run")
+                (drop (i32.const {}))
+            )
+        )"#,
+        constant
+    );
+    CanisterModule::new(wat::parse_str(wat).unwrap())
+}
+
+fn compile_module(config: &EmbeddersConfig, module: &CanisterModule) -> SerializedModule {
+    let embedder = WasmtimeEmbedder::new(config.clone(), no_op_logger());
+    let wasm = BinaryEncodedWasm::new(module.as_slice().to_vec());
+    compile(&embedder, &wasm).1.unwrap().1
+}
+
+/// Returns a cache whose disk tier finished loading the persisted modules.
+fn cache_with_disk_tier(dir: &Path, disk_capacity: NumBytes) -> CompilationCache {
+    let cache = CompilationCache::new_with_disk_tier(
+        MEMORY_CAPACITY,
+        dir,
+        disk_capacity,
+        &EmbeddersConfig::default(),
+        no_op_logger(),
+    );
+    cache.sync_disk_tier_for_testing();
+    cache
+}
+
+/// Returns all module files stored under the given directory.
+fn cache_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files.extend(cache_files(&path));
+        } else if path.file_name().unwrap() != MARKER_FILE_NAME {
+            files.push(path);
+        }
+    }
+    files
+}
+
+#[test]
+fn module_survives_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = EmbeddersConfig::default();
+    let module = canister_module(1);
+    let serialized_module = compile_module(&config, &module);
+
+    let cache = cache_with_disk_tier(dir.path(), DISK_CAPACITY);
+    cache.insert(&module, Ok(Arc::new(serialized_module.clone())));
+    drop(cache);
+
+    let cache = cache_with_disk_tier(dir.path(), DISK_CAPACITY);
+    let cached = cache.get(&module).unwrap().unwrap();
+    assert_eq!(cached.bytes.as_slice(), serialized_module.bytes.as_slice());
+    assert_eq!(
+        cached.exported_functions,
+        serialized_module.exported_functions
+    );
+    assert_eq!(cached.compilation_cost, serialized_module.compilation_cost);
+    assert!(cache.get(&canister_module(2)).is_none());
+
+    // The loaded module can be used without recompiling it.
+    let embedder = WasmtimeEmbedder::new(config, no_op_logger());
+    embedder.deserialize_module(&cached.bytes).unwrap();
+}
+
+#[test]
+fn compilation_errors_are_not_persisted() {
+    let dir = tempfile::tempdir().unwrap();
+    let module = CanisterModule::new(b"not a wasm module".to_vec());
+    let embedder = WasmtimeEmbedder::new(EmbeddersConfig::default(), no_op_logger());
+    let err = compile(
+        &embedder,
+        &BinaryEncodedWasm::new(module.as_slice().to_vec()),
+    )
+    .1
+    .unwrap_err();
+
+    let cache = cache_with_disk_tier(dir.path(), DISK_CAPACITY);
+    cache.insert(&module, Err(err));
+    assert!(cache.get(&module).unwrap().is_err());
+    assert!(cache_files(dir.path()).is_empty());
+}
+
+#[test]
+fn corrupted_file_is_removed() {
+    let dir = tempfile::tempdir().unwrap();
+    let module = canister_module(1);
+    let serialized_module = compile_module(&EmbeddersConfig::default(), &module);
+
+    let cache = cache_with_disk_tier(dir.path(), DISK_CAPACITY);
+    cache.insert(&module, Ok(Arc::new(serialized_module)));
+    drop(cache);
+
+    let files = cache_files(dir.path());
+    assert_eq!(files.len(), 1);
+    let mut contents = std::fs::read(&files[0]).unwrap();
+    *contents.last_mut().unwrap() ^= 0xff;
+    std::fs::write(&files[0], contents).unwrap();
+
+    let cache = cache_with_disk_tier(dir.path(), DISK_CAPACITY);
+    assert!(cache.get(&module).is_none());
+    assert!(cache_files(dir.path()).is_empty());
+}
+
+#[test]
+fn modules_of_other_embedder_config_are_not_loaded() {
+    let dir = tempfile::tempdir().unwrap();
+    let module = canister_module(1);
+    let serialized_module = compile_module(&EmbeddersConfig::default(), &module);
+
+    let cache = cache_with_disk_tier(dir.path(), DISK_CAPACITY);
+    cache.insert(&module, Ok(Arc::new(serialized_module)));
+    drop(cache);
+
+    let other_config = EmbeddersConfig {
+        max_globals: EmbeddersConfig::default().max_globals + 1,
+        ..EmbeddersConfig::default()
+    };
+    let cache = CompilationCache::new_with_disk_tier(
+        MEMORY_CAPACITY,
+        dir.path(),
+        DISK_CAPACITY,
+        &other_config,
+        no_op_logger(),
+    );
+    cache.sync_disk_tier_for_testing();
+    assert!(cache.get(&module).is_none());
+    assert!(cache_files(dir.path()).is_empty());
+}
+
+#[test]
+fn least_recently_used_file_is_evicted() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = EmbeddersConfig::default();
+    let modules: Vec<_> = (1..=3).map(canister_module).collect();
+
+    // Measure the size of a single cache file first.
+    let cache = cache_with_disk_tier(dir.path(), DISK_CAPACITY);
+    cache.insert(
+        &modules[0],
+        Ok(Arc::new(compile_module(&config, &modules[0]))),
+    );
+    cache.sync_disk_tier_for_testing();
+    let file_size = std::fs::metadata(&cache_files(dir.path())[0])
+        .unwrap()
+        .len();
+    cache.clear_for_testing();
+    drop(cache);
+
+    // Leave room for two files of a similar size.
+    let disk_capacity = NumBytes::new(2 * file_size + file_size / 2);
+    let cache = cache_with_disk_tier(dir.path(), disk_capacity);
+    for module in &modules {
+        cache.insert(module, Ok(Arc::new(compile_module(&config, module))));
+    }
+    cache.sync_disk_tier_for_testing();
+    assert_eq!(cache_files(dir.path()).len(), 2);
+    drop(cache);
+
+    let cache = cache_with_disk_tier(dir.path(), disk_capacity);
+    assert!(cache.get(&modules[0]).is_none());
+    assert!(cache.get(&modules[1]).is_some());
+    assert!(cache.get(&modules[2]).is_some());
+}
+
+#[test]
+fn unmarked_non_empty_directory_is_not_used() {
+    let dir = tempfile::tempdir().unwrap();
+    let unrelated_dir = dir.path().join("state");
+    std::fs::create_dir(&unrelated_dir).unwrap();
+    std::fs::write(unrelated_dir.join("checkpoint"), b"data").unwrap();
+    let module = canister_module(1);
+
+    let cache = cache_with_disk_tier(dir.path(), DISK_CAPACITY);
+    cache.insert(
+        &module,
+        Ok(Arc::new(compile_module(
+            &EmbeddersConfig::default(),
+            &module,
+        ))),
+    );
+    drop(cache);
+
+    // The cache falls back to the in-memory tier and leaves the directory alone.
+    assert_eq!(
+        cache_files(dir.path()),
+        vec![unrelated_dir.join("checkpoint")]
+    );
+    assert!(!dir.path().join(MARKER_FILE_NAME).exists());
+}
+
+#[test]
+fn only_entries_created_by_the_cache_are_removed() {
+    let dir = tempfile::tempdir().unwrap();
+    let module = canister_module(1);
+
+    let cache = cache_with_disk_tier(dir.path(), DISK_CAPACITY);
+    cache.insert(
+        &module,
+        Ok(Arc::new(compile_module(
+            &EmbeddersConfig::default(),
+            &module,
+        ))),
+    );
+    drop(cache);
+
+    // Entries that the cache could not have created survive a restart with
+    // another configuration.
+    let unrelated_file = dir.path().join("notes.txt");
+    std::fs::write(&unrelated_file, b"data").unwrap();
+    let other_config = EmbeddersConfig {
+        max_globals: EmbeddersConfig::default().max_globals + 1,
+        ..EmbeddersConfig::default()
+    };
+    let cache = CompilationCache::new_with_disk_tier(
+        MEMORY_CAPACITY,
+        dir.path(),
+        DISK_CAPACITY,
+        &other_config,
+        no_op_logger(),
+    );
+    cache.sync_disk_tier_for_testing();
+    assert!(cache.get(&module).is_none());
+    assert_eq!(cache_files(dir.path()), vec![unrelated_file]);
+}
@@ -44,6 +44,10 @@ use std::ops::Rem;
 /// assert_eq!(three_apples.checked_div_ceil(0_u8), None);
 /// assert_eq!(three_apples.checked_div_ceil(2_u8), Some(Apples::TWO));
 ///
+/// // Floor checked division by scalar
+/// assert_eq!(three_apples.checked_div_floor(0_u8), None);
+/// assert_eq!(three_apples.checked_div_floor(2_u8), Some(Apples::ONE));
+///
 /// // (Floor) division by two
 /// assert_eq!(Apples::ONE.div_by_two(), Apples::ZERO);
 /// assert_eq!(Apples::TWO.div_by_two(), Apples::ONE);
@@ -128,6 +132,10 @@ impl<Unit> CheckedAmountOf<Unit> {
         }
     }
 
+    pub fn checked_div_floor<T: Into<ethnum::u256>>(self, rhs: T) -> Option<Self> {
+        self.0.checked_div(rhs.into()).map(Self::from_inner)
+    }
+
     pub fn div_by_two(self) -> Self {
         Self::from_inner(self.0 >> 1)
     }
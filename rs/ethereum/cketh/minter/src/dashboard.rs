@@ -8,6 +8,7 @@ use ic_cketh_minter::endpoints::{EthTransaction, RetrieveEthStatus};
 use ic_cketh_minter::eth_logs::{EventSource, ReceivedEthEvent};
 use ic_cketh_minter::eth_rpc::Hash;
 use ic_cketh_minter::eth_rpc_client::responses::TransactionStatus;
+use ic_cketh_minter::eth_rpc_client::EthRpcClient;
 use ic_cketh_minter::lifecycle::EthereumNetwork;
 use ic_cketh_minter::numeric::{BlockNumber, LedgerBurnIndex, TransactionNonce, Wei};
 use ic_cketh_minter::state::transactions::{EthWithdrawalRequest, Reimbursed};
@@ -69,6 +70,9 @@ pub struct DashboardTemplate {
     pub reimbursed_transactions: Vec<Reimbursed>,
     pub eth_balance: EthBalance,
     pub skipped_blocks: BTreeSet<BlockNumber>,
+    pub rpc_providers: Vec<String>,
+    pub min_agreeing_rpc_providers: usize,
+    pub inconsistent_rpc_responses: BTreeMap<(String, String), u64>,
 }
 
 impl DashboardTemplate {
@@ -129,6 +133,8 @@ impl DashboardTemplate {
         reimbursed_transactions
             .sort_unstable_by_key(|reimbursed_tx| std::cmp::Reverse(reimbursed_tx.withdrawal_id));
 
+        let rpc_client = EthRpcClient::from_state(state);
+
         DashboardTemplate {
             ethereum_network: state.ethereum_network,
             ecdsa_key_name: state.ecdsa_key_name.clone(),
@@ -154,6 +160,9 @@ impl DashboardTemplate {
             reimbursed_transactions,
             eth_balance: state.eth_balance.clone(),
             skipped_blocks: state.skipped_blocks.clone(),
+            rpc_providers: rpc_client.provider_urls().map(String::from).collect(),
+            min_agreeing_rpc_providers: rpc_client.min_agreeing_providers(),
+            inconsistent_rpc_responses: ic_cketh_minter::eth_rpc::inconsistent_responses(),
         }
     }
 }
@@ -15,7 +15,7 @@ use ic_cketh_minter::state::State;
 use ic_cketh_minter::tx::{
     Eip1559Signature, Eip1559TransactionRequest, SignedEip1559TransactionRequest, TransactionPrice,
 };
-use maplit::btreeset;
+use maplit::{btreemap, btreeset};
 use std::str::FromStr;
 
 #[test]
@@ -80,6 +80,25 @@ fn should_display_block_sync() {
     );
 }
 
+#[test]
+fn should_display_rpc_providers() {
+    DashboardAssert::assert_that(initial_dashboard())
+        .has_min_agreeing_rpc_providers("2 out of 2")
+        .has_no_elements_matching("#inconsistent-rpc-responses");
+
+    let dashboard = DashboardTemplate {
+        inconsistent_rpc_responses: btreemap! {
+            ("eth_getLogs".to_string(), "https://rpc.ankr.com/eth_sepolia".to_string()) => 3,
+        },
+        ..initial_dashboard()
+    };
+
+    DashboardAssert::assert_that(dashboard).has_inconsistent_rpc_responses(
+        1,
+        &vec!["eth_getLogs", "https://rpc.ankr.com/eth_sepolia", "3"],
+    );
+}
+
 #[test]
 fn should_display_events_to_mint_sorted_by_decreasing_block_number() {
     DashboardAssert::assert_that(initial_dashboard()).has_no_elements_matching("#events-to-mint");
@@ -882,6 +901,26 @@ mod assertions {
             )
         }
 
+        pub fn has_min_agreeing_rpc_providers(&self, expected_value: &str) -> &Self {
+            self.has_string_value(
+                "#min-agreeing-rpc-providers > td",
+                expected_value,
+                "wrong minimum agreeing RPC providers",
+            )
+        }
+
+        pub fn has_inconsistent_rpc_responses(
+            &self,
+            row_index: u8,
+            expected_value: &Vec<&str>,
+        ) -> &Self {
+            self.has_table_row_string_value(
+                &format!("#inconsistent-rpc-responses + table > tbody > tr:nth-child({row_index})"),
+                expected_value,
+                "inconsistent-rpc-responses",
+            )
+        }
+
         pub fn has_rejected_deposits(&self, row_index: u8, expected_value: &Vec<&str>) -> &Self {
             self.has_table_row_string_value(
                 &format!("#rejected-deposits + table > tbody > tr:nth-child({row_index})"),
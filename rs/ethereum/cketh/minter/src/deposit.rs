@@ -1,6 +1,9 @@
 use crate::address::Address;
-use crate::eth_logs::{report_transaction_error, ReceivedEthEventError};
-use crate::eth_rpc::{BlockSpec, HttpOutcallError};
+use crate::eth_logs::{
+    report_transaction_error, ReceivedErc20Event, ReceivedEthEvent, ReceivedEthEventError,
+    RECEIVED_ERC20_EVENT_TOPIC, RECEIVED_ETH_EVENT_TOPIC,
+};
+use crate::eth_rpc::{BlockSpec, HttpOutcallError, LogEntry};
 use crate::eth_rpc_client::EthRpcClient;
 use crate::guard::TimerGuard;
 use crate::logs::{DEBUG, INFO};
@@ -11,6 +14,7 @@ use crate::state::{
 use ic_canister_log::log;
 use num_traits::ToPrimitive;
 use std::cmp::{min, Ordering};
+use std::fmt;
 use std::time::Duration;
 
 async fn mint_cketh() {
@@ -83,11 +87,232 @@ async fn mint_cketh() {
     }
 }
 
+async fn mint_ckerc20() {
+    use icrc_ledger_client_cdk::{CdkRuntime, ICRC1Client};
+    use icrc_ledger_types::icrc1::transfer::TransferArg;
+
+    let _guard = match TimerGuard::new(TaskType::MintCkErc20) {
+        Ok(guard) => guard,
+        Err(_) => return,
+    };
+
+    let (tokens, events) =
+        read_state(|s| (s.ckerc20_tokens.clone(), s.erc20_events_to_mint.clone()));
+
+    let mut error_count = 0;
+
+    for (event_source, event) in events {
+        let token = tokens
+            .get(&event.erc20_contract_address)
+            .expect("BUG: accepted ERC-20 deposit for an unsupported token");
+        let client = ICRC1Client {
+            runtime: CdkRuntime,
+            ledger_canister_id: token.ckerc20_ledger_id,
+        };
+        let block_index = match client
+            .transfer(TransferArg {
+                from_subaccount: None,
+                to: event.principal.into(),
+                fee: None,
+                created_at_time: None,
+                memo: Some(event.clone().into()),
+                amount: candid::Nat::from(event.value),
+            })
+            .await
+        {
+            Ok(Ok(block_index)) => block_index.0.to_u64().expect("nat does not fit into u64"),
+            Ok(Err(err)) => {
+                log!(
+                    INFO,
+                    "Failed to mint {}: {event:?} {err}",
+                    token.ckerc20_token_symbol
+                );
+                error_count += 1;
+                continue;
+            }
+            Err(err) => {
+                log!(
+                    INFO,
+                    "Failed to send a message to the ledger ({}): {err:?}",
+                    token.ckerc20_ledger_id
+                );
+                error_count += 1;
+                continue;
+            }
+        };
+        mutate_state(|s| {
+            process_event(
+                s,
+                EventType::MintedCkErc20 {
+                    event_source,
+                    mint_block_index: LedgerMintIndex::new(block_index),
+                },
+            )
+        });
+        log!(
+            INFO,
+            "Minted {} {} to {} in block {block_index}",
+            event.value,
+            token.ckerc20_token_symbol,
+            event.principal
+        );
+    }
+
+    if error_count > 0 {
+        log!(
+            INFO,
+            "Failed to mint {error_count} ckERC20 events, rescheduling the minting"
+        );
+        ic_cdk_timers::set_timer(crate::MINT_RETRY_DELAY, || ic_cdk::spawn(mint_ckerc20()));
+    }
+}
+
+/// Describes how to scrap the logs of one of the minter's helper smart contracts
+/// and how to record the discovered deposits in the minter state.
+trait LogScraping {
+    type Event: TryFrom<LogEntry, Error = ReceivedEthEventError> + fmt::Debug;
+    const ID: &'static str;
+    const TOPIC: [u8; 32];
+
+    fn contract_address(state: &State) -> Option<Address>;
+    fn last_scraped_block_number(state: &State) -> BlockNumber;
+    fn update_last_scraped_block_number(state: &mut State, block_number: BlockNumber);
+    fn skipped_block_event(block_number: BlockNumber) -> EventType;
+    /// Validates the deposit and records it as accepted or invalid.
+    fn record_deposit(state: &mut State, event: Self::Event);
+    /// Schedules the minting of the accepted deposits, if any.
+    fn schedule_mint(state: &State);
+}
+
+enum ReceivedEthLogScraping {}
+
+impl LogScraping for ReceivedEthLogScraping {
+    type Event = ReceivedEthEvent;
+    const ID: &'static str = "ETH";
+    const TOPIC: [u8; 32] = RECEIVED_ETH_EVENT_TOPIC;
+
+    fn contract_address(state: &State) -> Option<Address> {
+        state.ethereum_contract_address
+    }
+
+    fn last_scraped_block_number(state: &State) -> BlockNumber {
+        state.last_scraped_block_number
+    }
+
+    fn update_last_scraped_block_number(state: &mut State, block_number: BlockNumber) {
+        state.last_scraped_block_number = block_number;
+    }
+
+    fn skipped_block_event(block_number: BlockNumber) -> EventType {
+        EventType::SkippedBlock(block_number)
+    }
+
+    fn record_deposit(state: &mut State, event: ReceivedEthEvent) {
+        log!(
+            INFO,
+            "Received event {event:?}; will mint {} wei to {}",
+            event.value,
+            event.principal
+        );
+        if crate::blocklist::is_blocked(event.from_address) {
+            log!(
+                INFO,
+                "Received event from a blocked address: {} for {} WEI",
+                event.from_address,
+                event.value,
+            );
+            process_event(
+                state,
+                EventType::InvalidDeposit {
+                    event_source: event.source(),
+                    reason: format!("blocked address {}", event.from_address),
+                },
+            );
+        } else {
+            process_event(state, EventType::AcceptedDeposit(event));
+        }
+    }
+
+    fn schedule_mint(state: &State) {
+        if state.has_events_to_mint() {
+            ic_cdk_timers::set_timer(Duration::from_secs(0), || ic_cdk::spawn(mint_cketh()));
+        }
+    }
+}
+
+enum ReceivedErc20LogScraping {}
+
+impl LogScraping for ReceivedErc20LogScraping {
+    type Event = ReceivedErc20Event;
+    const ID: &'static str = "ERC-20";
+    const TOPIC: [u8; 32] = RECEIVED_ERC20_EVENT_TOPIC;
+
+    fn contract_address(state: &State) -> Option<Address> {
+        if state.ckerc20_tokens.is_empty() {
+            return None;
+        }
+        state.erc20_helper_contract_address
+    }
+
+    fn last_scraped_block_number(state: &State) -> BlockNumber {
+        state.last_erc20_scraped_block_number
+    }
+
+    fn update_last_scraped_block_number(state: &mut State, block_number: BlockNumber) {
+        state.last_erc20_scraped_block_number = block_number;
+    }
+
+    fn skipped_block_event(block_number: BlockNumber) -> EventType {
+        EventType::SkippedErc20Block(block_number)
+    }
+
+    fn record_deposit(state: &mut State, event: ReceivedErc20Event) {
+        log!(
+            INFO,
+            "Received event {event:?}; will mint {} ckERC20 to {}",
+            event.value,
+            event.principal
+        );
+        let invalid_reason = if crate::blocklist::is_blocked(event.from_address) {
+            Some(format!("blocked address {}", event.from_address))
+        } else if !state
+            .ckerc20_tokens
+            .contains_key(&event.erc20_contract_address)
+        {
+            Some(format!(
+                "unsupported ERC-20 token {}",
+                event.erc20_contract_address
+            ))
+        } else {
+            None
+        };
+        match invalid_reason {
+            Some(reason) => {
+                log!(INFO, "Received invalid ERC-20 deposit {event:?}: {reason}");
+                process_event(
+                    state,
+                    EventType::InvalidDeposit {
+                        event_source: event.source(),
+                        reason,
+                    },
+                );
+            }
+            None => process_event(state, EventType::AcceptedErc20Deposit(event)),
+        }
+    }
+
+    fn schedule_mint(state: &State) {
+        if state.has_erc20_events_to_mint() {
+            ic_cdk_timers::set_timer(Duration::from_secs(0), || ic_cdk::spawn(mint_ckerc20()));
+        }
+    }
+}
+
 /// Scraps Ethereum logs between `from` and `min(from + MAX_BLOCK_SPREAD, to)` since certain RPC providers
 /// require that the number of blocks queried is no greater than MAX_BLOCK_SPREAD.
 /// Returns the last block number that was scraped (which is `min(from + MAX_BLOCK_SPREAD, to)`) if there
 /// was no error when querying the providers, otherwise returns `None`.
-async fn scrap_eth_logs_range_inclusive(
+async fn scrap_logs_range_inclusive<S: LogScraping>(
     contract_address: Address,
     from: BlockNumber,
     to: BlockNumber,
@@ -103,14 +328,16 @@ async fn scrap_eth_logs_range_inclusive(
             let mut last_block_number = min(max_to, to);
             log!(
                 DEBUG,
-                "Scrapping ETH logs from block {:?} to block {:?}...",
+                "Scrapping {} logs from block {:?} to block {:?}...",
+                S::ID,
                 from,
                 last_block_number
             );
 
             let (transaction_events, errors) = loop {
-                match crate::eth_logs::last_received_eth_events(
+                match crate::eth_logs::last_received_events::<S::Event>(
                     contract_address,
+                    S::TOPIC,
                     from,
                     last_block_number,
                 )
@@ -120,15 +347,16 @@ async fn scrap_eth_logs_range_inclusive(
                     Err(e) => {
                         log!(
                         INFO,
-                        "Failed to get ETH logs from block {from} to block {last_block_number}: {e:?}",
+                        "Failed to get {} logs from block {from} to block {last_block_number}: {e:?}",
+                        S::ID,
                     );
                         if e.has_http_outcall_error_matching(
                             HttpOutcallError::is_response_too_large,
                         ) {
                             if from == last_block_number {
                                 mutate_state(|s| {
-                                    process_event(s, EventType::SkippedBlock(last_block_number));
-                                    s.last_scraped_block_number = last_block_number;
+                                    process_event(s, S::skipped_block_event(last_block_number));
+                                    S::update_last_scraped_block_number(s, last_block_number);
                                 });
                                 return Some(last_block_number);
                             } else {
@@ -149,38 +377,9 @@ async fn scrap_eth_logs_range_inclusive(
             };
 
             for event in transaction_events {
-                log!(
-                    INFO,
-                    "Received event {event:?}; will mint {} wei to {}",
-                    event.value,
-                    event.principal
-                );
-                if crate::blocklist::is_blocked(event.from_address) {
-                    log!(
-                        INFO,
-                        "Received event from a blocked address: {} for {} WEI",
-                        event.from_address,
-                        event.value,
-                    );
-                    mutate_state(|s| {
-                        process_event(
-                            s,
-                            EventType::InvalidDeposit {
-                                event_source: crate::eth_logs::EventSource {
-                                    transaction_hash: event.transaction_hash,
-                                    log_index: event.log_index,
-                                },
-                                reason: format!("blocked address {}", event.from_address),
-                            },
-                        )
-                    });
-                } else {
-                    mutate_state(|s| process_event(s, EventType::AcceptedDeposit(event)));
-                }
-            }
-            if read_state(State::has_events_to_mint) {
-                ic_cdk_timers::set_timer(Duration::from_secs(0), || ic_cdk::spawn(mint_cketh()));
+                mutate_state(|s| S::record_deposit(s, event));
             }
+            read_state(S::schedule_mint);
             for error in errors {
                 if let ReceivedEthEventError::InvalidEventSource { source, error } = &error {
                     mutate_state(|s| {
@@ -195,7 +394,7 @@ async fn scrap_eth_logs_range_inclusive(
                 }
                 report_transaction_error(error);
             }
-            mutate_state(|s| s.last_scraped_block_number = last_block_number);
+            mutate_state(|s| S::update_last_scraped_block_number(s, last_block_number));
             Some(last_block_number)
         }
         Ordering::Greater => {
@@ -212,33 +411,49 @@ pub async fn scrap_eth_logs() {
         Ok(guard) => guard,
         Err(_) => return,
     };
-    let contract_address = match read_state(|s| s.ethereum_contract_address) {
-        Some(address) => address,
+    if read_state(|s| {
+        ReceivedEthLogScraping::contract_address(s).is_none()
+            && ReceivedErc20LogScraping::contract_address(s).is_none()
+    }) {
+        log!(
+            DEBUG,
+            "[scrap_eth_logs]: skipping scrapping logs: no contract address"
+        );
+        return;
+    }
+    let last_block_number = match update_last_observed_block_number().await {
+        Some(block_number) => block_number,
         None => {
             log!(
                 DEBUG,
-                "[scrap_eth_logs]: skipping scrapping ETH logs: no contract address"
+                "[scrap_eth_logs]: skipping scrapping logs: no last observed block number"
             );
             return;
         }
     };
-    let last_block_number = match update_last_observed_block_number().await {
-        Some(block_number) => block_number,
+    scrap_logs::<ReceivedEthLogScraping>(last_block_number).await;
+    scrap_logs::<ReceivedErc20LogScraping>(last_block_number).await;
+}
+
+async fn scrap_logs<S: LogScraping>(last_block_number: BlockNumber) {
+    let contract_address = match read_state(S::contract_address) {
+        Some(address) => address,
         None => {
             log!(
                 DEBUG,
-                "[scrap_eth_logs]: skipping scrapping ETH logs: no last observed block number"
+                "[scrap_logs]: skipping scrapping {} logs: no contract address",
+                S::ID
             );
             return;
         }
     };
-    let mut last_scraped_block_number = read_state(|s| s.last_scraped_block_number);
+    let mut last_scraped_block_number = read_state(S::last_scraped_block_number);
 
     while last_scraped_block_number < last_block_number {
         let next_block_to_query = last_scraped_block_number
             .checked_increment()
             .unwrap_or(BlockNumber::MAX);
-        last_scraped_block_number = match scrap_eth_logs_range_inclusive(
+        last_scraped_block_number = match scrap_logs_range_inclusive::<S>(
             contract_address,
             next_block_to_query,
             last_block_number,
@@ -1,6 +1,7 @@
+use crate::erc20::CkErc20Token as InternalCkErc20Token;
 use crate::state::transactions::EthWithdrawalRequest;
 use crate::tx::{SignedEip1559TransactionRequest, TransactionPrice};
-use candid::{CandidType, Deserialize, Nat};
+use candid::{CandidType, Deserialize, Nat, Principal};
 use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
 use minicbor::{Decode, Encode};
 use std::fmt::{Display, Formatter};
@@ -165,6 +166,128 @@ impl From<TransferFromError> for WithdrawalError {
     }
 }
 
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CkErc20Token {
+    pub ckerc20_token_symbol: String,
+    pub erc20_contract_address: String,
+    pub ledger_canister_id: Principal,
+}
+
+impl From<InternalCkErc20Token> for CkErc20Token {
+    fn from(value: InternalCkErc20Token) -> Self {
+        Self {
+            ckerc20_token_symbol: value.ckerc20_token_symbol,
+            erc20_contract_address: value.erc20_contract_address.to_string(),
+            ledger_canister_id: value.ckerc20_ledger_id,
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize)]
+pub struct WithdrawErc20Arg {
+    pub amount: Nat,
+    pub ckerc20_ledger_id: Principal,
+    pub recipient: String,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct RetrieveErc20Request {
+    /// Index of the ckETH burn transaction paying for the transaction fee.
+    /// It identifies the withdrawal and can be used with `retrieve_eth_status`.
+    pub cketh_block_index: Nat,
+    /// Index of the ckERC20 burn transaction.
+    pub ckerc20_block_index: Nat,
+}
+
+#[derive(CandidType, Deserialize, Debug, PartialEq)]
+pub enum WithdrawErc20Error {
+    TokenNotSupported {
+        supported_tokens: Vec<CkErc20Token>,
+    },
+    RecipientAddressBlocked {
+        address: String,
+    },
+    /// Burning ckETH to pay for the transaction fee failed.
+    /// No funds were burned.
+    CkEthLedgerError {
+        error: LedgerError,
+    },
+    /// Burning ckERC20 tokens failed.
+    /// The ckETH burned in the block `cketh_block_index` will be reimbursed.
+    CkErc20LedgerError {
+        cketh_block_index: Nat,
+        error: LedgerError,
+    },
+    TemporarilyUnavailable(String),
+}
+
+#[derive(CandidType, Deserialize, Debug, PartialEq)]
+pub enum LedgerError {
+    InsufficientFunds {
+        balance: Nat,
+        failed_burn_amount: Nat,
+        token_symbol: String,
+        ledger_id: Principal,
+    },
+    InsufficientAllowance {
+        allowance: Nat,
+        failed_burn_amount: Nat,
+        token_symbol: String,
+        ledger_id: Principal,
+    },
+    TemporarilyUnavailable(String),
+}
+
+impl LedgerError {
+    /// Converts the error returned by the ledger `ledger_id` when the minter tried to burn
+    /// `failed_burn_amount` tokens with symbol `token_symbol`.
+    pub fn from_transfer_from_error(
+        error: TransferFromError,
+        failed_burn_amount: Nat,
+        token_symbol: String,
+        ledger_id: Principal,
+    ) -> Self {
+        match error {
+            TransferFromError::InsufficientFunds { balance } => Self::InsufficientFunds {
+                balance,
+                failed_burn_amount,
+                token_symbol,
+                ledger_id,
+            },
+            TransferFromError::InsufficientAllowance { allowance } => {
+                Self::InsufficientAllowance {
+                    allowance,
+                    failed_burn_amount,
+                    token_symbol,
+                    ledger_id,
+                }
+            }
+            TransferFromError::TemporarilyUnavailable => Self::TemporarilyUnavailable(format!(
+                "{token_symbol} ledger temporarily unavailable, try again"
+            )),
+            TransferFromError::GenericError {
+                error_code,
+                message,
+            } => Self::TemporarilyUnavailable(format!(
+                "{token_symbol} ledger unreachable, error code: {error_code}, with message: {message}"
+            )),
+            TransferFromError::BadFee { expected_fee } => {
+                panic!("bug: bad fee, expected fee: {expected_fee}")
+            }
+            TransferFromError::BadBurn { min_burn_amount } => {
+                panic!("bug: bad burn, minimum burn amount: {min_burn_amount}")
+            }
+            TransferFromError::TooOld => panic!("bug: transfer too old"),
+            TransferFromError::CreatedInFuture { ledger_time } => {
+                panic!("bug: created in future, ledger time: {ledger_time}")
+            }
+            TransferFromError::Duplicate { duplicate_of } => {
+                panic!("bug: duplicate transfer of: {duplicate_of}")
+            }
+        }
+    }
+}
+
 pub mod events {
     use crate::lifecycle::init::InitArg;
     use crate::lifecycle::upgrade::UpgradeArg;
@@ -286,5 +409,52 @@ pub mod events {
         SkippedBlock {
             block_number: Nat,
         },
+        AcceptedErc20Deposit {
+            transaction_hash: String,
+            block_number: Nat,
+            log_index: Nat,
+            from_address: String,
+            value: Nat,
+            principal: Principal,
+            erc20_contract_address: String,
+        },
+        MintedCkErc20 {
+            event_source: EventSource,
+            mint_block_index: Nat,
+        },
+        SyncedErc20ToBlock {
+            block_number: Nat,
+        },
+        AcceptedErc20WithdrawalRequest {
+            max_transaction_fee: Nat,
+            withdrawal_amount: Nat,
+            destination: String,
+            cketh_ledger_burn_index: Nat,
+            erc20_contract_address: String,
+            ckerc20_ledger_id: Principal,
+            ckerc20_ledger_burn_index: Nat,
+            from: Principal,
+            from_subaccount: Option<[u8; 32]>,
+            created_at: u64,
+        },
+        FailedErc20WithdrawalRequest {
+            withdrawal_id: Nat,
+            reimbursed_amount: Nat,
+            to: Principal,
+            to_subaccount: Option<[u8; 32]>,
+        },
+        ReimbursedErc20Withdrawal {
+            withdrawal_id: Nat,
+            ckerc20_ledger_id: Principal,
+            reimbursed_in_block: Nat,
+            reimbursed_amount: Nat,
+            transaction_hash: Option<String>,
+        },
+        SkippedErc20Block {
+            block_number: Nat,
+        },
+        ExpiredErc20WithdrawalRequest {
+            withdrawal_id: Nat,
+        },
     }
 }
@@ -0,0 +1,84 @@
+//! Support for ckERC20 tokens, i.e., ERC-20 tokens held by the minter on Ethereum
+//! and represented on the IC by a dedicated ICRC ledger per token.
+
+#[cfg(test)]
+mod tests;
+
+use crate::address::Address;
+use crate::lifecycle::upgrade::AddCkErc20Token;
+use crate::numeric::{Erc20Value, GasAmount};
+use candid::Principal;
+use hex_literal::hex;
+use minicbor::{Decode, Encode};
+use std::fmt;
+use std::str::FromStr;
+
+/// Selector of the ERC-20 function `transfer(address,uint256)`,
+/// i.e., the first 4 bytes of `keccak256("transfer(address,uint256)")`.
+pub const ERC20_TRANSFER_FUNCTION_SELECTOR: [u8; 4] = hex!("a9059cbb");
+
+/// Gas limit attached to a transaction calling `transfer` on an ERC-20 smart contract.
+/// Unlike a plain ETH transfer, the cost depends on the contract code, so we use a generous
+/// upper bound that covers the well-known token implementations.
+pub const ERC20_TRANSFER_GAS_LIMIT: GasAmount = GasAmount::new(65_000);
+
+/// An ERC-20 token supported by the minter.
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+pub struct CkErc20Token {
+    /// Address of the ERC-20 smart contract on Ethereum.
+    #[n(0)]
+    pub erc20_contract_address: Address,
+    /// Symbol of the token on the IC, e.g. "ckUSDC".
+    #[n(1)]
+    pub ckerc20_token_symbol: String,
+    /// The ledger handling transfers of that ckERC20 token.
+    /// The default account of the minter must be the minting account of that ledger.
+    #[cbor(n(2), with = "crate::cbor::principal")]
+    pub ckerc20_ledger_id: Principal,
+}
+
+impl fmt::Debug for CkErc20Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CkErc20Token")
+            .field("erc20_contract_address", &self.erc20_contract_address)
+            .field("ckerc20_token_symbol", &self.ckerc20_token_symbol)
+            .field(
+                "ckerc20_ledger_id",
+                &format_args!("{}", self.ckerc20_ledger_id),
+            )
+            .finish()
+    }
+}
+
+impl TryFrom<AddCkErc20Token> for CkErc20Token {
+    type Error = String;
+
+    fn try_from(value: AddCkErc20Token) -> Result<Self, Self::Error> {
+        let erc20_contract_address = Address::from_str(&value.erc20_contract_address)?;
+        if erc20_contract_address == Address::ZERO {
+            return Err("ERC-20 contract address cannot be the zero address".to_string());
+        }
+        if value.ckerc20_token_symbol.trim().is_empty() {
+            return Err("ckERC20 token symbol cannot be blank".to_string());
+        }
+        if value.ckerc20_ledger_id == Principal::anonymous() {
+            return Err("ckERC20 ledger ID cannot be the anonymous principal".to_string());
+        }
+        Ok(Self {
+            erc20_contract_address,
+            ckerc20_token_symbol: value.ckerc20_token_symbol,
+            ckerc20_ledger_id: value.ckerc20_ledger_id,
+        })
+    }
+}
+
+/// ABI-encodes a call to `transfer(address to, uint256 value)`:
+/// the 4-byte function selector followed by the two arguments, each padded to 32 bytes.
+pub fn encode_erc20_transfer_data(to: &Address, value: Erc20Value) -> Vec<u8> {
+    let mut data = Vec::with_capacity(4 + 32 + 32);
+    data.extend_from_slice(&ERC20_TRANSFER_FUNCTION_SELECTOR);
+    data.extend_from_slice(&[0_u8; 12]);
+    data.extend_from_slice(to.as_ref());
+    data.extend_from_slice(&value.to_be_bytes());
+    data
+}
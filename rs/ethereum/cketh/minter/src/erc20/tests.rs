@@ -0,0 +1,84 @@
+mod encode_erc20_transfer_data {
+    use crate::address::Address;
+    use crate::erc20::{encode_erc20_transfer_data, ERC20_TRANSFER_FUNCTION_SELECTOR};
+    use crate::numeric::Erc20Value;
+    use ic_crypto_sha3::Keccak256;
+    use std::str::FromStr;
+
+    #[test]
+    fn should_have_correct_function_selector() {
+        let hash = Keccak256::hash("transfer(address,uint256)");
+        assert_eq!(hash[..4], ERC20_TRANSFER_FUNCTION_SELECTOR);
+    }
+
+    #[test]
+    fn should_encode_transfer_call() {
+        let to = Address::from_str("0xdd2851cdd40ae6536831558dd46db62fac7a844d").unwrap();
+        let data = encode_erc20_transfer_data(&to, Erc20Value::from(1_000_000_u64));
+
+        assert_eq!(
+            hex::encode(data),
+            "a9059cbb\
+            000000000000000000000000dd2851cdd40ae6536831558dd46db62fac7a844d\
+            00000000000000000000000000000000000000000000000000000000000f4240"
+        );
+    }
+}
+
+mod ckerc20_token {
+    use crate::erc20::CkErc20Token;
+    use crate::lifecycle::upgrade::AddCkErc20Token;
+    use assert_matches::assert_matches;
+    use candid::Principal;
+
+    fn valid_token() -> AddCkErc20Token {
+        AddCkErc20Token {
+            erc20_contract_address: "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238".to_string(),
+            ckerc20_token_symbol: "ckSepoliaUSDC".to_string(),
+            ckerc20_ledger_id: Principal::from_text("mxzaz-hqaaa-aaaar-qaada-cai").unwrap(),
+        }
+    }
+
+    #[test]
+    fn should_accept_valid_token() {
+        let token = CkErc20Token::try_from(valid_token()).unwrap();
+
+        assert_eq!(
+            token.erc20_contract_address.to_string(),
+            "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238"
+        );
+        assert_eq!(token.ckerc20_token_symbol, "ckSepoliaUSDC");
+    }
+
+    #[test]
+    fn should_reject_invalid_token() {
+        assert_matches!(
+            CkErc20Token::try_from(AddCkErc20Token {
+                erc20_contract_address: "0x0000000000000000000000000000000000000000".to_string(),
+                ..valid_token()
+            }),
+            Err(_)
+        );
+        assert_matches!(
+            CkErc20Token::try_from(AddCkErc20Token {
+                erc20_contract_address: "invalid".to_string(),
+                ..valid_token()
+            }),
+            Err(_)
+        );
+        assert_matches!(
+            CkErc20Token::try_from(AddCkErc20Token {
+                ckerc20_token_symbol: " ".to_string(),
+                ..valid_token()
+            }),
+            Err(_)
+        );
+        assert_matches!(
+            CkErc20Token::try_from(AddCkErc20Token {
+                ckerc20_ledger_id: Principal::anonymous(),
+                ..valid_token()
+            }),
+            Err(_)
+        );
+    }
+}
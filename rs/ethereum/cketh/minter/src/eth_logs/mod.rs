@@ -5,7 +5,7 @@ use crate::address::Address;
 use crate::eth_rpc::{FixedSizeData, Hash, LogEntry};
 use crate::eth_rpc_client::{EthRpcClient, MultiCallError};
 use crate::logs::{DEBUG, INFO};
-use crate::numeric::{BlockNumber, LogIndex, Wei};
+use crate::numeric::{BlockNumber, Erc20Value, LogIndex, Wei};
 use crate::state::read_state;
 use candid::Principal;
 use hex_literal::hex;
@@ -17,6 +17,9 @@ use thiserror::Error;
 pub(crate) const RECEIVED_ETH_EVENT_TOPIC: [u8; 32] =
     hex!("257e057bb61920d8d0ed2cb7b720ac7f9c513cd1110bc9fa543079154f45f435");
 
+pub(crate) const RECEIVED_ERC20_EVENT_TOPIC: [u8; 32] =
+    hex!("4d69d0bd4287b7f66c548f90154dc81bc98f65a1b362775df5ae171a2ccd262b");
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Encode, Decode)]
 pub struct ReceivedEthEvent {
     #[n(0)]
@@ -46,6 +49,38 @@ impl fmt::Debug for ReceivedEthEvent {
     }
 }
 
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Encode, Decode)]
+pub struct ReceivedErc20Event {
+    #[n(0)]
+    pub transaction_hash: Hash,
+    #[n(1)]
+    pub block_number: BlockNumber,
+    #[cbor(n(2))]
+    pub log_index: LogIndex,
+    #[n(3)]
+    pub from_address: Address,
+    #[n(4)]
+    pub value: Erc20Value,
+    #[cbor(n(5), with = "crate::cbor::principal")]
+    pub principal: Principal,
+    #[n(6)]
+    pub erc20_contract_address: Address,
+}
+
+impl fmt::Debug for ReceivedErc20Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReceivedErc20Event")
+            .field("transaction_hash", &self.transaction_hash)
+            .field("block_number", &self.block_number)
+            .field("log_index", &self.log_index)
+            .field("from_address", &self.from_address)
+            .field("value", &self.value)
+            .field("principal", &format_args!("{}", self.principal))
+            .field("erc20_contract_address", &self.erc20_contract_address)
+            .finish()
+    }
+}
+
 /// A unique identifier of the event source: the source transaction hash and the log
 /// entry index.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Encode, Decode)]
@@ -71,11 +106,26 @@ impl ReceivedEthEvent {
     }
 }
 
-pub async fn last_received_eth_events(
+impl ReceivedErc20Event {
+    pub fn source(&self) -> EventSource {
+        EventSource {
+            transaction_hash: self.transaction_hash,
+            log_index: self.log_index,
+        }
+    }
+}
+
+/// Retrieves the logs with the given topic emitted by the contract at `contract_address`
+/// between blocks `from` and `to` (inclusive) and parses them into events of type `E`.
+pub async fn last_received_events<E>(
     contract_address: Address,
+    topic: [u8; 32],
     from: BlockNumber,
     to: BlockNumber,
-) -> Result<(Vec<ReceivedEthEvent>, Vec<ReceivedEthEventError>), MultiCallError<Vec<LogEntry>>> {
+) -> Result<(Vec<E>, Vec<ReceivedEthEventError>), MultiCallError<Vec<LogEntry>>>
+where
+    E: TryFrom<LogEntry, Error = ReceivedEthEventError>,
+{
     use crate::eth_rpc::GetLogsParam;
 
     if from > to {
@@ -90,15 +140,13 @@ pub async fn last_received_eth_events(
             from_block: from.into(),
             to_block: to.into(),
             address: vec![contract_address],
-            topics: vec![FixedSizeData(RECEIVED_ETH_EVENT_TOPIC)],
+            topics: vec![FixedSizeData(topic)],
         })
         .await?;
 
-    let (ok, not_ok): (Vec<_>, Vec<_>) = result
-        .into_iter()
-        .map(ReceivedEthEvent::try_from)
-        .partition(Result::is_ok);
-    let valid_transactions: Vec<ReceivedEthEvent> = ok.into_iter().map(Result::unwrap).collect();
+    let (ok, not_ok): (Vec<_>, Vec<_>) =
+        result.into_iter().map(E::try_from).partition(Result::is_ok);
+    let valid_transactions: Vec<E> = ok.into_iter().map(Result::unwrap).collect();
     let errors: Vec<ReceivedEthEventError> = not_ok.into_iter().map(Result::unwrap_err).collect();
     Ok((valid_transactions, errors))
 }
@@ -137,10 +185,20 @@ pub enum EventSourceError {
     InvalidEvent(String),
 }
 
-impl TryFrom<LogEntry> for ReceivedEthEvent {
-    type Error = ReceivedEthEventError;
+/// Metadata common to all log entries of interest, once the entry was validated
+/// not to be pending nor removed from the chain.
+struct ValidLogEntry {
+    block_number: BlockNumber,
+    event_source: EventSource,
+    topics: Vec<FixedSizeData>,
+    data: Vec<u8>,
+}
 
-    fn try_from(entry: LogEntry) -> Result<Self, Self::Error> {
+impl ValidLogEntry {
+    fn try_from_entry(
+        entry: LogEntry,
+        expected_topics: usize,
+    ) -> Result<Self, ReceivedEthEventError> {
         let _block_hash = entry
             .block_hash
             .ok_or(ReceivedEthEventError::PendingLogEntry)?;
@@ -170,53 +228,101 @@ impl TryFrom<LogEntry> for ReceivedEthEvent {
             });
         }
 
-        if entry.topics.len() != 3 {
+        if entry.topics.len() != expected_topics {
             return Err(ReceivedEthEventError::InvalidEventSource {
                 source: event_source,
                 error: EventSourceError::InvalidEvent(format!(
-                    "Expected exactly 3 topics, got {}",
+                    "Expected exactly {} topics, got {}",
+                    expected_topics,
                     entry.topics.len()
                 )),
             });
         }
-        let from_address = Address::try_from(&entry.topics[1].0).map_err(|err| {
+
+        Ok(Self {
+            block_number,
+            event_source,
+            topics: entry.topics,
+            data: entry.data.0,
+        })
+    }
+
+    fn parse_address(&self, topic_index: usize) -> Result<Address, ReceivedEthEventError> {
+        Address::try_from(&self.topics[topic_index].0).map_err(|err| {
             ReceivedEthEventError::InvalidEventSource {
-                source: event_source,
+                source: self.event_source,
                 error: EventSourceError::InvalidEvent(format!(
                     "Invalid address in log entry: {}",
                     err
                 )),
             }
-        })?;
-        let principal = parse_principal_from_slice(entry.topics[2].as_ref()).map_err(|_err| {
+        })
+    }
+
+    fn parse_principal(&self, topic_index: usize) -> Result<Principal, ReceivedEthEventError> {
+        parse_principal_from_slice(self.topics[topic_index].as_ref()).map_err(|_err| {
             ReceivedEthEventError::InvalidEventSource {
-                source: event_source,
+                source: self.event_source,
                 error: EventSourceError::InvalidPrincipal {
-                    invalid_principal: entry.topics[2].clone(),
+                    invalid_principal: self.topics[topic_index].clone(),
                 },
             }
-        })?;
-        let value_bytes: [u8; 32] =
-            entry
-                .data
-                .0
-                .try_into()
-                .map_err(|data| ReceivedEthEventError::InvalidEventSource {
-                    source: event_source,
-                    error: EventSourceError::InvalidEvent(format!(
-                        "Invalid data length; expected 32-byte value, got {}",
-                        hex::encode(data)
-                    )),
-                })?;
-        let value = Wei::from_be_bytes(value_bytes);
+        })
+    }
+
+    fn parse_value_bytes(&self) -> Result<[u8; 32], ReceivedEthEventError> {
+        self.data.clone().try_into().map_err(|data: Vec<u8>| {
+            ReceivedEthEventError::InvalidEventSource {
+                source: self.event_source,
+                error: EventSourceError::InvalidEvent(format!(
+                    "Invalid data length; expected 32-byte value, got {}",
+                    hex::encode(data)
+                )),
+            }
+        })
+    }
+}
+
+impl TryFrom<LogEntry> for ReceivedEthEvent {
+    type Error = ReceivedEthEventError;
+
+    /// Parses a `ReceivedEth(address indexed from, uint256 value, bytes32 indexed principal)` event.
+    fn try_from(entry: LogEntry) -> Result<Self, Self::Error> {
+        let entry = ValidLogEntry::try_from_entry(entry, 3)?;
+        let from_address = entry.parse_address(1)?;
+        let principal = entry.parse_principal(2)?;
+        let value = Wei::from_be_bytes(entry.parse_value_bytes()?);
 
         Ok(ReceivedEthEvent {
-            transaction_hash,
-            block_number,
-            log_index,
+            transaction_hash: entry.event_source.transaction_hash,
+            block_number: entry.block_number,
+            log_index: entry.event_source.log_index,
+            from_address,
+            value,
+            principal,
+        })
+    }
+}
+
+impl TryFrom<LogEntry> for ReceivedErc20Event {
+    type Error = ReceivedEthEventError;
+
+    /// Parses a `ReceivedErc20(address indexed erc20_contract_address, address indexed owner, uint256 amount, bytes32 indexed principal)` event.
+    fn try_from(entry: LogEntry) -> Result<Self, Self::Error> {
+        let entry = ValidLogEntry::try_from_entry(entry, 4)?;
+        let erc20_contract_address = entry.parse_address(1)?;
+        let from_address = entry.parse_address(2)?;
+        let principal = entry.parse_principal(3)?;
+        let value = Erc20Value::from_be_bytes(entry.parse_value_bytes()?);
+
+        Ok(ReceivedErc20Event {
+            transaction_hash: entry.event_source.transaction_hash,
+            block_number: entry.block_number,
+            log_index: entry.event_source.log_index,
             from_address,
             value,
             principal,
+            erc20_contract_address,
         })
     }
 }
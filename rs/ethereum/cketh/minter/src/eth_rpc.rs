@@ -18,6 +18,7 @@ use ic_cdk::api::management_canister::http_request::{
 };
 use ic_cdk_macros::query;
 pub use metrics::encode as encode_metrics;
+pub use metrics::inconsistent_responses;
 use minicbor::{Decode, Encode};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::fmt;
@@ -773,6 +774,12 @@ pub(super) mod metrics {
     pub struct HttpMetrics {
         /// Retry counts histograms indexed by the ETH RCP method name.
         retry_histogram_per_method: BTreeMap<String, RetryHistogram>,
+        /// Number of responses that disagreed with the other providers,
+        /// indexed by the ETH RPC method name and the provider URL.
+        inconsistent_responses: BTreeMap<(String, String), u64>,
+        /// Number of calls for which no quorum of providers could be reached,
+        /// indexed by the ETH RPC method name.
+        no_quorum_count_per_method: BTreeMap<String, u64>,
     }
 
     impl HttpMetrics {
@@ -783,6 +790,20 @@ pub(super) mod metrics {
                 .observe_retry_count(count);
         }
 
+        pub fn observe_inconsistent_response(&mut self, method: &str, provider_url: &str) {
+            *self
+                .inconsistent_responses
+                .entry((method.to_string(), provider_url.to_string()))
+                .or_default() += 1;
+        }
+
+        pub fn observe_no_quorum(&mut self, method: &str) {
+            *self
+                .no_quorum_count_per_method
+                .entry(method.to_string())
+                .or_default() += 1;
+        }
+
         #[cfg(test)]
         pub fn count_retries_in_bucket(&self, method: &str, count: usize) -> u64 {
             match self.retry_histogram_per_method.get(method) {
@@ -795,21 +816,45 @@ pub(super) mod metrics {
             &self,
             encoder: &mut MetricsEncoder<W>,
         ) -> std::io::Result<()> {
-            if self.retry_histogram_per_method.is_empty() {
-                return Ok(());
+            if !self.retry_histogram_per_method.is_empty() {
+                let mut histogram_vec = encoder.histogram_vec(
+                    "cketh_eth_rpc_call_retry_count",
+                    "The number of ETH RPC call retries by method.",
+                )?;
+
+                for (method, histogram) in &self.retry_histogram_per_method {
+                    histogram_vec = histogram_vec.histogram(
+                        &[("method", method.as_str())],
+                        histogram.iter(),
+                        histogram.retry_count as f64,
+                    )?;
+                }
             }
 
-            let mut histogram_vec = encoder.histogram_vec(
-                "cketh_eth_rpc_call_retry_count",
-                "The number of ETH RPC call retries by method.",
-            )?;
+            if !self.inconsistent_responses.is_empty() {
+                let mut counter_vec = encoder.counter_vec(
+                    "cketh_eth_rpc_inconsistent_responses",
+                    "The number of ETH RPC responses that disagreed with the other providers, by method and provider.",
+                )?;
 
-            for (method, histogram) in &self.retry_histogram_per_method {
-                histogram_vec = histogram_vec.histogram(
-                    &[("method", method.as_str())],
-                    histogram.iter(),
-                    histogram.retry_count as f64,
+                for ((method, provider), count) in &self.inconsistent_responses {
+                    counter_vec = counter_vec.value(
+                        &[("method", method.as_str()), ("provider", provider.as_str())],
+                        *count as f64,
+                    )?;
+                }
+            }
+
+            if !self.no_quorum_count_per_method.is_empty() {
+                let mut counter_vec = encoder.counter_vec(
+                    "cketh_eth_rpc_no_quorum",
+                    "The number of ETH RPC calls for which not enough providers agreed, by method.",
                 )?;
+
+                for (method, count) in &self.no_quorum_count_per_method {
+                    counter_vec =
+                        counter_vec.value(&[("method", method.as_str())], *count as f64)?;
+                }
             }
 
             Ok(())
@@ -825,6 +870,25 @@ pub(super) mod metrics {
         METRICS.with(|metrics| metrics.borrow_mut().observe_retry_count(method, count));
     }
 
+    /// Record that a provider returned a response inconsistent with the other providers.
+    pub fn observe_inconsistent_response(method: &str, provider_url: &str) {
+        METRICS.with(|metrics| {
+            metrics
+                .borrow_mut()
+                .observe_inconsistent_response(method, provider_url)
+        });
+    }
+
+    /// Record that not enough providers agreed on a response.
+    pub fn observe_no_quorum(method: &str) {
+        METRICS.with(|metrics| metrics.borrow_mut().observe_no_quorum(method));
+    }
+
+    /// Returns the number of inconsistent responses indexed by ETH RPC method and provider URL.
+    pub fn inconsistent_responses() -> BTreeMap<(String, String), u64> {
+        METRICS.with(|metrics| metrics.borrow().inconsistent_responses.clone())
+    }
+
     /// Encodes the metrics related to ETH RPC method calls.
     pub fn encode<W: std::io::Write>(encoder: &mut MetricsEncoder<W>) -> std::io::Result<()> {
         METRICS.with(|metrics| metrics.borrow().encode(encoder))
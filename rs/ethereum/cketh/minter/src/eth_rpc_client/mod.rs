@@ -6,13 +6,14 @@ use crate::eth_rpc::{
 use crate::eth_rpc_client::providers::{RpcNodeProvider, MAINNET_PROVIDERS, SEPOLIA_PROVIDERS};
 use crate::eth_rpc_client::requests::GetTransactionCountParams;
 use crate::eth_rpc_client::responses::TransactionReceipt;
+use crate::lifecycle::upgrade::EthRpcConfig;
 use crate::lifecycle::EthereumNetwork;
 use crate::logs::{DEBUG, INFO};
 use crate::numeric::TransactionCount;
 use crate::state::State;
 use ic_canister_log::log;
 use serde::{de::DeserializeOwned, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Debug;
 
 mod providers;
@@ -22,24 +23,133 @@ pub mod responses;
 #[cfg(test)]
 mod tests;
 
+/// JSON-RPC providers set by an upgrade argument, replacing the default providers of the network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RpcConfig {
+    providers: Vec<RpcNodeProvider>,
+    min_agreeing_providers: usize,
+}
+
+impl RpcConfig {
+    pub fn provider_urls(&self) -> impl Iterator<Item = &str> {
+        self.providers.iter().map(|provider| provider.url())
+    }
+
+    pub fn min_agreeing_providers(&self) -> usize {
+        self.min_agreeing_providers
+    }
+}
+
+impl TryFrom<EthRpcConfig> for RpcConfig {
+    type Error = String;
+
+    fn try_from(config: EthRpcConfig) -> Result<Self, Self::Error> {
+        if config.provider_urls.is_empty() {
+            return Err("at least one JSON-RPC provider must be specified".to_string());
+        }
+        let mut urls = BTreeSet::new();
+        for url in &config.provider_urls {
+            if !url.starts_with("https://") {
+                return Err(format!("JSON-RPC provider URL {url} must use HTTPS"));
+            }
+            if !urls.insert(url) {
+                return Err(format!(
+                    "JSON-RPC provider URL {url} is specified more than once"
+                ));
+            }
+        }
+        let num_providers = config.provider_urls.len();
+        let min_agreeing_providers = config.min_agreeing_providers as usize;
+        // Requiring a strict majority ensures that at most one response can reach the threshold.
+        if min_agreeing_providers > num_providers || 2 * min_agreeing_providers <= num_providers {
+            return Err(format!(
+                "min_agreeing_providers must be a strict majority of the {num_providers} providers, got {min_agreeing_providers}"
+            ));
+        }
+        Ok(Self {
+            providers: config
+                .provider_urls
+                .into_iter()
+                .map(RpcNodeProvider::Custom)
+                .collect(),
+            min_agreeing_providers,
+        })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EthRpcClient {
     chain: EthereumNetwork,
+    providers: Vec<RpcNodeProvider>,
+    /// Minimum number of providers that must return the same response for it to be accepted.
+    min_agreeing_providers: usize,
 }
 
 impl EthRpcClient {
-    const fn new(chain: EthereumNetwork) -> Self {
-        Self { chain }
+    fn new(chain: EthereumNetwork) -> Self {
+        let providers = match chain {
+            EthereumNetwork::Mainnet => MAINNET_PROVIDERS.to_vec(),
+            EthereumNetwork::Sepolia => SEPOLIA_PROVIDERS.to_vec(),
+        };
+        // Without explicit configuration, all providers must agree.
+        let min_agreeing_providers = providers.len();
+        Self {
+            chain,
+            providers,
+            min_agreeing_providers,
+        }
     }
 
-    pub const fn from_state(state: &State) -> Self {
-        Self::new(state.ethereum_network())
+    pub fn from_state(state: &State) -> Self {
+        match &state.eth_rpc_config {
+            Some(config) => Self {
+                chain: state.ethereum_network(),
+                providers: config.providers.clone(),
+                min_agreeing_providers: config.min_agreeing_providers,
+            },
+            None => Self::new(state.ethereum_network()),
+        }
     }
 
     fn providers(&self) -> &[RpcNodeProvider] {
-        match self.chain {
-            EthereumNetwork::Mainnet => &MAINNET_PROVIDERS,
-            EthereumNetwork::Sepolia => &SEPOLIA_PROVIDERS,
+        &self.providers
+    }
+
+    pub fn provider_urls(&self) -> impl Iterator<Item = &str> {
+        self.providers.iter().map(|provider| provider.url())
+    }
+
+    pub fn min_agreeing_providers(&self) -> usize {
+        self.min_agreeing_providers
+    }
+
+    /// Reduces the results with [`MultiCallResults::reduce_with_threshold`] and
+    /// records in the metrics the providers that disagreed.
+    fn reduce_with_quorum<T: Debug + PartialEq>(
+        &self,
+        method: &str,
+        results: MultiCallResults<T>,
+    ) -> Result<T, MultiCallError<T>> {
+        match results.reduce_with_threshold(self.min_agreeing_providers) {
+            Ok((value, dissenting_providers)) => {
+                for provider in dissenting_providers {
+                    log!(
+                        INFO,
+                        "[reduce_with_quorum]: provider {provider:?} disagreed with the quorum for {method}"
+                    );
+                    eth_rpc::metrics::observe_inconsistent_response(method, provider.url());
+                }
+                Ok(value)
+            }
+            Err(error) => {
+                if let MultiCallError::InconsistentResults(results) = &error {
+                    for provider in results.results.keys() {
+                        eth_rpc::metrics::observe_inconsistent_response(method, provider.url());
+                    }
+                    eth_rpc::metrics::observe_no_quorum(method);
+                }
+                Err(error)
+            }
         }
     }
 
@@ -130,7 +240,7 @@ impl EthRpcClient {
         let results: MultiCallResults<Vec<LogEntry>> = self
             .parallel_call("eth_getLogs", vec![params], ResponseSizeEstimate::new(100))
             .await;
-        results.reduce_with_equality()
+        self.reduce_with_quorum("eth_getLogs", results)
     }
 
     pub async fn eth_get_block_by_number(
@@ -154,7 +264,7 @@ impl EthRpcClient {
                 ResponseSizeEstimate::new(expected_block_size),
             )
             .await;
-        results.reduce_with_equality()
+        self.reduce_with_quorum("eth_getBlockByNumber", results)
     }
 
     pub async fn eth_get_transaction_receipt(
@@ -168,7 +278,7 @@ impl EthRpcClient {
                 ResponseSizeEstimate::new(700),
             )
             .await;
-        results.reduce_with_equality()
+        self.reduce_with_quorum("eth_getTransactionReceipt", results)
     }
 
     pub async fn eth_fee_history(
@@ -330,6 +440,50 @@ impl<T: Debug + PartialEq> MultiCallResults<T> {
         Ok(base_result)
     }
 
+    /// Accepts the result returned by at least `min_agreeing_providers` providers and
+    /// returns it together with the providers whose response differed from it.
+    /// If no result reaches the threshold, fails like [`Self::reduce_with_equality`].
+    pub(crate) fn reduce_with_threshold(
+        self,
+        min_agreeing_providers: usize,
+    ) -> Result<(T, Vec<RpcNodeProvider>), MultiCallError<T>> {
+        fn is_ok_result<T: PartialEq>(
+            result: &HttpOutcallResult<JsonRpcResult<T>>,
+            expected: &T,
+        ) -> bool {
+            matches!(result, Ok(JsonRpcResult::Result(value)) if value == expected)
+        }
+
+        let quorum_provider = self
+            .results
+            .iter()
+            .find_map(|(provider, result)| match result {
+                Ok(JsonRpcResult::Result(value)) => {
+                    let votes = self
+                        .results
+                        .values()
+                        .filter(|other| is_ok_result(other, value))
+                        .count();
+                    (votes >= min_agreeing_providers).then(|| provider.clone())
+                }
+                _ => None,
+            });
+        let Some(quorum_provider) = quorum_provider else {
+            return self.reduce_with_equality().map(|value| (value, vec![]));
+        };
+        let mut results = self.results;
+        let value = match results.remove(&quorum_provider) {
+            Some(Ok(JsonRpcResult::Result(value))) => value,
+            _ => panic!("BUG: quorum provider should have returned an ok result"),
+        };
+        let dissenting_providers = results
+            .into_iter()
+            .filter(|(_provider, result)| !is_ok_result(result, &value))
+            .map(|(provider, _result)| provider)
+            .collect();
+        Ok((value, dissenting_providers))
+    }
+
     pub fn reduce_with_min_by_key<F: FnMut(&T) -> K, K: Ord>(
         self,
         extractor: F,
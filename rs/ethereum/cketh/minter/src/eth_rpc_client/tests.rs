@@ -35,6 +35,71 @@ mod eth_rpc_client {
     }
 }
 
+mod rpc_config {
+    use crate::eth_rpc_client::RpcConfig;
+    use crate::lifecycle::upgrade::EthRpcConfig;
+    use assert_matches::assert_matches;
+
+    fn provider_urls(n: usize) -> Vec<String> {
+        (0..n)
+            .map(|i| format!("https://provider{i}.example.com"))
+            .collect()
+    }
+
+    #[test]
+    fn should_require_strict_majority() {
+        for (num_providers, min_agreeing_providers, is_valid) in [
+            (1, 1, true),
+            (2, 1, false),
+            (2, 2, true),
+            (3, 1, false),
+            (3, 2, true),
+            (3, 3, true),
+            (3, 4, false),
+            (4, 2, false),
+            (4, 3, true),
+        ] {
+            let result = RpcConfig::try_from(EthRpcConfig {
+                provider_urls: provider_urls(num_providers),
+                min_agreeing_providers,
+            });
+            assert_eq!(
+                result.is_ok(),
+                is_valid,
+                "unexpected result for {min_agreeing_providers} out of {num_providers}: {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_invalid_urls() {
+        assert_matches!(
+            RpcConfig::try_from(EthRpcConfig {
+                provider_urls: vec![],
+                min_agreeing_providers: 0,
+            }),
+            Err(_)
+        );
+        assert_matches!(
+            RpcConfig::try_from(EthRpcConfig {
+                provider_urls: vec!["http://provider.example.com".to_string()],
+                min_agreeing_providers: 1,
+            }),
+            Err(_)
+        );
+        assert_matches!(
+            RpcConfig::try_from(EthRpcConfig {
+                provider_urls: vec![
+                    "https://provider.example.com".to_string(),
+                    "https://provider.example.com".to_string(),
+                ],
+                min_agreeing_providers: 2,
+            }),
+            Err(_)
+        );
+    }
+}
+
 mod multi_call_results {
     use crate::eth_rpc_client::providers::{EthereumProvider, RpcNodeProvider};
 
@@ -429,6 +494,96 @@ mod multi_call_results {
         }
     }
 
+    mod reduce_with_threshold {
+        use crate::eth_rpc::{HttpOutcallError, JsonRpcResult};
+        use crate::eth_rpc_client::tests::multi_call_results::{ANKR, CLOUDFLARE, PUBLIC_NODE};
+        use crate::eth_rpc_client::{MultiCallError, MultiCallResults};
+        use ic_cdk::api::call::RejectionCode;
+
+        #[test]
+        fn should_accept_unanimous_result() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (ANKR, Ok(JsonRpcResult::Result("0x01".to_string()))),
+                (PUBLIC_NODE, Ok(JsonRpcResult::Result("0x01".to_string()))),
+                (CLOUDFLARE, Ok(JsonRpcResult::Result("0x01".to_string()))),
+            ]);
+
+            let reduced = results.reduce_with_threshold(3);
+
+            assert_eq!(reduced, Ok(("0x01".to_string(), vec![])));
+        }
+
+        #[test]
+        fn should_accept_2_out_of_3_and_report_dissenting_provider() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (ANKR, Ok(JsonRpcResult::Result("0x01".to_string()))),
+                (PUBLIC_NODE, Ok(JsonRpcResult::Result("0x02".to_string()))),
+                (CLOUDFLARE, Ok(JsonRpcResult::Result("0x01".to_string()))),
+            ]);
+
+            let reduced = results.reduce_with_threshold(2);
+
+            assert_eq!(reduced, Ok(("0x01".to_string(), vec![PUBLIC_NODE])));
+        }
+
+        #[test]
+        fn should_accept_2_out_of_3_when_one_provider_fails() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (ANKR, Ok(JsonRpcResult::Result("0x01".to_string()))),
+                (
+                    PUBLIC_NODE,
+                    Err(HttpOutcallError::IcError {
+                        code: RejectionCode::SysTransient,
+                        message: "timeout".to_string(),
+                    }),
+                ),
+                (CLOUDFLARE, Ok(JsonRpcResult::Result("0x01".to_string()))),
+            ]);
+
+            let reduced = results.reduce_with_threshold(2);
+
+            assert_eq!(reduced, Ok(("0x01".to_string(), vec![PUBLIC_NODE])));
+        }
+
+        #[test]
+        fn should_fail_when_threshold_not_reached() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (ANKR, Ok(JsonRpcResult::Result("0x01".to_string()))),
+                (PUBLIC_NODE, Ok(JsonRpcResult::Result("0x02".to_string()))),
+                (CLOUDFLARE, Ok(JsonRpcResult::Result("0x01".to_string()))),
+            ]);
+
+            let reduced = results.clone().reduce_with_threshold(3);
+
+            assert_eq!(reduced, Err(MultiCallError::InconsistentResults(results)));
+        }
+
+        #[test]
+        fn should_behave_like_reduce_with_equality_when_all_must_agree() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (
+                    ANKR,
+                    Err(HttpOutcallError::IcError {
+                        code: RejectionCode::CanisterReject,
+                        message: "reject".to_string(),
+                    }),
+                ),
+                (
+                    PUBLIC_NODE,
+                    Err(HttpOutcallError::IcError {
+                        code: RejectionCode::CanisterReject,
+                        message: "reject".to_string(),
+                    }),
+                ),
+            ]);
+
+            assert_eq!(
+                results.clone().reduce_with_threshold(2),
+                results.reduce_with_equality().map(|value| (value, vec![]))
+            );
+        }
+    }
+
     mod has_http_outcall_error_matching {
         use super::*;
         use crate::eth_rpc::{HttpOutcallError, JsonRpcResult};
@@ -4,6 +4,7 @@ mod cbor;
 pub mod checked_amount;
 pub mod deposit;
 pub mod endpoints;
+pub mod erc20;
 pub mod eth_logs;
 pub mod eth_rpc;
 pub mod eth_rpc_client;
@@ -33,3 +34,9 @@ pub const PROCESS_ETH_RETRIEVE_TRANSACTIONS_INTERVAL: Duration = Duration::from_
 pub const PROCESS_REIMBURSEMENT: Duration = Duration::from_secs(3 * 60);
 pub const PROCESS_ETH_RETRIEVE_TRANSACTIONS_RETRY_INTERVAL: Duration = Duration::from_secs(3 * 60);
 pub const MINT_RETRY_DELAY: Duration = Duration::from_secs(3 * 60);
+/// Factor applied to the estimated transaction fee to compute the ckETH charged for
+/// the transaction fee of an ERC-20 withdrawal.
+pub const ERC20_MAX_TRANSACTION_FEE_MULTIPLIER: u8 = 2;
+/// How long an ERC-20 withdrawal request may wait for the transaction fee to drop below
+/// the fee paid by the user before the request is dropped and the user reimbursed.
+pub const ERC20_WITHDRAWAL_REQUEST_EXPIRY: Duration = Duration::from_secs(24 * 60 * 60);
@@ -89,6 +89,14 @@ impl TryFrom<InitArg> for State {
             skipped_blocks: Default::default(),
             active_tasks: Default::default(),
             http_request_counter: 0,
+            erc20_helper_contract_address: None,
+            ckerc20_tokens: Default::default(),
+            last_erc20_scraped_block_number: last_scraped_block_number,
+            erc20_skipped_blocks: Default::default(),
+            erc20_events_to_mint: Default::default(),
+            minted_erc20_events: Default::default(),
+            erc20_balances: Default::default(),
+            eth_rpc_config: None,
         };
         state.validate_config()?;
         Ok(state)
@@ -4,7 +4,7 @@ use crate::state::audit::{process_event, replay_events, EventType};
 use crate::state::mutate_state;
 use crate::state::STATE;
 use crate::storage::total_event_count;
-use candid::{CandidType, Deserialize, Nat};
+use candid::{CandidType, Deserialize, Nat, Principal};
 use ic_canister_log::log;
 use minicbor::{Decode, Encode};
 
@@ -18,6 +18,35 @@ pub struct UpgradeArg {
     pub ethereum_contract_address: Option<String>,
     #[n(3)]
     pub ethereum_block_height: Option<CandidBlockTag>,
+    #[n(4)]
+    pub erc20_helper_contract_address: Option<String>,
+    #[cbor(n(5), with = "crate::cbor::nat::option")]
+    pub last_erc20_scraped_block_number: Option<Nat>,
+    #[n(6)]
+    pub add_ckerc20_tokens: Option<Vec<AddCkErc20Token>>,
+    #[n(7)]
+    pub eth_rpc_config: Option<EthRpcConfig>,
+}
+
+/// A new ERC-20 token to support, approved as part of a minter upgrade.
+#[derive(CandidType, Deserialize, Clone, Debug, Encode, Decode, PartialEq, Eq)]
+pub struct AddCkErc20Token {
+    #[n(0)]
+    pub erc20_contract_address: String,
+    #[n(1)]
+    pub ckerc20_token_symbol: String,
+    #[cbor(n(2), with = "crate::cbor::principal")]
+    pub ckerc20_ledger_id: Principal,
+}
+
+/// JSON-RPC providers queried by the minter and the number of them
+/// that must return the same response for it to be accepted.
+#[derive(CandidType, Deserialize, Clone, Debug, Encode, Decode, PartialEq, Eq)]
+pub struct EthRpcConfig {
+    #[n(0)]
+    pub provider_urls: Vec<String>,
+    #[n(1)]
+    pub min_agreeing_providers: u32,
 }
 
 pub fn post_upgrade(upgrade_args: Option<UpgradeArg>) {
@@ -8,24 +8,28 @@ use ic_cketh_minter::endpoints::events::{
     Event as CandidEvent, EventSource as CandidEventSource, GetEventsArg, GetEventsResult,
 };
 use ic_cketh_minter::endpoints::{
-    Eip1559TransactionPrice, RetrieveEthRequest, RetrieveEthStatus, WithdrawalArg, WithdrawalError,
+    CkErc20Token, Eip1559TransactionPrice, LedgerError, RetrieveErc20Request, RetrieveEthRequest,
+    RetrieveEthStatus, WithdrawErc20Arg, WithdrawErc20Error, WithdrawalArg, WithdrawalError,
 };
-use ic_cketh_minter::eth_logs::{EventSource, ReceivedEthEvent};
+use ic_cketh_minter::erc20::ERC20_TRANSFER_GAS_LIMIT;
+use ic_cketh_minter::eth_logs::{EventSource, ReceivedErc20Event, ReceivedEthEvent};
 use ic_cketh_minter::guard::retrieve_eth_guard;
 use ic_cketh_minter::lifecycle::MinterArg;
 use ic_cketh_minter::logs::{DEBUG, INFO};
-use ic_cketh_minter::memo::BurnMemo;
-use ic_cketh_minter::numeric::{LedgerBurnIndex, Wei};
+use ic_cketh_minter::memo::{erc20_convert_burn_memo, BurnMemo};
+use ic_cketh_minter::numeric::{Erc20Value, LedgerBurnIndex, Wei};
 use ic_cketh_minter::state::audit::{process_event, Event, EventType};
-use ic_cketh_minter::state::transactions::{EthWithdrawalRequest, Reimbursed};
+use ic_cketh_minter::state::transactions::{
+    Erc20Reimbursed, Erc20WithdrawalRequest, EthWithdrawalRequest, Reimbursed, ReimbursementRequest,
+};
 use ic_cketh_minter::state::{lazy_call_ecdsa_public_key, mutate_state, read_state, State, STATE};
-use ic_cketh_minter::tx::estimate_transaction_price;
+use ic_cketh_minter::tx::{estimate_transaction_price, TransactionPrice};
 use ic_cketh_minter::withdraw::{
     eth_fee_history, process_reimbursement, process_retrieve_eth_requests,
 };
 use ic_cketh_minter::{
-    state, storage, PROCESS_ETH_RETRIEVE_TRANSACTIONS_INTERVAL, PROCESS_REIMBURSEMENT,
-    SCRAPPING_ETH_LOGS_INTERVAL,
+    state, storage, ERC20_MAX_TRANSACTION_FEE_MULTIPLIER,
+    PROCESS_ETH_RETRIEVE_TRANSACTIONS_INTERVAL, PROCESS_REIMBURSEMENT, SCRAPPING_ETH_LOGS_INTERVAL,
 };
 use icrc_ledger_client_cdk::{CdkRuntime, ICRC1Client};
 use icrc_ledger_types::icrc1::transfer::Memo;
@@ -89,6 +93,9 @@ fn emit_preupgrade_events() {
         storage::record_event(EventType::SyncedToBlock {
             block_number: s.last_scraped_block_number,
         });
+        storage::record_event(EventType::SyncedErc20ToBlock {
+            block_number: s.last_erc20_scraped_block_number,
+        });
     });
 }
 
@@ -240,6 +247,245 @@ async fn withdraw_eth(
     }
 }
 
+#[update]
+#[candid_method(update)]
+async fn withdraw_erc20(
+    WithdrawErc20Arg {
+        amount,
+        ckerc20_ledger_id,
+        recipient,
+    }: WithdrawErc20Arg,
+) -> Result<RetrieveErc20Request, WithdrawErc20Error> {
+    let caller = validate_caller_not_anonymous();
+    let _guard = retrieve_eth_guard(caller).unwrap_or_else(|e| {
+        ic_cdk::trap(&format!(
+            "Failed retrieving guard for principal {}: {:?}",
+            caller, e
+        ))
+    });
+
+    let destination = validate_address_as_destination(&recipient).map_err(|e| match e {
+        AddressValidationError::Invalid { .. } | AddressValidationError::NotSupported(_) => {
+            ic_cdk::trap(&e.to_string())
+        }
+        AddressValidationError::Blocked(address) => WithdrawErc20Error::RecipientAddressBlocked {
+            address: address.to_string(),
+        },
+    })?;
+
+    let ckerc20_withdrawal_amount =
+        Erc20Value::try_from(amount).expect("failed to convert Nat to u256");
+
+    let ckerc20_token = read_state(|s| {
+        s.find_ckerc20_token_by_ledger_id(&ckerc20_ledger_id)
+            .cloned()
+    })
+    .ok_or_else(|| WithdrawErc20Error::TokenNotSupported {
+        supported_tokens: read_state(|s| {
+            s.ckerc20_tokens
+                .values()
+                .cloned()
+                .map(CkErc20Token::from)
+                .collect()
+        }),
+    })?;
+
+    let transaction_price = estimate_erc20_transaction_price().await?;
+    // Charge a margin on top of the estimated fee, so that the transaction can still be
+    // created and resubmitted if the gas price rises. The unspent part is refunded once
+    // the transaction is finalized.
+    let max_transaction_fee = transaction_price
+        .max_transaction_fee()
+        .checked_mul(ERC20_MAX_TRANSACTION_FEE_MULTIPLIER)
+        .ok_or_else(|| {
+            WithdrawErc20Error::TemporarilyUnavailable(
+                "failed to estimate transaction fee: overflow".to_string(),
+            )
+        })?;
+
+    let cketh_ledger = ICRC1Client {
+        runtime: CdkRuntime,
+        ledger_canister_id: read_state(|s| s.ledger_id),
+    };
+    let now = ic_cdk::api::time();
+
+    log!(
+        INFO,
+        "[withdraw_erc20]: burning {:?} ckETH for the transaction fee",
+        max_transaction_fee
+    );
+    let cketh_ledger_burn_index = match cketh_ledger
+        .transfer_from(TransferFromArgs {
+            spender_subaccount: None,
+            from: caller.into(),
+            to: ic_cdk::id().into(),
+            amount: Nat::from(max_transaction_fee),
+            fee: None,
+            memo: Some(Memo::from(BurnMemo::Erc20GasFee {
+                ckerc20_token_symbol: ckerc20_token.ckerc20_token_symbol.clone(),
+                ckerc20_withdrawal_amount,
+                to_address: destination,
+            })),
+            created_at_time: None,
+        })
+        .await
+    {
+        Ok(Ok(block_index)) => {
+            LedgerBurnIndex::new(block_index.0.to_u64().expect("nat does not fit into u64"))
+        }
+        Ok(Err(error)) => {
+            log!(
+                DEBUG,
+                "[withdraw_erc20]: failed to burn ckETH with error: {error:?}"
+            );
+            return Err(WithdrawErc20Error::CkEthLedgerError {
+                error: LedgerError::from_transfer_from_error(
+                    error,
+                    Nat::from(max_transaction_fee),
+                    "ckETH".to_string(),
+                    cketh_ledger.ledger_canister_id,
+                ),
+            });
+        }
+        Err((error_code, message)) => {
+            log!(
+                DEBUG,
+                "[withdraw_erc20]: failed to call ckETH ledger with error_code: {error_code} and message: {message}",
+            );
+            return Err(WithdrawErc20Error::TemporarilyUnavailable(format!(
+                "failed to call ckETH ledger with error_code: {error_code} and message: {message}"
+            )));
+        }
+    };
+
+    let ckerc20_ledger = ICRC1Client {
+        runtime: CdkRuntime,
+        ledger_canister_id: ckerc20_token.ckerc20_ledger_id,
+    };
+    log!(
+        INFO,
+        "[withdraw_erc20]: burning {:?} {}",
+        ckerc20_withdrawal_amount,
+        ckerc20_token.ckerc20_token_symbol
+    );
+    let ckerc20_burn_error = match ckerc20_ledger
+        .transfer_from(TransferFromArgs {
+            spender_subaccount: None,
+            from: caller.into(),
+            to: ic_cdk::id().into(),
+            amount: Nat::from(ckerc20_withdrawal_amount),
+            fee: None,
+            memo: Some(erc20_convert_burn_memo(
+                cketh_ledger_burn_index,
+                destination,
+            )),
+            created_at_time: None,
+        })
+        .await
+    {
+        Ok(Ok(block_index)) => {
+            let withdrawal_request = Erc20WithdrawalRequest {
+                max_transaction_fee,
+                withdrawal_amount: ckerc20_withdrawal_amount,
+                destination,
+                cketh_ledger_burn_index,
+                erc20_contract_address: ckerc20_token.erc20_contract_address,
+                ckerc20_ledger_id: ckerc20_token.ckerc20_ledger_id,
+                ckerc20_ledger_burn_index: LedgerBurnIndex::new(
+                    block_index.0.to_u64().expect("nat does not fit into u64"),
+                ),
+                from: caller,
+                from_subaccount: None,
+                created_at: now,
+            };
+            log!(
+                INFO,
+                "[withdraw_erc20]: queuing withdrawal request {:?}",
+                withdrawal_request
+            );
+            mutate_state(|s| {
+                process_event(
+                    s,
+                    EventType::AcceptedErc20WithdrawalRequest(withdrawal_request.clone()),
+                );
+            });
+            return Ok(RetrieveErc20Request {
+                cketh_block_index: Nat::from(cketh_ledger_burn_index.get()),
+                ckerc20_block_index: Nat::from(withdrawal_request.ckerc20_ledger_burn_index.get()),
+            });
+        }
+        Ok(Err(error)) => {
+            log!(
+                DEBUG,
+                "[withdraw_erc20]: failed to burn {} with error: {error:?}",
+                ckerc20_token.ckerc20_token_symbol
+            );
+            LedgerError::from_transfer_from_error(
+                error,
+                Nat::from(ckerc20_withdrawal_amount),
+                ckerc20_token.ckerc20_token_symbol,
+                ckerc20_token.ckerc20_ledger_id,
+            )
+        }
+        Err((error_code, message)) => {
+            log!(
+                DEBUG,
+                "[withdraw_erc20]: failed to call {} ledger with error_code: {error_code} and message: {message}",
+                ckerc20_token.ckerc20_token_symbol
+            );
+            LedgerError::TemporarilyUnavailable(format!(
+                "failed to call {} ledger with error_code: {error_code} and message: {message}",
+                ckerc20_token.ckerc20_token_symbol
+            ))
+        }
+    };
+
+    // The ckETH paid for the transaction fee was already burned and must be given back.
+    mutate_state(|s| {
+        process_event(
+            s,
+            EventType::FailedErc20WithdrawalRequest(ReimbursementRequest {
+                withdrawal_id: cketh_ledger_burn_index,
+                reimbursed_amount: max_transaction_fee,
+                to: caller,
+                to_subaccount: None,
+                transaction_hash: None,
+            }),
+        );
+    });
+    Err(WithdrawErc20Error::CkErc20LedgerError {
+        cketh_block_index: Nat::from(cketh_ledger_burn_index.get()),
+        error: ckerc20_burn_error,
+    })
+}
+
+async fn estimate_erc20_transaction_price() -> Result<TransactionPrice, WithdrawErc20Error> {
+    let fee_history = eth_fee_history().await.map_err(|e| {
+        WithdrawErc20Error::TemporarilyUnavailable(format!("failed to retrieve fee history: {e:?}"))
+    })?;
+    let transaction_price = estimate_transaction_price(&fee_history).map_err(|e| {
+        WithdrawErc20Error::TemporarilyUnavailable(format!(
+            "failed to estimate transaction price: {e:?}"
+        ))
+    })?;
+    Ok(TransactionPrice {
+        gas_limit: ERC20_TRANSFER_GAS_LIMIT,
+        ..transaction_price
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn get_ckerc20_tokens() -> Vec<CkErc20Token> {
+    read_state(|s| {
+        s.ckerc20_tokens
+            .values()
+            .cloned()
+            .map(CkErc20Token::from)
+            .collect()
+    })
+}
+
 #[update]
 #[candid_method(update)]
 async fn retrieve_eth_status(block_index: u64) -> RetrieveEthStatus {
@@ -430,6 +676,89 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                 EventType::SkippedBlock(block_number) => EP::SkippedBlock {
                     block_number: block_number.into(),
                 },
+                EventType::AcceptedErc20Deposit(ReceivedErc20Event {
+                    transaction_hash,
+                    block_number,
+                    log_index,
+                    from_address,
+                    value,
+                    principal,
+                    erc20_contract_address,
+                }) => EP::AcceptedErc20Deposit {
+                    transaction_hash: transaction_hash.to_string(),
+                    block_number: block_number.into(),
+                    log_index: log_index.into(),
+                    from_address: from_address.to_string(),
+                    value: value.into(),
+                    principal,
+                    erc20_contract_address: erc20_contract_address.to_string(),
+                },
+                EventType::MintedCkErc20 {
+                    event_source,
+                    mint_block_index,
+                } => EP::MintedCkErc20 {
+                    event_source: map_event_source(event_source),
+                    mint_block_index: mint_block_index.get().into(),
+                },
+                EventType::SyncedErc20ToBlock { block_number } => EP::SyncedErc20ToBlock {
+                    block_number: block_number.into(),
+                },
+                EventType::AcceptedErc20WithdrawalRequest(Erc20WithdrawalRequest {
+                    max_transaction_fee,
+                    withdrawal_amount,
+                    destination,
+                    cketh_ledger_burn_index,
+                    erc20_contract_address,
+                    ckerc20_ledger_id,
+                    ckerc20_ledger_burn_index,
+                    from,
+                    from_subaccount,
+                    created_at,
+                }) => EP::AcceptedErc20WithdrawalRequest {
+                    max_transaction_fee: max_transaction_fee.into(),
+                    withdrawal_amount: withdrawal_amount.into(),
+                    destination: destination.to_string(),
+                    cketh_ledger_burn_index: cketh_ledger_burn_index.get().into(),
+                    erc20_contract_address: erc20_contract_address.to_string(),
+                    ckerc20_ledger_id,
+                    ckerc20_ledger_burn_index: ckerc20_ledger_burn_index.get().into(),
+                    from,
+                    from_subaccount: from_subaccount.map(|s| s.0),
+                    created_at,
+                },
+                EventType::FailedErc20WithdrawalRequest(ReimbursementRequest {
+                    withdrawal_id,
+                    reimbursed_amount,
+                    to,
+                    to_subaccount,
+                    transaction_hash: _,
+                }) => EP::FailedErc20WithdrawalRequest {
+                    withdrawal_id: withdrawal_id.get().into(),
+                    reimbursed_amount: reimbursed_amount.into(),
+                    to,
+                    to_subaccount: to_subaccount.map(|s| s.0),
+                },
+                EventType::ReimbursedErc20Withdrawal(Erc20Reimbursed {
+                    withdrawal_id,
+                    ckerc20_ledger_id,
+                    reimbursed_in_block,
+                    reimbursed_amount,
+                    transaction_hash,
+                }) => EP::ReimbursedErc20Withdrawal {
+                    withdrawal_id: withdrawal_id.get().into(),
+                    ckerc20_ledger_id,
+                    reimbursed_in_block: reimbursed_in_block.get().into(),
+                    reimbursed_amount: reimbursed_amount.into(),
+                    transaction_hash: transaction_hash.map(|h| h.to_string()),
+                },
+                EventType::SkippedErc20Block(block_number) => EP::SkippedErc20Block {
+                    block_number: block_number.into(),
+                },
+                EventType::ExpiredErc20WithdrawalRequest { withdrawal_id } => {
+                    EP::ExpiredErc20WithdrawalRequest {
+                        withdrawal_id: withdrawal_id.get().into(),
+                    }
+                }
             },
         }
     }
@@ -501,6 +830,32 @@ fn http_request(req: HttpRequest) -> HttpResponse {
                 .value(&[("status", "accepted")], s.minted_events.len() as f64)?
                 .value(&[("status", "rejected")], s.invalid_events.len() as f64)?;
 
+                w.encode_gauge(
+                    "cketh_minter_last_erc20_processed_block",
+                    s.last_erc20_scraped_block_number.as_f64(),
+                    "The last Ethereum block the ckETH minter checked for ERC-20 deposits.",
+                )?;
+
+                w.encode_gauge(
+                    "cketh_minter_accepted_erc20_deposits",
+                    s.minted_erc20_events.len() as f64,
+                    "The number of ERC-20 deposits for which the ckETH minter minted ckERC20 tokens.",
+                )?;
+
+                let mut erc20_balances = w.gauge_vec(
+                    "cketh_minter_erc20_balances",
+                    "Known amount of ERC-20 tokens on the minter's address, by token.",
+                )?;
+                for (erc20_contract_address, balance) in s.erc20_balances.iter() {
+                    let symbol = s
+                        .ckerc20_tokens
+                        .get(erc20_contract_address)
+                        .map(|token| token.ckerc20_token_symbol.as_str())
+                        .unwrap_or("unknown");
+                    erc20_balances =
+                        erc20_balances.value(&[("ckerc20_token", symbol)], balance.as_f64())?;
+                }
+
                 w.encode_gauge(
                     "cketh_event_count",
                     storage::total_event_count() as f64,
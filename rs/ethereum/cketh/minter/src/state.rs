@@ -1,11 +1,15 @@
 use crate::address::Address;
-use crate::eth_logs::{EventSource, ReceivedEthEvent};
+use crate::erc20::CkErc20Token;
+use crate::eth_logs::{EventSource, ReceivedErc20Event, ReceivedEthEvent};
 use crate::eth_rpc::BlockTag;
 use crate::eth_rpc_client::responses::{TransactionReceipt, TransactionStatus};
+use crate::eth_rpc_client::RpcConfig;
 use crate::lifecycle::upgrade::UpgradeArg;
 use crate::lifecycle::EthereumNetwork;
 use crate::logs::DEBUG;
-use crate::numeric::{BlockNumber, LedgerBurnIndex, LedgerMintIndex, TransactionNonce, Wei};
+use crate::numeric::{
+    BlockNumber, Erc20Value, LedgerBurnIndex, LedgerMintIndex, TransactionNonce, Wei,
+};
 use candid::Principal;
 use ic_canister_log::log;
 use ic_cdk::api::management_canister::ecdsa::EcdsaPublicKeyResponse;
@@ -38,6 +42,18 @@ impl MintedEvent {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MintedErc20Event {
+    pub deposit_event: ReceivedErc20Event,
+    pub mint_block_index: LedgerMintIndex,
+}
+
+impl MintedErc20Event {
+    pub fn source(&self) -> EventSource {
+        self.deposit_event.source()
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct State {
     pub ethereum_network: EthereumNetwork,
@@ -68,6 +84,22 @@ pub struct State {
     /// Number of HTTP outcalls since the last upgrade.
     /// Used to correlate request and response in logs.
     pub http_request_counter: u64,
+
+    /// Address of the helper smart contract emitting ERC-20 deposit events.
+    pub erc20_helper_contract_address: Option<Address>,
+    /// ERC-20 tokens supported by the minter, indexed by their contract address.
+    pub ckerc20_tokens: BTreeMap<Address, CkErc20Token>,
+    pub last_erc20_scraped_block_number: BlockNumber,
+    pub erc20_skipped_blocks: BTreeSet<BlockNumber>,
+    pub erc20_events_to_mint: BTreeMap<EventSource, ReceivedErc20Event>,
+    pub minted_erc20_events: BTreeMap<EventSource, MintedErc20Event>,
+
+    /// Current balance of ERC-20 tokens held by the minter.
+    /// Computed based on audit events.
+    pub erc20_balances: Erc20Balances,
+
+    /// JSON-RPC providers to query instead of the default ones for the Ethereum network.
+    pub eth_rpc_config: Option<RpcConfig>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -78,6 +110,9 @@ pub enum InvalidStateError {
     InvalidEthereumContractAddress(String),
     InvalidMinimumWithdrawalAmount(String),
     InvalidLastScrapedBlockNumber(String),
+    InvalidErc20HelperContractAddress(String),
+    InvalidCkErc20Token(String),
+    InvalidEthRpcConfig(String),
 }
 
 impl State {
@@ -106,6 +141,33 @@ impl State {
                 "minimum_withdrawal_amount must be positive".to_string(),
             ));
         }
+        if self
+            .erc20_helper_contract_address
+            .iter()
+            .any(|address| address == &Address::ZERO)
+        {
+            return Err(InvalidStateError::InvalidErc20HelperContractAddress(
+                "erc20_helper_contract_address cannot be the zero address".to_string(),
+            ));
+        }
+        let mut ledger_ids = BTreeSet::new();
+        let mut symbols = BTreeSet::new();
+        for token in self.ckerc20_tokens.values() {
+            if token.ckerc20_ledger_id == self.ledger_id
+                || !ledger_ids.insert(token.ckerc20_ledger_id)
+            {
+                return Err(InvalidStateError::InvalidCkErc20Token(format!(
+                    "ledger {} is used by more than one token",
+                    token.ckerc20_ledger_id
+                )));
+            }
+            if !symbols.insert(token.ckerc20_token_symbol.clone()) {
+                return Err(InvalidStateError::InvalidCkErc20Token(format!(
+                    "symbol {} is used by more than one token",
+                    token.ckerc20_token_symbol
+                )));
+            }
+        }
         Ok(())
     }
 
@@ -135,13 +197,48 @@ impl State {
         !self.events_to_mint.is_empty()
     }
 
+    fn record_erc20_event_to_mint(&mut self, event: &ReceivedErc20Event) {
+        let event_source = event.source();
+        assert!(
+            !self.erc20_events_to_mint.contains_key(&event_source),
+            "there must be no two different events with the same source"
+        );
+        assert!(!self.minted_erc20_events.contains_key(&event_source));
+        assert!(!self.invalid_events.contains_key(&event_source));
+        assert!(
+            self.ckerc20_tokens
+                .contains_key(&event.erc20_contract_address),
+            "BUG: unsupported ERC-20 token {}",
+            event.erc20_contract_address
+        );
+
+        self.erc20_events_to_mint
+            .insert(event_source, event.clone());
+
+        self.erc20_balances
+            .erc20_add(event.erc20_contract_address, event.value);
+    }
+
+    pub fn has_erc20_events_to_mint(&self) -> bool {
+        !self.erc20_events_to_mint.is_empty()
+    }
+
+    /// Returns the supported ckERC20 token handled by the given ledger, if any.
+    pub fn find_ckerc20_token_by_ledger_id(&self, ledger_id: &Principal) -> Option<&CkErc20Token> {
+        self.ckerc20_tokens
+            .values()
+            .find(|token| &token.ckerc20_ledger_id == ledger_id)
+    }
+
     fn record_invalid_deposit(&mut self, source: EventSource, error: String) -> bool {
         assert!(
-            !self.events_to_mint.contains_key(&source),
+            !self.events_to_mint.contains_key(&source)
+                && !self.erc20_events_to_mint.contains_key(&source),
             "attempted to mark an accepted event as invalid"
         );
         assert!(
-            !self.minted_events.contains_key(&source),
+            !self.minted_events.contains_key(&source)
+                && !self.minted_erc20_events.contains_key(&source),
             "attempted to mark a minted event {source:?} as invalid"
         );
 
@@ -177,14 +274,55 @@ impl State {
         );
     }
 
+    fn record_successful_erc20_mint(
+        &mut self,
+        source: EventSource,
+        mint_block_index: LedgerMintIndex,
+    ) {
+        assert!(
+            !self.invalid_events.contains_key(&source),
+            "attempted to mint an event previously marked as invalid {source:?}"
+        );
+        let deposit_event = match self.erc20_events_to_mint.remove(&source) {
+            Some(event) => event,
+            None => panic!("attempted to mint ckERC20 for an unknown event {source:?}"),
+        };
+
+        assert_eq!(
+            self.minted_erc20_events.insert(
+                source,
+                MintedErc20Event {
+                    deposit_event,
+                    mint_block_index
+                }
+            ),
+            None,
+            "attempted to mint ckERC20 twice for the same event {source:?}"
+        );
+    }
+
     pub fn record_finalized_transaction(
         &mut self,
         withdrawal_id: &LedgerBurnIndex,
         receipt: &TransactionReceipt,
     ) {
+        let erc20_withdrawal_request = self
+            .eth_transactions
+            .get_processed_erc20_withdrawal_request(withdrawal_id)
+            .cloned();
         self.eth_transactions
             .record_finalized_transaction(*withdrawal_id, receipt.clone());
-        self.update_eth_balance_upon_withdrawal(withdrawal_id, receipt);
+        self.update_eth_balance_upon_withdrawal(
+            withdrawal_id,
+            receipt,
+            erc20_withdrawal_request.is_some(),
+        );
+        if let Some(request) = erc20_withdrawal_request {
+            if receipt.status == TransactionStatus::Success {
+                self.erc20_balances
+                    .erc20_sub(request.erc20_contract_address, request.withdrawal_amount);
+            }
+        }
     }
 
     pub fn next_request_id(&mut self) -> u64 {
@@ -203,6 +341,7 @@ impl State {
         &mut self,
         withdrawal_id: &LedgerBurnIndex,
         receipt: &TransactionReceipt,
+        is_erc20_withdrawal: bool,
     ) {
         let tx_fee = receipt.effective_transaction_fee();
         let tx = self
@@ -224,7 +363,10 @@ impl State {
         };
         self.eth_balance.eth_balance_sub(debited_amount);
         self.eth_balance.total_effective_tx_fees_add(tx_fee);
-        self.eth_balance.total_unspent_tx_fees_add(unspent_tx_fee);
+        // The unspent transaction fee of an ERC-20 withdrawal is refunded to the user.
+        if !is_erc20_withdrawal {
+            self.eth_balance.total_unspent_tx_fees_add(unspent_tx_fee);
+        }
     }
 
     pub fn record_skipped_block(&mut self, block_number: BlockNumber) {
@@ -235,6 +377,14 @@ impl State {
         );
     }
 
+    pub fn record_erc20_skipped_block(&mut self, block_number: BlockNumber) {
+        assert!(
+            self.erc20_skipped_blocks.insert(block_number),
+            "BUG: block {} was already skipped for ERC-20 deposits",
+            block_number
+        );
+    }
+
     pub const fn ethereum_network(&self) -> EthereumNetwork {
         self.ethereum_network
     }
@@ -251,6 +401,10 @@ impl State {
             minimum_withdrawal_amount,
             ethereum_contract_address,
             ethereum_block_height,
+            erc20_helper_contract_address,
+            last_erc20_scraped_block_number,
+            add_ckerc20_tokens,
+            eth_rpc_config,
         } = upgrade_args;
         if let Some(nonce) = next_transaction_nonce {
             let nonce = TransactionNonce::try_from(nonce)
@@ -272,6 +426,43 @@ impl State {
         if let Some(block_height) = ethereum_block_height {
             self.ethereum_block_height = block_height.into();
         }
+        if let Some(address) = erc20_helper_contract_address {
+            let erc20_helper_contract_address = Address::from_str(&address).map_err(|e| {
+                InvalidStateError::InvalidErc20HelperContractAddress(format!("ERROR: {}", e))
+            })?;
+            if self.erc20_helper_contract_address.is_none() {
+                // Unless specified otherwise, start scraping ERC-20 deposits
+                // from where the minter currently is for ETH deposits.
+                self.last_erc20_scraped_block_number = self.last_scraped_block_number;
+            }
+            self.erc20_helper_contract_address = Some(erc20_helper_contract_address);
+        }
+        if let Some(block_number) = last_erc20_scraped_block_number {
+            self.last_erc20_scraped_block_number =
+                BlockNumber::try_from(block_number).map_err(|e| {
+                    InvalidStateError::InvalidLastScrapedBlockNumber(format!("ERROR: {}", e))
+                })?;
+        }
+        for token in add_ckerc20_tokens.unwrap_or_default() {
+            let token = CkErc20Token::try_from(token)
+                .map_err(|e| InvalidStateError::InvalidCkErc20Token(format!("ERROR: {}", e)))?;
+            if self
+                .ckerc20_tokens
+                .contains_key(&token.erc20_contract_address)
+            {
+                return Err(InvalidStateError::InvalidCkErc20Token(format!(
+                    "ERROR: ERC-20 token {} is already supported",
+                    token.erc20_contract_address
+                )));
+            }
+            self.ckerc20_tokens
+                .insert(token.erc20_contract_address, token);
+        }
+        if let Some(config) = eth_rpc_config {
+            let config = RpcConfig::try_from(config)
+                .map_err(|e| InvalidStateError::InvalidEthRpcConfig(format!("ERROR: {}", e)))?;
+            self.eth_rpc_config = Some(config);
+        }
         self.validate_config()
     }
 
@@ -309,6 +500,19 @@ impl State {
         ensure_eq!(self.events_to_mint, other.events_to_mint);
         ensure_eq!(self.minted_events, other.minted_events);
         ensure_eq!(self.invalid_events, other.invalid_events);
+        ensure_eq!(
+            self.erc20_helper_contract_address,
+            other.erc20_helper_contract_address
+        );
+        ensure_eq!(self.ckerc20_tokens, other.ckerc20_tokens);
+        ensure_eq!(
+            self.last_erc20_scraped_block_number,
+            other.last_erc20_scraped_block_number
+        );
+        ensure_eq!(self.erc20_events_to_mint, other.erc20_events_to_mint);
+        ensure_eq!(self.minted_erc20_events, other.minted_erc20_events);
+        ensure_eq!(self.erc20_balances, other.erc20_balances);
+        ensure_eq!(self.eth_rpc_config, other.eth_rpc_config);
 
         self.eth_transactions
             .is_equivalent_to(&other.eth_transactions)
@@ -317,6 +521,10 @@ impl State {
     pub fn eth_balance(&self) -> &EthBalance {
         &self.eth_balance
     }
+
+    pub fn erc20_balances(&self) -> &Erc20Balances {
+        &self.erc20_balances
+    }
 }
 
 pub fn read_state<R>(f: impl FnOnce(&State) -> R) -> R {
@@ -460,9 +668,48 @@ impl EthBalance {
     }
 }
 
+/// Balances of ERC-20 tokens held by the minter, indexed by the ERC-20 contract address.
+/// Like [`EthBalance`], only deposits and withdrawals going through the minter are accounted for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Erc20Balances {
+    balance_by_erc20_contract: BTreeMap<Address, Erc20Value>,
+}
+
+impl Erc20Balances {
+    pub fn balance_of(&self, erc20_contract: &Address) -> Erc20Value {
+        *self
+            .balance_by_erc20_contract
+            .get(erc20_contract)
+            .unwrap_or(&Erc20Value::ZERO)
+    }
+
+    fn erc20_add(&mut self, erc20_contract: Address, deposit: Erc20Value) {
+        let balance = self.balance_of(&erc20_contract);
+        let new_balance = balance.checked_add(deposit).unwrap_or_else(|| {
+            panic!("BUG: overflow when adding {deposit} to {balance} for ERC-20 {erc20_contract}")
+        });
+        self.balance_by_erc20_contract
+            .insert(erc20_contract, new_balance);
+    }
+
+    fn erc20_sub(&mut self, erc20_contract: Address, withdrawal_amount: Erc20Value) {
+        let balance = self.balance_of(&erc20_contract);
+        let new_balance = balance.checked_sub(withdrawal_amount).unwrap_or_else(|| {
+            panic!("BUG: underflow when subtracting {withdrawal_amount} from {balance} for ERC-20 {erc20_contract}")
+        });
+        self.balance_by_erc20_contract
+            .insert(erc20_contract, new_balance);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Address, &Erc20Value)> {
+        self.balance_by_erc20_contract.iter()
+    }
+}
+
 #[derive(Debug, Hash, Copy, Clone, PartialEq, Eq, EnumIter)]
 pub enum TaskType {
     MintCkEth,
+    MintCkErc20,
     RetrieveEth,
     ScrapEthLogs,
     Reimbursement,
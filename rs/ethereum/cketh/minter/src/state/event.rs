@@ -1,8 +1,10 @@
-use crate::eth_logs::{EventSource, ReceivedEthEvent};
+use crate::eth_logs::{EventSource, ReceivedErc20Event, ReceivedEthEvent};
 use crate::eth_rpc_client::responses::TransactionReceipt;
 use crate::lifecycle::{init::InitArg, upgrade::UpgradeArg};
 use crate::numeric::{BlockNumber, LedgerBurnIndex, LedgerMintIndex};
-use crate::state::transactions::{EthWithdrawalRequest, Reimbursed};
+use crate::state::transactions::{
+    Erc20Reimbursed, Erc20WithdrawalRequest, EthWithdrawalRequest, Reimbursed, ReimbursementRequest,
+};
 use crate::tx::{Eip1559TransactionRequest, SignedEip1559TransactionRequest};
 use minicbor::{Decode, Encode};
 
@@ -93,6 +95,49 @@ pub enum EventType {
     /// The minter could not scrap the logs for that block.
     #[n(13)]
     SkippedBlock(#[n(0)] BlockNumber),
+    /// The minter discovered a ckERC20 deposit in the ERC-20 helper contract logs.
+    #[n(14)]
+    AcceptedErc20Deposit(#[n(0)] ReceivedErc20Event),
+    /// The minter minted ckERC20 in response to a deposit.
+    #[n(15)]
+    MintedCkErc20 {
+        /// The unique identifier of the deposit on the Ethereum network.
+        #[n(0)]
+        event_source: EventSource,
+        /// The transaction index on the ckERC20 ledger.
+        #[cbor(n(1), with = "crate::cbor::id")]
+        mint_block_index: LedgerMintIndex,
+    },
+    /// The minter processed the ERC-20 helper smart contract logs up to the specified height.
+    #[n(16)]
+    SyncedErc20ToBlock {
+        /// The last processed block number (inclusive).
+        #[n(0)]
+        block_number: BlockNumber,
+    },
+    /// The minter accepted a new ERC-20 withdrawal request.
+    #[n(17)]
+    AcceptedErc20WithdrawalRequest(#[n(0)] Erc20WithdrawalRequest),
+    /// The minter burned ckETH to pay for the transaction fee of an ERC-20 withdrawal
+    /// but failed to burn the corresponding ckERC20 tokens.
+    /// The burned ckETH must be reimbursed.
+    #[n(18)]
+    FailedErc20WithdrawalRequest(#[n(0)] ReimbursementRequest),
+    /// The minter successfully reimbursed the ckERC20 tokens of a failed ERC-20 withdrawal.
+    #[n(19)]
+    ReimbursedErc20Withdrawal(#[n(0)] Erc20Reimbursed),
+    /// The minter could not scrap the ERC-20 helper contract logs for that block.
+    #[n(20)]
+    SkippedErc20Block(#[n(0)] BlockNumber),
+    /// The minter could not create a transaction for an ERC-20 withdrawal request
+    /// because the transaction fee exceeded the fee paid by the user until the request expired.
+    /// Both the burned ckETH and the burned ckERC20 tokens must be reimbursed.
+    #[n(21)]
+    ExpiredErc20WithdrawalRequest {
+        /// The withdrawal identifier, i.e. the index of the ckETH burn transaction.
+        #[cbor(n(0), with = "crate::cbor::id")]
+        withdrawal_id: LedgerBurnIndex,
+    },
 }
 
 #[derive(Encode, Decode, Debug, PartialEq, Eq)]
@@ -1,17 +1,20 @@
 use crate::address::Address;
 use crate::checked_amount::CheckedAmountOf;
 use crate::endpoints::CandidBlockTag;
-use crate::eth_logs::{EventSource, ReceivedEthEvent};
+use crate::eth_logs::{EventSource, ReceivedErc20Event, ReceivedEthEvent};
 use crate::eth_rpc::{BlockTag, Hash};
 use crate::eth_rpc_client::responses::{TransactionReceipt, TransactionStatus};
 use crate::lifecycle::init::InitArg;
-use crate::lifecycle::upgrade::UpgradeArg;
+use crate::lifecycle::upgrade::{AddCkErc20Token, EthRpcConfig, UpgradeArg};
 use crate::lifecycle::EthereumNetwork;
 use crate::numeric::{
     wei_from_milli_ether, BlockNumber, GasAmount, LedgerBurnIndex, LedgerMintIndex, LogIndex,
     TransactionNonce, Wei, WeiPerGas,
 };
 use crate::state::event::{Event, EventType};
+use crate::state::transactions::{
+    Erc20Reimbursed, Erc20WithdrawalRequest, ReimbursementRequest, Subaccount,
+};
 use crate::state::State;
 use crate::tx::{
     AccessList, AccessListItem, Eip1559Signature, Eip1559TransactionRequest,
@@ -209,11 +212,11 @@ fn received_eth_event() -> ReceivedEthEvent {
 mod upgrade {
     use crate::address::Address;
     use crate::eth_rpc::BlockTag;
-    use crate::lifecycle::upgrade::UpgradeArg;
+    use crate::lifecycle::upgrade::{AddCkErc20Token, EthRpcConfig, UpgradeArg};
     use crate::numeric::{wei_from_milli_ether, TransactionNonce, Wei};
     use crate::state::{InvalidStateError, State};
     use assert_matches::assert_matches;
-    use candid::Nat;
+    use candid::{Nat, Principal};
     use num_bigint::BigUint;
     use std::str::FromStr;
 
@@ -271,6 +274,10 @@ mod upgrade {
                 "0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34".to_string(),
             ),
             ethereum_block_height: Some(CandidBlockTag::Safe),
+            erc20_helper_contract_address: None,
+            last_erc20_scraped_block_number: None,
+            add_ckerc20_tokens: None,
+            eth_rpc_config: None,
         };
 
         state.upgrade(upgrade_arg).expect("valid upgrade args");
@@ -287,9 +294,135 @@ mod upgrade {
         assert_eq!(state.ethereum_block_height, BlockTag::Safe);
     }
 
+    #[test]
+    fn should_add_ckerc20_tokens() {
+        let mut state = initial_state();
+        let usdc = AddCkErc20Token {
+            erc20_contract_address: "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238".to_string(),
+            ckerc20_token_symbol: "ckSepoliaUSDC".to_string(),
+            ckerc20_ledger_id: Principal::from_text("mxzaz-hqaaa-aaaar-qaada-cai").unwrap(),
+        };
+
+        state
+            .upgrade(UpgradeArg {
+                erc20_helper_contract_address: Some(
+                    "0xE1788E4834c896F1932188645cc36c54d1b80AC1".to_string(),
+                ),
+                add_ckerc20_tokens: Some(vec![usdc.clone()]),
+                ..Default::default()
+            })
+            .expect("valid upgrade args");
+
+        assert_eq!(
+            state.erc20_helper_contract_address,
+            Some(Address::from_str("0xE1788E4834c896F1932188645cc36c54d1b80AC1").unwrap())
+        );
+        assert_eq!(
+            state.last_erc20_scraped_block_number,
+            state.last_scraped_block_number
+        );
+        assert_eq!(
+            state
+                .find_ckerc20_token_by_ledger_id(&usdc.ckerc20_ledger_id)
+                .map(|token| token.ckerc20_token_symbol.clone()),
+            Some("ckSepoliaUSDC".to_string())
+        );
+
+        assert_matches!(
+            state.clone().upgrade(UpgradeArg {
+                add_ckerc20_tokens: Some(vec![usdc.clone()]),
+                ..Default::default()
+            }),
+            Err(InvalidStateError::InvalidCkErc20Token(_))
+        );
+        assert_matches!(
+            state.clone().upgrade(UpgradeArg {
+                add_ckerc20_tokens: Some(vec![AddCkErc20Token {
+                    erc20_contract_address: "0x7169D38820dfd117C3FA1f22a697dBA58d90BA06"
+                        .to_string(),
+                    ..usdc.clone()
+                }]),
+                ..Default::default()
+            }),
+            Err(InvalidStateError::InvalidCkErc20Token(_))
+        );
+        assert_matches!(
+            state.upgrade(UpgradeArg {
+                add_ckerc20_tokens: Some(vec![AddCkErc20Token {
+                    ckerc20_ledger_id: Principal::from_text("apia6-jaaaa-aaaar-qabma-cai").unwrap(),
+                    ckerc20_token_symbol: "ckETH2".to_string(),
+                    erc20_contract_address: "0x7169D38820dfd117C3FA1f22a697dBA58d90BA06"
+                        .to_string(),
+                }]),
+                ..Default::default()
+            }),
+            Err(InvalidStateError::InvalidCkErc20Token(_))
+        );
+    }
+
+    #[test]
+    fn should_configure_eth_rpc_providers() {
+        let mut state = initial_state();
+        let config = EthRpcConfig {
+            provider_urls: vec![
+                "https://rpc.ankr.com/eth".to_string(),
+                "https://ethereum.publicnode.com".to_string(),
+                "https://cloudflare-eth.com".to_string(),
+            ],
+            min_agreeing_providers: 2,
+        };
+
+        state
+            .upgrade(UpgradeArg {
+                eth_rpc_config: Some(config.clone()),
+                ..Default::default()
+            })
+            .expect("valid upgrade args");
+
+        let rpc_config = state.eth_rpc_config.clone().unwrap();
+        assert_eq!(
+            rpc_config.provider_urls().collect::<Vec<_>>(),
+            config.provider_urls
+        );
+        assert_eq!(rpc_config.min_agreeing_providers(), 2);
+
+        for invalid_config in [
+            EthRpcConfig {
+                provider_urls: vec![],
+                min_agreeing_providers: 0,
+            },
+            EthRpcConfig {
+                provider_urls: vec!["http://rpc.ankr.com/eth".to_string()],
+                min_agreeing_providers: 1,
+            },
+            EthRpcConfig {
+                provider_urls: vec![
+                    "https://rpc.ankr.com/eth".to_string(),
+                    "https://rpc.ankr.com/eth".to_string(),
+                ],
+                min_agreeing_providers: 2,
+            },
+            EthRpcConfig {
+                min_agreeing_providers: 1,
+                ..config.clone()
+            },
+            EthRpcConfig {
+                min_agreeing_providers: 4,
+                ..config.clone()
+            },
+        ] {
+            assert_matches!(
+                state.clone().upgrade(UpgradeArg {
+                    eth_rpc_config: Some(invalid_config),
+                    ..Default::default()
+                }),
+                Err(InvalidStateError::InvalidEthRpcConfig(_))
+            );
+        }
+    }
+
     fn initial_state() -> State {
         use crate::lifecycle::init::InitArg;
-        use candid::Principal;
         State::try_from(InitArg {
             ethereum_network: Default::default(),
             ecdsa_key_name: "test_key_1".to_string(),
@@ -388,12 +521,96 @@ prop_compose! {
         ethereum_block_height in proptest::option::of(arb_block_tag()),
         minimum_withdrawal_amount in proptest::option::of(arb_nat()),
         next_transaction_nonce in proptest::option::of(arb_nat()),
+        erc20_helper_contract_address in proptest::option::of(arb_address()),
+        last_erc20_scraped_block_number in proptest::option::of(arb_nat()),
+        add_ckerc20_tokens in proptest::option::of(pvec(arb_add_ckerc20_token(), 0..3)),
+        eth_rpc_config in proptest::option::of(arb_eth_rpc_config()),
     ) -> UpgradeArg {
         UpgradeArg {
             ethereum_contract_address: contract_address.map(|addr| addr.to_string()),
             ethereum_block_height,
             minimum_withdrawal_amount,
             next_transaction_nonce,
+            erc20_helper_contract_address: erc20_helper_contract_address.map(|addr| addr.to_string()),
+            last_erc20_scraped_block_number,
+            add_ckerc20_tokens,
+            eth_rpc_config,
+        }
+    }
+}
+
+prop_compose! {
+    fn arb_eth_rpc_config()(
+        provider_urls in pvec("https://[a-z]{1,10}\\.[a-z]{2,3}", 1..5),
+        min_agreeing_providers in any::<u32>(),
+    ) -> EthRpcConfig {
+        EthRpcConfig {
+            provider_urls,
+            min_agreeing_providers,
+        }
+    }
+}
+
+prop_compose! {
+    fn arb_add_ckerc20_token()(
+        erc20_contract_address in arb_address(),
+        ckerc20_token_symbol in "ck[A-Z]{3,5}",
+        ckerc20_ledger_id in arb_principal(),
+    ) -> AddCkErc20Token {
+        AddCkErc20Token {
+            erc20_contract_address: erc20_contract_address.to_string(),
+            ckerc20_token_symbol,
+            ckerc20_ledger_id,
+        }
+    }
+}
+
+prop_compose! {
+    fn arb_received_erc20_event()(
+        transaction_hash in arb_hash(),
+        block_number in arb_checked_amount_of(),
+        log_index in arb_checked_amount_of(),
+        from_address in arb_address(),
+        value in arb_checked_amount_of(),
+        principal in arb_principal(),
+        erc20_contract_address in arb_address(),
+    ) -> ReceivedErc20Event {
+        ReceivedErc20Event {
+            transaction_hash,
+            block_number,
+            log_index,
+            from_address,
+            value,
+            principal,
+            erc20_contract_address,
+        }
+    }
+}
+
+prop_compose! {
+    fn arb_erc20_withdrawal_request()(
+        max_transaction_fee in arb_checked_amount_of(),
+        withdrawal_amount in arb_checked_amount_of(),
+        destination in arb_address(),
+        cketh_ledger_burn_index in any::<u64>(),
+        erc20_contract_address in arb_address(),
+        ckerc20_ledger_id in arb_principal(),
+        ckerc20_ledger_burn_index in any::<u64>(),
+        from in arb_principal(),
+        from_subaccount in proptest::option::of(uniform32(any::<u8>())),
+        created_at in any::<u64>(),
+    ) -> Erc20WithdrawalRequest {
+        Erc20WithdrawalRequest {
+            max_transaction_fee,
+            withdrawal_amount,
+            destination,
+            cketh_ledger_burn_index: cketh_ledger_burn_index.into(),
+            erc20_contract_address,
+            ckerc20_ledger_id,
+            ckerc20_ledger_burn_index: ckerc20_ledger_burn_index.into(),
+            from,
+            from_subaccount: from_subaccount.map(Subaccount),
+            created_at,
         }
     }
 }
@@ -529,6 +746,55 @@ fn arb_event_type() -> impl Strategy<Value = EventType> {
                 transaction_receipt,
             }
         }),
+        arb_received_erc20_event().prop_map(EventType::AcceptedErc20Deposit),
+        (arb_event_source(), any::<u64>()).prop_map(|(event_source, index)| {
+            EventType::MintedCkErc20 {
+                event_source,
+                mint_block_index: index.into(),
+            }
+        }),
+        arb_checked_amount_of()
+            .prop_map(|block_number| EventType::SyncedErc20ToBlock { block_number }),
+        arb_erc20_withdrawal_request().prop_map(EventType::AcceptedErc20WithdrawalRequest),
+        (any::<u64>(), arb_checked_amount_of(), arb_principal()).prop_map(
+            |(withdrawal_id, reimbursed_amount, to)| {
+                EventType::FailedErc20WithdrawalRequest(ReimbursementRequest {
+                    withdrawal_id: withdrawal_id.into(),
+                    reimbursed_amount,
+                    to,
+                    to_subaccount: None,
+                    transaction_hash: None,
+                })
+            }
+        ),
+        (
+            any::<u64>(),
+            arb_principal(),
+            any::<u64>(),
+            arb_checked_amount_of(),
+            arb_hash()
+        )
+            .prop_map(
+                |(
+                    withdrawal_id,
+                    ckerc20_ledger_id,
+                    reimbursed_in_block,
+                    reimbursed_amount,
+                    transaction_hash,
+                )| {
+                    EventType::ReimbursedErc20Withdrawal(Erc20Reimbursed {
+                        withdrawal_id: withdrawal_id.into(),
+                        ckerc20_ledger_id,
+                        reimbursed_in_block: reimbursed_in_block.into(),
+                        reimbursed_amount,
+                        transaction_hash: Some(transaction_hash),
+                    })
+                }
+            ),
+        arb_checked_amount_of().prop_map(EventType::SkippedErc20Block),
+        any::<u64>().prop_map(|withdrawal_id| EventType::ExpiredErc20WithdrawalRequest {
+            withdrawal_id: withdrawal_id.into(),
+        }),
     ]
 }
 
@@ -708,6 +974,10 @@ fn state_equivalence() {
                 withdrawal_id: LedgerBurnIndex::new(6),
             },
         },
+        erc20_withdrawal_requests: Default::default(),
+        erc20_maybe_reimburse: Default::default(),
+        erc20_reimbursement_requests: Default::default(),
+        erc20_reimbursed: Default::default(),
     };
     let state = State {
         ethereum_network: EthereumNetwork::Mainnet,
@@ -759,6 +1029,14 @@ fn state_equivalence() {
         http_request_counter: 100,
         eth_balance: Default::default(),
         skipped_blocks: Default::default(),
+        erc20_helper_contract_address: None,
+        ckerc20_tokens: Default::default(),
+        last_erc20_scraped_block_number: BlockNumber::new(1_000_000),
+        erc20_skipped_blocks: Default::default(),
+        erc20_events_to_mint: Default::default(),
+        minted_erc20_events: Default::default(),
+        erc20_balances: Default::default(),
+        eth_rpc_config: None,
     };
 
     assert_eq!(
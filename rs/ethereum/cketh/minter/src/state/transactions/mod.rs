@@ -3,12 +3,15 @@ mod tests;
 
 use crate::address::Address;
 use crate::endpoints::{EthTransaction, RetrieveEthStatus, TxFinalizedStatus};
+use crate::erc20::{encode_erc20_transfer_data, ERC20_TRANSFER_GAS_LIMIT};
 use crate::eth_rpc::Hash;
 use crate::eth_rpc_client::responses::TransactionReceipt;
 use crate::eth_rpc_client::responses::TransactionStatus;
 use crate::lifecycle::EthereumNetwork;
 use crate::map::MultiKeyMap;
-use crate::numeric::{LedgerBurnIndex, LedgerMintIndex, TransactionCount, TransactionNonce, Wei};
+use crate::numeric::{
+    Erc20Value, LedgerBurnIndex, LedgerMintIndex, TransactionCount, TransactionNonce, Wei,
+};
 use crate::tx::{
     Eip1559TransactionRequest, FinalizedEip1559Transaction, SignedEip1559TransactionRequest,
     TransactionPrice,
@@ -42,6 +45,53 @@ pub struct EthWithdrawalRequest {
     pub created_at: Option<u64>,
 }
 
+/// ERC-20 withdrawal request issued by the user.
+/// The transaction fee is paid upfront by burning ckETH,
+/// while the withdrawn amount is burned on the ledger of the corresponding ckERC20 token.
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+pub struct Erc20WithdrawalRequest {
+    /// Amount of ckETH burned to pay for the transaction fee.
+    #[n(0)]
+    pub max_transaction_fee: Wei,
+    /// The amount of ERC-20 tokens that the receiver will get.
+    #[n(1)]
+    pub withdrawal_amount: Erc20Value,
+    /// The address to which the minter will send the ERC-20 tokens.
+    #[n(2)]
+    pub destination: Address,
+    /// The transaction ID of the ckETH burn operation on the ckETH ledger.
+    /// It is used as the withdrawal identifier.
+    #[cbor(n(3), with = "crate::cbor::id")]
+    pub cketh_ledger_burn_index: LedgerBurnIndex,
+    /// Address of the ERC-20 smart contract.
+    #[n(4)]
+    pub erc20_contract_address: Address,
+    /// The ledger on which ckERC20 tokens were burned.
+    #[cbor(n(5), with = "crate::cbor::principal")]
+    pub ckerc20_ledger_id: Principal,
+    /// The transaction ID of the ckERC20 burn operation on the ckERC20 ledger.
+    #[cbor(n(6), with = "crate::cbor::id")]
+    pub ckerc20_ledger_burn_index: LedgerBurnIndex,
+    /// The owner of the account from which the minter burned ckETH and ckERC20.
+    #[cbor(n(7), with = "crate::cbor::principal")]
+    pub from: Principal,
+    /// The subaccount from which the minter burned ckETH and ckERC20.
+    #[n(8)]
+    pub from_subaccount: Option<Subaccount>,
+    /// The IC time at which the withdrawal request arrived.
+    #[n(9)]
+    pub created_at: u64,
+}
+
+impl Erc20WithdrawalRequest {
+    /// Returns true if the request arrived at least [`crate::ERC20_WITHDRAWAL_REQUEST_EXPIRY`]
+    /// before `now_nanos`, so that it should no longer be processed.
+    pub fn is_expired(&self, now_nanos: u64) -> bool {
+        let expiry_nanos = crate::ERC20_WITHDRAWAL_REQUEST_EXPIRY.as_nanos() as u64;
+        now_nanos.saturating_sub(self.created_at) >= expiry_nanos
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Encode, Decode)]
 pub struct ReimbursementRequest {
     #[cbor(n(0), with = "crate::cbor::id")]
@@ -53,9 +103,10 @@ pub struct ReimbursementRequest {
     #[n(3)]
     pub to_subaccount: Option<Subaccount>,
     #[n(4)]
-    /// Transaction hash of the failed ETH transaction.
+    /// Transaction hash of the failed ETH transaction,
+    /// or of the ERC-20 transaction whose unspent transaction fee is refunded.
     /// We use this hash to link the mint reimbursement transaction
-    /// on the ledger with the failed ETH transaction.
+    /// on the ledger with the Ethereum transaction.
     pub transaction_hash: Option<Hash>,
 }
 
@@ -71,6 +122,42 @@ pub struct Reimbursed {
     pub transaction_hash: Option<Hash>,
 }
 
+/// Reimbursement of ckERC20 tokens for a failed ERC-20 transfer transaction
+/// or for an expired ERC-20 withdrawal request.
+/// In case of a failed transaction, the ckETH burned to pay for the transaction fee
+/// is not reimbursed, since the fee was consumed by the failed transaction.
+#[derive(Debug, Clone, Eq, PartialEq, Encode, Decode)]
+pub struct Erc20ReimbursementRequest {
+    #[cbor(n(0), with = "crate::cbor::id")]
+    pub withdrawal_id: LedgerBurnIndex,
+    #[cbor(n(1), with = "crate::cbor::principal")]
+    pub ckerc20_ledger_id: Principal,
+    #[n(2)]
+    pub reimbursed_amount: Erc20Value,
+    #[cbor(n(3), with = "crate::cbor::principal")]
+    pub to: Principal,
+    #[n(4)]
+    pub to_subaccount: Option<Subaccount>,
+    /// Transaction hash of the failed ERC-20 transfer transaction,
+    /// or `None` if the withdrawal request expired before a transaction was created.
+    #[n(5)]
+    pub transaction_hash: Option<Hash>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Encode, Decode)]
+pub struct Erc20Reimbursed {
+    #[cbor(n(0), with = "crate::cbor::id")]
+    pub withdrawal_id: LedgerBurnIndex,
+    #[cbor(n(1), with = "crate::cbor::principal")]
+    pub ckerc20_ledger_id: Principal,
+    #[cbor(n(2), with = "crate::cbor::id")]
+    pub reimbursed_in_block: LedgerMintIndex,
+    #[n(3)]
+    pub reimbursed_amount: Erc20Value,
+    #[n(4)]
+    pub transaction_hash: Option<Hash>,
+}
+
 #[derive(Clone, Eq, PartialEq, Encode, Decode)]
 #[cbor(transparent)]
 pub struct Subaccount(#[cbor(n(0), with = "minicbor::bytes")] pub [u8; 32]);
@@ -101,6 +188,25 @@ impl fmt::Debug for EthWithdrawalRequest {
     }
 }
 
+impl fmt::Debug for Erc20WithdrawalRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        f.debug_struct("Erc20WithdrawalRequest")
+            .field("max_transaction_fee", &self.max_transaction_fee)
+            .field("withdrawal_amount", &self.withdrawal_amount)
+            .field("destination", &self.destination)
+            .field("cketh_ledger_burn_index", &self.cketh_ledger_burn_index)
+            .field("erc20_contract_address", &self.erc20_contract_address)
+            .field(
+                "ckerc20_ledger_id",
+                &DebugPrincipal(&self.ckerc20_ledger_id),
+            )
+            .field("ckerc20_ledger_burn_index", &self.ckerc20_ledger_burn_index)
+            .field("from", &DebugPrincipal(&self.from))
+            .field("from_subaccount", &self.from_subaccount)
+            .finish()
+    }
+}
+
 /// State machine holding Ethereum transactions issued by the minter.
 /// Overall the transaction lifecycle is as follows:
 /// 1. The user's withdrawal request is enqueued and processed in a FIFO order.
@@ -115,6 +221,10 @@ impl fmt::Debug for EthWithdrawalRequest {
 ///    The others sent transactions for that nonce were never mined and can be discarded.
 /// 6. If a given transaction fails the minter will reimburse the user who requested the
 ///    withdrawal with the corresponding amount minus fees.
+///
+/// ERC-20 withdrawal requests go through the same lifecycle and share the same nonces,
+/// but are kept in separate queues since the transaction fee is paid separately in ckETH
+/// instead of being deducted from the withdrawn amount.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct EthTransactions {
     pub(in crate::state) withdrawal_requests: VecDeque<EthWithdrawalRequest>,
@@ -129,6 +239,12 @@ pub struct EthTransactions {
     pub(in crate::state) maybe_reimburse: BTreeMap<LedgerBurnIndex, EthWithdrawalRequest>,
     pub(in crate::state) reimbursement_requests: BTreeMap<LedgerBurnIndex, ReimbursementRequest>,
     pub(in crate::state) reimbursed: BTreeMap<LedgerBurnIndex, Reimbursed>,
+
+    pub(in crate::state) erc20_withdrawal_requests: VecDeque<Erc20WithdrawalRequest>,
+    pub(in crate::state) erc20_maybe_reimburse: BTreeMap<LedgerBurnIndex, Erc20WithdrawalRequest>,
+    pub(in crate::state) erc20_reimbursement_requests:
+        BTreeMap<LedgerBurnIndex, Erc20ReimbursementRequest>,
+    pub(in crate::state) erc20_reimbursed: BTreeMap<LedgerBurnIndex, Erc20Reimbursed>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -138,6 +254,11 @@ pub enum CreateTransactionError {
         withdrawal_amount: Wei,
         max_transaction_fee: Wei,
     },
+    InsufficientTransactionFee {
+        ledger_burn_index: LedgerBurnIndex,
+        allowed_max_transaction_fee: Wei,
+        max_transaction_fee: Wei,
+    },
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -161,6 +282,10 @@ impl EthTransactions {
             maybe_reimburse: Default::default(),
             reimbursement_requests: Default::default(),
             reimbursed: Default::default(),
+            erc20_withdrawal_requests: VecDeque::new(),
+            erc20_maybe_reimburse: Default::default(),
+            erc20_reimbursement_requests: Default::default(),
+            erc20_reimbursed: Default::default(),
         }
     }
 
@@ -180,21 +305,66 @@ impl EthTransactions {
         self.reimbursed.values().cloned().collect()
     }
 
+    pub fn get_erc20_reimbursement_requests(&self) -> Vec<Erc20ReimbursementRequest> {
+        self.erc20_reimbursement_requests
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_erc20_reimbursed_transactions(&self) -> Vec<Erc20Reimbursed> {
+        self.erc20_reimbursed.values().cloned().collect()
+    }
+
+    /// Returns the ERC-20 withdrawal request for which a transaction was created but not yet finalized.
+    pub fn get_processed_erc20_withdrawal_request(
+        &self,
+        withdrawal_id: &LedgerBurnIndex,
+    ) -> Option<&Erc20WithdrawalRequest> {
+        self.erc20_maybe_reimburse.get(withdrawal_id)
+    }
+
+    fn is_known_burn_index(&self, burn_index: &LedgerBurnIndex) -> bool {
+        self.withdrawal_requests
+            .iter()
+            .any(|r| &r.ledger_burn_index == burn_index)
+            || self
+                .erc20_withdrawal_requests
+                .iter()
+                .any(|r| &r.cketh_ledger_burn_index == burn_index)
+            || self.created_tx.contains_alt(burn_index)
+            || self.sent_tx.contains_alt(burn_index)
+            || self.finalized_tx.contains_alt(burn_index)
+            || self.reimbursement_requests.contains_key(burn_index)
+    }
+
     pub fn record_withdrawal_request(&mut self, request: EthWithdrawalRequest) {
         let burn_index = request.ledger_burn_index;
-        if self
-            .withdrawal_requests
-            .iter()
-            .any(|r| r.ledger_burn_index == burn_index)
-            || self.created_tx.contains_alt(&burn_index)
-            || self.sent_tx.contains_alt(&burn_index)
-            || self.finalized_tx.contains_alt(&burn_index)
-        {
+        if self.is_known_burn_index(&burn_index) {
             panic!("BUG: duplicate ledger burn index {burn_index}");
         }
         self.withdrawal_requests.push_back(request);
     }
 
+    pub fn record_erc20_withdrawal_request(&mut self, request: Erc20WithdrawalRequest) {
+        let burn_index = request.cketh_ledger_burn_index;
+        if self.is_known_burn_index(&burn_index) {
+            panic!("BUG: duplicate ckETH ledger burn index {burn_index}");
+        }
+        self.erc20_withdrawal_requests.push_back(request);
+    }
+
+    /// Records the reimbursement of the ckETH burned to pay for the transaction fee
+    /// of an ERC-20 withdrawal request that could not be accepted
+    /// (e.g. because burning the ckERC20 tokens failed).
+    pub fn record_failed_erc20_withdrawal_request(&mut self, request: ReimbursementRequest) {
+        let burn_index = request.withdrawal_id;
+        if self.is_known_burn_index(&burn_index) || self.reimbursed.contains_key(&burn_index) {
+            panic!("BUG: duplicate ckETH ledger burn index {burn_index}");
+        }
+        self.reimbursement_requests.insert(burn_index, request);
+    }
+
     /// Move an existing withdrawal request to the back of the queue.
     pub fn reschedule_withdrawal_request(&mut self, request: EthWithdrawalRequest) {
         assert_eq!(
@@ -210,11 +380,76 @@ impl EthTransactions {
         self.record_withdrawal_request(request);
     }
 
+    /// Move an existing ERC-20 withdrawal request to the back of the queue.
+    pub fn reschedule_erc20_withdrawal_request(&mut self, request: Erc20WithdrawalRequest) {
+        assert_eq!(
+            self.erc20_withdrawal_requests
+                .iter()
+                .filter(|r| r.cketh_ledger_burn_index == request.cketh_ledger_burn_index)
+                .count(),
+            1,
+            "BUG: expected exactly one ERC-20 withdrawal request with ckETH ledger burn index {}",
+            request.cketh_ledger_burn_index
+        );
+        self.erc20_withdrawal_requests.retain(|r| r != &request);
+        self.record_erc20_withdrawal_request(request);
+    }
+
+    /// Removes an ERC-20 withdrawal request for which no transaction could be created
+    /// before it expired, and records the reimbursement of both the ckETH burned to pay
+    /// for the transaction fee and the ckERC20 tokens burned.
+    pub fn record_expired_erc20_withdrawal_request(&mut self, withdrawal_id: LedgerBurnIndex) {
+        let request = self
+            .erc20_withdrawal_requests
+            .iter()
+            .find(|r| r.cketh_ledger_burn_index == withdrawal_id)
+            .cloned()
+            .unwrap_or_else(|| panic!("BUG: ERC-20 withdrawal request {withdrawal_id} not found"));
+        self.erc20_withdrawal_requests.retain(|r| r != &request);
+        assert_eq!(
+            self.reimbursement_requests.insert(
+                withdrawal_id,
+                ReimbursementRequest {
+                    withdrawal_id,
+                    reimbursed_amount: request.max_transaction_fee,
+                    to: request.from,
+                    to_subaccount: request.from_subaccount.clone(),
+                    transaction_hash: None,
+                },
+            ),
+            None,
+            "BUG: duplicate reimbursement request for {withdrawal_id}"
+        );
+        assert_eq!(
+            self.erc20_reimbursement_requests.insert(
+                withdrawal_id,
+                Erc20ReimbursementRequest {
+                    withdrawal_id,
+                    ckerc20_ledger_id: request.ckerc20_ledger_id,
+                    reimbursed_amount: request.withdrawal_amount,
+                    to: request.from,
+                    to_subaccount: request.from_subaccount,
+                    transaction_hash: None,
+                },
+            ),
+            None,
+            "BUG: duplicate ERC-20 reimbursement request for {withdrawal_id}"
+        );
+    }
+
     pub fn record_created_transaction(
         &mut self,
         withdrawal_id: LedgerBurnIndex,
         transaction: Eip1559TransactionRequest,
     ) {
+        if let Some(erc20_request) = self
+            .erc20_withdrawal_requests
+            .iter()
+            .find(|req| req.cketh_ledger_burn_index == withdrawal_id)
+            .cloned()
+        {
+            return self.record_created_erc20_transaction(erc20_request, transaction);
+        }
         let withdrawal_request = self
             .withdrawal_requests
             .iter()
@@ -249,6 +484,51 @@ impl EthTransactions {
             .insert(withdrawal_id, withdrawal_request);
     }
 
+    fn record_created_erc20_transaction(
+        &mut self,
+        withdrawal_request: Erc20WithdrawalRequest,
+        transaction: Eip1559TransactionRequest,
+    ) {
+        assert_eq!(
+            withdrawal_request.erc20_contract_address, transaction.destination,
+            "BUG: ERC-20 transaction must be sent to the ERC-20 contract"
+        );
+        assert_eq!(
+            transaction.amount,
+            Wei::ZERO,
+            "BUG: ERC-20 transaction must not transfer any ETH"
+        );
+        assert_eq!(
+            transaction.data,
+            encode_erc20_transfer_data(
+                &withdrawal_request.destination,
+                withdrawal_request.withdrawal_amount
+            ),
+            "BUG: ERC-20 transaction data does not match the withdrawal request"
+        );
+        assert!(
+            transaction.transaction_price().max_transaction_fee()
+                <= withdrawal_request.max_transaction_fee,
+            "BUG: ERC-20 transaction fee must not exceed the fee paid by the user"
+        );
+        let nonce = self.next_nonce;
+        assert_eq!(transaction.nonce, nonce, "BUG: transaction nonce mismatch");
+        self.next_nonce = self
+            .next_nonce
+            .checked_increment()
+            .expect("Transaction nonce overflow");
+        let withdrawal_id = withdrawal_request.cketh_ledger_burn_index;
+        self.erc20_withdrawal_requests
+            .retain(|r| r != &withdrawal_request);
+        assert_eq!(
+            self.created_tx
+                .try_insert(nonce, withdrawal_id, transaction),
+            Ok(())
+        );
+        self.erc20_maybe_reimburse
+            .insert(withdrawal_id, withdrawal_request);
+    }
+
     pub fn record_signed_transaction(
         &mut self,
         signed_transaction: SignedEip1559TransactionRequest,
@@ -304,6 +584,30 @@ impl EthTransactions {
             let last_tx = last_signed_tx.transaction().clone();
             let last_tx_price = last_tx.transaction_price();
             let last_tx_max_fee = last_tx_price.max_transaction_fee();
+            if let Some(erc20_request) = self.erc20_maybe_reimburse.get(burn_index) {
+                // The transaction fee of an ERC-20 withdrawal was paid upfront in ckETH
+                // and cannot be deducted from the transferred tokens,
+                // so the new fee is capped at what the user paid.
+                // Since a replacement transaction must increase the fees by at least 10%,
+                // once the cap is reached the last transaction is re-sent as is,
+                // without blocking the resubmission of the following transactions.
+                let min_new_tx_price = last_tx_price.clone().increase_by_10_percent();
+                if last_tx_price.is_fee_increased(&current_transaction_price)
+                    && min_new_tx_price.max_transaction_fee() <= erc20_request.max_transaction_fee
+                {
+                    let new_tx_price = min_new_tx_price
+                        .max(current_transaction_price.clone())
+                        .cap_max_transaction_fee(erc20_request.max_transaction_fee);
+                    let new_tx = Eip1559TransactionRequest {
+                        max_priority_fee_per_gas: new_tx_price.max_priority_fee_per_gas,
+                        max_fee_per_gas: new_tx_price.max_fee_per_gas,
+                        gas_limit: new_tx_price.gas_limit,
+                        ..last_tx
+                    };
+                    transactions_to_resubmit.push(Ok((*burn_index, new_tx)));
+                }
+                continue;
+            }
             if last_tx_price.is_fee_increased(&current_transaction_price) {
                 let new_tx_price = last_tx_price
                     .increase_by_10_percent()
@@ -408,6 +712,41 @@ impl EthTransactions {
             Ok(())
         );
 
+        if let Some(erc20_request) = self.erc20_maybe_reimburse.remove(&ledger_burn_index) {
+            // The user paid for the transaction fee with a margin,
+            // the part that was not consumed by the transaction is given back.
+            let unspent_tx_fee = erc20_request
+                .max_transaction_fee
+                .checked_sub(receipt.effective_transaction_fee())
+                .expect("BUG: ERC-20 transaction fee must not exceed the fee paid by the user");
+            if unspent_tx_fee > Wei::ZERO {
+                self.reimbursement_requests.insert(
+                    ledger_burn_index,
+                    ReimbursementRequest {
+                        withdrawal_id: ledger_burn_index,
+                        reimbursed_amount: unspent_tx_fee,
+                        to: erc20_request.from,
+                        to_subaccount: erc20_request.from_subaccount.clone(),
+                        transaction_hash: Some(receipt.transaction_hash),
+                    },
+                );
+            }
+            if receipt.status == TransactionStatus::Failure {
+                self.erc20_reimbursement_requests.insert(
+                    ledger_burn_index,
+                    Erc20ReimbursementRequest {
+                        withdrawal_id: ledger_burn_index,
+                        ckerc20_ledger_id: erc20_request.ckerc20_ledger_id,
+                        reimbursed_amount: erc20_request.withdrawal_amount,
+                        to: erc20_request.from,
+                        to_subaccount: erc20_request.from_subaccount,
+                        transaction_hash: Some(receipt.transaction_hash),
+                    },
+                );
+            }
+            return;
+        }
+
         let maybe_reimburse = self.maybe_reimburse.remove(&ledger_burn_index).expect(
             "failed to remove entry from maybe_reimburse map with block index: {ledger_burn_index}",
         );
@@ -448,11 +787,39 @@ impl EthTransactions {
         );
     }
 
+    pub fn record_finalized_erc20_reimbursement(
+        &mut self,
+        withdrawal_id: LedgerBurnIndex,
+        reimbursed_in_block: LedgerMintIndex,
+    ) {
+        let reimbursement_request = self
+            .erc20_reimbursement_requests
+            .remove(&withdrawal_id)
+            .expect("failed to remove ERC-20 reimbursement request");
+        assert_eq!(
+            self.erc20_reimbursed.insert(
+                withdrawal_id,
+                Erc20Reimbursed {
+                    withdrawal_id,
+                    ckerc20_ledger_id: reimbursement_request.ckerc20_ledger_id,
+                    reimbursed_in_block,
+                    reimbursed_amount: reimbursement_request.reimbursed_amount,
+                    transaction_hash: reimbursement_request.transaction_hash,
+                },
+            ),
+            None
+        );
+    }
+
     pub fn transaction_status(&self, burn_index: &LedgerBurnIndex) -> RetrieveEthStatus {
         if self
             .withdrawal_requests
             .iter()
             .any(|r| &r.ledger_burn_index == burn_index)
+            || self
+                .erc20_withdrawal_requests
+                .iter()
+                .any(|r| &r.cketh_ledger_burn_index == burn_index)
         {
             return RetrieveEthStatus::Pending;
         }
@@ -466,19 +833,40 @@ impl EthTransactions {
         }
 
         if let Some(tx) = self.finalized_tx.get_alt(burn_index) {
-            if let Some(reimbursed) = self.reimbursed.get(burn_index) {
-                return RetrieveEthStatus::TxFinalized(TxFinalizedStatus::Reimbursed {
-                    reimbursed_in_block: reimbursed.reimbursed_in_block.get().into(),
-                    transaction_hash: tx.transaction_hash().to_string(),
-                    reimbursed_amount: reimbursed.reimbursed_amount.into(),
-                });
-            }
             if tx.transaction_status() == &TransactionStatus::Failure {
-                return RetrieveEthStatus::TxFinalized(TxFinalizedStatus::PendingReimbursement(
-                    EthTransaction {
-                        transaction_hash: tx.transaction_hash().to_string(),
-                    },
-                ));
+                // A failed ERC-20 transaction is reimbursed in ckERC20 tokens,
+                // its ckETH reimbursement is only the refund of the unspent transaction fee.
+                let is_erc20 = self.erc20_reimbursement_requests.contains_key(burn_index)
+                    || self.erc20_reimbursed.contains_key(burn_index);
+                let reimbursed = if is_erc20 {
+                    self.erc20_reimbursed.get(burn_index).map(|reimbursed| {
+                        (
+                            reimbursed.reimbursed_in_block,
+                            candid::Nat::from(reimbursed.reimbursed_amount),
+                        )
+                    })
+                } else {
+                    self.reimbursed.get(burn_index).map(|reimbursed| {
+                        (
+                            reimbursed.reimbursed_in_block,
+                            candid::Nat::from(reimbursed.reimbursed_amount),
+                        )
+                    })
+                };
+                return match reimbursed {
+                    Some((reimbursed_in_block, reimbursed_amount)) => {
+                        RetrieveEthStatus::TxFinalized(TxFinalizedStatus::Reimbursed {
+                            reimbursed_in_block: reimbursed_in_block.get().into(),
+                            transaction_hash: tx.transaction_hash().to_string(),
+                            reimbursed_amount,
+                        })
+                    }
+                    None => RetrieveEthStatus::TxFinalized(
+                        TxFinalizedStatus::PendingReimbursement(EthTransaction {
+                            transaction_hash: tx.transaction_hash().to_string(),
+                        }),
+                    ),
+                };
             }
 
             return RetrieveEthStatus::TxFinalized(TxFinalizedStatus::Success(EthTransaction {
@@ -519,6 +907,34 @@ impl EthTransactions {
         self.withdrawal_requests.len()
     }
 
+    /// Same as [`Self::withdrawal_requests_batch`] for ERC-20 withdrawal requests.
+    /// Both kinds of requests share the same limit on pending transaction nonces.
+    pub fn erc20_withdrawal_requests_batch(
+        &self,
+        requested_batch_size: usize,
+    ) -> Vec<Erc20WithdrawalRequest> {
+        const MAX_NUM_PENDING_TRANSACTION_NONCES: usize = 1000;
+        let unique_pending_transaction_nonces: BTreeSet<_> =
+            self.created_tx.keys().chain(self.sent_tx.keys()).collect();
+        let actual_batch_size = min(
+            MAX_NUM_PENDING_TRANSACTION_NONCES
+                .saturating_sub(unique_pending_transaction_nonces.len()),
+            requested_batch_size,
+        );
+        self.erc20_withdrawal_requests_iter()
+            .take(actual_batch_size)
+            .cloned()
+            .collect()
+    }
+
+    pub fn erc20_withdrawal_requests_iter(&self) -> impl Iterator<Item = &Erc20WithdrawalRequest> {
+        self.erc20_withdrawal_requests.iter()
+    }
+
+    pub fn erc20_withdrawal_requests_len(&self) -> usize {
+        self.erc20_withdrawal_requests.len()
+    }
+
     pub fn transactions_to_sign_iter(
         &self,
     ) -> impl Iterator<
@@ -590,6 +1006,7 @@ impl EthTransactions {
 
     pub fn has_pending_requests(&self) -> bool {
         !self.withdrawal_requests.is_empty()
+            || !self.erc20_withdrawal_requests.is_empty()
             || !self.created_tx.is_empty()
             || !self.sent_tx.is_empty()
     }
@@ -650,6 +1067,25 @@ impl EthTransactions {
         ensure_eq!(self.reimbursement_requests, other.reimbursement_requests);
         ensure_eq!(self.reimbursed, other.reimbursed);
 
+        fn sorted_erc20_requests(
+            requests: &VecDeque<Erc20WithdrawalRequest>,
+        ) -> Vec<Erc20WithdrawalRequest> {
+            let mut buf: Vec<_> = requests.iter().cloned().collect();
+            buf.sort_unstable_by_key(|req| req.cketh_ledger_burn_index);
+            buf
+        }
+
+        ensure_eq!(
+            sorted_erc20_requests(&self.erc20_withdrawal_requests),
+            sorted_erc20_requests(&other.erc20_withdrawal_requests)
+        );
+        ensure_eq!(self.erc20_maybe_reimburse, other.erc20_maybe_reimburse);
+        ensure_eq!(
+            self.erc20_reimbursement_requests,
+            other.erc20_reimbursement_requests
+        );
+        ensure_eq!(self.erc20_reimbursed, other.erc20_reimbursed);
+
         Ok(())
     }
 
@@ -658,6 +1094,12 @@ impl EthTransactions {
             .iter()
             .chain(self.maybe_reimburse.values())
             .flat_map(|req| req.created_at.into_iter())
+            .chain(
+                self.erc20_withdrawal_requests
+                    .iter()
+                    .chain(self.erc20_maybe_reimburse.values())
+                    .map(|req| req.created_at),
+            )
             .min()
     }
 }
@@ -701,6 +1143,47 @@ pub fn create_transaction(
     })
 }
 
+/// Creates an EIP-1559 transaction calling `transfer` on the ERC-20 contract
+/// for the given withdrawal request.
+/// The transaction fee was already paid by the user in ckETH and so the transaction
+/// cannot be created if the current fee estimate exceeds it.
+///
+/// # Errors
+/// * `CreateTransactionError::InsufficientTransactionFee` if the paid fee does not cover the estimated transaction fee.
+pub fn create_erc20_transaction(
+    withdrawal_request: &Erc20WithdrawalRequest,
+    nonce: TransactionNonce,
+    transaction_price: TransactionPrice,
+    ethereum_network: EthereumNetwork,
+) -> Result<Eip1559TransactionRequest, CreateTransactionError> {
+    let transaction_price = TransactionPrice {
+        gas_limit: ERC20_TRANSFER_GAS_LIMIT,
+        ..transaction_price
+    };
+    let max_transaction_fee = transaction_price.max_transaction_fee();
+    if max_transaction_fee > withdrawal_request.max_transaction_fee {
+        return Err(CreateTransactionError::InsufficientTransactionFee {
+            ledger_burn_index: withdrawal_request.cketh_ledger_burn_index,
+            allowed_max_transaction_fee: withdrawal_request.max_transaction_fee,
+            max_transaction_fee,
+        });
+    }
+    Ok(Eip1559TransactionRequest {
+        chain_id: ethereum_network.chain_id(),
+        nonce,
+        max_priority_fee_per_gas: transaction_price.max_priority_fee_per_gas,
+        max_fee_per_gas: transaction_price.max_fee_per_gas,
+        gas_limit: transaction_price.gas_limit,
+        destination: withdrawal_request.erc20_contract_address,
+        amount: Wei::ZERO,
+        data: encode_erc20_transfer_data(
+            &withdrawal_request.destination,
+            withdrawal_request.withdrawal_amount,
+        ),
+        access_list: Default::default(),
+    })
+}
+
 /// Returns true if the two transactions are equal ignoring the transaction fee and amount.
 /// The following fields are ignored:
 /// * `max_fee_per_gas`
@@ -2,9 +2,11 @@ use crate::address::Address;
 use crate::eth_rpc::Hash;
 use crate::eth_rpc_client::responses::{TransactionReceipt, TransactionStatus};
 use crate::lifecycle::EthereumNetwork;
-use crate::numeric::{BlockNumber, GasAmount, LedgerBurnIndex, TransactionNonce, Wei, WeiPerGas};
+use crate::numeric::{
+    BlockNumber, Erc20Value, GasAmount, LedgerBurnIndex, TransactionNonce, Wei, WeiPerGas,
+};
 use crate::state::transactions::{
-    create_transaction, EthTransactions, EthWithdrawalRequest, Subaccount,
+    create_transaction, Erc20WithdrawalRequest, EthTransactions, EthWithdrawalRequest, Subaccount,
 };
 use crate::tx::{
     AccessList, Eip1559Signature, Eip1559TransactionRequest, SignedEip1559TransactionRequest,
@@ -1577,6 +1579,233 @@ mod eth_withdrawal_request {
     }
 }
 
+mod erc20_withdrawal_request {
+    use crate::numeric::{LedgerBurnIndex, TransactionNonce};
+    use crate::state::transactions::tests::{
+        erc20_withdrawal_request_with_index, DEFAULT_CREATED_AT,
+    };
+    use crate::state::transactions::{
+        Erc20ReimbursementRequest, EthTransactions, ReimbursementRequest,
+    };
+    use crate::ERC20_WITHDRAWAL_REQUEST_EXPIRY;
+
+    #[test]
+    fn should_expire_after_expiry_duration() {
+        let request = erc20_withdrawal_request_with_index(LedgerBurnIndex::new(131));
+        let expiry_nanos = ERC20_WITHDRAWAL_REQUEST_EXPIRY.as_nanos() as u64;
+
+        assert!(!request.is_expired(DEFAULT_CREATED_AT));
+        assert!(!request.is_expired(DEFAULT_CREATED_AT + expiry_nanos - 1));
+        assert!(request.is_expired(DEFAULT_CREATED_AT + expiry_nanos));
+    }
+
+    #[test]
+    fn should_reimburse_fee_and_tokens_of_expired_request() {
+        let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+        let first_request = erc20_withdrawal_request_with_index(LedgerBurnIndex::new(15));
+        let second_request = erc20_withdrawal_request_with_index(LedgerBurnIndex::new(16));
+        transactions.record_erc20_withdrawal_request(first_request.clone());
+        transactions.record_erc20_withdrawal_request(second_request.clone());
+
+        transactions.record_expired_erc20_withdrawal_request(LedgerBurnIndex::new(15));
+
+        assert_eq!(
+            transactions.erc20_withdrawal_requests_batch(5),
+            vec![second_request]
+        );
+        assert_eq!(
+            transactions.get_reimbursement_requests(),
+            vec![ReimbursementRequest {
+                withdrawal_id: LedgerBurnIndex::new(15),
+                reimbursed_amount: first_request.max_transaction_fee,
+                to: first_request.from,
+                to_subaccount: first_request.from_subaccount.clone(),
+                transaction_hash: None,
+            }]
+        );
+        assert_eq!(
+            transactions.get_erc20_reimbursement_requests(),
+            vec![Erc20ReimbursementRequest {
+                withdrawal_id: LedgerBurnIndex::new(15),
+                ckerc20_ledger_id: first_request.ckerc20_ledger_id,
+                reimbursed_amount: first_request.withdrawal_amount,
+                to: first_request.from,
+                to_subaccount: first_request.from_subaccount,
+                transaction_hash: None,
+            }]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "not found")]
+    fn should_panic_when_expiring_unknown_request() {
+        let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+        transactions.record_erc20_withdrawal_request(erc20_withdrawal_request_with_index(
+            LedgerBurnIndex::new(15),
+        ));
+
+        transactions.record_expired_erc20_withdrawal_request(LedgerBurnIndex::new(16));
+    }
+}
+
+mod erc20_transactions {
+    use crate::eth_rpc_client::responses::TransactionStatus;
+    use crate::lifecycle::EthereumNetwork;
+    use crate::numeric::{LedgerBurnIndex, TransactionCount, TransactionNonce, WeiPerGas};
+    use crate::state::transactions::tests::{
+        create_and_record_signed_transaction, create_and_record_transaction,
+        create_and_record_withdrawal_request, erc20_withdrawal_request_with_index,
+        sign_transaction, transaction_price, transaction_receipt,
+    };
+    use crate::state::transactions::{
+        create_erc20_transaction, Erc20WithdrawalRequest, EthTransactions, ReimbursementRequest,
+    };
+    use crate::tx::{SignedEip1559TransactionRequest, TransactionPrice};
+    use crate::ERC20_MAX_TRANSACTION_FEE_MULTIPLIER;
+
+    /// Records an ERC-20 withdrawal request paying twice the fee of `transaction_price`,
+    /// and the corresponding sent transaction.
+    fn send_erc20_transaction(
+        transactions: &mut EthTransactions,
+        ledger_burn_index: LedgerBurnIndex,
+        transaction_price: TransactionPrice,
+    ) -> (Erc20WithdrawalRequest, SignedEip1559TransactionRequest) {
+        let request = erc20_withdrawal_request_with_index(ledger_burn_index);
+        let tx = create_erc20_transaction(
+            &request,
+            transactions.next_transaction_nonce(),
+            transaction_price,
+            EthereumNetwork::Sepolia,
+        )
+        .unwrap();
+        let request = Erc20WithdrawalRequest {
+            max_transaction_fee: tx
+                .transaction_price()
+                .max_transaction_fee()
+                .checked_mul(ERC20_MAX_TRANSACTION_FEE_MULTIPLIER)
+                .unwrap(),
+            ..request
+        };
+        transactions.record_erc20_withdrawal_request(request.clone());
+        transactions.record_created_transaction(ledger_burn_index, tx.clone());
+        let signed_tx = create_and_record_signed_transaction(transactions, tx);
+        (request, signed_tx)
+    }
+
+    fn price_times(price: &TransactionPrice, factor: u8) -> TransactionPrice {
+        TransactionPrice {
+            max_fee_per_gas: price.max_fee_per_gas.checked_mul(factor).unwrap(),
+            max_priority_fee_per_gas: price.max_priority_fee_per_gas.checked_mul(factor).unwrap(),
+            ..price.clone()
+        }
+    }
+
+    #[test]
+    fn should_resubmit_with_fee_capped_at_paid_fee_when_gas_price_rises() {
+        let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+        let initial_price = transaction_price();
+        let (request, signed_tx) = send_erc20_transaction(
+            &mut transactions,
+            LedgerBurnIndex::new(15),
+            initial_price.clone(),
+        );
+        let last_tx_price = signed_tx.transaction().transaction_price();
+
+        // The gas price triples after the transaction was sent.
+        let resubmitted_txs = transactions
+            .create_resubmit_transactions(TransactionCount::ZERO, price_times(&initial_price, 3));
+
+        let (withdrawal_id, new_tx) = match resubmitted_txs.as_slice() {
+            [Ok((withdrawal_id, new_tx))] => (*withdrawal_id, new_tx.clone()),
+            _ => panic!("expected exactly one resubmitted transaction, got {resubmitted_txs:?}"),
+        };
+        assert_eq!(withdrawal_id, LedgerBurnIndex::new(15));
+        let new_tx_price = new_tx.transaction_price();
+        assert!(new_tx_price.max_transaction_fee() <= request.max_transaction_fee);
+        assert!(
+            new_tx_price.max_fee_per_gas
+                >= last_tx_price
+                    .clone()
+                    .increase_by_10_percent()
+                    .max_fee_per_gas
+        );
+        assert!(
+            new_tx_price.max_priority_fee_per_gas
+                >= last_tx_price
+                    .increase_by_10_percent()
+                    .max_priority_fee_per_gas
+        );
+        let capped_max_fee_per_gas: WeiPerGas = request
+            .max_transaction_fee
+            .checked_div_floor(new_tx_price.gas_limit.into_inner())
+            .unwrap()
+            .change_units();
+        assert_eq!(new_tx_price.max_fee_per_gas, capped_max_fee_per_gas);
+        transactions.record_resubmit_transaction(new_tx);
+    }
+
+    #[test]
+    fn should_not_block_following_transactions_once_fee_cap_is_reached() {
+        let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+        let initial_price = transaction_price();
+        let (_request, _signed_tx) = send_erc20_transaction(
+            &mut transactions,
+            LedgerBurnIndex::new(15),
+            initial_price.clone(),
+        );
+        let eth_request =
+            create_and_record_withdrawal_request(&mut transactions, LedgerBurnIndex::new(16));
+        let eth_tx =
+            create_and_record_transaction(&mut transactions, eth_request, transaction_price());
+        create_and_record_signed_transaction(&mut transactions, eth_tx);
+
+        // First rise: the ERC-20 transaction is resubmitted at the capped fee.
+        let resubmitted_txs = transactions
+            .create_resubmit_transactions(TransactionCount::ZERO, price_times(&initial_price, 3));
+        assert_eq!(resubmitted_txs.len(), 2);
+        let capped_tx = resubmitted_txs[0].clone().unwrap().1;
+        transactions.record_resubmit_transaction(capped_tx.clone());
+        transactions.record_signed_transaction(sign_transaction(capped_tx));
+
+        // Second rise: the ERC-20 transaction can no longer be replaced and is re-sent as is,
+        // while the following ETH transaction is still resubmitted.
+        let resubmitted_txs = transactions
+            .create_resubmit_transactions(TransactionCount::ZERO, price_times(&initial_price, 5));
+        assert_eq!(resubmitted_txs.len(), 1);
+        let (withdrawal_id, _new_tx) = resubmitted_txs[0].clone().unwrap();
+        assert_eq!(withdrawal_id, LedgerBurnIndex::new(16));
+    }
+
+    #[test]
+    fn should_refund_unspent_transaction_fee_on_finalization() {
+        let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+        let (request, signed_tx) = send_erc20_transaction(
+            &mut transactions,
+            LedgerBurnIndex::new(15),
+            transaction_price(),
+        );
+        let receipt = transaction_receipt(&signed_tx, TransactionStatus::Success);
+        let effective_tx_fee = receipt.effective_transaction_fee();
+
+        transactions.record_finalized_transaction(LedgerBurnIndex::new(15), receipt.clone());
+
+        assert_eq!(
+            transactions.get_reimbursement_requests(),
+            vec![ReimbursementRequest {
+                withdrawal_id: LedgerBurnIndex::new(15),
+                reimbursed_amount: request
+                    .max_transaction_fee
+                    .checked_sub(effective_tx_fee)
+                    .unwrap(),
+                to: request.from,
+                to_subaccount: request.from_subaccount,
+                transaction_hash: Some(receipt.transaction_hash),
+            }]
+        );
+        assert!(transactions.get_erc20_reimbursement_requests().is_empty());
+    }
+}
+
 mod create_transaction {
     use crate::lifecycle::EthereumNetwork;
     use crate::numeric::{LedgerBurnIndex, TransactionNonce, Wei};
@@ -1888,6 +2117,26 @@ fn withdrawal_request_with_index(ledger_burn_index: LedgerBurnIndex) -> EthWithd
     }
 }
 
+fn erc20_withdrawal_request_with_index(
+    cketh_ledger_burn_index: LedgerBurnIndex,
+) -> Erc20WithdrawalRequest {
+    use std::str::FromStr;
+    const SEPOLIA_USDC_ADDRESS: &str = "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238";
+    const CKSEPOLIA_USDC_LEDGER_ID: &str = "mxzaz-hqaaa-aaaar-qaada-cai";
+    Erc20WithdrawalRequest {
+        max_transaction_fee: Wei::new(30_000_000_000_000_000),
+        withdrawal_amount: Erc20Value::new(1_000_000),
+        destination: Address::from_str(DEFAULT_RECIPIENT_ADDRESS).unwrap(),
+        cketh_ledger_burn_index,
+        erc20_contract_address: Address::from_str(SEPOLIA_USDC_ADDRESS).unwrap(),
+        ckerc20_ledger_id: candid::Principal::from_str(CKSEPOLIA_USDC_LEDGER_ID).unwrap(),
+        ckerc20_ledger_burn_index: LedgerBurnIndex::new(7),
+        from: candid::Principal::from_str(DEFAULT_PRINCIPAL).unwrap(),
+        from_subaccount: Some(Subaccount(DEFAULT_SUBACCOUNT)),
+        created_at: DEFAULT_CREATED_AT,
+    }
+}
+
 fn signed_transaction_with_nonce(nonce: TransactionNonce) -> SignedEip1559TransactionRequest {
     SignedEip1559TransactionRequest::from((
         eip_1559_transaction_request_with_nonce(nonce),
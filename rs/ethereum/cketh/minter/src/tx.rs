@@ -416,6 +416,21 @@ impl TransactionPrice {
         }
     }
 
+    /// Lowers `max_fee_per_gas` (and `max_priority_fee_per_gas` if needed) so that
+    /// the max transaction fee does not exceed `max_transaction_fee`.
+    pub fn cap_max_transaction_fee(self, max_transaction_fee: Wei) -> Self {
+        let max_fee_per_gas_cap: WeiPerGas = max_transaction_fee
+            .checked_div_floor(self.gas_limit.into_inner())
+            .expect("BUG: gas limit must be non-zero")
+            .change_units();
+        let max_fee_per_gas = self.max_fee_per_gas.min(max_fee_per_gas_cap);
+        Self {
+            gas_limit: self.gas_limit,
+            max_fee_per_gas,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas.min(max_fee_per_gas),
+        }
+    }
+
     /// Returns true if the new transaction fee is higher than the current one
     pub fn is_fee_increased(&self, new: &Self) -> bool {
         self.max_fee_per_gas < new.max_fee_per_gas
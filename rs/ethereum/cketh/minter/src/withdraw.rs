@@ -11,7 +11,8 @@ use crate::logs::{DEBUG, INFO};
 use crate::numeric::{LedgerBurnIndex, LedgerMintIndex, TransactionCount};
 use crate::state::audit::{process_event, EventType};
 use crate::state::transactions::{
-    create_transaction, CreateTransactionError, Reimbursed, ReimbursementRequest,
+    create_erc20_transaction, create_transaction, CreateTransactionError, Erc20Reimbursed,
+    Erc20ReimbursementRequest, Reimbursed, ReimbursementRequest,
 };
 use crate::state::{mutate_state, read_state, State, TaskType};
 use crate::tx::{estimate_transaction_price, TransactionPrice};
@@ -37,6 +38,11 @@ pub async fn process_reimbursement() {
         }
     };
 
+    reimburse_cketh().await;
+    reimburse_ckerc20().await;
+}
+
+async fn reimburse_cketh() {
     let reimbursement_requests: Vec<ReimbursementRequest> =
         read_state(|s| s.eth_transactions.get_reimbursement_requests());
     if reimbursement_requests.is_empty() {
@@ -104,6 +110,75 @@ pub async fn process_reimbursement() {
     }
 }
 
+async fn reimburse_ckerc20() {
+    let reimbursement_requests: Vec<Erc20ReimbursementRequest> =
+        read_state(|s| s.eth_transactions.get_erc20_reimbursement_requests());
+    if reimbursement_requests.is_empty() {
+        return;
+    }
+
+    let mut error_count = 0;
+
+    for reimbursement_request in reimbursement_requests {
+        let ledger_canister_id = reimbursement_request.ckerc20_ledger_id;
+        let client = ICRC1Client {
+            runtime: CdkRuntime,
+            ledger_canister_id,
+        };
+        let args = TransferArg {
+            from_subaccount: None,
+            to: Account {
+                owner: reimbursement_request.to,
+                subaccount: reimbursement_request
+                    .to_subaccount
+                    .as_ref()
+                    .map(|subaccount| subaccount.0),
+            },
+            fee: None,
+            created_at_time: None,
+            memo: Some(reimbursement_request.clone().into()),
+            amount: Nat::from(reimbursement_request.reimbursed_amount),
+        };
+        let block_index = match client.transfer(args).await {
+            Ok(Ok(block_index)) => block_index
+                .0
+                .to_u64()
+                .expect("block index should fit into u64"),
+            Ok(Err(err)) => {
+                log!(INFO, "[process_reimbursement] Failed to mint ckERC20 {err}");
+                error_count += 1;
+                continue;
+            }
+            Err(err) => {
+                log!(
+                    INFO,
+                    "[process_reimbursement] Failed to send a message to the ledger ({ledger_canister_id}): {err:?}"
+                );
+                error_count += 1;
+                continue;
+            }
+        };
+        mutate_state(|s| {
+            process_event(
+                s,
+                EventType::ReimbursedErc20Withdrawal(Erc20Reimbursed {
+                    withdrawal_id: reimbursement_request.withdrawal_id,
+                    ckerc20_ledger_id: reimbursement_request.ckerc20_ledger_id,
+                    reimbursed_in_block: LedgerMintIndex::new(block_index),
+                    reimbursed_amount: reimbursement_request.reimbursed_amount,
+                    transaction_hash: reimbursement_request.transaction_hash,
+                }),
+            )
+        });
+    }
+    if error_count > 0 {
+        log!(
+            INFO,
+            "[process_reimbursement] Failed to reimburse ckERC20 to {error_count} users, retrying later."
+        );
+    }
+}
+
 pub async fn process_retrieve_eth_requests() {
     let _guard = match TimerGuard::new(TaskType::RetrieveEth) {
         Ok(guard) => guard,
@@ -256,6 +331,74 @@ fn create_transactions_batch(transaction_price: TransactionPrice) {
                 );
                 mutate_state(|s| s.eth_transactions.reschedule_withdrawal_request(request));
             }
+            Err(e @ CreateTransactionError::InsufficientTransactionFee { .. }) => {
+                log!(
+                    INFO,
+                    "[create_transactions_batch]: BUG: unexpected error {e:?} for an ETH withdrawal request. Request moved back to end of queue."
+                );
+                mutate_state(|s| s.eth_transactions.reschedule_withdrawal_request(request));
+            }
+        };
+    }
+    create_erc20_transactions_batch(transaction_price);
+}
+
+fn create_erc20_transactions_batch(transaction_price: TransactionPrice) {
+    let now_nanos = ic_cdk::api::time();
+    for request in read_state(|s| {
+        s.eth_transactions
+            .erc20_withdrawal_requests_batch(WITHDRAWAL_REQUESTS_BATCH_SIZE)
+    }) {
+        log!(
+            DEBUG,
+            "[create_erc20_transactions_batch]: processing {request:?}",
+        );
+        let ethereum_network = read_state(State::ethereum_network);
+        let nonce = read_state(|s| s.eth_transactions.next_transaction_nonce());
+        match create_erc20_transaction(&request, nonce, transaction_price.clone(), ethereum_network)
+        {
+            Ok(transaction) => {
+                log!(
+                    DEBUG,
+                    "[create_erc20_transactions_batch]: created transaction {transaction:?}",
+                );
+
+                mutate_state(|s| {
+                    process_event(
+                        s,
+                        EventType::CreatedTransaction {
+                            withdrawal_id: request.cketh_ledger_burn_index,
+                            transaction,
+                        },
+                    );
+                });
+            }
+            Err(e @ CreateTransactionError::InsufficientTransactionFee { .. })
+                if request.is_expired(now_nanos) =>
+            {
+                log!(
+                    INFO,
+                    "[create_erc20_transactions_batch]: Failed to create transaction: {e:?}. Request expired and will be reimbursed."
+                );
+                mutate_state(|s| {
+                    process_event(
+                        s,
+                        EventType::ExpiredErc20WithdrawalRequest {
+                            withdrawal_id: request.cketh_ledger_burn_index,
+                        },
+                    )
+                });
+            }
+            Err(e) => {
+                log!(
+                    INFO,
+                    "[create_erc20_transactions_batch]: Failed to create transaction: {e:?}. Request moved back to end of queue."
+                );
+                mutate_state(|s| {
+                    s.eth_transactions
+                        .reschedule_erc20_withdrawal_request(request)
+                });
+            }
         };
     }
 }
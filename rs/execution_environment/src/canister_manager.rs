@@ -1,6 +1,6 @@
 use crate::as_round_instructions;
 use crate::canister_settings::{validate_canister_settings, ValidatedCanisterSettings};
-use crate::execution::install_code::{validate_controller, OriginalContext};
+use crate::execution::install_code::{canister_layout, validate_controller, OriginalContext};
 use crate::execution::{install::execute_install, upgrade::execute_upgrade};
 use crate::execution_environment::{
     CompilationCostHandling, RoundContext, RoundCounters, RoundLimits,
@@ -9,16 +9,20 @@ use crate::{
     canister_settings::CanisterSettings,
     hypervisor::Hypervisor,
     types::{IngressResponse, Response},
-    util::GOVERNANCE_CANISTER_ID,
+    util::{GOVERNANCE_CANISTER_ID, REGISTRY_CANISTER_ID},
 };
 use ic_base_types::NumSeconds;
-use ic_config::flag_status::FlagStatus;
+use ic_config::{execution_environment::CanisterHistoryConfig, flag_status::FlagStatus};
 use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallModeV2, CanisterStatusResultV2,
-    CanisterStatusType, InstallChunkedCodeArgs, InstallCodeArgsV2, Method as Ic00Method,
-    StoredChunksReply, UploadChunkReply,
+    CanisterChangeDetails, CanisterChangeOrigin, CanisterHistoryArgs, CanisterHistoryEntry,
+    CanisterHistoryResponse, CanisterInstallModeV2, CanisterMethodStatsReply,
+    CanisterMigrationData, CanisterMigrationMetadata, CanisterMigrationSegment,
+    CanisterSettingsArgsBuilder, CanisterStatusResultV2, CanisterStatusType,
+    InstallChunkedCodeArgs, InstallCodeArgsV2, Method as Ic00Method, MethodStatsRecord,
+    MigratedCode, MigratedGlobal, MigratedPage, MigratedPages, MigratedStoredChunk,
+    MigratedWasmChunk, StoredChunksReply, UploadChunkReply,
 };
 use ic_interfaces::execution_environment::{
     CanisterOutOfCyclesError, HypervisorError, IngressHistoryWriter, SubnetAvailableMemory,
@@ -28,15 +32,19 @@ use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::system_state::ReservationError;
 use ic_replicated_state::{
-    canister_state::system_state::{
-        wasm_chunk_store::{self, WasmChunkStore},
-        CyclesUseCase,
+    canister_state::{
+        system_state::{
+            wasm_chunk_store::{self, WasmChunkStore},
+            CanisterHistory, CyclesUseCase,
+        },
+        WASM_PAGE_SIZE_IN_BYTES,
     },
     metadata_state::subnet_call_context_manager::InstallCodeCallId,
-    page_map::PageAllocatorFileDescriptor,
-    CallOrigin, CanisterState, CanisterStatus, NetworkTopology, ReplicatedState, SchedulerState,
-    SystemState,
+    page_map::{PageAllocatorFileDescriptor, PAGE_SIZE},
+    CallOrigin, CanisterState, CanisterStatus, Global, Memory, NetworkTopology, NumWasmPages,
+    PageIndex, PageMap, ReplicatedState, SchedulerState, SystemState,
 };
+use ic_sys::PageBytes;
 use ic_system_api::ExecutionParameters;
 use ic_types::{
     ingress::{IngressState, IngressStatus},
@@ -116,6 +124,7 @@ pub(crate) struct CanisterMgrConfig {
     rate_limiting_of_heap_delta: FlagStatus,
     heap_delta_rate_limit: NumBytes,
     upload_wasm_chunk_instructions: NumInstructions,
+    canister_history: CanisterHistoryConfig,
 }
 
 impl CanisterMgrConfig {
@@ -134,6 +143,7 @@ impl CanisterMgrConfig {
         rate_limiting_of_heap_delta: FlagStatus,
         heap_delta_rate_limit: NumBytes,
         upload_wasm_chunk_instructions: NumInstructions,
+        canister_history: CanisterHistoryConfig,
     ) -> Self {
         Self {
             subnet_memory_capacity,
@@ -149,6 +159,7 @@ impl CanisterMgrConfig {
             rate_limiting_of_heap_delta,
             heap_delta_rate_limit,
             upload_wasm_chunk_instructions,
+            canister_history,
         }
     }
 }
@@ -385,7 +396,13 @@ impl CanisterManager {
             | Ok(Ic00Method::BitcoinSendTransaction)
             | Ok(Ic00Method::BitcoinSendTransactionInternal)
             | Ok(Ic00Method::BitcoinGetCurrentFeePercentiles)
-            | Ok(Ic00Method::NodeMetricsHistory) => Err(UserError::new(
+            | Ok(Ic00Method::NodeMetricsHistory)
+            // Canister migration is driven by the registry canister.
+            | Ok(Ic00Method::ReadCanisterMigrationData)
+            | Ok(Ic00Method::WriteCanisterMigrationData)
+            | Ok(Ic00Method::RemoveMigratedCanister)
+            | Ok(Ic00Method::FinishCanisterMigration)
+            | Ok(Ic00Method::AbortCanisterMigration) => Err(UserError::new(
                 ErrorCode::CanisterRejectedMessage,
                 format!("Only canisters can call ic00 method {}", method_name),
             )),
@@ -402,6 +419,10 @@ impl CanisterManager {
             | Ok(Ic00Method::UpdateSettings)
             | Ok(Ic00Method::InstallCode)
             | Ok(Ic00Method::InstallChunkedCode)
+            | Ok(Ic00Method::AbortInstallCode)
+            | Ok(Ic00Method::CanisterMethodStats)
+            | Ok(Ic00Method::ResetCanisterMethodStats)
+            | Ok(Ic00Method::CanisterHistory)
             | Ok(Ic00Method::UploadChunk)
             | Ok(Ic00Method::StoredChunks)
             | Ok(Ic00Method::DeleteChunks)
@@ -491,6 +512,47 @@ impl CanisterManager {
         )
     }
 
+    /// Validates the automatic top-up settings against the replicated state:
+    /// the funding canister must be hosted by this subnet and the sender must
+    /// be one of its controllers. Disabling automatic top-ups (i.e. setting
+    /// the amount to zero) is always allowed, as is funding from a cycles
+    /// ledger account, where the ledger checks the approval of the owner.
+    ///
+    /// Either way, every top-up additionally requires the consent of the
+    /// funding source at the time of the top-up.
+    pub(crate) fn validate_auto_top_up(
+        &self,
+        settings: &CanisterSettings,
+        sender: PrincipalId,
+        state: &ReplicatedState,
+    ) -> Result<(), CanisterManagerError> {
+        let auto_top_up = match settings.auto_top_up() {
+            Some(auto_top_up)
+                if auto_top_up.amount > Cycles::zero() && auto_top_up.funding_account.is_none() =>
+            {
+                auto_top_up
+            }
+            _ => return Ok(()),
+        };
+        let funding_canister = state
+            .canister_state(&auto_top_up.funding_canister)
+            .ok_or_else(|| CanisterManagerError::InvalidSettings {
+                message: format!(
+                    "Funding canister {} is not hosted by subnet {}",
+                    auto_top_up.funding_canister, self.config.own_subnet_id
+                ),
+            })?;
+        if !funding_canister.controllers().contains(&sender) {
+            return Err(CanisterManagerError::InvalidSettings {
+                message: format!(
+                    "Only controllers of funding canister {} can use it for automatic top-ups",
+                    auto_top_up.funding_canister
+                ),
+            });
+        }
+        Ok(())
+    }
+
     /// Applies the requested settings on the canister.
     /// Note: Called only after validating the settings.
     /// Keep this function in sync with `validate_canister_settings()`.
@@ -544,6 +606,20 @@ impl CanisterManager {
         if let Some(freezing_threshold) = settings.freezing_threshold() {
             canister.system_state.freeze_threshold = freezing_threshold;
         }
+        if let Some(query_cache_max_staleness) = settings.query_cache_max_staleness() {
+            canister.system_state.query_cache_max_staleness = query_cache_max_staleness;
+        }
+        if let Some(auto_top_up) = settings.auto_top_up() {
+            // An amount of zero disables automatic top-ups.
+            canister.system_state.auto_top_up =
+                (auto_top_up.amount > Cycles::zero()).then_some(auto_top_up);
+        }
+        if let Some(extended_install_code_limit) = settings.extended_install_code_limit() {
+            canister.system_state.extended_install_code_limit = extended_install_code_limit;
+        }
+        if let Some(beneficiaries) = settings.auto_top_up_beneficiaries() {
+            canister.system_state.auto_top_up_beneficiaries = beneficiaries;
+        }
     }
 
     /// Tries to apply the requested settings on the canister identified by
@@ -561,6 +637,7 @@ impl CanisterManager {
         let sender = origin.origin();
 
         validate_controller(canister, &sender)?;
+        self.validate_canister_is_not_migrating(canister)?;
 
         let validated_settings = validate_canister_settings(
             settings,
@@ -583,6 +660,18 @@ impl CanisterManager {
 
         let is_controllers_change =
             validated_settings.controller().is_some() || validated_settings.controllers().is_some();
+        let settings_change = CanisterChangeDetails::settings_change(
+            validated_settings.freezing_threshold().map(|t| t.get()),
+            validated_settings
+                .compute_allocation()
+                .map(|a| a.as_percent()),
+            validated_settings
+                .memory_allocation()
+                .map(|a| a.bytes().get()),
+        );
+        let is_settings_change = validated_settings.freezing_threshold().is_some()
+            || validated_settings.compute_allocation().is_some()
+            || validated_settings.memory_allocation().is_some();
 
         let old_usage = canister.memory_usage();
         let old_mem = canister.memory_allocation().allocated_bytes(old_usage);
@@ -618,6 +707,14 @@ impl CanisterManager {
         }
 
         canister.system_state.canister_version += 1;
+        if is_settings_change {
+            canister.system_state.add_audit_change(
+                timestamp_nanos,
+                origin.clone(),
+                settings_change,
+                self.config.canister_history.max_settings_changes,
+            );
+        }
         if is_controllers_change {
             let new_controllers = canister.system_state.controllers.iter().copied().collect();
             canister.system_state.add_canister_change(
@@ -677,6 +774,10 @@ impl CanisterManager {
             .reserved_cycles_limit
             .get_or_insert_with(|| self.cycles_account_manager.default_reserved_balance_limit());
 
+        if let Err(err) = self.validate_auto_top_up(&settings, origin.origin(), state) {
+            return (Err(err), cycles);
+        }
+
         // Validate settings before `create_canister_helper` applies them
         match self.validate_settings_for_canister_creation(
             settings,
@@ -819,7 +920,9 @@ impl CanisterManager {
         round_counters: RoundCounters,
         subnet_size: usize,
     ) -> DtsInstallCodeResult {
-        if let Err(err) = validate_controller(&canister, &context.sender()) {
+        if let Err(err) = validate_controller(&canister, &context.sender())
+            .and_then(|()| self.validate_canister_is_not_migrating(&canister))
+        {
             return DtsInstallCodeResult::Finished {
                 canister,
                 message,
@@ -925,6 +1028,7 @@ impl CanisterManager {
         if sender != GOVERNANCE_CANISTER_ID.get() {
             validate_controller(canister, &sender)?
         }
+        self.validate_canister_is_not_migrating(canister)?;
 
         let rejects = uninstall_canister(
             &self.log,
@@ -1028,6 +1132,7 @@ impl CanisterManager {
         canister: &mut CanisterState,
     ) -> Result<Vec<StopCanisterContext>, CanisterManagerError> {
         validate_controller(canister, &sender)?;
+        self.validate_canister_is_not_migrating(canister)?;
 
         let stop_contexts = match &mut canister.system_state.status {
             CanisterStatus::Stopping { stop_contexts, .. } => std::mem::take(stop_contexts),
@@ -1081,7 +1186,7 @@ impl CanisterManager {
         let freeze_threshold = canister.system_state.freeze_threshold;
         let reserved_cycles_limit = canister.system_state.reserved_balance_limit();
 
-        Ok(CanisterStatusResultV2::new(
+        let mut status = CanisterStatusResultV2::new(
             canister.status(),
             canister
                 .execution_state
@@ -1115,7 +1220,36 @@ impl CanisterManager {
                 .scheduler_state
                 .total_query_stats
                 .egress_payload_size,
-        ))
+        )
+        .with_extended_install_code_limit(canister.system_state.extended_install_code_limit);
+        if let Some(auto_top_up) = canister.system_state.auto_top_up {
+            status = status.with_auto_top_up(
+                auto_top_up.funding_canister.get(),
+                auto_top_up.threshold.get(),
+                auto_top_up.amount.get(),
+            );
+            if let Some(account) = auto_top_up.funding_account {
+                status = status.with_auto_top_up_funding_account(account.owner, account.subaccount);
+            }
+        }
+        if let Some(progress) = canister.system_state.install_code_progress {
+            status = status.with_install_code_progress(
+                progress.start_time.as_nanos_since_unix_epoch(),
+                progress.executed_instructions.get(),
+                progress.num_restarts,
+            );
+        }
+        let queue_latency_stats = &canister.scheduler_state.queue_latency_stats;
+        if queue_latency_stats.rounds_with_input > 0 {
+            status = status.with_queue_latency(
+                queue_latency_stats.average_wait().as_nanos() as u64,
+                queue_latency_stats.max_wait_nanos,
+                queue_latency_stats.rounds_with_input,
+                queue_latency_stats.rounds_executed,
+                queue_latency_stats.scheduling_share_percent(),
+            );
+        }
+        Ok(status)
     }
 
     /// Permanently deletes a canister from `ReplicatedState`.
@@ -1148,6 +1282,7 @@ impl CanisterManager {
         validate_controller(canister_to_delete, &sender)?;
 
         self.validate_canister_is_stopped(canister_to_delete)?;
+        self.validate_canister_is_not_migrating(canister_to_delete)?;
 
         if canister_to_delete.has_input() || canister_to_delete.has_output() {
             return Err(CanisterManagerError::DeleteCanisterQueueNotEmpty(
@@ -1236,6 +1371,8 @@ impl CanisterManager {
             .reserved_cycles_limit
             .get_or_insert_with(|| self.cycles_account_manager.default_reserved_balance_limit());
 
+        self.validate_auto_top_up(&settings, sender, state)?;
+
         // Validate settings before `create_canister_helper` applies them
         // No creation fee applied.
         match self.validate_settings_for_canister_creation(
@@ -1418,6 +1555,20 @@ impl CanisterManager {
         Ok(())
     }
 
+    /// Rejects changes to a canister whose state is being copied to another
+    /// subnet, so that the target subnet receives a consistent copy.
+    fn validate_canister_is_not_migrating(
+        &self,
+        canister: &CanisterState,
+    ) -> Result<(), CanisterManagerError> {
+        if canister.system_state.migrating {
+            return Err(CanisterManagerError::CanisterMigrating(
+                canister.canister_id(),
+            ));
+        }
+        Ok(())
+    }
+
     /// Generates a new canister ID.
     ///
     /// Returns `Err` if the subnet can generate no more canister IDs; or a canister
@@ -1479,6 +1630,7 @@ impl CanisterManager {
         if sender != canister.system_state.canister_id.into() {
             validate_controller(canister, &sender)?
         }
+        self.validate_canister_is_not_migrating(canister)?;
 
         canister
             .system_state
@@ -1658,6 +1810,7 @@ impl CanisterManager {
         if sender != canister.system_state.canister_id.into() {
             validate_controller(canister, &sender)?
         }
+        self.validate_canister_is_not_migrating(canister)?;
         canister.system_state.wasm_chunk_store = WasmChunkStore::new(Arc::clone(&self.fd_factory));
         Ok(())
     }
@@ -1685,6 +1838,569 @@ impl CanisterManager {
             .collect();
         Ok(StoredChunksReply(keys))
     }
+
+    /// Returns the execution statistics of the methods of the canister.
+    pub(crate) fn canister_method_stats(
+        &self,
+        sender: PrincipalId,
+        canister: &CanisterState,
+    ) -> Result<CanisterMethodStatsReply, CanisterManagerError> {
+        validate_controller(canister, &sender)?;
+
+        let method_stats = canister
+            .system_state
+            .method_stats
+            .iter()
+            .map(|(method_name, stats)| MethodStatsRecord {
+                method_name: method_name.clone(),
+                num_calls: stats.num_calls,
+                num_traps: stats.num_traps,
+                instructions_executed: candid::Nat::from(stats.instructions_executed.get()),
+                dirty_pages: stats.dirty_pages,
+                num_responses: stats.num_responses,
+                average_latency_nanos: stats.average_latency().as_nanos() as u64,
+            })
+            .collect();
+        Ok(CanisterMethodStatsReply { method_stats })
+    }
+
+    /// Clears the execution statistics of the methods of the canister.
+    pub(crate) fn reset_canister_method_stats(
+        &self,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+    ) -> Result<(), CanisterManagerError> {
+        validate_controller(canister, &sender)?;
+        canister.system_state.method_stats.clear();
+        Ok(())
+    }
+
+    /// Returns a page of the retained canister history including the audit
+    /// changes, from the oldest to the most recent change.
+    pub(crate) fn canister_history(
+        &self,
+        sender: PrincipalId,
+        canister: &CanisterState,
+        args: &CanisterHistoryArgs,
+    ) -> Result<CanisterHistoryResponse, CanisterManagerError> {
+        validate_controller(canister, &sender)?;
+
+        let history = canister.system_state.get_canister_history();
+        let num_retained_changes = history.get_num_retained_changes() as u64;
+        let max_results = args
+            .max_results()
+            .unwrap_or(MAX_CANISTER_HISTORY_PAGE_SIZE)
+            .min(MAX_CANISTER_HISTORY_PAGE_SIZE);
+        // Pages are addressed by sequence number rather than by position, so
+        // that changes dropped between two calls do not shift the next page.
+        let mut remaining = history.get_changes_since(args.start().unwrap_or(0));
+        let changes: Vec<_> = remaining
+            .by_ref()
+            .take(max_results as usize)
+            .map(|(sequence_number, change)| {
+                CanisterHistoryEntry::new(sequence_number, change.as_ref().clone())
+            })
+            .collect();
+        let next_start = match (changes.last(), remaining.next()) {
+            (_, None) => None,
+            (Some(last), Some(_)) => Some(last.sequence_number() + 1),
+            (None, Some((sequence_number, _))) => Some(sequence_number),
+        };
+        Ok(CanisterHistoryResponse::new(
+            history.get_total_num_changes() + history.get_total_num_audit_changes(),
+            num_retained_changes,
+            changes,
+            next_start,
+        ))
+    }
+
+    /// Only the registry canister may use the canister migration API. Other
+    /// senders get the same error as for any other private method.
+    fn validate_canister_migration_sender(
+        &self,
+        sender: PrincipalId,
+    ) -> Result<(), CanisterManagerError> {
+        if sender != REGISTRY_CANISTER_ID.get() {
+            return Err(CanisterManagerError::SenderNotInWhitelist(sender));
+        }
+        Ok(())
+    }
+
+    /// Returns whether the routing table currently assigns the canister to
+    /// this subnet. A canister is written to the target subnet only before it
+    /// is rerouted, and removed from the source subnet only after that.
+    fn is_hosted_by_own_subnet(&self, state: &ReplicatedState, canister_id: CanisterId) -> bool {
+        state
+            .metadata
+            .network_topology
+            .routing_table
+            .route(canister_id.get())
+            == Some(self.config.own_subnet_id)
+    }
+
+    /// Reads a part of the state of a stopped canister that is migrated to
+    /// another subnet.
+    ///
+    /// The first read locks the canister: until the canister is removed from
+    /// this subnet or the migration is aborted, the canister cannot be
+    /// started and its code, settings and chunk store cannot be changed.
+    pub(crate) fn read_canister_migration_data(
+        &self,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        segment: CanisterMigrationSegment,
+    ) -> Result<CanisterMigrationData, CanisterManagerError> {
+        self.validate_canister_migration_sender(sender)?;
+        let canister_id = canister.canister_id();
+        if canister.status() != CanisterStatusType::Stopped {
+            return Err(CanisterManagerError::CanisterMigration {
+                canister_id,
+                message: "the canister must be stopped".to_string(),
+            });
+        }
+        canister.system_state.migrating = true;
+
+        let execution_state = canister.execution_state.as_ref();
+        let no_code = || CanisterManagerError::CanisterMigration {
+            canister_id,
+            message: "the canister has no code installed".to_string(),
+        };
+        match segment {
+            CanisterMigrationSegment::Metadata => Ok(CanisterMigrationData::Metadata(
+                migration_metadata(canister),
+            )),
+            CanisterMigrationSegment::WasmModule { offset } => {
+                let module = execution_state
+                    .ok_or_else(no_code)?
+                    .wasm_binary
+                    .binary
+                    .as_slice();
+                let start = offset.min(module.len() as u64) as usize;
+                let end = module
+                    .len()
+                    .min(start + wasm_chunk_store::chunk_size().get() as usize);
+                Ok(CanisterMigrationData::WasmModule(MigratedWasmChunk {
+                    offset,
+                    chunk: module[start..end].to_vec(),
+                }))
+            }
+            CanisterMigrationSegment::WasmMemory { start_page } => {
+                let memory = &execution_state.ok_or_else(no_code)?.wasm_memory;
+                Ok(CanisterMigrationData::WasmMemory(read_migrated_pages(
+                    &memory.page_map,
+                    start_page,
+                )))
+            }
+            CanisterMigrationSegment::StableMemory { start_page } => {
+                let memory = &execution_state.ok_or_else(no_code)?.stable_memory;
+                Ok(CanisterMigrationData::StableMemory(read_migrated_pages(
+                    &memory.page_map,
+                    start_page,
+                )))
+            }
+            CanisterMigrationSegment::WasmChunkStore { index } => {
+                let store = &canister.system_state.wasm_chunk_store;
+                let mut hashes = store.keys().skip(index as usize);
+                let chunk = hashes
+                    .next()
+                    .and_then(|hash| store.get_chunk_data(hash))
+                    .map(|pages| pages.flatten().copied().collect())
+                    .unwrap_or_default();
+                Ok(CanisterMigrationData::WasmChunkStore(MigratedStoredChunk {
+                    index,
+                    chunk,
+                    next_index: hashes.next().map(|_| index + 1),
+                }))
+            }
+        }
+    }
+
+    /// Returns the canister that receives the migrated state on the target
+    /// subnet, creating a stopped canister without cycles if it does not exist
+    /// yet.
+    fn migrated_canister_mut<'a>(
+        &self,
+        canister_id: CanisterId,
+        state: &'a mut ReplicatedState,
+    ) -> Result<&'a mut CanisterState, CanisterManagerError> {
+        if self.is_hosted_by_own_subnet(state, canister_id) {
+            return Err(CanisterManagerError::CanisterMigration {
+                canister_id,
+                message: format!(
+                    "the canister is already hosted by subnet {}",
+                    self.config.own_subnet_id
+                ),
+            });
+        }
+        if state.canister_state(&canister_id).is_none() {
+            let mut system_state = SystemState::new_running(
+                canister_id,
+                REGISTRY_CANISTER_ID.get(),
+                Cycles::zero(),
+                self.config.default_freeze_threshold,
+                Arc::clone(&self.fd_factory),
+            );
+            system_state.status = CanisterStatus::Stopped;
+            let scheduler_state = SchedulerState::new(state.metadata.batch_time);
+            state.put_canister_state(CanisterState::new(system_state, None, scheduler_state));
+        }
+        Ok(state.canister_state_mut(&canister_id).unwrap())
+    }
+
+    /// Stores a chunk in the chunk store of a migrated canister. Before the
+    /// metadata arrives, these are the chunks of the Wasm module, which is
+    /// assembled from them. Afterwards, these are the contents of the chunk
+    /// store of the canister.
+    pub(crate) fn write_migrated_wasm_chunk(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        chunk: &[u8],
+        state: &mut ReplicatedState,
+    ) -> Result<NumBytes, CanisterManagerError> {
+        self.validate_canister_migration_sender(sender)?;
+        let canister = self.migrated_canister_mut(canister_id, state)?;
+        canister
+            .system_state
+            .wasm_chunk_store
+            .insert_chunk(chunk)
+            .map_err(|err| CanisterManagerError::CanisterMigration {
+                canister_id,
+                message: err,
+            })?;
+        Ok(wasm_chunk_store::chunk_size())
+    }
+
+    /// Applies the metadata of a migrated canister. If the canister has code,
+    /// its Wasm module is assembled from the previously written chunks and a
+    /// new execution state is created with empty memories of the original
+    /// sizes.
+    pub(crate) fn write_migrated_metadata(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        settings: CanisterSettings,
+        metadata: CanisterMigrationMetadata,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+    ) -> Result<(), CanisterManagerError> {
+        self.validate_canister_migration_sender(sender)?;
+        let canister = self.migrated_canister_mut(canister_id, state)?;
+        let old_mem = canister
+            .memory_allocation()
+            .allocated_bytes(canister.memory_usage());
+
+        // The cycles of the canister are transferred only once it has been
+        // rerouted, so the freezing threshold cannot be checked here. The
+        // settings were valid on the source subnet, so only the capacity of
+        // this subnet is validated.
+        let validated_settings = validate_canister_settings(
+            settings,
+            canister.memory_usage(),
+            canister.message_memory_usage(),
+            canister.memory_allocation(),
+            &round_limits.subnet_available_memory,
+            &ResourceSaturation::default(),
+            canister.compute_allocation(),
+            round_limits.compute_allocation_used,
+            self.config.compute_capacity,
+            self.config.max_controllers,
+            canister.system_state.freeze_threshold,
+            Cycles::new(u128::MAX),
+            &self.cycles_account_manager,
+            subnet_size,
+            canister.system_state.reserved_balance(),
+            None,
+        )?;
+        let old_compute_allocation = canister.scheduler_state.compute_allocation.as_percent();
+        self.do_update_settings(validated_settings, canister);
+        round_limits.compute_allocation_used = round_limits.compute_allocation_used.saturating_add(
+            canister
+                .scheduler_state
+                .compute_allocation
+                .as_percent()
+                .saturating_sub(old_compute_allocation),
+        );
+
+        if let Some(code) = metadata.code {
+            let mut wasm_module = Vec::with_capacity(code.wasm_module_size as usize);
+            for hash in code.chunk_hashes_list {
+                let chunk = hash
+                    .as_slice()
+                    .try_into()
+                    .ok()
+                    .and_then(|hash: [u8; 32]| {
+                        canister.system_state.wasm_chunk_store.get_chunk_data(&hash)
+                    })
+                    .ok_or_else(|| CanisterManagerError::CanisterMigration {
+                        canister_id,
+                        message: format!(
+                            "chunk {} of the Wasm module is missing",
+                            hex::encode(&hash)
+                        ),
+                    })?;
+                for page in chunk {
+                    wasm_module.extend_from_slice(page);
+                }
+            }
+            if ic_crypto_sha2::Sha256::hash(&wasm_module)[..] != code.wasm_module_hash[..] {
+                return Err(CanisterManagerError::CanisterMigration {
+                    canister_id,
+                    message: "the hash of the assembled Wasm module does not match".to_string(),
+                });
+            }
+
+            let layout = canister_layout(&PathBuf::from("NOT_USED"), &canister_id);
+            let (_, result) = self.hypervisor.create_execution_state(
+                CanisterModule::new(wasm_module),
+                layout.raw_path(),
+                canister_id,
+                round_limits,
+                CompilationCostHandling::CountFullAmount,
+            );
+            let mut execution_state =
+                result.map_err(|err| CanisterManagerError::Hypervisor(canister_id, err))?;
+            execution_state.exported_globals = code
+                .exported_globals
+                .into_iter()
+                .map(|global| match global {
+                    MigratedGlobal::I32(value) => Global::I32(value),
+                    MigratedGlobal::I64(value) => Global::I64(value),
+                    MigratedGlobal::F32(value) => Global::F32(value),
+                    MigratedGlobal::F64(value) => Global::F64(value),
+                })
+                .collect();
+            // The memories are filled with the migrated pages afterwards, so
+            // they must not contain the data segments of the module.
+            execution_state.wasm_memory = Memory::new(
+                PageMap::new(Arc::clone(&self.fd_factory)),
+                NumWasmPages::new(code.wasm_memory_size as usize),
+            );
+            execution_state.stable_memory = Memory::new(
+                PageMap::new(Arc::clone(&self.fd_factory)),
+                NumWasmPages::new(code.stable_memory_size as usize),
+            );
+            canister.execution_state = Some(execution_state);
+            canister.system_state.wasm_chunk_store =
+                WasmChunkStore::new(Arc::clone(&self.fd_factory));
+        }
+
+        canister.system_state.certified_data = metadata.certified_data;
+        canister.system_state.canister_version = metadata.canister_version;
+        canister.system_state.global_timer =
+            CanisterTimer::from_nanos_since_unix_epoch(metadata.global_timer_nanos);
+        canister
+            .system_state
+            .set_canister_history(CanisterHistory::new(
+                metadata.recent_changes,
+                metadata.total_num_changes,
+            ));
+
+        let new_mem = canister
+            .memory_allocation()
+            .allocated_bytes(canister.memory_usage());
+        if new_mem > old_mem {
+            let requested = new_mem - old_mem;
+            round_limits
+                .subnet_available_memory
+                .try_decrement(requested, NumBytes::from(0), NumBytes::from(0))
+                .map_err(
+                    |_| CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                        requested,
+                        available: NumBytes::from(
+                            round_limits
+                                .subnet_available_memory
+                                .get_execution_memory()
+                                .max(0) as u64,
+                        ),
+                    },
+                )?;
+        }
+        Ok(())
+    }
+
+    /// Writes pages of the Wasm or stable memory of a migrated canister.
+    /// Returns the number of bytes written.
+    pub(crate) fn write_migrated_pages(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        pages: MigratedPages,
+        stable_memory: bool,
+        state: &mut ReplicatedState,
+    ) -> Result<NumBytes, CanisterManagerError> {
+        self.validate_canister_migration_sender(sender)?;
+        let canister = self.migrated_canister_mut(canister_id, state)?;
+        let execution_state = canister.execution_state.as_mut().ok_or_else(|| {
+            CanisterManagerError::CanisterMigration {
+                canister_id,
+                message: "the metadata must be written before the memories".to_string(),
+            }
+        })?;
+        let memory = if stable_memory {
+            &mut execution_state.stable_memory
+        } else {
+            &mut execution_state.wasm_memory
+        };
+
+        let memory_bytes = (memory.size.get() * WASM_PAGE_SIZE_IN_BYTES) as u64;
+        let mut updates = Vec::with_capacity(pages.pages.len());
+        for page in &pages.pages {
+            let contents: &PageBytes = page.contents.as_slice().try_into().map_err(|_| {
+                CanisterManagerError::CanisterMigration {
+                    canister_id,
+                    message: format!("page {} has an invalid size", page.index),
+                }
+            })?;
+            if page
+                .index
+                .saturating_add(1)
+                .saturating_mul(PAGE_SIZE as u64)
+                > memory_bytes
+            {
+                return Err(CanisterManagerError::CanisterMigration {
+                    canister_id,
+                    message: format!("page {} exceeds the memory size", page.index),
+                });
+            }
+            updates.push((PageIndex::from(page.index), contents));
+        }
+        memory.page_map.update(&updates);
+        Ok(NumBytes::from((updates.len() * PAGE_SIZE) as u64))
+    }
+
+    /// Removes a stopped canister that was migrated from this subnet, or a
+    /// partially written canister when a migration to this subnet is rolled
+    /// back. Returns the cycles of the canister minus the migration fee.
+    pub(crate) fn remove_migrated_canister(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &mut ReplicatedState,
+        subnet_size: usize,
+    ) -> Result<Cycles, CanisterManagerError> {
+        self.validate_canister_migration_sender(sender)?;
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        if self.is_hosted_by_own_subnet(state, canister_id) {
+            return Err(CanisterManagerError::CanisterMigration {
+                canister_id,
+                message: format!(
+                    "the canister is still hosted by subnet {}",
+                    self.config.own_subnet_id
+                ),
+            });
+        }
+        if canister.status() != CanisterStatusType::Stopped {
+            return Err(CanisterManagerError::CanisterMigration {
+                canister_id,
+                message: "the canister must be stopped".to_string(),
+            });
+        }
+        if canister.has_input() || canister.has_output() {
+            return Err(CanisterManagerError::CanisterMigration {
+                canister_id,
+                message: "the canister has messages in its queues".to_string(),
+            });
+        }
+
+        let canister = state.take_canister_state(&canister_id).unwrap();
+        let cycles = canister.system_state.balance() + canister.system_state.reserved_balance();
+        let fee = self
+            .cycles_account_manager
+            .canister_migration_fee(subnet_size)
+            .min(cycles);
+        // The canister is effectively created again on the target subnet, so
+        // the fee is accounted like a creation fee.
+        state
+            .metadata
+            .subnet_metrics
+            .observe_consumed_cycles_with_use_case(
+                CyclesUseCase::CanisterCreation,
+                NominalCycles::from(fee),
+            );
+        for (use_case, cycles) in canister
+            .system_state
+            .canister_metrics
+            .get_consumed_cycles_since_replica_started_by_use_cases()
+            .iter()
+        {
+            state
+                .metadata
+                .subnet_metrics
+                .observe_consumed_cycles_with_use_case(*use_case, *cycles);
+        }
+
+        info!(
+            self.log,
+            "Canister {} was removed from subnet {} after its migration, fee charged: {}.",
+            canister_id,
+            self.config.own_subnet_id,
+            fee
+        );
+        Ok(cycles - fee)
+    }
+
+    /// Credits the cycles of a migrated canister and starts it on this subnet
+    /// once the routing table assigns it to this subnet.
+    pub(crate) fn finish_canister_migration(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        cycles: Cycles,
+        state: &mut ReplicatedState,
+    ) -> Result<(), CanisterManagerError> {
+        self.validate_canister_migration_sender(sender)?;
+        if !self.is_hosted_by_own_subnet(state, canister_id) {
+            return Err(CanisterManagerError::CanisterMigration {
+                canister_id,
+                message: format!(
+                    "the canister is not hosted by subnet {} yet",
+                    self.config.own_subnet_id
+                ),
+            });
+        }
+        let canister = state
+            .canister_state_mut(&canister_id)
+            .ok_or(CanisterManagerError::CanisterNotFound(canister_id))?;
+        if canister.status() != CanisterStatusType::Stopped {
+            return Err(CanisterManagerError::CanisterMigration {
+                canister_id,
+                message: "the migration of the canister has already finished".to_string(),
+            });
+        }
+        canister
+            .system_state
+            .add_cycles(cycles, CyclesUseCase::NonConsumed);
+        canister.system_state.status = CanisterStatus::new_running();
+        Ok(())
+    }
+
+    /// Unlocks a canister whose migration from this subnet was rolled back,
+    /// so that its controllers can manage it again. The canister is left
+    /// stopped.
+    pub(crate) fn abort_canister_migration(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &mut ReplicatedState,
+    ) -> Result<(), CanisterManagerError> {
+        self.validate_canister_migration_sender(sender)?;
+        if !self.is_hosted_by_own_subnet(state, canister_id) {
+            return Err(CanisterManagerError::CanisterMigration {
+                canister_id,
+                message: format!(
+                    "the canister is no longer hosted by subnet {}",
+                    self.config.own_subnet_id
+                ),
+            });
+        }
+        let canister = state
+            .canister_state_mut(&canister_id)
+            .ok_or(CanisterManagerError::CanisterNotFound(canister_id))?;
+        canister.system_state.migrating = false;
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -1766,6 +2482,11 @@ pub(crate) enum CanisterManagerError {
     WasmChunkStoreError {
         message: String,
     },
+    CanisterMigration {
+        canister_id: CanisterId,
+        message: String,
+    },
+    CanisterMigrating(CanisterId),
 }
 
 impl From<CanisterManagerError> for UserError {
@@ -2002,6 +2723,23 @@ impl From<CanisterManagerError> for UserError {
                     )
                 )
             }
+            CanisterMigration { canister_id, message } => {
+                Self::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "Canister {} cannot be migrated: {}", canister_id, message
+                    )
+                )
+            }
+            CanisterMigrating(canister_id) => {
+                Self::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "Canister {} is being migrated to another subnet and cannot be changed until the migration finishes.",
+                        canister_id
+                    )
+                )
+            }
         }
     }
 }
@@ -2019,6 +2757,108 @@ impl From<CanisterManagerError> for RejectContext {
     }
 }
 
+/// The maximum number of canister changes returned by a single
+/// `canister_history` call.
+const MAX_CANISTER_HISTORY_PAGE_SIZE: u64 = 100;
+
+/// The maximum number of memory pages returned by a single read of a
+/// migrated canister's memory. Keeps the reply well below the message size
+/// limit.
+const MAX_MIGRATED_PAGES_PER_MESSAGE: usize = 256;
+
+/// The maximum number of memory pages that a single read of a migrated
+/// canister's memory inspects, so that sparse memories do not make the read
+/// arbitrarily expensive.
+const MAX_SCANNED_PAGES_PER_MESSAGE: u64 = 1 << 16;
+
+/// Collects the metadata of a canister that is migrated to another subnet.
+///
+/// The automatic top-up settings and beneficiaries are not migrated because
+/// a funding canister has to be hosted by the same subnet as the canisters
+/// it tops up, and a cycles ledger approval is scoped to the subnet.
+fn migration_metadata(canister: &CanisterState) -> CanisterMigrationMetadata {
+    let system_state = &canister.system_state;
+    let mut settings = CanisterSettingsArgsBuilder::new()
+        .with_controllers(system_state.controllers.iter().copied().collect())
+        .with_compute_allocation(canister.compute_allocation().as_percent())
+        .with_memory_allocation(canister.memory_allocation().bytes().get())
+        .with_freezing_threshold(system_state.freeze_threshold.get())
+        .with_query_cache_max_staleness(system_state.query_cache_max_staleness.get())
+        .with_extended_install_code_limit(system_state.extended_install_code_limit);
+    if let Some(limit) = system_state.reserved_balance_limit() {
+        settings = settings.with_reserved_cycles_limit(limit.get());
+    }
+
+    let history = system_state.get_canister_history();
+    let code = canister.execution_state.as_ref().map(|execution_state| {
+        let module = &execution_state.wasm_binary.binary;
+        MigratedCode {
+            wasm_module_hash: module.module_hash().to_vec(),
+            wasm_module_size: module.len() as u64,
+            chunk_hashes_list: module
+                .as_slice()
+                .chunks(wasm_chunk_store::chunk_size().get() as usize)
+                .map(|chunk| {
+                    serde_bytes::ByteBuf::from(ic_crypto_sha2::Sha256::hash(chunk).to_vec())
+                })
+                .collect(),
+            exported_globals: execution_state
+                .exported_globals
+                .iter()
+                .map(|global| match global {
+                    Global::I32(value) => MigratedGlobal::I32(*value),
+                    Global::I64(value) => MigratedGlobal::I64(*value),
+                    Global::F32(value) => MigratedGlobal::F32(*value),
+                    Global::F64(value) => MigratedGlobal::F64(*value),
+                })
+                .collect(),
+            wasm_memory_size: execution_state.wasm_memory.size.get() as u64,
+            stable_memory_size: execution_state.stable_memory.size.get() as u64,
+        }
+    });
+
+    CanisterMigrationMetadata {
+        settings: settings.build(),
+        certified_data: system_state.certified_data.clone(),
+        canister_version: system_state.canister_version,
+        global_timer_nanos: system_state.global_timer.to_nanos_since_unix_epoch(),
+        recent_changes: history
+            .get_all_changes()
+            .map(|change| change.as_ref().clone())
+            .collect(),
+        total_num_changes: history.get_total_num_changes(),
+        code,
+    }
+}
+
+/// Reads the non-zero pages of a migrated canister's memory starting at
+/// `start_page`. Zero pages are skipped because the memories on the target
+/// subnet start out empty.
+fn read_migrated_pages(page_map: &PageMap, start_page: u64) -> MigratedPages {
+    let num_pages = page_map.num_host_pages() as u64;
+    let end_page = num_pages.min(start_page.saturating_add(MAX_SCANNED_PAGES_PER_MESSAGE));
+    let mut pages = vec![];
+    for index in start_page..end_page {
+        let contents = page_map.get_page(PageIndex::from(index));
+        if contents.iter().any(|byte| *byte != 0) {
+            pages.push(MigratedPage {
+                index,
+                contents: contents.to_vec(),
+            });
+            if pages.len() == MAX_MIGRATED_PAGES_PER_MESSAGE {
+                return MigratedPages {
+                    pages,
+                    next_page: Some(index + 1).filter(|next_page| *next_page < num_pages),
+                };
+            }
+        }
+    }
+    MigratedPages {
+        pages,
+        next_page: Some(end_page).filter(|next_page| *next_page < num_pages),
+    }
+}
+
 /// Uninstalls a canister.
 ///
 /// See https://sdk.dfinity.org/docs/interface-spec/index.html#ic-uninstall_code
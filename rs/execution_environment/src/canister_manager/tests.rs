@@ -4,7 +4,7 @@ use crate::{
         uninstall_canister, AddCanisterChangeToHistory, CanisterManager, CanisterManagerError,
         CanisterMgrConfig, InstallCodeContext, StopCanisterResult,
     },
-    canister_settings::{CanisterSettings, CanisterSettingsBuilder},
+    canister_settings::{CanisterSettings, CanisterSettingsBuilder, MAX_AUTO_TOP_UP_BENEFICIARIES},
     execution_environment::{as_round_instructions, RoundCounters},
     hypervisor::Hypervisor,
     types::{IngressResponse, Response},
@@ -14,7 +14,9 @@ use assert_matches::assert_matches;
 use candid::Decode;
 use ic_base_types::{NumSeconds, PrincipalId};
 use ic_config::{
-    execution_environment::Config, flag_status::FlagStatus, subnet_config::SchedulerConfig,
+    execution_environment::{CanisterHistoryConfig, Config},
+    flag_status::FlagStatus,
+    subnet_config::SchedulerConfig,
 };
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
 use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
@@ -23,9 +25,9 @@ use ic_error_types::{ErrorCode, UserError};
 use ic_ic00_types::{
     CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterIdRecord,
     CanisterInstallMode, CanisterInstallModeV2, CanisterSettingsArgsBuilder,
-    CanisterStatusResultV2, CanisterStatusType, ClearChunkStoreArgs, CreateCanisterArgs, EmptyBlob,
-    InstallCodeArgsV2, Method, Payload, SkipPreUpgrade, StoredChunksArgs, StoredChunksReply,
-    UpdateSettingsArgs, UploadChunkArgs, UploadChunkReply,
+    CanisterStatusResultV2, CanisterStatusType, ClearChunkStoreArgs, CreateCanisterArgs,
+    CyclesLedgerAccountArgs, EmptyBlob, InstallCodeArgsV2, Method, Payload, SkipPreUpgrade,
+    StoredChunksArgs, StoredChunksReply, UpdateSettingsArgs, UploadChunkArgs, UploadChunkReply,
 };
 use ic_interfaces::execution_environment::{ExecutionMode, HypervisorError, SubnetAvailableMemory};
 use ic_logger::replica_logger::no_op_logger;
@@ -39,8 +41,8 @@ use ic_replicated_state::{
     page_map::{self, TestPageAllocatorFileDescriptorImpl},
     testing::CanisterQueuesTesting,
     testing::SystemStateTesting,
-    CallContextManager, CallOrigin, CanisterState, CanisterStatus, NumWasmPages, PageMap,
-    ReplicatedState, SystemState,
+    AutoTopUp, CallContextManager, CallOrigin, CanisterState, CanisterStatus, CyclesLedgerAccount,
+    NumWasmPages, PageMap, ReplicatedState, SystemState,
 };
 use ic_system_api::{ExecutionParameters, InstructionLimits};
 use ic_test_utilities::{
@@ -285,6 +287,7 @@ fn canister_manager_config(
         // 10 MiB should be enough for all the tests.
         NumBytes::from(10 * 1024 * 1024),
         SchedulerConfig::application_subnet().upload_wasm_chunk_instructions,
+        CanisterHistoryConfig::default(),
     )
 }
 
@@ -7581,3 +7584,183 @@ fn chunk_store_methods_succeed_from_canister_itself() {
         let _result = get_reply(test.ingress(uc, "update", wasm));
     }
 }
+
+#[test]
+fn update_settings_sets_auto_top_up() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let funding_canister = test
+        .universal_canister_with_cycles(Cycles::new(1_000_000_000_000))
+        .unwrap();
+    let canister_id = test
+        .universal_canister_with_cycles(Cycles::new(1_000_000_000_000))
+        .unwrap();
+
+    let payload = UpdateSettingsArgs {
+        canister_id: canister_id.get(),
+        settings: CanisterSettingsArgsBuilder::new()
+            .with_auto_top_up(funding_canister.get(), 1_000_000, 2_000_000)
+            .build(),
+        sender_canister_version: None,
+    }
+    .encode();
+    get_reply(test.subnet_message(Method::UpdateSettings, payload));
+    assert_eq!(
+        test.canister_state(canister_id).system_state.auto_top_up,
+        Some(AutoTopUp {
+            funding_canister,
+            threshold: Cycles::new(1_000_000),
+            amount: Cycles::new(2_000_000),
+            funding_account: None,
+        })
+    );
+
+    let reply = get_reply(test.canister_status(canister_id));
+    let status = CanisterStatusResultV2::decode(&reply).unwrap();
+    assert_eq!(
+        status.settings().auto_top_up().unwrap().funding_canister,
+        funding_canister.get()
+    );
+
+    // An amount of zero disables automatic top-ups.
+    let payload = UpdateSettingsArgs {
+        canister_id: canister_id.get(),
+        settings: CanisterSettingsArgsBuilder::new()
+            .with_auto_top_up(funding_canister.get(), 1_000_000, 0)
+            .build(),
+        sender_canister_version: None,
+    }
+    .encode();
+    get_reply(test.subnet_message(Method::UpdateSettings, payload));
+    assert_eq!(
+        test.canister_state(canister_id).system_state.auto_top_up,
+        None
+    );
+}
+
+#[test]
+fn update_settings_rejects_auto_top_up_from_uncontrolled_canister() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let funding_canister = test
+        .universal_canister_with_cycles(Cycles::new(1_000_000_000_000))
+        .unwrap();
+    let canister_id = test
+        .universal_canister_with_cycles(Cycles::new(1_000_000_000_000))
+        .unwrap();
+    test.canister_state_mut(funding_canister)
+        .system_state
+        .controllers = btreeset! {user_test_id(42).get()};
+
+    let payload = UpdateSettingsArgs {
+        canister_id: canister_id.get(),
+        settings: CanisterSettingsArgsBuilder::new()
+            .with_auto_top_up(funding_canister.get(), 1_000_000, 2_000_000)
+            .build(),
+        sender_canister_version: None,
+    }
+    .encode();
+    let err = test
+        .subnet_message(Method::UpdateSettings, payload)
+        .unwrap_err();
+    assert_eq!(ErrorCode::CanisterContractViolation, err.code());
+    assert_eq!(
+        test.canister_state(canister_id).system_state.auto_top_up,
+        None
+    );
+}
+
+#[test]
+fn update_settings_sets_auto_top_up_from_cycles_ledger_account() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test
+        .universal_canister_with_cycles(Cycles::new(1_000_000_000_000))
+        .unwrap();
+    // The cycles ledger is not hosted by this subnet.
+    let cycles_ledger = canister_test_id(4321);
+    let owner = user_test_id(7).get();
+
+    let payload = UpdateSettingsArgs {
+        canister_id: canister_id.get(),
+        settings: CanisterSettingsArgsBuilder::new()
+            .with_auto_top_up_from_account(
+                cycles_ledger.get(),
+                owner,
+                Some([1; 32]),
+                1_000_000,
+                2_000_000,
+            )
+            .build(),
+        sender_canister_version: None,
+    }
+    .encode();
+    get_reply(test.subnet_message(Method::UpdateSettings, payload));
+    assert_eq!(
+        test.canister_state(canister_id).system_state.auto_top_up,
+        Some(AutoTopUp {
+            funding_canister: cycles_ledger,
+            threshold: Cycles::new(1_000_000),
+            amount: Cycles::new(2_000_000),
+            funding_account: Some(CyclesLedgerAccount {
+                owner,
+                subaccount: Some([1; 32]),
+            }),
+        })
+    );
+
+    let reply = get_reply(test.canister_status(canister_id));
+    let status = CanisterStatusResultV2::decode(&reply).unwrap();
+    assert_eq!(
+        status.settings().auto_top_up().unwrap().funding_account,
+        Some(CyclesLedgerAccountArgs {
+            owner,
+            subaccount: Some(vec![1; 32]),
+        })
+    );
+}
+
+#[test]
+fn update_settings_sets_auto_top_up_beneficiaries() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test
+        .universal_canister_with_cycles(Cycles::new(1_000_000_000_000))
+        .unwrap();
+
+    let payload = UpdateSettingsArgs {
+        canister_id: canister_id.get(),
+        settings: CanisterSettingsArgsBuilder::new()
+            .with_auto_top_up_beneficiaries(vec![canister_test_id(1).get()])
+            .build(),
+        sender_canister_version: None,
+    }
+    .encode();
+    get_reply(test.subnet_message(Method::UpdateSettings, payload));
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .auto_top_up_beneficiaries,
+        btreeset! {canister_test_id(1)}
+    );
+
+    // The number of beneficiaries is bounded.
+    let payload = UpdateSettingsArgs {
+        canister_id: canister_id.get(),
+        settings: CanisterSettingsArgsBuilder::new()
+            .with_auto_top_up_beneficiaries(
+                (0..=MAX_AUTO_TOP_UP_BENEFICIARIES as u64)
+                    .map(|i| canister_test_id(i).get())
+                    .collect(),
+            )
+            .build(),
+        sender_canister_version: None,
+    }
+    .encode();
+    let err = test
+        .subnet_message(Method::UpdateSettings, payload)
+        .unwrap_err();
+    assert_eq!(ErrorCode::CanisterContractViolation, err.code());
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .auto_top_up_beneficiaries,
+        btreeset! {canister_test_id(1)}
+    );
+}
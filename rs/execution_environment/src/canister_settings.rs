@@ -3,15 +3,21 @@ use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, UserError};
 use ic_ic00_types::CanisterSettingsArgs;
 use ic_interfaces::execution_environment::SubnetAvailableMemory;
+use ic_replicated_state::{AutoTopUp, CyclesLedgerAccount};
 use ic_types::{
-    ComputeAllocation, Cycles, InvalidComputeAllocationError, InvalidMemoryAllocationError,
-    MemoryAllocation, PrincipalId,
+    CanisterId, ComputeAllocation, Cycles, InvalidComputeAllocationError,
+    InvalidMemoryAllocationError, MemoryAllocation, PrincipalId,
 };
 use num_traits::cast::ToPrimitive;
+use std::collections::BTreeSet;
 use std::convert::TryFrom;
 
 use crate::canister_manager::CanisterManagerError;
 
+/// The maximum number of canisters that may automatically top themselves up
+/// with the cycles of a single funding canister.
+pub(crate) const MAX_AUTO_TOP_UP_BENEFICIARIES: usize = 10;
+
 /// Struct used for decoding CanisterSettingsArgs
 #[derive(Default)]
 pub(crate) struct CanisterSettings {
@@ -21,6 +27,10 @@ pub(crate) struct CanisterSettings {
     pub(crate) memory_allocation: Option<MemoryAllocation>,
     pub(crate) freezing_threshold: Option<NumSeconds>,
     pub(crate) reserved_cycles_limit: Option<Cycles>,
+    pub(crate) query_cache_max_staleness: Option<NumSeconds>,
+    pub(crate) auto_top_up: Option<AutoTopUp>,
+    pub(crate) extended_install_code_limit: Option<bool>,
+    pub(crate) auto_top_up_beneficiaries: Option<BTreeSet<CanisterId>>,
 }
 
 impl CanisterSettings {
@@ -31,6 +41,10 @@ impl CanisterSettings {
         memory_allocation: Option<MemoryAllocation>,
         freezing_threshold: Option<NumSeconds>,
         reserved_cycles_limit: Option<Cycles>,
+        query_cache_max_staleness: Option<NumSeconds>,
+        auto_top_up: Option<AutoTopUp>,
+        extended_install_code_limit: Option<bool>,
+        auto_top_up_beneficiaries: Option<BTreeSet<CanisterId>>,
     ) -> Self {
         Self {
             controller,
@@ -39,6 +53,10 @@ impl CanisterSettings {
             memory_allocation,
             freezing_threshold,
             reserved_cycles_limit,
+            query_cache_max_staleness,
+            auto_top_up,
+            extended_install_code_limit,
+            auto_top_up_beneficiaries,
         }
     }
 
@@ -65,6 +83,22 @@ impl CanisterSettings {
     pub fn reserved_cycles_limit(&self) -> Option<Cycles> {
         self.reserved_cycles_limit
     }
+
+    pub fn query_cache_max_staleness(&self) -> Option<NumSeconds> {
+        self.query_cache_max_staleness
+    }
+
+    pub fn auto_top_up(&self) -> Option<AutoTopUp> {
+        self.auto_top_up
+    }
+
+    pub fn extended_install_code_limit(&self) -> Option<bool> {
+        self.extended_install_code_limit
+    }
+
+    pub fn auto_top_up_beneficiaries(&self) -> Option<BTreeSet<CanisterId>> {
+        self.auto_top_up_beneficiaries.clone()
+    }
 }
 
 impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
@@ -102,6 +136,63 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             None => None,
         };
 
+        let query_cache_max_staleness = match input.query_cache_max_staleness {
+            Some(staleness) => Some(NumSeconds::from(staleness.0.to_u64().ok_or(
+                UpdateSettingsError::QueryCacheMaxStalenessOutOfRange {
+                    provided: staleness,
+                },
+            )?)),
+            None => None,
+        };
+
+        let auto_top_up = match input.auto_top_up {
+            Some(args) => Some(AutoTopUp {
+                funding_canister: CanisterId::unchecked_from_principal(args.funding_canister),
+                threshold: Cycles::from(args.threshold.0.to_u128().ok_or(
+                    UpdateSettingsError::AutoTopUpThresholdOutOfRange {
+                        provided: args.threshold.clone(),
+                    },
+                )?),
+                amount: Cycles::from(args.amount.0.to_u128().ok_or(
+                    UpdateSettingsError::AutoTopUpAmountOutOfRange {
+                        provided: args.amount.clone(),
+                    },
+                )?),
+                funding_account: match args.funding_account {
+                    Some(account) => Some(CyclesLedgerAccount {
+                        owner: account.owner,
+                        subaccount: match account.subaccount {
+                            Some(subaccount) => {
+                                Some(<[u8; 32]>::try_from(subaccount.as_slice()).map_err(|_| {
+                                    UpdateSettingsError::InvalidAutoTopUpSubaccount {
+                                        length: subaccount.len(),
+                                    }
+                                })?)
+                            }
+                            None => None,
+                        },
+                    }),
+                    None => None,
+                },
+            }),
+            None => None,
+        };
+
+        let auto_top_up_beneficiaries = match input.auto_top_up_beneficiaries {
+            Some(beneficiaries) if beneficiaries.len() > MAX_AUTO_TOP_UP_BENEFICIARIES => {
+                return Err(UpdateSettingsError::TooManyAutoTopUpBeneficiaries {
+                    provided: beneficiaries.len(),
+                });
+            }
+            Some(beneficiaries) => Some(
+                beneficiaries
+                    .into_iter()
+                    .map(CanisterId::unchecked_from_principal)
+                    .collect(),
+            ),
+            None => None,
+        };
+
         Ok(CanisterSettings::new(
             controller,
             input
@@ -111,6 +202,10 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             memory_allocation,
             freezing_threshold,
             reserved_cycles_limit,
+            query_cache_max_staleness,
+            auto_top_up,
+            input.extended_install_code_limit,
+            auto_top_up_beneficiaries,
         ))
     }
 }
@@ -133,6 +228,10 @@ pub(crate) struct CanisterSettingsBuilder {
     memory_allocation: Option<MemoryAllocation>,
     freezing_threshold: Option<NumSeconds>,
     reserved_cycles_limit: Option<Cycles>,
+    query_cache_max_staleness: Option<NumSeconds>,
+    auto_top_up: Option<AutoTopUp>,
+    extended_install_code_limit: Option<bool>,
+    auto_top_up_beneficiaries: Option<BTreeSet<CanisterId>>,
 }
 
 #[allow(dead_code)]
@@ -145,6 +244,10 @@ impl CanisterSettingsBuilder {
             memory_allocation: None,
             freezing_threshold: None,
             reserved_cycles_limit: None,
+            query_cache_max_staleness: None,
+            auto_top_up: None,
+            extended_install_code_limit: None,
+            auto_top_up_beneficiaries: None,
         }
     }
 
@@ -156,6 +259,10 @@ impl CanisterSettingsBuilder {
             memory_allocation: self.memory_allocation,
             freezing_threshold: self.freezing_threshold,
             reserved_cycles_limit: self.reserved_cycles_limit,
+            query_cache_max_staleness: self.query_cache_max_staleness,
+            auto_top_up: self.auto_top_up,
+            extended_install_code_limit: self.extended_install_code_limit,
+            auto_top_up_beneficiaries: self.auto_top_up_beneficiaries,
         }
     }
 
@@ -200,6 +307,34 @@ impl CanisterSettingsBuilder {
             ..self
         }
     }
+
+    pub fn with_query_cache_max_staleness(self, query_cache_max_staleness: NumSeconds) -> Self {
+        Self {
+            query_cache_max_staleness: Some(query_cache_max_staleness),
+            ..self
+        }
+    }
+
+    pub fn with_auto_top_up(self, auto_top_up: AutoTopUp) -> Self {
+        Self {
+            auto_top_up: Some(auto_top_up),
+            ..self
+        }
+    }
+
+    pub fn with_extended_install_code_limit(self, extended_install_code_limit: bool) -> Self {
+        Self {
+            extended_install_code_limit: Some(extended_install_code_limit),
+            ..self
+        }
+    }
+
+    pub fn with_auto_top_up_beneficiaries(self, beneficiaries: BTreeSet<CanisterId>) -> Self {
+        Self {
+            auto_top_up_beneficiaries: Some(beneficiaries),
+            ..self
+        }
+    }
 }
 
 pub enum UpdateSettingsError {
@@ -207,6 +342,11 @@ pub enum UpdateSettingsError {
     MemoryAllocation(InvalidMemoryAllocationError),
     FreezingThresholdOutOfRange { provided: candid::Nat },
     ReservedCyclesLimitOutOfRange { provided: candid::Nat },
+    QueryCacheMaxStalenessOutOfRange { provided: candid::Nat },
+    AutoTopUpThresholdOutOfRange { provided: candid::Nat },
+    AutoTopUpAmountOutOfRange { provided: candid::Nat },
+    InvalidAutoTopUpSubaccount { length: usize },
+    TooManyAutoTopUpBeneficiaries { provided: usize },
 }
 
 impl From<UpdateSettingsError> for UserError {
@@ -242,6 +382,41 @@ impl From<UpdateSettingsError> for UserError {
                     provided
                 ),
             ),
+            UpdateSettingsError::QueryCacheMaxStalenessOutOfRange { provided } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Query cache max staleness expected to be in the range of [0..2^64-1], got {}",
+                    provided
+                ),
+            ),
+            UpdateSettingsError::AutoTopUpThresholdOutOfRange { provided } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Auto top-up threshold expected to be in the range of [0..2^128-1], got {}",
+                    provided
+                ),
+            ),
+            UpdateSettingsError::AutoTopUpAmountOutOfRange { provided } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Auto top-up amount expected to be in the range of [0..2^128-1], got {}",
+                    provided
+                ),
+            ),
+            UpdateSettingsError::InvalidAutoTopUpSubaccount { length } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Auto top-up funding subaccount expected to be 32 bytes long, got {}",
+                    length
+                ),
+            ),
+            UpdateSettingsError::TooManyAutoTopUpBeneficiaries { provided } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Expected at most {} auto top-up beneficiaries, got {}",
+                    MAX_AUTO_TOP_UP_BENEFICIARIES, provided
+                ),
+            ),
         }
     }
 }
@@ -265,6 +440,10 @@ pub(crate) struct ValidatedCanisterSettings {
     memory_allocation: Option<MemoryAllocation>,
     freezing_threshold: Option<NumSeconds>,
     reserved_cycles_limit: Option<Cycles>,
+    query_cache_max_staleness: Option<NumSeconds>,
+    auto_top_up: Option<AutoTopUp>,
+    extended_install_code_limit: Option<bool>,
+    auto_top_up_beneficiaries: Option<BTreeSet<CanisterId>>,
     reservation_cycles: Cycles,
 }
 
@@ -293,6 +472,22 @@ impl ValidatedCanisterSettings {
         self.reserved_cycles_limit
     }
 
+    pub fn query_cache_max_staleness(&self) -> Option<NumSeconds> {
+        self.query_cache_max_staleness
+    }
+
+    pub fn auto_top_up(&self) -> Option<AutoTopUp> {
+        self.auto_top_up
+    }
+
+    pub fn extended_install_code_limit(&self) -> Option<bool> {
+        self.extended_install_code_limit
+    }
+
+    pub fn auto_top_up_beneficiaries(&self) -> Option<BTreeSet<CanisterId>> {
+        self.auto_top_up_beneficiaries.clone()
+    }
+
     pub fn reservation_cycles(&self) -> Cycles {
         self.reservation_cycles
     }
@@ -490,6 +685,10 @@ pub(crate) fn validate_canister_settings(
         memory_allocation: settings.memory_allocation(),
         freezing_threshold: settings.freezing_threshold(),
         reserved_cycles_limit: settings.reserved_cycles_limit(),
+        query_cache_max_staleness: settings.query_cache_max_staleness(),
+        auto_top_up: settings.auto_top_up(),
+        extended_install_code_limit: settings.extended_install_code_limit(),
+        auto_top_up_beneficiaries: settings.auto_top_up_beneficiaries(),
         reservation_cycles,
     })
 }
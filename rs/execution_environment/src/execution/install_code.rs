@@ -433,6 +433,10 @@ impl InstallCodeHelper {
                 memory_allocation: original.requested_memory_allocation,
                 freezing_threshold: None,
                 reserved_cycles_limit: None,
+                query_cache_max_staleness: None,
+                auto_top_up: None,
+                extended_install_code_limit: None,
+                auto_top_up_beneficiaries: None,
             },
             self.canister.memory_usage(),
             self.canister.message_memory_usage(),
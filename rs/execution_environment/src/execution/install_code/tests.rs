@@ -10,8 +10,9 @@ use ic_types::{
 
 use ic_ic00_types::{
     CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode,
-    CanisterInstallModeV2, EmptyBlob, InstallChunkedCodeArgs, InstallCodeArgs, Method, Payload,
-    UploadChunkArgs, UploadChunkReply,
+    CanisterInstallModeV2, CanisterSettingsArgsBuilder, CanisterStatusResultV2, EmptyBlob,
+    InstallChunkedCodeArgs, InstallCodeArgs, Method, Payload, UpdateSettingsArgs, UploadChunkArgs,
+    UploadChunkReply,
 };
 use ic_replicated_state::canister_state::NextExecution;
 use ic_test_utilities_execution_environment::{
@@ -2161,3 +2162,174 @@ fn upgrade_with_dts_correctly_updates_system_state() {
 
     assert_eq!(history_entries_before + 1, history_entries_after);
 }
+
+fn enable_extended_install_code_limit(test: &mut ExecutionTest, canister_id: CanisterId) {
+    let payload = UpdateSettingsArgs {
+        canister_id: canister_id.get(),
+        settings: CanisterSettingsArgsBuilder::new()
+            .with_extended_install_code_limit(true)
+            .build(),
+        sender_canister_version: None,
+    }
+    .encode();
+    test.subnet_message(Method::UpdateSettings, payload)
+        .unwrap();
+}
+
+#[test]
+fn install_code_with_extended_limit_succeeds_and_reports_progress() {
+    let mut test = ExecutionTestBuilder::new()
+        // Set the install message limit very low to exceed it in `canister_init`.
+        .with_install_code_instruction_limit(1_500)
+        .with_install_code_slice_instruction_limit(1_000)
+        .with_extended_install_code_instruction_limit(1_000_000)
+        .with_deterministic_time_slicing()
+        .with_manual_execution()
+        .with_cost_to_compile_wasm_instruction(0)
+        .build();
+    let wasm: &str = r#"
+    (module
+         (import "ic0" "stable_grow" (func $stable_grow (param i32) (result i32)))
+         (func (export "canister_init")
+            (drop (memory.grow (i32.const 1)))
+            (memory.fill (i32.const 0) (i32.const 34) (i32.const 1000))
+            (memory.fill (i32.const 0) (i32.const 34) (i32.const 1000))
+            (drop (call $stable_grow (i32.const 1)))
+        )
+        (memory 0 20)
+    )"#;
+
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000_000));
+    enable_extended_install_code_limit(&mut test, canister_id);
+
+    let message_id = start_install_code_dts(&mut test, canister_id, wasm);
+
+    // The paused execution is visible in the canister status.
+    let status =
+        CanisterStatusResultV2::decode(&get_reply(test.canister_status(canister_id))).unwrap();
+    assert!(status.settings().extended_install_code_limit());
+    let progress = status.install_code_progress().unwrap();
+    assert_eq!(
+        progress.start_time_nanos(),
+        test.state().time().as_nanos_since_unix_epoch()
+    );
+    assert!(progress.executed_instructions() > 0);
+    assert_eq!(progress.num_restarts(), 0);
+
+    while test.canister_state(canister_id).next_execution() == NextExecution::ContinueInstallCode {
+        test.execute_slice(canister_id);
+    }
+
+    assert!(check_ingress_status(test.ingress_status(&message_id)).is_ok());
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .install_code_progress,
+        None
+    );
+}
+
+#[test]
+fn abort_install_code_rolls_back_canister() {
+    const INSTRUCTION_LIMIT: u64 = 3_000_000;
+    let mut test = ExecutionTestBuilder::new()
+        .with_install_code_instruction_limit(INSTRUCTION_LIMIT)
+        .with_install_code_slice_instruction_limit(1_000)
+        .with_deterministic_time_slicing()
+        .with_manual_execution()
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000_000));
+    let original_balance = test.canister_state(canister_id).system_state.balance();
+
+    let message_id = start_install_code_dts(&mut test, canister_id, DTS_INSTALL_WAT);
+    test.execute_slice(canister_id);
+    assert_eq!(
+        test.canister_state(canister_id).next_execution(),
+        NextExecution::ContinueInstallCode
+    );
+    let executed_instructions = test
+        .canister_state(canister_id)
+        .system_state
+        .install_code_progress
+        .unwrap()
+        .executed_instructions;
+
+    test.abort_install_code(canister_id).unwrap();
+
+    // The canister is back in its state before the execution started.
+    let canister = test.canister_state(canister_id);
+    assert_eq!(canister.next_execution(), NextExecution::None);
+    assert!(canister.execution_state.is_none());
+    assert_eq!(canister.system_state.install_code_progress, None);
+    // Only the executed instructions are charged.
+    assert_eq!(
+        canister.system_state.balance(),
+        original_balance
+            - test
+                .cycles_account_manager()
+                .execution_cost(executed_instructions, test.subnet_size())
+    );
+    assert_eq!(
+        test.state()
+            .metadata
+            .subnet_call_context_manager
+            .install_code_calls_len(),
+        0
+    );
+    assert_eq!(
+        check_ingress_status(test.ingress_status(&message_id))
+            .unwrap_err()
+            .code(),
+        ErrorCode::CanisterInstallCodeAborted
+    );
+
+    // There is nothing left to abort.
+    let err = test.abort_install_code(canister_id).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+}
+
+#[test]
+fn extended_install_code_is_rejected_when_aborted_at_checkpoint() {
+    const INSTRUCTION_LIMIT: u64 = 1_000_000;
+    let mut test = ExecutionTestBuilder::new()
+        .with_install_code_instruction_limit(1_500)
+        .with_install_code_slice_instruction_limit(1_000)
+        .with_extended_install_code_instruction_limit(INSTRUCTION_LIMIT)
+        .with_deterministic_time_slicing()
+        .with_manual_execution()
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000_000));
+    enable_extended_install_code_limit(&mut test, canister_id);
+    let original_balance = test.canister_state(canister_id).system_state.balance();
+
+    let message_id = start_install_code_dts(&mut test, canister_id, DTS_INSTALL_WAT);
+    test.execute_slice(canister_id);
+    let executed_instructions = test
+        .canister_state(canister_id)
+        .system_state
+        .install_code_progress
+        .unwrap()
+        .executed_instructions;
+    // The checkpoint aborts the paused execution.
+    test.abort_all_paused_executions();
+
+    // The execution is rejected instead of restarting from scratch.
+    test.execute_slice(canister_id);
+    let canister = test.canister_state(canister_id);
+    assert_eq!(canister.next_execution(), NextExecution::None);
+    assert!(canister.execution_state.is_none());
+    assert_eq!(canister.system_state.install_code_progress, None);
+    let err = check_ingress_status(test.ingress_status(&message_id)).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInstallCodeAborted);
+    assert!(err
+        .description()
+        .contains("did not finish before the checkpoint"));
+    // Only the instructions executed before the abort are charged.
+    assert_eq!(
+        test.canister_state(canister_id).system_state.balance(),
+        original_balance
+            - test
+                .cycles_account_manager()
+                .execution_cost(executed_instructions, test.subnet_size())
+    );
+}
@@ -507,6 +507,7 @@ impl ResponseHelper {
                 .get()
                 .saturating_sub(instructions_left.get()),
         );
+        let trapped = result.is_err();
         let action = self
             .canister
             .system_state
@@ -555,6 +556,25 @@ impl ResponseHelper {
             );
         }
 
+        if let Some(method_name) = &original.method_name {
+            let method_stats = self
+                .canister
+                .system_state
+                .method_stats
+                .entry(method_name.clone())
+                .or_default();
+            method_stats.observe_execution(
+                instructions_used,
+                heap_delta.get() / PAGE_SIZE as u64,
+                trapped,
+            );
+            if !matches!(response, ExecutionResponse::Empty) {
+                if let Some(call_context_time) = original.call_context_time {
+                    method_stats.observe_response(round.time.saturating_sub(call_context_time));
+                }
+            }
+        }
+
         ExecuteMessageResult::Finished {
             canister: self.canister,
             response,
@@ -638,6 +658,10 @@ struct OriginalContext {
     canister_id: CanisterId,
     subnet_memory_reservation: NumBytes,
     instructions_executed: NumInstructions,
+    // The method and the creation time of the call context, used to update
+    // the method stats of the canister.
+    method_name: Option<String>,
+    call_context_time: Option<Time>,
 }
 
 /// Struct used to hold necessary information for the
@@ -882,6 +906,8 @@ pub fn execute_response(
         canister_id: clean_canister.canister_id(),
         subnet_memory_reservation,
         instructions_executed: call_context.instructions_executed(),
+        method_name: call_context.method_name().map(String::from),
+        call_context_time: call_context.time(),
     };
 
     let mut helper =
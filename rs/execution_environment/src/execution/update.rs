@@ -6,7 +6,7 @@ use crate::execution::common::{
     ingress_status_with_processing_state, update_round_limits, validate_message,
 };
 use crate::execution_environment::{
-    ExecuteMessageResult, PausedExecution, RoundContext, RoundLimits,
+    ExecuteMessageResult, ExecutionResponse, PausedExecution, RoundContext, RoundLimits,
 };
 use ic_base_types::CanisterId;
 use ic_embedders::wasm_executor::{CanisterStateChanges, PausedWasmExecution, WasmExecutionResult};
@@ -274,15 +274,17 @@ impl UpdateHelper {
 
         validate_message(&canister, &original.method)?;
 
-        let call_context_id = canister
-            .system_state
-            .call_context_manager_mut()
+        let call_context_manager = canister.system_state.call_context_manager_mut().unwrap();
+        let call_context_id = call_context_manager.new_call_context(
+            original.call_origin.clone(),
+            original.call_or_task.cycles(),
+            original.time,
+        );
+        // Remember the method for attributing callbacks in the method stats.
+        call_context_manager
+            .call_context_mut(call_context_id)
             .unwrap()
-            .new_call_context(
-                original.call_origin.clone(),
-                original.call_or_task.cycles(),
-                original.time,
-            );
+            .set_method_name(original.method.name());
 
         let initial_cycles_balance = canister.system_state.balance();
 
@@ -394,6 +396,7 @@ impl UpdateHelper {
             round.log,
             round.counters.state_changes_error,
         );
+        let trapped = output.wasm_result.is_err();
         let heap_delta = if output.wasm_result.is_ok() {
             NumBytes::from((output.instance_stats.dirty_pages * ic_sys::PAGE_SIZE) as u64)
         } else {
@@ -437,6 +440,23 @@ impl UpdateHelper {
             original.subnet_size,
             round.log,
         );
+
+        let method_stats = self
+            .canister
+            .system_state
+            .method_stats
+            .entry(original.method.name())
+            .or_default();
+        method_stats.num_calls += 1;
+        method_stats.observe_execution(
+            instructions_used,
+            heap_delta.get() / ic_sys::PAGE_SIZE as u64,
+            trapped,
+        );
+        if !matches!(response, ExecutionResponse::Empty) {
+            method_stats.observe_response(round.time.saturating_sub(original.time));
+        }
+
         ExecuteMessageResult::Finished {
             canister: self.canister,
             response,
@@ -15,12 +15,13 @@ use crate::{
     },
     hypervisor::Hypervisor,
     ic00_permissions::Ic00MethodPermissions,
-    metrics::IngressFilterMetrics,
+    metrics::{IngressFilterMetrics, MeasurementScope},
+    query_handler::execute_replicated_query_call,
     NonReplicatedQueryKind,
 };
 use candid::Encode;
 use ic_base_types::PrincipalId;
-use ic_config::execution_environment::Config as ExecutionConfig;
+use ic_config::execution_environment::{CanisterHistoryConfig, Config as ExecutionConfig};
 use ic_config::flag_status::FlagStatus;
 use ic_constants::{LOG_CANISTER_OPERATION_CYCLES_THRESHOLD, SMALL_APP_SUBNET_MAX_SIZE};
 use ic_crypto_tecdsa::derive_tecdsa_public_key;
@@ -30,18 +31,21 @@ use ic_cycles_account_manager::{
 };
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CanisterChangeOrigin, CanisterHttpRequestArgs, CanisterIdRecord, CanisterInfoRequest,
-    CanisterInfoResponse, CanisterSettingsArgs, CanisterStatusType, ClearChunkStoreArgs,
+    AbortCanisterMigrationArgs, CanisterChangeDetails, CanisterChangeOrigin, CanisterHistoryArgs,
+    CanisterHttpRequestArgs, CanisterIdRecord, CanisterInfoRequest, CanisterInfoResponse,
+    CanisterMigrationData, CanisterSettingsArgs, CanisterStatusType, ClearChunkStoreArgs,
     ComputeInitialEcdsaDealingsArgs, CreateCanisterArgs, ECDSAPublicKeyArgs,
-    ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob, InstallChunkedCodeArgs, InstallCodeArgsV2,
-    Method as Ic00Method, NodeMetricsHistoryArgs, Payload as Ic00Payload,
-    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs, SetupInitialDKGArgs,
-    SignWithECDSAArgs, StoredChunksArgs, UninstallCodeArgs, UpdateSettingsArgs, UploadChunkArgs,
-    IC_00,
+    ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob, FinishCanisterMigrationArgs,
+    InstallChunkedCodeArgs, InstallCodeArgsV2, Method as Ic00Method, NodeMetricsHistoryArgs,
+    Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs,
+    ReadCanisterMigrationDataArgs, RemoveMigratedCanisterArgs, RemoveMigratedCanisterReply,
+    SetupInitialDKGArgs, SignWithECDSAArgs, StoredChunksArgs, UninstallCodeArgs,
+    UpdateSettingsArgs, UploadChunkArgs, WriteCanisterMigrationDataArgs, IC_00,
 };
 use ic_interfaces::execution_environment::{
     ExecutionMode, IngressHistoryWriter, RegistryExecutionSettings, SubnetAvailableMemory,
 };
+use ic_interfaces_state_manager::Labeled;
 use ic_logger::{error, info, warn, ReplicaLogger};
 use ic_metrics::{MetricsRegistry, Timer};
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
@@ -54,7 +58,8 @@ use ic_replicated_state::{
         SignWithEcdsaContext, StopCanisterCall, SubnetCallContext,
     },
     page_map::PageAllocatorFileDescriptor,
-    CanisterState, CanisterStatus, ExecutionTask, NetworkTopology, ReplicatedState,
+    CanisterState, CanisterStatus, ExecutionTask, InputQueueType, InstallCodeProgress,
+    NetworkTopology, ReplicatedState,
 };
 use ic_system_api::{ExecutionParameters, InstructionLimits};
 use ic_types::{
@@ -63,16 +68,19 @@ use ic_types::{
     crypto::threshold_sig::ni_dkg::NiDkgTargetId,
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
-        extract_effective_canister_id, AnonymousQuery, CanisterCall, CanisterCallOrTask,
-        CanisterMessage, CanisterMessageOrTask, CanisterTask, Payload, RejectContext, Request,
-        Response, SignedIngressContent, StopCanisterCallId, StopCanisterContext,
+        extract_effective_canister_id, AnonymousQuery, CallbackId, CanisterCall,
+        CanisterCallOrTask, CanisterMessage, CanisterMessageOrTask, CanisterTask, Payload,
+        RejectContext, Request, RequestOrResponse, Response, SignedIngressContent,
+        StopCanisterCallId, StopCanisterContext,
     },
     methods::SystemMethod,
     nominal_cycles::NominalCycles,
-    CanisterId, Cycles, LongExecutionMode, NumBytes, NumInstructions, SubnetId, Time,
+    CanisterId, Cycles, ExecutionRound, Height, LongExecutionMode, NumBytes, NumInstructions,
+    SubnetId, Time,
 };
 use ic_types::{messages::MessageId, methods::WasmMethod};
 use ic_wasm_types::WasmHash;
+use num_traits::cast::ToPrimitive;
 use phantom_newtype::AmountOf;
 use prometheus::IntCounter;
 use rand::RngCore;
@@ -80,7 +88,7 @@ use std::fmt;
 use std::str::FromStr;
 use std::sync::Mutex;
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     mem,
 };
 use std::{convert::Into, convert::TryFrom, sync::Arc};
@@ -176,6 +184,30 @@ pub fn as_num_instructions(a: RoundInstructions) -> NumInstructions {
     NumInstructions::from(u64::try_from(a.get()).unwrap_or(0))
 }
 
+/// Returns true if the given call can be executed synchronously as a query
+/// call of an update method to the given callee on this subnet.
+fn is_replicated_query_call(
+    request: &Request,
+    replicated_query_callbacks: &BTreeSet<CallbackId>,
+    callee: Option<&CanisterState>,
+) -> bool {
+    // The caller must have opted in via `ic0.call_replicated_query`.
+    if request.sender == request.receiver
+        || !request.payment.is_zero()
+        || !replicated_query_callbacks.contains(&request.sender_reply_callback)
+    {
+        return false;
+    }
+    match callee {
+        Some(callee) if matches!(callee.system_state.status, CanisterStatus::Running { .. }) => {
+            let method_name = request.method_name.clone();
+            callee.exports_method(&WasmMethod::Query(method_name.clone()))
+                || callee.exports_method(&WasmMethod::CompositeQuery(method_name))
+        }
+        _ => false,
+    }
+}
+
 /// Contains limits (or budget) for various resources that affect duration of
 /// a round such as
 /// - executed instructions,
@@ -352,6 +384,7 @@ impl ExecutionEnvironment {
             config.rate_limiting_of_heap_delta,
             heap_delta_rate_limit,
             upload_wasm_chunk_instructions,
+            config.canister_history.clone(),
         );
         let metrics = ExecutionEnvironmentMetrics::new(metrics_registry);
         let canister_manager = CanisterManager::new(
@@ -401,6 +434,184 @@ impl ExecutionEnvironment {
         )
     }
 
+    /// Executes the calls that update methods made to query methods of other
+    /// canisters on this subnet synchronously instead of inducting them into
+    /// the input queues of the callees.
+    ///
+    /// A call qualifies if the caller marked it via `ic0.call_replicated_query`,
+    /// no cycles are attached to it, and the callee is running and exports the
+    /// method as a query or as a composite query. The query call graph of the
+    /// call is evaluated against the current state, its state changes are
+    /// discarded and the response is pushed into the input queue of the
+    /// caller. By opting in, the caller pays for all instructions executed by
+    /// the call graph and the callees are not charged.
+    ///
+    /// Calls are executed only while the round has enough instructions left
+    /// for their maximum cost. The remaining calls are inducted as usual.
+    pub fn execute_replicated_query_calls(
+        &self,
+        state: &mut ReplicatedState,
+        current_round: ExecutionRound,
+        max_instructions_per_query: NumInstructions,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+    ) {
+        if self.config.replicated_query_calls == FlagStatus::Disabled {
+            return;
+        }
+        let max_instructions = self.config.max_query_call_graph_instructions;
+
+        // Take the qualifying calls out of the output queues of the callers.
+        let mut instructions_budget = round_limits.instructions;
+        let mut calls = vec![];
+        let mut canisters = state.take_canister_states();
+        let callers: Vec<CanisterId> = canisters
+            .iter()
+            .filter(|(_, canister)| canister.has_output())
+            .map(|(canister_id, _)| *canister_id)
+            .collect();
+        for caller_id in callers {
+            let mut caller = canisters.remove(&caller_id).unwrap();
+            let replicated_query_callbacks: BTreeSet<CallbackId> = caller
+                .system_state
+                .call_context_manager()
+                .map(|ccm| {
+                    ccm.callbacks()
+                        .iter()
+                        .filter(|(_, callback)| callback.replicated_query)
+                        .map(|(callback_id, _)| *callback_id)
+                        .collect()
+                })
+                .unwrap_or_default();
+            if replicated_query_callbacks.is_empty() {
+                canisters.insert(caller_id, caller);
+                continue;
+            }
+            caller
+                .system_state
+                .output_queues_for_each(|callee_id, msg| match msg {
+                    RequestOrResponse::Request(request)
+                        if instructions_budget >= as_round_instructions(max_instructions)
+                            && is_replicated_query_call(
+                                request,
+                                &replicated_query_callbacks,
+                                canisters.get(callee_id),
+                            ) =>
+                    {
+                        instructions_budget -= as_round_instructions(max_instructions);
+                        calls.push(Arc::clone(request));
+                        Ok(())
+                    }
+                    _ => Err(()),
+                });
+            canisters.insert(caller_id, caller);
+        }
+        state.put_canister_states(canisters);
+        if calls.is_empty() {
+            return;
+        }
+
+        // Prepay for the calls before the state is shared with the queries.
+        let mut prepaid_calls = vec![];
+        let mut responses = vec![];
+        for request in calls {
+            let caller = state.canister_state_mut(&request.sender).unwrap();
+            let memory_usage = caller.memory_usage();
+            let message_memory_usage = caller.message_memory_usage();
+            match self.cycles_account_manager.prepay_execution_cycles(
+                &mut caller.system_state,
+                memory_usage,
+                message_memory_usage,
+                caller.scheduler_state.compute_allocation,
+                max_instructions,
+                subnet_size,
+            ) {
+                Ok(prepaid_execution_cycles) => {
+                    prepaid_calls.push((request, prepaid_execution_cycles));
+                }
+                Err(err) => responses.push((
+                    Response {
+                        originator: request.sender,
+                        respondent: request.receiver,
+                        originator_reply_callback: request.sender_reply_callback,
+                        refund: Cycles::zero(),
+                        response_payload: Payload::Reject(RejectContext::from(UserError::new(
+                            ErrorCode::CanisterOutOfCycles,
+                            err,
+                        ))),
+                    },
+                    None,
+                )),
+            }
+        }
+
+        // The queries do not modify the state, so it is moved behind an `Arc`
+        // for their execution instead of being cloned.
+        let measurement_scope = MeasurementScope::root(&self.metrics.replicated_query_calls);
+        let own_subnet_id = state.metadata.own_subnet_id;
+        let shared_state = Arc::new(mem::replace(
+            state,
+            ReplicatedState::new(own_subnet_id, self.own_subnet_type),
+        ));
+        for (request, prepaid_execution_cycles) in prepaid_calls {
+            let (response, instructions_executed) = execute_replicated_query_call(
+                &self.log,
+                &self.hypervisor,
+                self.own_subnet_type,
+                Labeled::new(Height::new(current_round.get()), Arc::clone(&shared_state)),
+                &self.config,
+                request,
+                max_instructions_per_query,
+                &self.metrics.replicated_query_call_error,
+                &measurement_scope,
+            );
+            responses.push((
+                response,
+                Some((instructions_executed, prepaid_execution_cycles)),
+            ));
+        }
+        *state = Arc::try_unwrap(shared_state).unwrap_or_else(|shared_state| {
+            self.metrics.replicated_query_call_error.inc();
+            error!(
+                self.log,
+                "[EXC-BUG] The state is still shared after executing replicated query calls"
+            );
+            (*shared_state).clone()
+        });
+
+        let mut subnet_available_memory = self.subnet_available_memory(state).get_message_memory();
+        for (response, execution) in responses {
+            let caller = state.canister_state_mut(&response.originator).unwrap();
+            if let Some((instructions_executed, prepaid_execution_cycles)) = execution {
+                let instructions_left = max_instructions - instructions_executed;
+                self.cycles_account_manager.refund_unused_execution_cycles(
+                    &mut caller.system_state,
+                    instructions_left,
+                    max_instructions,
+                    prepaid_execution_cycles,
+                    &self.metrics.execution_cycles_refund_error,
+                    subnet_size,
+                    &self.log,
+                );
+                round_limits.instructions -= as_round_instructions(instructions_executed);
+            }
+            if let Err((err, msg)) = caller.push_input(
+                response.into(),
+                &mut subnet_available_memory,
+                self.own_subnet_type,
+                InputQueueType::LocalSubnet,
+            ) {
+                self.metrics.replicated_query_call_error.inc();
+                error!(
+                    self.log,
+                    "[EXC-BUG] Failed to push the response {:?} of a replicated query call: {}",
+                    msg,
+                    err
+                );
+            }
+        }
+    }
+
     /// Executes a replicated message sent to a subnet.
     /// Returns the new replicated state and the number of left instructions.
     #[allow(clippy::cognitive_complexity)]
@@ -742,6 +953,39 @@ impl ExecutionEnvironment {
                 Some((res, msg.take_cycles()))
             }
 
+            Ok(Ic00Method::AbortInstallCode) => match CanisterIdRecord::decode(payload) {
+                Err(err) => Some((Err(err), msg.take_cycles())),
+                Ok(args) => {
+                    match self.abort_install_code(
+                        args.get_canister_id(),
+                        *msg.sender(),
+                        &mut state,
+                        registry_settings.subnet_size,
+                    ) {
+                        Ok(mut install_code_msg) => {
+                            // Reject the aborted `install_code` message.
+                            let refund = install_code_msg.take_cycles();
+                            let err = UserError::new(
+                                ErrorCode::CanisterInstallCodeAborted,
+                                format!(
+                                    "Installing code on canister {} was aborted by {}.",
+                                    args.get_canister_id(),
+                                    msg.sender()
+                                ),
+                            );
+                            state = self.output_subnet_response(
+                                install_code_msg,
+                                state,
+                                Err(err),
+                                refund,
+                            );
+                            Some((Ok(EmptyBlob.encode()), msg.take_cycles()))
+                        }
+                        Err(err) => Some((Err(err), msg.take_cycles())),
+                    }
+                }
+            },
+
             Ok(Ic00Method::StopCanister) => match CanisterIdRecord::decode(payload) {
                 Err(err) => Some((Err(err), msg.take_cycles())),
                 Ok(args) => self.stop_canister(args.get_canister_id(), &msg, &mut state),
@@ -979,7 +1223,7 @@ impl ExecutionEnvironment {
                 let res = match ProvisionalTopUpCanisterArgs::decode(payload) {
                     Err(err) => Err(err),
                     Ok(args) => self.add_cycles(
-                        *msg.sender(),
+                        msg.canister_change_origin(None),
                         args.get_canister_id(),
                         args.to_u128(),
                         &mut state,
@@ -1064,6 +1308,36 @@ impl ExecutionEnvironment {
                 Some((res, msg.take_cycles()))
             }
 
+            Ok(Ic00Method::CanisterMethodStats) => {
+                let res = match CanisterIdRecord::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => {
+                        self.canister_method_stats(*msg.sender(), args.get_canister_id(), &state)
+                    }
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::ResetCanisterMethodStats) => {
+                let res = match CanisterIdRecord::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self.reset_canister_method_stats(
+                        *msg.sender(),
+                        args.get_canister_id(),
+                        &mut state,
+                    ),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::CanisterHistory) => {
+                let res = match CanisterHistoryArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self.canister_history(*msg.sender(), args, &state),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
             Ok(Ic00Method::StoredChunks) => {
                 let res = match StoredChunksArgs::decode(payload) {
                     Err(err) => Err(err),
@@ -1080,6 +1354,57 @@ impl ExecutionEnvironment {
                 Some((res, msg.take_cycles()))
             }
 
+            Ok(Ic00Method::ReadCanisterMigrationData) => {
+                let res = match ReadCanisterMigrationDataArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self.read_canister_migration_data(*msg.sender(), &mut state, args),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::WriteCanisterMigrationData) => {
+                let res = match WriteCanisterMigrationDataArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self.write_canister_migration_data(
+                        *msg.sender(),
+                        &mut state,
+                        args,
+                        round_limits,
+                        registry_settings.subnet_size,
+                    ),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::RemoveMigratedCanister) => {
+                let res = match RemoveMigratedCanisterArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self.remove_migrated_canister(
+                        *msg.sender(),
+                        &mut state,
+                        args,
+                        registry_settings.subnet_size,
+                    ),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::FinishCanisterMigration) => {
+                let res = match FinishCanisterMigrationArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self.finish_canister_migration(*msg.sender(), &mut state, args),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::AbortCanisterMigration) => {
+                let res = match AbortCanisterMigrationArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self.abort_canister_migration(*msg.sender(), &mut state, args),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
             Ok(Ic00Method::DeleteChunks) | Ok(Ic00Method::InstallChunkedCode) => Some((
                 Err(UserError::new(
                     ErrorCode::CanisterRejectedMessage,
@@ -1404,6 +1729,8 @@ impl ExecutionEnvironment {
         round_limits: &mut RoundLimits,
         subnet_size: usize,
     ) -> Result<Vec<u8>, UserError> {
+        self.canister_manager
+            .validate_auto_top_up(&settings, origin.origin(), state)?;
         let canister = get_canister_mut(canister_id, state)?;
         self.canister_manager
             .update_settings(
@@ -1439,12 +1766,114 @@ impl ExecutionEnvironment {
         }
     }
 
+    /// Aborts the pending `install_code` execution of the given canister and
+    /// returns the aborted `install_code` message.
+    ///
+    /// Changes of a sliced `install_code` execution are applied to the canister
+    /// only when the execution finishes, so aborting it rolls the canister back
+    /// to its state before the execution. The cycles prepaid for the execution
+    /// are refunded to the canister, except for the cost of the instructions
+    /// that have already been executed.
+    fn abort_install_code(
+        &self,
+        canister_id: CanisterId,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        subnet_size: usize,
+    ) -> Result<CanisterCall, UserError> {
+        let canister = get_canister_mut(canister_id, state)?;
+        validate_controller(canister, &sender)?;
+
+        let position = canister
+            .system_state
+            .task_queue
+            .iter()
+            .position(|task| {
+                matches!(
+                    task,
+                    ExecutionTask::PausedInstallCode(_) | ExecutionTask::AbortedInstallCode { .. }
+                )
+            })
+            .ok_or_else(|| {
+                UserError::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!(
+                        "Canister {} has no install_code execution in progress.",
+                        canister_id
+                    ),
+                )
+            })?;
+
+        let (message, call_id, prepaid_execution_cycles) =
+            match canister.system_state.task_queue.remove(position).unwrap() {
+                ExecutionTask::PausedInstallCode(id) => {
+                    let paused = self.take_paused_install_code(id).unwrap();
+                    let (message, call_id, prepaid_execution_cycles) = paused.abort(&self.log);
+                    canister.apply_priority_credit();
+                    canister.scheduler_state.long_execution_mode = LongExecutionMode::default();
+                    canister.system_state.apply_ingress_induction_cycles_debit(
+                        canister_id,
+                        &self.log,
+                        &self.metrics.charging_from_balance_error,
+                    );
+                    (message, call_id, prepaid_execution_cycles)
+                }
+                ExecutionTask::AbortedInstallCode {
+                    message,
+                    call_id,
+                    prepaid_execution_cycles,
+                } => (message, call_id, prepaid_execution_cycles),
+                task @ (ExecutionTask::Heartbeat
+                | ExecutionTask::GlobalTimer
+                | ExecutionTask::PausedExecution(_)
+                | ExecutionTask::AbortedExecution { .. }) => {
+                    panic!("Unexpected task {:?} in `abort_install_code`.", task);
+                }
+            };
+        // The instructions executed so far are charged, the rest is refunded.
+        let executed_instructions = canister
+            .system_state
+            .install_code_progress
+            .take()
+            .map_or(NumInstructions::from(0), |progress| {
+                progress.executed_instructions
+            });
+        let refund = prepaid_execution_cycles
+            - self
+                .cycles_account_manager
+                .execution_cost(executed_instructions, subnet_size);
+        canister
+            .system_state
+            .add_cycles(refund, CyclesUseCase::Instructions);
+
+        info!(
+            self.log,
+            "Aborted install_code message on canister {:?} on request of {:?}", canister_id, sender,
+        );
+
+        // The message can be removed because it is rejected by the caller.
+        let install_code_call = state
+            .metadata
+            .subnet_call_context_manager
+            .remove_install_code_call(call_id);
+        if install_code_call.is_none() {
+            self.metrics
+                .observe_call_id_without_install_code_call_error_counter(
+                    &self.log,
+                    call_id,
+                    canister_id,
+                );
+        }
+        Ok(message)
+    }
+
     fn deposit_cycles(
         &self,
         canister_id: CanisterId,
         msg: &mut CanisterCall,
         state: &mut ReplicatedState,
     ) -> (Result<Vec<u8>, UserError>, Cycles) {
+        let time = state.time();
         match state.canister_state_mut(&canister_id) {
             None => (
                 Err(UserError::new(
@@ -1459,6 +1888,12 @@ impl ExecutionEnvironment {
                 canister_state
                     .system_state
                     .add_cycles(cycles, CyclesUseCase::NonConsumed);
+                self.record_cycles_deposit(
+                    canister_state,
+                    time,
+                    msg.canister_change_origin(None),
+                    cycles,
+                );
                 if cycles.get() > LOG_CANISTER_OPERATION_CYCLES_THRESHOLD {
                     info!(
                         self.log,
@@ -1546,17 +1981,46 @@ impl ExecutionEnvironment {
 
     fn add_cycles(
         &self,
-        sender: PrincipalId,
+        origin: CanisterChangeOrigin,
         canister_id: CanisterId,
         cycles: Option<u128>,
         state: &mut ReplicatedState,
         provisional_whitelist: &ProvisionalWhitelist,
     ) -> Result<Vec<u8>, UserError> {
+        let time = state.time();
         let canister = get_canister_mut(canister_id, state)?;
+        let balance_before = canister.system_state.balance();
         self.canister_manager
-            .add_cycles(sender, cycles, canister, provisional_whitelist)
-            .map(|()| EmptyBlob.encode())
-            .map_err(|err| err.into())
+            .add_cycles(origin.origin(), cycles, canister, provisional_whitelist)
+            .map_err(UserError::from)?;
+        let deposited = canister.system_state.balance() - balance_before;
+        self.record_cycles_deposit(canister, time, origin, deposited);
+        Ok(EmptyBlob.encode())
+    }
+
+    /// Records a cycles deposit in the canister history if it reaches the
+    /// configured threshold.
+    fn record_cycles_deposit(
+        &self,
+        canister: &mut CanisterState,
+        time: Time,
+        origin: CanisterChangeOrigin,
+        cycles: Cycles,
+    ) {
+        let config = &self.config.canister_history;
+        if cycles >= config.cycles_threshold {
+            canister.system_state.add_audit_change(
+                time,
+                origin,
+                CanisterChangeDetails::cycles_deposit(cycles.get()),
+                config.max_cycles_deposits,
+            );
+        }
+    }
+
+    /// Returns the retention limits of the audit changes in canister history.
+    pub fn canister_history_config(&self) -> &CanisterHistoryConfig {
+        &self.config.canister_history
     }
 
     fn upload_chunk(
@@ -1616,20 +2080,51 @@ impl ExecutionEnvironment {
             .map_err(|err| err.into())
     }
 
+    fn canister_method_stats(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<Vec<u8>, UserError> {
+        let canister = get_canister(canister_id, state)?;
+        self.canister_manager
+            .canister_method_stats(sender, canister)
+            .map(|reply| reply.encode())
+            .map_err(|err| err.into())
+    }
+
+    fn reset_canister_method_stats(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &mut ReplicatedState,
+    ) -> Result<Vec<u8>, UserError> {
+        let canister = get_canister_mut(canister_id, state)?;
+        self.canister_manager
+            .reset_canister_method_stats(sender, canister)
+            .map(|()| EmptyBlob.encode())
+            .map_err(|err| err.into())
+    }
+
+    fn canister_history(
+        &self,
+        sender: PrincipalId,
+        args: CanisterHistoryArgs,
+        state: &ReplicatedState,
+    ) -> Result<Vec<u8>, UserError> {
+        let canister = get_canister(args.canister_id(), state)?;
+        self.canister_manager
+            .canister_history(sender, canister, &args)
+            .map(|reply| reply.encode())
+            .map_err(|err| err.into())
+    }
+
     fn node_metrics_history(
         &self,
         state: &ReplicatedState,
         args: NodeMetricsHistoryArgs,
     ) -> Result<Vec<u8>, UserError> {
-        if args.subnet_id != self.own_subnet_id.get() {
-            return Err(UserError::new(
-                ErrorCode::CanisterRejectedMessage,
-                format!(
-                    "Provided target subnet ID {} does not match current subnet ID {}.",
-                    args.subnet_id, self.own_subnet_id
-                ),
-            ));
-        }
+        self.validate_target_subnet_id(args.subnet_id)?;
 
         let result = state
             .metadata
@@ -1640,6 +2135,131 @@ impl ExecutionEnvironment {
         Ok(Encode!(&result).unwrap())
     }
 
+    /// Returns an error if the given subnet ID is not the ID of this subnet.
+    fn validate_target_subnet_id(&self, subnet_id: PrincipalId) -> Result<(), UserError> {
+        if subnet_id != self.own_subnet_id.get() {
+            return Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                format!(
+                    "Provided target subnet ID {} does not match current subnet ID {}.",
+                    subnet_id, self.own_subnet_id
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn read_canister_migration_data(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: ReadCanisterMigrationDataArgs,
+    ) -> Result<Vec<u8>, UserError> {
+        self.validate_target_subnet_id(args.subnet_id)?;
+        let canister = get_canister_mut(args.get_canister_id(), state)?;
+        self.canister_manager
+            .read_canister_migration_data(sender, canister, args.segment)
+            .map(|data| data.encode())
+            .map_err(|err| err.into())
+    }
+
+    fn write_canister_migration_data(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: WriteCanisterMigrationDataArgs,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+    ) -> Result<Vec<u8>, UserError> {
+        self.validate_target_subnet_id(args.subnet_id)?;
+        let canister_id = args.get_canister_id();
+        let heap_delta = match args.data {
+            CanisterMigrationData::Metadata(metadata) => {
+                let settings = CanisterSettings::try_from(metadata.settings.clone())?;
+                self.canister_manager.write_migrated_metadata(
+                    sender,
+                    canister_id,
+                    settings,
+                    metadata,
+                    state,
+                    round_limits,
+                    subnet_size,
+                )?;
+                NumBytes::from(0)
+            }
+            CanisterMigrationData::WasmModule(chunk) => self
+                .canister_manager
+                .write_migrated_wasm_chunk(sender, canister_id, &chunk.chunk, state)?,
+            // An empty chunk store is read as a single empty chunk.
+            CanisterMigrationData::WasmChunkStore(chunk) if chunk.chunk.is_empty() => {
+                NumBytes::from(0)
+            }
+            CanisterMigrationData::WasmChunkStore(chunk) => self
+                .canister_manager
+                .write_migrated_wasm_chunk(sender, canister_id, &chunk.chunk, state)?,
+            CanisterMigrationData::WasmMemory(pages) => self
+                .canister_manager
+                .write_migrated_pages(sender, canister_id, pages, false, state)?,
+            CanisterMigrationData::StableMemory(pages) => self
+                .canister_manager
+                .write_migrated_pages(sender, canister_id, pages, true, state)?,
+        };
+        state.metadata.heap_delta_estimate += heap_delta;
+        Ok(EmptyBlob.encode())
+    }
+
+    fn remove_migrated_canister(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: RemoveMigratedCanisterArgs,
+        subnet_size: usize,
+    ) -> Result<Vec<u8>, UserError> {
+        self.validate_target_subnet_id(args.subnet_id)?;
+        let cycles = self.canister_manager.remove_migrated_canister(
+            sender,
+            args.get_canister_id(),
+            state,
+            subnet_size,
+        )?;
+        Ok(RemoveMigratedCanisterReply {
+            cycles: candid::Nat::from(cycles.get()),
+        }
+        .encode())
+    }
+
+    fn finish_canister_migration(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: FinishCanisterMigrationArgs,
+    ) -> Result<Vec<u8>, UserError> {
+        self.validate_target_subnet_id(args.subnet_id)?;
+        let cycles = args.cycles.0.to_u128().ok_or_else(|| {
+            UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!("Cycles amount {} does not fit into 128 bits.", args.cycles),
+            )
+        })?;
+        self.canister_manager
+            .finish_canister_migration(sender, args.get_canister_id(), Cycles::new(cycles), state)
+            .map(|()| EmptyBlob.encode())
+            .map_err(|err| err.into())
+    }
+
+    fn abort_canister_migration(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: AbortCanisterMigrationArgs,
+    ) -> Result<Vec<u8>, UserError> {
+        self.validate_target_subnet_id(args.subnet_id)?;
+        self.canister_manager
+            .abort_canister_migration(sender, args.get_canister_id(), state)
+            .map(|()| EmptyBlob.encode())
+            .map_err(|err| err.into())
+    }
+
     // Executes an inter-canister response.
     //
     // Returns a tuple with the result, along with a flag indicating whether or
@@ -2252,7 +2872,7 @@ impl ExecutionEnvironment {
         prepaid_execution_cycles: Option<Cycles>,
         dts_status: DtsInstallCodeStatus,
         mut state: ReplicatedState,
-        instruction_limits: InstructionLimits,
+        mut instruction_limits: InstructionLimits,
         round_limits: &mut RoundLimits,
         subnet_size: usize,
     ) -> (ReplicatedState, Option<NumInstructions>) {
@@ -2310,6 +2930,20 @@ impl ExecutionEnvironment {
             }
         }
 
+        // Canisters may opt in to a larger instruction limit. Such an execution
+        // is sliced across many rounds, which requires deterministic time slicing.
+        // The scheduler starts it only in the first round after a checkpoint,
+        // and it is rejected if it does not finish before the next checkpoint.
+        if old_canister.system_state.extended_install_code_limit
+            && self.config.deterministic_time_slicing == FlagStatus::Enabled
+        {
+            instruction_limits.update(
+                self.config
+                    .max_instructions_per_extended_install_code
+                    .max(instruction_limits.message()),
+            );
+        }
+
         let canister_id = old_canister.canister_id();
         let new_wasm_hash = WasmHash::from(&install_context.wasm_module);
         let compilation_cost_handling = if state
@@ -2345,6 +2979,7 @@ impl ExecutionEnvironment {
             ingress_with_cycles_error: &self.metrics.ingress_with_cycles_error,
         };
 
+        let instructions_before = round_limits.instructions;
         let dts_result = self.canister_manager.install_code_dts(
             install_context,
             msg,
@@ -2360,7 +2995,15 @@ impl ExecutionEnvironment {
             round_counters,
             subnet_size,
         );
-        self.process_install_code_result(state, dts_result, dts_status, timer)
+        let instructions_executed =
+            as_num_instructions(instructions_before - round_limits.instructions);
+        self.process_install_code_result(
+            state,
+            dts_result,
+            dts_status,
+            instructions_executed,
+            timer,
+        )
     }
 
     /// Processes the result of install code message that was executed using
@@ -2369,18 +3012,20 @@ impl ExecutionEnvironment {
     /// - If the execution is paused, then it enqueues it to the task queue of
     ///   the canister.
     /// In both cases, the functions gets the canister from the result and adds
-    /// it to the replicated state.
+    /// it to the replicated state. The progress of a paused execution is
+    /// recorded in the canister state.
     fn process_install_code_result(
         &self,
         mut state: ReplicatedState,
         dts_result: DtsInstallCodeResult,
         dts_status: DtsInstallCodeStatus,
+        instructions_executed: NumInstructions,
         timer: Timer,
     ) -> (ReplicatedState, Option<NumInstructions>) {
         let execution_duration = timer.elapsed();
         match dts_result {
             DtsInstallCodeResult::Finished {
-                canister,
+                mut canister,
                 mut message,
                 call_id,
                 instructions_used,
@@ -2418,6 +3063,7 @@ impl ExecutionEnvironment {
                         Err(err.into())
                     }
                 };
+                canister.system_state.install_code_progress = None;
                 state.put_canister_state(canister);
                 let refund = message.take_cycles();
                 // The message can be removed because a response was produced.
@@ -2448,6 +3094,16 @@ impl ExecutionEnvironment {
                     .task_queue
                     .push_front(ExecutionTask::PausedInstallCode(id));
 
+                if dts_status == DtsInstallCodeStatus::StartingFirstExecution {
+                    canister.system_state.install_code_progress = None;
+                }
+                let time = state.time();
+                let progress = canister
+                    .system_state
+                    .install_code_progress
+                    .get_or_insert_with(|| InstallCodeProgress::new(time));
+                progress.executed_instructions += instructions_executed;
+
                 match (dts_status, ingress_status) {
                     (DtsInstallCodeStatus::StartingFirstExecution, Some((message_id, status))) => {
                         self.ingress_history_writer
@@ -2524,27 +3180,120 @@ impl ExecutionEnvironment {
                     log: &self.log,
                     time: state.metadata.time(),
                 };
+                let instructions_before = round_limits.instructions;
                 let dts_result = paused.resume(canister, round, round_limits);
                 let dts_status = DtsInstallCodeStatus::ResumingPausedOrAbortedExecution;
-                self.process_install_code_result(state, dts_result, dts_status, timer)
+                let instructions_executed =
+                    as_num_instructions(instructions_before - round_limits.instructions);
+                self.process_install_code_result(
+                    state,
+                    dts_result,
+                    dts_status,
+                    instructions_executed,
+                    timer,
+                )
             }
             ExecutionTask::AbortedInstallCode {
                 message,
                 call_id,
-                prepaid_execution_cycles,
-            } => self.execute_install_code(
-                message,
-                Some(call_id),
-                Some(prepaid_execution_cycles),
-                DtsInstallCodeStatus::ResumingPausedOrAbortedExecution,
-                state,
-                instruction_limits,
-                round_limits,
-                subnet_size,
-            ),
+                mut prepaid_execution_cycles,
+            } => {
+                let canister = state.canister_state_mut(canister_id).unwrap();
+                let (wasted_instructions, num_restarts) = canister
+                    .system_state
+                    .install_code_progress
+                    .as_mut()
+                    .map_or((NumInstructions::from(0), 0), |progress| {
+                        (
+                            std::mem::replace(
+                                &mut progress.executed_instructions,
+                                NumInstructions::from(0),
+                            ),
+                            progress.num_restarts,
+                        )
+                    });
+                // The work done before the abort is lost, but it is charged
+                // from the prepaid cycles so that restarts are not free.
+                if wasted_instructions > NumInstructions::from(0) {
+                    prepaid_execution_cycles -= self
+                        .cycles_account_manager
+                        .execution_cost(wasted_instructions, subnet_size);
+                }
+                // An execution with the extended limit starts in the first
+                // round after a checkpoint, so it was aborted because it did
+                // not finish before the next checkpoint. Restarting it would
+                // run into the same checkpoint again.
+                if canister.system_state.extended_install_code_limit && num_restarts > 0 {
+                    return self.reject_aborted_extended_install_code(
+                        state,
+                        canister_id,
+                        message,
+                        call_id,
+                        prepaid_execution_cycles,
+                    );
+                }
+                self.execute_install_code(
+                    message,
+                    Some(call_id),
+                    Some(prepaid_execution_cycles),
+                    DtsInstallCodeStatus::ResumingPausedOrAbortedExecution,
+                    state,
+                    instruction_limits,
+                    round_limits,
+                    subnet_size,
+                )
+            }
         }
     }
 
+    /// Rejects an `install_code` execution with the extended limit that was
+    /// aborted at a checkpoint and refunds the remaining prepaid cycles to the
+    /// canister.
+    fn reject_aborted_extended_install_code(
+        &self,
+        mut state: ReplicatedState,
+        canister_id: &CanisterId,
+        mut message: CanisterCall,
+        call_id: InstallCodeCallId,
+        prepaid_execution_cycles: Cycles,
+    ) -> (ReplicatedState, Option<NumInstructions>) {
+        let timer = Timer::start();
+        let canister = state.canister_state_mut(canister_id).unwrap();
+        canister.system_state.install_code_progress = None;
+        canister
+            .system_state
+            .add_cycles(prepaid_execution_cycles, CyclesUseCase::Instructions);
+        info!(
+            self.log,
+            "Rejected install_code message on canister {:?} aborted at a checkpoint", canister_id,
+        );
+        // The message can be removed because a response is produced.
+        let install_code_call = state
+            .metadata
+            .subnet_call_context_manager
+            .remove_install_code_call(call_id);
+        if install_code_call.is_none() {
+            self.metrics
+                .observe_call_id_without_install_code_call_error_counter(
+                    &self.log,
+                    call_id,
+                    *canister_id,
+                );
+        }
+        let err = UserError::new(
+            ErrorCode::CanisterInstallCodeAborted,
+            format!(
+                "Installing code on canister {} with the extended instruction limit did not \
+                finish before the checkpoint. Such executions start in the first round after \
+                a checkpoint and must finish before the next one.",
+                canister_id
+            ),
+        );
+        let refund = message.take_cycles();
+        let state = self.finish_subnet_message_execution(state, message, Err(err), refund, timer);
+        (state, Some(NumInstructions::from(0)))
+    }
+
     /// Returns the paused execution by its id.
     fn take_paused_execution(&self, id: PausedExecutionId) -> Option<Box<dyn PausedExecution>> {
         let mut guard = self.paused_execution_registry.lock().unwrap();
@@ -2584,6 +3333,7 @@ impl ExecutionEnvironment {
     /// Aborts paused execution in the given state.
     pub fn abort_canister(&self, canister: &mut CanisterState, log: &ReplicaLogger) {
         if !canister.system_state.task_queue.is_empty() {
+            let mut aborted_install_code = false;
             let task_queue = std::mem::take(&mut canister.system_state.task_queue);
             canister.system_state.task_queue = task_queue
                 .into_iter()
@@ -2605,6 +3355,7 @@ impl ExecutionEnvironment {
                         let paused = self.take_paused_install_code(id).unwrap();
                         let (message, call_id, prepaid_execution_cycles) = paused.abort(log);
                         self.metrics.executions_aborted.inc();
+                        aborted_install_code = true;
                         ExecutionTask::AbortedInstallCode {
                             message,
                             call_id,
@@ -2613,6 +3364,13 @@ impl ExecutionEnvironment {
                     }
                 })
                 .collect();
+            // The aborted `install_code` execution restarts from scratch. The
+            // instructions executed so far are charged when it restarts.
+            if aborted_install_code {
+                if let Some(progress) = canister.system_state.install_code_progress.as_mut() {
+                    progress.num_restarts += 1;
+                }
+            }
             canister.apply_priority_credit();
             // Aborting a long-running execution moves the canister to the
             // default execution mode because the canister does not have a
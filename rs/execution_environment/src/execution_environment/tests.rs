@@ -8,13 +8,14 @@ use ic_types::nominal_cycles::NominalCycles;
 use ic_base_types::{NumBytes, NumSeconds};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    self as ic00, BoundedHttpHeaders, CanisterChange, CanisterHttpRequestArgs, CanisterIdRecord,
+    self as ic00, BoundedHttpHeaders, CanisterChange, CanisterChangeDetails,
+    CanisterHistoryResponse, CanisterHttpRequestArgs, CanisterIdRecord, CanisterMethodStatsReply,
     CanisterStatusResultV2, CanisterStatusType, DerivationPath, EcdsaCurve, EcdsaKeyId, EmptyBlob,
     HttpMethod, Method, Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs,
     ProvisionalTopUpCanisterArgs, TransformContext, TransformFunc, IC_00,
 };
 use ic_registry_routing_table::canister_id_into_u64;
-use ic_registry_routing_table::CanisterIdRange;
+use ic_registry_routing_table::{CanisterIdRange, CanisterIdRanges};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
     canister_state::{DEFAULT_QUEUE_CAPACITY, WASM_PAGE_SIZE_IN_BYTES},
@@ -33,12 +34,14 @@ use ic_types::{
     messages::{
         CallbackId, Payload, RejectContext, RequestOrResponse, Response, MAX_RESPONSE_COUNT_BYTES,
     },
-    CanisterId, Cycles, PrincipalId, RegistryVersion,
+    CanisterId, Cycles, PrincipalId, RegistryVersion, SubnetId,
 };
 use ic_types_test_utils::ids::{canister_test_id, node_test_id, subnet_test_id, user_test_id};
 use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
 use maplit::btreemap;
 use std::mem::size_of;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[cfg(test)]
 mod canister_task;
@@ -740,6 +743,140 @@ fn get_canister_status_of_nonexisting_canister() {
     assert_eq!(ErrorCode::CanisterNotFound, err.code());
 }
 
+#[test]
+fn canister_method_stats_aggregate_calls_and_callbacks() {
+    let mut test = ExecutionTestBuilder::new().with_manual_execution().build();
+    let a_id = test.universal_canister().unwrap();
+    let b_id = test.universal_canister().unwrap();
+
+    // Canister A replies to one call and traps in another one.
+    test.ingress_raw(a_id, "update", wasm().reply().build());
+    test.execute_message(a_id);
+    test.ingress_raw(a_id, "update", wasm().trap().build());
+    test.execute_message(a_id);
+
+    // Canister A replies to the third call from the callback of a call to
+    // canister B. Each executed message advances the time by a second, so
+    // the third call is responded to 9 seconds after it started.
+    let a = wasm()
+        .inter_update(b_id, call_args().other_side(wasm().reply().build()))
+        .build();
+    test.ingress_raw(a_id, "update", a);
+    test.execute_message(a_id);
+    test.induct_messages();
+    test.state_mut().metadata.batch_time += Duration::from_secs(7);
+    test.execute_message(b_id);
+    test.induct_messages();
+    test.execute_message(a_id);
+
+    let result = test.canister_method_stats(a_id);
+    let reply = CanisterMethodStatsReply::decode(&get_reply(result)).unwrap();
+    assert_eq!(reply.method_stats.len(), 1);
+    let stats = &reply.method_stats[0];
+    assert_eq!(stats.method_name, "update");
+    assert_eq!(stats.num_calls, 3);
+    assert_eq!(stats.num_traps, 1);
+    assert_eq!(stats.num_responses, 3);
+    assert_eq!(stats.average_latency_nanos, 3_000_000_000);
+    let system_state = &test.canister_state(a_id).system_state;
+    assert_eq!(
+        stats.instructions_executed,
+        candid::Nat::from(
+            system_state.method_stats["update"]
+                .instructions_executed
+                .get()
+        )
+    );
+    assert!(
+        system_state.method_stats["update"]
+            .instructions_executed
+            .get()
+            > 0
+    );
+
+    test.reset_canister_method_stats(a_id).unwrap();
+    assert!(test
+        .canister_state(a_id)
+        .system_state
+        .method_stats
+        .is_empty());
+    let result = test.canister_method_stats(a_id);
+    let reply = CanisterMethodStatsReply::decode(&get_reply(result)).unwrap();
+    assert!(reply.method_stats.is_empty());
+}
+
+#[test]
+fn canister_method_stats_require_controller() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    test.set_user_id(user_test_id(42));
+
+    let err = test.canister_method_stats(canister_id).unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidController, err.code());
+    let err = test.reset_canister_method_stats(canister_id).unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidController, err.code());
+}
+
+#[test]
+fn canister_history_returns_pages_of_settings_changes_and_deposits() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_provisional_whitelist_all()
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    for freezing_threshold in 1..=3 {
+        test.update_freezing_threshold(canister_id, NumSeconds::new(freezing_threshold))
+            .unwrap();
+    }
+    // Only deposits of at least the threshold of 1T cycles are recorded.
+    for amount in [2_000_000_000_000, 1_000] {
+        let payload = ProvisionalTopUpCanisterArgs::new(canister_id, amount).encode();
+        test.subnet_message(Method::ProvisionalTopUpCanister, payload)
+            .unwrap();
+    }
+
+    let mut changes = vec![];
+    let mut start = None;
+    loop {
+        let result = test.canister_history(canister_id, start, Some(2));
+        let reply = CanisterHistoryResponse::decode(&get_reply(result)).unwrap();
+        assert!(reply.changes().len() <= 2);
+        assert_eq!(reply.total_num_changes(), reply.num_retained_changes());
+        changes.extend(reply.changes());
+        match reply.next_start() {
+            Some(next_start) => start = Some(next_start),
+            None => break,
+        }
+    }
+
+    // Canister creation, code installation, 3 settings changes and a deposit.
+    let sequence_numbers: Vec<_> = changes.iter().map(|c| c.sequence_number()).collect();
+    assert_eq!(sequence_numbers, (0..6).collect::<Vec<_>>());
+    let details: Vec<_> = changes
+        .iter()
+        .map(|c| c.change().details().clone())
+        .collect();
+    assert_eq!(details.len(), 6);
+    assert_eq!(
+        details[2..].to_vec(),
+        vec![
+            CanisterChangeDetails::settings_change(Some(1), None, None),
+            CanisterChangeDetails::settings_change(Some(2), None, None),
+            CanisterChangeDetails::settings_change(Some(3), None, None),
+            CanisterChangeDetails::cycles_deposit(2_000_000_000_000),
+        ]
+    );
+}
+
+#[test]
+fn canister_history_requires_controller() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    test.set_user_id(user_test_id(42));
+
+    let err = test.canister_history(canister_id, None, None).unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidController, err.code());
+}
+
 #[test]
 fn deposit_cycles_to_non_existing_canister_fails() {
     let mut test = ExecutionTestBuilder::new().build();
@@ -3022,3 +3159,420 @@ fn output_requests_on_application_subnets_update_subnet_available_memory_reserve
     );
     assert_correct_request(system_state, canister_id);
 }
+
+const MIGRATED_CANISTER_WAT: &str = r#"
+    (module
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (import "ic0" "msg_reply_data_append"
+            (func $msg_reply_data_append (param i32 i32)))
+        (import "ic0" "stable_grow" (func $stable_grow (param i32) (result i32)))
+        (import "ic0" "stable_read" (func $stable_read (param i32 i32 i32)))
+        (import "ic0" "stable_write" (func $stable_write (param i32 i32 i32)))
+        (func (export "canister_update write")
+            (i32.store (i32.const 70000) (i32.const 42))
+            (global.set $counter (i32.const 7))
+            (drop (call $stable_grow (i32.const 1)))
+            (call $stable_write (i32.const 100) (i32.const 70000) (i32.const 4))
+            (call $msg_reply)
+        )
+        (func (export "canister_update read")
+            (i32.store (i32.const 80000) (global.get $counter))
+            (call $stable_read (i32.const 80004) (i32.const 100) (i32.const 4))
+            (call $msg_reply_data_append (i32.const 70000) (i32.const 4))
+            (call $msg_reply_data_append (i32.const 80000) (i32.const 8))
+            (call $msg_reply)
+        )
+        (memory 2)
+        (global $counter (export "counter") (mut i32) (i32.const 0))
+    )"#;
+
+/// Assigns the given canister to the given subnet in the routing table.
+fn reroute_canister(test: &mut ExecutionTest, canister_id: CanisterId, subnet_id: SubnetId) {
+    let mut routing_table = (*test.state().metadata.network_topology.routing_table).clone();
+    routing_table
+        .assign_ranges(
+            CanisterIdRanges::try_from(vec![CanisterIdRange {
+                start: canister_id,
+                end: canister_id,
+            }])
+            .unwrap(),
+            subnet_id,
+        )
+        .unwrap();
+    test.state_mut().metadata.network_topology.routing_table = Arc::new(routing_table);
+}
+
+/// Calls a canister migration method as the configured caller and returns
+/// the reply or the reject message.
+fn call_canister_migration_method(
+    test: &mut ExecutionTest,
+    method: Method,
+    payload: Vec<u8>,
+) -> Result<Vec<u8>, String> {
+    let index = test.xnet_messages().len();
+    test.inject_call_to_ic00(method, payload, Cycles::zero());
+    test.execute_all();
+    match &test.get_xnet_response(index).response_payload {
+        Payload::Data(data) => Ok(data.clone()),
+        Payload::Reject(reject) => Err(reject.message().clone()),
+    }
+}
+
+fn read_canister_migration_data(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    segment: ic00::CanisterMigrationSegment,
+) -> ic00::CanisterMigrationData {
+    let args = ic00::ReadCanisterMigrationDataArgs {
+        subnet_id: test.state().metadata.own_subnet_id.get(),
+        canister_id: canister_id.get(),
+        segment,
+    };
+    let reply =
+        call_canister_migration_method(test, Method::ReadCanisterMigrationData, args.encode())
+            .unwrap();
+    ic00::CanisterMigrationData::decode(&reply).unwrap()
+}
+
+fn write_canister_migration_data(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    data: ic00::CanisterMigrationData,
+) -> Result<(), String> {
+    let args = ic00::WriteCanisterMigrationDataArgs {
+        subnet_id: test.state().metadata.own_subnet_id.get(),
+        canister_id: canister_id.get(),
+        data,
+    };
+    call_canister_migration_method(test, Method::WriteCanisterMigrationData, args.encode())
+        .map(|_| ())
+}
+
+#[test]
+fn canister_migration_methods_reject_other_senders() {
+    let own_subnet = subnet_test_id(1);
+    let nns_subnet = subnet_test_id(2);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_nns_subnet_id(nns_subnet)
+        .with_caller(nns_subnet, canister_test_id(1))
+        .build();
+    let canister_id = test.canister_from_wat(MIGRATED_CANISTER_WAT).unwrap();
+    let args = ic00::RemoveMigratedCanisterArgs {
+        subnet_id: own_subnet.get(),
+        canister_id: canister_id.get(),
+    };
+    let err =
+        call_canister_migration_method(&mut test, Method::RemoveMigratedCanister, args.encode())
+            .unwrap_err();
+    assert_eq!(err, "Sender not authorized to use method.");
+    assert!(test.state().canister_state(&canister_id).is_some());
+}
+
+#[test]
+fn canister_migration_requires_stopped_canister() {
+    let own_subnet = subnet_test_id(1);
+    let nns_subnet = subnet_test_id(2);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_nns_subnet_id(nns_subnet)
+        .with_caller(nns_subnet, CanisterId::from_u64(0))
+        .build();
+    let canister_id = test.canister_from_wat(MIGRATED_CANISTER_WAT).unwrap();
+    let args = ic00::ReadCanisterMigrationDataArgs {
+        subnet_id: own_subnet.get(),
+        canister_id: canister_id.get(),
+        segment: ic00::CanisterMigrationSegment::Metadata,
+    };
+    let err =
+        call_canister_migration_method(&mut test, Method::ReadCanisterMigrationData, args.encode())
+            .unwrap_err();
+    assert!(err.contains("the canister must be stopped"), "{}", err);
+}
+
+#[test]
+fn canister_migration_locks_source_canister() {
+    let own_subnet = subnet_test_id(1);
+    let nns_subnet = subnet_test_id(2);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_nns_subnet_id(nns_subnet)
+        .with_caller(nns_subnet, CanisterId::from_u64(0))
+        .build();
+    let canister_id = test.canister_from_wat(MIGRATED_CANISTER_WAT).unwrap();
+    test.stop_canister(canister_id);
+    test.process_stopping_canisters();
+    let canister_version = test
+        .canister_state(canister_id)
+        .system_state
+        .canister_version;
+
+    // The first read of the migrated state locks the canister.
+    read_canister_migration_data(
+        &mut test,
+        canister_id,
+        ic00::CanisterMigrationSegment::Metadata,
+    );
+    assert!(test.canister_state(canister_id).system_state.migrating);
+
+    let assert_locked = |result: Result<(), UserError>| {
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), ErrorCode::CanisterRejectedMessage);
+        assert!(
+            err.description().contains("is being migrated"),
+            "{}",
+            err.description()
+        );
+    };
+    assert_locked(test.start_canister(canister_id).map(|_| ()));
+    assert_locked(
+        test.update_freezing_threshold(canister_id, NumSeconds::from(1))
+            .map(|_| ()),
+    );
+    assert_locked(
+        test.canister_update_controller(canister_id, vec![user_test_id(7).get()])
+            .map(|_| ()),
+    );
+    assert_locked(
+        test.upgrade_canister(canister_id, wat::parse_str(MIGRATED_CANISTER_WAT).unwrap()),
+    );
+    assert_locked(test.uninstall_code(canister_id).map(|_| ()));
+    assert_eq!(
+        test.canister_state(canister_id).status(),
+        CanisterStatusType::Stopped
+    );
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .canister_version,
+        canister_version
+    );
+
+    // Aborting the migration unlocks the canister.
+    let args = ic00::AbortCanisterMigrationArgs {
+        subnet_id: own_subnet.get(),
+        canister_id: canister_id.get(),
+    };
+    call_canister_migration_method(&mut test, Method::AbortCanisterMigration, args.encode())
+        .unwrap();
+    assert!(!test.canister_state(canister_id).system_state.migrating);
+    test.start_canister(canister_id).unwrap();
+    assert_eq!(
+        test.canister_state(canister_id).status(),
+        CanisterStatusType::Running
+    );
+}
+
+#[test]
+fn canister_migration_moves_canister_state() {
+    let registry = CanisterId::from_u64(0);
+    let source_subnet = subnet_test_id(1);
+    let nns_subnet = subnet_test_id(2);
+    let target_subnet = subnet_test_id(3);
+    let mut source = ExecutionTestBuilder::new()
+        .with_own_subnet_id(source_subnet)
+        .with_nns_subnet_id(nns_subnet)
+        .with_caller(nns_subnet, registry)
+        .build();
+    let mut target = ExecutionTestBuilder::new()
+        .with_own_subnet_id(target_subnet)
+        .with_nns_subnet_id(nns_subnet)
+        .with_caller(nns_subnet, registry)
+        .build();
+
+    let canister_id = source.canister_from_wat(MIGRATED_CANISTER_WAT).unwrap();
+    let before = get_reply(source.ingress(canister_id, "write", vec![]));
+    assert_eq!(before, Vec::<u8>::new());
+    source.stop_canister(canister_id);
+    source.process_stopping_canisters();
+    reroute_canister(&mut target, canister_id, source_subnet);
+
+    // Copy the Wasm module, the metadata and the memories.
+    let metadata = read_canister_migration_data(
+        &mut source,
+        canister_id,
+        ic00::CanisterMigrationSegment::Metadata,
+    );
+    let module_size = match &metadata {
+        ic00::CanisterMigrationData::Metadata(metadata) => {
+            metadata.code.as_ref().unwrap().wasm_module_size
+        }
+        data => panic!("Unexpected migration data {:?}", data),
+    };
+    let chunk = read_canister_migration_data(
+        &mut source,
+        canister_id,
+        ic00::CanisterMigrationSegment::WasmModule { offset: 0 },
+    );
+    match &chunk {
+        ic00::CanisterMigrationData::WasmModule(chunk) => {
+            assert_eq!(chunk.chunk.len() as u64, module_size)
+        }
+        data => panic!("Unexpected migration data {:?}", data),
+    }
+    write_canister_migration_data(&mut target, canister_id, chunk).unwrap();
+    write_canister_migration_data(&mut target, canister_id, metadata).unwrap();
+    for segment in [
+        ic00::CanisterMigrationSegment::WasmMemory { start_page: 0 },
+        ic00::CanisterMigrationSegment::StableMemory { start_page: 0 },
+    ] {
+        let pages = read_canister_migration_data(&mut source, canister_id, segment);
+        match &pages {
+            ic00::CanisterMigrationData::WasmMemory(pages)
+            | ic00::CanisterMigrationData::StableMemory(pages) => {
+                assert_eq!(pages.pages.len(), 1);
+                assert_eq!(pages.next_page, None);
+            }
+            data => panic!("Unexpected migration data {:?}", data),
+        }
+        write_canister_migration_data(&mut target, canister_id, pages).unwrap();
+    }
+    assert_eq!(
+        target.execution_state(canister_id).exported_globals,
+        source.execution_state(canister_id).exported_globals
+    );
+    assert_eq!(
+        target.canister_state(canister_id).system_state.controllers,
+        source.canister_state(canister_id).system_state.controllers
+    );
+
+    // The canister cannot be started before it is rerouted.
+    let finish = |test: &mut ExecutionTest, cycles: candid::Nat| {
+        let args = ic00::FinishCanisterMigrationArgs {
+            subnet_id: target_subnet.get(),
+            canister_id: canister_id.get(),
+            cycles,
+        };
+        call_canister_migration_method(test, Method::FinishCanisterMigration, args.encode())
+    };
+    let err = finish(&mut target, candid::Nat::from(0_u64)).unwrap_err();
+    assert!(err.contains("is not hosted by subnet"), "{}", err);
+
+    // Move the cycles after rerouting the canister.
+    reroute_canister(&mut source, canister_id, target_subnet);
+    reroute_canister(&mut target, canister_id, target_subnet);
+    let balance = source.canister_state(canister_id).system_state.balance();
+    let args = ic00::RemoveMigratedCanisterArgs {
+        subnet_id: source_subnet.get(),
+        canister_id: canister_id.get(),
+    };
+    let reply =
+        call_canister_migration_method(&mut source, Method::RemoveMigratedCanister, args.encode())
+            .unwrap();
+    let reply = ic00::RemoveMigratedCanisterReply::decode(&reply).unwrap();
+    let fee = source
+        .cycles_account_manager()
+        .canister_migration_fee(source.subnet_size());
+    assert_eq!(reply.cycles, candid::Nat::from((balance - fee).get()));
+    assert!(source.state().canister_state(&canister_id).is_none());
+    finish(&mut target, reply.cycles).unwrap();
+
+    assert_eq!(
+        target.canister_state(canister_id).status(),
+        CanisterStatusType::Running
+    );
+    assert_eq!(
+        target.canister_state(canister_id).system_state.balance(),
+        balance - fee
+    );
+    let after = get_reply(target.ingress(canister_id, "read", vec![]));
+    assert_eq!(after, vec![42, 0, 0, 0, 7, 0, 0, 0, 42, 0, 0, 0]);
+}
+
+#[test]
+fn canister_migration_moves_wasm_chunk_store() {
+    let registry = CanisterId::from_u64(0);
+    let source_subnet = subnet_test_id(1);
+    let nns_subnet = subnet_test_id(2);
+    let target_subnet = subnet_test_id(3);
+    let mut source = ExecutionTestBuilder::new()
+        .with_own_subnet_id(source_subnet)
+        .with_nns_subnet_id(nns_subnet)
+        .with_caller(nns_subnet, registry)
+        .build();
+    let mut target = ExecutionTestBuilder::new()
+        .with_own_subnet_id(target_subnet)
+        .with_nns_subnet_id(nns_subnet)
+        .with_caller(nns_subnet, registry)
+        .build();
+
+    let canister_id = source.create_canister(Cycles::new(1_000_000_000_000));
+    for chunk in [vec![1, 2, 3], vec![4; 5_000]] {
+        source
+            .canister_state_mut(canister_id)
+            .system_state
+            .wasm_chunk_store
+            .insert_chunk(&chunk)
+            .unwrap();
+    }
+    source.stop_canister(canister_id);
+    source.process_stopping_canisters();
+    reroute_canister(&mut target, canister_id, source_subnet);
+
+    let metadata = read_canister_migration_data(
+        &mut source,
+        canister_id,
+        ic00::CanisterMigrationSegment::Metadata,
+    );
+    write_canister_migration_data(&mut target, canister_id, metadata).unwrap();
+    let mut next_index = Some(0);
+    while let Some(index) = next_index {
+        let chunk = read_canister_migration_data(
+            &mut source,
+            canister_id,
+            ic00::CanisterMigrationSegment::WasmChunkStore { index },
+        );
+        next_index = match &chunk {
+            ic00::CanisterMigrationData::WasmChunkStore(chunk) => chunk.next_index,
+            data => panic!("Unexpected migration data {:?}", data),
+        };
+        write_canister_migration_data(&mut target, canister_id, chunk).unwrap();
+    }
+
+    let chunk_hashes = |test: &ExecutionTest| {
+        test.canister_state(canister_id)
+            .system_state
+            .wasm_chunk_store
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(chunk_hashes(&target).len(), 2);
+    assert_eq!(chunk_hashes(&target), chunk_hashes(&source));
+}
+
+#[test]
+fn removing_partially_migrated_canister_rolls_back_migration() {
+    let registry = CanisterId::from_u64(0);
+    let source_subnet = subnet_test_id(1);
+    let nns_subnet = subnet_test_id(2);
+    let target_subnet = subnet_test_id(3);
+    let mut target = ExecutionTestBuilder::new()
+        .with_own_subnet_id(target_subnet)
+        .with_nns_subnet_id(nns_subnet)
+        .with_caller(nns_subnet, registry)
+        .build();
+    let canister_id = canister_test_id(1_000);
+    reroute_canister(&mut target, canister_id, source_subnet);
+
+    let chunk = ic00::CanisterMigrationData::WasmModule(ic00::MigratedWasmChunk {
+        offset: 0,
+        chunk: vec![1, 2, 3],
+    });
+    write_canister_migration_data(&mut target, canister_id, chunk).unwrap();
+    assert_eq!(
+        target.canister_state(canister_id).status(),
+        CanisterStatusType::Stopped
+    );
+
+    let args = ic00::RemoveMigratedCanisterArgs {
+        subnet_id: target_subnet.get(),
+        canister_id: canister_id.get(),
+    };
+    let reply =
+        call_canister_migration_method(&mut target, Method::RemoveMigratedCanister, args.encode())
+            .unwrap();
+    let reply = ic00::RemoveMigratedCanisterReply::decode(&reply).unwrap();
+    assert_eq!(reply.cycles, candid::Nat::from(0_u64));
+    assert!(target.state().canister_state(&canister_id).is_none());
+}
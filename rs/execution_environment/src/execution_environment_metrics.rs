@@ -1,3 +1,6 @@
+use crate::metrics::{
+    duration_histogram, instructions_histogram, messages_histogram, slices_histogram, ScopedMetrics,
+};
 use ic_cycles_account_manager::{
     CRITICAL_ERROR_EXECUTION_CYCLES_REFUND, CRITICAL_ERROR_RESPONSE_CYCLES_REFUND,
 };
@@ -51,6 +54,12 @@ pub(crate) struct ExecutionEnvironmentMetrics {
     pub(crate) invalid_canister_state_error: IntCounter,
     /// Critical error for failed canister creation.
     pub(crate) canister_creation_error: IntCounter,
+    /// Critical error for unexpected failures of query calls made by update
+    /// methods.
+    pub(crate) replicated_query_call_error: IntCounter,
+
+    /// Query calls made by update methods that were executed synchronously.
+    pub(crate) replicated_query_calls: ScopedMetrics,
 }
 impl ExecutionEnvironmentMetrics {
     pub fn new(metrics_registry: &MetricsRegistry) -> Self {
@@ -103,6 +112,34 @@ impl ExecutionEnvironmentMetrics {
                 .error_counter("execution_environment_invalid_canister_state"),
             canister_creation_error: metrics_registry
                 .error_counter("execution_environment_canister_creation_failed"),
+            replicated_query_call_error: metrics_registry
+                .error_counter("execution_environment_replicated_query_call"),
+            replicated_query_calls: ScopedMetrics {
+                duration: duration_histogram(
+                    "execution_replicated_query_calls_duration_seconds",
+                    "The duration of executing query calls made by update \
+                          methods in an inner round in seconds.",
+                    metrics_registry,
+                ),
+                instructions: instructions_histogram(
+                    "execution_replicated_query_calls_instructions",
+                    "The number of instructions executed by query calls made \
+                          by update methods in an inner round.",
+                    metrics_registry,
+                ),
+                slices: slices_histogram(
+                    "execution_replicated_query_calls_slices",
+                    "The number of slices executed by query calls made by \
+                          update methods in an inner round.",
+                    metrics_registry,
+                ),
+                messages: messages_histogram(
+                    "execution_replicated_query_calls_messages",
+                    "The number of messages executed by query calls made by \
+                          update methods in an inner round.",
+                    metrics_registry,
+                ),
+            },
         }
     }
 
@@ -288,5 +288,6 @@ fn dashboard_label_value_from(code: ErrorCode) -> &'static str {
         ReservedCyclesLimitExceededInMemoryGrow => "Canister cannot grow memory due to its reserved cycles limit",
         InsufficientCyclesInMessageMemoryGrow => "Canister does not have enough cycles to grow message memory",
         StopCanisterRequestTimeout => "Stop canister request timed out",
+        CanisterInstallCodeAborted => "Canister install code execution was aborted by a controller",
     }
 }
@@ -2,12 +2,12 @@ use ic_canister_sandbox_replica_controller::sandboxed_execution_controller::Sand
 use ic_config::execution_environment::{Config, MAX_COMPILATION_CACHE_SIZE};
 use ic_config::flag_status::FlagStatus;
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_embedders::wasm_executor::{WasmExecutionResult, WasmExecutor};
+use ic_embedders::wasm_executor::{PausedWasmExecution, WasmExecutionResult, WasmExecutor};
 use ic_embedders::wasm_utils::decoding::decoded_wasm_size;
 use ic_embedders::{wasm_executor::WasmExecutorImpl, WasmExecutionInput, WasmtimeEmbedder};
 use ic_embedders::{CompilationCache, CompilationResult};
 use ic_interfaces::execution_environment::{HypervisorResult, WasmExecutionOutput};
-use ic_logger::ReplicaLogger;
+use ic_logger::{warn, ReplicaLogger};
 use ic_metrics::buckets::decimal_buckets_with_zero;
 use ic_metrics::{buckets::exponential_buckets, MetricsRegistry};
 use ic_registry_subnet_type::SubnetType;
@@ -18,7 +18,7 @@ use ic_system_api::{sandbox_safe_system_state::SandboxSafeSystemState, ApiType};
 use ic_types::{methods::FuncRef, CanisterId, NumBytes, NumInstructions, SubnetId, Time};
 use ic_wasm_types::CanisterModule;
 use prometheus::{Histogram, IntCounter, IntGauge};
-use std::{path::PathBuf, sync::Arc};
+use std::{io::Write, path::PathBuf, sync::Arc};
 
 use crate::execution::common::{apply_canister_state_changes, update_round_limits};
 use crate::execution_environment::{as_round_instructions, CompilationCostHandling, RoundLimits};
@@ -157,6 +157,7 @@ pub struct Hypervisor {
     deterministic_time_slicing: FlagStatus,
     cost_to_compile_wasm_instruction: NumInstructions,
     dirty_page_overhead: NumInstructions,
+    function_profile_dir: Option<PathBuf>,
 }
 
 impl Hypervisor {
@@ -231,6 +232,17 @@ impl Hypervisor {
         embedder_config.subnet_type = own_subnet_type;
         embedder_config.dirty_page_overhead = dirty_page_overhead;
 
+        let compilation_cache = match &config.compilation_cache_dir {
+            Some(dir) => CompilationCache::new_with_disk_tier(
+                config.max_compilation_cache_size,
+                dir,
+                config.max_compilation_cache_disk_size,
+                &embedder_config,
+                log.clone(),
+            ),
+            None => CompilationCache::new(config.max_compilation_cache_size),
+        };
+
         let wasm_executor: Arc<dyn WasmExecutor> = match config.canister_sandboxing_flag {
             FlagStatus::Enabled => {
                 let executor = SandboxedExecutionController::new(
@@ -260,12 +272,13 @@ impl Hypervisor {
             own_subnet_type,
             log,
             cycles_account_manager,
-            compilation_cache: Arc::new(CompilationCache::new(config.max_compilation_cache_size)),
+            compilation_cache: Arc::new(compilation_cache),
             deterministic_time_slicing: config.deterministic_time_slicing,
             cost_to_compile_wasm_instruction: config
                 .embedders_config
                 .cost_to_compile_wasm_instruction,
             dirty_page_overhead,
+            function_profile_dir: config.embedders_config.wasm_function_profile_dir,
         }
     }
 
@@ -292,6 +305,7 @@ impl Hypervisor {
             deterministic_time_slicing,
             cost_to_compile_wasm_instruction,
             dirty_page_overhead,
+            function_profile_dir: None,
         }
     }
 
@@ -388,6 +402,18 @@ impl Hypervisor {
             self.dirty_page_overhead,
             execution_parameters.compute_allocation,
         );
+        let profile_writer = self
+            .function_profile_dir
+            .as_ref()
+            .map(|dir| FunctionProfileWriter {
+                dir: dir.clone(),
+                canister_id: system_state.canister_id(),
+                root_frame: match &func_ref {
+                    FuncRef::Method(method) => method.to_string(),
+                    FuncRef::UpdateClosure(_) | FuncRef::QueryClosure(_) => "callback".to_string(),
+                },
+                log: self.log.clone(),
+            });
         let (compilation_result, execution_result) = Arc::clone(&self.wasm_executor).execute(
             WasmExecutionInput {
                 api_type,
@@ -407,7 +433,10 @@ impl Hypervisor {
                 .observe_compilation_metrics(&compilation_result);
         }
         self.metrics.observe(&execution_result);
-        execution_result
+        match profile_writer {
+            Some(profile_writer) => profile_writer.observe(execution_result),
+            None => execution_result,
+        }
     }
 
     #[doc(hidden)]
@@ -415,3 +444,83 @@ impl Hypervisor {
         self.compilation_cache.clear_for_testing()
     }
 }
+
+/// Appends the Wasm function profiles of finished executions to
+/// `<dir>/<canister_id>.folded` in the folded stack format, where every line
+/// is `<entry point>;<function> <instructions>`.
+struct FunctionProfileWriter {
+    dir: PathBuf,
+    canister_id: CanisterId,
+    root_frame: String,
+    log: ReplicaLogger,
+}
+
+impl FunctionProfileWriter {
+    /// Writes the profile if the execution has finished. Otherwise, wraps the
+    /// paused execution so that the profile is written once it finishes.
+    fn observe(self, result: WasmExecutionResult) -> WasmExecutionResult {
+        match result {
+            WasmExecutionResult::Finished(slice, output, state_changes) => {
+                if let Err(err) = self.write(&output.instance_stats.function_profile) {
+                    warn!(
+                        self.log,
+                        "Failed to write the function profile of canister {}: {}",
+                        self.canister_id,
+                        err
+                    );
+                }
+                WasmExecutionResult::Finished(slice, output, state_changes)
+            }
+            WasmExecutionResult::Paused(slice, paused) => WasmExecutionResult::Paused(
+                slice,
+                Box::new(ProfiledPausedWasmExecution {
+                    paused,
+                    writer: self,
+                }),
+            ),
+        }
+    }
+
+    fn write(&self, profile: &[(String, u64)]) -> std::io::Result<()> {
+        if profile.is_empty() {
+            return Ok(());
+        }
+        let root_frame = self.root_frame.replace(';', ":");
+        let mut lines = String::new();
+        for (function, instructions) in profile {
+            lines.push_str(&format!("{};{} {}\n", root_frame, function, instructions));
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(format!("{}.folded", self.canister_id)))?
+            .write_all(lines.as_bytes())
+    }
+}
+
+/// A paused execution whose function profile is written when it finishes.
+struct ProfiledPausedWasmExecution {
+    paused: Box<dyn PausedWasmExecution>,
+    writer: FunctionProfileWriter,
+}
+
+impl std::fmt::Debug for ProfiledPausedWasmExecution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProfiledPausedWasmExecution")
+            .field("paused", &self.paused)
+            .field("canister_id", &self.writer.canister_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PausedWasmExecution for ProfiledPausedWasmExecution {
+    fn resume(self: Box<Self>, execution_state: &ExecutionState) -> WasmExecutionResult {
+        let Self { paused, writer } = *self;
+        writer.observe(paused.resume(execution_state))
+    }
+
+    fn abort(self: Box<Self>) {
+        self.paused.abort()
+    }
+}
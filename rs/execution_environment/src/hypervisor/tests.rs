@@ -6534,3 +6534,49 @@ fn stable_memory_grow_does_not_reserve_cycles_on_out_of_memory() {
         .reserved_balance();
     assert_eq!(reserved_cycles_before, reserved_cycles_after);
 }
+
+#[test]
+fn wasm_function_profile_counts_instructions_per_function() {
+    let wat = r#"
+        (module
+            (func $work (param $n i32)
+                (loop $loop
+                    (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+                    (br_if $loop (i32.gt_s (local.get $n) (i32.const 0)))
+                )
+            )
+            (func $unused)
+            (func (export "canister_update test")
+                (call $work (i32.const 100))
+            )
+        )"#;
+    let profile_dir = tempfile::tempdir().unwrap();
+    let mut test = ExecutionTestBuilder::new()
+        .with_wasm_function_profile_dir(profile_dir.path().to_path_buf())
+        .build();
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let result = test.ingress(canister_id, "test", vec![]);
+    assert_empty_reply(result);
+
+    let profile =
+        std::fs::read_to_string(profile_dir.path().join(format!("{}.folded", canister_id)))
+            .unwrap();
+    let mut lines: Vec<_> = profile.lines().collect();
+    lines.sort();
+    assert_eq!(lines.len(), 2, "{}", profile);
+    assert!(lines[0].starts_with("canister_update test;func_2 "));
+    assert!(lines[1].starts_with("canister_update test;work "));
+    let work_instructions: u64 = lines[1].rsplit(' ').next().unwrap().parse().unwrap();
+    assert!(work_instructions > 100);
+
+    // Profiling does not change the number of executed instructions.
+    let profiled_instructions = test.canister_executed_instructions(canister_id);
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let result = test.ingress(canister_id, "test", vec![]);
+    assert_empty_reply(result);
+    assert_eq!(
+        profiled_instructions,
+        test.canister_executed_instructions(canister_id)
+    );
+}
@@ -63,6 +63,26 @@ impl Ic00MethodPermissions {
                 allow_remote_subnet_sender: true,
                 allow_only_nns_subnet_sender: false,
             },
+            Ic00Method::AbortInstallCode => Self {
+                method,
+                allow_remote_subnet_sender: true,
+                allow_only_nns_subnet_sender: false,
+            },
+            Ic00Method::CanisterMethodStats => Self {
+                method,
+                allow_remote_subnet_sender: true,
+                allow_only_nns_subnet_sender: false,
+            },
+            Ic00Method::ResetCanisterMethodStats => Self {
+                method,
+                allow_remote_subnet_sender: true,
+                allow_only_nns_subnet_sender: false,
+            },
+            Ic00Method::CanisterHistory => Self {
+                method,
+                allow_remote_subnet_sender: true,
+                allow_only_nns_subnet_sender: false,
+            },
             Ic00Method::InstallChunkedCode => Self {
                 method,
                 allow_remote_subnet_sender: true,
@@ -156,6 +176,15 @@ impl Ic00MethodPermissions {
                 allow_remote_subnet_sender: true,
                 allow_only_nns_subnet_sender: false,
             },
+            Ic00Method::ReadCanisterMigrationData
+            | Ic00Method::WriteCanisterMigrationData
+            | Ic00Method::RemoveMigratedCanister
+            | Ic00Method::FinishCanisterMigration
+            | Ic00Method::AbortCanisterMigration => Self {
+                method,
+                allow_remote_subnet_sender: true,
+                allow_only_nns_subnet_sender: true,
+            },
         }
     }
 
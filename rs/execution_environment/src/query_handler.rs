@@ -34,7 +34,7 @@ use ic_types::{
     ingress::WasmResult,
     messages::{
         Blob, Certificate, CertificateDelegation, HttpQueryResponse, HttpQueryResponseReply,
-        UserQuery,
+        Request, Response, UserQuery,
     },
     CanisterId, NumInstructions,
 };
@@ -54,6 +54,7 @@ pub(crate) use self::query_scheduler::{QueryScheduler, QuerySchedulerFlag};
 use self::query_stats::QueryStatsCollector;
 use ic_ic00_types::{BitcoinGetBalanceArgs, BitcoinGetUtxosArgs, Payload, QueryMethod};
 use ic_replicated_state::NetworkTopology;
+use prometheus::IntCounter;
 
 /// Convert an object into CBOR binary.
 fn into_cbor<R: Serialize>(r: &R) -> Vec<u8> {
@@ -130,6 +131,7 @@ impl InternalHttpQueryHandler {
         local_query_execution_stats: QueryStatsCollector,
     ) -> Self {
         let query_cache_capacity = config.query_cache_capacity;
+        let query_cache_max_staleness = config.query_cache_max_staleness;
         Self {
             log,
             hypervisor,
@@ -139,7 +141,11 @@ impl InternalHttpQueryHandler {
             max_instructions_per_query,
             cycles_account_manager,
             local_query_execution_stats,
-            query_cache: query_cache::QueryCache::new(metrics_registry, query_cache_capacity),
+            query_cache: query_cache::QueryCache::new(
+                metrics_registry,
+                query_cache_capacity,
+                query_cache_max_staleness,
+            ),
         }
     }
 
@@ -190,6 +196,41 @@ fn route_bitcoin_message(
     Ok(canister_id)
 }
 
+/// Executes a query call made by an update method of a canister against the
+/// given replicated state and returns the response to the call along with the
+/// number of instructions executed by its query call graph.
+///
+/// State changes of the executed queries are discarded.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn execute_replicated_query_call(
+    log: &ReplicaLogger,
+    hypervisor: &Hypervisor,
+    own_subnet_type: SubnetType,
+    state: Labeled<Arc<ReplicatedState>>,
+    config: &Config,
+    request: Arc<Request>,
+    max_instructions_per_query: NumInstructions,
+    query_critical_error: &IntCounter,
+    measurement_scope: &MeasurementScope,
+) -> (Response, NumInstructions) {
+    let mut context = query_context::QueryContext::new_replicated(
+        log,
+        hypervisor,
+        own_subnet_type,
+        state,
+        subnet_memory_capacity(config),
+        config.max_canister_memory_size,
+        max_instructions_per_query,
+        config.max_query_call_graph_depth,
+        config.max_query_call_graph_instructions,
+        config.instruction_overhead_per_query_call,
+        config.composite_queries,
+        query_critical_error,
+    );
+    let response = context.run_replicated(request, measurement_scope);
+    (response, context.instructions_executed())
+}
+
 impl QueryHandler for InternalHttpQueryHandler {
     type State = ReplicatedState;
 
@@ -278,8 +319,13 @@ impl QueryHandler for InternalHttpQueryHandler {
         // Add the query execution result to the query cache  (if the query caching is enabled).
         if self.config.query_caching == FlagStatus::Enabled {
             if let (Some(key), Some(env)) = (cache_entry_key, cache_entry_env) {
-                self.query_cache
-                    .push(key, query_cache::EntryValue::new(env, result.clone()));
+                let value = query_cache::EntryValue::new(
+                    env,
+                    result.clone(),
+                    &context.system_api_call_counters(),
+                    context.evaluated_callbacks(),
+                );
+                self.query_cache.push(key, value);
             }
         }
         result
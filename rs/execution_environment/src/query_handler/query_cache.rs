@@ -1,11 +1,12 @@
 use ic_base_types::{CanisterId, NumBytes};
 use ic_error_types::UserError;
+use ic_interfaces::execution_environment::SystemApiCallCounters;
 use ic_metrics::MetricsRegistry;
 use ic_replicated_state::ReplicatedState;
 use ic_types::{ingress::WasmResult, messages::UserQuery, CountBytes, Cycles, Time, UserId};
 use ic_utils_lru_cache::LruCache;
 use prometheus::{Histogram, IntCounter, IntGauge};
-use std::{mem::size_of_val, sync::Mutex};
+use std::{mem::size_of_val, sync::Mutex, time::Duration};
 
 use crate::metrics::duration_histogram;
 
@@ -13,6 +14,7 @@ use crate::metrics::duration_histogram;
 /// Query Cache metrics.
 pub(crate) struct QueryCacheMetrics {
     pub hits: IntCounter,
+    pub stale_hits: IntCounter,
     pub misses: IntCounter,
     pub evicted_entries: IntCounter,
     pub evicted_entries_duration: Histogram,
@@ -32,6 +34,10 @@ impl QueryCacheMetrics {
                 "execution_query_cache_hits_total",
                 "The total number of replica side query cache hits",
             ),
+            stale_hits: metrics_registry.int_counter(
+                "execution_query_cache_stale_hits_total",
+                "The total number of query cache hits served within the canister staleness window",
+            ),
             misses: metrics_registry.int_counter(
                 "execution_query_cache_misses_total",
                 "The total number of replica side query cache misses",
@@ -116,8 +122,8 @@ impl From<&UserQuery> for EntryKey {
 /// Query Cache entry environment metadata.
 ///
 /// The structure captures the environment metadata. The cache entry is valid
-/// only when its environment metadata matches the current state environment.
-#[derive(PartialEq)]
+/// only when the parts of its environment metadata the query depends on match
+/// the current state environment.
 pub(crate) struct EntryEnv {
     /// The Consensus-determined time when the cache entry was created.
     pub batch_time: Time,
@@ -125,6 +131,8 @@ pub(crate) struct EntryEnv {
     pub canister_version: u64,
     /// Receiving canister cycles balance.
     pub canister_balance: Cycles,
+    /// Receiving canister query cache staleness window.
+    pub max_staleness: Duration,
 }
 
 impl CountBytes for EntryEnv {
@@ -142,6 +150,9 @@ impl TryFrom<(&EntryKey, &ReplicatedState)> for EntryEnv {
             batch_time: state.metadata.batch_time,
             canister_version: canister.system_state.canister_version,
             canister_balance: canister.system_state.balance(),
+            max_staleness: Duration::from_secs(
+                canister.system_state.query_cache_max_staleness.get(),
+            ),
         })
     }
 }
@@ -151,6 +162,12 @@ impl TryFrom<(&EntryKey, &ReplicatedState)> for EntryEnv {
 pub(crate) struct EntryValue {
     env: EntryEnv,
     result: Result<WasmResult, UserError>,
+    /// True if the result does not depend on the batch time, i.e. the query
+    /// did not read the time or the data certificate.
+    ignore_batch_time: bool,
+    /// True if the result does not depend on the canister balance, i.e. the
+    /// query did not read the canister cycles balance.
+    ignore_canister_balance: bool,
 }
 
 impl CountBytes for EntryValue {
@@ -160,16 +177,45 @@ impl CountBytes for EntryValue {
 }
 
 impl EntryValue {
-    pub(crate) fn new(env: EntryEnv, result: Result<WasmResult, UserError>) -> Self {
-        Self { env, result }
+    /// Creates a new cache entry value.
+    ///
+    /// The System API call counters define which parts of the environment
+    /// the result depends on. If the query evaluated response callbacks, the
+    /// result also depends on the other canisters in the call graph, so
+    /// the entry is invalidated on any environment change.
+    pub(crate) fn new(
+        env: EntryEnv,
+        result: Result<WasmResult, UserError>,
+        system_api_call_counters: &SystemApiCallCounters,
+        evaluated_callbacks: bool,
+    ) -> Self {
+        let ignore_batch_time = !evaluated_callbacks
+            && system_api_call_counters.time == 0
+            && system_api_call_counters.data_certificate_copy == 0;
+        let ignore_canister_balance = !evaluated_callbacks
+            && system_api_call_counters.canister_cycle_balance == 0
+            && system_api_call_counters.canister_cycle_balance128 == 0;
+        Self {
+            env,
+            result,
+            ignore_batch_time,
+            ignore_canister_balance,
+        }
     }
 
-    fn is_valid(&self, env: &EntryEnv) -> bool {
-        self.env == *env
+    /// Returns true if the entry may be served in the given environment.
+    ///
+    /// The canister version must always match. The time and the canister
+    /// balance must match only if the query read them, and only outside the
+    /// canister staleness window.
+    fn is_valid(&self, env: &EntryEnv, max_staleness: Duration) -> bool {
+        self.is_valid_canister_version(env)
+            && (self.is_within_staleness(env, max_staleness)
+                || (self.is_valid_time(env) && self.is_valid_canister_balance(env)))
     }
 
     fn is_valid_time(&self, env: &EntryEnv) -> bool {
-        self.env.batch_time == env.batch_time
+        self.ignore_batch_time || self.env.batch_time == env.batch_time
     }
 
     fn is_valid_canister_version(&self, env: &EntryEnv) -> bool {
@@ -177,7 +223,15 @@ impl EntryValue {
     }
 
     fn is_valid_canister_balance(&self, env: &EntryEnv) -> bool {
-        self.env.canister_balance == env.canister_balance
+        self.ignore_canister_balance || self.env.canister_balance == env.canister_balance
+    }
+
+    /// Returns true if the entry is not older than the canister staleness
+    /// window capped by the given `max_staleness`.
+    fn is_within_staleness(&self, env: &EntryEnv, max_staleness: Duration) -> bool {
+        let max_staleness = env.max_staleness.min(max_staleness);
+        !max_staleness.is_zero()
+            && env.batch_time.saturating_sub(self.env.batch_time) <= max_staleness
     }
 
     fn result(&self) -> Result<WasmResult, UserError> {
@@ -195,6 +249,8 @@ pub(crate) struct QueryCache {
     // We can't use `RwLock`, as the `LruCache::get()` requires mutable reference
     // to update the LRU.
     cache: Mutex<LruCache<EntryKey, EntryValue>>,
+    // The upper limit on the canister-defined staleness window.
+    max_staleness: Duration,
     // Query cache metrics (public for tests)
     pub(crate) metrics: QueryCacheMetrics,
 }
@@ -206,9 +262,14 @@ impl CountBytes for QueryCache {
 }
 
 impl QueryCache {
-    pub(crate) fn new(metrics_registry: &MetricsRegistry, capacity: NumBytes) -> Self {
+    pub(crate) fn new(
+        metrics_registry: &MetricsRegistry,
+        capacity: NumBytes,
+        max_staleness: Duration,
+    ) -> Self {
         QueryCache {
             cache: Mutex::new(LruCache::new(capacity)),
+            max_staleness,
             metrics: QueryCacheMetrics::new(metrics_registry),
         }
     }
@@ -222,10 +283,13 @@ impl QueryCache {
         let now = env.batch_time;
 
         if let Some(value) = cache.get(key) {
-            if value.is_valid(env) {
+            if value.is_valid(env, self.max_staleness) {
                 let res = value.result();
                 // Update the metrics.
                 self.metrics.hits.inc();
+                if !value.is_valid_time(env) || !value.is_valid_canister_balance(env) {
+                    self.metrics.stale_hits.inc();
+                }
                 let count_bytes = cache.count_bytes() as i64;
                 self.metrics.count_bytes.set(count_bytes);
                 // The cache entry is valid, return it.
@@ -279,6 +343,7 @@ impl QueryCache {
 mod tests {
     use std::time::Duration;
 
+    use ic_interfaces::execution_environment::SystemApiCallCounters;
     use ic_state_machine_tests::WasmResult;
     use ic_types::{time, Cycles};
 
@@ -291,8 +356,14 @@ mod tests {
             batch_time: current_time,
             canister_version: 1,
             canister_balance: Cycles::new(0),
+            max_staleness: Duration::ZERO,
         };
-        let entry_value = EntryValue::new(entry_env, Result::Ok(WasmResult::Reply(vec![])));
+        let entry_value = EntryValue::new(
+            entry_env,
+            Result::Ok(WasmResult::Reply(vec![])),
+            &SystemApiCallCounters::default(),
+            false,
+        );
         let forward_time = current_time + Duration::from_secs(2);
         assert_eq!(2.0, entry_value.elapsed_seconds(forward_time));
 
@@ -1,7 +1,7 @@
 use crate::{
     execution::common::{self, validate_method},
     execution::nonreplicated_query::execute_non_replicated_query,
-    execution_environment::{as_round_instructions, RoundLimits},
+    execution_environment::{as_num_instructions, as_round_instructions, RoundLimits},
     hypervisor::Hypervisor,
     metrics::{MeasurementScope, QueryHandlerMetrics, QUERY_HANDLER_CRITICAL_ERROR},
     NonReplicatedQueryKind, RoundInstructions,
@@ -11,12 +11,14 @@ use ic_config::flag_status::FlagStatus;
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
 use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
-use ic_interfaces::execution_environment::{ExecutionMode, HypervisorError, SubnetAvailableMemory};
+use ic_interfaces::execution_environment::{
+    ExecutionMode, HypervisorError, SubnetAvailableMemory, SystemApiCallCounters,
+};
 use ic_interfaces_state_manager::Labeled;
 use ic_logger::{error, ReplicaLogger};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    CallContextAction, CallOrigin, CanisterState, NetworkTopology, ReplicatedState,
+    CallContextAction, CallOrigin, CanisterState, CanisterStatus, NetworkTopology, ReplicatedState,
 };
 use ic_system_api::{ApiType, ExecutionParameters, InstructionLimits};
 use ic_types::{
@@ -86,10 +88,11 @@ pub(super) struct QueryContext<'a> {
     state: Labeled<Arc<ReplicatedState>>,
     network_topology: Arc<NetworkTopology>,
     // Certificate for certified queries + canister ID of the root query of this context
-    data_certificate: (Vec<u8>, CanisterId),
+    data_certificate: Option<(Vec<u8>, CanisterId)>,
     max_canister_memory_size: NumBytes,
     max_instructions_per_query: NumInstructions,
     max_query_call_graph_depth: usize,
+    max_query_call_graph_instructions: NumInstructions,
     instruction_overhead_per_query_call: RoundInstructions,
     round_limits: RoundLimits,
     composite_queries: FlagStatus,
@@ -98,6 +101,12 @@ pub(super) struct QueryContext<'a> {
     query_context_time_limit: Duration,
     query_critical_error: &'a IntCounter,
     local_query_execution_stats: Option<&'a QueryStatsCollector>,
+    // System API calls of all the executions in this context.
+    system_api_call_counters: SystemApiCallCounters,
+    // True if at least one response callback was executed in this context.
+    evaluated_callbacks: bool,
+    // True if the context executes query calls made by update methods.
+    replicated: bool,
 }
 
 impl<'a> QueryContext<'a> {
@@ -133,10 +142,11 @@ impl<'a> QueryContext<'a> {
             own_subnet_type,
             state,
             network_topology,
-            data_certificate: (data_certificate, canister_id),
+            data_certificate: Some((data_certificate, canister_id)),
             max_canister_memory_size,
             max_instructions_per_query,
             max_query_call_graph_depth,
+            max_query_call_graph_instructions,
             instruction_overhead_per_query_call: as_round_instructions(
                 instruction_overhead_per_query_call,
             ),
@@ -146,9 +156,56 @@ impl<'a> QueryContext<'a> {
             query_context_time_limit: max_query_call_walltime,
             query_critical_error,
             local_query_execution_stats,
+            system_api_call_counters: SystemApiCallCounters::default(),
+            evaluated_callbacks: false,
+            replicated: false,
         }
     }
 
+    /// Creates a context for executing inter-canister query calls made by
+    /// update methods in replicated execution.
+    ///
+    /// The context does not have a wall time limit and does not provide a
+    /// data certificate to the executed queries, so that the result of the
+    /// execution is deterministic.
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn new_replicated(
+        log: &'a ReplicaLogger,
+        hypervisor: &'a Hypervisor,
+        own_subnet_type: SubnetType,
+        state: Labeled<Arc<ReplicatedState>>,
+        subnet_available_memory: SubnetAvailableMemory,
+        max_canister_memory_size: NumBytes,
+        max_instructions_per_query: NumInstructions,
+        max_query_call_graph_depth: usize,
+        max_query_call_graph_instructions: NumInstructions,
+        instruction_overhead_per_query_call: NumInstructions,
+        composite_queries: FlagStatus,
+        query_critical_error: &'a IntCounter,
+    ) -> Self {
+        let mut context = Self::new(
+            log,
+            hypervisor,
+            own_subnet_type,
+            state,
+            vec![],
+            subnet_available_memory,
+            max_canister_memory_size,
+            max_instructions_per_query,
+            max_query_call_graph_depth,
+            max_query_call_graph_instructions,
+            Duration::MAX,
+            instruction_overhead_per_query_call,
+            composite_queries,
+            CanisterId::ic_00(),
+            query_critical_error,
+            None,
+        );
+        context.data_certificate = None;
+        context.replicated = true;
+        context
+    }
+
     /// Executes the given query sent by an end user.
     ///
     /// - If it produces a response return the response.
@@ -385,7 +442,7 @@ impl<'a> QueryContext<'a> {
         let execution_parameters = self.execution_parameters(&canister, instruction_limits);
 
         let data_certificate = self.get_data_certificate(&canister.canister_id());
-        let (mut canister, instructions_left, result, call_context_id) =
+        let (mut canister, instructions_left, result, call_context_id, call_counters) =
             execute_non_replicated_query(
                 query_kind,
                 method_name,
@@ -400,6 +457,8 @@ impl<'a> QueryContext<'a> {
                 self.query_critical_error,
             );
         let instructions_executed = instruction_limit - instructions_left;
+        self.system_api_call_counters
+            .saturating_accumulate(&call_counters);
 
         let ingress_payload_size = method_payload.len();
         let egress_payload_size = match &result {
@@ -560,6 +619,9 @@ impl<'a> QueryContext<'a> {
             &mut self.round_limits,
             self.query_critical_error,
         );
+        self.evaluated_callbacks = true;
+        self.system_api_call_counters
+            .saturating_accumulate(&output.system_api_call_counters);
 
         let canister_current_memory_usage = canister.memory_usage();
         let canister_current_message_memory_usage = canister.message_memory_usage();
@@ -658,6 +720,8 @@ impl<'a> QueryContext<'a> {
                 &mut self.round_limits,
                 self.query_critical_error,
             );
+        self.system_api_call_counters
+            .saturating_accumulate(&cleanup_output.system_api_call_counters);
 
         canister.execution_state = Some(output_execution_state);
         match cleanup_output.wasm_result {
@@ -715,6 +779,24 @@ impl<'a> QueryContext<'a> {
             }
         };
 
+        // Query calls made by update methods are rejected like regular calls
+        // if the callee is not running.
+        if self.replicated {
+            let err = match canister.system_state.status {
+                CanisterStatus::Running { .. } => None,
+                CanisterStatus::Stopping { .. } => Some(ErrorCode::CanisterStopping),
+                CanisterStatus::Stopped => Some(ErrorCode::CanisterStopped),
+            };
+            if let Some(code) = err {
+                return ExecutionResult::Response(to_query_result(Payload::Reject(
+                    RejectContext::from(UserError::new(
+                        code,
+                        format!("Canister {} is not running", canister_id),
+                    )),
+                )));
+            }
+        }
+
         let call_origin = CallOrigin::CanisterQuery(request.sender, request.sender_reply_callback);
 
         let method = match wasm_query_method(canister, request.method_name.clone()) {
@@ -772,6 +854,52 @@ impl<'a> QueryContext<'a> {
         }
     }
 
+    /// Executes the given query call sent by an update method of a canister
+    /// along with its outgoing query calls and returns the response to it.
+    ///
+    /// Errors that abort the evaluation of the query call graph, e.g.
+    /// exceeding its depth or instruction limit, are returned as a reject
+    /// response.
+    pub(super) fn run_replicated(
+        &mut self,
+        request: Arc<Request>,
+        measurement_scope: &MeasurementScope,
+    ) -> Response {
+        let result = match self.handle_request(Arc::clone(&request), measurement_scope) {
+            ExecutionResult::Response(response) => response,
+            ExecutionResult::Calls(canister, call_origin, requests) => evaluate_query_call_graph(
+                self,
+                canister,
+                call_origin,
+                requests,
+                self.max_query_call_graph_depth,
+                measurement_scope,
+            ),
+            ExecutionResult::SystemError(err) => QueryResponse::UserError(err),
+        };
+        match result {
+            QueryResponse::CanisterResponse(response) => response,
+            QueryResponse::UserError(err) => Response {
+                originator: request.sender,
+                respondent: request.receiver,
+                originator_reply_callback: request.sender_reply_callback,
+                response_payload: Payload::Reject(RejectContext::from(err)),
+                refund: Cycles::zero(),
+            },
+            QueryResponse::UserResponse(_) => {
+                unreachable!("A canister query cannot produce a user response.");
+            }
+        }
+    }
+
+    /// Returns the number of instructions executed by all queries and
+    /// response callbacks in this context, including the overhead of the
+    /// query calls.
+    pub(super) fn instructions_executed(&self) -> NumInstructions {
+        let instructions_left = as_num_instructions(self.round_limits.instructions);
+        self.max_query_call_graph_instructions - instructions_left
+    }
+
     /// Extracts the query result from the call context action.
     fn action_to_result(
         &self,
@@ -974,10 +1102,21 @@ impl<'a> QueryContext<'a> {
     }
 
     fn get_data_certificate(&self, canister_id: &CanisterId) -> Option<Vec<u8>> {
-        if canister_id != &self.data_certificate.1 {
-            None
-        } else {
-            Some(self.data_certificate.0.clone())
+        match &self.data_certificate {
+            Some((data_certificate, id)) if id == canister_id => Some(data_certificate.clone()),
+            _ => None,
         }
     }
+
+    /// Returns the System API call counters accumulated over all the
+    /// executions in this context.
+    pub(super) fn system_api_call_counters(&self) -> SystemApiCallCounters {
+        self.system_api_call_counters
+    }
+
+    /// Returns true if at least one response callback was executed, i.e.
+    /// the result depends on the replies of other canisters.
+    pub(super) fn evaluated_callbacks(&self) -> bool {
+        self.evaluated_callbacks
+    }
 }
@@ -5,7 +5,7 @@ use ic_config::execution_environment::INSTRUCTION_OVERHEAD_PER_QUERY_CALL;
 use ic_error_types::{ErrorCode, UserError};
 use ic_ic00_types::{BitcoinGetBalanceArgs, BitcoinGetUtxosArgs, Payload};
 use ic_registry_subnet_type::SubnetType;
-use ic_replicated_state::canister_state::system_state::CyclesUseCase;
+use ic_replicated_state::{canister_state::system_state::CyclesUseCase, CanisterStatus};
 use ic_test_utilities::{
     types::ids::user_test_id,
     universal_canister::{call_args, wasm},
@@ -600,7 +600,7 @@ fn query_cache_env_different_batch_time_returns_different_results() {
             source: user_test_id(1),
             receiver: canister_id,
             method_name: "query".into(),
-            method_payload: wasm().reply_data(&[42]).build(),
+            method_payload: wasm().time().reply_data(&[42]).build(),
             ingress_expiry: 0,
             nonce: None,
         },
@@ -618,7 +618,7 @@ fn query_cache_env_different_batch_time_returns_different_results() {
             source: user_test_id(1),
             receiver: canister_id,
             method_name: "query".into(),
-            method_payload: wasm().reply_data(&[42]).build(),
+            method_payload: wasm().time().reply_data(&[42]).build(),
             ingress_expiry: 0,
             nonce: None,
         },
@@ -659,7 +659,7 @@ fn query_cache_env_invalidated_entries_negative_duration_works() {
             source: user_test_id(1),
             receiver: canister_id,
             method_name: "query".into(),
-            method_payload: wasm().reply_data(&[42]).build(),
+            method_payload: wasm().time().reply_data(&[42]).build(),
             ingress_expiry: 0,
             nonce: None,
         },
@@ -677,7 +677,7 @@ fn query_cache_env_invalidated_entries_negative_duration_works() {
             source: user_test_id(1),
             receiver: canister_id,
             method_name: "query".into(),
-            method_payload: wasm().reply_data(&[42]).build(),
+            method_payload: wasm().time().reply_data(&[42]).build(),
             ingress_expiry: 0,
             nonce: None,
         },
@@ -768,7 +768,7 @@ fn query_cache_env_different_canister_balance_returns_different_results() {
             source: user_test_id(1),
             receiver: canister_id,
             method_name: "query".into(),
-            method_payload: wasm().reply_data(&[42]).build(),
+            method_payload: wasm().cycles_balance().reply_data(&[42]).build(),
             ingress_expiry: 0,
             nonce: None,
         },
@@ -788,7 +788,7 @@ fn query_cache_env_different_canister_balance_returns_different_results() {
             source: user_test_id(1),
             receiver: canister_id,
             method_name: "query".into(),
-            method_payload: wasm().reply_data(&[42]).build(),
+            method_payload: wasm().cycles_balance().reply_data(&[42]).build(),
             ingress_expiry: 0,
             nonce: None,
         },
@@ -825,7 +825,7 @@ fn query_cache_env_combined_invalidation() {
             source: user_test_id(1),
             receiver: canister_id,
             method_name: "query".into(),
-            method_payload: wasm().reply_data(&[42]).build(),
+            method_payload: wasm().time().cycles_balance().reply_data(&[42]).build(),
             ingress_expiry: 0,
             nonce: None,
         },
@@ -844,7 +844,7 @@ fn query_cache_env_combined_invalidation() {
             source: user_test_id(1),
             receiver: canister_id,
             method_name: "query".into(),
-            method_payload: wasm().reply_data(&[42]).build(),
+            method_payload: wasm().time().cycles_balance().reply_data(&[42]).build(),
             ingress_expiry: 0,
             nonce: None,
         },
@@ -864,6 +864,141 @@ fn query_cache_env_combined_invalidation() {
     }
 }
 
+#[test]
+fn query_cache_env_batch_time_is_ignored_if_not_read() {
+    let mut test = ExecutionTestBuilder::new().with_query_caching().build();
+    let canister_id = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let query = UserQuery {
+        source: user_test_id(1),
+        receiver: canister_id,
+        method_name: "query".into(),
+        method_payload: wasm().cycles_balance().reply_data(&[42]).build(),
+        ingress_expiry: 0,
+        nonce: None,
+    };
+    let output_1 = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+    test.state_mut().metadata.batch_time += Duration::from_secs(1);
+    let output_2 = test.query(query, Arc::new(test.state().clone()), vec![]);
+    {
+        let metrics = &downcast_query_handler(test.query_handler())
+            .query_cache
+            .metrics;
+        assert_eq!(1, metrics.hits.get());
+        assert_eq!(0, metrics.stale_hits.get());
+        assert_eq!(1, metrics.misses.get());
+        assert_eq!(output_1, output_2);
+        assert_eq!(0, metrics.invalidated_entries.get());
+    }
+}
+
+#[test]
+fn query_cache_env_canister_balance_is_ignored_if_not_read() {
+    let mut test = ExecutionTestBuilder::new().with_query_caching().build();
+    let canister_id = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let query = UserQuery {
+        source: user_test_id(1),
+        receiver: canister_id,
+        method_name: "query".into(),
+        method_payload: wasm().time().reply_data(&[42]).build(),
+        ingress_expiry: 0,
+        nonce: None,
+    };
+    let output_1 = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+    test.canister_state_mut(canister_id)
+        .system_state
+        .remove_cycles(1_u128.into(), CyclesUseCase::Memory);
+    let output_2 = test.query(query, Arc::new(test.state().clone()), vec![]);
+    {
+        let metrics = &downcast_query_handler(test.query_handler())
+            .query_cache
+            .metrics;
+        assert_eq!(1, metrics.hits.get());
+        assert_eq!(0, metrics.stale_hits.get());
+        assert_eq!(1, metrics.misses.get());
+        assert_eq!(output_1, output_2);
+        assert_eq!(0, metrics.invalidated_entries.get());
+    }
+}
+
+#[test]
+fn query_cache_env_stale_entry_is_served_within_max_staleness() {
+    let mut test = ExecutionTestBuilder::new().with_query_caching().build();
+    let canister_id = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    test.canister_update_query_cache_max_staleness(canister_id, Duration::from_secs(10))
+        .unwrap();
+    let query = UserQuery {
+        source: user_test_id(1),
+        receiver: canister_id,
+        method_name: "query".into(),
+        method_payload: wasm().time().cycles_balance().reply_data(&[42]).build(),
+        ingress_expiry: 0,
+        nonce: None,
+    };
+    let output_1 = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+
+    // Within the staleness window the entry is served despite the changes.
+    test.state_mut().metadata.batch_time += Duration::from_secs(5);
+    test.canister_state_mut(canister_id)
+        .system_state
+        .remove_cycles(1_u128.into(), CyclesUseCase::Memory);
+    let output_2 = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+    {
+        let metrics = &downcast_query_handler(test.query_handler())
+            .query_cache
+            .metrics;
+        assert_eq!(1, metrics.hits.get());
+        assert_eq!(1, metrics.stale_hits.get());
+        assert_eq!(1, metrics.misses.get());
+        assert_eq!(output_1, output_2);
+    }
+
+    // Outside the staleness window the entry is invalidated.
+    test.state_mut().metadata.batch_time += Duration::from_secs(10);
+    let output_3 = test.query(query, Arc::new(test.state().clone()), vec![]);
+    {
+        let metrics = &downcast_query_handler(test.query_handler())
+            .query_cache
+            .metrics;
+        assert_eq!(1, metrics.hits.get());
+        assert_eq!(2, metrics.misses.get());
+        assert_eq!(output_1, output_3);
+        assert_eq!(1, metrics.invalidated_entries.get());
+        assert_eq!(1, metrics.invalidated_entries_by_time.get());
+        assert_eq!(0, metrics.invalidated_entries_by_canister_version.get());
+        assert_eq!(1, metrics.invalidated_entries_by_canister_balance.get());
+    }
+}
+
+#[test]
+fn query_cache_env_stale_entry_is_invalidated_by_canister_version() {
+    let mut test = ExecutionTestBuilder::new().with_query_caching().build();
+    let canister_id = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    test.canister_update_query_cache_max_staleness(canister_id, Duration::from_secs(10))
+        .unwrap();
+    let query = UserQuery {
+        source: user_test_id(1),
+        receiver: canister_id,
+        method_name: "query".into(),
+        method_payload: wasm().reply_data(&[42]).build(),
+        ingress_expiry: 0,
+        nonce: None,
+    };
+    let output_1 = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+    test.canister_state_mut(canister_id)
+        .system_state
+        .canister_version += 1;
+    let output_2 = test.query(query, Arc::new(test.state().clone()), vec![]);
+    {
+        let metrics = &downcast_query_handler(test.query_handler())
+            .query_cache
+            .metrics;
+        assert_eq!(0, metrics.hits.get());
+        assert_eq!(2, metrics.misses.get());
+        assert_eq!(output_1, output_2);
+        assert_eq!(1, metrics.invalidated_entries_by_canister_version.get());
+    }
+}
+
 #[test]
 fn query_cache_env_old_invalid_entry_frees_memory() {
     static BIG_RESPONSE_SIZE: usize = 1_000_000;
@@ -2599,3 +2734,166 @@ fn test_call_context_performance_counter_correctly_reported_on_composite_query()
     assert!(counters[1] < counters[2]);
     assert!(counters[2] < counters[3]);
 }
+
+/// Returns a canister that calls the given method of the callee as a
+/// replicated query call from its `test` update method and replies with the
+/// reply data of the call, or with "rejected" if the call was rejected.
+fn replicated_query_caller_wat(callee: CanisterId, method_name: &str, payload: &[u8]) -> String {
+    let escape =
+        |bytes: &[u8]| -> String { bytes.iter().map(|b| format!("\\{:02x}", b)).collect() };
+    let callee = callee.get().to_vec();
+    format!(
+        r#"(module
+            (import "ic0" "call_new"
+                (func $call_new (param i32 i32 i32 i32 i32 i32 i32 i32)))
+            (import "ic0" "call_data_append" (func $call_data_append (param i32 i32)))
+            (import "ic0" "call_replicated_query" (func $call_replicated_query))
+            (import "ic0" "call_perform" (func $call_perform (result i32)))
+            (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+            (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i32 i32 i32)))
+            (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func $on_reply (param $env i32)
+                (call $msg_arg_data_copy (i32.const 30000) (i32.const 0) (call $msg_arg_data_size))
+                (call $msg_reply_data_append (i32.const 30000) (call $msg_arg_data_size))
+                (call $msg_reply))
+            (func $on_reject (param $env i32)
+                (call $msg_reply_data_append (i32.const 0) (i32.const 8))
+                (call $msg_reply))
+            (table funcref (elem $on_reply $on_reject))
+            (func $test
+                (call $call_new
+                    (i32.const 100) (i32.const {callee_len})
+                    (i32.const 200) (i32.const {method_name_len})
+                    (i32.const 0) (i32.const 0)
+                    (i32.const 1) (i32.const 0))
+                (call $call_data_append (i32.const 1000) (i32.const {payload_len}))
+                (call $call_replicated_query)
+                (drop (call $call_perform)))
+            (export "canister_update test" (func $test))
+            (memory $memory 1)
+            (data (i32.const 0) "rejected")
+            (data (i32.const 100) "{callee}")
+            (data (i32.const 200) "{method_name}")
+            (data (i32.const 1000) "{payload}")
+        )"#,
+        callee_len = callee.len(),
+        method_name_len = method_name.len(),
+        payload_len = payload.len(),
+        callee = escape(&callee),
+        method_name = method_name,
+        payload = escape(payload),
+    )
+}
+
+#[test]
+fn replicated_query_call_is_charged_to_the_caller() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_replicated_query_calls()
+        .build();
+    let callee = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let caller = test
+        .canister_from_cycles_and_wat(
+            CYCLES_BALANCE,
+            replicated_query_caller_wat(
+                callee,
+                "query",
+                &wasm().stable_grow(1).stable_size().reply_int().build(),
+            ),
+        )
+        .unwrap();
+    let callee_balance = test.canister_state(callee).system_state.balance();
+    let callee_instructions = test.canister_executed_instructions(callee);
+
+    let result = test.ingress(caller, "test", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(1_i32.to_le_bytes().to_vec()));
+
+    // The callee did not execute any message and was not charged.
+    assert_eq!(
+        test.canister_state(callee).system_state.balance(),
+        callee_balance
+    );
+    assert_eq!(
+        test.canister_executed_instructions(callee),
+        callee_instructions
+    );
+}
+
+#[test]
+fn query_call_from_update_is_not_replicated_without_opt_in() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_replicated_query_calls()
+        .build();
+    let caller = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let callee = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let callee_balance = test.canister_state(callee).system_state.balance();
+
+    let result = test
+        .ingress(
+            caller,
+            "update",
+            wasm()
+                .inter_query(
+                    callee,
+                    call_args().other_side(wasm().stable_grow(1).stable_size().reply_int()),
+                )
+                .build(),
+        )
+        .unwrap();
+    assert_eq!(result, WasmResult::Reply(1_i32.to_le_bytes().to_vec()));
+    assert!(test.canister_state(callee).system_state.balance() < callee_balance);
+}
+
+#[test]
+fn replicated_query_call_to_stopping_canister_is_rejected() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_replicated_query_calls()
+        .build();
+    let callee = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let caller = test
+        .canister_from_cycles_and_wat(
+            CYCLES_BALANCE,
+            replicated_query_caller_wat(callee, "query", &wasm().reply_data(&[1]).build()),
+        )
+        .unwrap();
+    let callee_state = test.canister_state_mut(callee);
+    let call_context_manager = callee_state
+        .system_state
+        .call_context_manager()
+        .unwrap()
+        .clone();
+    callee_state.system_state.status = CanisterStatus::Stopping {
+        call_context_manager,
+        stop_contexts: vec![],
+    };
+
+    let result = test.ingress(caller, "test", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(b"rejected".to_vec()));
+}
+
+#[test]
+fn replicated_query_call_evaluates_composite_query_call_graph() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_composite_queries()
+        .with_replicated_query_calls()
+        .build();
+    let mut canisters = vec![];
+    for _ in 0..2 {
+        canisters.push(test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap());
+    }
+
+    let canister_1 = wasm()
+        .composite_query(
+            canisters[1],
+            call_args().other_side(wasm().reply_data(&[2])),
+        )
+        .build();
+    let caller = test
+        .canister_from_cycles_and_wat(
+            CYCLES_BALANCE,
+            replicated_query_caller_wat(canisters[0], "composite_query", &canister_1),
+        )
+        .unwrap();
+    let result = test.ingress(caller, "test", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply([2_u8].to_vec()));
+}
@@ -7,12 +7,16 @@ use crate::{
     metrics::MeasurementScope,
     util::process_responses,
 };
+use candid::{CandidType, Deserialize, Encode};
 use ic_config::flag_status::FlagStatus;
 use ic_config::subnet_config::SchedulerConfig;
 use ic_crypto_prng::{Csprng, RandomnessPurpose::ExecutionThread};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, UserError};
-use ic_ic00_types::{CanisterStatusType, EcdsaKeyId, Method as Ic00Method};
+use ic_ic00_types::{
+    auto_top_up_spender_subaccount, CanisterChangeDetails, CanisterChangeOrigin,
+    CanisterStatusType, EcdsaKeyId, Method as Ic00Method,
+};
 use ic_interfaces::execution_environment::{ExecutionRoundType, RegistryExecutionSettings};
 use ic_interfaces::execution_environment::{
     IngressHistoryWriter, Scheduler, SubnetAvailableMemory,
@@ -21,7 +25,9 @@ use ic_logger::{debug, error, fatal, info, new_logger, warn, ReplicaLogger};
 use ic_metrics::MetricsRegistry;
 use ic_replicated_state::{
     canister_state::{
-        execution_state::NextScheduledMethod, system_state::CyclesUseCase, NextExecution,
+        execution_state::NextScheduledMethod,
+        system_state::{AutoTopUp, CyclesLedgerAccount, CyclesUseCase},
+        NextExecution,
     },
     page_map::PageAllocatorFileDescriptor,
     testing::ReplicatedStateTesting,
@@ -31,9 +37,10 @@ use ic_system_api::InstructionLimits;
 use ic_types::{
     crypto::canister_threshold_sig::MasterEcdsaPublicKey,
     ingress::{IngressState, IngressStatus},
-    messages::{CanisterMessage, Ingress, MessageId, StopCanisterContext},
+    messages::{CanisterMessage, Ingress, MessageId, Request, StopCanisterContext},
     AccumulatedPriority, CanisterId, ComputeAllocation, Cycles, ExecutionRound, LongExecutionMode,
-    MemoryAllocation, NumBytes, NumInstructions, NumSlices, Randomness, SubnetId, Time,
+    MemoryAllocation, NumBytes, NumInstructions, NumSlices, PrincipalId, Randomness, SubnetId,
+    Time,
 };
 use ic_types::{nominal_cycles::NominalCycles, NumMessages};
 use num_rational::Ratio;
@@ -52,6 +59,27 @@ use crate::util::debug_assert_or_critical_error;
 pub use round_schedule::RoundSchedule;
 use round_schedule::*;
 
+/// The method of the cycles ledger that withdraws cycles from an account
+/// with an ICRC-2 approval to a canister.
+pub(crate) const WITHDRAW_FROM_METHOD: &str = "withdraw_from";
+
+/// The argument of the `withdraw_from` method of the cycles ledger.
+#[derive(CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub(crate) struct WithdrawFromArgs {
+    pub spender_subaccount: Option<Vec<u8>>,
+    pub from: LedgerAccount,
+    pub to: PrincipalId,
+    pub created_at_time: Option<u64>,
+    pub amount: candid::Nat,
+}
+
+/// An ICRC-1 account on the cycles ledger.
+#[derive(CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub(crate) struct LedgerAccount {
+    pub owner: PrincipalId,
+    pub subaccount: Option<Vec<u8>>,
+}
+
 /// Only log potentially spammy messages this often (in rounds). With a block
 /// rate around 1.0, this will result in logging about once every 10 minutes.
 const SPAMMY_LOG_INTERVAL_ROUNDS: u64 = 10 * 60;
@@ -159,11 +187,55 @@ impl SchedulerImpl {
                 Reverse(rs.long_execution_mode),
                 Reverse(rs.has_aborted_or_paused_execution),
                 Reverse(rs.accumulated_priority),
+                Reverse(rs.has_overdue_responses),
                 rs.canister_id,
             )
         });
     }
 
+    /// Returns true if the responses in the input queues of the canister have
+    /// been waiting for at least `max_response_wait_rounds` rounds.
+    fn has_overdue_responses(
+        &self,
+        canister: &CanisterState,
+        current_round: ExecutionRound,
+    ) -> bool {
+        canister.scheduler_state.response_wait_start_round.is_some()
+            && canister.response_wait_rounds(current_round)
+                >= self.config.max_response_wait_rounds.get()
+    }
+
+    /// Moves the canister whose responses have been overdue for the longest
+    /// time to the front of the new executions, so that it gets scheduled
+    /// first on a scheduler core this round regardless of its accumulated
+    /// priority. The canister is still charged the priority credit of a
+    /// scheduled canister.
+    ///
+    /// As one canister with overdue responses is scheduled every round, the
+    /// responses of a canister wait at most `max_response_wait_rounds` plus
+    /// one round per canister whose responses became overdue earlier.
+    fn reserve_slot_for_overdue_responses(
+        &self,
+        new_execution_round_states: &mut [CanisterRoundState],
+        canister_states: &BTreeMap<CanisterId, CanisterState>,
+        current_round: ExecutionRound,
+    ) {
+        let longest_waiting = new_execution_round_states
+            .iter()
+            .enumerate()
+            .filter(|(_, rs)| rs.has_overdue_responses)
+            .max_by_key(|(i, rs)| {
+                (
+                    canister_states[&rs.canister_id].response_wait_rounds(current_round),
+                    Reverse(*i),
+                )
+            })
+            .map(|(i, _)| i);
+        if let Some(i) = longest_waiting {
+            new_execution_round_states[..=i].rotate_right(1);
+        }
+    }
+
     /// Orders the canisters and updates their accumulated priorities according to
     /// the strategy described in RUN-58.
     ///
@@ -211,6 +283,7 @@ impl SchedulerImpl {
         // Compute the priority of the canisters for this round.
         let mut accumulated_priority_invariant = AccumulatedPriority::default();
         let mut accumulated_priority_deviation = 0;
+        let mut number_of_canisters_with_overdue_responses = 0;
         for (&canister_id, canister) in canister_states.iter_mut() {
             if is_reset_round {
                 canister.scheduler_state.accumulated_priority = Default::default();
@@ -223,6 +296,14 @@ impl SchedulerImpl {
                 canister.scheduler_state.long_execution_mode = Default::default();
             }
 
+            // Responses that waited too long get a reserved slot this round
+            // (see `reserve_slot_for_overdue_responses()`), so that canisters
+            // with a higher priority cannot delay them indefinitely.
+            let has_overdue_responses = self.has_overdue_responses(canister, current_round);
+            if has_overdue_responses {
+                number_of_canisters_with_overdue_responses += 1;
+            }
+
             let compute_allocation = canister.scheduler_state.compute_allocation;
             let accumulated_priority = canister.scheduler_state.accumulated_priority;
             round_states.push(CanisterRoundState {
@@ -231,6 +312,7 @@ impl SchedulerImpl {
                 compute_allocation,
                 long_execution_mode: canister.scheduler_state.long_execution_mode,
                 has_aborted_or_paused_execution,
+                has_overdue_responses,
             });
 
             total_compute_allocation_percent += compute_allocation.as_percent() as i64;
@@ -256,6 +338,9 @@ impl SchedulerImpl {
         self.metrics
             .scheduler_accumulated_priority_deviation
             .set((accumulated_priority_deviation as f64 / number_of_canisters as f64).sqrt());
+        self.metrics
+            .canisters_with_overdue_responses_per_round
+            .observe(number_of_canisters_with_overdue_responses as f64);
 
         // Free capacity per canister in multiplied percent.
         // Note, to avoid division by zero when there are no canisters
@@ -332,6 +417,11 @@ impl SchedulerImpl {
         );
 
         self.order_canister_round_states(&mut round_states);
+        self.reserve_slot_for_overdue_responses(
+            &mut round_states[number_of_long_executions..],
+            canister_states,
+            current_round,
+        );
 
         let round_schedule = RoundSchedule::new(
             scheduler_cores,
@@ -475,6 +565,7 @@ impl SchedulerImpl {
         measurement_scope: &MeasurementScope,
         ongoing_long_install_code: bool,
         long_running_canister_ids: BTreeSet<CanisterId>,
+        can_start_extended_install_code: bool,
         registry_settings: &RegistryExecutionSettings,
         ecdsa_subnet_public_keys: &BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
     ) -> ReplicatedState {
@@ -482,7 +573,13 @@ impl SchedulerImpl {
             let mut available_subnet_messages = false;
             let mut loop_detector = state.subnet_queues_loop_detector();
             while let Some(msg) = state.peek_subnet_input() {
-                if can_execute_msg(&msg, ongoing_long_install_code, &long_running_canister_ids) {
+                if can_execute_msg(
+                    &msg,
+                    ongoing_long_install_code,
+                    &long_running_canister_ids,
+                    can_start_extended_install_code,
+                    &state,
+                ) {
                     available_subnet_messages = true;
                     break;
                 }
@@ -676,6 +773,13 @@ impl SchedulerImpl {
                         }
                     })
                     .collect();
+                // Paused executions are aborted at checkpoints, so executions
+                // with the extended `install_code` limit only start in the
+                // first round after a checkpoint to get a full checkpoint
+                // interval to finish.
+                let can_start_extended_install_code = self.deterministic_time_slicing
+                    == FlagStatus::Disabled
+                    || current_round.get() == state.metadata.last_checkpoint_round.get() + 1;
 
                 let mut subnet_round_limits = scheduler_round_limits.subnet_round_limits();
                 if !subnet_round_limits.reached() {
@@ -686,6 +790,7 @@ impl SchedulerImpl {
                         &subnet_measurement_scope,
                         ongoing_long_install_code,
                         long_running_canister_ids,
+                        can_start_extended_install_code,
                         registry_settings,
                         ecdsa_subnet_public_keys,
                     );
@@ -786,6 +891,17 @@ impl SchedulerImpl {
             if total_heap_delta >= self.config.max_heap_delta_per_iteration {
                 break state;
             }
+            // Query calls of update methods to canisters on this subnet are
+            // executed right away, so that the callers receive the responses
+            // in the next iteration.
+            self.exec_env.execute_replicated_query_calls(
+                &mut state,
+                current_round,
+                self.config.max_instructions_per_message_without_dts,
+                &mut round_limits,
+                registry_settings.subnet_size,
+            );
+            scheduler_round_limits.update_canister_round_limits(&round_limits);
             {
                 let _induction_timer = self.metrics.round_inner_iteration_fin_induct.start_timer();
                 self.induct_messages_on_same_subnet(&mut state);
@@ -1064,6 +1180,184 @@ impl SchedulerImpl {
             .observe(canister.compute_allocation().as_percent() as f64 / 100.0);
     }
 
+    /// Tops up the canisters whose balance fell below the threshold of their
+    /// automatic top-up settings with cycles from the respective funding
+    /// canister or cycles ledger account. Top-ups of a canister are rate
+    /// limited by `duration_between_auto_top_ups`.
+    fn top_up_canisters(&self, state: &mut ReplicatedState, subnet_size: usize) {
+        let state_time = state.time();
+        let due: Vec<(CanisterId, AutoTopUp)> = state
+            .canisters_iter()
+            // Postpone top-ups when a canister has a paused execution to avoid
+            // modifying the balance of a canister during an unfinished operation.
+            .filter(|canister| {
+                !canister.has_paused_execution() && !canister.has_paused_install_code()
+            })
+            .filter_map(|canister| {
+                self.cycles_account_manager
+                    .auto_top_up_due(&canister.system_state, state_time)
+                    .map(|auto_top_up| (canister.canister_id(), auto_top_up))
+            })
+            .collect();
+
+        for (canister_id, auto_top_up) in due {
+            let status = self.top_up_canister(state, canister_id, auto_top_up, subnet_size);
+            self.metrics.auto_top_ups.with_label_values(&[status]).inc();
+        }
+    }
+
+    /// Transfers `auto_top_up.amount` cycles from the funding canister to the
+    /// given canister and records the top-up in the canister history, or
+    /// requests the withdrawal from the cycles ledger account.
+    /// Returns the status of the attempt for metrics.
+    fn top_up_canister(
+        &self,
+        state: &mut ReplicatedState,
+        canister_id: CanisterId,
+        auto_top_up: AutoTopUp,
+        subnet_size: usize,
+    ) -> &'static str {
+        let state_time = state.time();
+        let mut canister = match state.take_canister_state(&canister_id) {
+            Some(canister) => canister,
+            None => return "canister_not_found",
+        };
+        // Rate limit all attempts, including the failed ones.
+        canister.system_state.time_of_last_auto_top_up = state_time;
+
+        if let Some(account) = auto_top_up.funding_account {
+            state.put_canister_state(canister);
+            return self.request_auto_top_up_withdrawal(
+                state,
+                canister_id,
+                auto_top_up.funding_canister,
+                account,
+                auto_top_up.amount,
+            );
+        }
+
+        let status = match state.canister_state_mut(&auto_top_up.funding_canister) {
+            None => "funding_canister_not_found",
+            Some(funding_canister)
+                if funding_canister.has_paused_execution()
+                    || funding_canister.has_paused_install_code() =>
+            {
+                "funding_canister_busy"
+            }
+            // The controllers of the funding canister have to list the topped
+            // up canister as a beneficiary. They can revoke this at any time.
+            Some(funding_canister)
+                if !funding_canister
+                    .system_state
+                    .auto_top_up_beneficiaries
+                    .contains(&canister_id) =>
+            {
+                "not_authorized"
+            }
+            Some(funding_canister) => {
+                match self.cycles_account_manager.transfer_auto_top_up(
+                    funding_canister,
+                    &mut canister,
+                    auto_top_up.amount,
+                    subnet_size,
+                ) {
+                    Ok(()) => {
+                        let history_config = self.exec_env.canister_history_config();
+                        if auto_top_up.amount >= history_config.cycles_threshold {
+                            funding_canister.system_state.add_audit_change(
+                                state_time,
+                                CanisterChangeOrigin::from_canister(canister_id.get(), None),
+                                CanisterChangeDetails::cycles_withdrawal(
+                                    canister_id.get(),
+                                    auto_top_up.amount.get(),
+                                ),
+                                history_config.max_cycles_withdrawals,
+                            );
+                        }
+                        canister.system_state.add_canister_change(
+                            state_time,
+                            CanisterChangeOrigin::from_canister(
+                                auto_top_up.funding_canister.get(),
+                                None,
+                            ),
+                            CanisterChangeDetails::auto_top_up(
+                                auto_top_up.funding_canister.get(),
+                                auto_top_up.amount.get(),
+                            ),
+                        );
+                        self.metrics
+                            .auto_top_up_cycles
+                            .observe(auto_top_up.amount.get() as f64);
+                        "success"
+                    }
+                    Err(err) => {
+                        info!(
+                            self.log,
+                            "Automatic top-up of canister {} from {} failed with {}",
+                            canister_id,
+                            auto_top_up.funding_canister,
+                            err
+                        );
+                        "insufficient_funds"
+                    }
+                }
+            }
+        };
+        state.put_canister_state(canister);
+        status
+    }
+
+    /// Asks the cycles ledger to withdraw `amount` cycles from `account` to
+    /// the given canister on behalf of this subnet. The ledger only executes
+    /// the withdrawal if the owner of the account approved the subnet as
+    /// spender with the subaccount of the canister, so the consent is checked
+    /// on every top-up. The response of the ledger is dropped.
+    fn request_auto_top_up_withdrawal(
+        &self,
+        state: &mut ReplicatedState,
+        canister_id: CanisterId,
+        cycles_ledger: CanisterId,
+        account: CyclesLedgerAccount,
+        amount: Cycles,
+    ) -> &'static str {
+        let args = WithdrawFromArgs {
+            spender_subaccount: Some(auto_top_up_spender_subaccount(&canister_id).to_vec()),
+            from: LedgerAccount {
+                owner: account.owner,
+                subaccount: account.subaccount.map(|subaccount| subaccount.to_vec()),
+            },
+            to: canister_id.get(),
+            // Lets the ledger deduplicate the withdrawal.
+            created_at_time: Some(state.time().as_nanos_since_unix_epoch()),
+            amount: candid::Nat::from(amount.get()),
+        };
+        let request = Request {
+            receiver: cycles_ledger,
+            sender: CanisterId::from(self.own_subnet_id),
+            sender_reply_callback: state
+                .metadata
+                .subnet_call_context_manager
+                .next_callback_id(),
+            payment: Cycles::zero(),
+            method_name: WITHDRAW_FROM_METHOD.to_string(),
+            method_payload: Encode!(&args).unwrap(),
+            metadata: None,
+        };
+        match state.push_subnet_output_request(Arc::new(request)) {
+            Ok(()) => "withdrawal_requested",
+            Err((err, _)) => {
+                info!(
+                    self.log,
+                    "Failed to request the automatic top-up of canister {} from {}: {}",
+                    canister_id,
+                    cycles_ledger,
+                    err
+                );
+                "subnet_queue_full"
+            }
+        }
+    }
+
     /// Charge canisters for their resource allocation and usage. Canisters
     /// that did not manage to pay are uninstalled.
     fn charge_canisters_for_resource_allocation_and_usage(
@@ -1274,7 +1568,11 @@ impl SchedulerImpl {
     }
 
     /// Aborts paused execution above `max_paused_executions` based on scheduler priority.
-    fn abort_paused_executions_above_limit(&self, state: &mut ReplicatedState) {
+    fn abort_paused_executions_above_limit(
+        &self,
+        state: &mut ReplicatedState,
+        current_round: ExecutionRound,
+    ) {
         let mut paused_round_states = state
             .canisters_iter()
             .filter_map(|canister| {
@@ -1285,6 +1583,7 @@ impl SchedulerImpl {
                         compute_allocation: Default::default(), // not used
                         long_execution_mode: canister.scheduler_state.long_execution_mode,
                         has_aborted_or_paused_execution: true,
+                        has_overdue_responses: self.has_overdue_responses(canister, current_round),
                     })
                 } else {
                     None
@@ -1304,10 +1603,16 @@ impl SchedulerImpl {
     }
 
     // Code that must be executed unconditionally after each round.
-    fn finish_round(&self, state: &mut ReplicatedState, current_round_type: ExecutionRoundType) {
+    fn finish_round(
+        &self,
+        state: &mut ReplicatedState,
+        current_round: ExecutionRound,
+        current_round_type: ExecutionRoundType,
+    ) {
         match current_round_type {
             ExecutionRoundType::CheckpointRound => {
                 state.metadata.heap_delta_estimate = NumBytes::from(0);
+                state.metadata.last_checkpoint_round = current_round;
                 // `expected_compiled_wasms` will be cleared upon store and load
                 // of a checkpoint because it doesn't exist in the protobuf
                 // metadata, but we make it explicit here anyway.
@@ -1320,7 +1625,7 @@ impl SchedulerImpl {
             }
             ExecutionRoundType::OrdinaryRound => {
                 if self.deterministic_time_slicing == FlagStatus::Enabled {
-                    self.abort_paused_executions_above_limit(state);
+                    self.abort_paused_executions_above_limit(state, current_round);
                 }
             }
         }
@@ -1473,7 +1778,7 @@ impl Scheduler for SchedulerImpl {
                     state.metadata.heap_delta_estimate,
                     self.config.subnet_heap_delta_capacity
                 );
-                self.finish_round(&mut state, current_round_type);
+                self.finish_round(&mut state, current_round, current_round_type);
                 self.metrics
                     .round_skipped_due_to_current_heap_delta_above_limit
                     .inc();
@@ -1629,7 +1934,11 @@ impl Scheduler for SchedulerImpl {
         let round_schedule = {
             let _timer = self.metrics.round_scheduling_duration.start_timer();
 
+            let time = state.time();
             let mut canisters = state.take_canister_states();
+            for canister in canisters.values_mut() {
+                canister.start_input_wait(time, current_round);
+            }
             let round_schedule_candidate = self.apply_scheduling_strategy(
                 &round_log,
                 self.config.scheduler_cores,
@@ -1762,13 +2071,16 @@ impl Scheduler for SchedulerImpl {
                 }
                 {
                     let _timer = self.metrics.round_finalization_charge.start_timer();
+                    // Top up canisters before charging them so that canisters
+                    // with a funding canister do not get uninstalled.
+                    self.top_up_canisters(&mut final_state, registry_settings.subnet_size);
                     self.charge_canisters_for_resource_allocation_and_usage(
                         &mut final_state,
                         registry_settings.subnet_size,
                     );
                 }
             }
-            self.finish_round(&mut final_state, current_round_type);
+            self.finish_round(&mut final_state, current_round, current_round_type);
             final_state
                 .metadata
                 .subnet_metrics
@@ -1900,6 +2212,7 @@ fn execute_canisters_on_thread(
         // - or the canister is blocked by a long-running install code.
         // - or the instruction limit is reached.
         // - or the canister finishes a long execution
+        let mut executed_slices = false;
         loop {
             match canister.next_execution() {
                 NextExecution::None | NextExecution::ContinueInstallCode => {
@@ -1955,6 +2268,7 @@ fn execute_canisters_on_thread(
                 );
             }
             total_slices_executed.inc_assign();
+            executed_slices = true;
             canister = new_canister;
             round_limits.instructions -=
                 as_round_instructions(config.instruction_overhead_per_message);
@@ -1984,6 +2298,13 @@ fn execute_canisters_on_thread(
         if let Some(es) = &mut canister.execution_state {
             es.last_executed_round = round_id;
         }
+        if executed_slices {
+            if let Some(wait) = canister.end_input_wait(time) {
+                metrics
+                    .canister_input_queue_wait
+                    .observe(wait.as_secs_f64());
+            }
+        }
         if !canister.has_input() || rank == 0 {
             // The very first canister is considered to have a full execution round for
             // scheduling purposes even if it did not complete within the round.
@@ -2226,28 +2547,57 @@ fn can_execute_msg(
     msg: &CanisterMessage,
     ongoing_long_install_code: bool,
     long_running_canister_ids: &BTreeSet<CanisterId>,
+    can_start_extended_install_code: bool,
+    state: &ReplicatedState,
 ) -> bool {
+    let maybe_method = match msg {
+        CanisterMessage::Ingress(ingress) => {
+            Ic00Method::from_str(ingress.method_name.as_str()).ok()
+        }
+        CanisterMessage::Request(request) => {
+            Ic00Method::from_str(request.method_name.as_str()).ok()
+        }
+        CanisterMessage::Response(_) => None,
+    };
+
     if let Some(effective_canister_id) = msg.effective_canister_id() {
         if long_running_canister_ids.contains(&effective_canister_id) {
-            return false;
+            // Querying the status of a canister and aborting its pending
+            // `install_code` execution do not conflict with that execution.
+            let allowed_during_install_code = matches!(
+                maybe_method,
+                Some(Ic00Method::CanisterStatus) | Some(Ic00Method::AbortInstallCode)
+            ) && state
+                .canister_state(&effective_canister_id)
+                .map_or(false, |canister| {
+                    canister.next_execution() == NextExecution::ContinueInstallCode
+                });
+            if !allowed_during_install_code {
+                return false;
+            }
         }
     }
 
-    if ongoing_long_install_code {
-        let maybe_install_code_method = match msg {
-            CanisterMessage::Ingress(ingress) => {
-                Ic00Method::from_str(ingress.method_name.as_str()).ok()
-            }
-            CanisterMessage::Request(request) => {
-                Ic00Method::from_str(request.method_name.as_str()).ok()
-            }
-            CanisterMessage::Response(_) => None,
-        };
-
+    let is_install_code = matches!(
+        maybe_method,
+        Some(Ic00Method::InstallCode) | Some(Ic00Method::InstallChunkedCode)
+    );
+    if ongoing_long_install_code && is_install_code {
         // Only one install code message allowed at a time.
-        match maybe_install_code_method {
-            Some(Ic00Method::InstallCode) | Some(Ic00Method::InstallChunkedCode) => return false,
-            _ => {}
+        return false;
+    }
+
+    if !can_start_extended_install_code && is_install_code {
+        // Install code messages of canisters that opted in to the extended
+        // limit wait for the first round after the next checkpoint.
+        let has_extended_limit = msg
+            .effective_canister_id()
+            .and_then(|canister_id| state.canister_state(&canister_id))
+            .map_or(false, |canister| {
+                canister.system_state.extended_install_code_limit
+            });
+        if has_extended_limit {
+            return false;
         }
     }
 
@@ -2293,6 +2643,10 @@ fn get_instructions_limits_for_subnet_message(
             | ComputeInitialEcdsaDealings
             | StartCanister
             | StopCanister
+            | AbortInstallCode
+            | CanisterMethodStats
+            | ResetCanisterMethodStats
+            | CanisterHistory
             | UninstallCode
             | UpdateSettings
             | BitcoinGetBalance
@@ -2307,7 +2661,12 @@ fn get_instructions_limits_for_subnet_message(
             | UploadChunk
             | StoredChunks
             | DeleteChunks
-            | ClearChunkStore => default_limits,
+            | ClearChunkStore
+            | ReadCanisterMigrationData
+            | WriteCanisterMigrationData
+            | RemoveMigratedCanister
+            | FinishCanisterMigration
+            | AbortCanisterMigration => default_limits,
             InstallCode | InstallChunkedCode => InstructionLimits::new(
                 dts,
                 config.max_instructions_per_install_code,
@@ -19,6 +19,9 @@ pub(super) struct CanisterRoundState {
     /// True when there is an aborted or paused long update execution.
     /// Note: this doesn't include paused or aborted install codes.
     pub(super) has_aborted_or_paused_execution: bool,
+    /// True when responses have been waiting in the input queues for at least
+    /// `SchedulerConfig::max_response_wait_rounds` rounds.
+    pub(super) has_overdue_responses: bool,
 }
 
 /// Represents three ordered active Canister ID groups to schedule.
@@ -62,6 +62,8 @@ pub(super) struct SchedulerMetrics {
     pub(super) inner_loop_consumed_non_zero_instructions_count: IntCounter,
     pub(super) inner_round_loop_consumed_max_instructions: IntCounter,
     pub(super) num_canisters_uninstalled_out_of_cycles: IntCounter,
+    pub(super) auto_top_ups: IntCounterVec,
+    pub(super) auto_top_up_cycles: Histogram,
     pub(super) round: ScopedMetrics,
     pub(super) round_preparation_duration: Histogram,
     pub(super) round_preparation_ingress: Histogram,
@@ -83,6 +85,8 @@ pub(super) struct SchedulerMetrics {
     pub(super) round_finalization_charge: Histogram,
     pub(super) canister_heap_delta_debits: Histogram,
     pub(super) heap_delta_rate_limited_canisters_per_round: Histogram,
+    pub(super) canister_input_queue_wait: Histogram,
+    pub(super) canisters_with_overdue_responses_per_round: Histogram,
     pub(super) canisters_not_in_routing_table: IntGauge,
     pub(super) canister_install_code_debits: Histogram,
     pub(super) old_open_call_contexts: IntGaugeVec,
@@ -276,6 +280,16 @@ impl SchedulerMetrics {
                 "The number of canisters that were uninstalled because \
                       they ran out of cycles.",
             ),
+            auto_top_ups: metrics_registry.int_counter_vec(
+                "scheduler_auto_top_ups_total",
+                "The number of automatic top-up attempts, by status.",
+                &["status"],
+            ),
+            auto_top_up_cycles: cycles_histogram(
+                "scheduler_auto_top_up_cycles",
+                "Cycles transferred by successful automatic top-ups.",
+                metrics_registry,
+            ),
             round: ScopedMetrics {
                 duration: duration_histogram(
                     "execution_round_duration_seconds",
@@ -545,6 +559,20 @@ impl SchedulerMetrics {
                 // 0, 1, 2, 5, …, 1000, 2000, 5000
                 decimal_buckets_with_zero(0, 3),
             ),
+            canister_input_queue_wait: metrics_registry.histogram(
+                "scheduler_canister_input_queue_wait_duration_seconds",
+                "The time input messages of a canister waited from the beginning of \
+                the round until the canister got executed, in seconds.",
+                // 0, 0.1s, 0.2s, 0.5s, …, 50s, 100s, 200s, 500s
+                decimal_buckets_with_zero(-1, 2),
+            ),
+            canisters_with_overdue_responses_per_round: metrics_registry.histogram(
+                "scheduler_canisters_with_overdue_responses_per_round",
+                "Number of canisters scheduled preferentially due to overdue responses \
+                in a given round.",
+                // 0, 1, 2, 5, …, 1000, 2000, 5000
+                decimal_buckets_with_zero(0, 3),
+            ),
             canisters_not_in_routing_table: metrics_registry.int_gauge(
                 "replicated_state_canisters_not_in_routing_table",
                 "Number of canisters in the state not assigned to the subnet range in the routing table."
@@ -24,7 +24,7 @@ use ic_ic00_types::{
 };
 use ic_interfaces::execution_environment::{
     ExecutionRoundType, HypervisorError, HypervisorResult, IngressHistoryWriter, InstanceStats,
-    RegistryExecutionSettings, Scheduler, WasmExecutionOutput,
+    RegistryExecutionSettings, Scheduler, SystemApiCallCounters, WasmExecutionOutput,
 };
 use ic_logger::{replica_logger::no_op_logger, ReplicaLogger};
 use ic_metrics::MetricsRegistry;
@@ -535,6 +535,12 @@ impl SchedulerTest {
             )
     }
 
+    pub fn top_up_canisters(&mut self) {
+        let subnet_size = self.subnet_size();
+        self.scheduler
+            .top_up_canisters(self.state.as_mut().unwrap(), subnet_size)
+    }
+
     pub fn induct_messages_on_same_subnet(&mut self) {
         self.scheduler
             .induct_messages_on_same_subnet(self.state.as_mut().unwrap());
@@ -1071,6 +1077,7 @@ impl TestWasmExecutorCore {
                 allocated_bytes: NumBytes::from(0),
                 allocated_message_bytes: NumBytes::from(0),
                 instance_stats: InstanceStats::default(),
+                system_api_call_counters: SystemApiCallCounters::default(),
             };
             self.schedule
                 .push((self.round, canister_id, instructions_to_execute));
@@ -1105,6 +1112,7 @@ impl TestWasmExecutorCore {
             mmap_count: 0,
             mprotect_count: 0,
             copy_page_count: 0,
+            function_profile: vec![],
         };
         let slice = SliceExecutionOutput {
             executed_instructions: instructions_to_execute,
@@ -1115,6 +1123,7 @@ impl TestWasmExecutorCore {
             allocated_message_bytes: NumBytes::from(0),
             num_instructions_left: instructions_left,
             instance_stats,
+            system_api_call_counters: SystemApiCallCounters::default(),
         };
         self.schedule
             .push((self.round, canister_id, instructions_to_execute));
@@ -1211,6 +1220,7 @@ impl TestWasmExecutorCore {
                 on_reply: closure.clone(),
                 on_reject: closure,
                 on_cleanup: None,
+                replicated_query: false,
             })
             .map_err(|err| err.to_string())?;
         let request = Request {
@@ -15,8 +15,9 @@ use ic_config::{
 };
 use ic_error_types::RejectCode;
 use ic_ic00_types::{
-    self as ic00, BoundedHttpHeaders, CanisterHttpResponsePayload, CanisterIdRecord,
-    CanisterStatusType, DerivationPath, EcdsaCurve, EmptyBlob, Method, Payload as _,
+    self as ic00, BoundedHttpHeaders, CanisterChange, CanisterHttpResponsePayload,
+    CanisterIdRecord, CanisterStatusType, DerivationPath, EcdsaCurve, EmptyBlob, Method,
+    Payload as _,
 };
 use ic_interfaces::execution_environment::SubnetAvailableMemory;
 use ic_logger::replica_logger::no_op_logger;
@@ -37,11 +38,12 @@ use ic_test_utilities::{
     },
 };
 use ic_test_utilities_metrics::{
-    fetch_counter, fetch_gauge, fetch_gauge_vec, fetch_histogram_stats, fetch_int_gauge,
-    fetch_int_gauge_vec, metric_vec, HistogramStats,
+    fetch_counter, fetch_gauge, fetch_gauge_vec, fetch_histogram_stats, fetch_int_counter_vec,
+    fetch_int_gauge, fetch_int_gauge_vec, metric_vec, HistogramStats,
 };
 use ic_types::messages::{
-    CallbackId, Payload, RejectContext, Response, StopCanisterCallId, MAX_RESPONSE_COUNT_BYTES,
+    CallbackId, Payload, RejectContext, RequestOrResponse, Response, StopCanisterCallId,
+    MAX_RESPONSE_COUNT_BYTES,
 };
 use ic_types::methods::SystemMethod;
 use ic_types::methods::WasmMethod;
@@ -2981,6 +2983,183 @@ fn scheduler_maintains_canister_order() {
     }
 }
 
+#[test]
+fn scheduler_records_queue_latency_of_canisters() {
+    // In this test we have 4 canisters with one input message each. The round
+    // limit allows executing only one canister per scheduler core, so half of
+    // the canisters wait for the next round, which starts one second later.
+    let mut test = SchedulerTestBuilder::new()
+        .with_scheduler_config(SchedulerConfig {
+            scheduler_cores: 2,
+            max_instructions_per_round: NumInstructions::from(20),
+            max_instructions_per_message: NumInstructions::from(10),
+            max_instructions_per_message_without_dts: NumInstructions::from(10),
+            max_instructions_per_slice: NumInstructions::from(10),
+            instruction_overhead_per_message: NumInstructions::from(0),
+            instruction_overhead_per_canister: NumInstructions::from(10),
+            ..SchedulerConfig::application_subnet()
+        })
+        .build();
+
+    let mut canisters = vec![];
+    for _ in 0..4 {
+        let canister = test.create_canister();
+        test.send_ingress(canister, ingress(10));
+        canisters.push(canister);
+    }
+
+    let start = test.state().time();
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    let waiting = canisters
+        .iter()
+        .filter(|id| test.canister_state(**id).has_input())
+        .count();
+    assert_eq!(waiting, 2);
+
+    test.set_time(start + Duration::from_secs(1));
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+
+    let mut max_wait_nanos = vec![];
+    for canister_id in canisters.iter() {
+        let canister = test.canister_state(*canister_id);
+        assert!(!canister.has_input());
+        assert_eq!(canister.scheduler_state.input_wait_start, None);
+        let stats = &canister.scheduler_state.queue_latency_stats;
+        assert_eq!(stats.num_waits, 1);
+        assert_eq!(stats.rounds_executed, 1);
+        assert_eq!(
+            stats.rounds_with_input,
+            stats.max_wait_nanos / 1_000_000_000 + 1
+        );
+        max_wait_nanos.push(stats.max_wait_nanos);
+    }
+    max_wait_nanos.sort();
+    assert_eq!(max_wait_nanos, vec![0, 0, 1_000_000_000, 1_000_000_000]);
+
+    let wait = fetch_histogram_stats(
+        test.metrics_registry(),
+        "scheduler_canister_input_queue_wait_duration_seconds",
+    )
+    .unwrap();
+    assert_eq!(wait.count, 4);
+    assert_floats_are_equal(wait.sum, 2.0);
+}
+
+#[test]
+fn scheduler_breaks_priority_ties_by_overdue_responses() {
+    let test = SchedulerTestBuilder::new().build();
+    let round_state =
+        |canister_id: u64, accumulated_priority: i64, overdue: bool| CanisterRoundState {
+            canister_id: canister_test_id(canister_id),
+            accumulated_priority: accumulated_priority.into(),
+            compute_allocation: ComputeAllocation::zero(),
+            long_execution_mode: LongExecutionMode::default(),
+            has_aborted_or_paused_execution: false,
+            has_overdue_responses: overdue,
+        };
+    let mut round_states = vec![
+        round_state(1, 100, false),
+        round_state(2, 0, true),
+        round_state(3, 50, false),
+        round_state(4, 50, true),
+        round_state(5, 0, false),
+    ];
+
+    test.scheduler()
+        .order_canister_round_states(&mut round_states);
+
+    let order: Vec<_> = round_states.iter().map(|rs| rs.canister_id).collect();
+    assert_eq!(
+        order,
+        vec![
+            canister_test_id(1),
+            canister_test_id(4),
+            canister_test_id(3),
+            canister_test_id(2),
+            canister_test_id(5),
+        ]
+    );
+}
+
+#[test]
+fn scheduler_bounds_wait_of_overdue_responses() {
+    // In this test two canisters with a high compute allocation receive new
+    // ingress messages every round and keep both scheduler cores busy. The
+    // round limit allows executing only one canister per scheduler core, so
+    // a canister without compute allocation only gets to execute its response
+    // through the slot reserved for overdue responses.
+    let max_response_wait_rounds = 3;
+    let mut test = SchedulerTestBuilder::new()
+        .with_scheduler_config(SchedulerConfig {
+            scheduler_cores: 2,
+            max_instructions_per_round: NumInstructions::from(20),
+            max_instructions_per_message: NumInstructions::from(10),
+            max_instructions_per_message_without_dts: NumInstructions::from(10),
+            max_instructions_per_slice: NumInstructions::from(10),
+            instruction_overhead_per_message: NumInstructions::from(0),
+            instruction_overhead_per_canister: NumInstructions::from(10),
+            max_response_wait_rounds: ExecutionRound::new(max_response_wait_rounds),
+            ..SchedulerConfig::application_subnet()
+        })
+        .build();
+
+    let mut busy_canisters = vec![];
+    for _ in 0..2 {
+        busy_canisters.push(test.create_canister_with(
+            Cycles::new(1_000_000_000_000_000),
+            ComputeAllocation::try_from(49).unwrap(),
+            MemoryAllocation::BestEffort,
+            None,
+            None,
+            None,
+        ));
+    }
+    let caller = test.create_canister();
+    let callee = test.create_canister();
+
+    // Get a response into the input queue of the caller while the busy
+    // canisters are idle.
+    test.send_ingress(
+        caller,
+        ingress(10).call(other_side(callee, 10), on_response(10)),
+    );
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    test.induct_messages_on_same_subnet();
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    test.induct_messages_on_same_subnet();
+    assert_eq!(
+        test.canister_state(caller)
+            .system_state
+            .queues()
+            .input_queues_response_count(),
+        1
+    );
+
+    let mut rounds = 0;
+    while test.canister_state(caller).has_input() {
+        for canister in busy_canisters.iter() {
+            test.send_ingress(*canister, ingress(10));
+        }
+        test.execute_round(ExecutionRoundType::OrdinaryRound);
+        rounds += 1;
+        assert!(
+            rounds <= max_response_wait_rounds + 1,
+            "The response waited for more than {} rounds",
+            rounds
+        );
+    }
+    assert_eq!(rounds, max_response_wait_rounds + 1);
+
+    // The busy canisters executed in every round except the one where the
+    // caller got the reserved slot.
+    let executed_busy_canister_rounds = test
+        .executed_schedule()
+        .into_iter()
+        .filter(|(_, canister, _)| busy_canisters.contains(canister))
+        .count() as u64;
+    assert_eq!(executed_busy_canister_rounds, 2 * rounds - 1);
+}
+
 #[test]
 fn ecdsa_signature_agreements_metric_is_updated() {
     let ecdsa_key = EcdsaKeyId {
@@ -4454,6 +4633,90 @@ fn dts_resume_install_code_after_abort() {
     );
 }
 
+#[test]
+fn dts_extended_install_code_starts_after_checkpoint() {
+    let mut test = SchedulerTestBuilder::new()
+        .with_scheduler_config(SchedulerConfig {
+            scheduler_cores: 2,
+            instruction_overhead_per_message: NumInstructions::from(0),
+            instruction_overhead_per_canister: NumInstructions::from(0),
+            max_instructions_per_round: NumInstructions::from(1000),
+            max_instructions_per_install_code: NumInstructions::new(1000),
+            max_instructions_per_install_code_slice: NumInstructions::new(10),
+            ..SchedulerConfig::application_subnet()
+        })
+        .with_deterministic_time_slicing()
+        .build();
+    test.advance_to_round(ExecutionRound::new(10));
+
+    let canister = test.create_canister();
+    test.canister_state_mut(canister)
+        .system_state
+        .extended_install_code_limit = true;
+    let install_code = TestInstallCode::Upgrade {
+        post_upgrade: instructions(100),
+    };
+    test.inject_install_code_call_to_ic00(canister, install_code);
+
+    // The execution waits for the first round after a checkpoint.
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    test.execute_round(ExecutionRoundType::CheckpointRound);
+    assert!(!test.canister_state(canister).has_paused_install_code());
+    assert_eq!(test.state().subnet_queues().input_queues_message_count(), 1);
+
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    assert!(test.canister_state(canister).has_paused_install_code());
+    assert_eq!(test.state().subnet_queues().input_queues_message_count(), 0);
+    for _ in 0..10 {
+        test.execute_round(ExecutionRoundType::OrdinaryRound);
+    }
+    assert!(!test.canister_state(canister).has_paused_install_code());
+    assert!(!test.canister_state(canister).has_aborted_install_code());
+    let responses = test.get_responses_to_injected_calls();
+    assert_eq!(responses.len(), 1);
+    assert!(matches!(responses[0].response_payload, Payload::Data(_)));
+}
+
+#[test]
+fn dts_canister_status_is_not_blocked_by_long_install_code() {
+    let mut test = SchedulerTestBuilder::new()
+        .with_scheduler_config(SchedulerConfig {
+            scheduler_cores: 2,
+            instruction_overhead_per_message: NumInstructions::from(0),
+            instruction_overhead_per_canister: NumInstructions::from(0),
+            max_instructions_per_round: NumInstructions::from(1000),
+            max_instructions_per_install_code: NumInstructions::new(1000),
+            max_instructions_per_install_code_slice: NumInstructions::new(10),
+            ..SchedulerConfig::application_subnet()
+        })
+        .with_deterministic_time_slicing()
+        .build();
+
+    let canister = test.create_canister();
+    let install_code = TestInstallCode::Upgrade {
+        post_upgrade: instructions(100),
+    };
+    test.inject_install_code_call_to_ic00(canister, install_code);
+
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    assert!(test.canister_state(canister).has_paused_install_code());
+
+    let arg = Encode!(&CanisterIdRecord::from(canister)).unwrap();
+    test.inject_call_to_ic00(
+        Method::CanisterStatus,
+        arg,
+        Cycles::zero(),
+        canister_test_id(10),
+        InputQueueType::LocalSubnet,
+    );
+    assert_eq!(test.state().subnet_queues().input_queues_message_count(), 1);
+
+    // The status message is executed while the install code is in progress.
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    assert_eq!(test.state().subnet_queues().input_queues_message_count(), 0);
+    assert!(test.canister_state(canister).has_paused_install_code());
+}
+
 #[test]
 fn dts_resume_long_execution_after_abort() {
     let mut test = SchedulerTestBuilder::new()
@@ -4924,3 +5187,302 @@ fn clean_in_progress_raw_rand_request_from_subnet_call_context_manager() {
     );
     assert!(test.state().subnet_queues().has_output());
 }
+
+#[test]
+fn auto_top_up_transfers_cycles_and_records_history() {
+    let mut test = SchedulerTestBuilder::new().build();
+    let initial_time = Time::from_nanos_since_unix_epoch(1_000_000_000_000);
+    test.set_time(initial_time);
+
+    let funding_balance = Cycles::new(1_000_000_000_000);
+    let funding_canister = test.create_canister_with(
+        funding_balance,
+        ComputeAllocation::zero(),
+        MemoryAllocation::BestEffort,
+        None,
+        Some(initial_time),
+        None,
+    );
+    let balance = Cycles::new(1_000_000);
+    let canister = test.create_canister_with(
+        balance,
+        ComputeAllocation::zero(),
+        MemoryAllocation::BestEffort,
+        None,
+        Some(initial_time),
+        None,
+    );
+    let amount = Cycles::new(1_000_000_000);
+    test.canister_state_mut(canister).system_state.auto_top_up = Some(AutoTopUp {
+        funding_canister,
+        threshold: Cycles::new(2_000_000),
+        amount,
+        funding_account: None,
+    });
+    test.canister_state_mut(funding_canister)
+        .system_state
+        .auto_top_up_beneficiaries
+        .insert(canister);
+
+    test.top_up_canisters();
+
+    assert_eq!(
+        test.canister_state(canister).system_state.balance(),
+        balance + amount
+    );
+    assert_eq!(
+        test.canister_state(funding_canister).system_state.balance(),
+        funding_balance - amount
+    );
+    let system_state = &test.canister_state(canister).system_state;
+    assert_eq!(system_state.time_of_last_auto_top_up, initial_time);
+    assert_eq!(
+        system_state
+            .get_canister_history()
+            .get_changes(1)
+            .collect::<Vec<_>>(),
+        vec![&Arc::new(CanisterChange::new(
+            initial_time.as_nanos_since_unix_epoch(),
+            system_state.canister_version,
+            CanisterChangeOrigin::from_canister(funding_canister.get(), None),
+            CanisterChangeDetails::auto_top_up(funding_canister.get(), amount.get()),
+        ))]
+    );
+    assert_eq!(
+        fetch_int_counter_vec(test.metrics_registry(), "scheduler_auto_top_ups_total"),
+        metric_vec(&[(&[("status", "success")], 1)]),
+    );
+}
+
+#[test]
+fn auto_top_up_is_rate_limited() {
+    let mut test = SchedulerTestBuilder::new().build();
+    let initial_time = Time::from_nanos_since_unix_epoch(1_000_000_000_000);
+    test.set_time(initial_time);
+
+    let funding_canister = test.create_canister();
+    let balance = Cycles::new(1_000_000);
+    let canister = test.create_canister_with(
+        balance,
+        ComputeAllocation::zero(),
+        MemoryAllocation::BestEffort,
+        None,
+        Some(initial_time),
+        None,
+    );
+    // The threshold stays above the balance after a top-up.
+    let amount = Cycles::new(1_000);
+    test.canister_state_mut(canister).system_state.auto_top_up = Some(AutoTopUp {
+        funding_canister,
+        threshold: Cycles::new(1_000_000_000),
+        amount,
+        funding_account: None,
+    });
+    test.canister_state_mut(funding_canister)
+        .system_state
+        .auto_top_up_beneficiaries
+        .insert(canister);
+
+    test.top_up_canisters();
+    test.top_up_canisters();
+    assert_eq!(
+        test.canister_state(canister).system_state.balance(),
+        balance + amount
+    );
+
+    let duration_between_auto_top_ups = test
+        .scheduler()
+        .cycles_account_manager
+        .duration_between_auto_top_ups();
+    test.set_time(initial_time + duration_between_auto_top_ups);
+    test.top_up_canisters();
+    assert_eq!(
+        test.canister_state(canister).system_state.balance(),
+        balance + amount + amount
+    );
+}
+
+#[test]
+fn auto_top_up_does_not_freeze_the_funding_canister() {
+    let mut test = SchedulerTestBuilder::new().build();
+    let initial_time = Time::from_nanos_since_unix_epoch(1_000_000_000_000);
+    test.set_time(initial_time);
+
+    let funding_balance = Cycles::new(1_000);
+    let funding_canister = test.create_canister_with(
+        funding_balance,
+        ComputeAllocation::zero(),
+        MemoryAllocation::BestEffort,
+        None,
+        Some(initial_time),
+        None,
+    );
+    let balance = Cycles::new(1_000_000);
+    let canister = test.create_canister_with(
+        balance,
+        ComputeAllocation::zero(),
+        MemoryAllocation::BestEffort,
+        None,
+        Some(initial_time),
+        None,
+    );
+    test.canister_state_mut(canister).system_state.auto_top_up = Some(AutoTopUp {
+        funding_canister,
+        threshold: Cycles::new(2_000_000),
+        amount: Cycles::new(1_000_000),
+        funding_account: None,
+    });
+    test.canister_state_mut(funding_canister)
+        .system_state
+        .auto_top_up_beneficiaries
+        .insert(canister);
+
+    test.top_up_canisters();
+
+    assert_eq!(
+        test.canister_state(canister).system_state.balance(),
+        balance
+    );
+    assert_eq!(
+        test.canister_state(funding_canister).system_state.balance(),
+        funding_balance
+    );
+    // Failed attempts are rate limited, too.
+    assert_eq!(
+        test.canister_state(canister)
+            .system_state
+            .time_of_last_auto_top_up,
+        initial_time
+    );
+    assert_eq!(
+        fetch_int_counter_vec(test.metrics_registry(), "scheduler_auto_top_ups_total"),
+        metric_vec(&[(&[("status", "insufficient_funds")], 1)]),
+    );
+}
+
+#[test]
+fn auto_top_up_requires_consent_of_the_funding_canister() {
+    let mut test = SchedulerTestBuilder::new().build();
+    let initial_time = Time::from_nanos_since_unix_epoch(1_000_000_000_000);
+    test.set_time(initial_time);
+
+    let funding_canister = test.create_canister();
+    let funding_balance = test.canister_state(funding_canister).system_state.balance();
+    let balance = Cycles::new(1_000_000);
+    let canister = test.create_canister_with(
+        balance,
+        ComputeAllocation::zero(),
+        MemoryAllocation::BestEffort,
+        None,
+        Some(initial_time),
+        None,
+    );
+    let amount = Cycles::new(1_000);
+    test.canister_state_mut(canister).system_state.auto_top_up = Some(AutoTopUp {
+        funding_canister,
+        threshold: Cycles::new(2_000_000),
+        amount,
+        funding_account: None,
+    });
+    // Another canister is listed as beneficiary, but not `canister`.
+    test.canister_state_mut(funding_canister)
+        .system_state
+        .auto_top_up_beneficiaries
+        .insert(canister_test_id(1234));
+
+    test.top_up_canisters();
+
+    assert_eq!(
+        test.canister_state(canister).system_state.balance(),
+        balance
+    );
+    assert_eq!(
+        test.canister_state(funding_canister).system_state.balance(),
+        funding_balance
+    );
+    assert_eq!(
+        fetch_int_counter_vec(test.metrics_registry(), "scheduler_auto_top_ups_total"),
+        metric_vec(&[(&[("status", "not_authorized")], 1)]),
+    );
+
+    // Once the funding canister consents, the next attempt succeeds.
+    test.canister_state_mut(funding_canister)
+        .system_state
+        .auto_top_up_beneficiaries
+        .insert(canister);
+    let duration_between_auto_top_ups = test
+        .scheduler()
+        .cycles_account_manager
+        .duration_between_auto_top_ups();
+    test.set_time(initial_time + duration_between_auto_top_ups);
+    test.top_up_canisters();
+
+    assert_eq!(
+        test.canister_state(canister).system_state.balance(),
+        balance + amount
+    );
+}
+
+#[test]
+fn auto_top_up_from_cycles_ledger_account_requests_withdrawal() {
+    let mut test = SchedulerTestBuilder::new().build();
+    let initial_time = Time::from_nanos_since_unix_epoch(1_000_000_000_000);
+    test.set_time(initial_time);
+
+    let cycles_ledger = canister_test_id(4321);
+    let balance = Cycles::new(1_000_000);
+    let canister = test.create_canister_with(
+        balance,
+        ComputeAllocation::zero(),
+        MemoryAllocation::BestEffort,
+        None,
+        Some(initial_time),
+        None,
+    );
+    let amount = Cycles::new(1_000_000_000);
+    let account = CyclesLedgerAccount {
+        owner: user_test_id(7).get(),
+        subaccount: Some([1; 32]),
+    };
+    test.canister_state_mut(canister).system_state.auto_top_up = Some(AutoTopUp {
+        funding_canister: cycles_ledger,
+        threshold: Cycles::new(2_000_000),
+        amount,
+        funding_account: Some(account),
+    });
+
+    test.top_up_canisters();
+
+    // The balance only changes once the ledger deposits the cycles.
+    assert_eq!(
+        test.canister_state(canister).system_state.balance(),
+        balance
+    );
+    let own_subnet_id = test.state().metadata.own_subnet_id;
+    let messages: Vec<_> = test.state_mut().output_into_iter().collect();
+    assert_eq!(messages.len(), 1);
+    let request = match &messages[0].1 {
+        RequestOrResponse::Request(request) => request.clone(),
+        RequestOrResponse::Response(_) => panic!("Expected a request"),
+    };
+    assert_eq!(request.sender, CanisterId::from(own_subnet_id));
+    assert_eq!(request.receiver, cycles_ledger);
+    assert_eq!(request.method_name, WITHDRAW_FROM_METHOD);
+    assert_eq!(
+        candid::Decode!(request.method_payload(), WithdrawFromArgs).unwrap(),
+        WithdrawFromArgs {
+            spender_subaccount: Some(auto_top_up_spender_subaccount(&canister).to_vec()),
+            from: LedgerAccount {
+                owner: account.owner,
+                subaccount: Some(vec![1; 32]),
+            },
+            to: canister.get(),
+            created_at_time: Some(initial_time.as_nanos_since_unix_epoch()),
+            amount: candid::Nat::from(amount.get()),
+        }
+    );
+    assert_eq!(
+        fetch_int_counter_vec(test.metrics_registry(), "scheduler_auto_top_ups_total"),
+        metric_vec(&[(&[("status", "withdrawal_requested")], 1)]),
+    );
+}
@@ -6,6 +6,7 @@ use ic_types::CanisterId;
 use prometheus::IntCounter;
 use std::sync::Arc;
 
+pub(crate) const REGISTRY_CANISTER_ID: CanisterId = CanisterId::from_u64(0);
 pub(crate) const GOVERNANCE_CANISTER_ID: CanisterId = CanisterId::from_u64(1);
 
 /// Debug assert a condition, increase an error counter, and log the error.
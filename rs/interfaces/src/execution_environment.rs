@@ -58,6 +58,47 @@ pub struct InstanceStats {
 
     /// Number of pages loaded by copying the data.
     pub copy_page_count: usize,
+
+    /// Number of instructions executed in each Wasm function. Only populated
+    /// if function profiling is enabled in the embedders config.
+    pub function_profile: Vec<(String, u64)>,
+}
+
+/// Counters of the System API calls whose results depend on the environment
+/// (e.g. on the time or on the canister balance) rather than on the canister
+/// state and the message.
+///
+/// The query cache uses them to invalidate only the entries whose results
+/// actually depend on the changed part of the environment.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SystemApiCallCounters {
+    /// Number of calls to `ic0.data_certificate_copy`.
+    pub data_certificate_copy: usize,
+
+    /// Number of calls to `ic0.canister_cycle_balance`.
+    pub canister_cycle_balance: usize,
+
+    /// Number of calls to `ic0.canister_cycle_balance128`.
+    pub canister_cycle_balance128: usize,
+
+    /// Number of calls to `ic0.time`.
+    pub time: usize,
+}
+
+impl SystemApiCallCounters {
+    /// Adds the counters of another execution to these counters.
+    pub fn saturating_accumulate(&mut self, rhs: &Self) {
+        self.data_certificate_copy = self
+            .data_certificate_copy
+            .saturating_add(rhs.data_certificate_copy);
+        self.canister_cycle_balance = self
+            .canister_cycle_balance
+            .saturating_add(rhs.canister_cycle_balance);
+        self.canister_cycle_balance128 = self
+            .canister_cycle_balance128
+            .saturating_add(rhs.canister_cycle_balance128);
+        self.time = self.time.saturating_add(rhs.time);
+    }
 }
 
 /// Errors that can be returned when fetching the available memory on a subnet.
@@ -584,6 +625,16 @@ pub trait SystemApi {
     /// See <https://sdk.dfinity.org/docs/interface-spec/index.html#system-api-call>
     fn ic0_call_on_cleanup(&mut self, fun: u32, env: u32) -> HypervisorResult<()>;
 
+    /// Marks the call under construction as a replicated query call. If the
+    /// callee is a running canister on the same subnet that exports the
+    /// method as a query, the call is executed synchronously within the
+    /// round, its state changes are discarded, and the instructions it
+    /// executes are charged to the caller instead of the callee. Otherwise,
+    /// the call is executed as a regular call.
+    ///
+    /// Can be called only from update methods and their callbacks.
+    fn ic0_call_replicated_query(&mut self) -> HypervisorResult<()>;
+
     /// (deprecated) Please use `ic0_call_cycles_add128` instead, as this API
     /// can only add a 64-bit value.
     ///
@@ -722,7 +773,7 @@ pub trait SystemApi {
     ) -> HypervisorResult<(NumPages, NumInstructions)>;
 
     /// The canister can query the IC for the current time.
-    fn ic0_time(&self) -> HypervisorResult<Time>;
+    fn ic0_time(&mut self) -> HypervisorResult<Time>;
 
     /// The canister can set a global one-off timer at the specific time.
     fn ic0_global_timer_set(&mut self, time: Time) -> HypervisorResult<Time>;
@@ -788,7 +839,7 @@ pub trait SystemApi {
     /// Returns the current balance in cycles.
     ///
     /// Traps if current canister balance cannot fit in a 64-bit value.
-    fn ic0_canister_cycle_balance(&self) -> HypervisorResult<u64>;
+    fn ic0_canister_cycle_balance(&mut self) -> HypervisorResult<u64>;
 
     /// This system call indicates the current cycle balance
     /// of the canister.
@@ -796,7 +847,7 @@ pub trait SystemApi {
     /// The amount of cycles is represented by a 128-bit value
     /// and is copied in the canister memory starting
     /// starting at the location `dst`.
-    fn ic0_canister_cycle_balance128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()>;
+    fn ic0_canister_cycle_balance128(&mut self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()>;
 
     /// (deprecated) Please use `ic0_msg_cycles_available128` instead.
     /// This API supports only 64-bit values.
@@ -897,7 +948,7 @@ pub trait SystemApi {
     /// (i.e. data_certificate_present returns 1).
     /// Traps if data_certificate_present returns 0.
     fn ic0_data_certificate_copy(
-        &self,
+        &mut self,
         dst: u32,
         offset: u32,
         size: u32,
@@ -1041,6 +1092,8 @@ pub struct WasmExecutionOutput {
     pub allocated_bytes: NumBytes,
     pub allocated_message_bytes: NumBytes,
     pub instance_stats: InstanceStats,
+    /// The environment-dependent System API calls performed by the execution.
+    pub system_api_call_counters: SystemApiCallCounters,
 }
 
 impl fmt::Display for WasmExecutionOutput {
@@ -21,6 +21,7 @@ pub struct CanisterSettings {
     pub compute_allocation: Option<candid::Nat>,
     pub memory_allocation: Option<candid::Nat>,
     pub freezing_threshold: Option<candid::Nat>,
+    pub reserved_cycles_limit: Option<candid::Nat>,
 }
 
 /// A wrapper call to the management canister `update_settings` API.
@@ -123,6 +123,12 @@ pub enum SubnetSelection {
 #[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
 pub struct SubnetFilter {
     pub subnet_type: Option<String>,
+    /// Only select subnets all of whose nodes are located in the given
+    /// geography. A geography is a prefix of the comma-separated region of
+    /// a data center in the registry, e.g., "Europe" or "Europe,CH".
+    pub geography: Option<String>,
+    /// Only select subnets that have at least this many nodes.
+    pub min_subnet_size: Option<u64>,
 }
 pub enum NotifyErrorCode {
     /// An internal error in the cycles minting canister (e.g., inconsistent state).
@@ -401,6 +407,23 @@ pub struct SubnetTypesToSubnetsResponse {
     pub data: Vec<(String, Vec<SubnetId>)>,
 }
 
+/// The response to `get_subnet_filters`, i.e., the values that can currently
+/// be used in a `SubnetFilter`.
+#[derive(Serialize, Deserialize, CandidType, Clone, PartialEq, Eq, Debug, Default)]
+pub struct SubnetFiltersResponse {
+    /// The available values of `SubnetFilter::subnet_type`.
+    pub subnet_types: Vec<String>,
+    /// The available values of `SubnetFilter::geography`, along with the
+    /// subnets located in each of them.
+    pub geographies: Vec<(String, Vec<SubnetId>)>,
+    /// The number of nodes of each subnet, as relevant for
+    /// `SubnetFilter::min_subnet_size`.
+    pub subnet_sizes: Vec<(SubnetId, u64)>,
+    /// The time (in seconds since the UNIX epoch) at which the subnet
+    /// information was last fetched from the registry, if ever.
+    pub last_registry_refresh_timestamp_seconds: Option<u64>,
+}
+
 #[derive(Serialize, Deserialize, CandidType, Clone, PartialEq, Eq, Debug, Default)]
 pub struct IcpXdrConversionRate {
     /// The time for which the market data was queried, expressed in UNIX epoch
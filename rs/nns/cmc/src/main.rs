@@ -23,9 +23,18 @@ use ic_ledger_core::tokens::CheckedSub;
 use ic_nervous_system_governance::maturity_modulation::{
     MAX_MATURITY_MODULATION_PERMYRIAD, MIN_MATURITY_MODULATION_PERMYRIAD,
 };
-use ic_nns_common::types::UpdateIcpXdrConversionRatePayload;
+use ic_nns_common::{
+    registry::{get_subnet_record, get_value},
+    types::UpdateIcpXdrConversionRatePayload,
+};
 use ic_nns_constants::{GOVERNANCE_CANISTER_ID, REGISTRY_CANISTER_ID};
-use ic_types::{CanisterId, Cycles, PrincipalId, SubnetId};
+use ic_protobuf::registry::{
+    dc::v1::DataCenterRecord, node::v1::NodeRecord, node_operator::v1::NodeOperatorRecord,
+};
+use ic_registry_keys::{
+    make_data_center_record_key, make_node_operator_record_key, make_node_record_key,
+};
+use ic_types::{CanisterId, Cycles, NodeId, PrincipalId, SubnetId};
 use icp_ledger::{
     AccountIdentifier, Block, BlockIndex, BlockRes, CyclesResponse, Memo, Operation, SendArgs,
     Subaccount, Tokens, TransactionNotification, DEFAULT_TRANSFER_FEE,
@@ -64,6 +73,10 @@ const MAX_NOTIFY_PURGE: usize = 100_000;
 /// This is the minimum amount needed for creating a canister as of October 2023.
 const CREATE_CANISTER_MIN_CYCLES: u64 = 100_000_000_000;
 
+/// How often the information about subnets that is used to resolve subnet
+/// filters (geography, subnet size) is re-fetched from the registry.
+const SUBNET_REGISTRY_INFO_REFRESH_INTERVAL_SECONDS: u64 = 24 * 60 * 60;
+
 thread_local! {
     static STATE: RefCell<Option<State>> = RefCell::new(None);
 }
@@ -195,9 +208,132 @@ pub struct State {
 
     /// This is used to ensure that only one exchange rate update is being performed at a time from heartbeat.
     pub update_exchange_rate_canister_state: Option<UpdateExchangeRateState>,
+
+    /// A cache of the registry information about the subnets known to the
+    /// CMC, used to resolve subnet filters on the geography or the size of
+    /// subnets. It is refreshed periodically from heartbeat.
+    pub subnet_registry_info: Option<BTreeMap<SubnetId, SubnetRegistryInfo>>,
+
+    /// When `subnet_registry_info` was last successfully refreshed.
+    pub subnet_registry_info_refresh_timestamp_seconds: Option<u64>,
+
+    /// When a refresh of `subnet_registry_info` was last started. This is
+    /// used to ensure that at most one refresh is started per
+    /// `SUBNET_REGISTRY_INFO_REFRESH_INTERVAL_SECONDS`, even if refreshes
+    /// fail. It is reset when the lists of subnets change, so that new
+    /// subnets are fetched on the next heartbeat.
+    pub subnet_registry_info_refresh_started_timestamp_seconds: Option<u64>,
 }
 
 impl State {
+    /// Returns the list of subnets in which the given controller is allowed
+    /// to create canisters if no subnet selection is provided.
+    fn get_subnets_for(&self, controller_id: &PrincipalId) -> Vec<SubnetId> {
+        if let Some(subnets) = self.authorized_subnets.get(controller_id) {
+            subnets.clone()
+        } else {
+            self.default_subnets.clone()
+        }
+    }
+
+    /// Returns all subnets that the CMC may create canisters on, i.e., the
+    /// default subnets, the authorized subnets, and the subnets of all types.
+    fn get_all_subnets(&self) -> BTreeSet<SubnetId> {
+        self.default_subnets
+            .iter()
+            .chain(self.authorized_subnets.values().flatten())
+            .chain(
+                self.subnet_types_to_subnets
+                    .iter()
+                    .flat_map(|types_to_subnets| types_to_subnets.values().flatten()),
+            )
+            .copied()
+            .collect()
+    }
+
+    /// Makes the next heartbeat refresh `subnet_registry_info`, so that
+    /// subnets added to the lists of subnets can be selected by subnet
+    /// filters without waiting for the next periodic refresh.
+    fn request_subnet_registry_info_refresh(&mut self) {
+        self.subnet_registry_info_refresh_started_timestamp_seconds = None;
+    }
+
+    /// Replaces `subnet_registry_info` with the information fetched for all
+    /// subnets known to the CMC, keeping the previous entry of the subnets
+    /// whose information could not be fetched (`None`).
+    fn update_subnet_registry_info(
+        &mut self,
+        fetched: BTreeMap<SubnetId, Option<SubnetRegistryInfo>>,
+        now_seconds: u64,
+    ) {
+        let previous = self
+            .subnet_registry_info
+            .take()
+            .expect("subnet registry info is `None`");
+        let subnet_registry_info = fetched
+            .into_iter()
+            .filter_map(|(subnet_id, info)| {
+                info.or_else(|| previous.get(&subnet_id).cloned())
+                    .map(|info| (subnet_id, info))
+            })
+            .collect();
+        self.subnet_registry_info = Some(subnet_registry_info);
+        self.subnet_registry_info_refresh_timestamp_seconds = Some(now_seconds);
+    }
+
+    /// Returns the subnets that satisfy the given filter and that the given
+    /// controller may create canisters on.
+    fn get_subnets_by_filter(
+        &self,
+        controller_id: &PrincipalId,
+        subnet_filter: &SubnetFilter,
+    ) -> Result<Vec<SubnetId>, String> {
+        let subnets = match &subnet_filter.subnet_type {
+            Some(subnet_type) => {
+                let subnet_types_to_subnets = self
+                    .subnet_types_to_subnets
+                    .as_ref()
+                    .expect("subnet types to subnets mapping is `None`");
+                subnet_types_to_subnets
+                    .get(subnet_type)
+                    .map(|set| set.iter().cloned().collect())
+                    .ok_or(format!(
+                        "Provided subnet type {} does not exist",
+                        subnet_type
+                    ))?
+            }
+            None => self.get_subnets_for(controller_id),
+        };
+
+        if subnet_filter.geography.is_none() && subnet_filter.min_subnet_size.is_none() {
+            return Ok(subnets);
+        }
+
+        // Subnets for which the registry information is not (yet) known are
+        // never selected by a geography or size filter.
+        let subnet_registry_info = self
+            .subnet_registry_info
+            .as_ref()
+            .expect("subnet registry info is `None`");
+        let subnets: Vec<SubnetId> = subnets
+            .into_iter()
+            .filter(|subnet_id| {
+                subnet_registry_info
+                    .get(subnet_id)
+                    .map(|info| info.matches(subnet_filter))
+                    .unwrap_or(false)
+            })
+            .collect();
+
+        if subnets.is_empty() {
+            return Err(format!(
+                "No subnet satisfies the provided subnet filter {:?}",
+                subnet_filter
+            ));
+        }
+        Ok(subnets)
+    }
+
     fn encode(&self) -> Vec<u8> {
         candid::encode_one(self).unwrap()
     }
@@ -265,10 +401,74 @@ impl Default for State {
             maturity_modulation_permyriad: Some(0),
             subnet_types_to_subnets: Some(BTreeMap::new()),
             update_exchange_rate_canister_state: Some(UpdateExchangeRateState::default()),
+            subnet_registry_info: Some(BTreeMap::new()),
+            subnet_registry_info_refresh_timestamp_seconds: None,
+            subnet_registry_info_refresh_started_timestamp_seconds: None,
         }
     }
 }
 
+/// What the CMC knows about a subnet from the registry.
+#[derive(Serialize, Deserialize, Clone, CandidType, Eq, PartialEq, Debug, Default)]
+pub struct SubnetRegistryInfo {
+    /// The regions of the data centers that host the nodes of the subnet, as
+    /// found in the registry, e.g., "Europe,CH,Zurich".
+    pub regions: BTreeSet<String>,
+    /// The number of nodes in the subnet.
+    pub size: u64,
+}
+
+impl SubnetRegistryInfo {
+    /// Returns the geographies that all nodes of the subnet are located in,
+    /// from the coarsest to the finest one. For example, if all nodes are in
+    /// data centers in the regions "Europe,CH,Zurich" and "Europe,CH,Geneva",
+    /// this returns `["Europe", "Europe,CH"]`.
+    fn geographies(&self) -> Vec<String> {
+        let mut regions = self.regions.iter().map(|region| region_components(region));
+        let mut common_prefix = match regions.next() {
+            Some(components) => components,
+            None => return vec![],
+        };
+        for components in regions {
+            let common_len = common_prefix
+                .iter()
+                .zip(components.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            common_prefix.truncate(common_len);
+        }
+        (1..=common_prefix.len())
+            .map(|len| common_prefix[..len].join(","))
+            .collect()
+    }
+
+    /// Whether the subnet satisfies the geography and size constraints of
+    /// the given filter. The subnet type is not taken into account.
+    fn matches(&self, filter: &SubnetFilter) -> bool {
+        let geography_matches = match &filter.geography {
+            Some(geography) => self
+                .geographies()
+                .contains(&region_components(geography).join(",")),
+            None => true,
+        };
+        let size_matches = match filter.min_subnet_size {
+            Some(min_subnet_size) => self.size >= min_subnet_size,
+            None => true,
+        };
+        geography_matches && size_matches
+    }
+}
+
+/// Splits a comma-separated region, e.g., "Europe, CH,Zurich", into its
+/// components, e.g., `["Europe", "CH", "Zurich"]`.
+fn region_components(region: &str) -> Vec<String> {
+    region
+        .split(',')
+        .map(|component| component.trim().to_string())
+        .filter(|component| !component.is_empty())
+        .collect()
+}
+
 // Helper to print messages in yellow
 fn print<S: std::convert::AsRef<str>>(s: S)
 where
@@ -377,6 +577,7 @@ fn set_authorized_subnetwork_list(who: Option<PrincipalId>, subnets: Vec<SubnetI
             print("[cycles] setting default subnet list");
             state.default_subnets = subnets;
         }
+        state.request_subnet_registry_info_refresh();
     });
 }
 
@@ -547,6 +748,7 @@ fn add_subnets_to_type(
                 ));
                 let existing_subnets = entry.get_mut();
                 existing_subnets.extend(subnets);
+                state.request_subnet_registry_info_refresh();
                 Ok(())
             }
             Entry::Vacant(_) => Err(ChangeSubnetTypeAssignmentError::TypeDoesNotExist(
@@ -625,6 +827,50 @@ fn get_subnet_types_to_subnets_() {
     over(candid_one, |_: ()| get_subnet_types_to_subnets())
 }
 
+#[candid_method(query, rename = "get_subnet_filters")]
+fn get_subnet_filters() -> SubnetFiltersResponse {
+    with_state(|state: &State| {
+        let subnet_types = state
+            .subnet_types_to_subnets
+            .as_ref()
+            .expect("subnet types to subnets mapping is not `None`")
+            .keys()
+            .cloned()
+            .collect();
+
+        let subnet_registry_info = state
+            .subnet_registry_info
+            .as_ref()
+            .expect("subnet registry info is not `None`");
+        let mut geographies: BTreeMap<String, Vec<SubnetId>> = BTreeMap::new();
+        for (subnet_id, info) in subnet_registry_info {
+            for geography in info.geographies() {
+                geographies.entry(geography).or_default().push(*subnet_id);
+            }
+        }
+        let subnet_sizes = subnet_registry_info
+            .iter()
+            .map(|(subnet_id, info)| (*subnet_id, info.size))
+            .collect();
+
+        SubnetFiltersResponse {
+            subnet_types,
+            geographies: geographies.into_iter().collect(),
+            subnet_sizes,
+            last_registry_refresh_timestamp_seconds: state
+                .subnet_registry_info_refresh_timestamp_seconds,
+        }
+    })
+}
+
+/// Retrieves the values that can currently be used in a subnet filter when
+/// creating a canister: the subnet types, and the geographies and sizes of
+/// the subnets as last fetched from the registry.
+#[export_name = "canister_query get_subnet_filters"]
+fn get_subnet_filters_() {
+    over(candid_one, |_: ()| get_subnet_filters())
+}
+
 #[candid_method(
     query,
     rename = "get_principals_authorized_to_create_canisters_to_subnets"
@@ -1847,22 +2093,7 @@ async fn do_create_canister(
     let mut subnets: Vec<SubnetId> = match subnet_selection {
         Some(option) => match option {
             SubnetSelection::Filter(subnet_filter) => {
-                with_state(|state| match subnet_filter.subnet_type {
-                    Some(subnet_type) => {
-                        let subnet_types_to_subnets = state
-                            .subnet_types_to_subnets
-                            .as_ref()
-                            .expect("subnet types to subnets mapping is `None`");
-                        subnet_types_to_subnets
-                            .get(&subnet_type)
-                            .map(|set| set.iter().cloned().collect())
-                            .ok_or(format!(
-                                "Provided subnet type {} does not exist",
-                                subnet_type
-                            ))
-                    }
-                    None => Ok(get_subnets_for(&controller_id)),
-                })
+                with_state(|state| state.get_subnets_by_filter(&controller_id, &subnet_filter))
             }
             SubnetSelection::Subnet { subnet } => with_state(|state| {
                 if state.default_subnets.contains(&subnet)
@@ -1994,13 +2225,7 @@ fn total_supply_() {
 /// Return the list of subnets in which this controller is allowed to create
 /// canisters
 fn get_subnets_for(controller_id: &PrincipalId) -> Vec<SubnetId> {
-    with_state(|state| {
-        if let Some(subnets) = state.authorized_subnets.get(controller_id) {
-            subnets.clone()
-        } else {
-            state.default_subnets.clone()
-        }
-    })
+    with_state(|state| state.get_subnets_for(controller_id))
 }
 
 async fn get_rng() -> Result<StdRng, String> {
@@ -2049,6 +2274,9 @@ fn post_upgrade(maybe_args: Option<CyclesCanisterInitPayload>) {
     if new_state.subnet_types_to_subnets.is_none() {
         new_state.subnet_types_to_subnets = Some(BTreeMap::new());
     }
+    if new_state.subnet_registry_info.is_none() {
+        new_state.subnet_registry_info = Some(BTreeMap::new());
+    }
 
     if let Some(args) = maybe_args {
         if let Some(xrc_flag) = args.exchange_rate_canister {
@@ -2066,6 +2294,120 @@ fn canister_heartbeat() {
         let future = update_exchange_rate();
         dfn_core::api::futures::spawn(future);
     }
+
+    let now_seconds = CanisterEnvironment.now_timestamp_seconds();
+    if with_state_mut(|state| start_subnet_registry_info_refresh(state, now_seconds)) {
+        dfn_core::api::futures::spawn(refresh_subnet_registry_info(now_seconds));
+    }
+}
+
+/// Returns whether a refresh of the subnet registry info is due and, if so,
+/// records that one is started now.
+fn start_subnet_registry_info_refresh(state: &mut State, now_seconds: u64) -> bool {
+    let due = match state.subnet_registry_info_refresh_started_timestamp_seconds {
+        Some(started) => {
+            now_seconds >= started.saturating_add(SUBNET_REGISTRY_INFO_REFRESH_INTERVAL_SECONDS)
+        }
+        None => true,
+    };
+    if due {
+        state.subnet_registry_info_refresh_started_timestamp_seconds = Some(now_seconds);
+    }
+    due
+}
+
+/// Fetches from the registry the information needed to resolve subnet
+/// filters for all subnets known to the CMC.
+async fn refresh_subnet_registry_info(now_seconds: u64) {
+    let subnets = with_state(|state| state.get_all_subnets());
+
+    // Many nodes share the same node operator, and many node operators the
+    // same data center, so avoid fetching those records more than once.
+    let mut node_operator_to_dc: BTreeMap<Vec<u8>, String> = BTreeMap::new();
+    let mut dc_to_region: BTreeMap<String, String> = BTreeMap::new();
+
+    let mut fetched = BTreeMap::new();
+    for subnet_id in subnets {
+        let info = match fetch_subnet_registry_info(
+            subnet_id,
+            &mut node_operator_to_dc,
+            &mut dc_to_region,
+        )
+        .await
+        {
+            Ok(info) => Some(info),
+            Err(err) => {
+                print(format!(
+                    "[cycles] Failed to fetch the registry information of subnet {}: {}",
+                    subnet_id, err
+                ));
+                None
+            }
+        };
+        fetched.insert(subnet_id, info);
+    }
+
+    with_state_mut(|state| state.update_subnet_registry_info(fetched, now_seconds));
+}
+
+async fn fetch_subnet_registry_info(
+    subnet_id: SubnetId,
+    node_operator_to_dc: &mut BTreeMap<Vec<u8>, String>,
+    dc_to_region: &mut BTreeMap<String, String>,
+) -> Result<SubnetRegistryInfo, String> {
+    let (subnet_record, _) = get_subnet_record(subnet_id)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    let mut regions = BTreeSet::new();
+    for node_id in &subnet_record.membership {
+        let node_id =
+            NodeId::from(PrincipalId::try_from(node_id.as_slice()).map_err(|err| err.to_string())?);
+        let (node_record, _) =
+            get_value::<NodeRecord>(make_node_record_key(node_id).as_bytes(), None)
+                .await
+                .map_err(|err| format!("node {}: {}", node_id, err))?;
+
+        let dc_id = match node_operator_to_dc.get(&node_record.node_operator_id) {
+            Some(dc_id) => dc_id.clone(),
+            None => {
+                let node_operator_id =
+                    PrincipalId::try_from(node_record.node_operator_id.as_slice())
+                        .map_err(|err| err.to_string())?;
+                let (node_operator_record, _) = get_value::<NodeOperatorRecord>(
+                    make_node_operator_record_key(node_operator_id).as_bytes(),
+                    None,
+                )
+                .await
+                .map_err(|err| format!("node operator {}: {}", node_operator_id, err))?;
+                node_operator_to_dc.insert(
+                    node_record.node_operator_id.clone(),
+                    node_operator_record.dc_id.clone(),
+                );
+                node_operator_record.dc_id
+            }
+        };
+
+        let region = match dc_to_region.get(&dc_id) {
+            Some(region) => region.clone(),
+            None => {
+                let (dc_record, _) = get_value::<DataCenterRecord>(
+                    make_data_center_record_key(&dc_id).as_bytes(),
+                    None,
+                )
+                .await
+                .map_err(|err| format!("data center {}: {}", dc_id, err))?;
+                dc_to_region.insert(dc_id, dc_record.region.clone());
+                dc_record.region
+            }
+        };
+        regions.insert(region);
+    }
+
+    Ok(SubnetRegistryInfo {
+        regions,
+        size: subnet_record.membership.len() as u64,
+    })
 }
 
 async fn update_exchange_rate() {
@@ -2178,6 +2520,8 @@ fn get_subnet_selection(
     } else if let Some(subnet_type) = subnet_type {
         Ok(Some(SubnetSelection::Filter(SubnetFilter {
             subnet_type: Some(subnet_type),
+            geography: None,
+            min_subnet_size: None,
         })))
     } else {
         Ok(subnet_selection)
@@ -2734,6 +3078,219 @@ mod tests {
         );
     }
 
+    fn subnet_registry_info(regions: &[&str], size: u64) -> SubnetRegistryInfo {
+        SubnetRegistryInfo {
+            regions: regions.iter().map(|region| region.to_string()).collect(),
+            size,
+        }
+    }
+
+    fn subnet_filter(geography: Option<&str>, min_subnet_size: Option<u64>) -> SubnetFilter {
+        SubnetFilter {
+            subnet_type: None,
+            geography: geography.map(|geography| geography.to_string()),
+            min_subnet_size,
+        }
+    }
+
+    #[test]
+    fn test_subnet_registry_info_geographies() {
+        assert_eq!(
+            subnet_registry_info(&[], 0).geographies(),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            subnet_registry_info(&["Europe,CH,Zurich", "Europe,CH,Geneva"], 2).geographies(),
+            vec!["Europe".to_string(), "Europe,CH".to_string()]
+        );
+        assert_eq!(
+            subnet_registry_info(&["Europe,CH,Zurich", "North America,US,Dallas"], 2).geographies(),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            subnet_registry_info(&["Europe, CH, Zurich"], 1).geographies(),
+            vec![
+                "Europe".to_string(),
+                "Europe,CH".to_string(),
+                "Europe,CH,Zurich".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_subnet_registry_info_matches() {
+        let info = subnet_registry_info(&["Europe,CH,Zurich", "Europe,DE,Berlin"], 13);
+
+        assert!(info.matches(&subnet_filter(None, None)));
+        assert!(info.matches(&subnet_filter(Some("Europe"), None)));
+        assert!(info.matches(&subnet_filter(Some(" Europe "), Some(13))));
+        assert!(!info.matches(&subnet_filter(Some("Europe,CH"), None)));
+        assert!(!info.matches(&subnet_filter(Some("Eur"), None)));
+        assert!(!info.matches(&subnet_filter(None, Some(14))));
+    }
+
+    #[test]
+    fn test_get_subnets_by_filter() {
+        let type1 = "Type1".to_string();
+        let controller = user_test_id(0).get();
+
+        let european_subnet = subnet_test_id(0);
+        let small_european_subnet = subnet_test_id(1);
+        let american_subnet = subnet_test_id(2);
+        let unknown_subnet = subnet_test_id(3);
+        let typed_european_subnet = subnet_test_id(4);
+
+        let state = State {
+            default_subnets: vec![
+                european_subnet,
+                small_european_subnet,
+                american_subnet,
+                unknown_subnet,
+            ],
+            subnet_types_to_subnets: Some(BTreeMap::from([(
+                type1.clone(),
+                BTreeSet::from([typed_european_subnet]),
+            )])),
+            subnet_registry_info: Some(BTreeMap::from([
+                (
+                    european_subnet,
+                    subnet_registry_info(&["Europe,CH,Zurich"], 13),
+                ),
+                (
+                    small_european_subnet,
+                    subnet_registry_info(&["Europe,DE,Berlin"], 4),
+                ),
+                (
+                    american_subnet,
+                    subnet_registry_info(&["North America,US,Dallas"], 13),
+                ),
+                (
+                    typed_european_subnet,
+                    subnet_registry_info(&["Europe,CH,Geneva"], 13),
+                ),
+            ])),
+            ..Default::default()
+        };
+
+        // Without geography and size constraints, all candidate subnets are
+        // selected, even those whose registry information is unknown.
+        assert_eq!(
+            state.get_subnets_by_filter(&controller, &subnet_filter(None, None)),
+            Ok(state.default_subnets.clone())
+        );
+
+        assert_eq!(
+            state.get_subnets_by_filter(&controller, &subnet_filter(Some("Europe"), None)),
+            Ok(vec![european_subnet, small_european_subnet])
+        );
+        assert_eq!(
+            state.get_subnets_by_filter(&controller, &subnet_filter(Some("Europe"), Some(13))),
+            Ok(vec![european_subnet])
+        );
+        assert_eq!(
+            state.get_subnets_by_filter(&controller, &subnet_filter(None, Some(10))),
+            Ok(vec![european_subnet, american_subnet])
+        );
+        assert!(state
+            .get_subnets_by_filter(&controller, &subnet_filter(Some("Asia"), None))
+            .is_err());
+
+        // The subnet type restricts the candidate subnets.
+        assert_eq!(
+            state.get_subnets_by_filter(
+                &controller,
+                &SubnetFilter {
+                    subnet_type: Some(type1),
+                    ..subnet_filter(Some("Europe,CH"), None)
+                }
+            ),
+            Ok(vec![typed_european_subnet])
+        );
+        assert!(state
+            .get_subnets_by_filter(
+                &controller,
+                &SubnetFilter {
+                    subnet_type: Some("Type2".to_string()),
+                    ..subnet_filter(None, None)
+                }
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_start_subnet_registry_info_refresh() {
+        let mut state = State::default();
+        let now_seconds = 1_000_000;
+
+        assert!(start_subnet_registry_info_refresh(&mut state, now_seconds));
+        assert!(!start_subnet_registry_info_refresh(
+            &mut state,
+            now_seconds + SUBNET_REGISTRY_INFO_REFRESH_INTERVAL_SECONDS - 1
+        ));
+        assert!(start_subnet_registry_info_refresh(
+            &mut state,
+            now_seconds + SUBNET_REGISTRY_INFO_REFRESH_INTERVAL_SECONDS
+        ));
+        assert_eq!(
+            state.subnet_registry_info_refresh_started_timestamp_seconds,
+            Some(now_seconds + SUBNET_REGISTRY_INFO_REFRESH_INTERVAL_SECONDS)
+        );
+    }
+
+    #[test]
+    fn test_request_subnet_registry_info_refresh() {
+        let mut state = State::default();
+        let now_seconds = 1_000_000;
+
+        assert!(start_subnet_registry_info_refresh(&mut state, now_seconds));
+        assert!(!start_subnet_registry_info_refresh(
+            &mut state,
+            now_seconds + 1
+        ));
+        state.request_subnet_registry_info_refresh();
+        assert!(start_subnet_registry_info_refresh(
+            &mut state,
+            now_seconds + 1
+        ));
+    }
+
+    #[test]
+    fn test_update_subnet_registry_info_keeps_entries_that_failed_to_refresh() {
+        let mut state = State::default();
+        let subnet1 = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+        let subnet2 = SubnetId::from(PrincipalId::new_subnet_test_id(2));
+        let subnet3 = SubnetId::from(PrincipalId::new_subnet_test_id(3));
+        state.subnet_registry_info = Some(BTreeMap::from([
+            (subnet1, subnet_registry_info(&["Europe,CH,Zurich"], 13)),
+            (subnet2, subnet_registry_info(&["Europe,CH,Zurich"], 13)),
+            (subnet3, subnet_registry_info(&["Europe,CH,Zurich"], 13)),
+        ]));
+
+        // The fetch failed for `subnet2`, and `subnet3` is no longer known.
+        state.update_subnet_registry_info(
+            BTreeMap::from([
+                (
+                    subnet1,
+                    Some(subnet_registry_info(&["Europe,CH,Geneva"], 28)),
+                ),
+                (subnet2, None),
+            ]),
+            1_000_000,
+        );
+
+        assert_eq!(
+            state.subnet_registry_info,
+            Some(BTreeMap::from([
+                (subnet1, subnet_registry_info(&["Europe,CH,Geneva"], 28)),
+                (subnet2, subnet_registry_info(&["Europe,CH,Zurich"], 13)),
+            ]))
+        );
+        assert_eq!(
+            state.subnet_registry_info_refresh_timestamp_seconds,
+            Some(1_000_000)
+        );
+    }
+
     #[test]
     fn test_candid_interface_compatibility() {
         use candid::utils::{service_compatible, CandidSource};
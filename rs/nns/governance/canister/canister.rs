@@ -61,7 +61,12 @@ use ic_nns_governance::{
 use prost::Message;
 use rand::{RngCore, SeedableRng};
 use rand_chacha::ChaCha20Rng;
-use std::{borrow::Cow, boxed::Box, str::FromStr, time::SystemTime};
+use std::{
+    borrow::Cow,
+    boxed::Box,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
 
 /// WASM memory equivalent to 4GiB, which we want to reserve for upgrades memory. The heap memory
 /// limit is 4GiB but its serialized form with prost should be smaller, so we reserve for 4GiB. This
@@ -69,6 +74,11 @@ use std::{borrow::Cow, boxed::Box, str::FromStr, time::SystemTime};
 /// pre_upgrade by trying to grow UPGRADES_MEMORY.
 const WASM_PAGES_RESERVED_FOR_UPGRADES_MEMORY: u64 = 65_536;
 
+/// How often a batch of neurons is checked for following to clear because the
+/// neurons did not refresh their voting power. With CLEAR_FOLLOWING_BATCH_SIZE
+/// neurons per batch, all neurons are visited within a few hours.
+const CLEAR_FOLLOWING_INTERVAL: Duration = Duration::from_secs(60);
+
 pub(crate) const LOG_PREFIX: &str = "[Governance] ";
 
 // https://dfinity.atlassian.net/browse/NNS1-1050: We are not following
@@ -330,6 +340,7 @@ fn canister_init_(init_payload: GovernanceProto) {
         Box::new(IcpLedgerCanister::new(LEDGER_CANISTER_ID)),
         Box::new(CMCCanister::<DfnRuntime>::new()),
     ));
+    schedule_timers();
 }
 
 #[export_name = "canister_pre_upgrade"]
@@ -372,6 +383,16 @@ fn canister_post_upgrade() {
         Box::new(IcpLedgerCanister::new(LEDGER_CANISTER_ID)),
         Box::new(CMCCanister::<DfnRuntime>::new()),
     ));
+    governance_mut().backfill_voting_power_refreshed_timestamps();
+    schedule_timers();
+}
+
+/// Schedules the recurring tasks that are driven by timers rather than by
+/// the heartbeat.
+fn schedule_timers() {
+    ic_cdk_timers::set_timer_interval(CLEAR_FOLLOWING_INTERVAL, || {
+        governance_mut().clear_following_of_neurons_that_did_not_refresh_voting_power()
+    });
 }
 
 #[cfg(feature = "test")]
@@ -91,6 +91,18 @@ pub struct NeuronInfo {
     /// of the different states.
     #[prost(enumeration = "NeuronType", optional, tag = "11")]
     pub neuron_type: ::core::option::Option<i32>,
+    /// See \[Neuron::voting_power_refreshed_timestamp_seconds\] for a description.
+    #[prost(uint64, optional, tag = "12")]
+    pub voting_power_refreshed_timestamp_seconds: ::core::option::Option<u64>,
+    /// The voting power that this neuron currently uses to decide proposals.
+    /// This is the same as `voting_power` (the "potential" voting power),
+    /// unless the neuron has not refreshed its voting power for a while, in
+    /// which case it is reduced (see VotingPowerEconomics).
+    #[prost(uint64, optional, tag = "13")]
+    pub deciding_voting_power: ::core::option::Option<u64>,
+    /// Whether the full neuron can be read by anyone. See \[Visibility\].
+    #[prost(enumeration = "Visibility", optional, tag = "14")]
+    pub visibility: ::core::option::Option<i32>,
 }
 /// A transfer performed from some account to stake a new neuron.
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
@@ -243,6 +255,19 @@ pub struct Neuron {
     /// of the different states.
     #[prost(enumeration = "NeuronType", optional, tag = "22")]
     pub neuron_type: ::core::option::Option<i32>,
+    /// The last time (in seconds since the UNIX epoch) at which the neuron
+    /// confirmed its voting power and following, either explicitly with a
+    /// RefreshVotingPower command, or implicitly by voting directly or setting
+    /// its following. If the neuron does not refresh for a long enough time,
+    /// its deciding voting power decays and eventually its following is cleared.
+    /// See VotingPowerEconomics.
+    #[prost(uint64, optional, tag = "23")]
+    pub voting_power_refreshed_timestamp_seconds: ::core::option::Option<u64>,
+    /// Whether the full neuron (including its followees) can be read by anyone.
+    /// If not set, known neurons are public and all other neurons are private.
+    /// See \[Visibility\].
+    #[prost(enumeration = "Visibility", optional, tag = "24")]
+    pub visibility: ::core::option::Option<i32>,
     /// At any time, at most one of `when_dissolved` and
     /// `dissolve_delay` are specified.
     ///
@@ -326,6 +351,92 @@ pub struct ExecuteNnsFunction {
     #[prost(bytes = "vec", tag = "2")]
     pub payload: ::prost::alloc::vec::Vec<u8>,
 }
+/// Installs, reinstalls or upgrades the code of an NNS canister. The
+/// request is forwarded to the NNS root canister, which controls the NNS
+/// canisters (except for itself). Unlike ExecuteNnsFunction with
+/// NNS_FUNCTION_NNS_CANISTER_UPGRADE, the fields of this proposal are typed,
+/// and the hashes of the wasm module and of the argument are recorded so that
+/// reviewers can compare them against a reproducible build.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InstallCode {
+    /// The canister whose code is to be changed. Required.
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// Required.
+    #[prost(enumeration = "install_code::CanisterInstallMode", optional, tag = "2")]
+    pub install_mode: ::core::option::Option<i32>,
+    /// The new wasm module. Required. Omitted when proposals are listed.
+    #[prost(bytes = "vec", optional, tag = "3")]
+    pub wasm_module: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    /// The argument passed to the canister's init or post_upgrade method.
+    /// Omitted when proposals are listed.
+    #[prost(bytes = "vec", optional, tag = "4")]
+    pub arg: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    /// Whether NNS root should skip stopping the canister before installing the
+    /// code. Defaults to false, i.e. the canister is stopped first.
+    #[prost(bool, optional, tag = "5")]
+    pub skip_stopping_before_installing: ::core::option::Option<bool>,
+    /// The SHA-256 of `wasm_module`. Set by governance when the proposal is
+    /// submitted; if set by the proposer, it must match. After execution, the
+    /// module hash of the canister is checked against this value.
+    #[prost(bytes = "vec", optional, tag = "6")]
+    pub wasm_module_hash: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    /// The SHA-256 of `arg`. Set by governance when the proposal is submitted;
+    /// if set by the proposer, it must match.
+    #[prost(bytes = "vec", optional, tag = "7")]
+    pub arg_hash: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+}
+/// Nested message and enum types in `InstallCode`.
+pub mod install_code {
+    #[derive(
+        candid::CandidType,
+        candid::Deserialize,
+        serde::Serialize,
+        comparable::Comparable,
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration,
+    )]
+    #[repr(i32)]
+    pub enum CanisterInstallMode {
+        Unspecified = 0,
+        Install = 1,
+        Reinstall = 2,
+        Upgrade = 3,
+    }
+    impl CanisterInstallMode {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                CanisterInstallMode::Unspecified => "CANISTER_INSTALL_MODE_UNSPECIFIED",
+                CanisterInstallMode::Install => "CANISTER_INSTALL_MODE_INSTALL",
+                CanisterInstallMode::Reinstall => "CANISTER_INSTALL_MODE_REINSTALL",
+                CanisterInstallMode::Upgrade => "CANISTER_INSTALL_MODE_UPGRADE",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "CANISTER_INSTALL_MODE_UNSPECIFIED" => Some(Self::Unspecified),
+                "CANISTER_INSTALL_MODE_INSTALL" => Some(Self::Install),
+                "CANISTER_INSTALL_MODE_REINSTALL" => Some(Self::Reinstall),
+                "CANISTER_INSTALL_MODE_UPGRADE" => Some(Self::Upgrade),
+                _ => None,
+            }
+        }
+    }
+}
 /// If adopted, a motion should guide the future strategy of the
 /// Internet Computer ecosystem.
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
@@ -497,7 +608,7 @@ pub struct Proposal {
     /// take.
     #[prost(
         oneof = "proposal::Action",
-        tags = "10, 12, 13, 14, 15, 16, 17, 18, 19, 21, 22, 23, 24"
+        tags = "10, 12, 13, 14, 15, 16, 17, 18, 19, 21, 22, 23, 24, 25"
     )]
     pub action: ::core::option::Option<proposal::Action>,
 }
@@ -582,6 +693,9 @@ pub mod proposal {
         /// Create a new SNS.
         #[prost(message, tag = "24")]
         CreateServiceNervousSystem(super::CreateServiceNervousSystem),
+        /// Install, reinstall or upgrade the code of an NNS canister, via NNS root.
+        #[prost(message, tag = "25")]
+        InstallCode(super::InstallCode),
     }
 }
 /// Empty message to use in oneof fields that represent empty
@@ -683,6 +797,14 @@ pub mod manage_neuron {
         #[prost(bool, tag = "1")]
         pub requested_setting_for_auto_stake_maturity: bool,
     }
+    /// Sets whether the full neuron can be read by anyone (see Visibility).
+    #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SetVisibility {
+        #[prost(enumeration = "super::Visibility", optional, tag = "1")]
+        pub visibility: ::core::option::Option<i32>,
+    }
     /// Commands that only configure a given neuron, but do not interact
     /// with the outside world. They all require the caller to be the
     /// controller of the neuron.
@@ -690,7 +812,7 @@ pub mod manage_neuron {
     #[allow(clippy::derive_partial_eq_without_eq)]
     #[derive(Clone, PartialEq, ::prost::Message)]
     pub struct Configure {
-        #[prost(oneof = "configure::Operation", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9")]
+        #[prost(oneof = "configure::Operation", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10")]
         pub operation: ::core::option::Option<configure::Operation>,
     }
     /// Nested message and enum types in `Configure`.
@@ -719,6 +841,8 @@ pub mod manage_neuron {
             LeaveCommunityFund(super::LeaveCommunityFund),
             #[prost(message, tag = "9")]
             ChangeAutoStakeMaturity(super::ChangeAutoStakeMaturity),
+            #[prost(message, tag = "10")]
+            SetVisibility(super::SetVisibility),
         }
     }
     /// Disburse this neuron's stake: transfer the staked ICP to the
@@ -811,6 +935,14 @@ pub mod manage_neuron {
         #[prost(uint32, optional, tag = "1")]
         pub percentage_to_stake: ::core::option::Option<u32>,
     }
+    /// Confirm that the neuron's following is still as intended and restore its
+    /// full deciding voting power. Neurons that don't refresh (whether
+    /// explicitly with this command, or implicitly by voting directly or by
+    /// setting following) eventually lose voting power and their following.
+    #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct RefreshVotingPower {}
     /// Disburse a portion of this neuron's stake into another neuron.
     /// This allows to split a neuron but with a new dissolve delay
     /// and owned by someone else.
@@ -958,6 +1090,8 @@ pub mod manage_neuron {
         Merge(Merge),
         #[prost(message, tag = "15")]
         StakeMaturity(StakeMaturity),
+        #[prost(message, tag = "16")]
+        RefreshVotingPower(RefreshVotingPower),
     }
 }
 /// The response of the ManageNeuron command
@@ -969,7 +1103,7 @@ pub mod manage_neuron {
 pub struct ManageNeuronResponse {
     #[prost(
         oneof = "manage_neuron_response::Command",
-        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13"
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14"
     )]
     pub command: ::core::option::Option<manage_neuron_response::Command>,
 }
@@ -1032,6 +1166,10 @@ pub mod manage_neuron_response {
     #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
     #[allow(clippy::derive_partial_eq_without_eq)]
     #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct RefreshVotingPowerResponse {}
+    #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
     pub struct SplitResponse {
         /// The ID of the Neuron created from splitting another Neuron
         #[prost(message, optional, tag = "1")]
@@ -1100,6 +1238,8 @@ pub mod manage_neuron_response {
         Merge(MergeResponse),
         #[prost(message, tag = "13")]
         StakeMaturity(StakeMaturityResponse),
+        #[prost(message, tag = "14")]
+        RefreshVotingPower(RefreshVotingPowerResponse),
     }
 }
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
@@ -1366,6 +1506,13 @@ pub struct ProposalData {
     /// `cf_participants` and use only this field for managing the Neurons' Fund swap participation.
     #[prost(message, optional, tag = "21")]
     pub neurons_fund_data: ::core::option::Option<NeuronsFundData>,
+    /// This is populated when an InstallCode proposal is executed, right before
+    /// NNS root is asked to change the code. It is empty if the canister had no
+    /// module. The proposal is only considered executed once the module hash of
+    /// the canister differs from this value and matches the proposed one.
+    #[prost(bytes = "vec", optional, tag = "22")]
+    pub install_code_module_hash_before_execution:
+        ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
 /// This structure contains data for settling the Neurons' Fund participation in an SNS token swap.
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
@@ -1819,6 +1966,30 @@ pub struct NetworkEconomics {
     /// If unspecified or zero, all proposals are kept.
     #[prost(uint32, tag = "10")]
     pub max_proposals_to_keep_per_topic: u32,
+    /// Parameters that control how the deciding voting power of neurons that
+    /// have not refreshed their voting power decays, and when their following
+    /// gets cleared.
+    #[prost(message, optional, tag = "11")]
+    pub voting_power_economics: ::core::option::Option<VotingPowerEconomics>,
+}
+/// Parameters that affect the voting power of neurons that have not recently
+/// refreshed their voting power (see Neuron.voting_power_refreshed_timestamp_seconds).
+///
+/// Unset fields mean that the default value is used (see
+/// VotingPowerEconomics::with_default_values).
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VotingPowerEconomics {
+    /// If a neuron has not refreshed its voting power for this amount of time,
+    /// its deciding voting power starts decreasing linearly.
+    #[prost(uint64, optional, tag = "1")]
+    pub start_reducing_voting_power_after_seconds: ::core::option::Option<u64>,
+    /// After a neuron's deciding voting power has been decreasing for this
+    /// amount of time, it reaches zero, and the neuron's following on all topics
+    /// other than NeuronManagement is cleared.
+    #[prost(uint64, optional, tag = "2")]
+    pub clear_following_after_seconds: ::core::option::Option<u64>,
 }
 /// A reward event is an event at which neuron maturity is increased
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
@@ -2670,6 +2841,10 @@ pub struct ListNeurons {
     /// neurons that the calling principal is authorized to read.
     #[prost(bool, tag = "2")]
     pub include_neurons_readable_by_caller: bool,
+    /// If true, `full_neurons` in the response also contains the public
+    /// neurons in the "requested list", regardless of who the caller is.
+    #[prost(bool, optional, tag = "3")]
+    pub include_public_neurons_in_full_neurons: ::core::option::Option<bool>,
 }
 /// A response to a `ListNeurons` request.
 ///
@@ -2685,7 +2860,8 @@ pub struct ListNeuronsResponse {
     /// For each neuron ID in the "requested list", if the neuron exists,
     /// and the caller is authorized to read the full neuron (controller,
     /// hot key, or controller or hot key of some followee on the
-    /// `ManageNeuron` topic).
+    /// `ManageNeuron` topic), or the neuron is public and
+    /// `include_public_neurons_in_full_neurons` was requested.
     #[prost(message, repeated, tag = "2")]
     pub full_neurons: ::prost::alloc::vec::Vec<Neuron>,
 }
@@ -3266,6 +3442,55 @@ impl NeuronState {
         }
     }
 }
+/// Controls who can read the full information of a neuron (including, for
+/// example, its followees) via `list_neurons`. The `NeuronInfo` of a neuron is
+/// readable by anyone regardless of its visibility.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    serde::Serialize,
+    comparable::Comparable,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum Visibility {
+    Unspecified = 0,
+    /// Only the controller and hot keys of the neuron, and those of its
+    /// followees on the NeuronManagement topic, can read the full neuron.
+    Private = 1,
+    /// Anyone can read the full neuron.
+    Public = 2,
+}
+impl Visibility {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Visibility::Unspecified => "VISIBILITY_UNSPECIFIED",
+            Visibility::Private => "VISIBILITY_PRIVATE",
+            Visibility::Public => "VISIBILITY_PUBLIC",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "VISIBILITY_UNSPECIFIED" => Some(Self::Unspecified),
+            "VISIBILITY_PRIVATE" => Some(Self::Private),
+            "VISIBILITY_PUBLIC" => Some(Self::Public),
+            _ => None,
+        }
+    }
+}
 /// Types of a Neuron.
 #[derive(
     candid::CandidType,
@@ -22,6 +22,7 @@ use crate::{
             GovernanceCachedMetrics, MakingSnsProposal, NeuronInFlightCommand, SeedAccounts,
         },
         governance_error::ErrorType,
+        install_code::CanisterInstallMode,
         manage_neuron,
         manage_neuron::{
             claim_or_refresh::{By, MemoAndController},
@@ -40,8 +41,8 @@ use crate::{
         swap_background_information, Ballot, CreateServiceNervousSystem,
         DerivedProposalInformation, ExecuteNnsFunction, GetNeuronsFundAuditInfoRequest,
         GetNeuronsFundAuditInfoResponse, Governance as GovernanceProto, GovernanceError,
-        KnownNeuron, ListKnownNeuronsResponse, ListNeurons, ListNeuronsResponse, ListProposalInfo,
-        ListProposalInfoResponse, ManageNeuron, ManageNeuronResponse,
+        InstallCode, KnownNeuron, ListKnownNeuronsResponse, ListNeurons, ListNeuronsResponse,
+        ListProposalInfo, ListProposalInfoResponse, ManageNeuron, ManageNeuronResponse,
         MostRecentMonthlyNodeProviderRewards, Motion, NetworkEconomics, Neuron, NeuronInfo,
         NeuronState, NeuronType, NeuronsFundAuditInfo, NeuronsFundData,
         NeuronsFundParticipation as NeuronsFundParticipationPb,
@@ -50,9 +51,14 @@ use crate::{
         RewardNodeProvider, RewardNodeProviders, SetSnsTokenSwapOpenTimeWindow,
         SettleCommunityFundParticipation, SettleNeuronsFundParticipationRequest,
         SettleNeuronsFundParticipationResponse, SwapBackgroundInformation, Tally, Topic,
-        UpdateNodeProvider, Vote, WaitForQuietState,
+        UpdateNodeProvider, Visibility, Vote, VotingPowerEconomics, WaitForQuietState,
+    },
+    proposals::{
+        create_service_nervous_system::ExecutedCreateServiceNervousSystemProposal,
+        install_code::{
+            INSTALL_CODE_VERIFICATION_INTERVAL_SECONDS, INSTALL_CODE_VERIFICATION_TIMEOUT_SECONDS,
+        },
     },
-    proposals::create_service_nervous_system::ExecutedCreateServiceNervousSystemProposal,
 };
 use async_trait::async_trait;
 use candid::{Decode, Encode};
@@ -63,6 +69,9 @@ use dfn_core::println;
 use dfn_protobuf::ToProto;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_crypto_sha2::Sha256;
+use ic_nervous_system_clients::{
+    canister_id_record::CanisterIdRecord, canister_status::CanisterStatusResult,
+};
 use ic_nervous_system_common::{
     cmc::CMC, ledger, ledger::IcpLedger, NervousSystemError, SECONDS_PER_DAY,
 };
@@ -157,6 +166,10 @@ pub const MAX_NEURON_AGE_FOR_AGE_BONUS: u64 = 4 * ONE_YEAR_SECONDS;
 /// The minimum dissolve delay so that a neuron may vote.
 pub const MIN_DISSOLVE_DELAY_FOR_VOTE_ELIGIBILITY_SECONDS: u64 = 6 * ONE_MONTH_SECONDS;
 
+/// The maximum number of neurons visited in a single call to
+/// clear_following_of_neurons_that_did_not_refresh_voting_power.
+pub const CLEAR_FOLLOWING_BATCH_SIZE: usize = 1_000;
+
 /// The maximum number of followees each neuron can establish for each topic.
 pub const MAX_FOLLOWEES_PER_TOPIC: usize = 15;
 
@@ -260,10 +273,35 @@ impl NetworkEconomics {
             minimum_icp_xdr_rate: 100,                                  // 1 XDR
             transaction_fee_e8s: DEFAULT_TRANSFER_FEE.get_e8s(),
             max_proposals_to_keep_per_topic: 100,
+            voting_power_economics: Some(VotingPowerEconomics::with_default_values()),
         }
     }
 }
 
+impl VotingPowerEconomics {
+    pub const DEFAULT_START_REDUCING_VOTING_POWER_AFTER_SECONDS: u64 = 6 * ONE_MONTH_SECONDS;
+    pub const DEFAULT_CLEAR_FOLLOWING_AFTER_SECONDS: u64 = ONE_MONTH_SECONDS;
+
+    pub const fn with_default_values() -> Self {
+        Self {
+            start_reducing_voting_power_after_seconds: Some(
+                Self::DEFAULT_START_REDUCING_VOTING_POWER_AFTER_SECONDS,
+            ),
+            clear_following_after_seconds: Some(Self::DEFAULT_CLEAR_FOLLOWING_AFTER_SECONDS),
+        }
+    }
+
+    pub fn get_start_reducing_voting_power_after_seconds(&self) -> u64 {
+        self.start_reducing_voting_power_after_seconds
+            .unwrap_or(Self::DEFAULT_START_REDUCING_VOTING_POWER_AFTER_SECONDS)
+    }
+
+    pub fn get_clear_following_after_seconds(&self) -> u64 {
+        self.clear_following_after_seconds
+            .unwrap_or(Self::DEFAULT_CLEAR_FOLLOWING_AFTER_SECONDS)
+    }
+}
+
 impl GovernanceError {
     pub fn new(error_type: ErrorType) -> Self {
         Self {
@@ -513,6 +551,14 @@ impl ManageNeuronResponse {
         }
     }
 
+    pub fn refresh_voting_power_response() -> Self {
+        ManageNeuronResponse {
+            command: Some(manage_neuron_response::Command::RefreshVotingPower(
+                manage_neuron_response::RefreshVotingPowerResponse {},
+            )),
+        }
+    }
+
     pub fn split_response(created_neuron_id: NeuronId) -> Self {
         let created_neuron_id = Some(created_neuron_id);
         ManageNeuronResponse {
@@ -807,6 +853,7 @@ impl Proposal {
                 Action::OpenSnsTokenSwap(_) | Action::CreateServiceNervousSystem(_) => {
                     Topic::SnsAndCommunityFund
                 }
+                Action::InstallCode(_) => Topic::NetworkCanisterManagement,
             }
         } else {
             println!("{}ERROR: No action -> no topic.", LOG_PREFIX);
@@ -841,6 +888,11 @@ impl Action {
                     None => false,
                 }
             }
+            // Like NnsCanisterUpgrade, upgrades are needed to fix the
+            // resource problem itself.
+            Action::InstallCode(install_code) => {
+                install_code.install_mode == Some(CanisterInstallMode::Upgrade as i32)
+            }
             _ => false,
         }
     }
@@ -867,6 +919,9 @@ impl Action {
                 }
                 Action::ExecuteNnsFunction(execute_nns_function)
             }
+            Action::InstallCode(install_code) => {
+                Action::InstallCode(install_code.omit_large_fields())
+            }
             action => action,
         }
     }
@@ -1453,6 +1508,17 @@ pub struct Governance {
 
     /// Scope guard for minting node provider rewards.
     minting_node_provider_rewards: bool,
+
+    /// Scope guard for verifying InstallCode proposals.
+    verifying_install_code_proposals: bool,
+
+    /// The time of the latest verification of InstallCode proposals.
+    latest_install_code_verification_timestamp_seconds: u64,
+
+    /// The neuron id that the next call to
+    /// `clear_following_of_neurons_that_did_not_refresh_voting_power` starts
+    /// with. None means that it starts from the first neuron.
+    next_neuron_id_to_clear_following: Option<NeuronId>,
 }
 
 pub fn governance_minting_account() -> AccountIdentifier {
@@ -1639,6 +1705,9 @@ impl Governance {
             latest_gc_num_proposals: 0,
             neuron_data_validator: NeuronDataValidator::new(),
             minting_node_provider_rewards: false,
+            verifying_install_code_proposals: false,
+            latest_install_code_verification_timestamp_seconds: 0,
+            next_neuron_id_to_clear_following: None,
         }
     }
 
@@ -1665,6 +1734,9 @@ impl Governance {
             latest_gc_num_proposals: 0,
             neuron_data_validator: NeuronDataValidator::new(),
             minting_node_provider_rewards: false,
+            verifying_install_code_proposals: false,
+            latest_install_code_verification_timestamp_seconds: 0,
+            next_neuron_id_to_clear_following: None,
         }
     }
 
@@ -2018,6 +2090,7 @@ impl Governance {
         caller: &PrincipalId,
     ) -> ListNeuronsResponse {
         let now = self.env.now();
+        let voting_power_economics = self.voting_power_economics();
         let implicitly_requested_neurons = if req.include_neurons_readable_by_caller {
             self.get_neuron_ids_by_principal(caller)
         } else {
@@ -2033,16 +2106,28 @@ impl Governance {
                 .iter()
                 .chain(implicitly_requested_neurons.iter())
         };
+        let include_public_neurons_in_full_neurons =
+            req.include_public_neurons_in_full_neurons.unwrap_or(false);
+        let get_full_neuron = |neuron_id: &NeuronId| match self.get_full_neuron(neuron_id, caller) {
+            Ok(neuron) => Some(neuron),
+            // Public neurons can be read by anyone, but only if requested.
+            Err(_) if include_public_neurons_in_full_neurons => self
+                .with_neuron(neuron_id, |neuron| neuron.clone())
+                .ok()
+                .filter(|neuron| neuron.effective_visibility() == Visibility::Public)
+                .map(|neuron| neuron.without_deprecated_topics_from_followees()),
+            Err(_) => None,
+        };
         ListNeuronsResponse {
             neuron_infos: requested_list()
                 .filter_map(|id| {
-                    self.with_neuron(id, |neuron| (id.id, neuron.get_neuron_info(now)))
-                        .ok()
+                    self.with_neuron(id, |neuron| {
+                        (id.id, neuron.get_neuron_info(&voting_power_economics, now))
+                    })
+                    .ok()
                 })
                 .collect(),
-            full_neurons: requested_list()
-                .filter_map(|neuron_id| self.get_full_neuron(neuron_id, caller).ok())
-                .collect(),
+            full_neurons: requested_list().filter_map(get_full_neuron).collect(),
         }
     }
 
@@ -2511,6 +2596,8 @@ impl Governance {
             known_neuron_data: None,
             spawn_at_timestamp_seconds: None,
             neuron_type: parent_neuron.neuron_type,
+            voting_power_refreshed_timestamp_seconds: parent_neuron
+                .voting_power_refreshed_timestamp_seconds,
         };
 
         // Add the child neuron to the set of neurons undergoing ledger updates.
@@ -2787,6 +2874,7 @@ impl Governance {
             joined_community_fund_timestamp_seconds: None,
             known_neuron_data: None,
             neuron_type: None,
+            voting_power_refreshed_timestamp_seconds: Some(creation_timestamp_seconds),
         };
 
         // `add_neuron` will verify that `child_neuron.controller` `is_self_authenticating()`, so we don't need to check it here.
@@ -3101,6 +3189,7 @@ impl Governance {
             known_neuron_data: None,
             spawn_at_timestamp_seconds: None,
             neuron_type: None,
+            voting_power_refreshed_timestamp_seconds: Some(creation_timestamp_seconds),
         };
 
         self.add_neuron(child_nid.id, child_neuron.clone())?;
@@ -3218,7 +3307,10 @@ impl Governance {
     /// neuron is accessible to any caller.
     pub fn get_neuron_info(&self, id: &NeuronId) -> Result<NeuronInfo, GovernanceError> {
         let now = self.env.now();
-        self.with_neuron(id, |neuron| neuron.get_neuron_info(now))
+        let voting_power_economics = self.voting_power_economics();
+        self.with_neuron(id, |neuron| {
+            neuron.get_neuron_info(&voting_power_economics, now)
+        })
     }
 
     /// Returns the neuron info for a neuron identified by id or subaccount.
@@ -3229,7 +3321,7 @@ impl Governance {
         find_by: &NeuronIdOrSubaccount,
     ) -> Result<NeuronInfo, GovernanceError> {
         self.with_neuron_by_neuron_id_or_subaccount(find_by, |neuron| {
-            neuron.get_neuron_info(self.env.now())
+            neuron.get_neuron_info(&self.voting_power_economics(), self.env.now())
         })
     }
 
@@ -3961,6 +4053,7 @@ impl Governance {
                     known_neuron_data: None,
                     spawn_at_timestamp_seconds: None,
                     neuron_type: None,
+                    voting_power_refreshed_timestamp_seconds: Some(now),
                 };
                 self.add_neuron(nid.id, neuron)
             }
@@ -4228,6 +4321,22 @@ impl Governance {
                         economics.max_proposals_to_keep_per_topic =
                             ne.max_proposals_to_keep_per_topic
                     }
+                    if let Some(new_voting_power_economics) = &ne.voting_power_economics {
+                        let voting_power_economics = economics
+                            .voting_power_economics
+                            .get_or_insert_with(VotingPowerEconomics::with_default_values);
+                        if let Some(seconds) =
+                            new_voting_power_economics.start_reducing_voting_power_after_seconds
+                        {
+                            voting_power_economics.start_reducing_voting_power_after_seconds =
+                                Some(seconds);
+                        }
+                        if let Some(seconds) =
+                            new_voting_power_economics.clear_following_after_seconds
+                        {
+                            voting_power_economics.clear_following_after_seconds = Some(seconds);
+                        }
+                    }
                 } else {
                     // If for some reason, we don't have an
                     // 'economics' proto, use the proposed one.
@@ -4378,9 +4487,220 @@ impl Governance {
                 )
                 .await;
             }
+            Action::InstallCode(install_code) => {
+                self.perform_install_code(pid, install_code).await;
+            }
         }
     }
 
+    /// Asks NNS root to change the code of the target canister.
+    ///
+    /// NNS root replies before the code is actually installed (otherwise, the
+    /// governance canister could not be upgraded this way). Therefore, success
+    /// is only recorded later, by `verify_install_code_proposals`, once the
+    /// canister reports the proposed module hash. To tell the new code apart
+    /// from the old one, the module hash of the canister is recorded before
+    /// the request is made.
+    async fn perform_install_code(&mut self, pid: u64, install_code: InstallCode) {
+        let request = match install_code.change_canister_request() {
+            Ok(request) => request,
+            Err(err) => {
+                self.set_proposal_execution_status(pid, Err(err));
+                return;
+            }
+        };
+
+        let module_hash_before_execution = match self.get_module_hash(&install_code).await {
+            Ok(module_hash) => module_hash.unwrap_or_default(),
+            Err(err) => {
+                self.set_proposal_execution_status(pid, Err(err));
+                return;
+            }
+        };
+        if Some(&module_hash_before_execution) == install_code.wasm_module_hash.as_ref() {
+            self.set_proposal_execution_status(
+                pid,
+                Err(GovernanceError::new_with_message(
+                    ErrorType::PreconditionFailed,
+                    format!(
+                        "The canister already has the proposed module hash {:?}, so \
+                         installing the code could not be verified.",
+                        install_code.wasm_module_hash,
+                    ),
+                )),
+            );
+            return;
+        }
+        match self.heap_data.proposals.get_mut(&pid) {
+            Some(proposal_data) => {
+                proposal_data.install_code_module_hash_before_execution =
+                    Some(module_hash_before_execution);
+            }
+            None => {
+                println!(
+                    "{}Proposal {} disappeared while executing InstallCode.",
+                    LOG_PREFIX, pid
+                );
+                return;
+            }
+        }
+
+        let payload = match Encode!(&request) {
+            Ok(payload) => payload,
+            Err(err) => {
+                self.set_proposal_execution_status(
+                    pid,
+                    Err(GovernanceError::new_with_message(
+                        ErrorType::PreconditionFailed,
+                        format!("Failed to encode ChangeCanisterRequest: {}", err),
+                    )),
+                );
+                return;
+            }
+        };
+
+        let result = self
+            .env
+            .call_canister_method(ROOT_CANISTER_ID, "change_nns_canister", payload)
+            .await;
+
+        if let Err((code, message)) = result {
+            self.set_proposal_execution_status(
+                pid,
+                Err(GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!(
+                        "Error executing InstallCode proposal. NNS root replied with code {:?}: {}",
+                        code, message
+                    ),
+                )),
+            );
+        }
+    }
+
+    fn should_verify_install_code_proposals(&self) -> bool {
+        !self.verifying_install_code_proposals
+            && self.env.now()
+                >= self
+                    .latest_install_code_verification_timestamp_seconds
+                    .saturating_add(INSTALL_CODE_VERIFICATION_INTERVAL_SECONDS)
+    }
+
+    /// For each adopted InstallCode proposal that has been executed but is not
+    /// yet marked as executed or failed, fetches the module hash of the target
+    /// canister from NNS root. The proposal is marked as executed once the
+    /// module hash differs from the one recorded before execution and matches
+    /// the proposed one, and as failed if that does not happen within
+    /// `INSTALL_CODE_VERIFICATION_TIMEOUT_SECONDS` of the decision.
+    async fn verify_install_code_proposals(&mut self) {
+        if self.verifying_install_code_proposals {
+            // There is an ongoing verification. Do nothing.
+            return;
+        }
+        self.verifying_install_code_proposals = true;
+        self.latest_install_code_verification_timestamp_seconds = self.env.now();
+
+        let pending = self
+            .heap_data
+            .proposals
+            .iter()
+            .filter(|(_, proposal_data)| proposal_data.status() == ProposalStatus::Adopted)
+            .filter_map(|(pid, proposal_data)| {
+                let module_hash_before_execution = proposal_data
+                    .install_code_module_hash_before_execution
+                    .clone()?;
+                match proposal_data.proposal.as_ref()?.action.as_ref()? {
+                    Action::InstallCode(install_code) => Some((
+                        *pid,
+                        install_code.clone().omit_large_fields(),
+                        module_hash_before_execution,
+                        proposal_data.decided_timestamp_seconds,
+                    )),
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for (pid, install_code, module_hash_before_execution, decided_timestamp_seconds) in pending
+        {
+            let module_hash = self.get_module_hash(&install_code).await;
+
+            // The proposal might have been marked as failed while we were
+            // waiting for NNS root.
+            let still_pending = self
+                .heap_data
+                .proposals
+                .get(&pid)
+                .map_or(false, |proposal_data| {
+                    proposal_data.status() == ProposalStatus::Adopted
+                });
+            if !still_pending {
+                continue;
+            }
+
+            match module_hash {
+                Ok(module_hash)
+                    if module_hash.is_some()
+                        && module_hash == install_code.wasm_module_hash
+                        && module_hash != Some(module_hash_before_execution.clone()) =>
+                {
+                    self.set_proposal_execution_status(pid, Ok(()));
+                }
+                result => {
+                    let deadline = decided_timestamp_seconds
+                        .saturating_add(INSTALL_CODE_VERIFICATION_TIMEOUT_SECONDS);
+                    if self.env.now() >= deadline {
+                        self.set_proposal_execution_status(
+                            pid,
+                            Err(GovernanceError::new_with_message(
+                                ErrorType::External,
+                                format!(
+                                    "The module hash of the canister did not match the \
+                                     proposed one ({:?}) within {} seconds. Last observed: {:?}",
+                                    install_code.wasm_module_hash,
+                                    INSTALL_CODE_VERIFICATION_TIMEOUT_SECONDS,
+                                    result,
+                                ),
+                            )),
+                        );
+                    }
+                }
+            }
+        }
+
+        self.verifying_install_code_proposals = false;
+    }
+
+    async fn get_module_hash(
+        &mut self,
+        install_code: &InstallCode,
+    ) -> Result<Option<Vec<u8>>, GovernanceError> {
+        let canister_id = install_code.valid_canister_id()?;
+        let payload = Encode!(&CanisterIdRecord::from(canister_id)).map_err(|err| {
+            GovernanceError::new_with_message(ErrorType::PreconditionFailed, err.to_string())
+        })?;
+        let response = self
+            .env
+            .call_canister_method(ROOT_CANISTER_ID, "canister_status", payload)
+            .await
+            .map_err(|(code, message)| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!(
+                        "Failed to get the status of {} from NNS root. Code {:?}: {}",
+                        canister_id, code, message
+                    ),
+                )
+            })?;
+        let status = Decode!(&response, CanisterStatusResult).map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::External,
+                format!("Failed to decode the canister status: {}", err),
+            )
+        })?;
+        Ok(status.module_hash)
+    }
+
     /// Fails immediately, because this type of proposal is obsolete.
     fn set_sns_token_swap_open_time_window(
         &mut self,
@@ -4916,6 +5236,16 @@ impl Governance {
             .expect("NetworkEconomics not present")
     }
 
+    /// The parameters that govern how the deciding voting power of neurons
+    /// that have not refreshed their voting power decays. Falls back to the
+    /// default values if they are not set in the network economics.
+    pub(crate) fn voting_power_economics(&self) -> VotingPowerEconomics {
+        self.economics()
+            .voting_power_economics
+            .clone()
+            .unwrap_or_else(VotingPowerEconomics::with_default_values)
+    }
+
     /// Inserts a proposals that has already been validated in the state.
     ///
     /// This is a low-level function that makes no verification whatsoever.
@@ -4987,6 +5317,9 @@ impl Governance {
             Action::ManageNeuron(manage_neuron) => {
                 self.validate_manage_neuron_proposal(manage_neuron)
             }
+
+            Action::InstallCode(install_code) => install_code.validate(),
+
             Action::ManageNetworkEconomics(_)
             | Action::ApproveGenesisKyc(_)
             | Action::AddOrRemoveNodeProvider(_)
@@ -5485,6 +5818,13 @@ impl Governance {
                     ..proposal.clone()
                 }
             }
+            // We record the hashes of the wasm module and of the argument, so
+            // that they can be verified even after the large fields are
+            // omitted from listings.
+            Action::InstallCode(ref install_code) => Proposal {
+                action: Some(Action::InstallCode(install_code.clone().with_hashes())),
+                ..proposal.clone()
+            },
             _ => proposal.clone(),
         };
 
@@ -5617,10 +5957,15 @@ impl Governance {
             _ => {
                 let mut ballots = HashMap::<u64, Ballot>::new();
                 let mut total_power: u128 = 0;
+                let voting_power_economics = self.voting_power_economics();
                 // No neuron in the stable storage should have maturity.
 
                 for neuron in self.neuron_store.voting_eligible_neurons(now_seconds) {
-                    let voting_power = neuron.voting_power(now_seconds);
+                    // Neurons that have not refreshed their voting power in a
+                    // while get a reduced say. Since rewards are based on
+                    // ballots, this also reduces their voting rewards.
+                    let voting_power =
+                        neuron.deciding_voting_power(&voting_power_economics, now_seconds);
 
                     total_power += voting_power as u128;
 
@@ -5937,6 +6282,9 @@ impl Governance {
             &mut self.neuron_store,
         );
 
+        // Voting directly confirms that the neuron is still actively managed.
+        self.with_neuron_mut(neuron_id, |neuron| neuron.refresh_voting_power(now_seconds))?;
+
         self.process_proposal(proposal_id.id);
 
         Ok(())
@@ -5948,6 +6296,8 @@ impl Governance {
     /// this topic. If the list has at least one element, replace the
     /// current list of followees for the given topic with the
     /// provided list. Note that the list is replaced, not added to.
+    ///
+    /// Setting following also refreshes the neuron's voting power.
     fn follow(
         &mut self,
         id: &NeuronId,
@@ -6015,7 +6365,9 @@ impl Governance {
             ));
         }
 
+        let now_seconds = self.env.now();
         self.with_neuron_mut(id, |neuron| {
+            neuron.refresh_voting_power(now_seconds);
             if follow_request.followees.is_empty() {
                 neuron.followees.remove(&follow_request.topic)
             } else {
@@ -6031,6 +6383,28 @@ impl Governance {
         Ok(())
     }
 
+    /// Confirms the neuron's following and restores its full deciding voting
+    /// power, as of now.
+    ///
+    /// The caller must be the controller or a hot key of the neuron.
+    fn refresh_voting_power(
+        &mut self,
+        id: &NeuronId,
+        caller: &PrincipalId,
+    ) -> Result<(), GovernanceError> {
+        let is_caller_authorized_to_vote =
+            self.with_neuron(id, |neuron| neuron.is_authorized_to_vote(caller))?;
+        if !is_caller_authorized_to_vote {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::NotAuthorized,
+                "Caller is not authorized to refresh the voting power of neuron.",
+            ));
+        }
+
+        let now_seconds = self.env.now();
+        self.with_neuron_mut(id, |neuron| neuron.refresh_voting_power(now_seconds))
+    }
+
     fn configure_neuron(
         &mut self,
         id: &NeuronId,
@@ -6220,6 +6594,7 @@ impl Governance {
             known_neuron_data: None,
             spawn_at_timestamp_seconds: None,
             neuron_type: None,
+            voting_power_refreshed_timestamp_seconds: Some(now),
         };
 
         // This also verifies that there are not too many neurons already.
@@ -6451,6 +6826,9 @@ impl Governance {
             Some(Command::RegisterVote(v)) => self
                 .register_vote(&id, caller, v)
                 .map(|_| ManageNeuronResponse::register_vote_response()),
+            Some(Command::RefreshVotingPower(_)) => self
+                .refresh_voting_power(&id, caller)
+                .map(|_| ManageNeuronResponse::refresh_voting_power_response()),
             Some(Command::ClaimOrRefresh(_)) => {
                 panic!("This should have already returned")
             }
@@ -6566,6 +6944,10 @@ impl Governance {
             self.spawn_neurons().await;
         }
 
+        if self.should_verify_install_code_proposals() {
+            self.verify_install_code_proposals().await;
+        }
+
         self.unstake_maturity_of_dissolved_neurons();
         self.maybe_gc();
         self.maybe_run_migrations();
@@ -6632,6 +7014,51 @@ impl Governance {
         }
     }
 
+    /// Clears the following (except on the NeuronManagement topic) of neurons
+    /// that have not refreshed their voting power for longer than
+    /// `start_reducing_voting_power_after_seconds + clear_following_after_seconds`.
+    /// At that point, their deciding voting power is already zero.
+    ///
+    /// Each call visits at most CLEAR_FOLLOWING_BATCH_SIZE neurons, in heap
+    /// and stable storage, and continues where the previous call stopped.
+    /// Once all neurons have been visited, the next call starts over. This is
+    /// intended to be called by a timer.
+    pub fn clear_following_of_neurons_that_did_not_refresh_voting_power(&mut self) {
+        let now_seconds = self.env.now();
+        let voting_power_economics = self.voting_power_economics();
+        let next_neuron_id = self
+            .next_neuron_id_to_clear_following
+            .unwrap_or(NeuronId { id: 0 });
+
+        self.next_neuron_id_to_clear_following = self
+            .neuron_store
+            .batch_clear_following_of_neurons_that_did_not_refresh_voting_power(
+                next_neuron_id,
+                CLEAR_FOLLOWING_BATCH_SIZE,
+                &voting_power_economics,
+                now_seconds,
+            );
+    }
+
+    /// Sets the voting power refreshed timestamp of neurons that were created
+    /// before it was tracked to the current time, i.e. the upgrade time. This
+    /// is intended to be called in post_upgrade.
+    ///
+    /// Only heap neurons are backfilled here, as there may be too many neurons
+    /// in stable storage to do this within a single message. These are
+    /// inactive, so they cannot vote anyway; their timestamp is set by
+    /// `clear_following_of_neurons_that_did_not_refresh_voting_power`.
+    pub fn backfill_voting_power_refreshed_timestamps(&mut self) {
+        let now_seconds = self.env.now();
+        let backfilled = self
+            .neuron_store
+            .backfill_heap_voting_power_refreshed_timestamps(now_seconds);
+        println!(
+            "{}Backfilled the voting power refreshed timestamp of {} neurons",
+            LOG_PREFIX, backfilled
+        );
+    }
+
     fn can_spawn_neurons(&self) -> bool {
         let spawning = self.heap_data.spawning_neurons;
         spawning.is_none() || !spawning.unwrap()
@@ -237,8 +237,9 @@ impl ManageNeuronRequestHandler<manage_neuron::Merge>
             .without_deprecated_topics_from_followees();
 
         let now = gov_proxy.now();
-        let source_neuron_info = source_neuron.get_neuron_info(now);
-        let target_neuron_info = target_neuron.get_neuron_info(now);
+        let voting_power_economics = gov_proxy.voting_power_economics();
+        let source_neuron_info = source_neuron.get_neuron_info(&voting_power_economics, now);
+        let target_neuron_info = target_neuron.get_neuron_info(&voting_power_economics, now);
 
         Ok(ManageNeuronResponse::merge_response(MergeResponse {
             source_neuron: Some(source_neuron),
@@ -1,6 +1,6 @@
 use crate::{
     governance::Governance,
-    pb::v1::{GovernanceError, Neuron},
+    pb::v1::{GovernanceError, Neuron, VotingPowerEconomics},
 };
 use async_trait::async_trait;
 use ic_nns_common::pb::v1::NeuronId;
@@ -81,6 +81,16 @@ impl GovernanceMutationProxy<'_> {
             }
         }
     }
+
+    /// Get the parameters that govern the decay of deciding voting power.
+    pub fn voting_power_economics(&self) -> VotingPowerEconomics {
+        match self {
+            GovernanceMutationProxy::Committing(real) => real.voting_power_economics(),
+            GovernanceMutationProxy::Simulating(simulating) => {
+                simulating.real_gov.voting_power_economics()
+            }
+        }
+    }
 }
 
 /// A struct representing a read-only reference to Governance along with accumulating changes, allowing
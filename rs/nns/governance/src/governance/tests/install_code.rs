@@ -0,0 +1,267 @@
+use crate::{
+    governance::{
+        tests::{ExpectedCallCanisterMethodCallArguments, MockEnvironment, StubCMC, StubIcpLedger},
+        Governance,
+    },
+    pb::v1::{
+        install_code::CanisterInstallMode, proposal::Action, Governance as GovernanceProto,
+        InstallCode, Proposal, ProposalData, ProposalStatus, Tally,
+    },
+    proposals::install_code::{
+        INSTALL_CODE_VERIFICATION_INTERVAL_SECONDS, INSTALL_CODE_VERIFICATION_TIMEOUT_SECONDS,
+    },
+};
+use candid::{Encode, Nat};
+use ic_crypto_sha2::Sha256;
+use ic_nervous_system_clients::{
+    canister_id_record::CanisterIdRecord,
+    canister_status::{CanisterStatusResult, CanisterStatusType, DefiniteCanisterSettings},
+};
+use ic_nns_common::pb::v1::ProposalId;
+use ic_nns_constants::{REGISTRY_CANISTER_ID, ROOT_CANISTER_ID};
+use maplit::btreemap;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+const PROPOSAL_ID: u64 = 1;
+const DECIDED_TIMESTAMP_SECONDS: u64 = 1_000;
+const WASM_MODULE: &[u8] = &[0, 0x61, 0x73, 0x6D, 1, 0, 0, 0];
+const OLD_MODULE_HASH: [u8; 32] = [42; 32];
+
+fn install_code() -> InstallCode {
+    InstallCode {
+        canister_id: Some(REGISTRY_CANISTER_ID.get()),
+        install_mode: Some(CanisterInstallMode::Upgrade as i32),
+        wasm_module: Some(WASM_MODULE.to_vec()),
+        arg: Some(vec![]),
+        skip_stopping_before_installing: None,
+        wasm_module_hash: None,
+        arg_hash: None,
+    }
+    .with_hashes()
+}
+
+fn canister_status_call(
+    module_hash: Option<Vec<u8>>,
+) -> (
+    ExpectedCallCanisterMethodCallArguments<'static>,
+    Result<Vec<u8>, (Option<i32>, String)>,
+) {
+    (
+        ExpectedCallCanisterMethodCallArguments {
+            target: ROOT_CANISTER_ID,
+            method_name: "canister_status",
+            request: Encode!(&CanisterIdRecord::from(REGISTRY_CANISTER_ID)).unwrap(),
+        },
+        Ok(Encode!(&CanisterStatusResult {
+            status: CanisterStatusType::Running,
+            module_hash,
+            memory_size: Nat::from(0_u64),
+            settings: DefiniteCanisterSettings {
+                controllers: vec![ROOT_CANISTER_ID.get()],
+            },
+            cycles: Nat::from(0_u64),
+        })
+        .unwrap()),
+    )
+}
+
+fn governance_with_adopted_install_code_proposal(
+    expected_calls: Vec<(
+        ExpectedCallCanisterMethodCallArguments<'static>,
+        Result<Vec<u8>, (Option<i32>, String)>,
+    )>,
+    install_code_module_hash_before_execution: Option<Vec<u8>>,
+    now: Arc<Mutex<u64>>,
+) -> Governance {
+    let proposal_data = ProposalData {
+        id: Some(ProposalId { id: PROPOSAL_ID }),
+        proposal: Some(Proposal {
+            title: Some("Upgrade the registry".to_string()),
+            action: Some(Action::InstallCode(install_code())),
+            ..Default::default()
+        }),
+        latest_tally: Some(Tally {
+            timestamp_seconds: DECIDED_TIMESTAMP_SECONDS,
+            yes: 100,
+            no: 0,
+            total: 100,
+        }),
+        decided_timestamp_seconds: DECIDED_TIMESTAMP_SECONDS,
+        install_code_module_hash_before_execution,
+        ..Default::default()
+    };
+    assert_eq!(proposal_data.status(), ProposalStatus::Adopted);
+
+    Governance::new(
+        GovernanceProto {
+            proposals: btreemap! { PROPOSAL_ID => proposal_data },
+            ..GovernanceProto::default()
+        },
+        Box::new(MockEnvironment {
+            expected_call_canister_method_calls: Arc::new(Mutex::new(VecDeque::from(
+                expected_calls,
+            ))),
+            now,
+        }),
+        Box::new(StubIcpLedger {}),
+        Box::new(StubCMC {}),
+    )
+}
+
+fn proposal_status(governance: &Governance) -> ProposalStatus {
+    governance.heap_data.proposals[&PROPOSAL_ID].status()
+}
+
+fn change_nns_canister_call(
+    result: Result<Vec<u8>, (Option<i32>, String)>,
+) -> (
+    ExpectedCallCanisterMethodCallArguments<'static>,
+    Result<Vec<u8>, (Option<i32>, String)>,
+) {
+    let request = install_code().change_canister_request().unwrap();
+    (
+        ExpectedCallCanisterMethodCallArguments {
+            target: ROOT_CANISTER_ID,
+            method_name: "change_nns_canister",
+            request: Encode!(&request).unwrap(),
+        },
+        result,
+    )
+}
+
+#[tokio::test]
+async fn test_perform_install_code_calls_root() {
+    let now = Arc::new(Mutex::new(DECIDED_TIMESTAMP_SECONDS));
+    let mut governance = governance_with_adopted_install_code_proposal(
+        vec![
+            canister_status_call(Some(OLD_MODULE_HASH.to_vec())),
+            change_nns_canister_call(Ok(Encode!().unwrap())),
+        ],
+        None,
+        now,
+    );
+
+    governance
+        .perform_install_code(PROPOSAL_ID, install_code())
+        .await;
+
+    // Root replying is not enough for the proposal to be considered executed.
+    assert_eq!(proposal_status(&governance), ProposalStatus::Adopted);
+    assert_eq!(
+        governance.heap_data.proposals[&PROPOSAL_ID].install_code_module_hash_before_execution,
+        Some(OLD_MODULE_HASH.to_vec())
+    );
+}
+
+#[tokio::test]
+async fn test_perform_install_code_fails_when_module_hash_is_already_installed() {
+    let now = Arc::new(Mutex::new(DECIDED_TIMESTAMP_SECONDS));
+    let mut governance = governance_with_adopted_install_code_proposal(
+        vec![canister_status_call(Some(
+            Sha256::hash(WASM_MODULE).to_vec(),
+        ))],
+        None,
+        now,
+    );
+
+    governance
+        .perform_install_code(PROPOSAL_ID, install_code())
+        .await;
+
+    // Root is not called, because success could not be told apart.
+    assert_eq!(proposal_status(&governance), ProposalStatus::Failed);
+}
+
+#[tokio::test]
+async fn test_perform_install_code_fails_when_root_rejects() {
+    let now = Arc::new(Mutex::new(DECIDED_TIMESTAMP_SECONDS));
+    let mut governance = governance_with_adopted_install_code_proposal(
+        vec![
+            canister_status_call(Some(OLD_MODULE_HASH.to_vec())),
+            change_nns_canister_call(Err((Some(5), "Canister trapped".to_string()))),
+        ],
+        None,
+        now,
+    );
+
+    governance
+        .perform_install_code(PROPOSAL_ID, install_code())
+        .await;
+
+    assert_eq!(proposal_status(&governance), ProposalStatus::Failed);
+}
+
+#[tokio::test]
+async fn test_verify_install_code_proposals_marks_executed_when_module_hash_matches() {
+    let now = Arc::new(Mutex::new(DECIDED_TIMESTAMP_SECONDS + 10));
+    let mut governance = governance_with_adopted_install_code_proposal(
+        vec![
+            canister_status_call(Some(OLD_MODULE_HASH.to_vec())),
+            canister_status_call(Some(Sha256::hash(WASM_MODULE).to_vec())),
+        ],
+        Some(OLD_MODULE_HASH.to_vec()),
+        now,
+    );
+
+    // The canister still runs the old code.
+    governance.verify_install_code_proposals().await;
+    assert_eq!(proposal_status(&governance), ProposalStatus::Adopted);
+
+    // The upgrade went through.
+    governance.verify_install_code_proposals().await;
+    assert_eq!(proposal_status(&governance), ProposalStatus::Executed);
+
+    // Nothing left to verify, i.e. no further calls are made.
+    governance.verify_install_code_proposals().await;
+}
+
+#[tokio::test]
+async fn test_verify_install_code_proposals_marks_failed_after_timeout() {
+    let now = Arc::new(Mutex::new(DECIDED_TIMESTAMP_SECONDS + 10));
+    let mut governance = governance_with_adopted_install_code_proposal(
+        vec![
+            canister_status_call(Some(OLD_MODULE_HASH.to_vec())),
+            canister_status_call(Some(OLD_MODULE_HASH.to_vec())),
+        ],
+        Some(OLD_MODULE_HASH.to_vec()),
+        now.clone(),
+    );
+
+    governance.verify_install_code_proposals().await;
+    assert_eq!(proposal_status(&governance), ProposalStatus::Adopted);
+
+    *now.lock().unwrap() = DECIDED_TIMESTAMP_SECONDS + INSTALL_CODE_VERIFICATION_TIMEOUT_SECONDS;
+    governance.verify_install_code_proposals().await;
+    assert_eq!(proposal_status(&governance), ProposalStatus::Failed);
+}
+
+#[tokio::test]
+async fn test_verify_install_code_proposals_skips_proposals_not_yet_executed() {
+    let now = Arc::new(Mutex::new(DECIDED_TIMESTAMP_SECONDS + 10));
+    // No calls are expected, because the module hash before execution has not
+    // been recorded yet.
+    let mut governance = governance_with_adopted_install_code_proposal(vec![], None, now);
+
+    governance.verify_install_code_proposals().await;
+    assert_eq!(proposal_status(&governance), ProposalStatus::Adopted);
+}
+
+#[tokio::test]
+async fn test_install_code_proposals_are_verified_at_most_once_per_interval() {
+    let now = Arc::new(Mutex::new(DECIDED_TIMESTAMP_SECONDS + 10));
+    let mut governance = governance_with_adopted_install_code_proposal(
+        vec![canister_status_call(Some(OLD_MODULE_HASH.to_vec()))],
+        Some(OLD_MODULE_HASH.to_vec()),
+        now.clone(),
+    );
+
+    assert!(governance.should_verify_install_code_proposals());
+    governance.verify_install_code_proposals().await;
+    assert!(!governance.should_verify_install_code_proposals());
+
+    *now.lock().unwrap() += INSTALL_CODE_VERIFICATION_INTERVAL_SECONDS;
+    assert!(governance.should_verify_install_code_proposals());
+}
@@ -32,6 +32,7 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+mod install_code;
 mod stake_maturity;
 
 #[test]
@@ -7,7 +7,8 @@ use crate::{
     },
     pb::v1::{
         governance_error::ErrorType, manage_neuron, neuron::DissolveState, Ballot, BallotInfo,
-        GovernanceError, Neuron, NeuronInfo, NeuronState, Topic, Vote,
+        GovernanceError, Neuron, NeuronInfo, NeuronState, Topic, Visibility, Vote,
+        VotingPowerEconomics,
     },
 };
 #[cfg(target_arch = "wasm32")]
@@ -126,6 +127,23 @@ impl Neuron {
             .collect()
     }
 
+    /// Returns whether anyone may read the full neuron. If the neuron has not
+    /// explicitly chosen its visibility, known neurons are public and all other
+    /// neurons are private.
+    pub fn effective_visibility(&self) -> Visibility {
+        match self.visibility.and_then(|v| Visibility::try_from(v).ok()) {
+            Some(Visibility::Private) => Visibility::Private,
+            Some(Visibility::Public) => Visibility::Public,
+            Some(Visibility::Unspecified) | None => {
+                if self.known_neuron_data.is_some() {
+                    Visibility::Public
+                } else {
+                    Visibility::Private
+                }
+            }
+        }
+    }
+
     /// Returns whether self is a member of Neurons Fund.
     pub(crate) fn is_a_neurons_fund_member(&self) -> bool {
         self.joined_community_fund_timestamp_seconds
@@ -167,6 +185,82 @@ impl Neuron {
         std::cmp::min(ad_stake, u64::MAX as u128) as u64
     }
 
+    /// Records that the neuron confirmed its voting power (and following) at
+    /// `now_seconds`. This restores the full deciding voting power.
+    pub(crate) fn refresh_voting_power(&mut self, now_seconds: u64) {
+        self.voting_power_refreshed_timestamp_seconds = Some(now_seconds);
+    }
+
+    /// Neurons that existed before refreshing was tracked get the upgrade time
+    /// as their refreshed timestamp (see
+    /// `Governance::backfill_voting_power_refreshed_timestamps`). Until that
+    /// happens, they are treated as having just refreshed.
+    fn seconds_since_voting_power_refreshed(&self, now_seconds: u64) -> u64 {
+        self.voting_power_refreshed_timestamp_seconds
+            .map_or(0, |timestamp_seconds| {
+                now_seconds.saturating_sub(timestamp_seconds)
+            })
+    }
+
+    /// Returns the voting power that this neuron uses to decide proposals.
+    ///
+    /// This is the (potential) voting power returned by `voting_power`, as
+    /// long as the neuron has refreshed its voting power within the last
+    /// `start_reducing_voting_power_after_seconds`. After that, it decreases
+    /// linearly, reaching zero after another `clear_following_after_seconds`.
+    pub fn deciding_voting_power(
+        &self,
+        voting_power_economics: &VotingPowerEconomics,
+        now_seconds: u64,
+    ) -> u64 {
+        let potential_voting_power = self.voting_power(now_seconds);
+
+        let seconds_since_refresh = self.seconds_since_voting_power_refreshed(now_seconds);
+        let start_reducing_after_seconds =
+            voting_power_economics.get_start_reducing_voting_power_after_seconds();
+        if seconds_since_refresh <= start_reducing_after_seconds {
+            return potential_voting_power;
+        }
+
+        let clear_following_after_seconds =
+            voting_power_economics.get_clear_following_after_seconds();
+        let reducing_for_seconds = seconds_since_refresh - start_reducing_after_seconds;
+        if reducing_for_seconds >= clear_following_after_seconds {
+            return 0;
+        }
+
+        // Here, 0 < reducing_for_seconds < clear_following_after_seconds, so
+        // the result is strictly between 0 and potential_voting_power.
+        let remaining_seconds = (clear_following_after_seconds - reducing_for_seconds) as u128;
+        ((potential_voting_power as u128 * remaining_seconds)
+            / clear_following_after_seconds as u128) as u64
+    }
+
+    /// Whether the neuron has gone without refreshing its voting power for so
+    /// long that its following should be cleared.
+    pub(crate) fn should_clear_following(
+        &self,
+        voting_power_economics: &VotingPowerEconomics,
+        now_seconds: u64,
+    ) -> bool {
+        let clear_after_seconds = voting_power_economics
+            .get_start_reducing_voting_power_after_seconds()
+            .saturating_add(voting_power_economics.get_clear_following_after_seconds());
+        self.seconds_since_voting_power_refreshed(now_seconds) >= clear_after_seconds
+            && self
+                .followees
+                .keys()
+                .any(|topic| *topic != Topic::NeuronManagement as i32)
+    }
+
+    /// Removes the neuron's followees on all topics except NeuronManagement.
+    /// Following on NeuronManagement is kept, because it controls who may
+    /// manage the neuron, rather than how the neuron votes.
+    pub(crate) fn clear_following_except_neuron_management(&mut self) {
+        self.followees
+            .retain(|topic, _| *topic == Topic::NeuronManagement as i32);
+    }
+
     /// Given the specified `ballots`: determine how this neuron would
     /// vote on a proposal of `topic` based on which neurons this
     /// neuron follows on this topic (or on the default topic if this
@@ -631,11 +725,33 @@ impl Neuron {
                 }
                 Ok(())
             }
+            manage_neuron::configure::Operation::SetVisibility(set_visibility) => {
+                let visibility = set_visibility
+                    .visibility
+                    .and_then(|v| Visibility::try_from(v).ok())
+                    .filter(|v| *v != Visibility::Unspecified)
+                    .ok_or_else(|| {
+                        GovernanceError::new_with_message(
+                            ErrorType::InvalidCommand,
+                            format!(
+                                "Operation SetVisibility requires a visibility of either \
+                                 private or public, but got {:?}",
+                                set_visibility.visibility,
+                            ),
+                        )
+                    })?;
+                self.visibility = Some(visibility as i32);
+                Ok(())
+            }
         }
     }
 
     /// Get the 'public' information associated with this neuron.
-    pub fn get_neuron_info(&self, now_seconds: u64) -> NeuronInfo {
+    pub fn get_neuron_info(
+        &self,
+        voting_power_economics: &VotingPowerEconomics,
+        now_seconds: u64,
+    ) -> NeuronInfo {
         NeuronInfo {
             retrieved_at_timestamp_seconds: now_seconds,
             state: self.state(now_seconds) as i32,
@@ -648,6 +764,11 @@ impl Neuron {
             joined_community_fund_timestamp_seconds: self.joined_community_fund_timestamp_seconds,
             known_neuron_data: self.known_neuron_data.as_ref().cloned(),
             neuron_type: self.neuron_type,
+            voting_power_refreshed_timestamp_seconds: self.voting_power_refreshed_timestamp_seconds,
+            deciding_voting_power: Some(
+                self.deciding_voting_power(voting_power_economics, now_seconds),
+            ),
+            visibility: Some(self.effective_visibility() as i32),
         }
     }
 
@@ -879,8 +1000,13 @@ impl NeuronInfo {
 
 #[cfg(test)]
 mod tests {
-    use crate::pb::v1::{neuron::DissolveState, Neuron, NeuronState};
+    use crate::pb::v1::{
+        neuron::{DissolveState, Followees},
+        Neuron, NeuronState, Topic, VotingPowerEconomics,
+    };
     use ic_nervous_system_common::{E8, SECONDS_PER_DAY};
+    use ic_nns_common::pb::v1::NeuronId;
+    use maplit::hashmap;
 
     const NOW: u64 = 123_456_789;
 
@@ -1180,4 +1306,95 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_deciding_voting_power_decays_linearly_after_not_refreshing() {
+        let voting_power_economics = VotingPowerEconomics {
+            start_reducing_voting_power_after_seconds: Some(1_000),
+            clear_following_after_seconds: Some(100),
+        };
+        let neuron = Neuron {
+            cached_neuron_stake_e8s: 10 * E8,
+            dissolve_state: Some(DissolveState::DissolveDelaySeconds(0)),
+            aging_since_timestamp_seconds: u64::MAX,
+            voting_power_refreshed_timestamp_seconds: Some(NOW),
+            ..Default::default()
+        };
+        let potential_voting_power = neuron.voting_power(NOW);
+        assert_eq!(potential_voting_power, 10 * E8);
+
+        for (seconds_since_refresh, expected_deciding_voting_power) in [
+            (0, potential_voting_power),
+            (1_000, potential_voting_power),
+            (1_025, potential_voting_power * 3 / 4),
+            (1_050, potential_voting_power / 2),
+            (1_099, potential_voting_power / 100),
+            (1_100, 0),
+            (5_000, 0),
+        ] {
+            assert_eq!(
+                neuron.deciding_voting_power(&voting_power_economics, NOW + seconds_since_refresh),
+                expected_deciding_voting_power,
+                "{} seconds after refreshing",
+                seconds_since_refresh
+            );
+        }
+    }
+
+    #[test]
+    fn test_refresh_voting_power_restores_deciding_voting_power() {
+        let voting_power_economics = VotingPowerEconomics::with_default_values();
+        let mut neuron = Neuron {
+            cached_neuron_stake_e8s: 10 * E8,
+            dissolve_state: Some(DissolveState::DissolveDelaySeconds(0)),
+            aging_since_timestamp_seconds: u64::MAX,
+            voting_power_refreshed_timestamp_seconds: Some(NOW),
+            ..Default::default()
+        };
+        let later = NOW + 10 * TWELVE_MONTHS_SECONDS;
+        assert_eq!(
+            neuron.deciding_voting_power(&voting_power_economics, later),
+            0
+        );
+
+        neuron.refresh_voting_power(later);
+
+        assert_eq!(neuron.voting_power_refreshed_timestamp_seconds, Some(later));
+        assert_eq!(
+            neuron.deciding_voting_power(&voting_power_economics, later),
+            neuron.voting_power(later)
+        );
+    }
+
+    #[test]
+    fn test_clear_following_keeps_neuron_management_followees() {
+        let voting_power_economics = VotingPowerEconomics {
+            start_reducing_voting_power_after_seconds: Some(1_000),
+            clear_following_after_seconds: Some(100),
+        };
+        let followees = |id| Followees {
+            followees: vec![NeuronId { id }],
+        };
+        let mut neuron = Neuron {
+            voting_power_refreshed_timestamp_seconds: Some(NOW),
+            followees: hashmap! {
+                Topic::Unspecified as i32 => followees(1),
+                Topic::Governance as i32 => followees(2),
+                Topic::NeuronManagement as i32 => followees(3),
+            },
+            ..Default::default()
+        };
+
+        assert!(!neuron.should_clear_following(&voting_power_economics, NOW + 1_099));
+        assert!(neuron.should_clear_following(&voting_power_economics, NOW + 1_100));
+
+        neuron.clear_following_except_neuron_management();
+
+        assert_eq!(
+            neuron.followees,
+            hashmap! { Topic::NeuronManagement as i32 => followees(3) }
+        );
+        // Nothing left to clear.
+        assert!(!neuron.should_clear_following(&voting_power_economics, NOW + 1_100));
+    }
 }
@@ -3,7 +3,10 @@ use crate::{
         Environment, TimeWarp, LOG_PREFIX, MIN_DISSOLVE_DELAY_FOR_VOTE_ELIGIBILITY_SECONDS,
     },
     neuron::neuron_id_range_to_u64_range,
-    pb::v1::{governance_error::ErrorType, GovernanceError, Neuron, NeuronState, Topic},
+    pb::v1::{
+        governance_error::ErrorType, GovernanceError, Neuron, NeuronState, Topic,
+        VotingPowerEconomics,
+    },
     storage::{
         neuron_indexes::{CorruptedNeuronIndexes, NeuronIndex},
         with_stable_neuron_indexes, with_stable_neuron_indexes_mut, with_stable_neuron_store,
@@ -644,6 +647,96 @@ impl NeuronStore {
             .collect()
     }
 
+    /// Sets the voting power refreshed timestamp of the heap neurons that do not
+    /// have one yet to `now_seconds`. Neurons in stable storage are inactive and
+    /// are handled by `batch_clear_following_of_neurons_that_did_not_refresh_voting_power`.
+    ///
+    /// The timestamp is not part of any index, so the neurons are updated in
+    /// place.
+    pub fn backfill_heap_voting_power_refreshed_timestamps(&mut self, now_seconds: u64) -> usize {
+        let mut backfilled = 0;
+        for neuron in self.heap_neurons.values_mut() {
+            if neuron.voting_power_refreshed_timestamp_seconds.is_none() {
+                neuron.voting_power_refreshed_timestamp_seconds = Some(now_seconds);
+                backfilled += 1;
+            }
+        }
+        backfilled
+    }
+
+    /// Visits at most `batch_size` neurons, in heap or stable storage, starting
+    /// with `next_neuron_id`. The following of visited neurons that have not
+    /// refreshed their voting power for too long is cleared, and neurons
+    /// without a voting power refreshed timestamp get `now_seconds`.
+    ///
+    /// Returns the neuron id the next batch will start with, or None if all
+    /// neurons have been visited.
+    pub fn batch_clear_following_of_neurons_that_did_not_refresh_voting_power(
+        &mut self,
+        next_neuron_id: NeuronId,
+        batch_size: usize,
+        voting_power_economics: &VotingPowerEconomics,
+        now_seconds: u64,
+    ) -> Option<NeuronId> {
+        let needs_update = |neuron: &Neuron| {
+            neuron.voting_power_refreshed_timestamp_seconds.is_none()
+                || neuron.should_clear_following(voting_power_economics, now_seconds)
+        };
+
+        // The neuron ids of the batch in ascending order, merged from both
+        // storages, along with whether the neuron needs to be updated.
+        let mut batch = BTreeMap::<u64, bool>::new();
+        let mut heap_len = 0;
+        for (neuron_id, neuron) in self
+            .heap_neurons
+            .range(next_neuron_id.id..)
+            .take(batch_size)
+        {
+            batch.insert(*neuron_id, needs_update(neuron));
+            heap_len += 1;
+        }
+        let stable_len = with_stable_neuron_store(|stable_neuron_store| {
+            let mut stable_len = 0;
+            for neuron in stable_neuron_store
+                .range_neurons(next_neuron_id..)
+                .take(batch_size)
+            {
+                let neuron_id = neuron.id.expect("Neuron must have an id").id;
+                *batch.entry(neuron_id).or_default() |= needs_update(&neuron);
+                stable_len += 1;
+            }
+            stable_len
+        });
+        let is_last_batch =
+            heap_len < batch_size && stable_len < batch_size && batch.len() <= batch_size;
+
+        let batch: Vec<(u64, bool)> = batch.into_iter().take(batch_size).collect();
+        for (neuron_id, _) in batch.iter().filter(|(_, needs_update)| *needs_update) {
+            let neuron_id = NeuronId { id: *neuron_id };
+            let result = self.with_neuron_mut(&neuron_id, |neuron| {
+                if neuron.voting_power_refreshed_timestamp_seconds.is_none() {
+                    neuron.refresh_voting_power(now_seconds);
+                } else if neuron.should_clear_following(voting_power_economics, now_seconds) {
+                    neuron.clear_following_except_neuron_management();
+                }
+            });
+            if let Err(e) = result {
+                println!(
+                    "{}Error when clearing following of neuron {:?}: {:?}",
+                    LOG_PREFIX, neuron_id, e
+                );
+            }
+        }
+
+        if is_last_batch {
+            None
+        } else {
+            batch
+                .last()
+                .and_then(|(neuron_id, _)| NeuronId { id: *neuron_id }.next())
+        }
+    }
+
     /// List all neuron ids of known neurons
     pub fn list_known_neuron_ids(&self) -> Vec<NeuronId> {
         with_stable_neuron_indexes(|indexes| indexes.known_neuron().list_known_neuron_ids())
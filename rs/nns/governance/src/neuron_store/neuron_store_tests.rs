@@ -512,6 +512,101 @@ fn test_batch_validate_neurons_in_stable_store_are_inactive_invalid() {
     assert_eq!(invalid_neuron_ids, vec![neuron.id.unwrap()]);
 }
 
+#[test]
+fn test_batch_clear_following_of_neurons_that_did_not_refresh_voting_power() {
+    let mut neuron_store = NeuronStore::new(BTreeMap::new());
+    let now = neuron_store.now();
+    let voting_power_economics = VotingPowerEconomics {
+        start_reducing_voting_power_after_seconds: Some(100),
+        clear_following_after_seconds: Some(10),
+    };
+    let followees = hashmap! {
+        Topic::Governance as i32 => Followees { followees: vec![NeuronId { id: 100 }] },
+    };
+
+    // Step 1.1: add 10 active neurons. The odd ones have not refreshed their voting power for so
+    // long that their following should be cleared, the even ones have no timestamp yet.
+    for i in 1..=10 {
+        neuron_store
+            .add_neuron(Neuron {
+                cached_neuron_stake_e8s: 1,
+                followees: followees.clone(),
+                voting_power_refreshed_timestamp_seconds: if i % 2 == 1 {
+                    Some(now - 110)
+                } else {
+                    None
+                },
+                ..simple_neuron(i)
+            })
+            .unwrap();
+    }
+    // Step 1.2: add 5 inactive neurons, which are stored in stable storage.
+    for i in 11..=15 {
+        neuron_store
+            .add_neuron(Neuron {
+                cached_neuron_stake_e8s: 0,
+                dissolve_state: Some(DissolveState::WhenDissolvedTimestampSeconds(1)),
+                ..simple_neuron(i)
+            })
+            .unwrap();
+    }
+    assert!(is_neuron_in_stable(NeuronId { id: 15 }));
+
+    // Step 2: process all neurons in batches of 4.
+    let mut next_neuron_id = NeuronId::min_value();
+    let mut num_batches = 0;
+    loop {
+        num_batches += 1;
+        match neuron_store.batch_clear_following_of_neurons_that_did_not_refresh_voting_power(
+            next_neuron_id,
+            4,
+            &voting_power_economics,
+            now,
+        ) {
+            Some(neuron_id) => next_neuron_id = neuron_id,
+            None => break,
+        }
+    }
+
+    // Step 3: verify that all neurons were visited in 4 batches.
+    assert_eq!(num_batches, 4);
+    for i in 1..=15 {
+        let neuron = neuron_store
+            .with_neuron(&NeuronId { id: i }, |neuron| neuron.clone())
+            .unwrap();
+        if i <= 10 && i % 2 == 1 {
+            assert_eq!(neuron.followees, hashmap! {}, "neuron {}", i);
+            assert_eq!(
+                neuron.voting_power_refreshed_timestamp_seconds,
+                Some(now - 110)
+            );
+        } else {
+            assert_eq!(
+                neuron.voting_power_refreshed_timestamp_seconds,
+                Some(now),
+                "neuron {}",
+                i
+            );
+        }
+        if i <= 10 && i % 2 == 0 {
+            assert_eq!(neuron.followees, followees, "neuron {}", i);
+        }
+    }
+    let mut followers =
+        neuron_store.get_followers_by_followee_and_topic(NeuronId { id: 100 }, Topic::Governance);
+    followers.sort_by_key(|neuron_id| neuron_id.id);
+    assert_eq!(
+        followers,
+        vec![
+            NeuronId { id: 2 },
+            NeuronId { id: 4 },
+            NeuronId { id: 6 },
+            NeuronId { id: 8 },
+            NeuronId { id: 10 },
+        ]
+    );
+}
+
 // Below are tests related to how the neurons are stored, which look at the internals of the neuron
 // store. They should probably be cleaned up after the inactive neuron migration since it's better
 // to test through its public API.
@@ -0,0 +1,244 @@
+use crate::pb::v1::{
+    governance_error::ErrorType, install_code::CanisterInstallMode, GovernanceError, InstallCode,
+};
+use ic_base_types::CanisterId;
+use ic_crypto_sha2::Sha256;
+use ic_ic00_types::CanisterInstallMode as RootCanisterInstallMode;
+use ic_nervous_system_root::change_canister::ChangeCanisterRequest;
+use ic_nns_constants::ROOT_CANISTER_ID;
+
+/// How long after an InstallCode proposal has been adopted the target canister
+/// has to report the proposed module hash. Past this deadline, the proposal is
+/// considered failed.
+pub const INSTALL_CODE_VERIFICATION_TIMEOUT_SECONDS: u64 = 24 * 60 * 60;
+
+/// How often the module hashes of the target canisters of pending InstallCode
+/// proposals are checked.
+pub const INSTALL_CODE_VERIFICATION_INTERVAL_SECONDS: u64 = 60;
+
+impl InstallCode {
+    /// Checks that the proposal is well-formed. In particular, if the proposer
+    /// supplied hashes, they must match the wasm module and the argument.
+    pub fn validate(&self) -> Result<(), GovernanceError> {
+        let canister_id = self.valid_canister_id()?;
+        if canister_id == ROOT_CANISTER_ID {
+            return Err(invalid_proposal_error(
+                "The code of NNS root cannot be changed through NNS root. \
+                 Use an NnsRootUpgrade proposal instead.",
+            ));
+        }
+
+        self.valid_install_mode()?;
+
+        let wasm_module = self.wasm_module.as_deref().unwrap_or_default();
+        if wasm_module.is_empty() {
+            return Err(invalid_proposal_error("The wasm module is required."));
+        }
+
+        let check_hash = |field_name: &str, hash: &Option<Vec<u8>>, content: &[u8]| match hash {
+            Some(hash) if hash[..] != Sha256::hash(content)[..] => {
+                Err(invalid_proposal_error(format!(
+                    "The {} does not match the content of the proposal.",
+                    field_name
+                )))
+            }
+            _ => Ok(()),
+        };
+        check_hash("wasm_module_hash", &self.wasm_module_hash, wasm_module)?;
+        check_hash(
+            "arg_hash",
+            &self.arg_hash,
+            self.arg.as_deref().unwrap_or_default(),
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns a copy of self where `wasm_module_hash` and `arg_hash` are
+    /// computed from the content of the proposal.
+    pub fn with_hashes(self) -> Self {
+        let wasm_module_hash =
+            Some(Sha256::hash(self.wasm_module.as_deref().unwrap_or_default()).to_vec());
+        let arg_hash = Some(Sha256::hash(self.arg.as_deref().unwrap_or_default()).to_vec());
+        Self {
+            wasm_module_hash,
+            arg_hash,
+            ..self
+        }
+    }
+
+    /// Drops the (potentially large) wasm module and argument, but keeps their
+    /// hashes, so that listed proposals can still be verified.
+    pub fn omit_large_fields(self) -> Self {
+        Self {
+            wasm_module: None,
+            arg: None,
+            ..self
+        }
+    }
+
+    /// The request to send to the `change_nns_canister` method of NNS root in
+    /// order to execute this proposal.
+    pub fn change_canister_request(&self) -> Result<ChangeCanisterRequest, GovernanceError> {
+        let canister_id = self.valid_canister_id()?;
+        let mode = match self.valid_install_mode()? {
+            CanisterInstallMode::Install => RootCanisterInstallMode::Install,
+            CanisterInstallMode::Reinstall => RootCanisterInstallMode::Reinstall,
+            CanisterInstallMode::Upgrade => RootCanisterInstallMode::Upgrade,
+            CanisterInstallMode::Unspecified => unreachable!("Checked by valid_install_mode."),
+        };
+        let stop_before_installing = !self.skip_stopping_before_installing.unwrap_or(false);
+
+        let request = ChangeCanisterRequest::new(stop_before_installing, mode, canister_id)
+            .with_wasm(self.wasm_module.clone().unwrap_or_default())
+            .with_arg(self.arg.clone().unwrap_or_default());
+
+        // Leave the memory allocation of the canister as it is.
+        Ok(ChangeCanisterRequest {
+            memory_allocation: None,
+            ..request
+        })
+    }
+
+    pub fn valid_canister_id(&self) -> Result<CanisterId, GovernanceError> {
+        let canister_id = self
+            .canister_id
+            .ok_or_else(|| invalid_proposal_error("The canister ID is required."))?;
+        CanisterId::try_from(canister_id).map_err(|err| {
+            invalid_proposal_error(format!(
+                "The canister ID {} is invalid: {}",
+                canister_id, err
+            ))
+        })
+    }
+
+    fn valid_install_mode(&self) -> Result<CanisterInstallMode, GovernanceError> {
+        match self
+            .install_mode
+            .and_then(|mode| CanisterInstallMode::try_from(mode).ok())
+        {
+            None | Some(CanisterInstallMode::Unspecified) => Err(invalid_proposal_error(format!(
+                "The install mode {:?} is invalid.",
+                self.install_mode
+            ))),
+            Some(mode) => Ok(mode),
+        }
+    }
+}
+
+fn invalid_proposal_error(message: impl ToString) -> GovernanceError {
+    GovernanceError::new_with_message(ErrorType::InvalidProposal, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_nns_constants::{GOVERNANCE_CANISTER_ID, REGISTRY_CANISTER_ID};
+
+    fn basic_install_code() -> InstallCode {
+        InstallCode {
+            canister_id: Some(REGISTRY_CANISTER_ID.get()),
+            install_mode: Some(CanisterInstallMode::Upgrade as i32),
+            wasm_module: Some(vec![0, 0x61, 0x73, 0x6D, 1, 0, 0, 0]),
+            arg: Some(vec![1, 2, 3]),
+            skip_stopping_before_installing: None,
+            wasm_module_hash: None,
+            arg_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_ok() {
+        assert_eq!(basic_install_code().validate(), Ok(()));
+        assert_eq!(basic_install_code().with_hashes().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_rejects_missing_or_invalid_fields() {
+        let invalid_install_codes = vec![
+            InstallCode {
+                canister_id: None,
+                ..basic_install_code()
+            },
+            InstallCode {
+                canister_id: Some(ROOT_CANISTER_ID.get()),
+                ..basic_install_code()
+            },
+            InstallCode {
+                install_mode: None,
+                ..basic_install_code()
+            },
+            InstallCode {
+                install_mode: Some(CanisterInstallMode::Unspecified as i32),
+                ..basic_install_code()
+            },
+            InstallCode {
+                install_mode: Some(100),
+                ..basic_install_code()
+            },
+            InstallCode {
+                wasm_module: None,
+                ..basic_install_code()
+            },
+            InstallCode {
+                wasm_module_hash: Some(vec![0; 32]),
+                ..basic_install_code()
+            },
+            InstallCode {
+                arg_hash: Some(vec![0; 32]),
+                ..basic_install_code()
+            },
+        ];
+
+        for install_code in invalid_install_codes {
+            let err = install_code.validate().unwrap_err();
+            assert_eq!(
+                err.error_type,
+                ErrorType::InvalidProposal as i32,
+                "{:?}",
+                install_code
+            );
+        }
+    }
+
+    #[test]
+    fn test_with_hashes_and_omit_large_fields() {
+        let install_code = basic_install_code().with_hashes();
+        assert_eq!(
+            install_code.wasm_module_hash,
+            Some(Sha256::hash(&[0, 0x61, 0x73, 0x6D, 1, 0, 0, 0]).to_vec())
+        );
+        assert_eq!(
+            install_code.arg_hash,
+            Some(Sha256::hash(&[1, 2, 3]).to_vec())
+        );
+
+        let listed = install_code.clone().omit_large_fields();
+        assert_eq!(
+            listed,
+            InstallCode {
+                wasm_module: None,
+                arg: None,
+                ..install_code
+            }
+        );
+    }
+
+    #[test]
+    fn test_change_canister_request() {
+        let install_code = InstallCode {
+            canister_id: Some(GOVERNANCE_CANISTER_ID.get()),
+            skip_stopping_before_installing: Some(true),
+            ..basic_install_code()
+        };
+
+        let request = install_code.change_canister_request().unwrap();
+
+        assert_eq!(request.canister_id, GOVERNANCE_CANISTER_ID);
+        assert_eq!(request.mode, RootCanisterInstallMode::Upgrade);
+        assert!(!request.stop_before_installing);
+        assert_eq!(request.wasm_module, install_code.wasm_module.unwrap());
+        assert_eq!(request.arg, vec![1, 2, 3]);
+        assert_eq!(request.memory_allocation, None);
+    }
+}
@@ -67,7 +67,7 @@ use ic_nns_governance::{
             disburse::Amount,
             ChangeAutoStakeMaturity, ClaimOrRefresh, Command, Configure, Disburse,
             DisburseToNeuron, IncreaseDissolveDelay, JoinCommunityFund, LeaveCommunityFund,
-            MergeMaturity, NeuronIdOrSubaccount, SetDissolveTimestamp, Spawn, Split,
+            MergeMaturity, NeuronIdOrSubaccount, SetDissolveTimestamp, SetVisibility, Spawn, Split,
             StartDissolving,
         },
         manage_neuron_response::{self, Command as CommandResponse},
@@ -92,7 +92,8 @@ use ic_nns_governance::{
         RewardEvent, RewardNodeProvider, RewardNodeProviders, SetDefaultFollowees,
         SettleCommunityFundParticipation, SettleNeuronsFundParticipationRequest,
         SwapBackgroundInformation, SwapParticipationLimits, Tally, TallyChange, Topic,
-        UpdateNodeProvider, Vote, WaitForQuietState, WaitForQuietStateDesc,
+        UpdateNodeProvider, Visibility, Vote, VotingPowerEconomics, WaitForQuietState,
+        WaitForQuietStateDesc,
     },
     proposals::create_service_nervous_system::ExecutedCreateServiceNervousSystemProposal,
 };
@@ -110,7 +111,7 @@ use ic_sns_wasm::pb::v1::{
 };
 use icp_ledger::{AccountIdentifier, Memo, Subaccount, Tokens};
 use lazy_static::lazy_static;
-use maplit::{btreemap, hashmap};
+use maplit::{btreemap, hashmap, hashset};
 use pretty_assertions::{assert_eq, assert_ne};
 use proptest::prelude::proptest;
 use rand::{prelude::IteratorRandom, rngs::StdRng, Rng, SeedableRng};
@@ -975,16 +976,23 @@ async fn test_cascade_following_new() {
         Changed::Changed(vec![NNSStateChange::GovernanceProto(vec![
             GovernanceChange::Neurons(vec![MapChange::Changed(
                 5,
-                vec![NeuronChange::RecentBallots(vec![VecChange::Added(
-                    0,
-                    vec![
-                        BallotInfoChange::ProposalId(OptionChange::Different(
-                            None,
-                            Some(ProposalId { id: 1 }),
-                        )),
-                        BallotInfoChange::Vote(I32Change(0, 1)),
-                    ],
-                )])],
+                vec![
+                    NeuronChange::RecentBallots(vec![VecChange::Added(
+                        0,
+                        vec![
+                            BallotInfoChange::ProposalId(OptionChange::Different(
+                                None,
+                                Some(ProposalId { id: 1 }),
+                            )),
+                            BallotInfoChange::Vote(I32Change(0, 1)),
+                        ],
+                    )]),
+                    // Voting directly refreshes the neuron's voting power.
+                    NeuronChange::VotingPowerRefreshedTimestampSeconds(OptionChange::Different(
+                        None,
+                        Some(999111000),
+                    )),
+                ],
             )]),
             GovernanceChange::Proposals(vec![MapChange::Changed(
                 1,
@@ -1078,16 +1086,21 @@ async fn test_cascade_following_new() {
                 ),
                 MapChange::Changed(
                     6,
-                    vec![NeuronChange::RecentBallots(vec![VecChange::Added(
-                        0,
-                        vec![
-                            BallotInfoChange::ProposalId(OptionChange::Different(
-                                None,
-                                Some(ProposalId { id: 1 }),
-                            )),
-                            BallotInfoChange::Vote(I32Change(0, 1)),
-                        ],
-                    )])],
+                    vec![
+                        NeuronChange::RecentBallots(vec![VecChange::Added(
+                            0,
+                            vec![
+                                BallotInfoChange::ProposalId(OptionChange::Different(
+                                    None,
+                                    Some(ProposalId { id: 1 }),
+                                )),
+                                BallotInfoChange::Vote(I32Change(0, 1)),
+                            ],
+                        )]),
+                        NeuronChange::VotingPowerRefreshedTimestampSeconds(
+                            OptionChange::Different(None, Some(999111000)),
+                        ),
+                    ],
                 ),
             ]),
             GovernanceChange::Proposals(vec![MapChange::Changed(
@@ -1670,6 +1683,106 @@ async fn test_all_follow_proposer() {
     );
 }
 
+/// Neurons that have not refreshed their voting power for a long time get
+/// ballots with reduced voting power (and therefore reduced rewards), until
+/// they refresh their voting power with the RefreshVotingPower command.
+#[test]
+fn test_refresh_voting_power() {
+    let driver = fake::FakeDriver::default();
+    let mut gov = Governance::new(
+        fixture_for_following(),
+        driver.get_fake_env(),
+        driver.get_fake_ledger(),
+        driver.get_fake_cmc(),
+    );
+    let voting_power_economics = VotingPowerEconomics::with_default_values();
+    let stale_neuron_id = NeuronId { id: 5 };
+
+    // Pretend that neuron 5 last refreshed its voting power so long ago that
+    // its deciding voting power has reached zero.
+    let stale_timestamp_seconds = driver.now()
+        - voting_power_economics.get_start_reducing_voting_power_after_seconds()
+        - voting_power_economics.get_clear_following_after_seconds();
+    gov.neuron_store
+        .with_neuron_mut(&stale_neuron_id, |neuron| {
+            neuron.voting_power_refreshed_timestamp_seconds = Some(stale_timestamp_seconds);
+        })
+        .expect("Neuron not found");
+
+    let make_motion_proposal = |gov: &mut Governance| {
+        gov.make_proposal(
+            &NeuronId { id: 1 },
+            // Must match neuron 1's serialized_id.
+            &principal(1),
+            &Proposal {
+                title: Some("A Reasonable Title".to_string()),
+                summary: "test".to_string(),
+                action: Some(proposal::Action::Motion(Motion {
+                    motion_text: "dummy text".to_string(),
+                })),
+                ..Default::default()
+            },
+        )
+        .now_or_never()
+        .unwrap()
+        .unwrap()
+    };
+    let ballot_voting_power = |gov: &Governance, proposal_id: ProposalId, neuron_id: u64| {
+        gov.get_proposal_data(proposal_id)
+            .unwrap()
+            .ballots
+            .get(&neuron_id)
+            .unwrap()
+            .voting_power
+    };
+
+    let proposal_id = make_motion_proposal(&mut gov);
+    assert_eq!(
+        ballot_voting_power(&gov, proposal_id, stale_neuron_id.id),
+        0
+    );
+    assert!(ballot_voting_power(&gov, proposal_id, 6) > 0);
+
+    let refresh_voting_power = |gov: &mut Governance, caller: PrincipalId| {
+        gov.manage_neuron(
+            &caller,
+            &ManageNeuron {
+                id: None,
+                neuron_id_or_subaccount: Some(NeuronIdOrSubaccount::NeuronId(stale_neuron_id)),
+                command: Some(manage_neuron::Command::RefreshVotingPower(
+                    manage_neuron::RefreshVotingPower {},
+                )),
+            },
+        )
+        .now_or_never()
+        .unwrap()
+    };
+
+    // Only the controller or a hot key of the neuron may refresh its voting power.
+    let response = refresh_voting_power(&mut gov, principal(6));
+    assert_eq!(response.err().unwrap().error_type(), NotAuthorized);
+
+    let response = refresh_voting_power(&mut gov, principal(5));
+    assert_eq!(
+        response,
+        ManageNeuronResponse::refresh_voting_power_response()
+    );
+    let neuron = gov
+        .with_neuron(&stale_neuron_id, |neuron| neuron.clone())
+        .unwrap();
+    assert_eq!(
+        neuron.voting_power_refreshed_timestamp_seconds,
+        Some(driver.now())
+    );
+
+    // New proposals see the full voting power of the refreshed neuron.
+    let proposal_id = make_motion_proposal(&mut gov);
+    assert_eq!(
+        ballot_voting_power(&gov, proposal_id, stale_neuron_id.id),
+        neuron.voting_power(driver.now())
+    );
+}
+
 /// Here we configure the nodes so that to vote no to the proposal.
 ///
 /// Neuron 1 still makes the proposal.
@@ -4209,6 +4322,7 @@ fn create_mature_neuron(dissolved: bool) -> (fake::FakeDriver, Governance, Neuro
             aging_since_timestamp_seconds: driver.now(),
             dissolve_state: Some(DissolveState::DissolveDelaySeconds(dissolve_delay_seconds)),
             kyc_verified: true,
+            voting_power_refreshed_timestamp_seconds: Some(driver.now()),
             ..Default::default()
         }
     );
@@ -4238,7 +4352,9 @@ fn create_mature_neuron(dissolved: bool) -> (fake::FakeDriver, Governance, Neuro
             .with_neuron(&id, |neuron| neuron.clone())
             .expect("Neuron not found");
         assert_eq!(
-            neuron.get_neuron_info(driver.now()).state(),
+            neuron
+                .get_neuron_info(&VotingPowerEconomics::with_default_values(), driver.now())
+                .state(),
             NeuronState::Dissolved
         );
     } else {
@@ -5112,7 +5228,9 @@ fn test_neuron_split_fails() {
         .neuron_minimum_stake_e8s;
 
     assert_eq!(
-        neuron.get_neuron_info(driver.now()).state(),
+        neuron
+            .get_neuron_info(&VotingPowerEconomics::with_default_values(), driver.now())
+            .state(),
         NeuronState::NotDissolving
     );
 
@@ -5229,7 +5347,9 @@ fn test_neuron_split() {
         .transaction_fee_e8s;
 
     assert_eq!(
-        neuron.get_neuron_info(driver.now()).state(),
+        neuron
+            .get_neuron_info(&VotingPowerEconomics::with_default_values(), driver.now())
+            .state(),
         NeuronState::NotDissolving
     );
 
@@ -5322,7 +5442,9 @@ fn test_seed_neuron_split() {
         .transaction_fee_e8s;
 
     assert_eq!(
-        neuron.get_neuron_info(driver.now()).state(),
+        neuron
+            .get_neuron_info(&VotingPowerEconomics::with_default_values(), driver.now())
+            .state(),
         NeuronState::NotDissolving
     );
 
@@ -5401,7 +5523,9 @@ fn test_neuron_spawn() {
 
     let now = driver.now();
     assert_eq!(
-        gov.with_neuron(&id, |neuron| neuron.get_neuron_info(now).state())
+        gov.with_neuron(&id, |neuron| neuron
+            .get_neuron_info(&VotingPowerEconomics::with_default_values(), now)
+            .state())
             .unwrap(),
         NeuronState::NotDissolving
     );
@@ -5515,6 +5639,7 @@ fn test_neuron_spawn() {
             kyc_verified: true,
             maturity_e8s_equivalent: parent_maturity_e8s_equivalent,
             neuron_type: None,
+            voting_power_refreshed_timestamp_seconds: Some(driver.now()),
             ..Default::default()
         }
     );
@@ -5556,6 +5681,7 @@ fn test_neuron_spawn() {
             )),
             kyc_verified: true,
             maturity_e8s_equivalent: 0,
+            voting_power_refreshed_timestamp_seconds: Some(creation_timestamp),
             ..Default::default()
         }
     );
@@ -5581,7 +5707,9 @@ fn test_neuron_spawn_with_subaccount() {
 
     let now = driver.now();
     assert_eq!(
-        gov.with_neuron(&id, |neuron| neuron.get_neuron_info(now).state())
+        gov.with_neuron(&id, |neuron| neuron
+            .get_neuron_info(&VotingPowerEconomics::with_default_values(), now)
+            .state())
             .unwrap(),
         NeuronState::NotDissolving
     );
@@ -5719,6 +5847,7 @@ fn test_neuron_spawn_with_subaccount() {
             )),
             kyc_verified: true,
             maturity_e8s_equivalent: 0,
+            voting_power_refreshed_timestamp_seconds: Some(creation_timestamp),
             ..Default::default()
         }
     );
@@ -5769,7 +5898,9 @@ fn assert_neuron_spawn_partial(
         .with_neuron(&id, |neuron| neuron.clone())
         .expect("Neuron did not exist");
     assert_eq!(
-        neuron.get_neuron_info(driver.now()).state(),
+        neuron
+            .get_neuron_info(&VotingPowerEconomics::with_default_values(), driver.now())
+            .state(),
         NeuronState::NotDissolving
     );
 
@@ -5874,6 +6005,7 @@ fn assert_neuron_spawn_partial(
             )),
             kyc_verified: true,
             maturity_e8s_equivalent: 0,
+            voting_power_refreshed_timestamp_seconds: Some(creation_timestamp),
             ..Default::default()
         }
     );
@@ -6130,7 +6262,9 @@ fn test_disburse_to_neuron() {
     // The neuron state should now be "Dissolved", meaning we can
     // now disburse the neuron.
     assert_eq!(
-        parent_neuron.get_neuron_info(driver.now()).state(),
+        parent_neuron
+            .get_neuron_info(&VotingPowerEconomics::with_default_values(), driver.now())
+            .state(),
         NeuronState::Dissolved
     );
 
@@ -6183,6 +6317,7 @@ fn test_disburse_to_neuron() {
             aging_since_timestamp_seconds: driver.now(),
             dissolve_state: Some(DissolveState::DissolveDelaySeconds(24 * 60 * 60)),
             kyc_verified: true,
+            voting_power_refreshed_timestamp_seconds: Some(driver.now()),
             ..Default::default()
         }
     );
@@ -8591,6 +8726,7 @@ fn test_list_neurons() {
     let p1_listing = gov.list_neurons_by_principal(
         &ListNeurons {
             include_neurons_readable_by_caller: true,
+            include_public_neurons_in_full_neurons: None,
             neuron_ids: vec![],
         },
         &p1,
@@ -8611,6 +8747,7 @@ fn test_list_neurons() {
     let p5_listing = gov.list_neurons_by_principal(
         &ListNeurons {
             include_neurons_readable_by_caller: true,
+            include_public_neurons_in_full_neurons: None,
             neuron_ids: vec![200],
         },
         &p5,
@@ -8637,6 +8774,7 @@ fn test_list_neurons() {
     let p4_listing = gov.list_neurons_by_principal(
         &ListNeurons {
             include_neurons_readable_by_caller: true,
+            include_public_neurons_in_full_neurons: None,
             neuron_ids: vec![42, 99],
         },
         &p4,
@@ -8659,6 +8797,180 @@ fn test_list_neurons() {
     );
 }
 
+#[test]
+fn test_list_neurons_includes_public_neurons_in_full_neurons() {
+    let driver = fake::FakeDriver::default();
+    let owner = principal(1);
+    let stranger = principal(2);
+
+    let proto = GovernanceProto {
+        neurons: btreemap! {
+            // Private by default.
+            1 => Neuron {
+                id: Some(NeuronId { id: 1 }),
+                controller: Some(owner),
+                ..Default::default()
+            },
+            // Explicitly public.
+            2 => Neuron {
+                id: Some(NeuronId { id: 2 }),
+                controller: Some(owner),
+                visibility: Some(Visibility::Public as i32),
+                ..Default::default()
+            },
+            // Known neurons are public by default.
+            3 => Neuron {
+                id: Some(NeuronId { id: 3 }),
+                controller: Some(owner),
+                known_neuron_data: Some(KnownNeuronData {
+                    name: "Known Neuron".to_string(),
+                    description: None,
+                }),
+                ..Default::default()
+            },
+            // Known neurons can opt out.
+            4 => Neuron {
+                id: Some(NeuronId { id: 4 }),
+                controller: Some(owner),
+                known_neuron_data: Some(KnownNeuronData {
+                    name: "Private Known Neuron".to_string(),
+                    description: None,
+                }),
+                visibility: Some(Visibility::Private as i32),
+                ..Default::default()
+            },
+        },
+        ..Default::default()
+    };
+    let gov = Governance::new(
+        proto,
+        driver.get_fake_env(),
+        driver.get_fake_ledger(),
+        driver.get_fake_cmc(),
+    );
+
+    let full_neuron_ids = |response: &ListNeuronsResponse| {
+        response
+            .full_neurons
+            .iter()
+            .map(|neuron| neuron.id.unwrap().id)
+            .collect::<HashSet<u64>>()
+    };
+
+    // Without the flag, a stranger only gets the neuron infos.
+    let listing = gov.list_neurons_by_principal(
+        &ListNeurons {
+            neuron_ids: vec![1, 2, 3, 4],
+            include_neurons_readable_by_caller: false,
+            include_public_neurons_in_full_neurons: None,
+        },
+        &stranger,
+    );
+    assert_eq!(
+        listing
+            .neuron_infos
+            .keys()
+            .copied()
+            .collect::<HashSet<u64>>(),
+        hashset! {1, 2, 3, 4}
+    );
+    assert_eq!(full_neuron_ids(&listing), hashset! {});
+    assert_eq!(
+        listing
+            .neuron_infos
+            .iter()
+            .map(|(id, info)| (*id, info.visibility))
+            .collect::<BTreeMap<u64, Option<i32>>>(),
+        btreemap! {
+            1 => Some(Visibility::Private as i32),
+            2 => Some(Visibility::Public as i32),
+            3 => Some(Visibility::Public as i32),
+            4 => Some(Visibility::Private as i32),
+        }
+    );
+
+    // With the flag, a stranger also gets the full public neurons.
+    let listing = gov.list_neurons_by_principal(
+        &ListNeurons {
+            neuron_ids: vec![1, 2, 3, 4],
+            include_neurons_readable_by_caller: false,
+            include_public_neurons_in_full_neurons: Some(true),
+        },
+        &stranger,
+    );
+    assert_eq!(full_neuron_ids(&listing), hashset! {2, 3});
+
+    // The controller still sees all of its neurons in full.
+    let listing = gov.list_neurons_by_principal(
+        &ListNeurons {
+            neuron_ids: vec![],
+            include_neurons_readable_by_caller: true,
+            include_public_neurons_in_full_neurons: Some(true),
+        },
+        &owner,
+    );
+    assert_eq!(full_neuron_ids(&listing), hashset! {1, 2, 3, 4});
+}
+
+#[test]
+fn test_set_visibility() {
+    let (driver, _, mut neuron) = create_mature_neuron(false);
+    assert_eq!(neuron.effective_visibility(), Visibility::Private);
+
+    let set_visibility = |visibility: Option<Visibility>| Configure {
+        operation: Some(Operation::SetVisibility(SetVisibility {
+            visibility: visibility.map(|v| v as i32),
+        })),
+    };
+
+    // Only the controller may change the visibility.
+    let hot_key = principal(42);
+    neuron.hot_keys.push(hot_key);
+    assert!(neuron
+        .configure(
+            &hot_key,
+            driver.now(),
+            &set_visibility(Some(Visibility::Public))
+        )
+        .is_err());
+    assert_eq!(neuron.effective_visibility(), Visibility::Private);
+
+    // The visibility must be specified.
+    assert!(neuron
+        .configure(
+            &TEST_NEURON_1_OWNER_PRINCIPAL,
+            driver.now(),
+            &set_visibility(None)
+        )
+        .is_err());
+    assert!(neuron
+        .configure(
+            &TEST_NEURON_1_OWNER_PRINCIPAL,
+            driver.now(),
+            &set_visibility(Some(Visibility::Unspecified))
+        )
+        .is_err());
+
+    neuron
+        .configure(
+            &TEST_NEURON_1_OWNER_PRINCIPAL,
+            driver.now(),
+            &set_visibility(Some(Visibility::Public)),
+        )
+        .unwrap();
+    assert_eq!(neuron.visibility, Some(Visibility::Public as i32));
+    assert_eq!(neuron.effective_visibility(), Visibility::Public);
+
+    neuron
+        .configure(
+            &TEST_NEURON_1_OWNER_PRINCIPAL,
+            driver.now(),
+            &set_visibility(Some(Visibility::Private)),
+        )
+        .unwrap();
+    assert_eq!(neuron.effective_visibility(), Visibility::Private);
+}
+
 #[test]
 fn test_list_proposals_omits_deprecated_topics_from_followees() {
     let controller = principal(1);
@@ -8693,6 +9005,7 @@ fn test_list_proposals_omits_deprecated_topics_from_followees() {
         &ListNeurons {
             neuron_ids: vec![neuron_id.id],
             include_neurons_readable_by_caller: true,
+            include_public_neurons_in_full_neurons: None,
         },
         &controller,
     );
@@ -221,6 +221,7 @@ pub async fn change_canister_controllers(
             compute_allocation: None,
             memory_allocation: None,
             freezing_threshold: None,
+            reserved_cycles_limit: None,
         },
         sender_canister_version: management_canister_client.canister_version(),
     };
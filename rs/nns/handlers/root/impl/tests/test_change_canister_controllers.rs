@@ -91,6 +91,7 @@ async fn test_change_canister_controllers_authentication() {
                 compute_allocation: None,
                 memory_allocation: None,
                 freezing_threshold: None,
+                reserved_cycles_limit: None,
             },
             sender_canister_version: None,
         })
@@ -145,6 +146,7 @@ async fn test_change_canister_controllers_handles_replica_errors() {
                 compute_allocation: None,
                 memory_allocation: None,
                 freezing_threshold: None,
+                reserved_cycles_limit: None,
             },
             sender_canister_version: None,
         })
@@ -1255,6 +1255,7 @@ pub fn list_neurons(state_machine: &mut StateMachine, sender: PrincipalId) -> Li
             Encode!(&ListNeurons {
                 neuron_ids: vec![],
                 include_neurons_readable_by_caller: true,
+                include_public_neurons_in_full_neurons: None,
             })
             .unwrap(),
         )
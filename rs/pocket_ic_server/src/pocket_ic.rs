@@ -38,6 +38,10 @@ use tokio::runtime::Runtime;
 /// Used for generating canister ID ranges that do not appear on mainnet.
 pub const MAXIMUM_NUMBER_OF_SUBNETS_ON_MAINNET: u64 = 1024;
 
+/// If set, the instructions executed in each Wasm function are profiled and
+/// the profiles are written to this directory in the folded stack format.
+const WASM_FUNCTION_PROFILE_DIR_ENV_NAME: &str = "POCKET_IC_WASM_FUNCTION_PROFILE_DIR";
+
 pub struct PocketIc {
     subnets: Arc<RwLock<BTreeMap<SubnetId, Arc<StateMachine>>>>,
     routing_table: RoutingTable,
@@ -109,7 +113,11 @@ impl PocketIc {
         } in subnet_config_info
         {
             let subnet_config = SubnetConfig::new(conv_type(subnet_kind));
-            let hypervisor_config = execution_environment::Config::default();
+            let mut hypervisor_config = execution_environment::Config::default();
+            hypervisor_config.embedders_config.wasm_function_profile_dir =
+                std::env::var(WASM_FUNCTION_PROFILE_DIR_ENV_NAME)
+                    .ok()
+                    .map(std::path::PathBuf::from);
             let sm_config = StateMachineConfig::new(subnet_config, hypervisor_config);
             let subnet_size = subnet_size(subnet_kind);
             StateMachineBuilder::new()
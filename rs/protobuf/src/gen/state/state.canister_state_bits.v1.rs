@@ -11,6 +11,9 @@ pub struct CallContext {
     pub time_nanos: ::core::option::Option<u64>,
     #[prost(uint64, tag = "10")]
     pub instructions_executed: u64,
+    /// The name of the method that created the call context.
+    #[prost(string, optional, tag = "11")]
+    pub method_name: ::core::option::Option<::prost::alloc::string::String>,
     #[prost(oneof = "call_context::CallOrigin", tags = "1, 2, 3, 4, 7")]
     pub call_origin: ::core::option::Option<call_context::CallOrigin>,
 }
@@ -89,6 +92,8 @@ pub struct Callback {
     #[prost(message, optional, tag = "9")]
     pub prepayment_for_response_transmission:
         ::core::option::Option<super::super::queues::v1::Cycles>,
+    #[prost(bool, tag = "10")]
+    pub replicated_query: bool,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -445,6 +450,38 @@ pub struct CanisterControllersChange {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterAutoTopUp {
+    #[prost(message, optional, tag = "1")]
+    pub funding_canister: ::core::option::Option<super::super::super::types::v1::PrincipalId>,
+    #[prost(message, optional, tag = "2")]
+    pub amount: ::core::option::Option<Unsigned128>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterSettingsChange {
+    #[prost(uint64, optional, tag = "1")]
+    pub freezing_threshold: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub compute_allocation: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "3")]
+    pub memory_allocation: ::core::option::Option<u64>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterCyclesDeposit {
+    #[prost(message, optional, tag = "1")]
+    pub amount: ::core::option::Option<Unsigned128>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterCyclesWithdrawal {
+    #[prost(message, optional, tag = "1")]
+    pub receiver: ::core::option::Option<super::super::super::types::v1::PrincipalId>,
+    #[prost(message, optional, tag = "2")]
+    pub amount: ::core::option::Option<Unsigned128>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CanisterChange {
     #[prost(uint64, tag = "1")]
     pub timestamp_nanos: u64,
@@ -452,7 +489,7 @@ pub struct CanisterChange {
     pub canister_version: u64,
     #[prost(oneof = "canister_change::ChangeOrigin", tags = "3, 4")]
     pub change_origin: ::core::option::Option<canister_change::ChangeOrigin>,
-    #[prost(oneof = "canister_change::ChangeDetails", tags = "5, 6, 7, 8")]
+    #[prost(oneof = "canister_change::ChangeDetails", tags = "5, 6, 7, 8, 9, 10, 11, 12")]
     pub change_details: ::core::option::Option<canister_change::ChangeDetails>,
 }
 /// Nested message and enum types in `CanisterChange`.
@@ -476,6 +513,14 @@ pub mod canister_change {
         CanisterCodeDeployment(super::CanisterCodeDeployment),
         #[prost(message, tag = "8")]
         CanisterControllersChange(super::CanisterControllersChange),
+        #[prost(message, tag = "9")]
+        CanisterAutoTopUp(super::CanisterAutoTopUp),
+        #[prost(message, tag = "10")]
+        CanisterSettingsChange(super::CanisterSettingsChange),
+        #[prost(message, tag = "11")]
+        CanisterCyclesDeposit(super::CanisterCyclesDeposit),
+        #[prost(message, tag = "12")]
+        CanisterCyclesWithdrawal(super::CanisterCyclesWithdrawal),
     }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -485,6 +530,13 @@ pub struct CanisterHistory {
     pub changes: ::prost::alloc::vec::Vec<CanisterChange>,
     #[prost(uint64, tag = "2")]
     pub total_num_changes: u64,
+    /// The total number of settings changes and cycles deposits and withdrawals
+    /// ever recorded. They are not included in `total_num_changes`.
+    #[prost(uint64, tag = "3")]
+    pub total_num_audit_changes: u64,
+    /// The sequence numbers of the `changes`, in the same order.
+    #[prost(uint64, repeated, tag = "4")]
+    pub change_sequence_numbers: ::prost::alloc::vec::Vec<u64>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -494,6 +546,73 @@ pub struct Unsigned128 {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AutoTopUp {
+    #[prost(message, optional, tag = "1")]
+    pub funding_canister: ::core::option::Option<super::super::super::types::v1::CanisterId>,
+    #[prost(message, optional, tag = "2")]
+    pub threshold: ::core::option::Option<super::super::queues::v1::Cycles>,
+    #[prost(message, optional, tag = "3")]
+    pub amount: ::core::option::Option<super::super::queues::v1::Cycles>,
+    /// If set, `funding_canister` is the cycles ledger and the cycles are
+    /// withdrawn from this account.
+    #[prost(message, optional, tag = "4")]
+    pub funding_account: ::core::option::Option<CyclesLedgerAccount>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CyclesLedgerAccount {
+    #[prost(message, optional, tag = "1")]
+    pub owner: ::core::option::Option<super::super::super::types::v1::PrincipalId>,
+    #[prost(bytes = "vec", optional, tag = "2")]
+    pub subaccount: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+}
+/// The progress of an `install_code` execution that is sliced across rounds.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InstallCodeProgress {
+    #[prost(uint64, tag = "1")]
+    pub start_time_nanos: u64,
+    #[prost(uint64, tag = "2")]
+    pub executed_instructions: u64,
+    #[prost(uint64, tag = "3")]
+    pub num_restarts: u64,
+}
+/// Execution statistics of a canister method aggregated over its calls.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MethodStats {
+    #[prost(string, tag = "1")]
+    pub method_name: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub num_calls: u64,
+    #[prost(uint64, tag = "3")]
+    pub num_traps: u64,
+    #[prost(uint64, tag = "4")]
+    pub instructions_executed: u64,
+    #[prost(uint64, tag = "5")]
+    pub dirty_pages: u64,
+    #[prost(uint64, tag = "6")]
+    pub num_responses: u64,
+    #[prost(uint64, tag = "7")]
+    pub total_latency_nanos: u64,
+}
+/// Statistics about the queueing delay and the scheduling share of a canister.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueueLatencyStats {
+    #[prost(uint64, tag = "1")]
+    pub num_waits: u64,
+    #[prost(uint64, tag = "2")]
+    pub total_wait_nanos: u64,
+    #[prost(uint64, tag = "3")]
+    pub max_wait_nanos: u64,
+    #[prost(uint64, tag = "4")]
+    pub rounds_with_input: u64,
+    #[prost(uint64, tag = "5")]
+    pub rounds_executed: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TotalQueryStats {
     #[prost(message, optional, tag = "1")]
     pub num_calls: ::core::option::Option<Unsigned128>,
@@ -601,6 +720,40 @@ pub struct CanisterStateBits {
     /// Statistics on query execution for entire lifetime of canister.
     #[prost(message, optional, tag = "41")]
     pub total_query_stats: ::core::option::Option<TotalQueryStats>,
+    /// The maximum age in seconds of a stale query cache entry that may be served.
+    #[prost(uint64, tag = "42")]
+    pub query_cache_max_staleness: u64,
+    /// Settings for automatically topping up the canister from a funding canister.
+    #[prost(message, optional, tag = "43")]
+    pub auto_top_up: ::core::option::Option<AutoTopUp>,
+    /// The time of the last automatic top-up attempt.
+    #[prost(uint64, tag = "44")]
+    pub time_of_last_auto_top_up_nanos: u64,
+    /// Whether `install_code` messages run with the extended instruction limit.
+    #[prost(bool, tag = "45")]
+    pub extended_install_code_limit: bool,
+    /// The progress of the pending `install_code` execution, if any.
+    #[prost(message, optional, tag = "46")]
+    pub install_code_progress: ::core::option::Option<InstallCodeProgress>,
+    /// Execution statistics of the methods of the canister.
+    #[prost(message, repeated, tag = "47")]
+    pub method_stats: ::prost::alloc::vec::Vec<MethodStats>,
+    /// The time since which input messages wait for execution, if any.
+    #[prost(uint64, optional, tag = "48")]
+    pub input_wait_start_nanos: ::core::option::Option<u64>,
+    /// The round since which responses wait for execution, if any.
+    #[prost(uint64, optional, tag = "49")]
+    pub response_wait_start_round: ::core::option::Option<u64>,
+    #[prost(message, optional, tag = "50")]
+    pub queue_latency_stats: ::core::option::Option<QueueLatencyStats>,
+    /// The canisters that may automatically top themselves up with cycles of
+    /// this canister.
+    #[prost(message, repeated, tag = "51")]
+    pub auto_top_up_beneficiaries:
+        ::prost::alloc::vec::Vec<super::super::super::types::v1::CanisterId>,
+    /// Whether the canister is locked by an ongoing migration to another subnet.
+    #[prost(bool, tag = "52")]
+    pub migrating: bool,
     #[prost(oneof = "canister_state_bits::CanisterStatus", tags = "11, 12, 13")]
     pub canister_status: ::core::option::Option<canister_state_bits::CanisterStatus>,
 }
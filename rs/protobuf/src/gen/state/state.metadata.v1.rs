@@ -449,6 +449,9 @@ pub struct SystemMetadata {
     pub node_public_keys: ::prost::alloc::vec::Vec<NodePublicKeyEntry>,
     #[prost(message, optional, tag = "20")]
     pub blockmaker_metrics_time_series: ::core::option::Option<BlockmakerMetricsTimeSeries>,
+    /// The last round whose state was checkpointed.
+    #[prost(uint64, tag = "21")]
+    pub last_checkpoint_round: u64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
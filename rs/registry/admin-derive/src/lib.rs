@@ -27,12 +27,16 @@ fn impl_proposal_metadata(ast: &syn::DeriveInput) -> TokenStream {
             }
 
             fn proposer_and_sender(&self, sender: Sender) -> (NeuronId, Sender) {
-                let use_test_neuron = self.test_neuron_proposer || (self.dry_run && matches!(sender, Sender::Anonymous));
+                let use_test_neuron = self.test_neuron_proposer || (self.is_dry_run() && matches!(sender, Sender::Anonymous));
                 get_proposer_and_sender(self.proposer.clone(), sender, use_test_neuron)
             }
 
             fn is_dry_run(&self) -> bool {
-                self.dry_run
+                self.dry_run || self.dry_run_local_store.is_some()
+            }
+
+            fn dry_run_local_store(&self) -> Option<PathBuf> {
+                self.dry_run_local_store.clone()
             }
 
             fn is_json(&self) -> bool {
@@ -94,6 +98,13 @@ pub fn derive_common_proposal_fields(_: TokenStream, item: TokenStream) -> Token
                             /// If set, JSON output will be printed for --dry-run
                             #[clap(long)]
                             pub json: bool,
+
+                            /// If set, implies --dry-run, and additionally executes the proposal
+                            /// against the registry read from the given registry local store,
+                            /// with the same validation as the registry canister, and prints the
+                            /// resulting changes to the registry.
+                            #[clap(long)]
+                            pub dry_run_local_store: Option<PathBuf>,
                     });
                     stream.extend(gen);
                     stream.extend(group.stream());
@@ -175,6 +175,7 @@ extern crate ic_admin_derive;
 
 extern crate chrono;
 
+mod registry_dry_run;
 mod types;
 
 #[cfg(test)]
@@ -547,6 +548,7 @@ pub trait ProposalMetadata {
     fn proposer_and_sender(&self, sender: Sender) -> (NeuronId, Sender);
     fn is_dry_run(&self) -> bool;
     fn is_json(&self) -> bool;
+    fn dry_run_local_store(&self) -> Option<PathBuf>;
 }
 
 /// Trait to extract the title for proposal type.
@@ -5991,7 +5993,10 @@ async fn propose_external_proposal_from_command<
         Some(proposer),
     ));
 
-    print_proposal(&payload, &cmd);
+    let registry_changes = cmd.dry_run_local_store().map(|local_store_path| {
+        dry_run_against_local_store(&local_store_path, nns_function, &payload)
+    });
+    print_proposal_with_registry_changes(&payload, &cmd, registry_changes.as_ref());
 
     if cmd.is_dry_run() {
         return;
@@ -6022,6 +6027,48 @@ async fn propose_external_proposal_from_command<
     };
 }
 
+/// Executes a proposal against the registry read from the registry local
+/// store at `local_store_path`, and returns the resulting registry changes.
+/// Exits with an error if the registry canister would reject the proposal.
+fn dry_run_against_local_store<C: CandidType>(
+    local_store_path: &Path,
+    nns_function: NnsFunction,
+    payload: &C,
+) -> registry_dry_run::RegistryDryRunResult {
+    let mut registry = registry_dry_run::registry_from_local_store(local_store_path);
+    let payload = Encode!(payload).expect("Failed to encode the proposal payload.");
+    match registry_dry_run::dry_run_registry_proposal(&mut registry, nns_function, &payload) {
+        Ok(result) => result,
+        Err(err) => {
+            eprintln!(
+                "The proposal would be rejected by the registry canister: {}",
+                err
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Prints the registry changes of a dry run in a human-readable form.
+fn print_registry_changes(result: &registry_dry_run::RegistryDryRunResult) {
+    println!(
+        "\nRegistry changes against version {} of the local store:",
+        result.base_version
+    );
+    if result.changes.is_empty() {
+        println!("    (none)");
+    }
+    for change in &result.changes {
+        let render = |value: &Option<serde_json::Value>| match value {
+            Some(value) => serde_json::to_string_pretty(&value).unwrap(),
+            None => "<absent>".to_string(),
+        };
+        println!("\nKey: {}", change.key);
+        println!("Old value: {}", render(&change.old_value));
+        println!("New value: {}", render(&change.new_value));
+    }
+}
+
 #[derive(Serialize)]
 struct FirewallCommandResult {
     entries: Vec<FirewallRule>,
@@ -7127,19 +7174,36 @@ impl RootCanisterClient {
 fn print_proposal<T: Serialize + Debug, Command: ProposalMetadata + ProposalTitle>(
     payload: &T,
     cmd: &Command,
+) {
+    print_proposal_with_registry_changes(payload, cmd, None);
+}
+
+/// Like `print_proposal`, but also prints the registry changes of a dry run,
+/// if any. In JSON mode, the proposal and the registry changes are printed as
+/// a single JSON object.
+fn print_proposal_with_registry_changes<
+    T: Serialize + Debug,
+    Command: ProposalMetadata + ProposalTitle,
+>(
+    payload: &T,
+    cmd: &Command,
+    registry_changes: Option<&registry_dry_run::RegistryDryRunResult>,
 ) {
     if cmd.is_json() {
         #[derive(Serialize)]
-        struct Proposal<T> {
+        struct Proposal<'a, T> {
             title: String,
             summary: String,
             payload: T,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            registry_changes: Option<&'a registry_dry_run::RegistryDryRunResult>,
         }
 
         let serialized = serde_json::to_string_pretty(&Proposal {
             title: cmd.title(),
             summary: cmd.summary(),
             payload,
+            registry_changes,
         })
         .expect("Serialization for the cmd to JSON failed.");
         println!("{}", serialized);
@@ -7147,5 +7211,8 @@ fn print_proposal<T: Serialize + Debug, Command: ProposalMetadata + ProposalTitl
         println!("Title: {}\n", cmd.title());
         println!("Summary: {}\n", cmd.summary());
         println!("Payload: {:#?}", payload);
+        if let Some(registry_changes) = registry_changes {
+            print_registry_changes(registry_changes);
+        }
     }
 }
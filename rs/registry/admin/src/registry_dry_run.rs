@@ -0,0 +1,383 @@
+//! Offline execution of registry proposals for `--dry-run-local-store`.
+//!
+//! The payload of a proposal is applied, using the same `do_*` handlers as
+//! the registry canister, to a copy of the registry that is read from a
+//! registry local store. This way, a proposal can be validated and the
+//! registry mutations it would cause can be reviewed before it is submitted.
+
+use candid::{CandidType, Decode};
+use ic_base_types::RegistryVersion;
+use ic_nns_governance::pb::v1::NnsFunction;
+use ic_protobuf::registry::{
+    dc::v1::AddOrRemoveDataCentersProposalPayload, node_operator::v1::RemoveNodeOperatorsPayload,
+    node_rewards::v2::UpdateNodeRewardsTableProposalPayload,
+};
+use ic_registry_local_store::{Changelog, LocalStoreImpl, LocalStoreReader};
+use ic_registry_transport::pb::v1::{
+    registry_mutation::Type, RegistryAtomicMutateRequest, RegistryMutation,
+};
+use prost::Message;
+use registry_canister::{
+    mutations::{
+        complete_canister_migration::CompleteCanisterMigrationPayload,
+        do_add_api_boundary_node::AddApiBoundaryNodePayload,
+        do_add_node_operator::AddNodeOperatorPayload,
+        do_add_nodes_to_subnet::AddNodesToSubnetPayload,
+        do_change_subnet_membership::ChangeSubnetMembershipPayload,
+        do_remove_api_boundary_nodes::RemoveApiBoundaryNodesPayload,
+        do_remove_nodes_from_subnet::RemoveNodesFromSubnetPayload,
+        do_set_firewall_config::SetFirewallConfigPayload,
+        do_update_api_boundary_node_domain::UpdateApiBoundaryNodeDomainPayload,
+        do_update_api_boundary_nodes_version::UpdateApiBoundaryNodesVersionPayload,
+        do_update_elected_hostos_versions::UpdateElectedHostosVersionsPayload,
+        do_update_elected_replica_versions::UpdateElectedReplicaVersionsPayload,
+        do_update_node_operator_config::UpdateNodeOperatorConfigPayload,
+        do_update_nodes_hostos_version::UpdateNodesHostosVersionPayload,
+        do_update_subnet::UpdateSubnetPayload,
+        do_update_subnet_replica::UpdateSubnetReplicaVersionPayload,
+        do_update_unassigned_nodes_config::UpdateUnassignedNodesConfigPayload,
+        firewall::{
+            AddFirewallRulesPayload, RemoveFirewallRulesPayload, UpdateFirewallRulesPayload,
+        },
+        node_management::do_remove_nodes::RemoveNodesPayload,
+        prepare_canister_migration::PrepareCanisterMigrationPayload,
+        reroute_canister_ranges::RerouteCanisterRangesPayload,
+    },
+    pb::v1::{
+        registry_stable_storage::Version as ReprVersion, ChangelogEntry, RegistryStableStorage,
+    },
+    registry::Registry,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+};
+
+/// A registry key whose value would be changed by a proposal.
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct RegistryKeyChange {
+    pub key: String,
+    /// The value before the proposal is executed, or `None` if the key does
+    /// not exist.
+    pub old_value: Option<Value>,
+    /// The value after the proposal is executed, or `None` if the key is
+    /// deleted.
+    pub new_value: Option<Value>,
+}
+
+/// The outcome of executing a proposal against a local copy of the registry.
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct RegistryDryRunResult {
+    /// The latest version of the registry local store the proposal was
+    /// executed against.
+    pub base_version: u64,
+    pub changes: Vec<RegistryKeyChange>,
+}
+
+/// Reads the full content of the registry local store at `path` into a
+/// `Registry`, as the registry canister would hold it.
+pub(crate) fn registry_from_local_store(path: &Path) -> Registry {
+    let changelog = LocalStoreImpl::new(path)
+        .get_changelog_since_version(RegistryVersion::from(0))
+        .unwrap_or_else(|err| {
+            panic!(
+                "Failed to read the registry local store at {}: {}",
+                path.display(),
+                err
+            )
+        });
+    registry_from_changelog(changelog)
+}
+
+fn registry_from_changelog(changelog: Changelog) -> Registry {
+    let changelog = changelog
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let mutations = entry
+                .into_iter()
+                .map(|key_mutation| match key_mutation.value {
+                    Some(value) => RegistryMutation {
+                        mutation_type: Type::Upsert as i32,
+                        key: key_mutation.key.into_bytes(),
+                        value,
+                    },
+                    None => RegistryMutation {
+                        mutation_type: Type::Delete as i32,
+                        key: key_mutation.key.into_bytes(),
+                        value: vec![],
+                    },
+                })
+                .collect();
+            ChangelogEntry {
+                // The i-th entry of a changelog read since version 0 is
+                // at version i + 1.
+                version: i as u64 + 1,
+                encoded_mutation: RegistryAtomicMutateRequest {
+                    mutations,
+                    preconditions: vec![],
+                }
+                .encode_to_vec(),
+            }
+        })
+        .collect();
+
+    let mut registry = Registry::new();
+    registry.from_serializable_form(RegistryStableStorage {
+        version: ReprVersion::Version1 as i32,
+        deltas: vec![],
+        changelog,
+    });
+    registry
+}
+
+/// Executes the proposal with the given function and (candid encoded)
+/// payload against `registry`, and returns the changes it made, or the
+/// reason why the registry canister would reject the proposal.
+pub(crate) fn dry_run_registry_proposal(
+    registry: &mut Registry,
+    nns_function: NnsFunction,
+    payload: &[u8],
+) -> Result<RegistryDryRunResult, String> {
+    let base_version = registry.latest_version();
+
+    // Like in the registry canister, invalid payloads make the handlers
+    // panic, so the panic message is the reason for the rejection.
+    catch_unwind(AssertUnwindSafe(|| {
+        execute_registry_proposal(registry, nns_function, payload)
+    }))
+    .map_err(|panic| {
+        panic
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or_else(|| "The registry canister would reject the proposal.".to_string())
+    })??;
+
+    let changes = registry
+        .get_changes_since(base_version, None)
+        .into_iter()
+        .map(|delta| {
+            let key = String::from_utf8_lossy(&delta.key).to_string();
+            let to_value = |bytes: &[u8]| ic_regedit::raw_data_to_value(&key, bytes);
+            let old_value = registry
+                .get(&delta.key, base_version)
+                .map(|value| to_value(&value.value));
+            let new_value = registry
+                .get(&delta.key, registry.latest_version())
+                .map(|value| to_value(&value.value));
+            RegistryKeyChange {
+                key,
+                old_value,
+                new_value,
+            }
+        })
+        .collect();
+
+    Ok(RegistryDryRunResult {
+        base_version,
+        changes,
+    })
+}
+
+fn decode<T: CandidType + DeserializeOwned>(payload: &[u8]) -> Result<T, String> {
+    Decode!(payload, T).map_err(|err| format!("Failed to decode the proposal payload: {}", err))
+}
+
+fn execute_registry_proposal(
+    registry: &mut Registry,
+    nns_function: NnsFunction,
+    payload: &[u8],
+) -> Result<(), String> {
+    match nns_function {
+        NnsFunction::AssignNoid => {
+            registry.do_add_node_operator(decode::<AddNodeOperatorPayload>(payload)?)
+        }
+        NnsFunction::AddNodeToSubnet => {
+            registry.do_add_nodes_to_subnet(decode::<AddNodesToSubnetPayload>(payload)?)
+        }
+        NnsFunction::RemoveNodesFromSubnet => {
+            registry.do_remove_nodes_from_subnet(decode::<RemoveNodesFromSubnetPayload>(payload)?)
+        }
+        NnsFunction::ChangeSubnetMembership => {
+            registry.do_change_subnet_membership(decode::<ChangeSubnetMembershipPayload>(payload)?)
+        }
+        NnsFunction::UpdateElectedReplicaVersions => {
+            registry.do_update_elected_replica_versions(decode::<
+                UpdateElectedReplicaVersionsPayload,
+            >(payload)?)
+        }
+        NnsFunction::UpdateSubnetReplicaVersion => registry.do_update_subnet_replica_version(
+            decode::<UpdateSubnetReplicaVersionPayload>(payload)?,
+        ),
+        NnsFunction::UpdateElectedHostosVersions => registry.do_update_elected_hostos_versions(
+            decode::<UpdateElectedHostosVersionsPayload>(payload)?,
+        ),
+        NnsFunction::UpdateNodesHostosVersion => {
+            registry
+                .do_update_nodes_hostos_version(decode::<UpdateNodesHostosVersionPayload>(payload)?)
+        }
+        NnsFunction::UpdateNodeOperatorConfig => {
+            registry
+                .do_update_node_operator_config(decode::<UpdateNodeOperatorConfigPayload>(payload)?)
+        }
+        NnsFunction::UpdateConfigOfSubnet => {
+            registry.do_update_subnet(decode::<UpdateSubnetPayload>(payload)?)
+        }
+        NnsFunction::ClearProvisionalWhitelist => registry.do_clear_provisional_whitelist(),
+        NnsFunction::SetFirewallConfig => {
+            registry.do_set_firewall_config(decode::<SetFirewallConfigPayload>(payload)?)
+        }
+        NnsFunction::AddFirewallRules => {
+            registry.do_add_firewall_rules(decode::<AddFirewallRulesPayload>(payload)?)
+        }
+        NnsFunction::RemoveFirewallRules => {
+            registry.do_remove_firewall_rules(decode::<RemoveFirewallRulesPayload>(payload)?)
+        }
+        NnsFunction::UpdateFirewallRules => {
+            registry.do_update_firewall_rules(decode::<UpdateFirewallRulesPayload>(payload)?)
+        }
+        NnsFunction::RemoveNodes => {
+            registry.do_remove_nodes(decode::<RemoveNodesPayload>(payload)?)
+        }
+        NnsFunction::UpdateNodeRewardsTable => {
+            registry.do_update_node_rewards_table(decode::<UpdateNodeRewardsTableProposalPayload>(
+                payload,
+            )?)
+        }
+        NnsFunction::AddOrRemoveDataCenters => {
+            registry.do_add_or_remove_data_centers(decode::<AddOrRemoveDataCentersProposalPayload>(
+                payload,
+            )?)
+        }
+        NnsFunction::UpdateUnassignedNodesConfig => registry.do_update_unassigned_nodes_config(
+            decode::<UpdateUnassignedNodesConfigPayload>(payload)?,
+        ),
+        NnsFunction::RemoveNodeOperators => {
+            registry.do_remove_node_operators(decode::<RemoveNodeOperatorsPayload>(payload)?)
+        }
+        NnsFunction::RerouteCanisterRanges => {
+            registry.reroute_canister_ranges(decode::<RerouteCanisterRangesPayload>(payload)?)?
+        }
+        NnsFunction::PrepareCanisterMigration => {
+            registry
+                .prepare_canister_migration(decode::<PrepareCanisterMigrationPayload>(payload)?)?
+        }
+        NnsFunction::CompleteCanisterMigration => registry
+            .complete_canister_migration(decode::<CompleteCanisterMigrationPayload>(payload)?)?,
+        NnsFunction::AddApiBoundaryNode => {
+            registry.do_add_api_boundary_node(decode::<AddApiBoundaryNodePayload>(payload)?)
+        }
+        NnsFunction::RemoveApiBoundaryNodes => {
+            registry.do_remove_api_boundary_nodes(decode::<RemoveApiBoundaryNodesPayload>(payload)?)
+        }
+        NnsFunction::UpdateApiBoundaryNodeDomain => registry.do_update_api_boundary_node_domain(
+            decode::<UpdateApiBoundaryNodeDomainPayload>(payload)?,
+        ),
+        NnsFunction::UpdateApiBoundaryNodesVersion => registry
+            .do_update_api_boundary_nodes_version(decode::<UpdateApiBoundaryNodesVersionPayload>(
+                payload,
+            )?),
+        NnsFunction::CreateSubnet | NnsFunction::RecoverSubnet => {
+            return Err(format!(
+                "{:?} proposals make the registry canister call other canisters and cannot be \
+                 executed offline.",
+                nns_function
+            ))
+        }
+        _ => {
+            return Err(format!(
+                "{:?} proposals are not executed by the registry canister.",
+                nns_function
+            ))
+        }
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Encode;
+    use ic_protobuf::registry::replica_version::v1::{
+        BlessedReplicaVersions, ReplicaVersionRecord,
+    };
+    use ic_registry_keys::{make_blessed_replica_versions_key, make_replica_version_key};
+    use ic_registry_local_store::KeyMutation;
+
+    fn replica_version_changelog() -> Changelog {
+        vec![
+            vec![KeyMutation {
+                key: make_blessed_replica_versions_key(),
+                value: Some(
+                    BlessedReplicaVersions {
+                        blessed_version_ids: vec!["version_a".to_string()],
+                    }
+                    .encode_to_vec(),
+                ),
+            }],
+            vec![KeyMutation {
+                key: make_replica_version_key("version_a"),
+                value: Some(
+                    ReplicaVersionRecord {
+                        release_package_sha256_hex: "".to_string(),
+                        release_package_urls: vec![],
+                        guest_launch_measurement_sha256_hex: None,
+                    }
+                    .encode_to_vec(),
+                ),
+            }],
+        ]
+    }
+
+    #[test]
+    fn test_registry_from_changelog() {
+        let registry = registry_from_changelog(replica_version_changelog());
+
+        assert_eq!(registry.latest_version(), 2);
+        assert!(registry
+            .get(make_replica_version_key("version_a").as_bytes(), 2)
+            .is_some());
+        assert!(registry
+            .get(make_replica_version_key("version_a").as_bytes(), 1)
+            .is_none());
+    }
+
+    #[test]
+    fn test_dry_run_rejects_proposals_not_executed_by_the_registry() {
+        let mut registry = registry_from_changelog(replica_version_changelog());
+
+        let result = dry_run_registry_proposal(
+            &mut registry,
+            NnsFunction::NnsCanisterUpgrade,
+            &Encode!().unwrap(),
+        );
+
+        assert!(result.is_err());
+        assert_eq!(registry.latest_version(), 2);
+    }
+
+    #[test]
+    fn test_dry_run_reports_rejection_by_registry_handler() {
+        let mut registry = registry_from_changelog(replica_version_changelog());
+
+        // version_b is neither elected nor part of the payload.
+        let payload = UpdateElectedReplicaVersionsPayload {
+            replica_version_to_elect: None,
+            release_package_sha256_hex: None,
+            release_package_urls: vec![],
+            guest_launch_measurement_sha256_hex: None,
+            replica_versions_to_unelect: vec!["version_b".to_string()],
+        };
+
+        let result = dry_run_registry_proposal(
+            &mut registry,
+            NnsFunction::UpdateElectedReplicaVersions,
+            &Encode!(&payload).unwrap(),
+        );
+
+        assert!(result.is_err(), "{:?}", result);
+    }
+}
@@ -2,10 +2,10 @@ use candid::{candid_method, Decode};
 use dfn_candid::{candid, candid_one};
 use dfn_core::{
     api::{arg_data, data_certificate, reply},
-    over, over_async, over_may_reject, stable,
+    over, over_async, over_async_may_reject, over_may_reject, stable,
 };
-use ic_base_types::NodeId;
-use ic_certified_map::{AsHashTree, HashTree};
+use ic_base_types::{CanisterId, NodeId};
+use ic_certified_map::HashTree;
 use ic_nns_constants::{GOVERNANCE_CANISTER_ID, ROOT_CANISTER_ID};
 use ic_protobuf::registry::{
     dc::v1::{AddOrRemoveDataCentersProposalPayload, DataCenterRecord},
@@ -17,8 +17,10 @@ use ic_registry_transport::{
     deserialize_get_value_request,
     pb::v1::{
         registry_error::Code, CertifiedResponse, RegistryAtomicMutateResponse, RegistryDelta,
-        RegistryError, RegistryGetChangesSinceRequest, RegistryGetChangesSinceResponse,
-        RegistryGetLatestVersionResponse, RegistryGetValueResponse,
+        RegistryError, RegistryGetCertifiedValueResponse, RegistryGetChangesSinceRequest,
+        RegistryGetChangesSinceResponse, RegistryGetLatestVersionResponse,
+        RegistryGetSnapshotRequest, RegistryGetValueRequest, RegistryGetValueResponse,
+        RegistrySnapshotResponse,
     },
     serialize_atomic_mutate_response, serialize_get_changes_since_response,
     serialize_get_value_response,
@@ -26,7 +28,10 @@ use ic_registry_transport::{
 use ic_types::PrincipalId;
 use prost::Message;
 use registry_canister::{
-    certification::{current_version_tree, hash_tree_to_proto},
+    certification::{
+        certified_snapshot_tree, certified_value_tree, current_version_tree, hash_tree_to_proto,
+        pruned_delta_tree, pruned_keys_tree, registry_tree,
+    },
     common::LOG_PREFIX,
     init::RegistryCanisterInitPayload,
     mutations::{
@@ -38,6 +43,10 @@ use registry_canister::{
         do_change_subnet_membership::ChangeSubnetMembershipPayload,
         do_create_subnet::CreateSubnetPayload,
         do_delete_subnet::DeleteSubnetPayload,
+        do_migrate_canister::{
+            check_canister_migration_controllers, execute_canister_migration_step,
+            is_registry_canister_migration_step, CanisterMigrationStatus, MigrateCanisterPayload,
+        },
         do_recover_subnet::RecoverSubnetPayload,
         do_remove_api_boundary_nodes::RemoveApiBoundaryNodesPayload,
         do_remove_nodes_from_subnet::RemoveNodesFromSubnetPayload,
@@ -66,13 +75,14 @@ use registry_canister::{
         reroute_canister_ranges::RerouteCanisterRangesPayload,
     },
     pb::v1::{
-        GetSubnetForCanisterRequest, GetSubnetForCanisterResponse, NodeProvidersMonthlyXdrRewards,
-        RegistryCanisterStableStorage,
+        canister_migration_state::Step, GetSubnetForCanisterRequest, GetSubnetForCanisterResponse,
+        NodeProvidersMonthlyXdrRewards, RegistryCanisterStableStorage,
     },
     proto_on_wire::protobuf,
     registry::{EncodedVersion, Registry, MAX_REGISTRY_DELTAS_SIZE},
     registry_lifecycle,
 };
+use std::time::Duration;
 
 #[cfg(target_arch = "wasm32")]
 use dfn_core::println;
@@ -158,6 +168,7 @@ fn canister_pre_upgrade() {
     let ss = RegistryCanisterStableStorage {
         registry: Some(registry.serializable_form()),
         pre_upgrade_version: Some(registry.latest_version()),
+        canister_migrations: registry.ongoing_canister_migrations(),
     };
     ss.encode(&mut serialized)
         .expect("Error serializing to stable.");
@@ -173,6 +184,13 @@ fn canister_post_upgrade() {
     let stable_storage = stable::get();
     // delegate real work to more testable function
     registry_lifecycle::canister_post_upgrade(registry, stable_storage.as_slice());
+
+    // Resume the canister migrations that were in progress.
+    for migration in registry.ongoing_canister_migrations() {
+        if migration.step() != Step::Failed {
+            schedule_canister_migration_step(migration.get_canister_id(), Duration::ZERO);
+        }
+    }
 }
 
 ic_nervous_system_common_build_metadata::define_get_build_metadata_candid_method! {}
@@ -214,7 +232,7 @@ fn get_certified_changes_since() {
     over(
         protobuf,
         |req: RegistryGetChangesSinceRequest| -> CertifiedResponse {
-            use ic_certified_map::{fork, labeled, labeled_hash};
+            use ic_certified_map::labeled;
             let latest_version = registry().latest_version();
             let from_version = EncodedVersion::from(req.version.saturating_add(1));
 
@@ -227,13 +245,14 @@ fn get_certified_changes_since() {
                 .changelog()
                 .value_range(from_version.as_ref(), to_version.as_ref());
 
-            let hash_tree = fork(
+            let hash_tree = registry_tree(
                 current_version_tree(latest_version),
                 if req.version < latest_version {
                     labeled(b"delta", delta_tree)
                 } else {
-                    HashTree::Pruned(labeled_hash(b"delta", &registry().changelog().root_hash()))
+                    pruned_delta_tree(registry())
                 },
+                pruned_keys_tree(registry()),
             );
 
             certified_response(hash_tree)
@@ -295,16 +314,84 @@ fn get_latest_version() {
 #[export_name = "canister_query get_certified_latest_version"]
 fn get_certified_latest_version() {
     over(protobuf, |_: Vec<u8>| -> CertifiedResponse {
-        use ic_certified_map::{fork, labeled_hash};
         let latest_version = registry().latest_version();
-        let hash_tree = fork(
+        let hash_tree = registry_tree(
             current_version_tree(latest_version),
-            HashTree::Pruned(labeled_hash(b"delta", &registry().changelog().root_hash())),
+            pruned_delta_tree(registry()),
+            pruned_keys_tree(registry()),
         );
         certified_response(hash_tree)
     });
 }
 
+/// Returns the value of a key at the requested version (the latest version if
+/// none is specified), together with a hash tree and certificate proving the
+/// history of the key up to that version. See
+/// [`registry_canister::certification`] for the layout of the tree.
+#[export_name = "canister_query get_certified_value"]
+fn get_certified_value() {
+    over(
+        protobuf,
+        |req: RegistryGetValueRequest| -> RegistryGetCertifiedValueResponse {
+            let registry = registry();
+            let version = req
+                .version
+                .unwrap_or_else(|| registry.latest_version())
+                .min(registry.latest_version());
+
+            RegistryGetCertifiedValueResponse {
+                value: registry.get_change_at_or_before(&req.key, version).cloned(),
+                hash_tree: Some(hash_tree_to_proto(certified_value_tree(
+                    registry, &req.key, version,
+                ))),
+                certificate: data_certificate().unwrap(),
+            }
+        },
+    )
+}
+
+/// Returns a page of the full registry contents at the requested version (the
+/// latest version if none is specified), together with a hash tree and
+/// certificate proving the page: the latest value of every key present at
+/// that version, in ascending key order, starting after `start_after_key`.
+/// See [`registry_canister::certification::certified_snapshot_tree`] for the
+/// contents of the tree.
+#[export_name = "canister_query get_snapshot"]
+fn get_snapshot() {
+    over(
+        protobuf,
+        |req: RegistryGetSnapshotRequest| -> RegistrySnapshotResponse {
+            let registry = registry();
+            let version = req
+                .version
+                .unwrap_or_else(|| registry.latest_version())
+                .min(registry.latest_version());
+
+            let (entries, complete) =
+                registry.get_snapshot(version, &req.start_after_key, MAX_REGISTRY_DELTAS_SIZE);
+            let last_key = if complete {
+                None
+            } else {
+                entries.last().map(|delta| delta.key.as_slice())
+            };
+            let hash_tree = hash_tree_to_proto(certified_snapshot_tree(
+                registry,
+                version,
+                &req.start_after_key,
+                last_key,
+            ));
+            RegistrySnapshotResponse {
+                error: None,
+                version,
+                entries,
+                complete,
+                hash_tree: Some(hash_tree),
+                certificate: data_certificate().unwrap(),
+            }
+        },
+    )
+}
+
 #[export_name = "canister_update atomic_mutate"]
 fn atomic_mutate() {
     let caller = dfn_core::api::caller();
@@ -826,6 +913,91 @@ fn complete_canister_migration_(payload: CompleteCanisterMigrationPayload) -> Re
     Ok(())
 }
 
+// Unlike the other canister migration endpoints, this one is not restricted
+// to governance: any controller of a canister may move it to another subnet,
+// provided that the registry canister is also one of its controllers. The
+// call returns once the migration was started; the migration itself proceeds
+// in the background.
+#[export_name = "canister_update migrate_canister"]
+fn migrate_canister() {
+    over_async_may_reject(candid_one, |payload: MigrateCanisterPayload| async move {
+        migrate_canister_(payload).await
+    });
+}
+
+#[candid_method(update, rename = "migrate_canister")]
+async fn migrate_canister_(payload: MigrateCanisterPayload) -> Result<(), String> {
+    // The caller is no longer available once the first call was made.
+    let caller = dfn_core::api::caller();
+    println!("{}call: migrate_canister from: {}", LOG_PREFIX, caller);
+    let result = start_canister_migration(caller, payload).await;
+    if let Err(msg) = &result {
+        println!("{} Reject: {}", LOG_PREFIX, msg);
+    }
+    result
+}
+
+/// Records the migration of a canister and schedules its first step. The
+/// reply does not wait for the migration to finish.
+///
+/// The registry is never borrowed across an await point: other messages may
+/// mutate it while the canister status is fetched.
+async fn start_canister_migration(
+    caller: PrincipalId,
+    payload: MigrateCanisterPayload,
+) -> Result<(), String> {
+    let (canister_id, _) = registry().validate_migrate_canister_payload(&payload)?;
+    let was_running = check_canister_migration_controllers(caller, canister_id).await?;
+    registry_mut().do_migrate_canister(payload, was_running)?;
+    recertify_registry();
+    schedule_canister_migration_step(canister_id, Duration::ZERO);
+    Ok(())
+}
+
+/// Executes the next step of the migration of `canister_id` after `delay`.
+///
+/// At most one step of a migration is scheduled at any time: the next step
+/// is scheduled only once the current one finished.
+fn schedule_canister_migration_step(canister_id: CanisterId, delay: Duration) {
+    ic_cdk_timers::set_timer(delay, move || {
+        dfn_core::api::futures::spawn(run_canister_migration_step(canister_id))
+    });
+}
+
+async fn run_canister_migration_step(canister_id: CanisterId) {
+    let migration = match registry().get_ongoing_canister_migration(&canister_id) {
+        Some(migration) if migration.step() != Step::Failed => migration.clone(),
+        _ => return,
+    };
+    let result = if is_registry_canister_migration_step(migration.step()) {
+        registry_mut().execute_registry_canister_migration_step(migration)
+    } else {
+        execute_canister_migration_step(migration).await
+    };
+    let next_step_delay = registry_mut().record_canister_migration_step(canister_id, result);
+    recertify_registry();
+    if let Some(delay) = next_step_delay {
+        schedule_canister_migration_step(canister_id, delay);
+    }
+}
+
+#[export_name = "canister_query get_canister_migration_status"]
+fn get_canister_migration_status() {
+    over(candid_one, |canister_id: PrincipalId| {
+        get_canister_migration_status_(canister_id)
+    })
+}
+
+/// Returns the status of the migration of a canister requested through
+/// `migrate_canister`, or `None` if the canister is not being migrated. A
+/// migration that failed is reported until it is resolved manually.
+#[candid_method(query, rename = "get_canister_migration_status")]
+fn get_canister_migration_status_(canister_id: PrincipalId) -> Option<CanisterMigrationStatus> {
+    registry()
+        .get_ongoing_canister_migration(&CanisterId::unchecked_from_principal(canister_id))
+        .map(CanisterMigrationStatus::from)
+}
+
 #[export_name = "canister_query get_node_providers_monthly_xdr_rewards"]
 fn get_node_providers_monthly_xdr_rewards() {
     check_caller_is_governance_and_log("get_node_providers_monthly_xdr_rewards");
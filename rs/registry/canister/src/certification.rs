@@ -8,21 +8,30 @@
 //! |
 //! +-- current_version -- [ LEB128-encoded VERSION ]
 //! |
-//! `-- delta --+-- [ big-endian encoded 1u64    ] -- [ serialized protobuf ]
-//!             |
-//!             …
-//!             |
-//!             `-- [ big-endian encoded VERSION ] -- [ serialized protobuf ]
+//! +-- delta --+-- [ big-endian encoded 1u64    ] -- [ serialized protobuf ]
+//! |           |
+//! |           …
+//! |           |
+//! |           `-- [ big-endian encoded VERSION ] -- [ serialized protobuf ]
+//! |
+//! `-- keys --+-- [ KEY ] --+-- [ big-endian encoded V1 ] -- [ VALUE HASH ]
+//!            |             |
+//!            …             …
 //! ```
 //!
-//! where lebels under "delta" form contiguous range [1,VERSION].
+//! where lebels under "delta" form contiguous range [1,VERSION], and the
+//! labels under each KEY are the versions at which that key was mutated. The
+//! VALUE HASH is the SHA-256 hash of the value written at that version, or the
+//! empty blob if the key was deleted at that version.
 
 #[cfg(target_arch = "wasm32")]
 use dfn_core::api::set_certified_data;
-use ic_certified_map::{labeled, HashTree};
+use ic_certified_map::{fork, labeled, labeled_hash, AsHashTree, HashTree};
 use ic_protobuf::messaging::xnet::v1 as pb;
+use prost::Message;
 
-use crate::registry::{Registry, Version};
+use crate::registry::{EncodedVersion, Registry, Version};
+use std::ops::Bound;
 
 /// The maximum amount of bytes a 64-bit number can occupy when encoded in
 /// LEB128.
@@ -37,6 +46,147 @@ pub fn current_version_tree(v: Version) -> HashTree<'static> {
     )
 }
 
+/// Returns the "delta" subtree with all the changelog entries pruned.
+pub fn pruned_delta_tree(registry: &Registry) -> HashTree<'static> {
+    HashTree::Pruned(labeled_hash(b"delta", &registry.changelog().root_hash()))
+}
+
+/// Returns the "keys" subtree with all the per-key histories pruned.
+pub fn pruned_keys_tree(registry: &Registry) -> HashTree<'static> {
+    HashTree::Pruned(labeled_hash(b"keys", &registry.key_index().root_hash()))
+}
+
+/// Combines the subtrees of the registry's hash tree into the root tree,
+/// following the layout described in the module documentation.
+pub fn registry_tree<'a>(
+    current_version: HashTree<'a>,
+    delta: HashTree<'a>,
+    keys: HashTree<'a>,
+) -> HashTree<'a> {
+    fork(fork(current_version, delta), keys)
+}
+
+/// Returns a hash tree proving the value of `key` at `version`.
+///
+/// If the key was ever mutated at or before `version`, the "keys" subtree
+/// reveals the latest such change together with all the changes made to the
+/// key after it, up to and including `version`. A client can thus check that
+/// the revealed change is the one in effect at `version`. Otherwise, the tree
+/// reveals the versions of the key's history that are adjacent to `version`
+/// (or the absence of the key altogether).
+pub fn certified_value_tree<'a>(
+    registry: &'a Registry,
+    key: &[u8],
+    version: Version,
+) -> HashTree<'a> {
+    registry_tree(
+        current_version_tree(registry.latest_version()),
+        pruned_delta_tree(registry),
+        labeled(b"keys", key_history_witness(registry, key, version)),
+    )
+}
+
+/// Returns a hash tree proving a page of the snapshot of the registry at
+/// `version`, as returned by [`Registry::get_snapshot`].
+///
+/// The "keys" subtree reveals every key greater than `start_after_key` up to
+/// and including `last_key`, or all of them if `last_key` is `None`, together
+/// with the keys adjacent to that range, so that a client can check that no
+/// key is missing from the page. For every revealed key in the range, it also
+/// reveals the history needed to prove the value of the key at `version`, as
+/// in [`certified_value_tree`].
+pub fn certified_snapshot_tree<'a>(
+    registry: &'a Registry,
+    version: Version,
+    start_after_key: &[u8],
+    last_key: Option<&[u8]>,
+) -> HashTree<'a> {
+    let upper_bound = match last_key {
+        Some(last_key) => last_key.to_vec(),
+        None => {
+            // The smallest key greater than all the keys of the registry and
+            // `start_after_key`: revealing the keys up to it proves that there
+            // are no more keys after the page.
+            let mut upper_bound = registry
+                .store
+                .keys()
+                .next_back()
+                .map_or(start_after_key, |max_key| {
+                    max_key.as_slice().max(start_after_key)
+                })
+                .to_vec();
+            upper_bound.push(0);
+            upper_bound
+        }
+    };
+
+    let key_index = registry.key_index();
+    let mut keys_tree = key_index.key_range(start_after_key, &upper_bound);
+    for (key, _) in registry.store.range::<[u8], _>((
+        Bound::Excluded(start_after_key),
+        Bound::Included(upper_bound.as_slice()),
+    )) {
+        keys_tree = merge_witnesses(keys_tree, key_history_witness(registry, key, version));
+    }
+
+    registry_tree(
+        current_version_tree(registry.latest_version()),
+        pruned_delta_tree(registry),
+        labeled(b"keys", keys_tree),
+    )
+}
+
+/// Returns the size of the encoded witness revealing the part of the history
+/// of `key` that determines its value at `version`.
+///
+/// This bounds what `key` adds to the tree of a snapshot page, see
+/// [`certified_snapshot_tree`]. It grows with the length of the key's history.
+pub fn key_history_witness_size(registry: &Registry, key: &[u8], version: Version) -> usize {
+    hash_tree_to_proto(key_history_witness(registry, key, version)).encoded_len()
+}
+
+/// Returns the witness of the "keys" subtree revealing the part of the history
+/// of `key` that determines its value at `version`.
+fn key_history_witness<'a>(registry: &'a Registry, key: &[u8], version: Version) -> HashTree<'a> {
+    let key_index = registry.key_index();
+    if key_index.get(key).is_some() {
+        let from_version = EncodedVersion::from(
+            registry
+                .get_change_at_or_before(key, version)
+                .map(|change| change.version)
+                .unwrap_or(0),
+        );
+        let to_version = EncodedVersion::from(version);
+        key_index.nested_witness(key, |versions| {
+            versions.value_range(from_version.as_ref(), to_version.as_ref())
+        })
+    } else {
+        key_index.witness(key)
+    }
+}
+
+/// Merges two witnesses of the same tree into a witness revealing everything
+/// that either of them reveals.
+fn merge_witnesses<'a>(lhs: HashTree<'a>, rhs: HashTree<'a>) -> HashTree<'a> {
+    use HashTree::*;
+
+    match (lhs, rhs) {
+        (Pruned(_), tree) | (tree, Pruned(_)) => tree,
+        (Fork(lhs), Fork(rhs)) => {
+            let (lhs_left, lhs_right) = *lhs;
+            let (rhs_left, rhs_right) = *rhs;
+            fork(
+                merge_witnesses(lhs_left, rhs_left),
+                merge_witnesses(lhs_right, rhs_right),
+            )
+        }
+        (Labeled(label, lhs), Labeled(_, rhs)) => {
+            Labeled(label, Box::new(merge_witnesses(*lhs, *rhs)))
+        }
+        (tree, _) => tree,
+    }
+}
+
 /// Encodes a hash tree into the protobuf representation expected by
 /// the registry client.
 pub fn hash_tree_to_proto(tree: HashTree<'_>) -> pb::MixedHashTree {
@@ -65,12 +215,12 @@ pub fn hash_tree_to_proto(tree: HashTree<'_>) -> pb::MixedHashTree {
 #[cfg(target_arch = "wasm32")]
 /// Updates the certified data for the canister from the current registry state
 pub fn recertify_registry(registry: &Registry) {
-    use ic_certified_map::{fork_hash, labeled_hash, AsHashTree};
-
-    let root_hash = fork_hash(
-        &current_version_tree(registry.latest_version()).reconstruct(),
-        &labeled_hash(b"delta", &registry.changelog().root_hash()),
-    );
+    let root_hash = registry_tree(
+        current_version_tree(registry.latest_version()),
+        pruned_delta_tree(registry),
+        pruned_keys_tree(registry),
+    )
+    .reconstruct();
 
     set_certified_data(&root_hash);
 }
@@ -108,6 +108,132 @@ pub struct RegistryCanisterStableStorage {
     /// back after an upgrade
     #[prost(uint64, optional, tag = "3")]
     pub pre_upgrade_version: ::core::option::Option<u64>,
+    /// The canister migrations requested through `migrate_canister` that are
+    /// still in progress.
+    #[prost(message, repeated, tag = "4")]
+    pub canister_migrations: ::prost::alloc::vec::Vec<CanisterMigrationState>,
+}
+/// The progress of a canister migration requested by a controller of the
+/// canister through `migrate_canister`. The migration is driven one step at a
+/// time by a timer, so it survives upgrades of the registry canister.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterMigrationState {
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    #[prost(message, optional, tag = "2")]
+    pub source_subnet: ::core::option::Option<::ic_base_types::PrincipalId>,
+    #[prost(message, optional, tag = "3")]
+    pub target_subnet: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// Whether the canister was running when the migration was requested.
+    #[prost(bool, tag = "4")]
+    pub was_running: bool,
+    #[prost(enumeration = "canister_migration_state::Step", tag = "5")]
+    pub step: i32,
+    /// The offset, page or chunk index that the current copy step resumes at.
+    #[prost(uint64, tag = "6")]
+    pub position: u64,
+    /// The cycles removed from the source subnet, as a little-endian u128.
+    #[prost(bytes = "vec", tag = "7")]
+    pub cycles: ::prost::alloc::vec::Vec<u8>,
+    /// The number of consecutive failed attempts of the current step.
+    #[prost(uint32, tag = "8")]
+    pub failed_attempts: u32,
+    /// The error of the last failed attempt, if any.
+    #[prost(string, tag = "9")]
+    pub last_error: ::prost::alloc::string::String,
+    /// The version of the canister when it was locked for the migration. The
+    /// migration is rolled back if the version changes before the rerouting.
+    #[prost(uint64, tag = "10")]
+    pub canister_version: u64,
+    /// The step that kept failing, if the migration is in `STEP_FAILED`.
+    #[prost(enumeration = "canister_migration_state::Step", tag = "11")]
+    pub failed_step: i32,
+}
+/// Nested message and enum types in `CanisterMigrationState`.
+pub mod canister_migration_state {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Step {
+        Unspecified = 0,
+        /// Stop the canister on the source subnet.
+        StopCanister = 1,
+        /// Copy the Wasm module, starting at byte `position`.
+        CopyWasmModule = 2,
+        /// Write the metadata, which assembles the Wasm module on the target.
+        WriteMetadata = 3,
+        /// Copy the Wasm memory, starting at page `position`.
+        CopyWasmMemory = 4,
+        /// Copy the stable memory, starting at page `position`.
+        CopyStableMemory = 5,
+        /// Copy the Wasm chunk store, starting at chunk `position`.
+        CopyWasmChunkStore = 6,
+        /// Assign the canister to the target subnet in the routing table.
+        Reroute = 7,
+        /// Remove the canister from the source subnet, recording its cycles.
+        RemoveFromSource = 8,
+        /// Start the canister on the target subnet with the recorded cycles.
+        StartOnTarget = 9,
+        /// Remove the `canister_migrations` entry.
+        Complete = 10,
+        /// Remove the partially written canister from the target subnet.
+        RollBackRemoveFromTarget = 11,
+        /// Remove the `canister_migrations` entry of the aborted migration.
+        RollBackComplete = 12,
+        /// Unlock the canister and start it again if it was running before the
+        /// migration.
+        RollBackRestoreStatus = 13,
+        /// The migration failed in `failed_step` and can neither make progress nor
+        /// be rolled back. The `canister_migrations` entry is kept until the
+        /// migration is resolved manually.
+        Failed = 14,
+    }
+    impl Step {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Step::Unspecified => "STEP_UNSPECIFIED",
+                Step::StopCanister => "STEP_STOP_CANISTER",
+                Step::CopyWasmModule => "STEP_COPY_WASM_MODULE",
+                Step::WriteMetadata => "STEP_WRITE_METADATA",
+                Step::CopyWasmMemory => "STEP_COPY_WASM_MEMORY",
+                Step::CopyStableMemory => "STEP_COPY_STABLE_MEMORY",
+                Step::CopyWasmChunkStore => "STEP_COPY_WASM_CHUNK_STORE",
+                Step::Reroute => "STEP_REROUTE",
+                Step::RemoveFromSource => "STEP_REMOVE_FROM_SOURCE",
+                Step::StartOnTarget => "STEP_START_ON_TARGET",
+                Step::Complete => "STEP_COMPLETE",
+                Step::RollBackRemoveFromTarget => "STEP_ROLL_BACK_REMOVE_FROM_TARGET",
+                Step::RollBackComplete => "STEP_ROLL_BACK_COMPLETE",
+                Step::RollBackRestoreStatus => "STEP_ROLL_BACK_RESTORE_STATUS",
+                Step::Failed => "STEP_FAILED",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "STEP_UNSPECIFIED" => Some(Self::Unspecified),
+                "STEP_STOP_CANISTER" => Some(Self::StopCanister),
+                "STEP_COPY_WASM_MODULE" => Some(Self::CopyWasmModule),
+                "STEP_WRITE_METADATA" => Some(Self::WriteMetadata),
+                "STEP_COPY_WASM_MEMORY" => Some(Self::CopyWasmMemory),
+                "STEP_COPY_STABLE_MEMORY" => Some(Self::CopyStableMemory),
+                "STEP_COPY_WASM_CHUNK_STORE" => Some(Self::CopyWasmChunkStore),
+                "STEP_REROUTE" => Some(Self::Reroute),
+                "STEP_REMOVE_FROM_SOURCE" => Some(Self::RemoveFromSource),
+                "STEP_START_ON_TARGET" => Some(Self::StartOnTarget),
+                "STEP_COMPLETE" => Some(Self::Complete),
+                "STEP_ROLL_BACK_REMOVE_FROM_TARGET" => Some(Self::RollBackRemoveFromTarget),
+                "STEP_ROLL_BACK_COMPLETE" => Some(Self::RollBackComplete),
+                "STEP_ROLL_BACK_RESTORE_STATUS" => Some(Self::RollBackRestoreStatus),
+                "STEP_FAILED" => Some(Self::Failed),
+                _ => None,
+            }
+        }
+    }
 }
 /// Maps Node Provider IDs to the amount (in 10,000ths of an SDR) they should be
 /// rewarded for providing nodes to the Internet Computer for the month.
@@ -0,0 +1,856 @@
+use crate::{
+    common::LOG_PREFIX,
+    mutations::{
+        complete_canister_migration::CompleteCanisterMigrationPayload,
+        prepare_canister_migration::PrepareCanisterMigrationPayload,
+        reroute_canister_ranges::RerouteCanisterRangesPayload,
+    },
+    pb::v1::{canister_migration_state::Step, CanisterMigrationState},
+    registry::Registry,
+};
+
+use candid::{CandidType, Deserialize, Encode, Nat};
+use dfn_core::api::{call, CanisterId};
+#[cfg(target_arch = "wasm32")]
+use dfn_core::println;
+use ic_base_types::{PrincipalId, SubnetId};
+use ic_error_types::RejectCode;
+use ic_ic00_types::{
+    AbortCanisterMigrationArgs, CanisterIdRecord, CanisterMigrationData, CanisterMigrationMetadata,
+    CanisterMigrationSegment, CanisterStatusResultV2, CanisterStatusType, EmptyBlob,
+    FinishCanisterMigrationArgs, MigratedPages, MigratedStoredChunk, Payload,
+    ReadCanisterMigrationDataArgs, RemoveMigratedCanisterArgs, RemoveMigratedCanisterReply,
+    WriteCanisterMigrationDataArgs,
+};
+use ic_nns_constants::REGISTRY_CANISTER_ID;
+use ic_registry_keys::make_subnet_record_key;
+use ic_registry_routing_table::CanisterIdRange;
+use on_wire::bytes;
+use serde::Serialize;
+use std::time::Duration;
+
+/// The number of consecutive failed attempts of a step up to and including
+/// the rerouting of the canister after which the migration is rolled back.
+const MAX_FAILED_ATTEMPTS_BEFORE_ROLL_BACK: u32 = 10;
+
+/// The number of consecutive failed attempts of a step after the rerouting,
+/// or of a step of a rollback, after which the migration is marked as failed.
+/// These steps cannot be rolled back, so they are retried for a while (10
+/// minutes) to give the subnets time to observe the rerouting.
+const MAX_FAILED_ATTEMPTS_BEFORE_FAILURE: u32 = 120;
+
+/// The delay before a failed step of a canister migration is attempted again.
+/// Among others, this gives the subnets time to observe a rerouted canister.
+pub const CANISTER_MIGRATION_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// The result of executing a step of a canister migration: the state to
+/// continue from, or `None` if the migration is finished.
+pub type CanisterMigrationStepResult = Result<Option<CanisterMigrationState>, String>;
+
+impl Registry {
+    /// Checks that `payload` describes a valid migration and returns the
+    /// canister to migrate along with the subnet currently hosting it.
+    pub fn validate_migrate_canister_payload(
+        &self,
+        payload: &MigrateCanisterPayload,
+    ) -> Result<(CanisterId, SubnetId), String> {
+        let canister_id = CanisterId::try_from(payload.canister_id)
+            .map_err(|err| format!("invalid canister ID {}: {:?}", payload.canister_id, err))?;
+        let target = payload.target_subnet;
+
+        let version = self.latest_version();
+        self.get(&make_subnet_record_key(target).into_bytes(), version)
+            .ok_or_else(|| format!("target {} is not a known subnet", target))?;
+        let source = self
+            .get_routing_table_or_panic(version)
+            .route(payload.canister_id)
+            .ok_or_else(|| format!("canister {} is not hosted by any subnet", canister_id))?;
+        if source == target {
+            return Err(format!(
+                "canister {} is already hosted by subnet {}",
+                canister_id, target
+            ));
+        }
+        match self.ongoing_canister_migrations.get(&canister_id) {
+            Some(migration) if migration.step() == Step::Failed => {
+                return Err(format!(
+                    "the migration of canister {} failed in step {:?}: {}",
+                    canister_id,
+                    migration.failed_step(),
+                    migration.last_error
+                ));
+            }
+            Some(_) => {
+                return Err(format!(
+                    "canister {} is already being migrated",
+                    canister_id
+                ));
+            }
+            None => {}
+        }
+        Ok((canister_id, source))
+    }
+
+    /// Starts the migration of a canister to another subnet on behalf of one
+    /// of its controllers.
+    ///
+    /// The migration is only recorded here: an entry in
+    /// `canister_migrations` marks the canister as being migrated and the
+    /// progress of the migration is persisted in the registry canister. The
+    /// steps are then executed one at a time by
+    /// `execute_canister_migration_step` and
+    /// `execute_registry_canister_migration_step`, whose outcome is recorded
+    /// by `record_canister_migration_step`:
+    ///
+    /// 1. The canister is stopped and locked on the source subnet, so that its
+    ///    controllers cannot change it, and its Wasm module, metadata,
+    ///    memories and Wasm chunk store are copied to the target subnet.
+    /// 2. The routing table is updated to assign the canister to the target
+    ///    subnet.
+    /// 3. The canister is removed from the source subnet and started on the
+    ///    target subnet with its remaining cycles.
+    ///
+    /// If a step before the rerouting keeps failing, or the canister changed
+    /// while it was copied, the partially written canister is removed from
+    /// the target subnet, and the canister is unlocked and started again if it
+    /// was running when the migration was requested. If a step after the
+    /// rerouting or a step of a rollback keeps failing, the migration is
+    /// marked as failed.
+    pub fn do_migrate_canister(
+        &mut self,
+        payload: MigrateCanisterPayload,
+        was_running: bool,
+    ) -> Result<(), String> {
+        println!("{}do_migrate_canister: {:?}", LOG_PREFIX, payload);
+
+        // The registry may have changed while the canister status was
+        // fetched, so the payload is validated again.
+        let (canister_id, source) = self.validate_migrate_canister_payload(&payload)?;
+        let target = payload.target_subnet;
+
+        // This also rejects concurrent migrations of the same canister by
+        // other means, e.g. a proposal.
+        self.prepare_canister_migration(PrepareCanisterMigrationPayload {
+            canister_id_ranges: canister_id_ranges(canister_id),
+            source_subnet: source,
+            destination_subnet: target,
+        })?;
+
+        let mut migration = CanisterMigrationState {
+            canister_id: Some(canister_id.get()),
+            source_subnet: Some(source.get()),
+            target_subnet: Some(target.get()),
+            was_running,
+            ..Default::default()
+        };
+        migration.set_step(Step::StopCanister);
+        self.ongoing_canister_migrations
+            .insert(canister_id, migration);
+        Ok(())
+    }
+
+    /// Returns the migration of `canister_id` requested through
+    /// `migrate_canister`, if it is still in progress or failed.
+    pub fn get_ongoing_canister_migration(
+        &self,
+        canister_id: &CanisterId,
+    ) -> Option<&CanisterMigrationState> {
+        self.ongoing_canister_migrations.get(canister_id)
+    }
+
+    /// Returns the canisters whose migration is still in progress or failed.
+    pub fn ongoing_canister_migrations(&self) -> Vec<CanisterMigrationState> {
+        self.ongoing_canister_migrations.values().cloned().collect()
+    }
+
+    /// Restores the migrations in progress from stable storage.
+    pub fn restore_ongoing_canister_migrations(&mut self, migrations: Vec<CanisterMigrationState>) {
+        self.ongoing_canister_migrations = migrations
+            .into_iter()
+            .map(|migration| (migration.get_canister_id(), migration))
+            .collect();
+    }
+
+    /// Executes a step of `migration` that only changes the contents of the
+    /// registry. Such steps are applied atomically, so they are trivially
+    /// idempotent.
+    pub fn execute_registry_canister_migration_step(
+        &mut self,
+        migration: CanisterMigrationState,
+    ) -> CanisterMigrationStepResult {
+        let canister_id = migration.get_canister_id();
+        let (source, target) = (migration.get_source_subnet(), migration.get_target_subnet());
+        match migration.step() {
+            Step::Reroute => {
+                self.reroute_canister_ranges(RerouteCanisterRangesPayload {
+                    reassigned_canister_ranges: canister_id_ranges(canister_id),
+                    source_subnet: source,
+                    destination_subnet: target,
+                })?;
+                Ok(Some(migration.advance(Step::RemoveFromSource, 0)))
+            }
+            Step::Complete => {
+                self.complete_canister_migration(CompleteCanisterMigrationPayload {
+                    canister_id_ranges: canister_id_ranges(canister_id),
+                    migration_trace: vec![source, target],
+                })?;
+                Ok(None)
+            }
+            Step::RollBackComplete => {
+                self.complete_canister_migration(CompleteCanisterMigrationPayload {
+                    canister_id_ranges: canister_id_ranges(canister_id),
+                    migration_trace: vec![source, target],
+                })?;
+                Ok(Some(migration.advance(Step::RollBackRestoreStatus, 0)))
+            }
+            step => Err(format!("step {:?} does not change the registry", step)),
+        }
+    }
+
+    /// Records the result of executing the current step of the migration of
+    /// `canister_id` and returns the delay after which the next step is to be
+    /// executed, or `None` if the migration is finished or failed.
+    ///
+    /// A failed step is retried after `CANISTER_MIGRATION_RETRY_DELAY`. After
+    /// `MAX_FAILED_ATTEMPTS_BEFORE_ROLL_BACK` consecutive failures of a step
+    /// that precedes the rerouting, the migration is rolled back instead.
+    /// After `MAX_FAILED_ATTEMPTS_BEFORE_FAILURE` consecutive failures of any
+    /// other step, the migration is marked as failed and kept, so that the
+    /// failure can be queried through `get_canister_migration_status`.
+    pub fn record_canister_migration_step(
+        &mut self,
+        canister_id: CanisterId,
+        result: CanisterMigrationStepResult,
+    ) -> Option<Duration> {
+        let migration = self.ongoing_canister_migrations.get_mut(&canister_id)?;
+        if migration.step() == Step::Failed {
+            return None;
+        }
+        match result {
+            Ok(Some(next)) => {
+                *migration = next;
+                Some(Duration::ZERO)
+            }
+            Ok(None) => {
+                println!(
+                    "{}canister migration of {} finished with step {:?}",
+                    LOG_PREFIX,
+                    canister_id,
+                    migration.step()
+                );
+                self.ongoing_canister_migrations.remove(&canister_id);
+                None
+            }
+            Err(err) => {
+                println!(
+                    "{}canister migration of {} failed in step {:?}: {}",
+                    LOG_PREFIX,
+                    canister_id,
+                    migration.step(),
+                    err
+                );
+                migration.failed_attempts += 1;
+                migration.last_error = err;
+                if can_roll_back(migration.step()) {
+                    if migration.failed_attempts >= MAX_FAILED_ATTEMPTS_BEFORE_ROLL_BACK {
+                        println!(
+                            "{}rolling back the canister migration of {}",
+                            LOG_PREFIX, canister_id
+                        );
+                        migration.set_step(Step::RollBackRemoveFromTarget);
+                        migration.position = 0;
+                        migration.failed_attempts = 0;
+                    }
+                } else if migration.failed_attempts >= MAX_FAILED_ATTEMPTS_BEFORE_FAILURE {
+                    println!(
+                        "{}the canister migration of {} failed in step {:?}",
+                        LOG_PREFIX,
+                        canister_id,
+                        migration.step()
+                    );
+                    migration.set_failed_step(migration.step());
+                    migration.set_step(Step::Failed);
+                    return None;
+                }
+                Some(CANISTER_MIGRATION_RETRY_DELAY)
+            }
+        }
+    }
+}
+
+impl CanisterMigrationState {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id.expect("missing canister_id"))
+    }
+
+    pub fn get_source_subnet(&self) -> SubnetId {
+        SubnetId::from(self.source_subnet.expect("missing source_subnet"))
+    }
+
+    pub fn get_target_subnet(&self) -> SubnetId {
+        SubnetId::from(self.target_subnet.expect("missing target_subnet"))
+    }
+
+    fn get_cycles(&self) -> u128 {
+        let mut cycles = [0; 16];
+        let len = self.cycles.len().min(16);
+        cycles[..len].copy_from_slice(&self.cycles[..len]);
+        u128::from_le_bytes(cycles)
+    }
+
+    /// Returns the state after the current step succeeded, continuing with
+    /// `step` at `position`.
+    fn advance(mut self, step: Step, position: u64) -> Self {
+        self.set_step(step);
+        self.position = position;
+        self.failed_attempts = 0;
+        self.last_error.clear();
+        self
+    }
+
+    /// Returns the state that rolls back the migration because of `reason`.
+    fn roll_back(self, reason: String) -> Self {
+        println!(
+            "{}rolling back the canister migration of {}: {}",
+            LOG_PREFIX,
+            self.get_canister_id(),
+            reason
+        );
+        let mut migration = self.advance(Step::RollBackRemoveFromTarget, 0);
+        migration.last_error = reason;
+        migration
+    }
+
+    /// Returns why the migration has to be rolled back if the canister
+    /// changed since it was locked. The canister cannot be changed while it
+    /// is locked, so this only guards against a torn copy.
+    fn canister_version_changed(&self, metadata: &CanisterMigrationMetadata) -> Option<String> {
+        (metadata.canister_version != self.canister_version).then(|| {
+            format!(
+                "the canister version changed from {} to {} during the migration",
+                self.canister_version, metadata.canister_version
+            )
+        })
+    }
+}
+
+/// The status of a canister migration requested through `migrate_canister`,
+/// as returned by `get_canister_migration_status`.
+#[derive(Debug, CandidType, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CanisterMigrationStatus {
+    pub source_subnet: SubnetId,
+    pub target_subnet: SubnetId,
+    /// The current step, e.g. `STEP_COPY_WASM_MEMORY` or `STEP_FAILED`.
+    pub step: String,
+    /// The step that kept failing if `step` is `STEP_FAILED`.
+    pub failed_step: Option<String>,
+    pub failed_attempts: u32,
+    pub last_error: String,
+}
+
+impl From<&CanisterMigrationState> for CanisterMigrationStatus {
+    fn from(migration: &CanisterMigrationState) -> Self {
+        Self {
+            source_subnet: migration.get_source_subnet(),
+            target_subnet: migration.get_target_subnet(),
+            step: migration.step().as_str_name().to_string(),
+            failed_step: (migration.step() == Step::Failed)
+                .then(|| migration.failed_step().as_str_name().to_string()),
+            failed_attempts: migration.failed_attempts,
+            last_error: migration.last_error.clone(),
+        }
+    }
+}
+
+/// Returns whether `step` is executed by
+/// `Registry::execute_registry_canister_migration_step` rather than by
+/// `execute_canister_migration_step`.
+pub fn is_registry_canister_migration_step(step: Step) -> bool {
+    matches!(
+        step,
+        Step::Reroute | Step::Complete | Step::RollBackComplete
+    )
+}
+
+/// Returns whether a migration that keeps failing in `step` can still be
+/// rolled back, i.e. whether the canister was not rerouted yet.
+fn can_roll_back(step: Step) -> bool {
+    matches!(
+        step,
+        Step::StopCanister
+            | Step::CopyWasmModule
+            | Step::WriteMetadata
+            | Step::CopyWasmMemory
+            | Step::CopyStableMemory
+            | Step::CopyWasmChunkStore
+            | Step::Reroute
+    )
+}
+
+fn canister_id_ranges(canister_id: CanisterId) -> Vec<CanisterIdRange> {
+    vec![CanisterIdRange {
+        start: canister_id,
+        end: canister_id,
+    }]
+}
+
+/// Checks that `caller` and the registry canister are controllers of
+/// `canister_id` and returns whether the canister is running.
+pub async fn check_canister_migration_controllers(
+    caller: PrincipalId,
+    canister_id: CanisterId,
+) -> Result<bool, String> {
+    // Only a controller of the canister may fetch its status, so this also
+    // checks that the registry can stop and start the canister.
+    let status: CanisterStatusResultV2 =
+        call_ic00("canister_status", &CanisterIdRecord::from(canister_id))
+            .await
+            .map_err(|err| {
+                format!(
+                    "the registry canister {} must be a controller of canister {}: {}",
+                    REGISTRY_CANISTER_ID, canister_id, err
+                )
+            })?;
+    if !status.controllers().contains(&caller) {
+        return Err(format!(
+            "caller {} is not a controller of canister {}",
+            caller, canister_id
+        ));
+    }
+    Ok(status.status() == CanisterStatusType::Running)
+}
+
+/// Executes a step of `migration` that calls the management canister.
+///
+/// Every step either only reads state or makes progress that is recorded in
+/// the returned state, so a step that failed can be executed again.
+pub async fn execute_canister_migration_step(
+    migration: CanisterMigrationState,
+) -> CanisterMigrationStepResult {
+    let canister_id = migration.get_canister_id();
+    let (source, target) = (migration.get_source_subnet(), migration.get_target_subnet());
+    let read = |segment| ReadCanisterMigrationDataArgs {
+        subnet_id: source.get(),
+        canister_id: canister_id.get(),
+        segment,
+    };
+    let write = |data| WriteCanisterMigrationDataArgs {
+        subnet_id: target.get(),
+        canister_id: canister_id.get(),
+        data,
+    };
+
+    match migration.step() {
+        Step::StopCanister => {
+            let EmptyBlob =
+                call_ic00("stop_canister", &CanisterIdRecord::from(canister_id)).await?;
+            // The first read locks the canister on the source subnet.
+            let metadata = read_metadata(read(CanisterMigrationSegment::Metadata)).await?;
+            let mut migration = migration;
+            migration.canister_version = metadata.canister_version;
+            // The Wasm module is assembled on the target subnet when the
+            // metadata is written, so it has to be copied first.
+            Ok(Some(if metadata.code.is_some() {
+                migration.advance(Step::CopyWasmModule, 0)
+            } else {
+                migration.advance(Step::WriteMetadata, 0)
+            }))
+        }
+        Step::CopyWasmModule => {
+            let offset = migration.position;
+            let data: CanisterMigrationData = call_ic00(
+                "read_canister_migration_data",
+                &read(CanisterMigrationSegment::WasmModule { offset }),
+            )
+            .await?;
+            let chunk_size = match &data {
+                CanisterMigrationData::WasmModule(chunk) => chunk.chunk.len() as u64,
+                data => return Err(format!("unexpected migration data {:?}", data)),
+            };
+            if chunk_size == 0 {
+                return Ok(Some(migration.advance(Step::WriteMetadata, 0)));
+            }
+            let EmptyBlob = call_ic00("write_canister_migration_data", &write(data)).await?;
+            Ok(Some(
+                migration.advance(Step::CopyWasmModule, offset + chunk_size),
+            ))
+        }
+        Step::WriteMetadata => {
+            let metadata = read_metadata(read(CanisterMigrationSegment::Metadata)).await?;
+            if let Some(reason) = migration.canister_version_changed(&metadata) {
+                return Ok(Some(migration.roll_back(reason)));
+            }
+            let has_code = metadata.code.is_some();
+            let EmptyBlob = call_ic00(
+                "write_canister_migration_data",
+                &write(CanisterMigrationData::Metadata(metadata)),
+            )
+            .await?;
+            // The memories can be written only once the Wasm module was
+            // assembled.
+            Ok(Some(if has_code {
+                migration.advance(Step::CopyWasmMemory, 0)
+            } else {
+                migration.advance(Step::CopyWasmChunkStore, 0)
+            }))
+        }
+        Step::CopyWasmMemory | Step::CopyStableMemory => {
+            let start_page = migration.position;
+            let stable_memory = migration.step() == Step::CopyStableMemory;
+            let segment = if stable_memory {
+                CanisterMigrationSegment::StableMemory { start_page }
+            } else {
+                CanisterMigrationSegment::WasmMemory { start_page }
+            };
+            let data: CanisterMigrationData =
+                call_ic00("read_canister_migration_data", &read(segment)).await?;
+            let next_page = match &data {
+                CanisterMigrationData::WasmMemory(MigratedPages { next_page, .. })
+                    if !stable_memory =>
+                {
+                    *next_page
+                }
+                CanisterMigrationData::StableMemory(MigratedPages { next_page, .. })
+                    if stable_memory =>
+                {
+                    *next_page
+                }
+                data => return Err(format!("unexpected migration data {:?}", data)),
+            };
+            let EmptyBlob = call_ic00("write_canister_migration_data", &write(data)).await?;
+            Ok(Some(match next_page {
+                Some(next_page) => migration.advance(migration.step(), next_page),
+                None if stable_memory => migration.advance(Step::CopyWasmChunkStore, 0),
+                None => migration.advance(Step::CopyStableMemory, 0),
+            }))
+        }
+        Step::CopyWasmChunkStore => {
+            let index = migration.position;
+            let data: CanisterMigrationData = call_ic00(
+                "read_canister_migration_data",
+                &read(CanisterMigrationSegment::WasmChunkStore { index }),
+            )
+            .await?;
+            let next_index = match &data {
+                CanisterMigrationData::WasmChunkStore(MigratedStoredChunk {
+                    next_index, ..
+                }) => *next_index,
+                data => return Err(format!("unexpected migration data {:?}", data)),
+            };
+            let EmptyBlob = call_ic00("write_canister_migration_data", &write(data)).await?;
+            match next_index {
+                Some(next_index) => Ok(Some(
+                    migration.advance(Step::CopyWasmChunkStore, next_index),
+                )),
+                None => {
+                    // The copy is complete: check that the canister did not
+                    // change while it was copied before rerouting it.
+                    let metadata = read_metadata(read(CanisterMigrationSegment::Metadata)).await?;
+                    if let Some(reason) = migration.canister_version_changed(&metadata) {
+                        return Ok(Some(migration.roll_back(reason)));
+                    }
+                    Ok(Some(migration.advance(Step::Reroute, 0)))
+                }
+            }
+        }
+        Step::RemoveFromSource => {
+            // This fails until the source subnet observed the rerouting.
+            let reply: RemoveMigratedCanisterReply = call_ic00(
+                "remove_migrated_canister",
+                &RemoveMigratedCanisterArgs {
+                    subnet_id: source.get(),
+                    canister_id: canister_id.get(),
+                },
+            )
+            .await?;
+            let cycles = u128::try_from(reply.cycles.0)
+                .map_err(|err| format!("invalid cycles balance: {}", err))?;
+            let mut migration = migration.advance(Step::StartOnTarget, 0);
+            migration.cycles = cycles.to_le_bytes().to_vec();
+            Ok(Some(migration))
+        }
+        Step::StartOnTarget => {
+            // This fails until the target subnet observed the rerouting.
+            let EmptyBlob = call_ic00(
+                "finish_canister_migration",
+                &FinishCanisterMigrationArgs {
+                    subnet_id: target.get(),
+                    canister_id: canister_id.get(),
+                    cycles: Nat::from(migration.get_cycles()),
+                },
+            )
+            .await?;
+            Ok(Some(migration.advance(Step::Complete, 0)))
+        }
+        Step::RollBackRemoveFromTarget => {
+            let removed = call(
+                CanisterId::ic_00(),
+                "remove_migrated_canister",
+                bytes,
+                Encode!(&RemoveMigratedCanisterArgs {
+                    subnet_id: target.get(),
+                    canister_id: canister_id.get(),
+                })
+                .unwrap(),
+            )
+            .await;
+            match removed {
+                Ok(_) => {}
+                // Nothing was written to the target subnet yet.
+                Err((Some(code), msg))
+                    if code == RejectCode::DestinationInvalid as i32
+                        && msg.contains("not found") => {}
+                Err((code, msg)) => {
+                    return Err(format!(
+                        "remove_migrated_canister was rejected with code {:?}: {}",
+                        code, msg
+                    ))
+                }
+            }
+            Ok(Some(migration.advance(Step::RollBackComplete, 0)))
+        }
+        Step::RollBackRestoreStatus => {
+            let EmptyBlob = call_ic00(
+                "abort_canister_migration",
+                &AbortCanisterMigrationArgs {
+                    subnet_id: source.get(),
+                    canister_id: canister_id.get(),
+                },
+            )
+            .await?;
+            // The canister is left stopped if it was not running before.
+            if migration.was_running {
+                let EmptyBlob =
+                    call_ic00("start_canister", &CanisterIdRecord::from(canister_id)).await?;
+            }
+            Ok(None)
+        }
+        step => Err(format!(
+            "step {:?} does not call the management canister",
+            step
+        )),
+    }
+}
+
+async fn read_metadata(
+    args: ReadCanisterMigrationDataArgs,
+) -> Result<CanisterMigrationMetadata, String> {
+    match call_ic00("read_canister_migration_data", &args).await? {
+        CanisterMigrationData::Metadata(metadata) => Ok(metadata),
+        data => Err(format!("unexpected migration data {:?}", data)),
+    }
+}
+
+/// Calls a method of the management canister and decodes its reply.
+async fn call_ic00<Args, Reply>(method: &str, args: &Args) -> Result<Reply, String>
+where
+    Args: CandidType,
+    Reply: for<'a> Payload<'a>,
+{
+    let reply = call(CanisterId::ic_00(), method, bytes, Encode!(args).unwrap())
+        .await
+        .map_err(|(code, msg)| format!("{} was rejected with code {:?}: {}", method, code, msg))?;
+    Reply::decode(&reply)
+        .map_err(|err| format!("failed to decode the reply of {}: {}", method, err))
+}
+
+/// The argument for the `migrate_canister` update call.
+#[derive(Debug, CandidType, Serialize, Deserialize)]
+pub struct MigrateCanisterPayload {
+    /// The canister to migrate.
+    pub canister_id: PrincipalId,
+    /// The subnet that the canister is migrated to.
+    pub target_subnet: SubnetId,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migration(step: Step) -> CanisterMigrationState {
+        let mut migration = CanisterMigrationState {
+            canister_id: Some(CanisterId::from_u64(1).get()),
+            source_subnet: Some(PrincipalId::new_subnet_test_id(1)),
+            target_subnet: Some(PrincipalId::new_subnet_test_id(2)),
+            was_running: true,
+            ..Default::default()
+        };
+        migration.set_step(step);
+        migration
+    }
+
+    fn registry_with_migration(migration: CanisterMigrationState) -> Registry {
+        let mut registry = Registry::new();
+        registry.restore_ongoing_canister_migrations(vec![migration]);
+        registry
+    }
+
+    #[test]
+    fn successful_step_resets_failed_attempts() {
+        let canister_id = CanisterId::from_u64(1);
+        let mut failed = migration(Step::CopyWasmMemory);
+        failed.failed_attempts = 3;
+        failed.last_error = "error".to_string();
+        let mut registry = registry_with_migration(failed.clone());
+
+        let next = failed.advance(Step::CopyWasmMemory, 42);
+        assert_eq!(
+            registry.record_canister_migration_step(canister_id, Ok(Some(next))),
+            Some(Duration::ZERO)
+        );
+
+        let migration = registry
+            .get_ongoing_canister_migration(&canister_id)
+            .unwrap();
+        assert_eq!(migration.step(), Step::CopyWasmMemory);
+        assert_eq!(migration.position, 42);
+        assert_eq!(migration.failed_attempts, 0);
+        assert!(migration.last_error.is_empty());
+    }
+
+    #[test]
+    fn failing_copy_is_rolled_back() {
+        let canister_id = CanisterId::from_u64(1);
+        let mut registry = registry_with_migration(migration(Step::CopyStableMemory));
+
+        for attempt in 1..=MAX_FAILED_ATTEMPTS_BEFORE_ROLL_BACK {
+            assert_eq!(
+                registry.record_canister_migration_step(canister_id, Err("error".to_string())),
+                Some(CANISTER_MIGRATION_RETRY_DELAY)
+            );
+            let migration = registry
+                .get_ongoing_canister_migration(&canister_id)
+                .unwrap();
+            if attempt < MAX_FAILED_ATTEMPTS_BEFORE_ROLL_BACK {
+                assert_eq!(migration.step(), Step::CopyStableMemory);
+                assert_eq!(migration.failed_attempts, attempt);
+            } else {
+                assert_eq!(migration.step(), Step::RollBackRemoveFromTarget);
+                assert_eq!(migration.failed_attempts, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn failing_steps_after_rerouting_are_retried() {
+        let canister_id = CanisterId::from_u64(1);
+        let mut registry = registry_with_migration(migration(Step::StartOnTarget));
+
+        for _ in 0..2 * MAX_FAILED_ATTEMPTS_BEFORE_ROLL_BACK {
+            assert_eq!(
+                registry.record_canister_migration_step(canister_id, Err("error".to_string())),
+                Some(CANISTER_MIGRATION_RETRY_DELAY)
+            );
+        }
+        let migration = registry
+            .get_ongoing_canister_migration(&canister_id)
+            .unwrap();
+        assert_eq!(migration.step(), Step::StartOnTarget);
+        assert_eq!(migration.last_error, "error");
+    }
+
+    fn fail_until_done(registry: &mut Registry, canister_id: CanisterId, error: &str) -> u32 {
+        let mut attempts = 0;
+        while let Some(delay) =
+            registry.record_canister_migration_step(canister_id, Err(error.to_string()))
+        {
+            assert_eq!(delay, CANISTER_MIGRATION_RETRY_DELAY);
+            attempts += 1;
+            assert!(
+                attempts < MAX_FAILED_ATTEMPTS_BEFORE_FAILURE,
+                "retried forever"
+            );
+        }
+        attempts + 1
+    }
+
+    #[test]
+    fn failing_step_after_rerouting_is_marked_failed() {
+        let canister_id = CanisterId::from_u64(1);
+        let mut registry = registry_with_migration(migration(Step::RemoveFromSource));
+
+        let attempts = fail_until_done(&mut registry, canister_id, "error");
+
+        assert_eq!(attempts, MAX_FAILED_ATTEMPTS_BEFORE_FAILURE);
+        let migration = registry
+            .get_ongoing_canister_migration(&canister_id)
+            .unwrap();
+        assert_eq!(migration.step(), Step::Failed);
+        assert_eq!(migration.failed_step(), Step::RemoveFromSource);
+        // A failed migration is not executed anymore.
+        assert_eq!(
+            registry.record_canister_migration_step(canister_id, Err("error".to_string())),
+            None
+        );
+    }
+
+    #[test]
+    fn failing_roll_back_is_marked_failed() {
+        let canister_id = CanisterId::from_u64(1);
+        let mut registry = registry_with_migration(migration(Step::RollBackRestoreStatus));
+        // E.g. the registry canister was removed from the controllers.
+        let error = "start_canister was rejected with code Some(5): Only the controllers \
+            of the canister can control it.";
+
+        fail_until_done(&mut registry, canister_id, error);
+
+        let status = CanisterMigrationStatus::from(
+            registry
+                .get_ongoing_canister_migration(&canister_id)
+                .unwrap(),
+        );
+        assert_eq!(
+            status,
+            CanisterMigrationStatus {
+                source_subnet: SubnetId::from(PrincipalId::new_subnet_test_id(1)),
+                target_subnet: SubnetId::from(PrincipalId::new_subnet_test_id(2)),
+                step: "STEP_FAILED".to_string(),
+                failed_step: Some("STEP_ROLL_BACK_RESTORE_STATUS".to_string()),
+                failed_attempts: MAX_FAILED_ATTEMPTS_BEFORE_FAILURE,
+                last_error: error.to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn changed_canister_version_rolls_back() {
+        let mut copying = migration(Step::CopyWasmChunkStore);
+        copying.canister_version = 3;
+        let metadata = |canister_version| CanisterMigrationMetadata {
+            settings: ic_ic00_types::CanisterSettingsArgsBuilder::new().build(),
+            certified_data: vec![],
+            canister_version,
+            global_timer_nanos: None,
+            recent_changes: vec![],
+            total_num_changes: 0,
+            code: None,
+        };
+
+        assert_eq!(copying.canister_version_changed(&metadata(3)), None);
+        let reason = copying.canister_version_changed(&metadata(4)).unwrap();
+        let rolled_back = copying.roll_back(reason.clone());
+
+        assert_eq!(rolled_back.step(), Step::RollBackRemoveFromTarget);
+        assert_eq!(rolled_back.position, 0);
+        assert_eq!(rolled_back.last_error, reason);
+    }
+
+    #[test]
+    fn finished_migration_is_removed() {
+        let canister_id = CanisterId::from_u64(1);
+        let mut registry = registry_with_migration(migration(Step::RollBackRestoreStatus));
+
+        assert_eq!(
+            registry.record_canister_migration_step(canister_id, Ok(None)),
+            None
+        );
+        assert!(registry.ongoing_canister_migrations().is_empty());
+    }
+
+    #[test]
+    fn cycles_are_stored_little_endian() {
+        let mut migration = migration(Step::StartOnTarget);
+        migration.cycles = (u64::MAX as u128 + 7).to_le_bytes().to_vec();
+        assert_eq!(migration.get_cycles(), u64::MAX as u128 + 7);
+    }
+}
@@ -10,6 +10,7 @@ pub mod do_change_subnet_membership;
 pub mod do_clear_provisional_whitelist;
 pub mod do_create_subnet;
 pub mod do_delete_subnet;
+pub mod do_migrate_canister;
 pub mod do_recover_subnet;
 pub mod do_remove_api_boundary_nodes;
 pub mod do_remove_node_operators;
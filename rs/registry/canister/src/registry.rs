@@ -1,10 +1,14 @@
 use crate::{
+    certification::key_history_witness_size,
     common::LOG_PREFIX,
     pb::v1::{
-        registry_stable_storage::Version as ReprVersion, ChangelogEntry, RegistryStableStorage,
+        registry_stable_storage::Version as ReprVersion, CanisterMigrationState, ChangelogEntry,
+        RegistryStableStorage,
     },
 };
+use ic_base_types::CanisterId;
 use ic_certified_map::RbTree;
+use ic_crypto_sha2::Sha256;
 use ic_registry_transport::{
     pb::v1::{
         registry_mutation::Type, RegistryAtomicMutateRequest, RegistryDelta, RegistryMutation,
@@ -18,6 +22,7 @@ use std::{
     cmp::max,
     collections::{BTreeMap, VecDeque},
     fmt,
+    ops::Bound,
 };
 
 #[cfg(target_arch = "wasm32")]
@@ -94,6 +99,29 @@ pub struct Registry {
     /// RegistryAtomicMutateRequest.  We keep the serialized version around to
     /// make sure that hash trees stay the same even if protobuf schema evolves.
     pub(crate) changelog: RbTree<EncodedVersion, Vec<u8>>,
+
+    /// An index of all the changes made to each key, for certification
+    /// purposes: for every key, it maps the versions at which the key was
+    /// mutated to `registry_value_leaf` of the corresponding value.
+    ///
+    /// This allows proving the value of a single key at any version without
+    /// revealing the changelog entries of all the other versions.
+    pub(crate) key_index: RbTree<Vec<u8>, RbTree<EncodedVersion, Vec<u8>>>,
+
+    /// The canister migrations requested through `migrate_canister` that are
+    /// still in progress. They are not part of the registry contents, but are
+    /// persisted across upgrades so that the migrations can be resumed.
+    pub(crate) ongoing_canister_migrations: BTreeMap<CanisterId, CanisterMigrationState>,
+}
+
+/// The leaf stored in the key index for a change of a key: the SHA-256 hash
+/// of the value, or the empty blob if the change is a deletion.
+pub fn registry_value_leaf(value: &RegistryValue) -> Vec<u8> {
+    if value.deletion_marker {
+        vec![]
+    } else {
+        Sha256::hash(&value.value).to_vec()
+    }
 }
 
 impl Registry {
@@ -149,6 +177,53 @@ impl Registry {
         Some(value)
     }
 
+    /// Returns the latest change, including deletions, made to `key` at or
+    /// before `version`.
+    pub fn get_change_at_or_before(&self, key: &[u8], version: Version) -> Option<&RegistryValue> {
+        self.store
+            .get(key)?
+            .iter()
+            .rev()
+            .find(|value| value.version <= version)
+    }
+
+    /// Returns a page of the snapshot of the registry at `version`: for every
+    /// key strictly greater than `start_after_key` that is present at
+    /// `version`, a delta with its latest value at or before `version`.
+    ///
+    /// Keys are returned in ascending order, and as many as fit into
+    /// `max_bytes` (but at least one), accounting for the witness that
+    /// certifies the history of every scanned key. The returned flag tells
+    /// whether the page contains the last key of the snapshot.
+    pub fn get_snapshot(
+        &self,
+        version: Version,
+        start_after_key: &[u8],
+        max_bytes: usize,
+    ) -> (Vec<RegistryDelta>, bool) {
+        let mut entries = vec![];
+        let mut size = 0;
+        for (key, _) in self
+            .store
+            .range::<[u8], _>((Bound::Excluded(start_after_key), Bound::Unbounded))
+        {
+            size += key_history_witness_size(self, key, version);
+            let value = match self.get(key, version) {
+                Some(value) => value,
+                None => continue,
+            };
+            size += key.len() + value.value.len();
+            if size > max_bytes && !entries.is_empty() {
+                return (entries, false);
+            }
+            entries.push(RegistryDelta {
+                key: key.clone(),
+                values: vec![value.clone()],
+            });
+        }
+        (entries, true)
+    }
+
     /// Computes the number of deltas with version greater than `since_version`
     /// that fit into the specified byte limit.
     ///
@@ -343,6 +418,10 @@ impl Registry {
         &self.changelog
     }
 
+    pub fn key_index(&self) -> &RbTree<Vec<u8>, RbTree<EncodedVersion, Vec<u8>>> {
+        &self.key_index
+    }
+
     /// Inserts a changelog entry at the given version, while enforcing the
     /// [`MAX_REGISTRY_DELTAS_SIZE`] limit.
     fn changelog_insert(&mut self, version: u64, req: &RegistryAtomicMutateRequest) {
@@ -358,6 +437,27 @@ impl Registry {
         }
 
         self.changelog.insert(version, bytes);
+        self.key_index_insert(version, &req.mutations);
+    }
+
+    /// Records the given mutations, applied at the given version, in the key
+    /// index.
+    fn key_index_insert(&mut self, version: EncodedVersion, mutations: &[RegistryMutation]) {
+        for mutation in mutations {
+            let leaf = registry_value_leaf(&RegistryValue {
+                value: mutation.value.clone(),
+                version: version.as_version(),
+                deletion_marker: mutation.mutation_type == Type::Delete as i32,
+            });
+            if self.key_index.get(&mutation.key).is_some() {
+                self.key_index
+                    .modify(&mutation.key, |versions| versions.insert(version, leaf));
+            } else {
+                let mut versions = RbTree::new();
+                versions.insert(version, leaf);
+                self.key_index.insert(mutation.key.clone(), versions);
+            }
+        }
     }
 
     /// Sets the content of the registry from its serialized representation.
@@ -468,6 +568,7 @@ fn pb_encode(msg: &impl prost::Message) -> Vec<u8> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::certification::{certified_snapshot_tree, hash_tree_to_proto};
     use ic_registry_transport::{delete, insert, update, upsert};
     use rand::{Rng, SeedableRng};
     use rand_distr::{Alphanumeric, Distribution, Poisson, Uniform};
@@ -580,6 +681,193 @@ mod tests {
         serialize_then_deserialize(registry);
     }
 
+    #[test]
+    fn test_key_index() {
+        let mut registry = Registry::new();
+        let key = vec![1, 2, 3, 4];
+        let other_key = vec![5];
+        let value = vec![5, 6, 7, 8];
+        let value2 = vec![9, 10, 11, 12];
+        assert_empty!(apply_mutations_skip_invariant_checks(
+            &mut registry,
+            vec![insert(&key, &value)]
+        ));
+        assert_empty!(apply_mutations_skip_invariant_checks(
+            &mut registry,
+            vec![insert(&other_key, &value)]
+        ));
+        assert_empty!(apply_mutations_skip_invariant_checks(
+            &mut registry,
+            vec![update(&key, &value2)]
+        ));
+        assert_empty!(apply_mutations_skip_invariant_checks(
+            &mut registry,
+            vec![delete(&key)]
+        ));
+
+        let versions = registry.key_index().get(&key).unwrap();
+        assert_eq!(
+            versions.get(EncodedVersion::from(1).as_ref()),
+            Some(&Sha256::hash(&value).to_vec())
+        );
+        assert_eq!(versions.get(EncodedVersion::from(2).as_ref()), None);
+        assert_eq!(
+            versions.get(EncodedVersion::from(3).as_ref()),
+            Some(&Sha256::hash(&value2).to_vec())
+        );
+        assert_eq!(
+            versions.get(EncodedVersion::from(4).as_ref()),
+            Some(&vec![])
+        );
+
+        let other_versions = registry.key_index().get(&other_key).unwrap();
+        assert_eq!(
+            other_versions.get(EncodedVersion::from(2).as_ref()),
+            Some(&Sha256::hash(&value).to_vec())
+        );
+
+        assert_eq!(registry.get_change_at_or_before(&key, 0), None);
+        assert_eq!(
+            registry.get_change_at_or_before(&key, 2).unwrap().version,
+            1
+        );
+        assert!(
+            registry
+                .get_change_at_or_before(&key, 4)
+                .unwrap()
+                .deletion_marker
+        );
+
+        serialize_then_deserialize(registry);
+    }
+
+    #[test]
+    fn test_get_snapshot() {
+        let mut registry = Registry::new();
+        let value = vec![5, 6, 7, 8];
+        let value2 = vec![9, 10, 11, 12];
+        assert_empty!(apply_mutations_skip_invariant_checks(
+            &mut registry,
+            vec![
+                insert(b"a", &value),
+                insert(b"b", &value),
+                insert(b"c", &value)
+            ]
+        ));
+        assert_empty!(apply_mutations_skip_invariant_checks(
+            &mut registry,
+            vec![update(b"a", &value2), delete(b"b")]
+        ));
+
+        let keys_and_values = |entries: Vec<RegistryDelta>| {
+            entries
+                .into_iter()
+                .map(|delta| (delta.key, delta.values[0].value.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        // The snapshot at version 1 contains the original values.
+        let (entries, complete) = registry.get_snapshot(1, &[], MAX_REGISTRY_DELTAS_SIZE);
+        assert!(complete);
+        assert_eq!(
+            keys_and_values(entries),
+            vec![
+                (b"a".to_vec(), value.clone()),
+                (b"b".to_vec(), value.clone()),
+                (b"c".to_vec(), value.clone())
+            ]
+        );
+
+        // The snapshot at version 2 skips deleted keys.
+        let (entries, complete) = registry.get_snapshot(2, &[], MAX_REGISTRY_DELTAS_SIZE);
+        assert!(complete);
+        assert_eq!(
+            keys_and_values(entries),
+            vec![(b"a".to_vec(), value2), (b"c".to_vec(), value.clone())]
+        );
+
+        // Pages contain at least one entry, and continue after the given key.
+        let (entries, complete) = registry.get_snapshot(1, &[], 1);
+        assert!(!complete);
+        assert_eq!(
+            keys_and_values(entries),
+            vec![(b"a".to_vec(), value.clone())]
+        );
+        let (entries, complete) = registry.get_snapshot(1, b"a", 1);
+        assert!(!complete);
+        assert_eq!(
+            keys_and_values(entries),
+            vec![(b"b".to_vec(), value.clone())]
+        );
+        let (entries, complete) = registry.get_snapshot(1, b"b", 1);
+        assert!(complete);
+        assert_eq!(keys_and_values(entries), vec![(b"c".to_vec(), value)]);
+
+        // Nothing is present before the first version.
+        assert_eq!(
+            registry.get_snapshot(0, &[], MAX_REGISTRY_DELTAS_SIZE),
+            (vec![], true)
+        );
+    }
+
+    #[test]
+    fn test_get_snapshot_pages_fit_with_long_key_history() {
+        let mut registry = Registry::new();
+        let keys: Vec<Vec<u8>> = (b'a'..=b'z').map(|key| vec![key]).collect();
+        assert_empty!(apply_mutations_skip_invariant_checks(
+            &mut registry,
+            keys.iter().map(|key| insert(key, [0; 16])).collect()
+        ));
+        // The witness of a key grows with the number of times it was mutated.
+        for i in 0..500_u64 {
+            assert_empty!(apply_mutations_skip_invariant_checks(
+                &mut registry,
+                keys.iter()
+                    .map(|key| update(key, i.to_be_bytes()))
+                    .collect()
+            ));
+        }
+        let version = registry.latest_version();
+
+        let max_bytes = 2_000;
+        let mut returned_keys = vec![];
+        let mut start_after_key = vec![];
+        loop {
+            let (entries, complete) = registry.get_snapshot(version, &start_after_key, max_bytes);
+            let last_key = if complete {
+                None
+            } else {
+                entries.last().map(|delta| delta.key.as_slice())
+            };
+            let hash_tree = hash_tree_to_proto(certified_snapshot_tree(
+                &registry,
+                version,
+                &start_after_key,
+                last_key,
+            ));
+            let page_size = hash_tree.encoded_len()
+                + entries
+                    .iter()
+                    .map(|delta| delta.encoded_len())
+                    .sum::<usize>();
+            // As for `MAX_REGISTRY_DELTAS_SIZE`, ⅓ of the response is reserved
+            // for encoding overhead.
+            assert!(
+                page_size <= max_bytes * 3 / 2,
+                "Page after {:?} takes {} bytes",
+                start_after_key,
+                page_size
+            );
+
+            returned_keys.extend(entries.into_iter().map(|delta| delta.key));
+            if complete {
+                break;
+            }
+            start_after_key = returned_keys.last().unwrap().clone();
+        }
+        assert_eq!(returned_keys, keys);
+    }
+
     #[test]
     fn test_get_changes_since() {
         let mut registry = Registry::new();
@@ -15,6 +15,8 @@ pub fn canister_post_upgrade(registry: &mut Registry, stable_storage: &[u8]) {
             .expect("Error decoding from stable"),
     );
 
+    registry.restore_ongoing_canister_migrations(registry_storage.canister_migrations);
+
     registry.check_global_state_invariants(&[]);
     // Registry::from_serializable_from guarantees this always passes in this function
     // because it fills in missing versions to maintain that invariant
@@ -56,6 +58,7 @@ mod test {
         let ss = RegistryCanisterStableStorage {
             registry: Some(registry.serializable_form()),
             pre_upgrade_version: override_version.or_else(|| Some(registry.latest_version())),
+            canister_migrations: registry.ongoing_canister_migrations(),
         };
         ss.encode(&mut serialized)
             .expect("Error serializing to stable.");
@@ -1,8 +1,10 @@
 use ic_certification::{verify_certified_data, CertificateValidationError};
-use ic_crypto_tree_hash::{LabeledTree, MixedHashTree};
+use ic_crypto_sha2::Sha256;
+use ic_crypto_tree_hash::{LabeledTree, LookupStatus, MixedHashTree};
 use ic_interfaces_registry::RegistryTransportRecord;
 use ic_registry_transport::pb::v1::{
-    registry_mutation::Type, CertifiedResponse, RegistryAtomicMutateRequest,
+    registry_mutation::Type, CertifiedResponse, RegistryAtomicMutateRequest, RegistryDelta,
+    RegistryGetCertifiedValueResponse, RegistrySnapshotResponse, RegistryValue,
 };
 use ic_types::{
     crypto::threshold_sig::ThresholdSigPublicKey, CanisterId, RegistryVersion, SubnetId, Time,
@@ -214,6 +216,387 @@ pub fn decode_certified_deltas(
     Ok((changes, current_version, time))
 }
 
+/// Decodes the value of `key` at `version` from its hash tree
+/// representation, checking that `value` is the latest change made to the key
+/// at or before `version` (or that there is no such change if `value` is
+/// `None`).
+pub fn decode_value_hash_tree(
+    key: &[u8],
+    version: u64,
+    value: Option<RegistryValue>,
+    hash_tree: &MixedHashTree,
+) -> Result<Option<RegistryValue>, CertificationError> {
+    let current_version = certified_current_version(hash_tree)?;
+    if version > current_version {
+        return Err(CertificationError::InvalidDeltas(format!(
+            "requested version {} is newer than the current version {}",
+            version, current_version
+        )));
+    }
+
+    let versions = match hash_tree.lookup(&[&b"keys"[..], key]) {
+        LookupStatus::Found(versions) => versions,
+        LookupStatus::Absent if value.is_none() => return Ok(None),
+        LookupStatus::Absent => {
+            return Err(CertificationError::InvalidDeltas(
+                "the hash tree proves that the key was never set".to_string(),
+            ))
+        }
+        LookupStatus::Unknown => {
+            return Err(CertificationError::MalformedHashTree(
+                "the hash tree does not contain the history of the key".to_string(),
+            ))
+        }
+    };
+
+    match (change_at_or_before(versions, version)?, &value) {
+        (Some((v, leaf)), Some(value)) if v == value.version => {
+            let expected = if value.deletion_marker {
+                vec![]
+            } else {
+                Sha256::hash(&value.value).to_vec()
+            };
+            if leaf != expected {
+                return Err(CertificationError::InvalidDeltas(format!(
+                    "the value of the key at version {} does not match its certified hash",
+                    v
+                )));
+            }
+        }
+        (None, None) => {}
+        _ => {
+            return Err(CertificationError::InvalidDeltas(
+                "the value does not match the certified history of the key".to_string(),
+            ))
+        }
+    }
+
+    Ok(value)
+}
+
+/// Checks that `entries` is the page of the registry snapshot at `version`
+/// that starts after `start_after_key`, as proven by `hash_tree`: the entries
+/// must be exactly the keys present at `version` in the key range of the
+/// page, each with its value at `version`. The key range ends at the last
+/// entry, or covers all the remaining keys if `complete` is set.
+pub fn decode_snapshot_hash_tree(
+    version: u64,
+    start_after_key: &[u8],
+    entries: &[RegistryDelta],
+    complete: bool,
+    hash_tree: &MixedHashTree,
+) -> Result<(), CertificationError> {
+    let current_version = certified_current_version(hash_tree)?;
+    if version > current_version {
+        return Err(CertificationError::InvalidDeltas(format!(
+            "snapshot version {} is newer than the current version {}",
+            version, current_version
+        )));
+    }
+
+    let keys = match hash_tree.lookup(&[&b"keys"[..]]) {
+        LookupStatus::Found(keys) => keys,
+        _ => {
+            return Err(CertificationError::MalformedHashTree(
+                "the hash tree does not contain the keys of the registry".to_string(),
+            ))
+        }
+    };
+    let last_key = if complete {
+        None
+    } else {
+        match entries.last() {
+            Some(entry) => Some(entry.key.as_slice()),
+            None => {
+                return Err(CertificationError::InvalidDeltas(
+                    "an incomplete snapshot page has no entries".to_string(),
+                ))
+            }
+        }
+    };
+
+    let mut revealed = vec![];
+    collect_keys(keys, &mut revealed)?;
+
+    // All the keys of the page's range must be revealed: there must be no
+    // pruned subtree, which could hide keys, between the last revealed key
+    // before the range and the last revealed key of the range (or the end of
+    // the tree if the page is complete).
+    let first = revealed
+        .iter()
+        .rposition(|entry| matches!(entry, Some((key, _)) if *key <= start_after_key))
+        .map_or(0, |index| index + 1);
+    let end = match last_key {
+        Some(last_key) => revealed
+            .iter()
+            .rposition(|entry| matches!(entry, Some((key, _)) if *key <= last_key))
+            .map_or(0, |index| index + 1),
+        None => revealed.len(),
+    };
+    if end < first {
+        return Err(CertificationError::InvalidDeltas(
+            "the snapshot entries are outside of the requested key range".to_string(),
+        ));
+    }
+    if revealed[first..end].iter().any(Option::is_none) {
+        return Err(CertificationError::MalformedHashTree(
+            "the hash tree does not reveal all the keys of the snapshot page".to_string(),
+        ));
+    }
+
+    let mut entries = entries.iter();
+    for (key, versions) in revealed[first..end].iter().flatten() {
+        let (change_version, leaf) = match change_at_or_before(versions, version)? {
+            // Deleted keys are not part of the snapshot.
+            Some((change_version, leaf)) if !leaf.is_empty() => (change_version, leaf),
+            _ => continue,
+        };
+        let entry = entries
+            .next()
+            .filter(|entry| entry.key == *key)
+            .ok_or_else(|| {
+                CertificationError::InvalidDeltas(format!(
+                    "the snapshot does not contain the key {:?}",
+                    key
+                ))
+            })?;
+        match entry.values.as_slice() {
+            [value]
+                if value.version == change_version
+                    && !value.deletion_marker
+                    && Sha256::hash(&value.value)[..] == leaf[..] => {}
+            _ => {
+                return Err(CertificationError::InvalidDeltas(format!(
+                    "the value of the key {:?} does not match its certified hash",
+                    key
+                )))
+            }
+        }
+    }
+    if let Some(entry) = entries.next() {
+        return Err(CertificationError::InvalidDeltas(format!(
+            "the key {:?} is not present in the snapshot",
+            entry.key
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns the current version certified by a hash tree of the registry.
+fn certified_current_version(hash_tree: &MixedHashTree) -> Result<u64, CertificationError> {
+    let labeled_tree = LabeledTree::<Vec<u8>>::try_from(hash_tree.clone()).map_err(|err| {
+        CertificationError::MalformedHashTree(format!(
+            "failed to convert hash tree to labeled tree: {:?}",
+            err
+        ))
+    })?;
+    Ok(
+        CertifiedPayload::deserialize(LabeledTreeDeserializer::new(&labeled_tree))
+            .map_err(|err| {
+                CertificationError::DeserError(format!(
+                    "failed to unpack certified payload from the labeled tree: {}",
+                    err
+                ))
+            })?
+            .current_version
+            .0,
+    )
+}
+
+/// Returns the latest change made at or before `version` in the revealed
+/// history of a key, as a pair of the version of the change and the
+/// corresponding leaf, or `None` if the key was not changed up to `version`.
+fn change_at_or_before(
+    versions: &MixedHashTree,
+    version: u64,
+) -> Result<Option<(u64, Vec<u8>)>, CertificationError> {
+    // The versions revealed in the history of the key, in ascending order, up
+    // to and including the first one that is newer than `version`. Pruned
+    // subtrees are represented as `None`.
+    let mut history = vec![];
+    collect_versions(versions, version, &mut history)?;
+
+    // The latest change at or before `version` is the last one revealed, and
+    // it must be followed by the first newer version (or nothing) without
+    // any pruned subtree in between, which could hide newer changes.
+    let change_index = history
+        .iter()
+        .rposition(|entry| matches!(entry, Some((v, _)) if *v <= version));
+    let next_index = change_index.map_or(0, |index| index + 1);
+    if history[next_index..].iter().any(Option::is_none) {
+        return Err(CertificationError::MalformedHashTree(
+            "the history of the key is not fully revealed up to the requested version".to_string(),
+        ));
+    }
+
+    Ok(change_index.and_then(|index| history[index].take()))
+}
+
+/// Collects the keys revealed in the (in-order traversal of the) "keys"
+/// subtree, together with their histories. Pruned subtrees are represented as
+/// `None`.
+fn collect_keys<'a>(
+    tree: &'a MixedHashTree,
+    keys: &mut Vec<Option<(&'a [u8], &'a MixedHashTree)>>,
+) -> Result<(), CertificationError> {
+    match tree {
+        MixedHashTree::Empty => {}
+        MixedHashTree::Fork(lr) => {
+            collect_keys(&lr.0, keys)?;
+            collect_keys(&lr.1, keys)?;
+        }
+        MixedHashTree::Labeled(label, subtree) => {
+            keys.push(Some((label.as_bytes(), subtree.as_ref())))
+        }
+        MixedHashTree::Leaf(_) => {
+            return Err(CertificationError::MalformedHashTree(
+                "unlabeled leaf among the keys of the registry".to_string(),
+            ))
+        }
+        MixedHashTree::Pruned(_) => keys.push(None),
+    }
+    Ok(())
+}
+
+/// Collects the versions revealed in the (in-order traversal of the) history of
+/// a key, stopping after the first version newer than `version`.
+fn collect_versions(
+    tree: &MixedHashTree,
+    version: u64,
+    history: &mut Vec<Option<(u64, Vec<u8>)>>,
+) -> Result<(), CertificationError> {
+    if matches!(history.last(), Some(Some((v, _))) if *v > version) {
+        return Ok(());
+    }
+    match tree {
+        MixedHashTree::Empty => {}
+        MixedHashTree::Fork(lr) => {
+            collect_versions(&lr.0, version, history)?;
+            collect_versions(&lr.1, version, history)?;
+        }
+        MixedHashTree::Labeled(label, subtree) => {
+            let v = <[u8; 8]>::try_from(label.as_bytes()).map_err(|_| {
+                CertificationError::MalformedHashTree(format!(
+                    "invalid version label {:?} in the history of a key",
+                    label
+                ))
+            })?;
+            match subtree.as_ref() {
+                MixedHashTree::Leaf(leaf) => {
+                    history.push(Some((u64::from_be_bytes(v), leaf.clone())))
+                }
+                _ => history.push(None),
+            }
+        }
+        MixedHashTree::Leaf(_) => {
+            return Err(CertificationError::MalformedHashTree(
+                "unlabeled leaf in the history of a key".to_string(),
+            ))
+        }
+        MixedHashTree::Pruned(_) => history.push(None),
+    }
+    Ok(())
+}
+
+/// Parses a response of the "get_certified_value" registry method, validates
+/// data integrity and authenticity and returns
+///   * The latest change made to `key` at or before `version` (`None` if the
+///     key was never set up to that version; a value with the deletion marker
+///     set if it was deleted).
+///   * The time when the received data was last certified by the subnet.
+pub fn decode_certified_value(
+    key: &[u8],
+    version: u64,
+    canister_id: &CanisterId,
+    nns_pk: &ThresholdSigPublicKey,
+    payload: &[u8],
+) -> Result<(Option<RegistryValue>, Time), CertificationError> {
+    let response = RegistryGetCertifiedValueResponse::decode(payload).map_err(|err| {
+        CertificationError::DeserError(format!(
+            "failed to decode certified value response from {}: {:?}",
+            canister_id, err
+        ))
+    })?;
+
+    let hash_tree = response.hash_tree.ok_or_else(|| {
+        CertificationError::MalformedHashTree(
+            "certified value response has an empty hash tree".to_string(),
+        )
+    })?;
+    let mixed_hash_tree = MixedHashTree::try_from(hash_tree).map_err(|err| {
+        CertificationError::DeserError(format!(
+            "failed to deserialize MixedHashTree from {}: {:?}",
+            canister_id, err
+        ))
+    })?;
+
+    let time = verify_certified_data(
+        &response.certificate[..],
+        canister_id,
+        nns_pk,
+        mixed_hash_tree.digest().as_bytes(),
+    )
+    .map_err(embed_certificate_error)?;
+
+    let value = decode_value_hash_tree(key, version, response.value, &mixed_hash_tree)?;
+
+    Ok((value, time))
+}
+
+/// Validates data integrity and authenticity of a page returned by the
+/// "get_snapshot" registry method for a request with the given `version` and
+/// `start_after_key`. Returns the time when the received data was last
+/// certified by the subnet.
+pub fn verify_certified_snapshot_page(
+    version: Option<u64>,
+    start_after_key: &[u8],
+    canister_id: &CanisterId,
+    nns_pk: &ThresholdSigPublicKey,
+    response: &RegistrySnapshotResponse,
+) -> Result<Time, CertificationError> {
+    let hash_tree = response.hash_tree.clone().ok_or_else(|| {
+        CertificationError::MalformedHashTree(
+            "snapshot response has an empty hash tree".to_string(),
+        )
+    })?;
+    let mixed_hash_tree = MixedHashTree::try_from(hash_tree).map_err(|err| {
+        CertificationError::DeserError(format!(
+            "failed to deserialize MixedHashTree from {}: {:?}",
+            canister_id, err
+        ))
+    })?;
+
+    let time = verify_certified_data(
+        &response.certificate[..],
+        canister_id,
+        nns_pk,
+        mixed_hash_tree.digest().as_bytes(),
+    )
+    .map_err(embed_certificate_error)?;
+
+    // The snapshot is taken at the requested version, or at the current
+    // version if none or a newer one was requested.
+    let current_version = certified_current_version(&mixed_hash_tree)?;
+    let expected_version = version.map_or(current_version, |v| v.min(current_version));
+    if response.version != expected_version {
+        return Err(CertificationError::InvalidDeltas(format!(
+            "expected a snapshot at version {}, got version {}",
+            expected_version, response.version
+        )));
+    }
+
+    decode_snapshot_hash_tree(
+        response.version,
+        start_after_key,
+        &response.entries,
+        response.complete,
+        &mixed_hash_tree,
+    )?;
+
+    Ok(time)
+}
+
 /// An auxiliary type that instructs serde to deserialize blob as a protobuf
 /// message.
 struct Protobuf<T>(T);
@@ -1,13 +1,19 @@
-use super::{decode_certified_deltas, CertificationError};
+use super::{
+    decode_certified_deltas, decode_snapshot_hash_tree, decode_value_hash_tree, CertificationError,
+};
 use ic_certification_test_utils::{CertificateBuilder, CertificateData};
+use ic_crypto_sha2::Sha256;
 use ic_crypto_tree_hash::{
     flatmap, Digest, FlatMap, HashTreeBuilder, HashTreeBuilderImpl, Label, LabeledTree,
-    WitnessGenerator,
+    MixedHashTree, WitnessGenerator,
 };
 use ic_interfaces_registry::RegistryTransportRecord;
 use ic_registry_transport::{
     delete,
-    pb::v1::{CertifiedResponse, RegistryAtomicMutateRequest, RegistryMutation},
+    pb::v1::{
+        CertifiedResponse, RegistryAtomicMutateRequest, RegistryDelta, RegistryMutation,
+        RegistryValue,
+    },
     upsert,
 };
 use ic_types::{
@@ -117,6 +123,136 @@ fn make_certified_delta(
     (cid, pk, encoded_response)
 }
 
+/// Builds the hash tree of a registry at `current_version` in which `key` was
+/// changed at the versions in `history` (to the given value, or deleted if
+/// `None`), revealing only the changes at the versions in `revealed`.
+fn make_value_tree(
+    current_version: u64,
+    key: &[u8],
+    history: &[(u64, Option<&[u8]>)],
+    revealed: &[u64],
+) -> MixedHashTree {
+    let leaf = |value: &Option<&[u8]>| match value {
+        Some(value) => Sha256::hash(value).to_vec(),
+        None => vec![],
+    };
+
+    let mut encoded_version = vec![];
+    leb128::write::unsigned(&mut encoded_version, current_version).unwrap();
+
+    let mut b = HashTreeBuilderImpl::new();
+    b.start_subtree();
+    b.new_edge(Label::from("current_version"));
+    b.start_leaf();
+    b.write_leaf(&encoded_version[..]);
+    b.finish_leaf();
+    b.new_edge(Label::from("keys"));
+    b.start_subtree();
+    b.new_edge(Label::from(key));
+    b.start_subtree();
+    let mut versions: FlatMap<Label, LabeledTree<Vec<u8>>> = FlatMap::new();
+    for (version, value) in history {
+        let label = Label::from(version.to_be_bytes());
+        b.new_edge(label.clone());
+        b.start_leaf();
+        b.write_leaf(&leaf(value)[..]);
+        b.finish_leaf();
+        if revealed.contains(version) {
+            versions
+                .try_append(label, LabeledTree::Leaf(leaf(value)))
+                .unwrap();
+        }
+    }
+    b.finish_subtree();
+    b.finish_subtree();
+    b.finish_subtree();
+
+    let data_tree = LabeledTree::SubTree(flatmap!(
+        Label::from("current_version") => LabeledTree::Leaf(encoded_version),
+        Label::from("keys") => LabeledTree::SubTree(flatmap!(
+            Label::from(key) => LabeledTree::SubTree(versions),
+        )),
+    ));
+    b.witness_generator()
+        .unwrap()
+        .mixed_hash_tree(&data_tree)
+        .unwrap()
+}
+
+/// Builds a hash tree of a registry with the given `keys` (in ascending order)
+/// and their histories, revealing the complete histories of the `revealed`
+/// keys and pruning the other ones.
+fn make_snapshot_tree(
+    current_version: u64,
+    keys: &[(&str, &[(u64, Option<&[u8]>)])],
+    revealed: &[&str],
+) -> MixedHashTree {
+    let leaf = |value: &Option<&[u8]>| match value {
+        Some(value) => Sha256::hash(value).to_vec(),
+        None => vec![],
+    };
+
+    let mut encoded_version = vec![];
+    leb128::write::unsigned(&mut encoded_version, current_version).unwrap();
+
+    let mut b = HashTreeBuilderImpl::new();
+    b.start_subtree();
+    b.new_edge(Label::from("current_version"));
+    b.start_leaf();
+    b.write_leaf(&encoded_version[..]);
+    b.finish_leaf();
+    b.new_edge(Label::from("keys"));
+    b.start_subtree();
+    let mut revealed_keys: FlatMap<Label, LabeledTree<Vec<u8>>> = FlatMap::new();
+    for (key, history) in keys {
+        b.new_edge(Label::from(*key));
+        b.start_subtree();
+        let mut versions: FlatMap<Label, LabeledTree<Vec<u8>>> = FlatMap::new();
+        for (version, value) in history.iter() {
+            let label = Label::from(version.to_be_bytes());
+            b.new_edge(label.clone());
+            b.start_leaf();
+            b.write_leaf(&leaf(value)[..]);
+            b.finish_leaf();
+            versions
+                .try_append(label, LabeledTree::Leaf(leaf(value)))
+                .unwrap();
+        }
+        b.finish_subtree();
+        if revealed.contains(key) {
+            revealed_keys
+                .try_append(Label::from(*key), LabeledTree::SubTree(versions))
+                .unwrap();
+        }
+    }
+    b.finish_subtree();
+    b.finish_subtree();
+
+    let data_tree = LabeledTree::SubTree(flatmap!(
+        Label::from("current_version") => LabeledTree::Leaf(encoded_version),
+        Label::from("keys") => LabeledTree::SubTree(revealed_keys),
+    ));
+    b.witness_generator()
+        .unwrap()
+        .mixed_hash_tree(&data_tree)
+        .unwrap()
+}
+
+fn snapshot_entry(key: &str, version: u64, value: &[u8]) -> RegistryDelta {
+    RegistryDelta {
+        key: key.as_bytes().to_vec(),
+        values: vec![value_at(version, value)],
+    }
+}
+
+fn value_at(version: u64, value: &[u8]) -> RegistryValue {
+    RegistryValue {
+        value: value.to_vec(),
+        version,
+        deletion_marker: false,
+    }
+}
+
 fn set_key(version: u64, k: impl ToString, v: impl AsRef<[u8]>) -> RegistryTransportRecord {
     RegistryTransportRecord {
         version: RegistryVersion::from(version),
@@ -310,3 +446,165 @@ fn test_decode_empty_prefix() {
         other => panic!("Expected InvalidDeltas error, got {:?}", other),
     }
 }
+
+#[test]
+fn test_decode_value() {
+    let history = [(1, Some(&b"a"[..])), (3, Some(&b"b"[..])), (5, None)];
+    let tree = make_value_tree(5, b"key", &history, &[3, 5]);
+
+    assert_eq!(
+        decode_value_hash_tree(b"key", 4, Some(value_at(3, b"b")), &tree).unwrap(),
+        Some(value_at(3, b"b"))
+    );
+    let tree = make_value_tree(5, b"key", &history, &[1]);
+    assert_eq!(
+        decode_value_hash_tree(b"key", 0, None, &tree).unwrap(),
+        None
+    );
+    let tree = make_value_tree(5, b"key", &history, &[5]);
+    let deleted = RegistryValue {
+        value: vec![],
+        version: 5,
+        deletion_marker: true,
+    };
+    assert_eq!(
+        decode_value_hash_tree(b"key", 5, Some(deleted.clone()), &tree).unwrap(),
+        Some(deleted)
+    );
+}
+
+#[test]
+fn test_decode_value_with_hidden_newer_change() {
+    // The change at version 5 is pruned, so the tree does not prove that the
+    // change at version 3 is still in effect at version 5.
+    let history = [
+        (1, Some(&b"a"[..])),
+        (3, Some(&b"b"[..])),
+        (5, Some(&b"c"[..])),
+    ];
+    let tree = make_value_tree(5, b"key", &history, &[3]);
+
+    assert!(matches!(
+        decode_value_hash_tree(b"key", 5, Some(value_at(3, b"b")), &tree),
+        Err(CertificationError::MalformedHashTree(_))
+    ));
+}
+
+#[test]
+fn test_decode_value_mismatch() {
+    let history = [
+        (1, Some(&b"a"[..])),
+        (3, Some(&b"b"[..])),
+        (5, Some(&b"c"[..])),
+    ];
+    let tree = make_value_tree(5, b"key", &history, &[1, 3, 5]);
+
+    // Wrong value.
+    assert!(matches!(
+        decode_value_hash_tree(b"key", 4, Some(value_at(3, b"c")), &tree),
+        Err(CertificationError::InvalidDeltas(_))
+    ));
+    // Outdated value.
+    assert!(matches!(
+        decode_value_hash_tree(b"key", 4, Some(value_at(1, b"a")), &tree),
+        Err(CertificationError::InvalidDeltas(_))
+    ));
+    // Missing value.
+    assert!(matches!(
+        decode_value_hash_tree(b"key", 4, None, &tree),
+        Err(CertificationError::InvalidDeltas(_))
+    ));
+    // Version newer than the current version.
+    assert!(matches!(
+        decode_value_hash_tree(b"key", 6, Some(value_at(5, b"c")), &tree),
+        Err(CertificationError::InvalidDeltas(_))
+    ));
+}
+
+const SNAPSHOT_KEYS: [(&str, &[(u64, Option<&[u8]>)]); 4] = [
+    ("a", &[(1, Some(b"a1")), (4, Some(b"a4"))]),
+    ("b", &[(2, Some(b"b2")), (3, None)]),
+    ("c", &[(3, Some(b"c3"))]),
+    ("d", &[(5, Some(b"d5"))]),
+];
+
+#[test]
+fn test_decode_snapshot() {
+    let tree = make_snapshot_tree(5, &SNAPSHOT_KEYS, &["a", "b", "c", "d"]);
+    let entries = [snapshot_entry("a", 4, b"a4"), snapshot_entry("c", 3, b"c3")];
+
+    // The deleted key "b" and the key "d" created later are not part of the
+    // snapshot at version 4.
+    assert!(decode_snapshot_hash_tree(4, b"", &entries, true, &tree).is_ok());
+    // An incomplete page only needs to reveal the keys up to its last entry.
+    let tree = make_snapshot_tree(5, &SNAPSHOT_KEYS, &["a", "b"]);
+    assert!(decode_snapshot_hash_tree(4, b"", &entries[..1], false, &tree).is_ok());
+    // A page starting after a key does not need to reveal the preceding keys.
+    let tree = make_snapshot_tree(5, &SNAPSHOT_KEYS, &["b", "c", "d"]);
+    assert!(decode_snapshot_hash_tree(4, b"b", &entries[1..], true, &tree).is_ok());
+}
+
+#[test]
+fn test_decode_snapshot_with_pruned_key() {
+    // The key "c" is pruned, so the tree does not prove that the page is
+    // complete.
+    let tree = make_snapshot_tree(5, &SNAPSHOT_KEYS, &["a", "b", "d"]);
+    let entries = [snapshot_entry("a", 4, b"a4")];
+
+    assert!(matches!(
+        decode_snapshot_hash_tree(4, b"", &entries, true, &tree),
+        Err(CertificationError::MalformedHashTree(_))
+    ));
+}
+
+#[test]
+fn test_decode_snapshot_mismatch() {
+    let tree = make_snapshot_tree(5, &SNAPSHOT_KEYS, &["a", "b", "c", "d"]);
+
+    // Missing key.
+    assert!(matches!(
+        decode_snapshot_hash_tree(4, b"", &[snapshot_entry("c", 3, b"c3")], true, &tree),
+        Err(CertificationError::InvalidDeltas(_))
+    ));
+    // Wrong value.
+    assert!(matches!(
+        decode_snapshot_hash_tree(
+            4,
+            b"",
+            &[snapshot_entry("a", 4, b"a1"), snapshot_entry("c", 3, b"c3")],
+            true,
+            &tree
+        ),
+        Err(CertificationError::InvalidDeltas(_))
+    ));
+    // Deleted key.
+    assert!(matches!(
+        decode_snapshot_hash_tree(
+            4,
+            b"",
+            &[
+                snapshot_entry("a", 4, b"a4"),
+                snapshot_entry("b", 2, b"b2"),
+                snapshot_entry("c", 3, b"c3")
+            ],
+            true,
+            &tree
+        ),
+        Err(CertificationError::InvalidDeltas(_))
+    ));
+    // Version newer than the current version.
+    assert!(matches!(
+        decode_snapshot_hash_tree(
+            6,
+            b"",
+            &[
+                snapshot_entry("a", 4, b"a4"),
+                snapshot_entry("c", 3, b"c3"),
+                snapshot_entry("d", 5, b"d5")
+            ],
+            true,
+            &tree
+        ),
+        Err(CertificationError::InvalidDeltas(_))
+    ));
+}
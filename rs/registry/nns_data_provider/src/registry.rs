@@ -1,4 +1,7 @@
-use ic_registry_transport::pb::v1::RegistryGetLatestVersionResponse;
+use ic_registry_transport::pb::v1::{
+    RegistryGetLatestVersionResponse, RegistryGetSnapshotRequest, RegistrySnapshotResponse,
+    RegistryValue,
+};
 use prost::Message;
 use rand::seq::SliceRandom;
 use std::time::Duration;
@@ -192,6 +195,104 @@ impl RegistryCanister {
         }
     }
 
+    /// Obtains the latest change made to 'key' at or before 'version' using a
+    /// certified endpoint, together with the time at which the response was
+    /// certified. Returns `None` if the key was never set up to 'version',
+    /// and a value with the deletion marker set if it was deleted.
+    pub async fn get_certified_value(
+        &self,
+        key: Vec<u8>,
+        version: u64,
+        nns_public_key: &ThresholdSigPublicKey,
+    ) -> Result<(Option<RegistryValue>, Time), Error> {
+        let payload = serialize_get_value_request(key.clone(), Some(version)).unwrap();
+        let response = self
+            .choose_random_agent()
+            .execute_query(&self.canister_id, "get_certified_value", payload)
+            .await
+            .map_err(|err| {
+                Error::UnknownError(format!(
+                    "Failed to query get_certified_value on canister {}: {}",
+                    self.canister_id, err,
+                ))
+            })?
+            .ok_or_else(|| {
+                Error::UnknownError(format!(
+                    "No response was received when queried get_certified_value on {}",
+                    self.canister_id,
+                ))
+            })?;
+
+        crate::certification::decode_certified_value(
+            &key,
+            version,
+            &self.canister_id,
+            nns_public_key,
+            &response[..],
+        )
+        .map_err(|err| Error::UnknownError(format!("{:?}", err)))
+    }
+
+    /// Obtains the contents of the registry at 'version_opt' (or at the latest
+    /// version if it is None), i.e. the latest value of every key present at
+    /// that version, without the history of changes. Returns the deltas, each
+    /// holding a single value, and the version of the snapshot.
+    ///
+    /// Every page of the snapshot is verified against the certificate issued
+    /// by the NNS subnet.
+    pub async fn get_snapshot(
+        &self,
+        version_opt: Option<u64>,
+        nns_public_key: &ThresholdSigPublicKey,
+    ) -> Result<(Vec<RegistryDelta>, u64), Error> {
+        let agent = self.choose_random_agent();
+        let mut entries: Vec<RegistryDelta> = vec![];
+        let mut version = version_opt;
+        loop {
+            let start_after_key = entries
+                .last()
+                .map(|delta| delta.key.clone())
+                .unwrap_or_default();
+            let request = RegistryGetSnapshotRequest {
+                version,
+                start_after_key: start_after_key.clone(),
+            };
+            let response = agent
+                .execute_query(&self.canister_id, "get_snapshot", request.encode_to_vec())
+                .await
+                .map_err(|err| {
+                    Error::UnknownError(format!(
+                        "Error on registry_get_snapshot: {} using agent {:?}",
+                        err, &agent
+                    ))
+                })?
+                .ok_or_else(|| {
+                    Error::UnknownError(
+                        "No response was received from registry_get_snapshot.".to_string(),
+                    )
+                })?;
+            let response = RegistrySnapshotResponse::decode(response.as_slice())
+                .map_err(|err| Error::MalformedMessage(err.to_string()))?;
+            if let Some(error) = response.error {
+                return Err(Error::from(error));
+            }
+            crate::certification::verify_certified_snapshot_page(
+                version,
+                &start_after_key,
+                &self.canister_id,
+                nns_public_key,
+                &response,
+            )
+            .map_err(|err| Error::UnknownError(format!("{:?}", err)))?;
+            // Pin the version, so that all the pages belong to the same snapshot.
+            version = Some(response.version);
+            entries.extend(response.entries);
+            if response.complete {
+                return Ok((entries, response.version));
+            }
+        }
+    }
+
     /// Applies 'mutations' to the registry.
     pub async fn atomic_mutate(
         &self,
@@ -15,6 +15,7 @@ use ic_registry_local_store::{
     changelog_to_compact_delta, KeyMutation, LocalStoreImpl, LocalStoreWriter,
 };
 use normalization::NormalizedSnapshot;
+pub use protobuf::raw_data_to_value;
 use serde_json::Value;
 use snapshot::Snapshot;
 use std::{fs::File, io::Write, path::PathBuf};
@@ -57,7 +57,9 @@ pub(crate) struct Transformers {
     pub s: fn(Value) -> Vec<u8>,
 }
 
-pub(crate) fn raw_data_to_value(key: &str, data: &[u8]) -> Value {
+/// Decodes the protobuf encoded value stored under `key` in the registry into
+/// a self-describing JSON value.
+pub fn raw_data_to_value(key: &str, data: &[u8]) -> Value {
     (get_transformer(key).d)(data)
 }
 
@@ -284,3 +284,72 @@ pub struct CertifiedResponse {
     #[prost(bytes = "vec", tag = "2")]
     pub certificate: ::prost::alloc::vec::Vec<u8>,
 }
+/// Message corresponding to the response from the canister to a
+/// get_certified_value() request, whose argument is a RegistryGetValueRequest.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegistryGetCertifiedValueResponse {
+    /// The latest change made to the requested key at or before the requested
+    /// version. Not set if the key was never mutated up to that version. If the
+    /// latest change is a deletion, 'deletion_marker' is set.
+    #[prost(message, optional, tag = "1")]
+    pub value: ::core::option::Option<RegistryValue>,
+    /// The hash tree proving that 'value' is the latest change made to the key
+    /// up to the requested version. It reveals the "keys" subtree of the
+    /// certified registry state for the requested key and version range.
+    #[prost(message, optional, tag = "2")]
+    pub hash_tree: ::core::option::Option<::ic_protobuf::messaging::xnet::v1::MixedHashTree>,
+    /// The certificate obtained from the system using
+    /// ic0.data_certificate_copy.
+    #[prost(bytes = "vec", tag = "3")]
+    pub certificate: ::prost::alloc::vec::Vec<u8>,
+}
+/// Message to retrieve a compacted snapshot of the registry, i.e., the value of
+/// every key that is present at a given version, without the history of changes.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegistryGetSnapshotRequest {
+    /// The version of the snapshot.
+    /// Optional: If not set, or newer than the latest version, the snapshot is
+    /// taken at the latest version.
+    #[prost(message, optional, tag = "1")]
+    pub version: ::core::option::Option<u64>,
+    /// Only keys strictly greater than this key are returned. This is used to
+    /// page through a snapshot that does not fit in a single response, by setting
+    /// it to the last key of the previous response.
+    #[prost(bytes = "vec", tag = "2")]
+    pub start_after_key: ::prost::alloc::vec::Vec<u8>,
+}
+/// Message corresponding to the response from the canister
+/// to a get_snapshot() request.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegistrySnapshotResponse {
+    /// If anything went wrong, the registry canister
+    /// will set this error.
+    #[prost(message, optional, tag = "1")]
+    pub error: ::core::option::Option<RegistryError>,
+    /// The version of the snapshot.
+    #[prost(uint64, tag = "2")]
+    pub version: u64,
+    /// The keys that are present at 'version', in ascending order, each with a
+    /// single value: the latest one at or before 'version'.
+    #[prost(message, repeated, tag = "3")]
+    pub entries: ::prost::alloc::vec::Vec<RegistryDelta>,
+    /// Whether 'entries' contains the last key of the snapshot. If not, the
+    /// remaining entries can be retrieved by setting 'start_after_key' to the
+    /// last key in 'entries'.
+    #[prost(bool, tag = "4")]
+    pub complete: bool,
+    /// The hash tree proving that 'entries' are exactly the keys present at
+    /// 'version' that are greater than the requested 'start_after_key', up to
+    /// the last key in 'entries' (or all of them if 'complete' is set), with
+    /// their values at 'version'. It reveals the "keys" subtree of the certified
+    /// registry state for that key range.
+    #[prost(message, optional, tag = "5")]
+    pub hash_tree: ::core::option::Option<::ic_protobuf::messaging::xnet::v1::MixedHashTree>,
+    /// The certificate obtained from the system using
+    /// ic0.data_certificate_copy.
+    #[prost(bytes = "vec", tag = "6")]
+    pub certificate: ::prost::alloc::vec::Vec<u8>,
+}
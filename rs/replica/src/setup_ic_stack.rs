@@ -173,13 +173,19 @@ pub fn construct_ic_stack(
         subnet_config.cycles_account_manager_config,
     ));
 
+    let mut hypervisor_config = config.hypervisor.clone();
+    // Keep compiled canister modules next to the replicated state, so that they
+    // survive replica restarts.
+    hypervisor_config
+        .compilation_cache_dir
+        .get_or_insert_with(|| config.state_manager.state_root().join("compilation_cache"));
     let execution_services = ExecutionServices::setup_execution(
         log.clone(),
         metrics_registry,
         subnet_id,
         subnet_type,
         subnet_config.scheduler_config,
-        config.hypervisor.clone(),
+        hypervisor_config,
         cycles_account_manager.clone(),
         state_manager.clone(),
         state_manager.get_fd_factory(),
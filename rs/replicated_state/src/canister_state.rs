@@ -9,6 +9,7 @@ use crate::canister_state::system_state::{CanisterStatus, ExecutionTask, SystemS
 use crate::{InputQueueType, StateError};
 pub use execution_state::{EmbedderCache, ExecutionState, ExportedFunctions, Global};
 use ic_ic00_types::CanisterStatusType;
+use ic_protobuf::state::canister_state_bits::v1 as pb;
 use ic_registry_subnet_type::SubnetType;
 use ic_types::batch::TotalQueryStats;
 use ic_types::methods::SystemMethod;
@@ -82,6 +83,93 @@ pub struct SchedulerState {
     /// At the end of an "epoch", each node deterministically aggregates all those partial
     /// query statistics received from consensus blocks and mutates these values.
     pub total_query_stats: TotalQueryStats,
+
+    /// The time since which the canister has input messages waiting for
+    /// execution, if any. It is set at the beginning of a round and cleared
+    /// when the canister gets executed.
+    pub input_wait_start: Option<Time>,
+
+    /// The round since which the canister has responses waiting for
+    /// execution, if any. The scheduler prioritizes canisters whose responses
+    /// have waited for too many rounds.
+    pub response_wait_start_round: Option<ExecutionRound>,
+
+    /// Statistics about how long the input messages of the canister wait and
+    /// how often the canister gets scheduled.
+    pub queue_latency_stats: QueueLatencyStats,
+}
+
+/// Statistics about the queueing delay and the scheduling share of a canister,
+/// accumulated since the canister has been created.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueueLatencyStats {
+    /// The number of times the canister got executed after its input messages
+    /// had been waiting since the beginning of a round.
+    pub num_waits: u64,
+
+    /// The total time the input messages waited over all `num_waits` waits.
+    pub total_wait_nanos: u64,
+
+    /// The longest time the input messages waited.
+    pub max_wait_nanos: u64,
+
+    /// The number of rounds that the canister began with input messages.
+    pub rounds_with_input: u64,
+
+    /// The number of rounds with input messages in which the canister got
+    /// executed.
+    pub rounds_executed: u64,
+}
+
+impl QueueLatencyStats {
+    /// Returns the average time the input messages waited for execution.
+    pub fn average_wait(&self) -> Duration {
+        match self.num_waits {
+            0 => Duration::ZERO,
+            n => Duration::from_nanos(self.total_wait_nanos / n),
+        }
+    }
+
+    /// Returns the share of the rounds with input messages in which the
+    /// canister got executed, in percent.
+    pub fn scheduling_share_percent(&self) -> u64 {
+        match self.rounds_with_input {
+            0 => 100,
+            n => self.rounds_executed.saturating_mul(100) / n,
+        }
+    }
+
+    fn observe_wait(&mut self, wait: Duration) {
+        let wait_nanos = wait.as_nanos() as u64;
+        self.num_waits += 1;
+        self.total_wait_nanos = self.total_wait_nanos.saturating_add(wait_nanos);
+        self.max_wait_nanos = self.max_wait_nanos.max(wait_nanos);
+        self.rounds_executed += 1;
+    }
+}
+
+impl From<&QueueLatencyStats> for pb::QueueLatencyStats {
+    fn from(item: &QueueLatencyStats) -> Self {
+        Self {
+            num_waits: item.num_waits,
+            total_wait_nanos: item.total_wait_nanos,
+            max_wait_nanos: item.max_wait_nanos,
+            rounds_with_input: item.rounds_with_input,
+            rounds_executed: item.rounds_executed,
+        }
+    }
+}
+
+impl From<pb::QueueLatencyStats> for QueueLatencyStats {
+    fn from(item: pb::QueueLatencyStats) -> Self {
+        Self {
+            num_waits: item.num_waits,
+            total_wait_nanos: item.total_wait_nanos,
+            max_wait_nanos: item.max_wait_nanos,
+            rounds_with_input: item.rounds_with_input,
+            rounds_executed: item.rounds_executed,
+        }
+    }
 }
 
 impl Default for SchedulerState {
@@ -96,6 +184,9 @@ impl Default for SchedulerState {
             install_code_debit: 0.into(),
             time_of_last_allocation_charge: UNIX_EPOCH,
             total_query_stats: TotalQueryStats::default(),
+            input_wait_start: None,
+            response_wait_start_round: None,
+            queue_latency_stats: QueueLatencyStats::default(),
         }
     }
 }
@@ -150,6 +241,56 @@ impl CanisterState {
         self.system_state.canister_id()
     }
 
+    /// Starts tracking the wait of the input messages of the canister at the
+    /// beginning of a round. A wait that is already being tracked continues.
+    pub fn start_input_wait(&mut self, time: Time, round: ExecutionRound) {
+        let has_responses = self.system_state.queues().input_queues_response_count() > 0;
+        let scheduler_state = &mut self.scheduler_state;
+        if !self.system_state.has_input() {
+            scheduler_state.input_wait_start = None;
+            scheduler_state.response_wait_start_round = None;
+            return;
+        }
+        scheduler_state.queue_latency_stats.rounds_with_input += 1;
+        scheduler_state.input_wait_start.get_or_insert(time);
+        if has_responses {
+            scheduler_state
+                .response_wait_start_round
+                .get_or_insert(round);
+        } else {
+            scheduler_state.response_wait_start_round = None;
+        }
+    }
+
+    /// Ends the wait of the input messages of the canister after it got
+    /// executed and returns how long they waited, if a wait was tracked.
+    ///
+    /// The wait of the responses only ends once no responses are left in the
+    /// input queues, so that responses the canister did not get to in this
+    /// round keep counting towards `max_response_wait_rounds`.
+    pub fn end_input_wait(&mut self, time: Time) -> Option<Duration> {
+        let has_responses = self.system_state.queues().input_queues_response_count() > 0;
+        let scheduler_state = &mut self.scheduler_state;
+        if !has_responses {
+            scheduler_state.response_wait_start_round = None;
+        }
+        let start = scheduler_state.input_wait_start.take()?;
+        let wait = Duration::from_nanos(
+            time.as_nanos_since_unix_epoch()
+                .saturating_sub(start.as_nanos_since_unix_epoch()),
+        );
+        scheduler_state.queue_latency_stats.observe_wait(wait);
+        Some(wait)
+    }
+
+    /// Returns the number of rounds for which the responses of the canister
+    /// have been waiting for execution.
+    pub fn response_wait_rounds(&self, current_round: ExecutionRound) -> u64 {
+        self.scheduler_state
+            .response_wait_start_round
+            .map_or(0, |start| current_round.get().saturating_sub(start.get()))
+    }
+
     pub fn controllers(&self) -> &BTreeSet<PrincipalId> {
         &self.system_state.controllers
     }
@@ -24,7 +24,9 @@ use ic_types::{
         Request, RequestOrResponse, Response, StopCanisterContext,
     },
     nominal_cycles::NominalCycles,
-    CanisterId, CanisterTimer, Cycles, MemoryAllocation, NumBytes, PrincipalId, Time,
+    time::UNIX_EPOCH,
+    CanisterId, CanisterTimer, Cycles, MemoryAllocation, NumBytes, NumInstructions, PrincipalId,
+    Time,
 };
 use lazy_static::lazy_static;
 use maplit::btreeset;
@@ -35,7 +37,7 @@ use std::{
     convert::{TryFrom, TryInto},
 };
 use std::{collections::BTreeSet, sync::Arc};
-use std::{collections::VecDeque, str::FromStr};
+use std::{collections::VecDeque, str::FromStr, time::Duration};
 
 lazy_static! {
     static ref DEFAULT_PRINCIPAL_MULTIPLE_CONTROLLERS: PrincipalId =
@@ -178,15 +180,23 @@ pub fn compute_total_canister_change_size(changes: &VecDeque<Arc<CanisterChange>
 /// with the oldest canister changes at lowest indices.
 /// The system can drop the oldest canister changes from the list to keep its length bounded
 /// (with `20` latest canister changes to always remain in the list).
+/// Audit changes (settings changes and cycles deposits and withdrawals) are
+/// bounded separately per kind and are only returned by `canister_history`.
 /// The system also drops all canister changes if the canister runs out of cycles.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct CanisterHistory {
     /// The canister changes stored in the order from the oldest to the most recent.
     changes: Arc<VecDeque<Arc<CanisterChange>>>,
+    /// The sequence numbers of the `changes`, in the same order. The sequence
+    /// number of a change is the number of changes of all kinds recorded
+    /// before it, so it stays the same when older changes are dropped.
+    sequence_numbers: Arc<VecDeque<u64>>,
     /// The `total_num_changes` records the total number of canister changes
-    /// that have ever been recorded. In particular, if the system drops some canister changes,
-    /// `total_num_changes` does not decrease.
+    /// other than audit changes that have ever been recorded. In particular,
+    /// if the system drops some canister changes, `total_num_changes` does not decrease.
     total_num_changes: u64,
+    /// The total number of audit changes that have ever been recorded.
+    total_num_audit_changes: u64,
     /// Sum over `c.count_bytes()` for all canister changes `c`.
     /// We pre-compute and store the sum in a field to optimize the running time
     /// of computing the sum as the canister history memory usage is requested frequently.
@@ -194,10 +204,29 @@ pub struct CanisterHistory {
 }
 
 impl CanisterHistory {
+    /// Creates a history from the given canister changes, ordered from the
+    /// oldest to the most recent one, and the total number of changes that
+    /// have ever been recorded. Used when a canister moves to another subnet.
+    pub fn new(changes: Vec<CanisterChange>, total_num_changes: u64) -> Self {
+        let mut history = Self::default();
+        for change in changes {
+            if change.details().is_audit_change() {
+                // The audit changes have already been bounded by the
+                // retention limits of the source subnet.
+                history.add_audit_change(change, u64::MAX);
+            } else {
+                history.add_canister_change(change);
+            }
+        }
+        history.total_num_changes = history.total_num_changes.max(total_num_changes);
+        history
+    }
+
     /// Clears all canister changes and their memory usage,
     /// but keeps the total number of changes recorded.
     pub fn clear(&mut self) {
         self.changes = Arc::new(Default::default());
+        self.sequence_numbers = Arc::new(Default::default());
         self.canister_history_memory_usage = NumBytes::from(0);
 
         debug_assert_eq!(
@@ -211,18 +240,52 @@ impl CanisterHistory {
     /// of canister changes does not exceed `MAX_CANISTER_HISTORY_CHANGES`
     /// by dropping the oldest entry if necessary.
     pub fn add_canister_change(&mut self, canister_change: CanisterChange) {
+        debug_assert!(!canister_change.details().is_audit_change());
+        self.push_change(canister_change, MAX_CANISTER_HISTORY_CHANGES);
+        self.total_num_changes += 1;
+    }
+
+    /// Adds an audit change to the history, updating the memory usage and
+    /// total number of audit changes. It drops the oldest changes of the same
+    /// kind to retain at most `max_retained` of them.
+    pub fn add_audit_change(&mut self, canister_change: CanisterChange, max_retained: u64) {
+        debug_assert!(canister_change.details().is_audit_change());
+        self.push_change(canister_change, max_retained);
+        self.total_num_audit_changes += 1;
+    }
+
+    fn push_change(&mut self, canister_change: CanisterChange, max_retained: u64) {
+        // Audit changes are bounded per kind, all other changes together.
+        let same_kind = |change: &Arc<CanisterChange>| {
+            let (a, b) = (change.details(), canister_change.details());
+            match (a.is_audit_change(), b.is_audit_change()) {
+                (false, false) => true,
+                (true, true) => std::mem::discriminant(a) == std::mem::discriminant(b),
+                _ => false,
+            }
+        };
+        let sequence_number = self.total_num_changes + self.total_num_audit_changes;
         let changes = Arc::make_mut(&mut self.changes);
-        if changes.len() >= MAX_CANISTER_HISTORY_CHANGES as usize {
-            let change_size = changes
-                .pop_front()
-                .as_ref()
-                .map(|c| c.count_bytes())
-                .unwrap_or_default();
-            self.canister_history_memory_usage -= change_size;
+        let sequence_numbers = Arc::make_mut(&mut self.sequence_numbers);
+        let mut num_retained = changes.iter().filter(|&c| same_kind(c)).count() as u64;
+        while num_retained > 0 && num_retained >= max_retained {
+            if let Some(index) = changes.iter().position(&same_kind) {
+                let change_size = changes
+                    .remove(index)
+                    .as_ref()
+                    .map(|c| c.count_bytes())
+                    .unwrap_or_default();
+                sequence_numbers.remove(index);
+                self.canister_history_memory_usage -= change_size;
+            }
+            num_retained -= 1;
         }
-        self.canister_history_memory_usage += canister_change.count_bytes();
-        changes.push_back(Arc::new(canister_change));
-        self.total_num_changes += 1;
+        if max_retained > 0 {
+            self.canister_history_memory_usage += canister_change.count_bytes();
+            changes.push_back(Arc::new(canister_change));
+            sequence_numbers.push_back(sequence_number);
+        }
+        debug_assert_eq!(self.changes.len(), self.sequence_numbers.len());
 
         debug_assert_eq!(
             self.get_memory_usage(),
@@ -231,27 +294,259 @@ impl CanisterHistory {
     }
 
     /// Returns an iterator over the requested number of most recent canister changes
-    /// or, if more changes are requested than available in the history,
-    /// an iterator over all canister changes.
+    /// other than audit changes or, if more changes are requested than available in
+    /// the history, an iterator over all such canister changes.
     /// The changes are iterated in chronological order, i.e., from the oldest to the most recent.
     pub fn get_changes(
         &self,
         num_requested_changes: usize,
     ) -> impl Iterator<Item = &Arc<CanisterChange>> {
-        let num_all_changes = self.changes.len();
+        let is_info_change = |change: &&Arc<CanisterChange>| !change.details().is_audit_change();
+        let num_all_changes = self.changes.iter().filter(is_info_change).count();
         let num_changes = num_requested_changes.min(num_all_changes);
-        self.changes.range((num_all_changes - num_changes)..)
+        self.changes
+            .iter()
+            .filter(is_info_change)
+            .skip(num_all_changes - num_changes)
+    }
+
+    /// Returns an iterator over all retained canister changes including the
+    /// audit changes in chronological order.
+    pub fn get_all_changes(&self) -> impl Iterator<Item = &Arc<CanisterChange>> {
+        self.changes.iter()
+    }
+
+    /// Returns the retained canister changes including the audit changes whose
+    /// sequence number is at least `start`, together with their sequence
+    /// numbers, in chronological order.
+    pub fn get_changes_since(
+        &self,
+        start: u64,
+    ) -> impl Iterator<Item = (u64, &Arc<CanisterChange>)> {
+        self.sequence_numbers
+            .iter()
+            .copied()
+            .zip(self.changes.iter())
+            .skip_while(move |(sequence_number, _)| *sequence_number < start)
+    }
+
+    /// Returns the number of retained canister changes including the audit changes.
+    pub fn get_num_retained_changes(&self) -> usize {
+        self.changes.len()
     }
 
     pub fn get_total_num_changes(&self) -> u64 {
         self.total_num_changes
     }
 
+    pub fn get_total_num_audit_changes(&self) -> u64 {
+        self.total_num_audit_changes
+    }
+
     pub fn get_memory_usage(&self) -> NumBytes {
         self.canister_history_memory_usage
     }
 }
 
+/// Settings for automatically topping up a canister once its balance drops
+/// below `threshold`, either from a funding canister on the same subnet or
+/// from an account on the cycles ledger.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AutoTopUp {
+    /// The canister from which the cycles are withdrawn. The cycles ledger
+    /// if `funding_account` is set.
+    pub funding_canister: CanisterId,
+    /// The balance below which the canister gets topped up.
+    pub threshold: Cycles,
+    /// The number of cycles transferred per top-up.
+    pub amount: Cycles,
+    /// The account on the cycles ledger from which the cycles are withdrawn,
+    /// if any.
+    pub funding_account: Option<CyclesLedgerAccount>,
+}
+
+/// An account on the cycles ledger.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CyclesLedgerAccount {
+    pub owner: PrincipalId,
+    pub subaccount: Option<[u8; 32]>,
+}
+
+impl From<&AutoTopUp> for pb::AutoTopUp {
+    fn from(item: &AutoTopUp) -> Self {
+        Self {
+            funding_canister: Some(item.funding_canister.into()),
+            threshold: Some(item.threshold.into()),
+            amount: Some(item.amount.into()),
+            funding_account: item.funding_account.map(|account| pb::CyclesLedgerAccount {
+                owner: Some(account.owner.into()),
+                subaccount: account.subaccount.map(|subaccount| subaccount.to_vec()),
+            }),
+        }
+    }
+}
+
+impl TryFrom<pb::AutoTopUp> for AutoTopUp {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb::AutoTopUp) -> Result<Self, Self::Error> {
+        Ok(Self {
+            funding_canister: try_from_option_field(
+                value.funding_canister,
+                "AutoTopUp::funding_canister",
+            )?,
+            threshold: try_from_option_field(value.threshold, "AutoTopUp::threshold")?,
+            amount: try_from_option_field(value.amount, "AutoTopUp::amount")?,
+            funding_account: value
+                .funding_account
+                .map(|account| -> Result<_, ProxyDecodeError> {
+                    Ok(CyclesLedgerAccount {
+                        owner: try_from_option_field(account.owner, "CyclesLedgerAccount::owner")?,
+                        subaccount: account
+                            .subaccount
+                            .map(|subaccount| {
+                                <[u8; 32]>::try_from(subaccount.as_slice()).map_err(|_| {
+                                    ProxyDecodeError::Other(format!(
+                                        "Expected a 32-byte subaccount, got {} bytes",
+                                        subaccount.len()
+                                    ))
+                                })
+                            })
+                            .transpose()?,
+                    })
+                })
+                .transpose()?,
+        })
+    }
+}
+
+/// The progress of an `install_code` execution that is sliced across rounds
+/// with deterministic time slicing and has not finished yet.
+///
+/// A paused execution cannot be persisted, so it is aborted at a checkpoint
+/// and restarted from scratch afterwards. The progress survives the restart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InstallCodeProgress {
+    /// The time when the execution started.
+    pub start_time: Time,
+    /// The instructions executed and not yet charged. The instructions of an
+    /// aborted execution are charged when it restarts.
+    pub executed_instructions: NumInstructions,
+    /// The number of times the execution was aborted and restarted.
+    pub num_restarts: u64,
+}
+
+impl InstallCodeProgress {
+    pub fn new(start_time: Time) -> Self {
+        Self {
+            start_time,
+            executed_instructions: NumInstructions::from(0),
+            num_restarts: 0,
+        }
+    }
+}
+
+impl From<&InstallCodeProgress> for pb::InstallCodeProgress {
+    fn from(item: &InstallCodeProgress) -> Self {
+        Self {
+            start_time_nanos: item.start_time.as_nanos_since_unix_epoch(),
+            executed_instructions: item.executed_instructions.get(),
+            num_restarts: item.num_restarts,
+        }
+    }
+}
+
+impl From<pb::InstallCodeProgress> for InstallCodeProgress {
+    fn from(value: pb::InstallCodeProgress) -> Self {
+        Self {
+            start_time: Time::from_nanos_since_unix_epoch(value.start_time_nanos),
+            executed_instructions: NumInstructions::from(value.executed_instructions),
+            num_restarts: value.num_restarts,
+        }
+    }
+}
+
+/// Execution statistics of a canister method aggregated over its calls.
+///
+/// The executions of response and cleanup callbacks are attributed to the
+/// method that made the outgoing call.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MethodStats {
+    /// The number of executions of the method, excluding callbacks.
+    pub num_calls: u64,
+    /// The number of executions of the method or its callbacks that trapped.
+    pub num_traps: u64,
+    /// The instructions executed by the method and its callbacks.
+    pub instructions_executed: NumInstructions,
+    /// The memory pages dirtied by the method and its callbacks.
+    pub dirty_pages: u64,
+    /// The number of calls of the method that were responded to.
+    pub num_responses: u64,
+    /// The total time from the start of the execution of a call to its
+    /// response, summed over all responded calls.
+    pub total_latency: Duration,
+}
+
+impl MethodStats {
+    /// Records a finished execution of the method or one of its callbacks.
+    pub fn observe_execution(
+        &mut self,
+        instructions_executed: NumInstructions,
+        dirty_pages: u64,
+        trapped: bool,
+    ) {
+        self.instructions_executed += instructions_executed;
+        self.dirty_pages = self.dirty_pages.saturating_add(dirty_pages);
+        if trapped {
+            self.num_traps += 1;
+        }
+    }
+
+    /// Records that a call of the method was responded to after `latency`.
+    pub fn observe_response(&mut self, latency: Duration) {
+        self.num_responses += 1;
+        self.total_latency = self.total_latency.saturating_add(latency);
+    }
+
+    /// Returns the average time from the start of the execution of a call to
+    /// its response.
+    pub fn average_latency(&self) -> Duration {
+        match self.num_responses {
+            0 => Duration::ZERO,
+            n => Duration::from_nanos((self.total_latency.as_nanos() / n as u128) as u64),
+        }
+    }
+}
+
+/// Converts the statistics without the method name, which is the key of the
+/// statistics in `SystemState::method_stats`.
+impl From<&MethodStats> for pb::MethodStats {
+    fn from(item: &MethodStats) -> Self {
+        Self {
+            method_name: String::new(),
+            num_calls: item.num_calls,
+            num_traps: item.num_traps,
+            instructions_executed: item.instructions_executed.get(),
+            dirty_pages: item.dirty_pages,
+            num_responses: item.num_responses,
+            total_latency_nanos: item.total_latency.as_nanos() as u64,
+        }
+    }
+}
+
+impl From<pb::MethodStats> for MethodStats {
+    fn from(value: pb::MethodStats) -> Self {
+        Self {
+            num_calls: value.num_calls,
+            num_traps: value.num_traps,
+            instructions_executed: NumInstructions::from(value.instructions_executed),
+            dirty_pages: value.dirty_pages,
+            num_responses: value.num_responses,
+            total_latency: Duration::from_nanos(value.total_latency_nanos),
+        }
+    }
+}
+
 /// State that is controlled and owned by the system (IC).
 ///
 /// Contains structs needed for running and maintaining the canister on the IC.
@@ -331,6 +626,39 @@ pub struct SystemState {
 
     /// Store of Wasm chunks to support installation of large Wasm modules.
     pub wasm_chunk_store: WasmChunkStore,
+
+    /// The maximum age of a query cache entry of this canister that may be
+    /// served after the batch time or the canister balance have changed.
+    /// Zero means that such entries are never served.
+    pub query_cache_max_staleness: NumSeconds,
+
+    /// Settings for automatic top-ups from a funding canister, if any.
+    pub auto_top_up: Option<AutoTopUp>,
+
+    /// The canisters that may automatically top themselves up with cycles
+    /// of this canister. Set by the controllers of this canister.
+    pub auto_top_up_beneficiaries: BTreeSet<CanisterId>,
+
+    /// The time of the last automatic top-up attempt. Used to rate limit
+    /// the top-ups of a canister.
+    pub time_of_last_auto_top_up: Time,
+
+    /// Whether `install_code` messages of the canister run with the extended
+    /// instruction limit.
+    pub extended_install_code_limit: bool,
+
+    /// The progress of the pending `install_code` execution, if any.
+    pub install_code_progress: Option<InstallCodeProgress>,
+
+    /// Execution statistics of the methods of the canister, keyed by method
+    /// name. Only contains methods that were executed since the last reset.
+    pub method_stats: BTreeMap<String, MethodStats>,
+
+    /// Whether the canister is being migrated to another subnet. Set once
+    /// the state of the stopped canister is first read for the migration;
+    /// management calls that could change the canister are rejected while
+    /// it is set.
+    pub migrating: bool,
 }
 
 /// A wrapper around the different canister statuses.
@@ -624,6 +952,8 @@ impl From<&CanisterHistory> for pb::CanisterHistory {
                 .map(|e| (&(**e)).into())
                 .collect::<Vec<pb::CanisterChange>>(),
             total_num_changes: item.total_num_changes,
+            total_num_audit_changes: item.total_num_audit_changes,
+            change_sequence_numbers: item.sequence_numbers.iter().copied().collect(),
         }
     }
 }
@@ -638,9 +968,20 @@ impl TryFrom<pb::CanisterHistory> for CanisterHistory {
             .map(|e| Ok(Arc::new(e.try_into()?)))
             .collect::<Result<VecDeque<_>, Self::Error>>()?;
         let canister_history_memory_usage = compute_total_canister_change_size(&changes);
+        let sequence_numbers = if value.change_sequence_numbers.len() == changes.len() {
+            value.change_sequence_numbers.into_iter().collect()
+        } else {
+            // Histories written before sequence numbers were recorded: number
+            // the retained changes consecutively, ending at the latest one.
+            let total = value.total_num_changes + value.total_num_audit_changes;
+            let first = total.saturating_sub(changes.len() as u64);
+            (first..first + changes.len() as u64).collect()
+        };
         Ok(Self {
             changes: Arc::new(changes),
+            sequence_numbers: Arc::new(sequence_numbers),
             total_num_changes: value.total_num_changes,
+            total_num_audit_changes: value.total_num_audit_changes,
             canister_history_memory_usage,
         })
     }
@@ -702,6 +1043,14 @@ impl SystemState {
             canister_version: 0,
             canister_history: CanisterHistory::default(),
             wasm_chunk_store,
+            query_cache_max_staleness: NumSeconds::from(0),
+            auto_top_up: None,
+            auto_top_up_beneficiaries: BTreeSet::new(),
+            time_of_last_auto_top_up: UNIX_EPOCH,
+            extended_install_code_limit: false,
+            install_code_progress: None,
+            method_stats: BTreeMap::new(),
+            migrating: false,
         }
     }
 
@@ -745,6 +1094,14 @@ impl SystemState {
         canister_history: CanisterHistory,
         wasm_chunk_store_data: PageMap,
         wasm_chunk_store_metadata: WasmChunkStoreMetadata,
+        query_cache_max_staleness: NumSeconds,
+        auto_top_up: Option<AutoTopUp>,
+        auto_top_up_beneficiaries: BTreeSet<CanisterId>,
+        time_of_last_auto_top_up: Time,
+        extended_install_code_limit: bool,
+        install_code_progress: Option<InstallCodeProgress>,
+        method_stats: BTreeMap<String, MethodStats>,
+        migrating: bool,
     ) -> Self {
         Self {
             controllers,
@@ -767,6 +1124,14 @@ impl SystemState {
                 wasm_chunk_store_data,
                 wasm_chunk_store_metadata,
             ),
+            query_cache_max_staleness,
+            auto_top_up,
+            auto_top_up_beneficiaries,
+            time_of_last_auto_top_up,
+            extended_install_code_limit,
+            install_code_progress,
+            method_stats,
+            migrating,
         }
     }
 
@@ -1445,9 +1810,35 @@ impl SystemState {
         self.canister_history.add_canister_change(new_change);
     }
 
+    /// Adds an audit change (a settings change or a cycles deposit or
+    /// withdrawal) to canister history, retaining at most `max_retained`
+    /// changes of the same kind.
+    pub fn add_audit_change(
+        &mut self,
+        timestamp_nanos: Time,
+        change_origin: CanisterChangeOrigin,
+        change_details: CanisterChangeDetails,
+        max_retained: u64,
+    ) {
+        let new_change = CanisterChange::new(
+            timestamp_nanos.as_nanos_since_unix_epoch(),
+            self.canister_version,
+            change_origin,
+            change_details,
+        );
+        self.canister_history
+            .add_audit_change(new_change, max_retained);
+    }
+
     pub fn get_canister_history(&self) -> &CanisterHistory {
         &self.canister_history
     }
+
+    /// Replaces the canister history, e.g. with the history of a canister
+    /// migrated from another subnet.
+    pub fn set_canister_history(&mut self, canister_history: CanisterHistory) {
+        self.canister_history = canister_history;
+    }
 }
 
 /// Implements memory limits verification for pushing a canister-to-canister
@@ -48,6 +48,13 @@ pub struct CallContext {
     /// The total number of instructions executed in the given call context.
     /// This value is used for the `ic0.performance_counter` type 1.
     instructions_executed: NumInstructions,
+
+    /// The name of the method that created the `CallContext`. Used to
+    /// attribute the execution of callbacks to the method in the per-method
+    /// statistics of the canister. This field is only optional to accommodate
+    /// contexts that were created before this field was added and contexts of
+    /// queries.
+    method_name: Option<String>,
 }
 
 impl CallContext {
@@ -65,6 +72,7 @@ impl CallContext {
             available_cycles,
             time: Some(time),
             instructions_executed: NumInstructions::default(),
+            method_name: None,
         }
     }
 
@@ -122,6 +130,16 @@ impl CallContext {
     pub fn instructions_executed(&self) -> NumInstructions {
         self.instructions_executed
     }
+
+    /// The name of the method that created the call context, if known.
+    pub fn method_name(&self) -> Option<&str> {
+        self.method_name.as_deref()
+    }
+
+    /// Sets the name of the method that created the call context.
+    pub fn set_method_name(&mut self, method_name: String) {
+        self.method_name = Some(method_name);
+    }
 }
 
 impl From<&CallContext> for pb::CallContext {
@@ -134,6 +152,7 @@ impl From<&CallContext> for pb::CallContext {
             available_funds: Some((&funds).into()),
             time_nanos: item.time.map(|t| t.as_nanos_since_unix_epoch()),
             instructions_executed: item.instructions_executed.get(),
+            method_name: item.method_name.clone(),
         }
     }
 }
@@ -151,6 +170,7 @@ impl TryFrom<pb::CallContext> for CallContext {
             available_cycles: funds.cycles(),
             time: value.time_nanos.map(Time::from_nanos_since_unix_epoch),
             instructions_executed: value.instructions_executed.into(),
+            method_name: value.method_name,
         })
     }
 }
@@ -337,6 +357,7 @@ impl CallContextManager {
                 available_cycles: cycles,
                 time: Some(time),
                 instructions_executed: NumInstructions::default(),
+                method_name: None,
             },
         );
         id
@@ -551,6 +551,126 @@ fn update_balance_and_consumed_cycles_by_use_case_correctly() {
     );
 }
 
+#[test]
+fn canister_state_tracks_input_and_response_wait() {
+    let mut fixture = CanisterStateFixture::new();
+    let start = mock_time();
+
+    // Rounds without input are not tracked.
+    fixture
+        .canister_state
+        .start_input_wait(start, ExecutionRound::from(1));
+    assert_eq!(
+        fixture.canister_state.scheduler_state.input_wait_start,
+        None
+    );
+    assert_eq!(
+        fixture.canister_state.scheduler_state.queue_latency_stats,
+        QueueLatencyStats::default()
+    );
+
+    fixture
+        .push_input(
+            default_input_request(),
+            SubnetType::Application,
+            InputQueueType::RemoteSubnet,
+        )
+        .unwrap();
+    fixture
+        .canister_state
+        .start_input_wait(start, ExecutionRound::from(2));
+    assert_eq!(
+        fixture.canister_state.scheduler_state.input_wait_start,
+        Some(start)
+    );
+    assert_eq!(
+        fixture
+            .canister_state
+            .response_wait_rounds(ExecutionRound::from(5)),
+        0
+    );
+
+    // A response arriving later does not restart the wait of the request, but
+    // starts the wait of the responses.
+    fixture.with_input_reservation();
+    let response = default_input_response(fixture.make_callback());
+    fixture
+        .push_input(
+            response,
+            SubnetType::Application,
+            InputQueueType::RemoteSubnet,
+        )
+        .unwrap();
+    fixture
+        .canister_state
+        .start_input_wait(start + Duration::from_secs(1), ExecutionRound::from(3));
+    assert_eq!(
+        fixture.canister_state.scheduler_state.input_wait_start,
+        Some(start)
+    );
+    assert_eq!(
+        fixture
+            .canister_state
+            .response_wait_rounds(ExecutionRound::from(5)),
+        2
+    );
+
+    assert_eq!(
+        fixture
+            .canister_state
+            .end_input_wait(start + Duration::from_secs(3)),
+        Some(Duration::from_secs(3))
+    );
+    // The response is still queued, so its wait continues.
+    assert_eq!(
+        fixture
+            .canister_state
+            .response_wait_rounds(ExecutionRound::from(5)),
+        2
+    );
+
+    // The wait of the responses ends once they have all been consumed.
+    while fixture.canister_state.pop_input().is_some() {}
+    assert_eq!(
+        fixture
+            .canister_state
+            .end_input_wait(start + Duration::from_secs(3)),
+        None
+    );
+    assert_eq!(
+        fixture
+            .canister_state
+            .response_wait_rounds(ExecutionRound::from(5)),
+        0
+    );
+    assert_eq!(
+        fixture.canister_state.scheduler_state.queue_latency_stats,
+        QueueLatencyStats {
+            num_waits: 1,
+            total_wait_nanos: 3_000_000_000,
+            max_wait_nanos: 3_000_000_000,
+            rounds_with_input: 2,
+            rounds_executed: 1,
+        }
+    );
+    assert_eq!(
+        fixture
+            .canister_state
+            .scheduler_state
+            .queue_latency_stats
+            .scheduling_share_percent(),
+        50
+    );
+
+    // Nothing to observe without a tracked wait.
+    assert_eq!(
+        fixture
+            .canister_state
+            .end_input_wait(start + Duration::from_secs(4)),
+        None
+    );
+}
+
 #[test]
 fn canister_state_callback_round_trip() {
     use ic_protobuf::state::canister_state_bits::v1 as pb;
@@ -779,6 +899,122 @@ fn canister_history_operations() {
     }
 }
 
+#[test]
+fn canister_history_retains_audit_changes_per_kind() {
+    let mut canister_history = CanisterHistory::default();
+    let origin = CanisterChangeOrigin::from_user(user_test_id(42).get());
+    let controllers_change = |i| {
+        CanisterChange::new(
+            42,
+            0,
+            origin.clone(),
+            CanisterChangeDetails::controllers_change(vec![canister_test_id(i).get()]),
+        )
+    };
+    let deposit = |amount| {
+        CanisterChange::new(
+            42,
+            0,
+            origin.clone(),
+            CanisterChangeDetails::cycles_deposit(amount),
+        )
+    };
+    let settings_change = |threshold| {
+        CanisterChange::new(
+            42,
+            0,
+            origin.clone(),
+            CanisterChangeDetails::settings_change(Some(threshold), None, None),
+        )
+    };
+
+    for i in 0..(MAX_CANISTER_HISTORY_CHANGES + 2) {
+        canister_history.add_canister_change(controllers_change(i));
+        canister_history.add_audit_change(deposit(i as u128), 3);
+    }
+    canister_history.add_audit_change(settings_change(1), 3);
+    // A retention limit of zero drops all changes of the kind and only counts
+    // the new change.
+    canister_history.add_audit_change(settings_change(2), 0);
+
+    assert_eq!(
+        canister_history.get_total_num_changes(),
+        MAX_CANISTER_HISTORY_CHANGES + 2
+    );
+    assert_eq!(
+        canister_history.get_total_num_audit_changes(),
+        MAX_CANISTER_HISTORY_CHANGES + 4
+    );
+    // Audit changes do not evict the other changes and are not returned by
+    // `get_changes`.
+    assert_eq!(
+        canister_history
+            .get_changes(usize::MAX)
+            .map(|c| (**c).clone())
+            .collect::<Vec<_>>(),
+        (2..(MAX_CANISTER_HISTORY_CHANGES + 2))
+            .map(controllers_change)
+            .collect::<Vec<_>>()
+    );
+    let audit_changes: Vec<_> = canister_history
+        .get_all_changes()
+        .filter(|c| c.details().is_audit_change())
+        .map(|c| (**c).clone())
+        .collect();
+    let max = MAX_CANISTER_HISTORY_CHANGES as u128;
+    assert_eq!(
+        audit_changes,
+        vec![deposit(max - 1), deposit(max), deposit(max + 1)]
+    );
+    assert_eq!(
+        canister_history.get_num_retained_changes(),
+        MAX_CANISTER_HISTORY_CHANGES as usize + 3
+    );
+}
+
+#[test]
+fn canister_history_sequence_numbers_survive_dropped_changes() {
+    use ic_protobuf::state::canister_state_bits::v1 as pb;
+
+    let mut canister_history = CanisterHistory::default();
+    let origin = CanisterChangeOrigin::from_user(user_test_id(42).get());
+    let deposit = |amount| {
+        CanisterChange::new(
+            42,
+            0,
+            origin.clone(),
+            CanisterChangeDetails::cycles_deposit(amount),
+        )
+    };
+    for amount in 0..3 {
+        canister_history.add_audit_change(deposit(amount), 2);
+    }
+    let sequence_numbers = |history: &CanisterHistory, start| {
+        history
+            .get_changes_since(start)
+            .map(|(sequence_number, _)| sequence_number)
+            .collect::<Vec<_>>()
+    };
+    // The first deposit is dropped, the remaining ones keep their numbers.
+    assert_eq!(sequence_numbers(&canister_history, 0), vec![1, 2]);
+
+    canister_history.add_audit_change(deposit(3), 2);
+    assert_eq!(sequence_numbers(&canister_history, 2), vec![2, 3]);
+    assert_eq!(sequence_numbers(&canister_history, 3), vec![3]);
+
+    let round_trip =
+        CanisterHistory::try_from(pb::CanisterHistory::from(&canister_history)).unwrap();
+    assert_eq!(round_trip, canister_history);
+
+    // Histories without recorded sequence numbers are numbered consecutively.
+    let legacy = pb::CanisterHistory {
+        change_sequence_numbers: vec![],
+        ..pb::CanisterHistory::from(&canister_history)
+    };
+    let legacy = CanisterHistory::try_from(legacy).unwrap();
+    assert_eq!(sequence_numbers(&legacy, 0), vec![2, 3]);
+}
+
 #[test]
 fn drops_aborted_canister_install_after_split() {
     let mut canister_state = CanisterStateFixture::new().canister_state;
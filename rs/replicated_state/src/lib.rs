@@ -43,11 +43,12 @@ pub use canister_state::{
     execution_state::Memory,
     num_bytes_try_from,
     system_state::{
-        memory_required_to_push_request, CallContext, CallContextAction, CallContextManager,
-        CallOrigin, CanisterMetrics, CanisterStatus, ExecutionTask, SystemState,
+        memory_required_to_push_request, AutoTopUp, CallContext, CallContextAction,
+        CallContextManager, CallOrigin, CanisterMetrics, CanisterStatus, CyclesLedgerAccount,
+        ExecutionTask, InstallCodeProgress, MethodStats, SystemState,
     },
     CanisterQueues, CanisterState, EmbedderCache, ExecutionState, ExportedFunctions, Global,
-    NumWasmPages, SchedulerState,
+    NumWasmPages, QueueLatencyStats, SchedulerState,
 };
 pub use metadata_state::{
     IngressHistoryState, NetworkTopology, Stream, SubnetTopology, SystemMetadata,
@@ -41,7 +41,7 @@ use ic_types::{
     subnet_id_into_protobuf, subnet_id_try_from_protobuf,
     time::{Time, UNIX_EPOCH},
     xnet::{StreamHeader, StreamIndex, StreamIndexedQueue, StreamSlice},
-    CountBytes, CryptoHashOfPartialState, NodeId, NumBytes, PrincipalId, SubnetId,
+    CountBytes, CryptoHashOfPartialState, ExecutionRound, NodeId, NumBytes, PrincipalId, SubnetId,
 };
 use ic_wasm_types::WasmHash;
 use serde::{Deserialize, Serialize};
@@ -144,6 +144,10 @@ pub struct SystemMetadata {
     /// 2).
     pub heap_delta_estimate: NumBytes,
 
+    /// The last round whose state was checkpointed. Set by the scheduler at
+    /// the end of each checkpoint round.
+    pub last_checkpoint_round: ExecutionRound,
+
     pub subnet_metrics: SubnetMetrics,
 
     /// The set of WASM modules we expect to be present in the [`Hypervisor`]'s
@@ -551,6 +555,7 @@ impl From<&SystemMetadata> for pb_metadata::SystemMetadata {
             state_sync_version: item.state_sync_version as u32,
             certification_version: item.certification_version as u32,
             heap_delta_estimate: item.heap_delta_estimate.get(),
+            last_checkpoint_round: item.last_checkpoint_round.get(),
             own_subnet_features: Some(item.own_subnet_features.into()),
             subnet_metrics: Some((&item.subnet_metrics).into()),
             bitcoin_get_successors_follow_up_responses: item
@@ -678,6 +683,7 @@ impl TryFrom<(pb_metadata::SystemMetadata, &dyn CheckpointLoadingMetrics)> for S
             },
 
             heap_delta_estimate: NumBytes::from(item.heap_delta_estimate),
+            last_checkpoint_round: ExecutionRound::from(item.last_checkpoint_round),
             subnet_metrics: match item.subnet_metrics {
                 Some(subnet_metrics) => subnet_metrics.try_into()?,
                 None => SubnetMetrics::default(),
@@ -720,6 +726,7 @@ impl SystemMetadata {
             // hard-to-track bugs in state manager.
             certification_version: CertificationVersion::V0,
             heap_delta_estimate: NumBytes::from(0),
+            last_checkpoint_round: ExecutionRound::from(0),
             subnet_metrics: Default::default(),
             expected_compiled_wasms: BTreeSet::new(),
             bitcoin_get_successors_follow_up_responses: BTreeMap::default(),
@@ -988,6 +995,8 @@ impl SystemMetadata {
             // Set by `commit_and_certify()` at the end of the round. Not used before.
             certification_version: _,
             ref heap_delta_estimate,
+            // Only compared with the current round, which keeps increasing.
+            last_checkpoint_round: _,
             subnet_metrics: _,
             ref expected_compiled_wasms,
             bitcoin_get_successors_follow_up_responses: _,
@@ -2272,6 +2281,7 @@ pub(crate) mod testing {
             state_sync_version: CURRENT_STATE_SYNC_VERSION,
             certification_version: CertificationVersion::V0,
             heap_delta_estimate: Default::default(),
+            last_checkpoint_round: Default::default(),
             subnet_metrics: Default::default(),
             expected_compiled_wasms: Default::default(),
             bitcoin_get_successors_follow_up_responses: Default::default(),
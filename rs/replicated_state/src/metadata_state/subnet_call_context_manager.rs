@@ -237,6 +237,14 @@ impl SubnetCallContextManager {
         callback_id
     }
 
+    /// Allocates a callback ID for a request sent by the subnet that does not
+    /// track a context. The response to such a request is dropped.
+    pub fn next_callback_id(&mut self) -> CallbackId {
+        let callback_id = CallbackId::new(self.next_callback_id);
+        self.next_callback_id += 1;
+        callback_id
+    }
+
     pub fn retrieve_context(
         &mut self,
         callback_id: CallbackId,
@@ -17,7 +17,9 @@ use ic_registry_subnet_type::SubnetType;
 use ic_types::{
     batch::RawQueryStats,
     ingress::IngressStatus,
-    messages::{CallbackId, CanisterMessage, Ingress, MessageId, RequestOrResponse, Response},
+    messages::{
+        CallbackId, CanisterMessage, Ingress, MessageId, Request, RequestOrResponse, Response,
+    },
     xnet::QueueId,
     CanisterId, MemoryAllocation, NumBytes, SubnetId, Time,
 };
@@ -740,6 +742,16 @@ impl ReplicatedState {
         self.subnet_queues.push_output_response(msg)
     }
 
+    /// Pushes a `Request` sent by the subnet itself into the relevant subnet
+    /// output queue, reserving a slot for the response.
+    pub fn push_subnet_output_request(
+        &mut self,
+        msg: Arc<Request>,
+    ) -> Result<(), (StateError, Arc<Request>)> {
+        let time = self.time();
+        self.subnet_queues.push_output_request(msg, time)
+    }
+
     /// Returns a circular iterator that consumes messages from all canisters'
     /// and the subnet's output queues.
     ///
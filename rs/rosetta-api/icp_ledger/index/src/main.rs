@@ -12,22 +12,26 @@ use ic_icp_index::{
 use ic_icrc1_index_ng::GetAccountTransactionsArgs;
 use ic_ledger_core::block::{BlockType, EncodedBlock};
 use ic_stable_structures::memory_manager::{MemoryId, VirtualMemory};
+use ic_stable_structures::storable::Blob;
 use ic_stable_structures::{
     cell::Cell as StableCell, log::Log as StableLog, memory_manager::MemoryManager,
     DefaultMemoryImpl, Storable,
 };
 use ic_stable_structures::{BoundedStorable, StableBTreeMap};
 use icp_ledger::{
-    AccountIdentifier, ArchivedEncodedBlocksRange, Block, BlockIndex, GetBlocksArgs,
-    GetEncodedBlocksResult, Operation, QueryEncodedBlocksResponse, MAX_BLOCKS_PER_REQUEST,
+    AccountIdBlob, AccountIdentifier, ArchivedEncodedBlocksRange, Block, BlockIndex, GetBlocksArgs,
+    GetEncodedBlocksResult, Operation, QueryEncodedBlocksResponse,
+    MAX_ACCOUNT_IDENTIFIERS_PER_REQUEST, MAX_BLOCKS_PER_REQUEST,
 };
 use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
 use num_traits::cast::ToPrimitive;
 use scopeguard::{guard, ScopeGuard};
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::cmp::Reverse;
+use std::collections::BTreeSet;
 use std::time::Duration;
 
 /// The maximum number of blocks to return in a single [get_blocks] request.
@@ -39,6 +43,8 @@ const BLOCK_LOG_INDEX_MEMORY_ID: MemoryId = MemoryId::new(1);
 const BLOCK_LOG_DATA_MEMORY_ID: MemoryId = MemoryId::new(2);
 const ACCOUNTIDENTIFIER_BLOCK_IDS_MEMORY_ID: MemoryId = MemoryId::new(3);
 const ACCOUNTIDENTIFIER_DATA_MEMORY_ID: MemoryId = MemoryId::new(4);
+const ACCOUNTIDENTIFIER_TO_ACCOUNT_MEMORY_ID: MemoryId = MemoryId::new(5);
+const ALLOWANCES_MEMORY_ID: MemoryId = MemoryId::new(6);
 
 const DEFAULT_MAX_WAIT_TIME: Duration = Duration::from_secs(2);
 const DEFAULT_RETRY_WAIT_TIME: Duration = Duration::from_secs(1);
@@ -57,6 +63,17 @@ type AccountIdentifierBlockIdsMap = StableBTreeMap<AccountIdentifierBlockIdsMapK
 type AccountIdentifierDataMapKey = (AccountIdentifierDataType, [u8; 28]);
 type AccountIdentifierDataMap = StableBTreeMap<AccountIdentifierDataMapKey, u64, VM>;
 
+// The account is stored as its owner, of type Blob<29>, and its effective
+// subaccount.
+type AccountIdentifierToAccountMap = StableBTreeMap<[u8; 28], (Blob<29>, [u8; 32]), VM>;
+
+// The key is the pair (account_identifier, spender_account_identifier). The
+// value is the allowance in e8s and its expiration in nanoseconds since the
+// UNIX epoch, where NO_EXPIRATION stands for an allowance that never expires.
+type AllowancesMapKey = ([u8; 28], [u8; 28]);
+type AllowancesMap = StableBTreeMap<AllowancesMapKey, (u64, u64), VM>;
+const NO_EXPIRATION: u64 = u64::MAX;
+
 thread_local! {
     /// Static memory manager to manage the memory available for stable structures.
     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
@@ -82,6 +99,17 @@ thread_local! {
     static ACCOUNTIDENTIFIER_DATA: RefCell<AccountIdentifierDataMap> = with_memory_manager(|memory_manager| {
         RefCell::new(AccountIdentifierDataMap::init(memory_manager.get(ACCOUNTIDENTIFIER_DATA_MEMORY_ID)))
     });
+
+    /// Map that contains the accounts of the account_identifiers that the
+    /// ledger could resolve.
+    static ACCOUNTIDENTIFIER_TO_ACCOUNT: RefCell<AccountIdentifierToAccountMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(AccountIdentifierToAccountMap::init(memory_manager.get(ACCOUNTIDENTIFIER_TO_ACCOUNT_MEMORY_ID)))
+    });
+
+    /// Map that contains the allowances granted by approve blocks.
+    static ALLOWANCES: RefCell<AllowancesMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(AllowancesMap::init(memory_manager.get(ALLOWANCES_MEMORY_ID)))
+    });
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -227,6 +255,85 @@ fn balance_key(account_identifier: AccountIdentifier) -> (AccountIdentifierDataT
     (AccountIdentifierDataType::Balance, account_identifier.hash)
 }
 
+/// A helper function to access the account_identifier to account map.
+fn with_account_identifier_to_account<R>(
+    f: impl FnOnce(&mut AccountIdentifierToAccountMap) -> R,
+) -> R {
+    ACCOUNTIDENTIFIER_TO_ACCOUNT.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+/// A helper function to access the allowances.
+fn with_allowances<R>(f: impl FnOnce(&mut AllowancesMap) -> R) -> R {
+    ALLOWANCES.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+fn get_account(account_identifier: AccountIdentifier) -> Option<Account> {
+    with_account_identifier_to_account(|accounts| accounts.get(&account_identifier.hash)).map(
+        |(owner, subaccount)| Account {
+            owner: Principal::from_slice(owner.as_slice()),
+            subaccount: (subaccount != [0; 32]).then_some(subaccount),
+        },
+    )
+}
+
+fn record_account(account: Account) {
+    let owner = Blob::try_from(account.owner.as_slice())
+        .unwrap_or_else(|_| ic_cdk::trap("a principal cannot be longer than 29 bytes"));
+    let subaccount = *account.effective_subaccount();
+    with_account_identifier_to_account(|accounts| {
+        accounts.insert(AccountIdentifier::from(account).hash, (owner, subaccount))
+    });
+}
+
+/// A helper function to access the allowance that `spender` has on the
+/// funds of `account_identifier`. Expired allowances are reported as zero.
+fn get_allowance(account_identifier: AccountIdentifier, spender: AccountIdentifier) -> Allowance {
+    let allowance =
+        with_allowances(|allowances| allowances.get(&(account_identifier.hash, spender.hash)));
+    match allowance {
+        Some((amount, expires_at)) if expires_at > ic_cdk::api::time() => Allowance {
+            allowance: amount.into(),
+            expires_at: (expires_at != NO_EXPIRATION).then_some(expires_at),
+        },
+        _ => Allowance {
+            allowance: 0u64.into(),
+            expires_at: None,
+        },
+    }
+}
+
+/// A helper function to change an allowance.
+/// It removes the allowance if the amount is 0.
+fn set_allowance(
+    account_identifier: AccountIdentifier,
+    spender: AccountIdentifier,
+    amount: u64,
+    expires_at: u64,
+) {
+    let key = (account_identifier.hash, spender.hash);
+    with_allowances(|allowances| {
+        if amount == 0 {
+            allowances.remove(&key);
+        } else {
+            allowances.insert(key, (amount, expires_at));
+        }
+    });
+}
+
+/// A helper function to consume part of an allowance, as the ledger does for
+/// transfer_from and burn_from operations.
+fn use_allowance(account_identifier: AccountIdentifier, spender: AccountIdentifier, amount: u64) {
+    let key = (account_identifier.hash, spender.hash);
+    let (allowance, expires_at) =
+        with_allowances(|allowances| allowances.get(&key)).unwrap_or((0, NO_EXPIRATION));
+    set_allowance(
+        account_identifier,
+        spender,
+        allowance.saturating_sub(amount),
+        expires_at,
+    );
+}
+
 #[init]
 #[candid_method(init)]
 fn init(init_arg: InitArg) {
@@ -313,6 +420,7 @@ pub async fn build_index() -> Result<(), String> {
     let next_txid = with_blocks(|blocks| blocks.len());
     log!(P0, "[build_index]: next transaction id is {:?}", next_txid);
     let res = get_blocks_from_ledger(next_txid).await?;
+    let mut unresolved_account_identifiers = BTreeSet::new();
     log!(
         P0,
         "[build_index]: received {} blocks from ledger",
@@ -332,7 +440,7 @@ pub async fn build_index() -> Result<(), String> {
             next_archived_txid += candid_blocks.len() as u64;
             tx_indexed_count += candid_blocks.len();
             remaining -= candid_blocks.len() as u64;
-            append_blocks(candid_blocks)?;
+            unresolved_account_identifiers.append(&mut append_blocks(candid_blocks)?);
         }
     }
     log!(
@@ -342,7 +450,8 @@ pub async fn build_index() -> Result<(), String> {
     );
     tx_indexed_count += res.blocks.len();
     log!(P0, "[build_index]: received {} blocks", tx_indexed_count);
-    append_blocks(res.blocks)?;
+    unresolved_account_identifiers.append(&mut append_blocks(res.blocks)?);
+    sync_accounts(unresolved_account_identifiers).await;
     let wait_time = compute_wait_time(tx_indexed_count);
     log!(
         P1,
@@ -382,7 +491,44 @@ pub fn compute_wait_time(indexed_tx_count: usize) -> Duration {
     DEFAULT_MAX_WAIT_TIME * (100f64 * numerator) as u32 / 100
 }
 
-fn append_blocks(new_blocks: Vec<EncodedBlock>) -> Result<(), String> {
+/// Fetches from the ledger the accounts of the given account_identifiers, so
+/// that [account_identifier_to_account] can resolve them. The ledger cannot
+/// resolve every account_identifier, and failing to fetch accounts does not
+/// affect the rest of the index, so errors are only logged.
+async fn sync_accounts(account_identifiers: BTreeSet<AccountIdentifier>) {
+    let ledger_id = with_state(|state| state.ledger_id);
+    let account_identifiers: Vec<AccountIdentifier> = account_identifiers.into_iter().collect();
+    for chunk in account_identifiers.chunks(MAX_ACCOUNT_IDENTIFIERS_PER_REQUEST) {
+        let req: Vec<AccountIdBlob> = chunk.iter().map(|id| id.to_address()).collect();
+        let res: Result<(Vec<Option<Account>>,), _> =
+            ic_cdk::call(ledger_id, "account_identifiers_to_accounts", (req,)).await;
+        match res {
+            Ok((accounts,)) => {
+                for (account_identifier, account) in chunk.iter().zip(accounts) {
+                    if let Some(account) = account
+                        .filter(|account| AccountIdentifier::from(*account) == *account_identifier)
+                    {
+                        record_account(account);
+                    }
+                }
+            }
+            Err((code, msg)) => {
+                log!(
+                    P1,
+                    "[sync_accounts]: failed to resolve account identifiers: code: {:#?} message: {}",
+                    code,
+                    msg
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// Appends the blocks to the index and returns the account_identifiers
+/// involved in them that cannot be resolved to an account yet.
+fn append_blocks(new_blocks: Vec<EncodedBlock>) -> Result<BTreeSet<AccountIdentifier>, String> {
+    let mut unresolved_account_identifiers = BTreeSet::new();
     // the index of the next block that we
     // are going to append
     let mut block_index = with_blocks(|blocks| blocks.len());
@@ -397,21 +543,28 @@ fn append_blocks(new_blocks: Vec<EncodedBlock>) -> Result<(), String> {
         let decoded_block = decode_encoded_block(block_index, block)?;
 
         // add the block idx to the indices
+        let account_identifiers = get_account_identifiers(&decoded_block)?;
         with_account_identifier_block_ids(|account_identifier_block_ids| {
-            for account_identifier in get_account_identifiers(&decoded_block)? {
+            for account_identifier in &account_identifiers {
                 account_identifier_block_ids.insert(
-                    account_identifier_block_ids_key(account_identifier, block_index),
+                    account_identifier_block_ids_key(*account_identifier, block_index),
                     (),
                 );
             }
-            Ok::<(), String>(())
-        })?;
+        });
+        unresolved_account_identifiers.extend(
+            account_identifiers
+                .into_iter()
+                .filter(|account_identifier| get_account(*account_identifier).is_none()),
+        );
         // change the balance of the involved accounts
         process_balance_changes(block_index, &decoded_block)?;
+        // change the allowances of the involved accounts
+        process_allowance_changes(&decoded_block);
 
         block_index += 1;
     }
-    Ok(())
+    Ok(unresolved_account_identifiers)
 }
 
 fn process_balance_changes(block_index: BlockIndex, block: &Block) -> Result<(), String> {
@@ -433,6 +586,38 @@ fn process_balance_changes(block_index: BlockIndex, block: &Block) -> Result<(),
     Ok(())
 }
 
+fn process_allowance_changes(block: &Block) {
+    match block.transaction.operation {
+        Operation::Approve {
+            from,
+            spender,
+            allowance,
+            expires_at,
+            ..
+        } => set_allowance(
+            from,
+            spender,
+            allowance.get_e8s(),
+            expires_at.map_or(NO_EXPIRATION, |expires_at| {
+                expires_at.as_nanos_since_unix_epoch()
+            }),
+        ),
+        Operation::Transfer {
+            from,
+            spender: Some(spender),
+            amount,
+            fee,
+            ..
+        } if from != spender => use_allowance(from, spender, amount.get_e8s() + fee.get_e8s()),
+        Operation::Burn {
+            from,
+            spender: Some(spender),
+            amount,
+        } if from != spender => use_allowance(from, spender, amount.get_e8s()),
+        Operation::Burn { .. } | Operation::Mint { .. } | Operation::Transfer { .. } => (),
+    }
+}
+
 fn debit(block_index: BlockIndex, account_identifier: AccountIdentifier, amount: u64) {
     change_balance(account_identifier, |balance| {
         if balance < amount {
@@ -706,6 +891,28 @@ fn icrc1_balance_of(account: Account) -> u64 {
     get_balance(account.into())
 }
 
+/// Returns the account identifier of the account, in the format used by the
+/// other methods of the index.
+#[query]
+#[candid_method(query)]
+fn account_identifier(account: Account) -> AccountIdentifier {
+    account.into()
+}
+
+/// Returns the account with the given account identifier, if the ledger could
+/// resolve it when the index synced a block involving it.
+#[query]
+#[candid_method(query)]
+fn account_identifier_to_account(account_identifier: AccountIdentifier) -> Option<Account> {
+    get_account(account_identifier)
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc2_allowance(arg: AllowanceArgs) -> Allowance {
+    get_allowance(arg.account.into(), arg.spender.into())
+}
+
 #[query]
 #[candid_method(query)]
 fn status() -> Status {
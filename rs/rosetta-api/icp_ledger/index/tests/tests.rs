@@ -16,7 +16,9 @@ use icp_ledger::{
 use icp_ledger::{FeatureFlags, LedgerCanisterInitPayload, Memo, Operation, Transaction};
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc1::transfer::{BlockIndex, TransferArg, TransferError};
+use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
 use icrc_ledger_types::icrc3::blocks::GetBlocksRequest;
 use num_traits::cast::ToPrimitive;
 use serde_bytes::ByteBuf;
@@ -172,6 +174,36 @@ fn index_balance_of(env: &StateMachine, canister_id: CanisterId, account: Accoun
     accountidentifier_balance
 }
 
+fn icrc2_allowance(
+    env: &StateMachine,
+    canister_id: CanisterId,
+    account: Account,
+    spender: Account,
+) -> Allowance {
+    let req = Encode!(&AllowanceArgs { account, spender }).unwrap();
+    let res = env
+        .query(canister_id, "icrc2_allowance", req)
+        .expect("Failed to send icrc2_allowance")
+        .bytes();
+    Decode!(&res, Allowance).expect("Failed to decode icrc2_allowance response")
+}
+
+fn index_account_identifier_to_account(
+    env: &StateMachine,
+    index_id: CanisterId,
+    account_identifier: AccountIdentifier,
+) -> Option<Account> {
+    let res = env
+        .query(
+            index_id,
+            "account_identifier_to_account",
+            Encode!(&account_identifier).unwrap(),
+        )
+        .expect("Failed to send account_identifier_to_account")
+        .bytes();
+    Decode!(&res, Option<Account>).expect("Failed to decode account_identifier_to_account response")
+}
+
 fn status(env: &StateMachine, index_id: CanisterId) -> Status {
     let res = env
         .query(index_id, "status", Encode!(&()).unwrap())
@@ -988,6 +1020,89 @@ fn test_approve_args() {
     );
 }
 
+#[test]
+fn test_icrc2_allowance_and_account_identifier_to_account() {
+    let env = &StateMachine::new();
+    let ledger_id = install_ledger(
+        env,
+        vec![(
+            AccountIdentifier::from(account(1, 0)),
+            Tokens::from_e8s(10_000_000),
+        )]
+        .into_iter()
+        .collect(),
+        default_archive_options(),
+    );
+    let index_id = install_index(env, ledger_id);
+
+    approve(
+        env,
+        ledger_id,
+        account(1, 0),
+        ApproveTestArgs::new(account(1, 0), account(2, 0), 100_000),
+    );
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+    assert_eq!(
+        icrc2_allowance(env, ledger_id, account(1, 0), account(2, 0)),
+        icrc2_allowance(env, index_id, account(1, 0), account(2, 0))
+    );
+
+    // Using part of the allowance decreases it by the amount plus the fee.
+    let req = TransferFromArgs {
+        spender_subaccount: account(2, 0).subaccount,
+        from: account(1, 0),
+        to: account(3, 0),
+        amount: 40_000.into(),
+        fee: None,
+        memo: None,
+        created_at_time: None,
+    };
+    let res = env
+        .execute_ingress_as(
+            account(2, 0).owner.into(),
+            ledger_id,
+            "icrc2_transfer_from",
+            Encode!(&req).unwrap(),
+        )
+        .expect("Failed to transfer tokens")
+        .bytes();
+    Decode!(&res, Result<BlockIndex, TransferFromError>)
+        .unwrap()
+        .expect("Failed to transfer tokens");
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+    let allowance = icrc2_allowance(env, index_id, account(1, 0), account(2, 0));
+    assert_eq!(allowance.allowance, Nat::from(100_000 - 40_000 - FEE));
+    assert_eq!(
+        allowance,
+        icrc2_allowance(env, ledger_id, account(1, 0), account(2, 0))
+    );
+
+    // Approving zero removes the allowance.
+    approve(
+        env,
+        ledger_id,
+        account(1, 0),
+        ApproveTestArgs::new(account(1, 0), account(2, 0), 0),
+    );
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+    assert_eq!(
+        icrc2_allowance(env, index_id, account(1, 0), account(2, 0)).allowance,
+        Nat::from(0u64)
+    );
+
+    // The accounts involved in the blocks above are resolved via the ledger.
+    for account in [account(1, 0), account(2, 0), account(3, 0)] {
+        assert_eq!(
+            index_account_identifier_to_account(env, index_id, account.into()),
+            Some(account)
+        );
+    }
+    assert_eq!(
+        index_account_identifier_to_account(env, index_id, account(4, 0).into()),
+        None
+    );
+}
+
 #[test]
 fn test_post_upgrade_start_timer() {
     let env = &StateMachine::new();
@@ -10,6 +10,8 @@ use ic_ledger_core::{
 };
 use ic_ledger_core::{block::BlockIndex, tokens::Tokens};
 use ic_ledger_hash_of::HashOf;
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+use ic_stable_structures::{storable::Blob, DefaultMemoryImpl, Memory, StableBTreeMap};
 use icp_ledger::{
     AccountIdentifier, ApprovalKey, Block, FeatureFlags, LedgerBalances, Memo, Operation,
     PaymentError, Transaction, TransferError, TransferFee, UpgradeArgs, DEFAULT_TRANSFER_FEE,
@@ -19,7 +21,9 @@ use intmap::IntMap;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io;
 use std::sync::RwLock;
 use std::time::Duration;
 
@@ -34,6 +38,167 @@ lazy_static! {
     pub static ref MAX_MESSAGE_SIZE_BYTES: RwLock<usize> = RwLock::new(1024 * 1024);
 }
 
+/// Memory ids for stable structures used in the ledger canister.
+const UPGRADES_MEMORY_ID: MemoryId = MemoryId::new(0);
+const ACCOUNT_IDENTIFIER_TO_ACCOUNT_MEMORY_ID: MemoryId = MemoryId::new(1);
+
+/// The magic bytes at the beginning of the stable memory once it is managed by
+/// the memory manager. Before that, the stable memory starts with the length
+/// of the serialized ledger state.
+pub const MEMORY_MANAGER_MAGIC_BYTES: &[u8; 3] = b"MGR";
+
+const WASM_PAGE_SIZE_IN_BYTES: u64 = 64 * 1024;
+
+/// The maximum number of accounts that can be passed to a single
+/// `backfill_accounts` call.
+pub const MAX_ACCOUNTS_PER_BACKFILL: usize = 1_000;
+
+type VM = VirtualMemory<DefaultMemoryImpl>;
+
+// The account is stored as its owner, of type Blob<29>, and its effective
+// subaccount.
+type AccountIdentifierToAccountMap = StableBTreeMap<[u8; 28], (Blob<29>, [u8; 32]), VM>;
+
+thread_local! {
+    /// Static memory manager to manage the memory available for stable structures.
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
+
+    /// The memory where the ledger state is serialized during upgrades.
+    pub static UPGRADES_MEMORY: RefCell<VM> = MEMORY_MANAGER.with(|memory_manager|
+        RefCell::new(memory_manager.borrow().get(UPGRADES_MEMORY_ID)));
+
+    /// The ICRC-1 accounts that the ledger knows about, indexed by the hash of
+    /// their account identifier. Account identifiers are hashes, so the ledger
+    /// can only resolve identifiers of accounts it has been told about.
+    static ACCOUNT_IDENTIFIER_TO_ACCOUNT: RefCell<AccountIdentifierToAccountMap> = MEMORY_MANAGER.with(|memory_manager|
+        RefCell::new(AccountIdentifierToAccountMap::init(memory_manager.borrow().get(ACCOUNT_IDENTIFIER_TO_ACCOUNT_MEMORY_ID))));
+}
+
+/// Returns whether the stable memory is managed by the memory manager, i.e.,
+/// whether the ledger state was serialized into [UPGRADES_MEMORY]. This must
+/// be checked before any stable structure is accessed, as initializing the
+/// memory manager overwrites the beginning of the stable memory.
+pub fn is_stable_memory_managed() -> bool {
+    let memory = DefaultMemoryImpl::default();
+    if memory.size() == 0 {
+        return false;
+    }
+    let mut magic_bytes = [0u8; 3];
+    memory.read(0, &mut magic_bytes);
+    &magic_bytes == MEMORY_MANAGER_MAGIC_BYTES
+}
+
+/// Reads a stable memory sequentially, starting at offset 0.
+pub struct StableMemoryReader<'a, M: Memory> {
+    memory: &'a M,
+    offset: u64,
+}
+
+impl<'a, M: Memory> StableMemoryReader<'a, M> {
+    pub fn new(memory: &'a M) -> Self {
+        Self { memory, offset: 0 }
+    }
+}
+
+impl<'a, M: Memory> io::Read for StableMemoryReader<'a, M> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size_in_bytes = self.memory.size() * WASM_PAGE_SIZE_IN_BYTES;
+        let to_read = (buf.len() as u64).min(size_in_bytes.saturating_sub(self.offset)) as usize;
+        self.memory.read(self.offset, &mut buf[..to_read]);
+        self.offset += to_read as u64;
+        Ok(to_read)
+    }
+}
+
+/// Writes a stable memory sequentially, starting at offset 0, growing it as
+/// needed.
+pub struct StableMemoryWriter<'a, M: Memory> {
+    memory: &'a M,
+    offset: u64,
+}
+
+impl<'a, M: Memory> StableMemoryWriter<'a, M> {
+    pub fn new(memory: &'a M) -> Self {
+        Self { memory, offset: 0 }
+    }
+}
+
+impl<'a, M: Memory> io::Write for StableMemoryWriter<'a, M> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let end = self.offset + buf.len() as u64;
+        let size_in_bytes = self.memory.size() * WASM_PAGE_SIZE_IN_BYTES;
+        if end > size_in_bytes {
+            let additional_pages =
+                (end - size_in_bytes + WASM_PAGE_SIZE_IN_BYTES - 1) / WASM_PAGE_SIZE_IN_BYTES;
+            if self.memory.grow(additional_pages) < 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "Failed to grow stable memory",
+                ));
+            }
+        }
+        self.memory.write(self.offset, buf);
+        self.offset = end;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Remembers the account so that its account identifier can be resolved
+/// back to it.
+pub fn record_account(account: Account) {
+    let owner = Blob::try_from(account.owner.as_slice())
+        .expect("a principal cannot be longer than 29 bytes");
+    let subaccount = *account.effective_subaccount();
+    ACCOUNT_IDENTIFIER_TO_ACCOUNT.with(|cell| {
+        let mut map = cell.borrow_mut();
+        let key = AccountIdentifier::from(account).hash;
+        if !map.contains_key(&key) {
+            map.insert(key, (owner, subaccount));
+        }
+    });
+}
+
+/// Returns the ICRC-1 account with the specified account identifier, if the
+/// ledger knows about that account.
+pub fn account_for_identifier(account_identifier: &AccountIdentifier) -> Option<Account> {
+    ACCOUNT_IDENTIFIER_TO_ACCOUNT.with(|cell| {
+        cell.borrow()
+            .get(&account_identifier.hash)
+            .map(|(owner, subaccount)| Account {
+                owner: candid::Principal::from_slice(owner.as_slice()),
+                subaccount: (subaccount != [0; 32]).then_some(subaccount),
+            })
+    })
+}
+
+/// Returns the number of accounts whose account identifier can be resolved.
+pub fn known_accounts_len() -> u64 {
+    ACCOUNT_IDENTIFIER_TO_ACCOUNT.with(|cell| cell.borrow().len())
+}
+
+/// Records those of the given accounts whose account identifier holds a
+/// balance, so that accounts created before the ledger started recording
+/// accounts can be resolved too. Returns the number of newly recorded
+/// accounts.
+pub fn backfill_accounts(ledger: &Ledger, accounts: &[Account]) -> usize {
+    let mut recorded = 0;
+    for account in accounts {
+        let account_identifier = AccountIdentifier::from(*account);
+        if account_for_identifier(&account_identifier).is_some()
+            || ledger.balances.account_balance(&account_identifier) == Tokens::ZERO
+        {
+            continue;
+        }
+        record_account(*account);
+        recorded += 1;
+    }
+    recorded
+}
+
 // Wasm bytecode of an Archive Node.
 pub const ARCHIVE_NODE_BYTECODE: &[u8] =
     std::include_bytes!(std::env!("LEDGER_ARCHIVE_NODE_CANISTER_WASM_PATH"));
@@ -32,7 +32,7 @@ use icp_ledger::{
     IterBlocksArgs, LedgerCanisterPayload, Memo, Name, Operation, PaymentError,
     QueryBlocksResponse, QueryEncodedBlocksResponse, SendArgs, Subaccount, Symbol, TipOfChainRes,
     TotalSupplyArgs, Transaction, TransferArgs, TransferError, TransferFee, TransferFeeArgs,
-    MAX_BLOCKS_PER_REQUEST, MEMO_SIZE_BYTES,
+    MAX_ACCOUNT_IDENTIFIERS_PER_REQUEST, MAX_BLOCKS_PER_REQUEST, MEMO_SIZE_BYTES,
 };
 use icrc_ledger_types::icrc1::transfer::TransferArg;
 use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
@@ -44,7 +44,10 @@ use icrc_ledger_types::{
     icrc1::account::Account, icrc2::transfer_from::TransferFromArgs,
     icrc2::transfer_from::TransferFromError,
 };
-use ledger_canister::{Ledger, LEDGER, MAX_MESSAGE_SIZE_BYTES};
+use ledger_canister::{
+    is_stable_memory_managed, record_account, Ledger, StableMemoryReader, StableMemoryWriter,
+    LEDGER, MAX_ACCOUNTS_PER_BACKFILL, MAX_MESSAGE_SIZE_BYTES, UPGRADES_MEMORY,
+};
 use num_traits::cast::ToPrimitive;
 #[allow(unused_imports)]
 use on_wire::IntoWire;
@@ -117,6 +120,9 @@ fn init(
         maximum_number_of_accounts,
         accounts_overflow_trim_quantity,
     );
+    if let Some(icrc1_minting_account) = icrc1_minting_account {
+        record_account(icrc1_minting_account);
+    }
     match max_message_size_bytes {
         None => {
             print(format!(
@@ -239,6 +245,10 @@ async fn send(
         Err(PaymentError::TransferError(transfer_error)) => return Err(transfer_error),
         Err(PaymentError::Reject(msg)) => panic!("{}", msg),
     };
+    record_account(Account {
+        owner: caller_principal_id.into(),
+        subaccount: from_subaccount.map(|subaccount| subaccount.0),
+    });
     set_certified_data(&hash.into_bytes());
 
     // Don't put anything that could ever trap after this call or people using this
@@ -341,6 +351,11 @@ async fn icrc1_send(
             created_at_time,
         };
         let (block_index, hash) = apply_transaction(&mut *ledger, tx, now, effective_fee)?;
+        record_account(from_account);
+        record_account(to_account);
+        if let Some(spender_account) = spender_account {
+            record_account(spender_account);
+        }
 
         set_certified_data(&hash.into_bytes());
 
@@ -731,8 +746,27 @@ fn main() {
 
 fn post_upgrade(args: Option<LedgerCanisterPayload>) {
     let mut ledger = LEDGER.write().unwrap();
-    *ledger = ciborium::de::from_reader(stable::StableReader::new())
-        .expect("Decoding stable memory failed");
+    // Ledgers that were not yet upgraded to use stable structures store their
+    // state at the beginning of the stable memory. This has to be read before
+    // any stable structure is accessed, as that initializes the memory manager.
+    if is_stable_memory_managed() {
+        *ledger = UPGRADES_MEMORY.with(|memory| {
+            let memory = memory.borrow();
+            ciborium::de::from_reader(std::io::BufReader::with_capacity(
+                STABLE_MEMORY_BUFFER_SIZE,
+                StableMemoryReader::new(&*memory),
+            ))
+            .expect("Decoding stable memory failed")
+        });
+    } else {
+        *ledger = ciborium::de::from_reader(stable::StableReader::new())
+            .expect("Decoding stable memory failed");
+    }
+    // Make the minting account resolvable also on ledgers that were created
+    // before accounts were recorded.
+    if let Some(icrc1_minting_account) = ledger.icrc1_minting_account {
+        record_account(icrc1_minting_account);
+    }
 
     if let Some(args) = args {
         match args {
@@ -753,6 +787,10 @@ fn post_upgrade(args: Option<LedgerCanisterPayload>) {
     );
 }
 
+/// The size of the buffer used to read and write the ledger state from and to
+/// stable memory during upgrades.
+const STABLE_MEMORY_BUFFER_SIZE: usize = 1024 * 1024;
+
 #[export_name = "canister_post_upgrade"]
 fn post_upgrade_() {
     over_init(|CandidOne(args)| post_upgrade(args));
@@ -768,8 +806,16 @@ fn pre_upgrade() {
         .read()
         // This should never happen, but it's better to be safe than sorry
         .unwrap_or_else(|poisoned| poisoned.into_inner());
-    ciborium::ser::into_writer(&*ledger, stable::StableWriter::new())
-        .expect("failed to write ledger state to stable memory");
+    UPGRADES_MEMORY.with(|memory| {
+        let memory = memory.borrow();
+        let mut writer = std::io::BufWriter::with_capacity(
+            STABLE_MEMORY_BUFFER_SIZE,
+            StableMemoryWriter::new(&*memory),
+        );
+        ciborium::ser::into_writer(&*ledger, &mut writer)
+            .expect("failed to write ledger state to stable memory");
+        std::io::Write::flush(&mut writer).expect("failed to write ledger state to stable memory");
+    });
 }
 
 struct Access;
@@ -1065,6 +1111,51 @@ fn compute_account_identifier_candid() {
     over(candid_one, compute_account_identifier)
 }
 
+#[candid_method(query, rename = "account_identifier_to_account")]
+fn account_identifier_to_account(arg: AccountIdBlob) -> Option<Account> {
+    let account_identifier = AccountIdentifier::from_address(arg).unwrap_or_else(|e| {
+        trap_with(&format!("Invalid account identifier: {}", e));
+    });
+    ledger_canister::account_for_identifier(&account_identifier)
+}
+
+#[export_name = "canister_query account_identifier_to_account"]
+fn account_identifier_to_account_candid() {
+    over(candid_one, account_identifier_to_account)
+}
+
+#[candid_method(query, rename = "account_identifiers_to_accounts")]
+fn account_identifiers_to_accounts(arg: Vec<AccountIdBlob>) -> Vec<Option<Account>> {
+    if arg.len() > MAX_ACCOUNT_IDENTIFIERS_PER_REQUEST {
+        trap_with(&format!(
+            "Cannot resolve more than {} account identifiers per request.",
+            MAX_ACCOUNT_IDENTIFIERS_PER_REQUEST
+        ));
+    }
+    arg.into_iter().map(account_identifier_to_account).collect()
+}
+
+#[export_name = "canister_query account_identifiers_to_accounts"]
+fn account_identifiers_to_accounts_candid() {
+    over(candid_one, account_identifiers_to_accounts)
+}
+
+#[candid_method(update, rename = "backfill_accounts")]
+fn backfill_accounts(arg: Vec<Account>) -> u64 {
+    if arg.len() > MAX_ACCOUNTS_PER_BACKFILL {
+        trap_with(&format!(
+            "Cannot backfill more than {} accounts per request.",
+            MAX_ACCOUNTS_PER_BACKFILL
+        ));
+    }
+    ledger_canister::backfill_accounts(&LEDGER.read().unwrap(), &arg) as u64
+}
+
+#[export_name = "canister_update backfill_accounts"]
+fn backfill_accounts_candid() {
+    over(candid_one, backfill_accounts)
+}
+
 #[export_name = "canister_query icrc1_balance_of"]
 fn icrc1_balance_of_candid() {
     over(candid_one, icrc1_balance_of)
@@ -1298,6 +1389,11 @@ fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::i
         ledger.balances.store.len() as f64,
         "Total number of accounts in the balance store.",
     )?;
+    w.encode_gauge(
+        "ledger_known_icrc1_accounts",
+        ledger_canister::known_accounts_len() as f64,
+        "Total number of ICRC-1 accounts that can be resolved from their account identifier.",
+    )?;
     w.encode_gauge(
         "ledger_most_recent_block_time_seconds",
         ledger.blockchain.last_timestamp.as_nanos_since_unix_epoch() as f64 / 1_000_000_000.0,
@@ -1438,6 +1534,8 @@ async fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
                 };
                 err
             })?;
+        record_account(from_account);
+        record_account(arg.spender);
 
         set_certified_data(&hash.into_bytes());
 
@@ -1,4 +1,7 @@
-use crate::{AccountIdentifier, Ledger};
+use crate::{
+    account_for_identifier, backfill_accounts, known_accounts_len, record_account,
+    AccountIdentifier, Ledger, StableMemoryReader, StableMemoryWriter,
+};
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_ledger_canister_core::{
     archive::Archive,
@@ -1370,3 +1373,88 @@ fn test_approval_burn_from() {
     assert_eq!(ctx.balances().account_balance(&spender), Tokens::ZERO);
     assert_eq!(ctx.balances().total_supply().get_e8s(), 90_000);
 }
+
+#[test]
+fn test_account_identifier_to_account() {
+    let account = icrc_ledger_types::icrc1::account::Account {
+        owner: PrincipalId::new_user_test_id(1).0,
+        subaccount: Some([1; 32]),
+    };
+    let account_identifier = AccountIdentifier::from(account);
+
+    assert_eq!(account_for_identifier(&account_identifier), None);
+
+    record_account(account);
+    record_account(account);
+
+    assert_eq!(account_for_identifier(&account_identifier), Some(account));
+    assert_eq!(account_for_identifier(&test_account_id(1)), None);
+    assert_eq!(known_accounts_len(), 1);
+
+    // The default subaccount resolves to an account without subaccount.
+    let default_subaccount_account = icrc_ledger_types::icrc1::account::Account {
+        owner: PrincipalId::new_user_test_id(1).0,
+        subaccount: Some([0; 32]),
+    };
+    record_account(default_subaccount_account);
+    assert_eq!(
+        account_for_identifier(&test_account_id(1)),
+        Some(icrc_ledger_types::icrc1::account::Account {
+            owner: PrincipalId::new_user_test_id(1).0,
+            subaccount: None,
+        })
+    );
+}
+
+#[test]
+fn test_backfill_accounts_only_records_accounts_with_balance() {
+    let mut ctx = Ledger::default();
+    let funded = icrc_ledger_types::icrc1::account::Account {
+        owner: PrincipalId::new_user_test_id(1).0,
+        subaccount: Some([1; 32]),
+    };
+    let unfunded = icrc_ledger_types::icrc1::account::Account {
+        owner: PrincipalId::new_user_test_id(2).0,
+        subaccount: None,
+    };
+    apply_operation(
+        &mut ctx,
+        &Operation::Mint {
+            to: AccountIdentifier::from(funded),
+            amount: Tokens::from_e8s(1000),
+        },
+        ts(1),
+    )
+    .unwrap();
+
+    assert_eq!(backfill_accounts(&ctx, &[funded, unfunded]), 1);
+    assert_eq!(
+        account_for_identifier(&AccountIdentifier::from(funded)),
+        Some(funded)
+    );
+    assert_eq!(
+        account_for_identifier(&AccountIdentifier::from(unfunded)),
+        None
+    );
+
+    // Backfilling is idempotent.
+    assert_eq!(backfill_accounts(&ctx, &[funded]), 0);
+    assert_eq!(known_accounts_len(), 1);
+}
+
+#[test]
+fn test_stable_memory_reader_reads_what_writer_wrote() {
+    use ic_stable_structures::{DefaultMemoryImpl, Memory};
+    use std::io::{Read, Write};
+
+    let memory = DefaultMemoryImpl::default();
+    let bytes: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+    StableMemoryWriter::new(&memory).write_all(&bytes).unwrap();
+    assert_eq!(memory.size(), 4);
+
+    let mut read_bytes = vec![0; bytes.len()];
+    StableMemoryReader::new(&memory)
+        .read_exact(&mut read_bytes)
+        .unwrap();
+    assert_eq!(read_bytes, bytes);
+}
@@ -44,6 +44,10 @@ pub const DEFAULT_TRANSFER_FEE: Tokens = Tokens::from_e8s(10_000);
 
 pub const MAX_BLOCKS_PER_REQUEST: usize = 2000;
 
+/// The maximum number of account identifiers that can be resolved to accounts
+/// in a single `account_identifiers_to_accounts` request.
+pub const MAX_ACCOUNT_IDENTIFIERS_PER_REQUEST: usize = 1000;
+
 pub const MEMO_SIZE_BYTES: usize = 32;
 
 pub type LedgerBalances = Balances<BTreeMap<AccountIdentifier, Tokens>>;
@@ -44,10 +44,11 @@ use ic_sns_governance::{
         GetMetadataResponse, GetMode, GetModeResponse, GetNeuron, GetNeuronResponse, GetProposal,
         GetProposalResponse, GetRunningSnsVersionRequest, GetRunningSnsVersionResponse,
         GetSnsInitializationParametersRequest, GetSnsInitializationParametersResponse,
-        Governance as GovernanceProto, ListNervousSystemFunctionsResponse, ListNeurons,
-        ListNeuronsResponse, ListProposals, ListProposalsResponse, ManageNeuron,
-        ManageNeuronResponse, NervousSystemParameters, ProposalData, ProposalRewardStatus,
-        RewardEvent, SetMode, SetModeResponse,
+        GetTreasuryManagersRequest, GetTreasuryManagersResponse, GetUpgradeJournalRequest,
+        GetUpgradeJournalResponse, Governance as GovernanceProto,
+        ListNervousSystemFunctionsResponse, ListNeurons, ListNeuronsResponse, ListProposals,
+        ListProposalsResponse, ManageNeuron, ManageNeuronResponse, NervousSystemParameters,
+        ProposalData, ProposalRewardStatus, RewardEvent, SetMode, SetModeResponse,
     },
     types::{Environment, HeapGrowthPotential},
     LEGACY_REWARD_EVENT_END_TIMESTAMP_SECONDS,
@@ -689,6 +690,34 @@ fn get_running_sns_version_(_: GetRunningSnsVersionRequest) -> GetRunningSnsVers
     }
 }
 
+/// Gets the upgrade journal of the SNS, which records target version changes as well as the
+/// upgrade steps (automatic or initiated by proposals) and their outcomes.
+#[export_name = "canister_query get_upgrade_journal"]
+fn get_upgrade_journal() {
+    log!(INFO, "get_upgrade_journal");
+    over(candid_one, get_upgrade_journal_)
+}
+
+/// Internal method for calling get_upgrade_journal.
+#[candid_method(query, rename = "get_upgrade_journal")]
+fn get_upgrade_journal_(request: GetUpgradeJournalRequest) -> GetUpgradeJournalResponse {
+    governance().get_upgrade_journal(request)
+}
+
+/// Gets the treasury managers that SNS treasury funds have been deposited into, together with
+/// the balances and audit trails they reported.
+#[export_name = "canister_query get_treasury_managers"]
+fn get_treasury_managers() {
+    log!(INFO, "get_treasury_managers");
+    over(candid_one, get_treasury_managers_)
+}
+
+/// Internal method for calling get_treasury_managers.
+#[candid_method(query, rename = "get_treasury_managers")]
+fn get_treasury_managers_(request: GetTreasuryManagersRequest) -> GetTreasuryManagersResponse {
+    governance().get_treasury_managers(request)
+}
+
 /// Marks an in progress upgrade that has passed its deadline as failed.
 #[export_name = "canister_update fail_stuck_upgrade_in_progress"]
 fn fail_stuck_upgrade_in_progress() {
@@ -432,6 +432,93 @@ pub struct ManageSnsMetadata {
     #[prost(string, optional, tag = "4")]
     pub description: ::core::option::Option<::prost::alloc::string::String>,
 }
+/// A proposal function to change the parameters of the SNS ledger.
+/// The ledger is upgraded (through SNS root) to the WASM of the currently
+/// deployed SNS version, passing the new values as upgrade arguments.
+/// Fields with None values will remain unchanged.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ManageLedgerParameters {
+    /// The new transfer fee, in e8s.
+    #[prost(uint64, optional, tag = "1")]
+    pub transfer_fee: ::core::option::Option<u64>,
+    /// The new token name, must be between 4 and 255 characters.
+    #[prost(string, optional, tag = "2")]
+    pub token_name: ::core::option::Option<::prost::alloc::string::String>,
+    /// The new token symbol, must be between 3 and 10 characters.
+    #[prost(string, optional, tag = "3")]
+    pub token_symbol: ::core::option::Option<::prost::alloc::string::String>,
+    /// Base64 representation of the new token logo. Max length is 341334 characters, roughly 256 Kb.
+    #[prost(string, optional, tag = "4")]
+    pub token_logo: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// A proposal function to change the settings of registered dapp canisters
+/// (through SNS root). Only settings with Some values are changed; controllers
+/// cannot be changed through this proposal.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ManageDappCanisterSettings {
+    /// The registered dapp canisters whose settings should be changed.
+    #[prost(message, repeated, tag = "1")]
+    pub canister_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+    /// The new compute allocation, in percent. Must be at most 100.
+    #[prost(uint64, optional, tag = "2")]
+    pub compute_allocation: ::core::option::Option<u64>,
+    /// The new memory allocation, in bytes.
+    #[prost(uint64, optional, tag = "3")]
+    pub memory_allocation: ::core::option::Option<u64>,
+    /// The new freezing threshold, in seconds.
+    #[prost(uint64, optional, tag = "4")]
+    pub freezing_threshold: ::core::option::Option<u64>,
+    /// The new reserved cycles limit, in cycles.
+    #[prost(uint64, optional, tag = "5")]
+    pub reserved_cycles_limit: ::core::option::Option<u64>,
+}
+/// A proposal function to set the version the SNS is upgraded to automatically.
+/// Once adopted, governance upgrades the SNS canisters one blessed upgrade step
+/// at a time (as UpgradeSnsToNextVersion would) until the target is reached.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AdvanceSnsTargetVersion {
+    /// The new target version. Must be on the SNS's upgrade path (as reported by
+    /// SNS-WASM) after the currently deployed version. If not set, the latest
+    /// version on the upgrade path is used.
+    #[prost(message, optional, tag = "1")]
+    pub new_target: ::core::option::Option<governance::Version>,
+}
+/// A proposal to deposit SNS treasury funds into a treasury manager, e.g. to
+/// provide liquidity on a DEX. A treasury manager is a registered dapp canister
+/// implementing the treasury manager interface (see treasury_manager.did). The
+/// funds are transferred to the treasury manager's default account, after which
+/// it is notified of the deposit. The balances and audit trail it reports back
+/// are recorded in Governance.treasury_managers.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DepositToTreasuryManager {
+    /// The treasury manager to deposit into.
+    #[prost(message, optional, tag = "1")]
+    pub treasury_manager_canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// The amount to deposit from the ICP treasury, in e8s. May be zero.
+    #[prost(uint64, tag = "2")]
+    pub icp_amount_e8s: u64,
+    /// The amount to deposit from the SNS token treasury, in e8s. May be zero.
+    #[prost(uint64, tag = "3")]
+    pub sns_token_amount_e8s: u64,
+}
+/// A proposal to withdraw all funds managed by a treasury manager back to the
+/// SNS treasury accounts.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WithdrawFromTreasuryManager {
+    /// The treasury manager to withdraw from.
+    #[prost(message, optional, tag = "1")]
+    pub treasury_manager_canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+}
 /// A proposal function to upgrade the SNS to the next version.  The versions are such that only
 /// one kind of canister will update at the same time.
 /// This returns an error if the canister cannot be upgraded or no upgrades are available.
@@ -493,7 +580,7 @@ pub struct Proposal {
     /// of this mapping.
     #[prost(
         oneof = "proposal::Action",
-        tags = "4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16"
+        tags = "4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21"
     )]
     pub action: ::core::option::Option<proposal::Action>,
 }
@@ -589,6 +676,31 @@ pub mod proposal {
         /// Id = 12.
         #[prost(message, tag = "16")]
         MintSnsTokens(super::MintSnsTokens),
+        /// Change some parameters on the ledger.
+        ///
+        /// Id = 13.
+        #[prost(message, tag = "17")]
+        ManageLedgerParameters(super::ManageLedgerParameters),
+        /// Change the settings of registered dapp canisters.
+        ///
+        /// Id = 14.
+        #[prost(message, tag = "18")]
+        ManageDappCanisterSettings(super::ManageDappCanisterSettings),
+        /// Set the version the SNS is upgraded to automatically.
+        ///
+        /// Id = 15.
+        #[prost(message, tag = "19")]
+        AdvanceSnsTargetVersion(super::AdvanceSnsTargetVersion),
+        /// Deposit SNS treasury funds into a treasury manager.
+        ///
+        /// Id = 16.
+        #[prost(message, tag = "20")]
+        DepositToTreasuryManager(super::DepositToTreasuryManager),
+        /// Withdraw the funds managed by a treasury manager back to the SNS treasury.
+        ///
+        /// Id = 17.
+        #[prost(message, tag = "21")]
+        WithdrawFromTreasuryManager(super::WithdrawFromTreasuryManager),
     }
 }
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
@@ -1067,6 +1179,30 @@ pub struct NervousSystemParameters {
     /// (enabled) agree.
     #[prost(bool, optional, tag = "22")]
     pub maturity_modulation_disabled: ::core::option::Option<bool>,
+    /// Which canisters may receive treasury deposits via DepositToTreasuryManager
+    /// proposals, and how much may be moved by a single such proposal.
+    ///
+    /// Being registered as a dapp canister is not sufficient for a canister to
+    /// receive treasury funds; it must also be listed here.
+    #[prost(message, optional, tag = "23")]
+    pub treasury_manager_parameters: ::core::option::Option<TreasuryManagerParameters>,
+}
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TreasuryManagerParameters {
+    /// The canisters that DepositToTreasuryManager proposals may target.
+    #[prost(message, repeated, tag = "1")]
+    pub approved_canister_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+    /// The maximum amount of ICP that a single DepositToTreasuryManager proposal
+    /// may transfer out of the treasury. When unset, no ICP can be deposited.
+    #[prost(uint64, optional, tag = "2")]
+    pub max_icp_amount_e8s_per_proposal: ::core::option::Option<u64>,
+    /// The maximum amount of SNS tokens that a single DepositToTreasuryManager
+    /// proposal may transfer out of the treasury. When unset, no SNS tokens can
+    /// be deposited.
+    #[prost(uint64, optional, tag = "3")]
+    pub max_sns_token_amount_e8s_per_proposal: ::core::option::Option<u64>,
 }
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -1313,6 +1449,21 @@ pub struct Governance {
     pub is_finalizing_disburse_maturity: ::core::option::Option<bool>,
     #[prost(message, optional, tag = "26")]
     pub maturity_modulation: ::core::option::Option<governance::MaturityModulation>,
+    /// The version that the SNS is being upgraded to automatically. While
+    /// deployed_version is behind this version, governance upgrades the SNS
+    /// canisters one blessed upgrade step at a time during heartbeats. Set by
+    /// AdvanceSnsTargetVersion proposals, and cleared if an automatic upgrade
+    /// step fails.
+    #[prost(message, optional, tag = "27")]
+    pub target_version: ::core::option::Option<governance::Version>,
+    /// A bounded log of the events related to SNS upgrades (target version
+    /// changes, upgrade steps and their outcomes), oldest first.
+    #[prost(message, optional, tag = "28")]
+    pub upgrade_journal: ::core::option::Option<UpgradeJournal>,
+    /// The treasury managers that SNS treasury funds have been deposited into,
+    /// together with what they have reported back.
+    #[prost(message, repeated, tag = "29")]
+    pub treasury_managers: ::prost::alloc::vec::Vec<TreasuryManager>,
 }
 /// Nested message and enum types in `Governance`.
 pub mod governance {
@@ -1510,7 +1661,8 @@ pub mod governance {
         /// allowing us to fail in case we otherwise have gotten stuck.
         #[prost(uint64, tag = "3")]
         pub checking_upgrade_lock: u64,
-        /// The proposal that initiated this upgrade
+        /// The proposal that initiated this upgrade, or 0 if this upgrade is an
+        /// automatic step towards `Governance.target_version`.
         #[prost(uint64, tag = "4")]
         pub proposal_id: u64,
     }
@@ -1579,6 +1731,136 @@ pub mod governance {
         }
     }
 }
+/// Balances held by a treasury manager on behalf of the SNS.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TreasuryManagerBalances {
+    #[prost(uint64, tag = "1")]
+    pub icp_e8s: u64,
+    #[prost(uint64, tag = "2")]
+    pub sns_token_e8s: u64,
+}
+/// An entry in the audit trail of a treasury manager.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TreasuryManagerAuditTrailEntry {
+    #[prost(uint64, optional, tag = "1")]
+    pub timestamp_seconds: ::core::option::Option<u64>,
+    /// The proposal that led to this entry.
+    #[prost(uint64, optional, tag = "2")]
+    pub proposal_id: ::core::option::Option<u64>,
+    /// What happened, e.g. a deposit, a withdrawal or an operation (such as a
+    /// swap) that the treasury manager reported.
+    #[prost(string, tag = "3")]
+    pub human_readable: ::prost::alloc::string::String,
+    /// Whether the entry was reported by the treasury manager itself, as opposed
+    /// to recorded by governance.
+    #[prost(bool, tag = "4")]
+    pub reported_by_treasury_manager: bool,
+}
+/// What governance knows about a treasury manager.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TreasuryManager {
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// The balances most recently reported by the treasury manager.
+    #[prost(message, optional, tag = "2")]
+    pub balances: ::core::option::Option<TreasuryManagerBalances>,
+    /// When the balances were last reported (seconds since UNIX epoch).
+    #[prost(uint64, optional, tag = "3")]
+    pub balances_timestamp_seconds: ::core::option::Option<u64>,
+    /// A bounded audit trail, oldest entry first.
+    #[prost(message, repeated, tag = "4")]
+    pub audit_trail: ::prost::alloc::vec::Vec<TreasuryManagerAuditTrailEntry>,
+}
+/// An event related to an SNS upgrade, recorded in Governance.upgrade_journal.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpgradeJournalEntry {
+    /// Seconds since UNIX epoch at which the event was recorded.
+    #[prost(uint64, optional, tag = "1")]
+    pub timestamp_seconds: ::core::option::Option<u64>,
+    #[prost(oneof = "upgrade_journal_entry::Event", tags = "2, 3, 4, 5")]
+    pub event: ::core::option::Option<upgrade_journal_entry::Event>,
+}
+/// Nested message and enum types in `UpgradeJournalEntry`.
+pub mod upgrade_journal_entry {
+    /// The target version was set by an AdvanceSnsTargetVersion proposal.
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct TargetVersionSet {
+        #[prost(message, optional, tag = "1")]
+        pub old_target_version: ::core::option::Option<super::governance::Version>,
+        #[prost(message, optional, tag = "2")]
+        pub new_target_version: ::core::option::Option<super::governance::Version>,
+        /// The proposal that set the target version.
+        #[prost(uint64, tag = "3")]
+        pub proposal_id: u64,
+    }
+    /// The target version was cleared, so automatic upgrades stopped.
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct TargetVersionReset {
+        #[prost(message, optional, tag = "1")]
+        pub old_target_version: ::core::option::Option<super::governance::Version>,
+        /// Why the target version was cleared.
+        #[prost(string, tag = "2")]
+        pub human_readable: ::prost::alloc::string::String,
+    }
+    /// An upgrade of one SNS canister type was kicked off.
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct UpgradeStarted {
+        #[prost(message, optional, tag = "1")]
+        pub current_version: ::core::option::Option<super::governance::Version>,
+        #[prost(message, optional, tag = "2")]
+        pub expected_version: ::core::option::Option<super::governance::Version>,
+        /// The proposal that kicked off the upgrade. Not set for automatic upgrade
+        /// steps towards Governance.target_version.
+        #[prost(uint64, optional, tag = "3")]
+        pub proposal_id: ::core::option::Option<u64>,
+    }
+    /// An upgrade that was kicked off has completed or failed.
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct UpgradeOutcome {
+        /// Whether the running version matched the expected version.
+        #[prost(bool, tag = "1")]
+        pub succeeded: bool,
+        /// A description of the outcome.
+        #[prost(string, tag = "2")]
+        pub human_readable: ::prost::alloc::string::String,
+    }
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Event {
+        #[prost(message, tag = "2")]
+        TargetVersionSet(TargetVersionSet),
+        #[prost(message, tag = "3")]
+        TargetVersionReset(TargetVersionReset),
+        #[prost(message, tag = "4")]
+        UpgradeStarted(UpgradeStarted),
+        #[prost(message, tag = "5")]
+        UpgradeOutcome(UpgradeOutcome),
+    }
+}
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpgradeJournal {
+    #[prost(message, repeated, tag = "1")]
+    pub entries: ::prost::alloc::vec::Vec<UpgradeJournalEntry>,
+}
 /// Request message for 'get_metadata'.
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -1629,6 +1911,40 @@ pub struct GetRunningSnsVersionResponse {
     #[prost(message, optional, tag = "2")]
     pub pending_version: ::core::option::Option<governance::UpgradeInProgress>,
 }
+/// Request for the SNS's upgrade journal.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetUpgradeJournalRequest {}
+/// Response with the SNS's upgrade journal, together with the versions that
+/// are needed to interpret it.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetUpgradeJournalResponse {
+    /// The currently deployed version of the SNS.
+    #[prost(message, optional, tag = "1")]
+    pub deployed_version: ::core::option::Option<governance::Version>,
+    /// The version the SNS is being upgraded to automatically, if any.
+    #[prost(message, optional, tag = "2")]
+    pub target_version: ::core::option::Option<governance::Version>,
+    /// The upgrade journal, oldest entry first.
+    #[prost(message, optional, tag = "3")]
+    pub upgrade_journal: ::core::option::Option<UpgradeJournal>,
+}
+/// Request for the treasury managers known to governance.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetTreasuryManagersRequest {}
+/// Response with the treasury managers known to governance.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetTreasuryManagersResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub treasury_managers: ::prost::alloc::vec::Vec<TreasuryManager>,
+}
 /// Request to fail an upgrade proposal that is Adopted but not Executed or
 /// Failed if it is past the time when it should have been marked as failed.
 /// This is useful in the case where the asynchronous process may have failed to
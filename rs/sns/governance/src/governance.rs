@@ -7,7 +7,7 @@ use crate::{
         get_canister_id, perform_execute_generic_nervous_system_function_call,
         upgrade_canister_directly,
     },
-    ledger::ICRC1Ledger,
+    ledger::{ICRC1Ledger, LedgerArgument, LedgerUpgradeArgs, ICRC1_TOKEN_LOGO_KEY},
     logs::{ERROR, INFO},
     neuron::{
         NeuronState, RemovePermissionsStatus, DEFAULT_VOTING_POWER_PERCENTAGE_MULTIPLIER,
@@ -15,6 +15,7 @@ use crate::{
     },
     pb::{
         sns_root_types::{
+            ManageDappCanisterSettingsRequest, ManageDappCanisterSettingsResponse,
             RegisterDappCanistersRequest, RegisterDappCanistersResponse, SetDappControllersRequest,
             SetDappControllersResponse,
         },
@@ -39,22 +40,29 @@ use crate::{
             neuron::{DissolveState, Followees},
             proposal::Action,
             transfer_sns_treasury_funds::TransferFrom,
-            Account as AccountProto, Ballot, ClaimSwapNeuronsError, ClaimSwapNeuronsRequest,
-            ClaimSwapNeuronsResponse, ClaimedSwapNeuronStatus, DefaultFollowees,
-            DeregisterDappCanisters, DisburseMaturityInProgress, Empty,
-            ExecuteGenericNervousSystemFunction, FailStuckUpgradeInProgressRequest,
-            FailStuckUpgradeInProgressResponse, GetMaturityModulationRequest,
-            GetMaturityModulationResponse, GetMetadataRequest, GetMetadataResponse, GetMode,
-            GetModeResponse, GetNeuron, GetNeuronResponse, GetProposal, GetProposalResponse,
-            GetSnsInitializationParametersRequest, GetSnsInitializationParametersResponse,
+            upgrade_journal_entry::{
+                self, TargetVersionReset, TargetVersionSet, UpgradeOutcome, UpgradeStarted,
+            },
+            Account as AccountProto, AdvanceSnsTargetVersion, Ballot, ClaimSwapNeuronsError,
+            ClaimSwapNeuronsRequest, ClaimSwapNeuronsResponse, ClaimedSwapNeuronStatus,
+            DefaultFollowees, DepositToTreasuryManager, DeregisterDappCanisters,
+            DisburseMaturityInProgress, Empty, ExecuteGenericNervousSystemFunction,
+            FailStuckUpgradeInProgressRequest, FailStuckUpgradeInProgressResponse,
+            GetMaturityModulationRequest, GetMaturityModulationResponse, GetMetadataRequest,
+            GetMetadataResponse, GetMode, GetModeResponse, GetNeuron, GetNeuronResponse,
+            GetProposal, GetProposalResponse, GetSnsInitializationParametersRequest,
+            GetSnsInitializationParametersResponse, GetTreasuryManagersRequest,
+            GetTreasuryManagersResponse, GetUpgradeJournalRequest, GetUpgradeJournalResponse,
             Governance as GovernanceProto, GovernanceError, ListNervousSystemFunctionsResponse,
-            ListNeurons, ListNeuronsResponse, ListProposals, ListProposalsResponse, ManageNeuron,
-            ManageNeuronResponse, ManageSnsMetadata, MintSnsTokens, NervousSystemFunction,
-            NervousSystemParameters, Neuron, NeuronId, NeuronPermission, NeuronPermissionList,
-            NeuronPermissionType, Proposal, ProposalData, ProposalDecisionStatus, ProposalId,
-            ProposalRewardStatus, RegisterDappCanisters, RewardEvent, Tally,
-            TransferSnsTreasuryFunds, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote,
-            WaitForQuietState,
+            ListNeurons, ListNeuronsResponse, ListProposals, ListProposalsResponse,
+            ManageDappCanisterSettings, ManageLedgerParameters, ManageNeuron, ManageNeuronResponse,
+            ManageSnsMetadata, MintSnsTokens, NervousSystemFunction, NervousSystemParameters,
+            Neuron, NeuronId, NeuronPermission, NeuronPermissionList, NeuronPermissionType,
+            Proposal, ProposalData, ProposalDecisionStatus, ProposalId, ProposalRewardStatus,
+            RegisterDappCanisters, RewardEvent, Tally, TransferSnsTreasuryFunds, TreasuryManager,
+            TreasuryManagerAuditTrailEntry, TreasuryManagerBalances, UpgradeJournalEntry,
+            UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote, WaitForQuietState,
+            WithdrawFromTreasuryManager,
         },
     },
     proposal::{
@@ -62,8 +70,13 @@ use crate::{
         MAX_NUMBER_OF_PROPOSALS_WITH_BALLOTS,
     },
     sns_upgrade::{
-        get_all_sns_canisters, get_running_version, get_upgrade_params, get_wasm, SnsCanisterType,
-        UpgradeSnsParams,
+        get_all_sns_canisters, get_running_version, get_upgrade_params,
+        get_upgrade_params_for_next_version, get_upgrade_steps_to_target_version, get_wasm,
+        SnsCanisterType, UpgradeSnsParams,
+    },
+    treasury_manager::{
+        DepositRequest, TreasuryManagerReport, TreasuryManagerResult, WithdrawAccounts,
+        WithdrawRequest,
     },
     types::{is_registered_function_id, Environment, HeapGrowthPotential, LedgerUpdateLock},
 };
@@ -86,7 +99,10 @@ use ic_nervous_system_governance::maturity_modulation::{
 use ic_nervous_system_root::change_canister::ChangeCanisterRequest;
 use ic_nns_constants::LEDGER_CANISTER_ID as NNS_LEDGER_CANISTER_ID;
 use icp_ledger::DEFAULT_TRANSFER_FEE as NNS_DEFAULT_TRANSFER_FEE;
-use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::{
+    icrc::generic_metadata_value::MetadataValue,
+    icrc1::account::{Account, Subaccount},
+};
 use lazy_static::lazy_static;
 use maplit::hashset;
 use rust_decimal::Decimal;
@@ -134,10 +150,25 @@ const SEVEN_DAYS_IN_SECONDS: u64 = 7 * 24 * 3600;
 pub const HEAP_SIZE_SOFT_LIMIT_IN_WASM32_PAGES: usize =
     MAX_HEAP_SIZE_IN_KIB / WASM32_PAGE_SIZE_IN_KIB * 7 / 8;
 
+/// The `UpgradeInProgress.proposal_id` of upgrades that are automatic steps towards
+/// `Governance.target_version` rather than initiated by a proposal. Proposal IDs start at 1.
+const AUTOMATIC_UPGRADE_STEP_PROPOSAL_ID: u64 = 0;
+
+/// The maximum number of entries kept in the upgrade journal. Older entries are dropped first.
+pub const MAX_UPGRADE_JOURNAL_ENTRIES: usize = 1_000;
+
+/// How long an automatic upgrade step towards `Governance.target_version` may hold its lock
+/// before it is considered abandoned (e.g. because the heartbeat trapped while awaiting a call).
+const ADVANCE_TOWARDS_TARGET_VERSION_LOCK_TIMEOUT_SECONDS: u64 = 10 * 60;
+
 /// Prefixes each log line for this canister.
 pub fn log_prefix() -> String {
     "[Governance] ".into()
 }
+/// The maximum number of entries kept in the audit trail of each treasury manager. When the
+/// limit is reached, the oldest entries are dropped.
+pub const MAX_TREASURY_MANAGER_AUDIT_TRAIL_ENTRIES: usize = 1_000;
+
 /// The static MEMO used when calculating the SNS Treasury subaccount.
 pub const TREASURY_SUBACCOUNT_NONCE: u64 = 0;
 
@@ -676,6 +707,12 @@ pub struct Governance {
 
     /// The number of proposals after the last time "garbage collection" was run.
     pub latest_gc_num_proposals: usize,
+
+    /// The timestamp, in seconds since the unix epoch, at which the automatic upgrade step
+    /// towards `proto.target_version` that is currently being initiated was started, if any.
+    /// This prevents concurrent heartbeats (and upgrade proposals) from initiating upgrades
+    /// at the same time.
+    advance_towards_target_version_lock_timestamp_seconds: Option<u64>,
 }
 
 impl Governance {
@@ -734,6 +771,7 @@ impl Governance {
             closest_proposal_deadline_timestamp_seconds: 0,
             latest_gc_timestamp_seconds: 0,
             latest_gc_num_proposals: 0,
+            advance_towards_target_version_lock_timestamp_seconds: None,
         };
 
         gov.initialize_indices();
@@ -2111,6 +2149,26 @@ impl Governance {
                 self.perform_transfer_sns_treasury_funds(transfer).await
             }
             Action::MintSnsTokens(mint) => self.perform_mint_sns_tokens(mint).await,
+            Action::ManageLedgerParameters(manage_ledger_parameters) => {
+                self.perform_manage_ledger_parameters(proposal_id, manage_ledger_parameters)
+                    .await
+            }
+            Action::ManageDappCanisterSettings(manage_dapp_canister_settings) => {
+                self.perform_manage_dapp_canister_settings(manage_dapp_canister_settings)
+                    .await
+            }
+            Action::AdvanceSnsTargetVersion(advance_sns_target_version) => {
+                self.perform_advance_sns_target_version(proposal_id, advance_sns_target_version)
+                    .await
+            }
+            Action::DepositToTreasuryManager(deposit) => {
+                self.perform_deposit_to_treasury_manager(proposal_id, deposit)
+                    .await
+            }
+            Action::WithdrawFromTreasuryManager(withdraw) => {
+                self.perform_withdraw_from_treasury_manager(proposal_id, withdraw)
+                    .await
+            }
             // This should not be possible, because Proposal validation is performed when
             // a proposal is first made.
             Action::Unspecified(_) => Err(GovernanceError::new_with_message(
@@ -2308,6 +2366,63 @@ impl Governance {
             })
     }
 
+    /// Changes the settings of registered dapp canisters through root.
+    async fn perform_manage_dapp_canister_settings(
+        &self,
+        manage_dapp_canister_settings: ManageDappCanisterSettings,
+    ) -> Result<(), GovernanceError> {
+        let canister_ids = manage_dapp_canister_settings.canister_ids.clone();
+        let payload = candid::Encode!(&ManageDappCanisterSettingsRequest::from(
+            manage_dapp_canister_settings
+        ))
+        .map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::InvalidProposal,
+                format!("Could not encode ManageDappCanisterSettingsRequest: {err:?}"),
+            )
+        })?;
+        let reply = self
+            .env
+            .call_canister(
+                self.proto.root_canister_id_or_panic(),
+                "manage_dapp_canister_settings",
+                payload,
+            )
+            .await
+            .map_err(|err| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Canister method call failed: {err:?}"),
+                )
+            })?;
+        let ManageDappCanisterSettingsResponse { failure_reason } =
+            candid::Decode!(&reply, ManageDappCanisterSettingsResponse).map_err(|err| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Could not decode ManageDappCanisterSettingsResponse: {err:?}"),
+                )
+            })?;
+
+        match failure_reason {
+            None => {
+                log!(
+                    INFO,
+                    "Changed the settings of the following dapp canisters: {:?}.",
+                    canister_ids
+                );
+                Ok(())
+            }
+            Some(failure_reason) => Err(GovernanceError::new_with_message(
+                ErrorType::External,
+                format!(
+                    "When trying to change the settings of the following dapp canisters: \
+                     {:?}: {}",
+                    canister_ids, failure_reason
+                ),
+            )),
+        }
+    }
+
     // Make a change to the values of Sns Metadata
     fn perform_manage_sns_metadata(
         &mut self,
@@ -2433,7 +2548,7 @@ impl Governance {
         proposal_id: u64,
         upgrade: UpgradeSnsControlledCanister,
     ) -> Result<(), GovernanceError> {
-        err_if_another_upgrade_is_in_progress(&self.proto.proposals, proposal_id)?;
+        self.err_if_any_other_upgrade_is_in_progress(proposal_id)?;
 
         let sns_canisters =
             get_all_sns_canisters(&*self.env, self.proto.root_canister_id_or_panic())
@@ -2527,17 +2642,12 @@ impl Governance {
         &mut self,
         proposal_id: u64,
     ) -> Result<bool, GovernanceError> {
-        err_if_another_upgrade_is_in_progress(&self.proto.proposals, proposal_id)?;
+        self.err_if_any_other_upgrade_is_in_progress(proposal_id)?;
 
         let current_version = self.proto.deployed_version_or_panic();
         let root_canister_id = self.proto.root_canister_id_or_panic();
 
-        let UpgradeSnsParams {
-            next_version,
-            canister_type_to_upgrade,
-            new_wasm_hash,
-            canister_ids_to_upgrade,
-        } = get_upgrade_params(&*self.env, root_canister_id, &current_version)
+        let upgrade_params = get_upgrade_params(&*self.env, root_canister_id, &current_version)
             .await
             .map_err(|e| {
                 GovernanceError::new_with_message(
@@ -2546,12 +2656,54 @@ impl Governance {
                 )
             })?;
 
+        self.initiate_upgrade_to_next_sns_version(proposal_id, current_version, upgrade_params)
+            .await
+    }
+
+    /// Kicks off the upgrade of one SNS canister type from `current_version` to the next
+    /// version described by `upgrade_params`. `proposal_id` is the proposal that initiated the
+    /// upgrade, or `AUTOMATIC_UPGRADE_STEP_PROPOSAL_ID` for automatic steps towards
+    /// `target_version`.
+    ///
+    /// Return `Ok(true)` if the upgrade was completed successfully, return `Ok(false)` if an
+    /// upgrade was successfully kicked-off, but its completion is pending.
+    async fn initiate_upgrade_to_next_sns_version(
+        &mut self,
+        proposal_id: u64,
+        current_version: Version,
+        upgrade_params: UpgradeSnsParams,
+    ) -> Result<bool, GovernanceError> {
+        let root_canister_id = self.proto.root_canister_id_or_panic();
+        let UpgradeSnsParams {
+            next_version,
+            canister_type_to_upgrade,
+            new_wasm_hash,
+            canister_ids_to_upgrade,
+        } = upgrade_params;
+
+        let upgrade_started = UpgradeStarted {
+            current_version: Some(current_version),
+            expected_version: Some(next_version.clone()),
+            proposal_id: (proposal_id != AUTOMATIC_UPGRADE_STEP_PROPOSAL_ID).then_some(proposal_id),
+        };
+
         // SNS Swap is controlled by NNS Governance, so this SNS instance cannot upgrade it.
         // Simply set `deployed_version` to `next_version` version so that other SNS upgrades can
         // be executed, and let the Swap upgrade occur externally (e.g. by someone submitting an
         // NNS proposal).
         if canister_type_to_upgrade == SnsCanisterType::Swap {
             self.proto.deployed_version = Some(next_version);
+            self.push_to_upgrade_journal(upgrade_journal_entry::Event::UpgradeStarted(
+                upgrade_started,
+            ));
+            self.push_to_upgrade_journal(upgrade_journal_entry::Event::UpgradeOutcome(
+                UpgradeOutcome {
+                    succeeded: true,
+                    human_readable: "Swap is upgraded by the NNS; only the deployed version \
+                                     was advanced."
+                        .to_string(),
+                },
+            ));
             return Ok(true);
         }
 
@@ -2589,6 +2741,9 @@ impl Governance {
 
         // A canister upgrade has been successfully kicked-off. Set the pending upgrade-in-progress
         // field so that Governance's heartbeat logic can check on the status of this upgrade.
+        self.push_to_upgrade_journal(upgrade_journal_entry::Event::UpgradeStarted(
+            upgrade_started,
+        ));
         self.proto.pending_version = Some(UpgradeInProgress {
             target_version: Some(next_version),
             mark_failed_at_seconds: self.env.now() + 5 * 60,
@@ -2605,6 +2760,47 @@ impl Governance {
         Ok(false)
     }
 
+    /// Executes an AdvanceSnsTargetVersion proposal by setting `target_version`. The actual
+    /// upgrades are then performed by the heartbeat (see `advance_towards_target_version`).
+    async fn perform_advance_sns_target_version(
+        &mut self,
+        proposal_id: u64,
+        advance_sns_target_version: AdvanceSnsTargetVersion,
+    ) -> Result<(), GovernanceError> {
+        let current_version = self.proto.deployed_version_or_panic();
+
+        let (target_version, upgrade_steps) = get_upgrade_steps_to_target_version(
+            &*self.env,
+            &current_version,
+            advance_sns_target_version.new_target.as_ref(),
+            self.env.canister_id().get(),
+        )
+        .await
+        .map_err(|e| {
+            GovernanceError::new_with_message(
+                ErrorType::InvalidProposal,
+                format!("Could not execute proposal: {}", e),
+            )
+        })?;
+
+        let old_target_version = self.proto.target_version.replace(target_version.clone());
+        self.push_to_upgrade_journal(upgrade_journal_entry::Event::TargetVersionSet(
+            TargetVersionSet {
+                old_target_version,
+                new_target_version: Some(target_version),
+                proposal_id,
+            },
+        ));
+
+        log!(
+            INFO,
+            "Set the SNS target version; {} upgrade step(s) remaining.",
+            upgrade_steps.len()
+        );
+
+        Ok(())
+    }
+
     async fn perform_transfer_sns_treasury_funds(
         &mut self,
         transfer: TransferSnsTreasuryFunds,
@@ -2668,118 +2864,587 @@ impl Governance {
         }
     }
 
-    async fn perform_mint_sns_tokens(
+    /// Executes a DepositToTreasuryManager proposal: transfers the amounts from the SNS
+    /// treasury to the default account of the treasury manager, and then notifies the
+    /// treasury manager of the deposit. If only some of the transfers succeed, the treasury
+    /// manager is still notified of the funds it received, so that they can be withdrawn.
+    async fn perform_deposit_to_treasury_manager(
         &mut self,
-        mint: MintSnsTokens,
+        proposal_id: u64,
+        deposit: DepositToTreasuryManager,
     ) -> Result<(), GovernanceError> {
-        let to = Account {
-            owner: mint
-                .to_principal
-                .ok_or(GovernanceError::new_with_message(
+        let treasury_manager_canister_id =
+            deposit.treasury_manager_canister_id.ok_or_else(|| {
+                GovernanceError::new_with_message(
                     ErrorType::InvalidProposal,
-                    "Expected mint to have a target principal",
-                ))?
-                .0,
-            subaccount: mint
-                .to_subaccount
-                .as_ref()
-                .map(|s| bytes_to_subaccount(&s.subaccount[..]))
-                .transpose()?,
-        };
-        let amount_e8s = mint.amount_e8s.ok_or(GovernanceError::new_with_message(
-            ErrorType::InvalidProposal,
-            "Expected MintSnsTokens to have an an amount_e8s",
-        ))?;
-        self.ledger
-            .transfer_funds(amount_e8s, 0, None, to, mint.memo())
+                    "Expected deposit to have a treasury_manager_canister_id",
+                )
+            })?;
+        self.err_if_not_registered_dapp_canister(treasury_manager_canister_id)
             .await?;
-        Ok(())
-    }
-
-    // Returns an option with the NervousSystemParameters
-    fn nervous_system_parameters(&self) -> Option<&NervousSystemParameters> {
-        self.proto.parameters.as_ref()
-    }
-
-    /// Returns the NervousSystemParameters or panics
-    fn nervous_system_parameters_or_panic(&self) -> &NervousSystemParameters {
-        self.nervous_system_parameters()
-            .expect("NervousSystemParameters not present")
-    }
-
-    /// Returns the list of permissions that a principal that claims a neuron will have for
-    /// that neuron, as defined in the nervous system parameters' neuron_claimer_permissions.
-    fn neuron_claimer_permissions_or_panic(&self) -> NeuronPermissionList {
-        self.nervous_system_parameters_or_panic()
-            .neuron_claimer_permissions
-            .as_ref()
-            .expect("NervousSystemParameters.neuron_claimer_permissions must be present")
-            .clone()
-    }
-
-    /// Returns the default followees that a newly claimed neuron will have, as defined in
-    /// the nervous system parameters' default_followees.
-    /// TODO NNS1-2169: default followees are not currently supported.
-    fn default_followees_or_panic(&self) -> DefaultFollowees {
-        self.nervous_system_parameters_or_panic()
-            .default_followees
-            .as_ref()
-            .expect("NervousSystemParameters.default_followees must be present")
+        // The parameters may have changed since the proposal was made.
+        let defects = self
+            .nervous_system_parameters_or_panic()
+            .treasury_manager_parameters
             .clone()
-    }
-
-    /// Returns the ledger's transaction fee as stored in the service nervous parameters.
-    fn transaction_fee_e8s_or_panic(&self) -> u64 {
-        self.nervous_system_parameters_or_panic()
-            .transaction_fee_e8s
-            .expect("NervousSystemParameters must have transaction_fee_e8s")
-    }
+            .unwrap_or_default()
+            .deposit_defects(&deposit);
+        if !defects.is_empty() {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                defects.join("\n"),
+            ));
+        }
 
-    /// Returns the initial voting period of proposals.
-    fn initial_voting_period_seconds_or_panic(&self) -> u64 {
-        self.nervous_system_parameters_or_panic()
-            .initial_voting_period_seconds
-            .expect("NervousSystemParameters must have initial_voting_period_seconds")
-    }
+        let to = Account {
+            owner: treasury_manager_canister_id.0,
+            subaccount: None,
+        };
+        let mut failures = vec![];
 
-    /// Returns the wait for quiet deadline extension period for proposals.
-    fn wait_for_quiet_deadline_increase_seconds_or_panic(&self) -> u64 {
-        self.nervous_system_parameters_or_panic()
-            .wait_for_quiet_deadline_increase_seconds
-            .expect("NervousSystemParameters must have wait_for_quiet_deadline_increase_seconds")
-    }
+        let mut icp_amount_e8s = deposit.icp_amount_e8s;
+        if icp_amount_e8s > 0 {
+            if let Err(e) = self
+                .nns_ledger
+                .transfer_funds(
+                    icp_amount_e8s,
+                    NNS_DEFAULT_TRANSFER_FEE.get_e8s(),
+                    None,
+                    to,
+                    proposal_id,
+                )
+                .await
+            {
+                failures.push(format!("Error making ICP treasury transfer: {}", e));
+                icp_amount_e8s = 0;
+            }
+        }
 
-    /// Returns the neuron minimum stake e8s from the nervous system parameters.
-    fn neuron_minimum_stake_e8s_or_panic(&self) -> u64 {
-        self.nervous_system_parameters_or_panic()
-            .neuron_minimum_stake_e8s
-            .expect("NervousSystemParameters must have neuron_minimum_stake_e8s")
-    }
+        let mut sns_token_amount_e8s = deposit.sns_token_amount_e8s;
+        if sns_token_amount_e8s > 0 {
+            let transaction_fee_e8s = self.transaction_fee_e8s_or_panic();
+            let treasury_subaccount = compute_distribution_subaccount_bytes(
+                self.env.canister_id().get(),
+                TREASURY_SUBACCOUNT_NONCE,
+            );
+            if let Err(e) = self
+                .ledger
+                .transfer_funds(
+                    sns_token_amount_e8s,
+                    transaction_fee_e8s,
+                    Some(treasury_subaccount),
+                    to,
+                    proposal_id,
+                )
+                .await
+            {
+                failures.push(format!("Error making SNS Token treasury transfer: {}", e));
+                sns_token_amount_e8s = 0;
+            }
+        }
 
-    fn max_followees_per_function_or_panic(&self) -> u64 {
-        self.nervous_system_parameters_or_panic()
-            .max_followees_per_function
-            .expect("NervousSystemParameters must have max_followees_per_function")
-    }
+        if icp_amount_e8s > 0 || sns_token_amount_e8s > 0 {
+            self.push_to_treasury_manager_audit_trail(
+                treasury_manager_canister_id,
+                proposal_id,
+                format!(
+                    "Deposited {} e8s of ICP and {} e8s of SNS tokens.",
+                    icp_amount_e8s, sns_token_amount_e8s
+                ),
+            );
 
-    /// Inserts a proposals that has already been validated in the state.
-    ///
-    /// This is a low-level function that makes no verification whatsoever.
-    fn insert_proposal(&mut self, pid: u64, data: ProposalData) {
-        let initial_voting_period_seconds = data.initial_voting_period_seconds;
+            let request = DepositRequest {
+                icp_amount_e8s,
+                sns_token_amount_e8s,
+                withdraw_accounts: self.treasury_manager_withdraw_accounts(),
+            };
+            match self
+                .call_treasury_manager(
+                    treasury_manager_canister_id,
+                    "deposit",
+                    Encode!(&request).unwrap(),
+                )
+                .await
+            {
+                Ok(report) => self.record_treasury_manager_report(
+                    treasury_manager_canister_id,
+                    proposal_id,
+                    report,
+                ),
+                Err(e) => failures.push(format!(
+                    "Could not notify the treasury manager of the deposit: {}",
+                    e
+                )),
+            }
+        }
 
-        self.closest_proposal_deadline_timestamp_seconds = std::cmp::min(
-            data.proposal_creation_timestamp_seconds + initial_voting_period_seconds,
-            self.closest_proposal_deadline_timestamp_seconds,
+        if failures.is_empty() {
+            return Ok(());
+        }
+        let failure_reason = failures.join("\n");
+        self.push_to_treasury_manager_audit_trail(
+            treasury_manager_canister_id,
+            proposal_id,
+            format!("The deposit failed: {}", failure_reason),
         );
-        self.proto.proposals.insert(pid, data);
-        self.process_proposal(pid);
+        Err(GovernanceError::new_with_message(
+            ErrorType::External,
+            failure_reason,
+        ))
     }
 
-    /// Returns the next proposal id.
-    fn next_proposal_id(&self) -> u64 {
-        // Correctness is based on the following observations:
-        // * Proposal GC never removes the proposal with highest ID.
+    /// Executes a WithdrawFromTreasuryManager proposal by asking the treasury manager to
+    /// return all funds it manages to the SNS treasury.
+    async fn perform_withdraw_from_treasury_manager(
+        &mut self,
+        proposal_id: u64,
+        withdraw: WithdrawFromTreasuryManager,
+    ) -> Result<(), GovernanceError> {
+        let treasury_manager_canister_id =
+            withdraw.treasury_manager_canister_id.ok_or_else(|| {
+                GovernanceError::new_with_message(
+                    ErrorType::InvalidProposal,
+                    "Expected withdrawal to have a treasury_manager_canister_id",
+                )
+            })?;
+        if !self.proto.treasury_managers.iter().any(|treasury_manager| {
+            treasury_manager.canister_id == Some(treasury_manager_canister_id)
+        }) {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::NotFound,
+                format!(
+                    "No funds were ever deposited into treasury manager {}.",
+                    treasury_manager_canister_id
+                ),
+            ));
+        }
+
+        let request = WithdrawRequest {
+            withdraw_accounts: self.treasury_manager_withdraw_accounts(),
+        };
+        match self
+            .call_treasury_manager(
+                treasury_manager_canister_id,
+                "withdraw",
+                Encode!(&request).unwrap(),
+            )
+            .await
+        {
+            Ok(report) => {
+                self.push_to_treasury_manager_audit_trail(
+                    treasury_manager_canister_id,
+                    proposal_id,
+                    "Withdrew all managed funds to the SNS treasury.".to_string(),
+                );
+                self.record_treasury_manager_report(
+                    treasury_manager_canister_id,
+                    proposal_id,
+                    report,
+                );
+                Ok(())
+            }
+            Err(e) => {
+                let failure_reason = format!("Could not withdraw from the treasury manager: {}", e);
+                self.push_to_treasury_manager_audit_trail(
+                    treasury_manager_canister_id,
+                    proposal_id,
+                    failure_reason.clone(),
+                );
+                Err(GovernanceError::new_with_message(
+                    ErrorType::External,
+                    failure_reason,
+                ))
+            }
+        }
+    }
+
+    /// Returns an error if `canister_id` is not a dapp canister registered with SNS root.
+    async fn err_if_not_registered_dapp_canister(
+        &self,
+        canister_id: PrincipalId,
+    ) -> Result<(), GovernanceError> {
+        let registered_dapp_canister_ids =
+            get_all_sns_canisters(&*self.env, self.proto.root_canister_id_or_panic())
+                .await
+                .map_err(|e| {
+                    GovernanceError::new_with_message(
+                        ErrorType::External,
+                        format!("Unable to determine the registered dapp canisters: {}", e),
+                    )
+                })?
+                .dapps;
+
+        if !registered_dapp_canister_ids.contains(&canister_id) {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!("{} is not a registered dapp canister.", canister_id),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The SNS treasury accounts, to which treasury managers return the funds they manage.
+    fn treasury_manager_withdraw_accounts(&self) -> WithdrawAccounts {
+        let governance_canister_id = self.env.canister_id().get();
+        WithdrawAccounts {
+            icp: Account {
+                owner: governance_canister_id.0,
+                subaccount: None,
+            },
+            sns_token: Account {
+                owner: governance_canister_id.0,
+                subaccount: Some(compute_distribution_subaccount_bytes(
+                    governance_canister_id,
+                    TREASURY_SUBACCOUNT_NONCE,
+                )),
+            },
+        }
+    }
+
+    /// Calls a method of the treasury manager interface (see treasury_manager.did).
+    async fn call_treasury_manager(
+        &self,
+        treasury_manager_canister_id: PrincipalId,
+        method_name: &str,
+        arg: Vec<u8>,
+    ) -> Result<TreasuryManagerReport, String> {
+        let canister_id = CanisterId::try_from(treasury_manager_canister_id)
+            .map_err(|err| format!("Invalid treasury manager canister ID: {err:?}"))?;
+        let reply = self
+            .env
+            .call_canister(canister_id, method_name, arg)
+            .await
+            .map_err(|err| format!("Canister method call failed: {err:?}"))?;
+        Decode!(&reply, TreasuryManagerResult)
+            .map_err(|err| format!("Could not decode the response to {method_name}: {err:?}"))?
+    }
+
+    /// Records the balances and audit trail reported by a treasury manager.
+    fn record_treasury_manager_report(
+        &mut self,
+        treasury_manager_canister_id: PrincipalId,
+        proposal_id: u64,
+        report: TreasuryManagerReport,
+    ) {
+        let TreasuryManagerReport {
+            icp_balance_e8s,
+            sns_token_balance_e8s,
+            audit_trail,
+        } = report;
+        let now = self.env.now();
+
+        let treasury_manager = self.treasury_manager_mut(treasury_manager_canister_id);
+        treasury_manager.balances = Some(TreasuryManagerBalances {
+            icp_e8s: icp_balance_e8s,
+            sns_token_e8s: sns_token_balance_e8s,
+        });
+        treasury_manager.balances_timestamp_seconds = Some(now);
+
+        // A treasury manager may report events that were already recorded, e.g. when it
+        // reports its full audit trail each time. Only events after the most recently
+        // recorded one are appended.
+        let reported_entries = treasury_manager
+            .audit_trail
+            .iter()
+            .filter(|entry| entry.reported_by_treasury_manager);
+        let last_reported_timestamp_seconds = reported_entries
+            .clone()
+            .filter_map(|entry| entry.timestamp_seconds)
+            .max();
+        let new_entries: Vec<_> = audit_trail
+            .into_iter()
+            .filter(|event| match last_reported_timestamp_seconds {
+                None => true,
+                Some(last) => {
+                    event.timestamp_seconds > last
+                        || (event.timestamp_seconds == last
+                            && !reported_entries.clone().any(|entry| {
+                                entry.timestamp_seconds == Some(last)
+                                    && entry.human_readable == event.human_readable
+                            }))
+                }
+            })
+            .map(|event| TreasuryManagerAuditTrailEntry {
+                timestamp_seconds: Some(event.timestamp_seconds),
+                proposal_id: Some(proposal_id),
+                human_readable: event.human_readable,
+                reported_by_treasury_manager: true,
+            })
+            .collect();
+        treasury_manager.audit_trail.extend(new_entries);
+        truncate_treasury_manager_audit_trail(&mut treasury_manager.audit_trail);
+    }
+
+    /// Records an entry in the audit trail of a treasury manager.
+    fn push_to_treasury_manager_audit_trail(
+        &mut self,
+        treasury_manager_canister_id: PrincipalId,
+        proposal_id: u64,
+        human_readable: String,
+    ) {
+        let entry = TreasuryManagerAuditTrailEntry {
+            timestamp_seconds: Some(self.env.now()),
+            proposal_id: Some(proposal_id),
+            human_readable,
+            reported_by_treasury_manager: false,
+        };
+        let audit_trail = &mut self
+            .treasury_manager_mut(treasury_manager_canister_id)
+            .audit_trail;
+        audit_trail.push(entry);
+        truncate_treasury_manager_audit_trail(audit_trail);
+    }
+
+    /// Returns the record of the given treasury manager, creating it if needed.
+    fn treasury_manager_mut(
+        &mut self,
+        treasury_manager_canister_id: PrincipalId,
+    ) -> &mut TreasuryManager {
+        let treasury_managers = &mut self.proto.treasury_managers;
+        let index = match treasury_managers.iter().position(|treasury_manager| {
+            treasury_manager.canister_id == Some(treasury_manager_canister_id)
+        }) {
+            Some(index) => index,
+            None => {
+                treasury_managers.push(TreasuryManager {
+                    canister_id: Some(treasury_manager_canister_id),
+                    ..Default::default()
+                });
+                treasury_managers.len() - 1
+            }
+        };
+        &mut treasury_managers[index]
+    }
+
+    async fn perform_mint_sns_tokens(
+        &mut self,
+        mint: MintSnsTokens,
+    ) -> Result<(), GovernanceError> {
+        let to = Account {
+            owner: mint
+                .to_principal
+                .ok_or(GovernanceError::new_with_message(
+                    ErrorType::InvalidProposal,
+                    "Expected mint to have a target principal",
+                ))?
+                .0,
+            subaccount: mint
+                .to_subaccount
+                .as_ref()
+                .map(|s| bytes_to_subaccount(&s.subaccount[..]))
+                .transpose()?,
+        };
+        let amount_e8s = mint.amount_e8s.ok_or(GovernanceError::new_with_message(
+            ErrorType::InvalidProposal,
+            "Expected MintSnsTokens to have an an amount_e8s",
+        ))?;
+        self.ledger
+            .transfer_funds(amount_e8s, 0, None, to, mint.memo())
+            .await?;
+        Ok(())
+    }
+
+    /// Executes a ManageLedgerParameters proposal by upgrading the SNS ledger (through SNS
+    /// root) to the ledger WASM of the currently deployed SNS version, passing the new
+    /// parameters as upgrade arguments. Reinstalling the same WASM keeps the ledger (and thus
+    /// its index and archives) on the blessed upgrade path.
+    async fn perform_manage_ledger_parameters(
+        &mut self,
+        proposal_id: u64,
+        manage_ledger_parameters: ManageLedgerParameters,
+    ) -> Result<(), GovernanceError> {
+        self.err_if_any_other_upgrade_is_in_progress(proposal_id)?;
+
+        let current_version = self.proto.deployed_version_or_panic();
+        let ledger_canister_id = self.proto.ledger_canister_id_or_panic();
+
+        let ledger_wasm = get_wasm(
+            &*self.env,
+            current_version.ledger_wasm_hash,
+            SnsCanisterType::Ledger,
+        )
+        .await
+        .map_err(|e| {
+            GovernanceError::new_with_message(
+                ErrorType::External,
+                format!("Could not execute proposal: {}", e),
+            )
+        })?
+        .wasm;
+
+        let ManageLedgerParameters {
+            transfer_fee,
+            token_name,
+            token_symbol,
+            token_logo,
+        } = manage_ledger_parameters;
+
+        // The ledger replaces all of its metadata on upgrade, so the new logo is merged into
+        // the metadata that the ledger currently stores.
+        let metadata = match token_logo {
+            Some(token_logo) => Some(
+                self.ledger_metadata_with_logo(ledger_canister_id, token_logo)
+                    .await?,
+            ),
+            None => None,
+        };
+
+        let ledger_upgrade_arg = Encode!(&LedgerArgument::Upgrade(Some(LedgerUpgradeArgs {
+            metadata,
+            token_name,
+            token_symbol,
+            transfer_fee: transfer_fee.map(candid::Nat::from),
+        })))
+        .unwrap();
+
+        self.upgrade_non_root_canister(
+            ledger_canister_id,
+            ledger_wasm,
+            ledger_upgrade_arg,
+            CanisterInstallMode::Upgrade,
+        )
+        .await?;
+
+        // Keep the fee that governance uses for its own ledger transactions in sync.
+        if let Some(transfer_fee) = transfer_fee {
+            if let Some(parameters) = self.proto.parameters.as_mut() {
+                parameters.transaction_fee_e8s = Some(transfer_fee);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the metadata stored in the ledger, with the token logo replaced by `token_logo`.
+    /// The standard `icrc1:*` entries that the ledger derives from its state (name, symbol,
+    /// fee, ...) are left out, except for the logo.
+    async fn ledger_metadata_with_logo(
+        &self,
+        ledger_canister_id: CanisterId,
+        token_logo: String,
+    ) -> Result<Vec<(String, MetadataValue)>, GovernanceError> {
+        const DERIVED_METADATA_KEYS: [&str; 5] = [
+            "icrc1:decimals",
+            "icrc1:name",
+            "icrc1:symbol",
+            "icrc1:fee",
+            "icrc1:max_memo_length",
+        ];
+
+        let reply = self
+            .env
+            .call_canister(ledger_canister_id, "icrc1_metadata", Encode!().unwrap())
+            .await
+            .map_err(|err| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Could not get the ledger metadata: {:?}", err),
+                )
+            })?;
+        let metadata = Decode!(&reply, Vec<(String, MetadataValue)>).map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::External,
+                format!("Could not decode the ledger metadata: {}", err),
+            )
+        })?;
+
+        let mut metadata: Vec<_> = metadata
+            .into_iter()
+            .filter(|(key, _)| {
+                key != ICRC1_TOKEN_LOGO_KEY && !DERIVED_METADATA_KEYS.contains(&key.as_str())
+            })
+            .collect();
+        metadata.push((
+            ICRC1_TOKEN_LOGO_KEY.to_string(),
+            MetadataValue::Text(token_logo),
+        ));
+        Ok(metadata)
+    }
+
+    // Returns an option with the NervousSystemParameters
+    fn nervous_system_parameters(&self) -> Option<&NervousSystemParameters> {
+        self.proto.parameters.as_ref()
+    }
+
+    /// Returns the NervousSystemParameters or panics
+    fn nervous_system_parameters_or_panic(&self) -> &NervousSystemParameters {
+        self.nervous_system_parameters()
+            .expect("NervousSystemParameters not present")
+    }
+
+    /// Returns the list of permissions that a principal that claims a neuron will have for
+    /// that neuron, as defined in the nervous system parameters' neuron_claimer_permissions.
+    fn neuron_claimer_permissions_or_panic(&self) -> NeuronPermissionList {
+        self.nervous_system_parameters_or_panic()
+            .neuron_claimer_permissions
+            .as_ref()
+            .expect("NervousSystemParameters.neuron_claimer_permissions must be present")
+            .clone()
+    }
+
+    /// Returns the default followees that a newly claimed neuron will have, as defined in
+    /// the nervous system parameters' default_followees.
+    /// TODO NNS1-2169: default followees are not currently supported.
+    fn default_followees_or_panic(&self) -> DefaultFollowees {
+        self.nervous_system_parameters_or_panic()
+            .default_followees
+            .as_ref()
+            .expect("NervousSystemParameters.default_followees must be present")
+            .clone()
+    }
+
+    /// Returns the ledger's transaction fee as stored in the service nervous parameters.
+    fn transaction_fee_e8s_or_panic(&self) -> u64 {
+        self.nervous_system_parameters_or_panic()
+            .transaction_fee_e8s
+            .expect("NervousSystemParameters must have transaction_fee_e8s")
+    }
+
+    /// Returns the initial voting period of proposals.
+    fn initial_voting_period_seconds_or_panic(&self) -> u64 {
+        self.nervous_system_parameters_or_panic()
+            .initial_voting_period_seconds
+            .expect("NervousSystemParameters must have initial_voting_period_seconds")
+    }
+
+    /// Returns the wait for quiet deadline extension period for proposals.
+    fn wait_for_quiet_deadline_increase_seconds_or_panic(&self) -> u64 {
+        self.nervous_system_parameters_or_panic()
+            .wait_for_quiet_deadline_increase_seconds
+            .expect("NervousSystemParameters must have wait_for_quiet_deadline_increase_seconds")
+    }
+
+    /// Returns the neuron minimum stake e8s from the nervous system parameters.
+    fn neuron_minimum_stake_e8s_or_panic(&self) -> u64 {
+        self.nervous_system_parameters_or_panic()
+            .neuron_minimum_stake_e8s
+            .expect("NervousSystemParameters must have neuron_minimum_stake_e8s")
+    }
+
+    fn max_followees_per_function_or_panic(&self) -> u64 {
+        self.nervous_system_parameters_or_panic()
+            .max_followees_per_function
+            .expect("NervousSystemParameters must have max_followees_per_function")
+    }
+
+    /// Inserts a proposals that has already been validated in the state.
+    ///
+    /// This is a low-level function that makes no verification whatsoever.
+    fn insert_proposal(&mut self, pid: u64, data: ProposalData) {
+        let initial_voting_period_seconds = data.initial_voting_period_seconds;
+
+        self.closest_proposal_deadline_timestamp_seconds = std::cmp::min(
+            data.proposal_creation_timestamp_seconds + initial_voting_period_seconds,
+            self.closest_proposal_deadline_timestamp_seconds,
+        );
+        self.proto.proposals.insert(pid, data);
+        self.process_proposal(pid);
+    }
+
+    /// Returns the next proposal id.
+    fn next_proposal_id(&self) -> u64 {
+        // Correctness is based on the following observations:
+        // * Proposal GC never removes the proposal with highest ID.
         // * The proposal map is a BTreeMap, so the proposals are ordered by id.
         self.proto
             .proposals
@@ -4402,6 +5067,10 @@ impl Governance {
             self.check_upgrade_status().await;
         }
 
+        if self.should_advance_towards_target_version() {
+            self.advance_towards_target_version().await;
+        }
+
         let should_distribute_rewards = measure_span(
             self.profiling_information,
             "should_distribute_rewards",
@@ -5015,9 +5684,20 @@ impl Governance {
                     self.env.now(),
                     target_version
                 );
-                self.set_proposal_execution_status(proposal_id, Ok(()));
-                self.proto.deployed_version = Some(target_version);
-                self.proto.pending_version = None;
+                if proposal_id != AUTOMATIC_UPGRADE_STEP_PROPOSAL_ID {
+                    self.set_proposal_execution_status(proposal_id, Ok(()));
+                }
+                self.push_to_upgrade_journal(upgrade_journal_entry::Event::UpgradeOutcome(
+                    UpgradeOutcome {
+                        succeeded: true,
+                        human_readable: format!(
+                            "Upgrade marked successful at {} seconds from UNIX epoch.",
+                            self.env.now()
+                        ),
+                    },
+                ));
+                self.proto.deployed_version = Some(target_version);
+                self.proto.pending_version = None;
             }
             Err(errors) => {
                 // We are past mark_failed_at_seconds.
@@ -5040,17 +5720,198 @@ impl Governance {
 
     // This method sets internal state to remove pending_version and sets the proposal status to
     // an error for an UpgradeSnsToNextVersion actions failure.  This unblocks further upgrade proposals.
+    // If the failed upgrade was an automatic step towards target_version, the target version is
+    // cleared instead, so that the failing step is not retried over and over again.
     fn fail_sns_upgrade_to_next_version_proposal(
         &mut self,
         proposal_id: u64,
         error: GovernanceError,
     ) {
         log!(ERROR, "{}", error.error_message);
-        let result = Err(error);
-        self.set_proposal_execution_status(proposal_id, result);
+        self.push_to_upgrade_journal(upgrade_journal_entry::Event::UpgradeOutcome(
+            UpgradeOutcome {
+                succeeded: false,
+                human_readable: error.error_message.clone(),
+            },
+        ));
+        if proposal_id == AUTOMATIC_UPGRADE_STEP_PROPOSAL_ID {
+            self.reset_target_version("An automatic upgrade step failed.".to_string());
+        } else {
+            let result = Err(error);
+            self.set_proposal_execution_status(proposal_id, result);
+        }
         self.proto.pending_version = None;
     }
 
+    /// Returns an error if an upgrade other than the one initiated by `executing_proposal_id`
+    /// is in progress, be it initiated by another proposal or an automatic step towards
+    /// `target_version`.
+    fn err_if_any_other_upgrade_is_in_progress(
+        &self,
+        executing_proposal_id: u64,
+    ) -> Result<(), GovernanceError> {
+        let automatic_upgrade_step_is_in_progress = self.is_advancing_towards_target_version()
+            || self
+                .proto
+                .pending_version
+                .as_ref()
+                .map_or(false, |pending_version| {
+                    pending_version.proposal_id == AUTOMATIC_UPGRADE_STEP_PROPOSAL_ID
+                });
+        if automatic_upgrade_step_is_in_progress {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::ResourceExhausted,
+                "An automatic upgrade towards the SNS target version is currently in progress. \
+                 Please, try again later.",
+            ));
+        }
+
+        err_if_another_upgrade_is_in_progress(&self.proto.proposals, executing_proposal_id)
+    }
+
+    /// Returns whether an automatic upgrade step towards `target_version` should be initiated,
+    /// i.e. the SNS is not yet at the target version and no other upgrade is in progress.
+    fn should_advance_towards_target_version(&self) -> bool {
+        let Some(target_version) = self.proto.target_version.as_ref() else {
+            return false;
+        };
+
+        self.proto.deployed_version.as_ref() != Some(target_version)
+            && self.proto.pending_version.is_none()
+            && !self.is_advancing_towards_target_version()
+            && err_if_another_upgrade_is_in_progress(
+                &self.proto.proposals,
+                AUTOMATIC_UPGRADE_STEP_PROPOSAL_ID,
+            )
+            .is_ok()
+    }
+
+    /// Returns whether an automatic upgrade step is currently being initiated.
+    fn is_advancing_towards_target_version(&self) -> bool {
+        self.advance_towards_target_version_lock_timestamp_seconds
+            .map_or(false, |lock_timestamp_seconds| {
+                self.env.now()
+                    < lock_timestamp_seconds + ADVANCE_TOWARDS_TARGET_VERSION_LOCK_TIMEOUT_SECONDS
+            })
+    }
+
+    /// Initiates the next upgrade step towards `target_version`. The completion of the step is
+    /// then checked by `check_upgrade_status`, like for UpgradeSnsToNextVersion proposals. If the
+    /// step cannot be initiated, the target version is cleared.
+    async fn advance_towards_target_version(&mut self) {
+        self.advance_towards_target_version_lock_timestamp_seconds = Some(self.env.now());
+        let result = self.try_advance_towards_target_version().await;
+        self.advance_towards_target_version_lock_timestamp_seconds = None;
+
+        if let Err(message) = result {
+            self.reset_target_version(format!(
+                "Could not initiate an automatic upgrade step: {}",
+                message
+            ));
+        }
+    }
+
+    async fn try_advance_towards_target_version(&mut self) -> Result<(), String> {
+        let current_version = self
+            .proto
+            .deployed_version
+            .clone()
+            .ok_or_else(|| "Governance has no recorded deployed_version.".to_string())?;
+        let target_version = self
+            .proto
+            .target_version
+            .clone()
+            .ok_or_else(|| "Governance has no target_version.".to_string())?;
+        let root_canister_id = self.proto.root_canister_id_or_panic();
+
+        // Make sure the target is (still) ahead of the deployed version, e.g. in case the SNS
+        // was upgraded past it through UpgradeSnsToNextVersion proposals.
+        let (_, upgrade_steps) = get_upgrade_steps_to_target_version(
+            &*self.env,
+            &current_version,
+            Some(&target_version),
+            self.env.canister_id().get(),
+        )
+        .await?;
+
+        // The target may have been changed by a proposal while awaiting the response.
+        if self.proto.target_version.as_ref() != Some(&target_version) {
+            return Ok(());
+        }
+
+        let next_version = upgrade_steps
+            .into_iter()
+            .next()
+            .ok_or_else(|| "There are no upgrade steps to the target version.".to_string())?;
+        let upgrade_params = get_upgrade_params_for_next_version(
+            &*self.env,
+            root_canister_id,
+            &current_version,
+            next_version,
+        )
+        .await?;
+
+        self.initiate_upgrade_to_next_sns_version(
+            AUTOMATIC_UPGRADE_STEP_PROPOSAL_ID,
+            current_version,
+            upgrade_params,
+        )
+        .await
+        .map(|_| ())
+        .map_err(|e| e.error_message)
+    }
+
+    /// Clears `target_version`, which stops automatic upgrades, and records why.
+    fn reset_target_version(&mut self, human_readable: String) {
+        log!(ERROR, "Clearing the SNS target version: {}", human_readable);
+        let old_target_version = self.proto.target_version.take();
+        self.push_to_upgrade_journal(upgrade_journal_entry::Event::TargetVersionReset(
+            TargetVersionReset {
+                old_target_version,
+                human_readable,
+            },
+        ));
+    }
+
+    /// Records an event in the upgrade journal, dropping the oldest entries if needed.
+    fn push_to_upgrade_journal(&mut self, event: upgrade_journal_entry::Event) {
+        let timestamp_seconds = self.env.now();
+        let entries = &mut self
+            .proto
+            .upgrade_journal
+            .get_or_insert_with(Default::default)
+            .entries;
+
+        entries.push(UpgradeJournalEntry {
+            timestamp_seconds: Some(timestamp_seconds),
+            event: Some(event),
+        });
+        if entries.len() > MAX_UPGRADE_JOURNAL_ENTRIES {
+            let excess = entries.len() - MAX_UPGRADE_JOURNAL_ENTRIES;
+            entries.drain(..excess);
+        }
+    }
+
+    /// Returns the upgrade journal together with the deployed and target versions.
+    pub fn get_upgrade_journal(&self, _: GetUpgradeJournalRequest) -> GetUpgradeJournalResponse {
+        GetUpgradeJournalResponse {
+            deployed_version: self.proto.deployed_version.clone(),
+            target_version: self.proto.target_version.clone(),
+            upgrade_journal: self.proto.upgrade_journal.clone(),
+        }
+    }
+
+    /// Returns the treasury managers that SNS treasury funds have been deposited into, with
+    /// the balances and audit trails they reported.
+    pub fn get_treasury_managers(
+        &self,
+        _: GetTreasuryManagersRequest,
+    ) -> GetTreasuryManagersResponse {
+        GetTreasuryManagersResponse {
+            treasury_managers: self.proto.treasury_managers.clone(),
+        }
+    }
+
     /// Checks whether the heap can grow.
     fn check_heap_can_grow(&self) -> Result<(), GovernanceError> {
         match self.env.heap_growth_potential() {
@@ -5286,13 +6147,23 @@ impl Governance {
     }
 }
 
+/// Drops the oldest entries of a treasury manager's audit trail beyond
+/// MAX_TREASURY_MANAGER_AUDIT_TRAIL_ENTRIES.
+fn truncate_treasury_manager_audit_trail(audit_trail: &mut Vec<TreasuryManagerAuditTrailEntry>) {
+    if audit_trail.len() > MAX_TREASURY_MANAGER_AUDIT_TRAIL_ENTRIES {
+        let excess = audit_trail.len() - MAX_TREASURY_MANAGER_AUDIT_TRAIL_ENTRIES;
+        audit_trail.drain(..excess);
+    }
+}
+
 fn err_if_another_upgrade_is_in_progress(
     id_to_proposal_data: &BTreeMap</* proposal ID */ u64, ProposalData>,
     executing_proposal_id: u64,
 ) -> Result<(), GovernanceError> {
-    let upgrade_action_ids: [u64; 2] = [
+    let upgrade_action_ids: [u64; 3] = [
         (&Action::UpgradeSnsControlledCanister(UpgradeSnsControlledCanister::default())).into(),
         (&Action::UpgradeSnsToNextVersion(UpgradeSnsToNextVersion::default())).into(),
+        (&Action::ManageLedgerParameters(ManageLedgerParameters::default())).into(),
     ];
 
     for (other_proposal_id, proposal_data) in id_to_proposal_data {
@@ -5378,14 +6249,15 @@ mod tests {
             manage_neuron_response,
             nervous_system_function::{FunctionType, GenericNervousSystemFunction},
             neuron, Account as AccountProto, Motion, NeuronPermissionType, ProposalData,
-            ProposalId, Tally, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion,
-            VotingRewardsParameters, WaitForQuietState,
+            ProposalId, Tally, TreasuryManagerParameters, UpgradeSnsControlledCanister,
+            UpgradeSnsToNextVersion, VotingRewardsParameters, WaitForQuietState,
         },
         reward,
         sns_upgrade::{
             CanisterSummary, GetNextSnsVersionRequest, GetNextSnsVersionResponse,
             GetSnsCanistersSummaryRequest, GetSnsCanistersSummaryResponse, GetWasmRequest,
-            GetWasmResponse, SnsCanisterType, SnsVersion, SnsWasm,
+            GetWasmResponse, ListUpgradeStep, ListUpgradeStepsRequest, ListUpgradeStepsResponse,
+            SnsCanisterType, SnsVersion, SnsWasm,
         },
         types::{test_helpers::NativeEnvironment, ONE_DAY_SECONDS},
     };
@@ -6894,6 +7766,609 @@ mod tests {
         }
     }
 
+    fn set_list_upgrade_steps_response(
+        env: &mut NativeEnvironment,
+        current_version: &SnsVersion,
+        steps: Vec<SnsVersion>,
+    ) {
+        env.set_call_canister_response(
+            SNS_WASM_CANISTER_ID,
+            "list_upgrade_steps",
+            Encode!(&ListUpgradeStepsRequest {
+                starting_at: Some(current_version.clone()),
+                sns_governance_canister_id: Some(TEST_GOVERNANCE_CANISTER_ID.get()),
+                limit: 0,
+            })
+            .unwrap(),
+            Ok(Encode!(&ListUpgradeStepsResponse {
+                steps: steps
+                    .into_iter()
+                    .map(|version| ListUpgradeStep {
+                        version: Some(version),
+                    })
+                    .collect(),
+            })
+            .unwrap()),
+        );
+    }
+
+    fn versions_for_target_version_tests() -> (SnsVersion, SnsVersion) {
+        let current_version = SnsVersion {
+            root_wasm_hash: vec![1, 2, 3],
+            governance_wasm_hash: vec![2, 3, 4],
+            ledger_wasm_hash: vec![3, 4, 5],
+            swap_wasm_hash: vec![4, 5, 6],
+            archive_wasm_hash: vec![5, 6, 7],
+            index_wasm_hash: vec![6, 7, 8],
+        };
+        let next_version = SnsVersion {
+            governance_wasm_hash: vec![9, 9, 9],
+            ..current_version.clone()
+        };
+        (current_version, next_version)
+    }
+
+    #[test]
+    fn test_advance_sns_target_version_sets_target_version() {
+        let (current_version, next_version) = versions_for_target_version_tests();
+        let mut env = NativeEnvironment::new(Some(*TEST_GOVERNANCE_CANISTER_ID));
+        set_list_upgrade_steps_response(
+            &mut env,
+            &current_version,
+            vec![current_version.clone(), next_version.clone()],
+        );
+
+        let action = Action::AdvanceSnsTargetVersion(AdvanceSnsTargetVersion { new_target: None });
+        let proposal_id = 1;
+        let proposal = ProposalData {
+            action: (&action).into(),
+            id: Some(proposal_id.into()),
+            ballots: btreemap! {
+                "neuron 1".to_string() => Ballot {
+                    vote: Vote::Yes as i32,
+                    voting_power: 9001,
+                    cast_timestamp_seconds: 1,
+                },
+            },
+            wait_for_quiet_state: Some(WaitForQuietState::default()),
+            proposal: Some(Proposal {
+                title: "Advance SNS target version".to_string(),
+                action: Some(action),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mut governance = Governance::new(
+            GovernanceProto {
+                proposals: btreemap! { proposal_id => proposal },
+                root_canister_id: Some(TEST_ROOT_CANISTER_ID.get()),
+                deployed_version: Some(current_version.clone().into()),
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::new(env),
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+
+        let proposal_data = execute_proposal(&mut governance, proposal_id);
+        assert_eq!(proposal_data.status(), ProposalDecisionStatus::Executed);
+
+        let response = governance.get_upgrade_journal(GetUpgradeJournalRequest {});
+        assert_eq!(response.deployed_version, Some(current_version.into()));
+        assert_eq!(response.target_version, Some(next_version.clone().into()));
+        let entries = response.upgrade_journal.unwrap().entries;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].event,
+            Some(upgrade_journal_entry::Event::TargetVersionSet(
+                TargetVersionSet {
+                    old_target_version: None,
+                    new_target_version: Some(next_version.into()),
+                    proposal_id,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_advance_towards_target_version_initiates_automatic_upgrade_step() {
+        let (current_version, next_version) = versions_for_target_version_tests();
+        let mut env = setup_env_for_sns_upgrade_to_next_version_test(
+            &current_version,
+            &next_version,
+            vec![9, 9, 9],
+            SnsCanisterType::Governance,
+            std_sns_canisters_summary_response(),
+        );
+        set_list_upgrade_steps_response(
+            &mut env,
+            &current_version,
+            vec![current_version.clone(), next_version.clone()],
+        );
+        let assert_required_calls = env.get_assert_required_calls_fn();
+        let now = env.now();
+
+        let mut governance = Governance::new(
+            GovernanceProto {
+                root_canister_id: Some(TEST_ROOT_CANISTER_ID.get()),
+                ledger_canister_id: Some(TEST_LEDGER_CANISTER_ID.get()),
+                deployed_version: Some(current_version.clone().into()),
+                target_version: Some(next_version.clone().into()),
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::new(env),
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+
+        assert!(governance.should_advance_towards_target_version());
+        governance
+            .advance_towards_target_version()
+            .now_or_never()
+            .unwrap();
+
+        assert_required_calls();
+        assert_eq!(
+            governance.proto.pending_version.clone().unwrap(),
+            UpgradeInProgress {
+                target_version: Some(next_version.clone().into()),
+                mark_failed_at_seconds: now + 5 * 60,
+                checking_upgrade_lock: 0,
+                proposal_id: AUTOMATIC_UPGRADE_STEP_PROPOSAL_ID,
+            }
+        );
+        // While the step is in progress, no further step is initiated.
+        assert!(!governance.should_advance_towards_target_version());
+        assert_eq!(
+            governance.proto.target_version,
+            Some(next_version.clone().into())
+        );
+        let entries = governance.proto.upgrade_journal.clone().unwrap().entries;
+        assert_eq!(
+            entries.last().unwrap().event,
+            Some(upgrade_journal_entry::Event::UpgradeStarted(
+                UpgradeStarted {
+                    current_version: Some(current_version.into()),
+                    expected_version: Some(next_version.into()),
+                    proposal_id: None,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_advance_towards_target_version_resets_target_version_on_failure() {
+        let (current_version, next_version) = versions_for_target_version_tests();
+        let mut env = NativeEnvironment::new(Some(*TEST_GOVERNANCE_CANISTER_ID));
+        // The target version is no longer on the upgrade path.
+        set_list_upgrade_steps_response(&mut env, &current_version, vec![current_version.clone()]);
+
+        let mut governance = Governance::new(
+            GovernanceProto {
+                root_canister_id: Some(TEST_ROOT_CANISTER_ID.get()),
+                deployed_version: Some(current_version.into()),
+                target_version: Some(next_version.clone().into()),
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::new(env),
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+
+        governance
+            .advance_towards_target_version()
+            .now_or_never()
+            .unwrap();
+
+        assert!(governance.proto.pending_version.is_none());
+        assert!(governance.proto.target_version.is_none());
+        assert!(governance
+            .advance_towards_target_version_lock_timestamp_seconds
+            .is_none());
+        let entries = governance.proto.upgrade_journal.clone().unwrap().entries;
+        assert_eq!(entries.len(), 1);
+        assert_matches!(
+            &entries[0].event,
+            Some(upgrade_journal_entry::Event::TargetVersionReset(TargetVersionReset {
+                old_target_version,
+                ..
+            })) if *old_target_version == Some(next_version.into())
+        );
+    }
+
+    fn treasury_manager_withdraw_accounts_for_test() -> crate::treasury_manager::WithdrawAccounts {
+        let governance_canister_id = TEST_GOVERNANCE_CANISTER_ID.get();
+        crate::treasury_manager::WithdrawAccounts {
+            icp: Account {
+                owner: governance_canister_id.0,
+                subaccount: None,
+            },
+            sns_token: Account {
+                owner: governance_canister_id.0,
+                subaccount: Some(compute_distribution_subaccount_bytes(
+                    governance_canister_id,
+                    TREASURY_SUBACCOUNT_NONCE,
+                )),
+            },
+        }
+    }
+
+    fn treasury_manager_approving_parameters(
+        treasury_manager_canister_id: PrincipalId,
+    ) -> NervousSystemParameters {
+        NervousSystemParameters {
+            treasury_manager_parameters: Some(TreasuryManagerParameters {
+                approved_canister_ids: vec![treasury_manager_canister_id],
+                max_icp_amount_e8s_per_proposal: Some(1_000 * E8),
+                max_sns_token_amount_e8s_per_proposal: Some(1_000 * E8),
+            }),
+            ..NervousSystemParameters::with_default_values()
+        }
+    }
+
+    #[test]
+    fn test_deposit_to_treasury_manager_records_report() {
+        use crate::treasury_manager::{AuditTrailEvent, DepositRequest};
+
+        let treasury_manager_canister_id = TEST_DAPP_CANISTER_IDS[0];
+        let mut env = NativeEnvironment::new(Some(*TEST_GOVERNANCE_CANISTER_ID));
+        env.set_call_canister_response(
+            *TEST_ROOT_CANISTER_ID,
+            "get_sns_canisters_summary",
+            Encode!(&GetSnsCanistersSummaryRequest {
+                update_canister_list: Some(true)
+            })
+            .unwrap(),
+            Ok(Encode!(&std_sns_canisters_summary_response()).unwrap()),
+        );
+        env.require_call_canister_invocation(
+            treasury_manager_canister_id,
+            "deposit",
+            Encode!(&DepositRequest {
+                icp_amount_e8s: 100 * E8,
+                sns_token_amount_e8s: 500 * E8,
+                withdraw_accounts: treasury_manager_withdraw_accounts_for_test(),
+            })
+            .unwrap(),
+            Some(Ok(Encode!(&TreasuryManagerResult::Ok(
+                TreasuryManagerReport {
+                    icp_balance_e8s: 100 * E8,
+                    sns_token_balance_e8s: 500 * E8,
+                    audit_trail: vec![AuditTrailEvent {
+                        timestamp_seconds: 42,
+                        human_readable: "Added liquidity.".to_string(),
+                    }],
+                }
+            ))
+            .unwrap())),
+        );
+        let assert_required_calls = env.get_assert_required_calls_fn();
+        let now = env.now();
+
+        let mut governance = Governance::new(
+            GovernanceProto {
+                root_canister_id: Some(TEST_ROOT_CANISTER_ID.get()),
+                parameters: Some(treasury_manager_approving_parameters(
+                    treasury_manager_canister_id.get(),
+                )),
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::new(env),
+            Box::new(AlwaysSucceedingLedger {}),
+            Box::new(AlwaysSucceedingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+
+        governance
+            .perform_deposit_to_treasury_manager(
+                1,
+                DepositToTreasuryManager {
+                    treasury_manager_canister_id: Some(treasury_manager_canister_id.get()),
+                    icp_amount_e8s: 100 * E8,
+                    sns_token_amount_e8s: 500 * E8,
+                },
+            )
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+
+        assert_required_calls();
+        let treasury_managers = governance
+            .get_treasury_managers(GetTreasuryManagersRequest {})
+            .treasury_managers;
+        assert_eq!(
+            treasury_managers,
+            vec![TreasuryManager {
+                canister_id: Some(treasury_manager_canister_id.get()),
+                balances: Some(TreasuryManagerBalances {
+                    icp_e8s: 100 * E8,
+                    sns_token_e8s: 500 * E8,
+                }),
+                balances_timestamp_seconds: Some(now),
+                audit_trail: vec![
+                    TreasuryManagerAuditTrailEntry {
+                        timestamp_seconds: Some(now),
+                        proposal_id: Some(1),
+                        human_readable: "Deposited 10000000000 e8s of ICP and 50000000000 e8s \
+                                         of SNS tokens."
+                            .to_string(),
+                        reported_by_treasury_manager: false,
+                    },
+                    TreasuryManagerAuditTrailEntry {
+                        timestamp_seconds: Some(42),
+                        proposal_id: Some(1),
+                        human_readable: "Added liquidity.".to_string(),
+                        reported_by_treasury_manager: true,
+                    },
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_record_treasury_manager_report_does_not_duplicate_audit_trail() {
+        use crate::treasury_manager::AuditTrailEvent;
+
+        let treasury_manager_canister_id = TEST_DAPP_CANISTER_IDS[0].get();
+        let mut governance = Governance::new(
+            basic_governance_proto().try_into().unwrap(),
+            Box::new(NativeEnvironment::new(Some(*TEST_GOVERNANCE_CANISTER_ID))),
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+        let event = |timestamp_seconds: u64, human_readable: &str| AuditTrailEvent {
+            timestamp_seconds,
+            human_readable: human_readable.to_string(),
+        };
+        let report = |audit_trail: Vec<AuditTrailEvent>| TreasuryManagerReport {
+            icp_balance_e8s: 100 * E8,
+            sns_token_balance_e8s: 500 * E8,
+            audit_trail,
+        };
+
+        // Both reports contain the full audit trail of the treasury manager.
+        governance.record_treasury_manager_report(
+            treasury_manager_canister_id,
+            1,
+            report(vec![event(10, "Added liquidity."), event(20, "Swapped.")]),
+        );
+        governance.record_treasury_manager_report(
+            treasury_manager_canister_id,
+            2,
+            report(vec![
+                event(10, "Added liquidity."),
+                event(20, "Swapped."),
+                event(20, "Swapped again."),
+                event(30, "Removed liquidity."),
+            ]),
+        );
+
+        let audit_trail: Vec<_> = governance
+            .get_treasury_managers(GetTreasuryManagersRequest {})
+            .treasury_managers[0]
+            .audit_trail
+            .iter()
+            .map(|entry| {
+                (
+                    entry.timestamp_seconds.unwrap(),
+                    entry.proposal_id.unwrap(),
+                    entry.human_readable.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            audit_trail,
+            vec![
+                (10, 1, "Added liquidity."),
+                (20, 1, "Swapped."),
+                (20, 2, "Swapped again."),
+                (30, 2, "Removed liquidity."),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deposit_to_treasury_manager_requires_registered_dapp_canister() {
+        let mut env = NativeEnvironment::new(Some(*TEST_GOVERNANCE_CANISTER_ID));
+        env.set_call_canister_response(
+            *TEST_ROOT_CANISTER_ID,
+            "get_sns_canisters_summary",
+            Encode!(&GetSnsCanistersSummaryRequest {
+                update_canister_list: Some(true)
+            })
+            .unwrap(),
+            Ok(Encode!(&std_sns_canisters_summary_response()).unwrap()),
+        );
+
+        let mut governance = Governance::new(
+            GovernanceProto {
+                root_canister_id: Some(TEST_ROOT_CANISTER_ID.get()),
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::new(env),
+            // Transfers would panic, so this also checks that no funds are moved.
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+
+        let err = governance
+            .perform_deposit_to_treasury_manager(
+                1,
+                DepositToTreasuryManager {
+                    treasury_manager_canister_id: Some(TEST_LEDGER_CANISTER_ID.get()),
+                    icp_amount_e8s: 100 * E8,
+                    sns_token_amount_e8s: 0,
+                },
+            )
+            .now_or_never()
+            .unwrap()
+            .unwrap_err();
+
+        assert_eq!(err.error_type, ErrorType::PreconditionFailed as i32);
+        assert!(governance.proto.treasury_managers.is_empty());
+    }
+
+    #[test]
+    fn test_deposit_to_treasury_manager_requires_approval_at_execution() {
+        let treasury_manager_canister_id = TEST_DAPP_CANISTER_IDS[0];
+        let mut env = NativeEnvironment::new(Some(*TEST_GOVERNANCE_CANISTER_ID));
+        env.set_call_canister_response(
+            *TEST_ROOT_CANISTER_ID,
+            "get_sns_canisters_summary",
+            Encode!(&GetSnsCanistersSummaryRequest {
+                update_canister_list: Some(true)
+            })
+            .unwrap(),
+            Ok(Encode!(&std_sns_canisters_summary_response()).unwrap()),
+        );
+
+        // The treasury manager is a registered dapp canister, but is not
+        // approved by the (default) nervous system parameters.
+        let mut governance = Governance::new(
+            GovernanceProto {
+                root_canister_id: Some(TEST_ROOT_CANISTER_ID.get()),
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::new(env),
+            // Transfers would panic, so this also checks that no funds are moved.
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+
+        let err = governance
+            .perform_deposit_to_treasury_manager(
+                1,
+                DepositToTreasuryManager {
+                    treasury_manager_canister_id: Some(treasury_manager_canister_id.get()),
+                    icp_amount_e8s: 100 * E8,
+                    sns_token_amount_e8s: 0,
+                },
+            )
+            .now_or_never()
+            .unwrap()
+            .unwrap_err();
+
+        assert_eq!(err.error_type, ErrorType::PreconditionFailed as i32);
+        assert!(
+            err.error_message
+                .contains("is not approved to receive treasury deposits"),
+            "{:?}",
+            err
+        );
+        assert!(governance.proto.treasury_managers.is_empty());
+    }
+
+    #[test]
+    fn test_withdraw_from_treasury_manager() {
+        use crate::treasury_manager::WithdrawRequest;
+
+        let treasury_manager_canister_id = TEST_DAPP_CANISTER_IDS[0];
+        let mut env = NativeEnvironment::new(Some(*TEST_GOVERNANCE_CANISTER_ID));
+        env.require_call_canister_invocation(
+            treasury_manager_canister_id,
+            "withdraw",
+            Encode!(&WithdrawRequest {
+                withdraw_accounts: treasury_manager_withdraw_accounts_for_test(),
+            })
+            .unwrap(),
+            Some(Ok(Encode!(&TreasuryManagerResult::Ok(
+                TreasuryManagerReport {
+                    icp_balance_e8s: 0,
+                    sns_token_balance_e8s: 0,
+                    audit_trail: vec![],
+                }
+            ))
+            .unwrap())),
+        );
+        let assert_required_calls = env.get_assert_required_calls_fn();
+        let now = env.now();
+
+        let mut governance = Governance::new(
+            GovernanceProto {
+                root_canister_id: Some(TEST_ROOT_CANISTER_ID.get()),
+                treasury_managers: vec![TreasuryManager {
+                    canister_id: Some(treasury_manager_canister_id.get()),
+                    balances: Some(TreasuryManagerBalances {
+                        icp_e8s: 100 * E8,
+                        sns_token_e8s: 500 * E8,
+                    }),
+                    balances_timestamp_seconds: Some(now - 1),
+                    audit_trail: vec![],
+                }],
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::new(env),
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+
+        // Withdrawing from an unknown treasury manager fails.
+        let err = governance
+            .perform_withdraw_from_treasury_manager(
+                1,
+                WithdrawFromTreasuryManager {
+                    treasury_manager_canister_id: Some(CanisterId::from(601).get()),
+                },
+            )
+            .now_or_never()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.error_type, ErrorType::NotFound as i32);
+
+        governance
+            .perform_withdraw_from_treasury_manager(
+                2,
+                WithdrawFromTreasuryManager {
+                    treasury_manager_canister_id: Some(treasury_manager_canister_id.get()),
+                },
+            )
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+
+        assert_required_calls();
+        let treasury_manager = &governance.proto.treasury_managers[0];
+        assert_eq!(
+            treasury_manager.balances,
+            Some(TreasuryManagerBalances {
+                icp_e8s: 0,
+                sns_token_e8s: 0,
+            })
+        );
+        assert_eq!(treasury_manager.balances_timestamp_seconds, Some(now));
+        assert_eq!(
+            treasury_manager.audit_trail,
+            vec![TreasuryManagerAuditTrailEntry {
+                timestamp_seconds: Some(now),
+                proposal_id: Some(2),
+                human_readable: "Withdrew all managed funds to the SNS treasury.".to_string(),
+                reported_by_treasury_manager: false,
+            }]
+        );
+    }
+
     #[test]
     fn test_distribute_rewards_does_not_block_upgrades() {
         // Setup the canister ids for the test
@@ -7,9 +7,12 @@ use ic_ledger_core::{block::BlockIndex, Tokens};
 pub use ic_nervous_system_common::ledger::ICRC1Ledger;
 use ic_nervous_system_common::NervousSystemError;
 use icrc_ledger_client::{ICRC1Client, Runtime};
-use icrc_ledger_types::icrc1::{
-    account::{Account, Subaccount},
-    transfer::{Memo, TransferArg},
+use icrc_ledger_types::{
+    icrc::generic_metadata_value::MetadataValue,
+    icrc1::{
+        account::{Account, Subaccount},
+        transfer::{Memo, TransferArg},
+    },
 };
 use num_traits::ToPrimitive;
 
@@ -115,3 +118,24 @@ impl ICRC1Ledger for LedgerCanister {
         CanisterId::unchecked_from_principal(principal_id)
     }
 }
+
+/// The metadata key under which the ICRC-1 ledger stores the token logo.
+pub(crate) const ICRC1_TOKEN_LOGO_KEY: &str = "icrc1:logo";
+
+/// Copied from ic-icrc1-ledger to avoid depending on the ledger crate.
+/// Only the fields that SNS governance sets are included; the ledger treats
+/// missing optional fields as unset.
+#[derive(candid::CandidType, candid::Deserialize, Clone, Debug, Default, PartialEq)]
+pub(crate) struct LedgerUpgradeArgs {
+    pub metadata: Option<Vec<(String, MetadataValue)>>,
+    pub token_name: Option<String>,
+    pub token_symbol: Option<String>,
+    pub transfer_fee: Option<Nat>,
+}
+
+/// Copied from ic-icrc1-ledger. The `Init` variant is omitted because SNS
+/// governance only ever upgrades the ledger.
+#[derive(candid::CandidType, candid::Deserialize, Clone, Debug, PartialEq)]
+pub(crate) enum LedgerArgument {
+    Upgrade(Option<LedgerUpgradeArgs>),
+}
@@ -11,6 +11,7 @@ pub mod pb;
 pub mod proposal;
 pub mod reward;
 pub mod sns_upgrade;
+pub mod treasury_manager;
 pub mod types;
 
 // A special value that we put into reward_event_end_timestamp_seconds field to make the proposal
@@ -8,16 +8,21 @@ use crate::{
         proposal,
         proposal::Action,
         transfer_sns_treasury_funds::TransferFrom,
-        DeregisterDappCanisters, ExecuteGenericNervousSystemFunction, Governance,
-        ManageSnsMetadata, MintSnsTokens, Motion, NervousSystemFunction, NervousSystemParameters,
-        Proposal, ProposalData, ProposalDecisionStatus, ProposalRewardStatus,
-        RegisterDappCanisters, Tally, TransferSnsTreasuryFunds, UpgradeSnsControlledCanister,
-        UpgradeSnsToNextVersion, Vote,
+        AdvanceSnsTargetVersion, DepositToTreasuryManager, DeregisterDappCanisters,
+        ExecuteGenericNervousSystemFunction, Governance, ManageDappCanisterSettings,
+        ManageLedgerParameters, ManageSnsMetadata, MintSnsTokens, Motion, NervousSystemFunction,
+        NervousSystemParameters, Proposal, ProposalData, ProposalDecisionStatus,
+        ProposalRewardStatus, RegisterDappCanisters, Tally, TransferSnsTreasuryFunds,
+        TreasuryManager, TreasuryManagerParameters, UpgradeSnsControlledCanister,
+        UpgradeSnsToNextVersion, Vote, WithdrawFromTreasuryManager,
     },
 };
 
 use crate::{
-    sns_upgrade::{get_upgrade_params, UpgradeSnsParams},
+    sns_upgrade::{
+        get_all_sns_canisters, get_upgrade_params, get_upgrade_steps_to_target_version,
+        UpgradeSnsParams,
+    },
     types::{Environment, DEFAULT_TRANSFER_FEE},
     validate_chars_count, validate_len, validate_required_field,
 };
@@ -246,6 +251,61 @@ pub async fn validate_and_render_action(
                 .unwrap_or(DEFAULT_TRANSFER_FEE.get_e8s());
             validate_and_render_mint_sns_tokens(mint, sns_transfer_fee_e8s)
         }
+        proposal::Action::ManageLedgerParameters(manage_ledger_parameters) => {
+            validate_and_render_manage_ledger_parameters(manage_ledger_parameters)
+        }
+        proposal::Action::ManageDappCanisterSettings(manage_dapp_canister_settings) => {
+            let registered_dapp_canister_ids = get_all_sns_canisters(env, root_canister_id)
+                .await
+                .map_err(|err| {
+                    format!("Unable to determine the registered dapp canisters: {}", err)
+                })?
+                .dapps;
+            validate_and_render_manage_dapp_canister_settings(
+                manage_dapp_canister_settings,
+                &registered_dapp_canister_ids,
+            )
+        }
+        proposal::Action::AdvanceSnsTargetVersion(advance_sns_target_version) => {
+            let current_version = governance_proto.deployed_version_or_panic();
+
+            validate_and_render_advance_sns_target_version(
+                advance_sns_target_version,
+                env,
+                current_version,
+            )
+            .await
+        }
+        proposal::Action::DepositToTreasuryManager(deposit) => {
+            let registered_dapp_canister_ids = get_all_sns_canisters(env, root_canister_id)
+                .await
+                .map_err(|err| {
+                    format!("Unable to determine the registered dapp canisters: {}", err)
+                })?
+                .dapps;
+            let sns_transfer_fee_e8s = governance_proto
+                .parameters
+                .as_ref()
+                .and_then(|params| params.transaction_fee_e8s)
+                .unwrap_or(DEFAULT_TRANSFER_FEE.get_e8s());
+            let treasury_manager_parameters = governance_proto
+                .parameters
+                .as_ref()
+                .and_then(|params| params.treasury_manager_parameters.clone())
+                .unwrap_or_default();
+            validate_and_render_deposit_to_treasury_manager(
+                deposit,
+                &registered_dapp_canister_ids,
+                &treasury_manager_parameters,
+                sns_transfer_fee_e8s,
+            )
+        }
+        proposal::Action::WithdrawFromTreasuryManager(withdraw) => {
+            validate_and_render_withdraw_from_treasury_manager(
+                withdraw,
+                &governance_proto.treasury_managers,
+            )
+        }
     }
 }
 
@@ -436,6 +496,287 @@ fn validate_and_render_mint_sns_tokens(
     ))
 }
 
+/// The minimum number of characters in the name of the SNS token.
+pub const LEDGER_TOKEN_NAME_CHARS_MIN: usize = 4;
+/// The maximum number of characters in the name of the SNS token.
+pub const LEDGER_TOKEN_NAME_CHARS_MAX: usize = 255;
+/// The minimum number of characters in the symbol of the SNS token.
+pub const LEDGER_TOKEN_SYMBOL_CHARS_MIN: usize = 3;
+/// The maximum number of characters in the symbol of the SNS token.
+pub const LEDGER_TOKEN_SYMBOL_CHARS_MAX: usize = 10;
+
+/// Validates and renders a proposal with action ManageLedgerParameters.
+fn validate_and_render_manage_ledger_parameters(
+    manage_ledger_parameters: &ManageLedgerParameters,
+) -> Result<String, String> {
+    let ManageLedgerParameters {
+        transfer_fee,
+        token_name,
+        token_symbol,
+        token_logo,
+    } = manage_ledger_parameters;
+
+    let mut defects = vec![];
+    let mut changes = vec![];
+
+    if let Some(transfer_fee) = transfer_fee {
+        changes.push(format!("## Set token transfer fee: {} e8s", transfer_fee));
+    }
+    if let Some(token_name) = token_name {
+        if let Err(err) = validate_chars_count(
+            "token_name",
+            token_name,
+            LEDGER_TOKEN_NAME_CHARS_MIN,
+            LEDGER_TOKEN_NAME_CHARS_MAX,
+        ) {
+            defects.push(err);
+        }
+        changes.push(format!("## Set token name: {}", token_name));
+    }
+    if let Some(token_symbol) = token_symbol {
+        if let Err(err) = validate_chars_count(
+            "token_symbol",
+            token_symbol,
+            LEDGER_TOKEN_SYMBOL_CHARS_MIN,
+            LEDGER_TOKEN_SYMBOL_CHARS_MAX,
+        ) {
+            defects.push(err);
+        }
+        changes.push(format!("## Set token symbol: {}", token_symbol));
+    }
+    if let Some(token_logo) = token_logo {
+        if let Err(err) = SnsMetadata::validate_logo(token_logo) {
+            defects.push(err.replace("SnsMetadata.logo", "token_logo"));
+        }
+        changes.push(format!(
+            "## Set token logo (base64 encoding):\n{}",
+            token_logo
+        ));
+    }
+
+    if changes.is_empty() {
+        defects.push("ManageLedgerParameters must change at least one value.".to_string());
+    }
+
+    if !defects.is_empty() {
+        return Err(format!(
+            "ManageLedgerParameters proposal was invalid for the following reason(s):\n{}",
+            defects.join("\n"),
+        ));
+    }
+
+    Ok(format!(
+        "# Proposal to change ledger parameters:\n{}",
+        changes.join("\n")
+    ))
+}
+
+/// The maximum compute allocation (in percent) that can be set for a dapp canister.
+const MAX_COMPUTE_ALLOCATION_PERCENT: u64 = 100;
+
+/// Validates and renders a proposal with action ManageDappCanisterSettings.
+/// All targeted canisters must be among `registered_dapp_canister_ids`.
+fn validate_and_render_manage_dapp_canister_settings(
+    manage_dapp_canister_settings: &ManageDappCanisterSettings,
+    registered_dapp_canister_ids: &[PrincipalId],
+) -> Result<String, String> {
+    let ManageDappCanisterSettings {
+        canister_ids,
+        compute_allocation,
+        memory_allocation,
+        freezing_threshold,
+        reserved_cycles_limit,
+    } = manage_dapp_canister_settings;
+
+    let mut defects = vec![];
+    let mut changes = vec![];
+
+    if canister_ids.is_empty() {
+        defects
+            .push("ManageDappCanisterSettings must specify at least one canister id.".to_string());
+    }
+    let mut seen_canister_ids = HashSet::new();
+    for canister_id in canister_ids {
+        if !seen_canister_ids.insert(canister_id) {
+            defects.push(format!(
+                "Canister id {} is specified more than once.",
+                canister_id
+            ));
+        } else if !registered_dapp_canister_ids.contains(canister_id) {
+            defects.push(format!(
+                "Canister id {} is not a registered dapp canister.",
+                canister_id
+            ));
+        }
+    }
+
+    if let Some(compute_allocation) = compute_allocation {
+        if *compute_allocation > MAX_COMPUTE_ALLOCATION_PERCENT {
+            defects.push(format!(
+                "compute_allocation must be at most {}, but is {}.",
+                MAX_COMPUTE_ALLOCATION_PERCENT, compute_allocation
+            ));
+        }
+        changes.push(format!("- Compute allocation: {}%", compute_allocation));
+    }
+    if let Some(memory_allocation) = memory_allocation {
+        changes.push(format!("- Memory allocation: {} bytes", memory_allocation));
+    }
+    if let Some(freezing_threshold) = freezing_threshold {
+        changes.push(format!(
+            "- Freezing threshold: {} seconds",
+            freezing_threshold
+        ));
+    }
+    if let Some(reserved_cycles_limit) = reserved_cycles_limit {
+        changes.push(format!(
+            "- Reserved cycles limit: {} cycles",
+            reserved_cycles_limit
+        ));
+    }
+
+    if changes.is_empty() {
+        defects.push("ManageDappCanisterSettings must change at least one setting.".to_string());
+    }
+
+    if !defects.is_empty() {
+        return Err(format!(
+            "ManageDappCanisterSettings proposal was invalid for the following reason(s):\n{}",
+            defects.join("\n"),
+        ));
+    }
+
+    Ok(format!(
+        "# Proposal to change the settings of {} dapp canister(s):\n\
+         ## Canister ids:\n{}\n\
+         ## New settings:\n{}",
+        canister_ids.len(),
+        canister_ids
+            .iter()
+            .map(|canister_id| format!("- {}", canister_id))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        changes.join("\n"),
+    ))
+}
+
+/// Validates and renders a proposal with action DepositToTreasuryManager.
+fn validate_and_render_deposit_to_treasury_manager(
+    deposit: &DepositToTreasuryManager,
+    registered_dapp_canister_ids: &[PrincipalId],
+    treasury_manager_parameters: &TreasuryManagerParameters,
+    sns_transfer_fee_e8s: u64,
+) -> Result<String, String> {
+    let DepositToTreasuryManager {
+        treasury_manager_canister_id,
+        icp_amount_e8s,
+        sns_token_amount_e8s,
+    } = deposit;
+
+    let mut defects = vec![];
+
+    match treasury_manager_canister_id {
+        None => defects.push("Must specify a treasury_manager_canister_id.".to_string()),
+        Some(canister_id) if !registered_dapp_canister_ids.contains(canister_id) => {
+            defects.push(format!(
+                "The treasury manager {} is not a registered dapp canister.",
+                canister_id
+            ))
+        }
+        Some(_) => (),
+    }
+    defects.append(&mut treasury_manager_parameters.deposit_defects(deposit));
+
+    if *icp_amount_e8s == 0 && *sns_token_amount_e8s == 0 {
+        defects.push("Must deposit a non-zero amount of ICP or SNS tokens.".to_string());
+    }
+    let icp_fee_e8s = NNS_DEFAULT_TRANSFER_FEE.get_e8s();
+    if *icp_amount_e8s != 0 && *icp_amount_e8s < icp_fee_e8s {
+        defects.push(format!(
+            "For deposits from the ICP Treasury, the fee and minimum transaction is {} e8s",
+            icp_fee_e8s
+        ));
+    }
+    if *sns_token_amount_e8s != 0 && *sns_token_amount_e8s < sns_transfer_fee_e8s {
+        defects.push(format!(
+            "For deposits from the SNS Token Treasury, the fee and minimum transaction is {} e8s",
+            sns_transfer_fee_e8s
+        ));
+    }
+
+    if !defects.is_empty() {
+        return Err(format!(
+            "DepositToTreasuryManager proposal was invalid for the following reason(s):\n{}",
+            defects.join("\n"),
+        ));
+    }
+
+    let display_icp_amount = i2d(*icp_amount_e8s) / i2d(E8);
+    let display_sns_token_amount = i2d(*sns_token_amount_e8s) / i2d(E8);
+    let max_icp_amount_e8s = treasury_manager_parameters
+        .max_icp_amount_e8s_per_proposal
+        .unwrap_or_default();
+    let max_sns_token_amount_e8s = treasury_manager_parameters
+        .max_sns_token_amount_e8s_per_proposal
+        .unwrap_or_default();
+
+    Ok(format!(
+        r"# Proposal to deposit SNS Treasury funds into a treasury manager:
+## Treasury manager: {treasury_manager_canister_id}
+## ICP amount: {display_icp_amount:.8} ICP
+## ICP amount (e8s): {icp_amount_e8s} (limit per proposal: {max_icp_amount_e8s})
+## SNS token amount: {display_sns_token_amount:.8} SNS Tokens
+## SNS token amount (e8s): {sns_token_amount_e8s} (limit per proposal: {max_sns_token_amount_e8s})",
+        treasury_manager_canister_id = treasury_manager_canister_id.unwrap(),
+    ))
+}
+
+/// Validates and renders a proposal with action WithdrawFromTreasuryManager.
+fn validate_and_render_withdraw_from_treasury_manager(
+    withdraw: &WithdrawFromTreasuryManager,
+    treasury_managers: &[TreasuryManager],
+) -> Result<String, String> {
+    let treasury_manager = withdraw
+        .treasury_manager_canister_id
+        .ok_or_else(|| "Must specify a treasury_manager_canister_id.".to_string())
+        .and_then(|canister_id| {
+            treasury_managers
+                .iter()
+                .find(|treasury_manager| treasury_manager.canister_id == Some(canister_id))
+                .ok_or_else(|| {
+                    format!(
+                        "No funds were ever deposited into treasury manager {}.",
+                        canister_id
+                    )
+                })
+        })
+        .map_err(|defect| {
+            format!(
+                "WithdrawFromTreasuryManager proposal was invalid for the following reason:\n{}",
+                defect
+            )
+        })?;
+
+    let balances =
+        match &treasury_manager.balances {
+            Some(balances) => format!(
+            "{} e8s of ICP and {} e8s of SNS tokens (reported at {} seconds since the UNIX epoch)",
+            balances.icp_e8s,
+            balances.sns_token_e8s,
+            treasury_manager.balances_timestamp_seconds.unwrap_or_default()
+        ),
+            None => "Never reported".to_string(),
+        };
+
+    Ok(format!(
+        r"# Proposal to withdraw all funds from a treasury manager to the SNS Treasury:
+## Treasury manager: {}
+## Last reported balances: {}",
+        treasury_manager.canister_id.unwrap(),
+        balances,
+    ))
+}
+
 /// Validates and renders a proposal with action UpgradeSnsControlledCanister.
 fn validate_and_render_upgrade_sns_controlled_canister(
     upgrade: &UpgradeSnsControlledCanister,
@@ -559,6 +900,46 @@ async fn validate_and_render_upgrade_sns_to_next_version(
     ))
 }
 
+/// Validates and renders a proposal with action AdvanceSnsTargetVersion.
+async fn validate_and_render_advance_sns_target_version(
+    advance_sns_target_version: &AdvanceSnsTargetVersion,
+    env: &dyn Environment,
+    current_version: Version,
+) -> Result<String, String> {
+    let (target_version, upgrade_steps) = get_upgrade_steps_to_target_version(
+        env,
+        &current_version,
+        advance_sns_target_version.new_target.as_ref(),
+        env.canister_id().get(),
+    )
+    .await
+    .map_err(|e| {
+        format!(
+            "AdvanceSnsTargetVersion was invalid for the following reason: {}\n",
+            e
+        )
+    })?;
+
+    Ok(format!(
+        r"# Proposal to advance SNS target version:
+
+The SNS will be upgraded automatically, one upgrade step at a time, until it
+runs the target version.
+
+## SNS Current Version:
+{}
+
+## SNS New Target Version:
+{}
+
+## Number of upgrade steps: {}
+",
+        render_version(&current_version),
+        render_version(&target_version),
+        upgrade_steps.len(),
+    ))
+}
+
 #[derive(Debug)]
 pub(crate) struct ValidGenericNervousSystemFunction {
     pub id: u64,
@@ -1391,7 +1772,7 @@ mod tests {
         pb::v1::{
             governance::{self, Version},
             Empty, Governance as GovernanceProto, NeuronId, Proposal, ProposalId, Subaccount,
-            WaitForQuietState,
+            TreasuryManagerBalances, WaitForQuietState,
         },
         sns_upgrade::{
             CanisterSummary, GetNextSnsVersionRequest, GetNextSnsVersionResponse,
@@ -1447,6 +1828,9 @@ mod tests {
             sns_initialization_parameters: "".to_string(),
             is_finalizing_disburse_maturity: None,
             maturity_modulation: None,
+            target_version: None,
+            upgrade_journal: None,
+            treasury_managers: vec![],
         }
     }
 
@@ -2752,6 +3136,345 @@ Version {
         );
     }
 
+    #[test]
+    fn validate_and_render_manage_ledger_parameters_renders_changes() {
+        let rendered = validate_and_render_manage_ledger_parameters(&ManageLedgerParameters {
+            transfer_fee: Some(111),
+            token_name: Some("Better Token".to_string()),
+            token_symbol: Some("BTR".to_string()),
+            token_logo: None,
+        })
+        .unwrap();
+        assert_eq!(
+            rendered,
+            "# Proposal to change ledger parameters:\n\
+             ## Set token transfer fee: 111 e8s\n\
+             ## Set token name: Better Token\n\
+             ## Set token symbol: BTR"
+        );
+    }
+
+    #[test]
+    fn validate_and_render_manage_ledger_parameters_no_change() {
+        let err = validate_and_render_manage_ledger_parameters(&ManageLedgerParameters::default())
+            .unwrap_err();
+        assert!(
+            err.contains("ManageLedgerParameters must change at least one value."),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn validate_and_render_manage_ledger_parameters_invalid_fields() {
+        let err = validate_and_render_manage_ledger_parameters(&ManageLedgerParameters {
+            transfer_fee: None,
+            token_name: Some("abc".to_string()),
+            token_symbol: Some("TOOLONGSYMBOL".to_string()),
+            token_logo: Some("not a logo".to_string()),
+        })
+        .unwrap_err();
+        assert!(err.contains("token_name"), "{}", err);
+        assert!(err.contains("token_symbol"), "{}", err);
+        assert!(err.contains("token_logo"), "{}", err);
+    }
+
+    #[test]
+    fn validate_and_render_manage_dapp_canister_settings_renders_changes() {
+        let dapp_canister_id = CanisterId::from_u64(1000).get();
+        let rendered = validate_and_render_manage_dapp_canister_settings(
+            &ManageDappCanisterSettings {
+                canister_ids: vec![dapp_canister_id],
+                compute_allocation: Some(10),
+                memory_allocation: None,
+                freezing_threshold: Some(2_592_000),
+                reserved_cycles_limit: Some(1_000_000),
+            },
+            &[dapp_canister_id],
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            format!(
+                "# Proposal to change the settings of 1 dapp canister(s):\n\
+                 ## Canister ids:\n\
+                 - {}\n\
+                 ## New settings:\n\
+                 - Compute allocation: 10%\n\
+                 - Freezing threshold: 2592000 seconds\n\
+                 - Reserved cycles limit: 1000000 cycles",
+                dapp_canister_id
+            )
+        );
+    }
+
+    #[test]
+    fn validate_and_render_manage_dapp_canister_settings_no_change() {
+        let dapp_canister_id = CanisterId::from_u64(1000).get();
+        let err = validate_and_render_manage_dapp_canister_settings(
+            &ManageDappCanisterSettings {
+                canister_ids: vec![dapp_canister_id],
+                ..Default::default()
+            },
+            &[dapp_canister_id],
+        )
+        .unwrap_err();
+        assert!(
+            err.contains("ManageDappCanisterSettings must change at least one setting."),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn validate_and_render_manage_dapp_canister_settings_invalid_canisters_and_settings() {
+        let dapp_canister_id = CanisterId::from_u64(1000).get();
+        let unregistered_canister_id = CanisterId::from_u64(1001).get();
+        let err = validate_and_render_manage_dapp_canister_settings(
+            &ManageDappCanisterSettings {
+                canister_ids: vec![dapp_canister_id, dapp_canister_id, unregistered_canister_id],
+                compute_allocation: Some(101),
+                ..Default::default()
+            },
+            &[dapp_canister_id],
+        )
+        .unwrap_err();
+        assert!(
+            err.contains(&format!(
+                "Canister id {} is specified more than once.",
+                dapp_canister_id
+            )),
+            "{}",
+            err
+        );
+        assert!(
+            err.contains(&format!(
+                "Canister id {} is not a registered dapp canister.",
+                unregistered_canister_id
+            )),
+            "{}",
+            err
+        );
+        assert!(
+            err.contains("compute_allocation must be at most 100"),
+            "{}",
+            err
+        );
+
+        let err = validate_and_render_manage_dapp_canister_settings(
+            &ManageDappCanisterSettings {
+                canister_ids: vec![],
+                freezing_threshold: Some(1),
+                ..Default::default()
+            },
+            &[dapp_canister_id],
+        )
+        .unwrap_err();
+        assert!(
+            err.contains("ManageDappCanisterSettings must specify at least one canister id."),
+            "{}",
+            err
+        );
+    }
+
+    fn approved_treasury_manager_parameters(
+        treasury_manager_canister_id: PrincipalId,
+    ) -> TreasuryManagerParameters {
+        TreasuryManagerParameters {
+            approved_canister_ids: vec![treasury_manager_canister_id],
+            max_icp_amount_e8s_per_proposal: Some(5_000_000_000),
+            max_sns_token_amount_e8s_per_proposal: Some(100_000_000),
+        }
+    }
+
+    #[test]
+    fn validate_and_render_deposit_to_treasury_manager_renders_for_valid_inputs() {
+        let treasury_manager_canister_id = CanisterId::from_u64(1000).get();
+        let rendered = validate_and_render_deposit_to_treasury_manager(
+            &DepositToTreasuryManager {
+                treasury_manager_canister_id: Some(treasury_manager_canister_id),
+                icp_amount_e8s: 1_000_000_000,
+                sns_token_amount_e8s: 0,
+            },
+            &[treasury_manager_canister_id],
+            &approved_treasury_manager_parameters(treasury_manager_canister_id),
+            10_000,
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            format!(
+                r"# Proposal to deposit SNS Treasury funds into a treasury manager:
+## Treasury manager: {}
+## ICP amount: 10.00000000 ICP
+## ICP amount (e8s): 1000000000 (limit per proposal: 5000000000)
+## SNS token amount: 0.00000000 SNS Tokens
+## SNS token amount (e8s): 0 (limit per proposal: 100000000)",
+                treasury_manager_canister_id
+            )
+        );
+    }
+
+    #[test]
+    fn validate_and_render_deposit_to_treasury_manager_invalid() {
+        let treasury_manager_canister_id = CanisterId::from_u64(1000).get();
+        let unregistered_canister_id = CanisterId::from_u64(1001).get();
+
+        let err = validate_and_render_deposit_to_treasury_manager(
+            &DepositToTreasuryManager {
+                treasury_manager_canister_id: Some(unregistered_canister_id),
+                icp_amount_e8s: 0,
+                sns_token_amount_e8s: 0,
+            },
+            &[treasury_manager_canister_id],
+            &approved_treasury_manager_parameters(treasury_manager_canister_id),
+            10_000,
+        )
+        .unwrap_err();
+        assert!(
+            err.contains(&format!(
+                "The treasury manager {} is not a registered dapp canister.",
+                unregistered_canister_id
+            )),
+            "{}",
+            err
+        );
+        assert!(
+            err.contains("Must deposit a non-zero amount of ICP or SNS tokens."),
+            "{}",
+            err
+        );
+
+        let err = validate_and_render_deposit_to_treasury_manager(
+            &DepositToTreasuryManager {
+                treasury_manager_canister_id: None,
+                icp_amount_e8s: 1,
+                sns_token_amount_e8s: 1,
+            },
+            &[treasury_manager_canister_id],
+            &approved_treasury_manager_parameters(treasury_manager_canister_id),
+            10_000,
+        )
+        .unwrap_err();
+        assert!(
+            err.contains("Must specify a treasury_manager_canister_id."),
+            "{}",
+            err
+        );
+        assert!(
+            err.contains("For deposits from the ICP Treasury, the fee and minimum transaction"),
+            "{}",
+            err
+        );
+        assert!(
+            err.contains(
+                "For deposits from the SNS Token Treasury, the fee and minimum transaction is \
+                 10000 e8s"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn validate_and_render_deposit_to_treasury_manager_requires_approval_and_limits() {
+        let treasury_manager_canister_id = CanisterId::from_u64(1000).get();
+        let deposit = DepositToTreasuryManager {
+            treasury_manager_canister_id: Some(treasury_manager_canister_id),
+            icp_amount_e8s: 1_000_000_000,
+            sns_token_amount_e8s: 1_000_000_000,
+        };
+
+        // Being a registered dapp canister is not enough.
+        let err = validate_and_render_deposit_to_treasury_manager(
+            &deposit,
+            &[treasury_manager_canister_id],
+            &TreasuryManagerParameters::default(),
+            10_000,
+        )
+        .unwrap_err();
+        assert!(
+            err.contains(&format!(
+                "The treasury manager {} is not approved to receive treasury deposits.",
+                treasury_manager_canister_id
+            )),
+            "{}",
+            err
+        );
+        // Unset limits do not allow any deposits.
+        assert!(
+            err.contains("The ICP amount 1000000000 e8s exceeds the per-proposal limit of 0 e8s."),
+            "{}",
+            err
+        );
+
+        let err = validate_and_render_deposit_to_treasury_manager(
+            &deposit,
+            &[treasury_manager_canister_id],
+            &approved_treasury_manager_parameters(treasury_manager_canister_id),
+            10_000,
+        )
+        .unwrap_err();
+        assert!(!err.contains("is not approved"), "{}", err);
+        assert!(!err.contains("The ICP amount"), "{}", err);
+        assert!(
+            err.contains(
+                "The SNS token amount 1000000000 e8s exceeds the per-proposal limit of \
+                 100000000 e8s."
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn validate_and_render_withdraw_from_treasury_manager() {
+        let treasury_manager_canister_id = CanisterId::from_u64(1000).get();
+        let treasury_managers = vec![TreasuryManager {
+            canister_id: Some(treasury_manager_canister_id),
+            balances: Some(TreasuryManagerBalances {
+                icp_e8s: 100,
+                sns_token_e8s: 200,
+            }),
+            balances_timestamp_seconds: Some(1_700_000_000),
+            audit_trail: vec![],
+        }];
+
+        let rendered = validate_and_render_withdraw_from_treasury_manager(
+            &WithdrawFromTreasuryManager {
+                treasury_manager_canister_id: Some(treasury_manager_canister_id),
+            },
+            &treasury_managers,
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            format!(
+                r"# Proposal to withdraw all funds from a treasury manager to the SNS Treasury:
+## Treasury manager: {}
+## Last reported balances: 100 e8s of ICP and 200 e8s of SNS tokens (reported at 1700000000 seconds since the UNIX epoch)",
+                treasury_manager_canister_id
+            )
+        );
+
+        let unknown_canister_id = CanisterId::from_u64(1001).get();
+        let err = validate_and_render_withdraw_from_treasury_manager(
+            &WithdrawFromTreasuryManager {
+                treasury_manager_canister_id: Some(unknown_canister_id),
+            },
+            &treasury_managers,
+        )
+        .unwrap_err();
+        assert!(
+            err.contains(&format!(
+                "No funds were ever deposited into treasury manager {}.",
+                unknown_canister_id
+            )),
+            "{}",
+            err
+        );
+    }
+
     #[test]
     fn validate_and_render_register_dapp_canisters_lists_canisters() {
         let canister_ids = (0..10_u8)
@@ -120,3 +120,40 @@ pub struct CanisterCallError {
     #[prost(string, tag = "2")]
     pub description: ::prost::alloc::string::String,
 }
+/// Change the settings of the listed dapp canisters. Only settings that are set
+/// are changed; controllers are managed via SetDappControllers instead.
+/// Same proto in root.proto. TODO(NNS1-1589)
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct ManageDappCanisterSettingsRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub canister_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+    #[prost(uint64, optional, tag = "2")]
+    pub compute_allocation: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "3")]
+    pub memory_allocation: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "4")]
+    pub freezing_threshold: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "5")]
+    pub reserved_cycles_limit: ::core::option::Option<u64>,
+}
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct ManageDappCanisterSettingsResponse {
+    /// Describes why the settings of (some of) the canisters could not be
+    /// updated. Not set if all updates succeeded.
+    #[prost(string, optional, tag = "1")]
+    pub failure_reason: ::core::option::Option<::prost::alloc::string::String>,
+}
@@ -38,6 +38,18 @@ pub(crate) async fn get_upgrade_params(
         }
     };
 
+    get_upgrade_params_for_next_version(env, root_canister_id, current_version, next_version).await
+}
+
+/// Like `get_upgrade_params`, but for a `next_version` that the caller already
+/// obtained from SNS-WASM (e.g. as the first of the upgrade steps returned by
+/// `get_upgrade_steps`).
+pub(crate) async fn get_upgrade_params_for_next_version(
+    env: &dyn Environment,
+    root_canister_id: CanisterId,
+    current_version: &Version,
+    next_version: Version,
+) -> Result<UpgradeSnsParams, String> {
     let (canister_type_to_upgrade, new_wasm_hash) =
         canister_type_and_wasm_hash_for_upgrade(current_version, &next_version)?;
 
@@ -240,6 +252,83 @@ async fn sns_canisters_summary(
         .map_err(|e| format!("Failed to decode response: {:?}", e))
 }
 
+/// Returns the versions on the upgrade path of this SNS that come after
+/// `current_version`, in the order in which they must be upgraded to, as
+/// reported by SNS-WASM's `list_upgrade_steps`. `sns_governance_canister_id`
+/// is used so that custom upgrade paths for this SNS are taken into account.
+pub(crate) async fn get_upgrade_steps(
+    env: &dyn Environment,
+    current_version: &Version,
+    sns_governance_canister_id: PrincipalId,
+) -> Result<Vec<Version>, String> {
+    let arg = Encode!(&ListUpgradeStepsRequest {
+        starting_at: Some(current_version.clone().into()),
+        sns_governance_canister_id: Some(sns_governance_canister_id),
+        limit: 0,
+    })
+    .map_err(|e| format!("Could not encode ListUpgradeStepsRequest: {:?}", e))?;
+
+    let response = env
+        .call_canister(SNS_WASM_CANISTER_ID, "list_upgrade_steps", arg)
+        .await
+        .map_err(|e| format!("Request failed for list_upgrade_steps: {:?}", e))?;
+
+    let response = Decode!(&response, ListUpgradeStepsResponse)
+        .map_err(|e| format!("Could not decode response to list_upgrade_steps: {:?}", e))?;
+
+    let mut steps = response
+        .steps
+        .into_iter()
+        .map(|step| {
+            step.version
+                .map(Version::from)
+                .ok_or_else(|| "list_upgrade_steps returned a step without a version".to_string())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // The response starts with the version it was asked to start at.
+    if steps.first() == Some(current_version) {
+        steps.remove(0);
+    }
+
+    Ok(steps)
+}
+
+/// Determines the target version that an AdvanceSnsTargetVersion proposal
+/// would set, given the currently deployed version. If `new_target` is None,
+/// the latest version on the upgrade path is chosen. Returns the target version
+/// and the upgrade steps needed to reach it (the target being the last one).
+pub(crate) async fn get_upgrade_steps_to_target_version(
+    env: &dyn Environment,
+    current_version: &Version,
+    new_target: Option<&Version>,
+    sns_governance_canister_id: PrincipalId,
+) -> Result<(Version, Vec<Version>), String> {
+    let mut steps = get_upgrade_steps(env, current_version, sns_governance_canister_id).await?;
+
+    let target_version = match new_target {
+        Some(new_target) => new_target.clone(),
+        None => steps.last().cloned().ok_or_else(|| {
+            format!(
+                "There is no version after the current SNS version on the upgrade path: {}",
+                render_version(current_version)
+            )
+        })?,
+    };
+
+    let Some(target_position) = steps.iter().position(|step| *step == target_version) else {
+        return Err(format!(
+            "The target version is not on the upgrade path after the current SNS version.\n\
+             Target version: {}\nCurrent version: {}",
+            render_version(&target_version),
+            render_version(current_version)
+        ));
+    };
+    steps.truncate(target_position + 1);
+
+    Ok((target_version, steps))
+}
+
 /// Get the next version of the SNS based on a given version.
 async fn get_next_version(env: &dyn Environment, current_version: &Version) -> Option<Version> {
     let arg = Encode!(&GetNextSnsVersionRequest {
@@ -438,6 +527,34 @@ pub(crate) struct GetNextSnsVersionResponse {
     pub next_version: ::core::option::Option<SnsVersion>,
 }
 
+/// Duplicated from ic-sns-wasms to avoid circular dependency as a temporary workaround
+/// The request type accepted by the list_upgrade_steps canister method
+#[derive(candid::CandidType, candid::Deserialize, Clone, PartialEq, ::prost::Message)]
+pub(crate) struct ListUpgradeStepsRequest {
+    #[prost(message, optional, tag = "1")]
+    pub starting_at: ::core::option::Option<SnsVersion>,
+    #[prost(message, optional, tag = "2")]
+    pub sns_governance_canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    #[prost(uint32, tag = "3")]
+    pub limit: u32,
+}
+
+/// Duplicated from ic-sns-wasms to avoid circular dependency as a temporary workaround
+/// The response type returned by the list_upgrade_steps canister method
+#[derive(candid::CandidType, candid::Deserialize, Clone, PartialEq, ::prost::Message)]
+pub(crate) struct ListUpgradeStepsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub steps: ::prost::alloc::vec::Vec<ListUpgradeStep>,
+}
+
+/// Duplicated from ic-sns-wasms to avoid circular dependency as a temporary workaround
+/// A step in the upgrade path. The human-readable version is omitted.
+#[derive(candid::CandidType, candid::Deserialize, Clone, PartialEq, ::prost::Message)]
+pub(crate) struct ListUpgradeStep {
+    #[prost(message, optional, tag = "1")]
+    pub version: ::core::option::Option<SnsVersion>,
+}
+
 /// Duplicated from ic-sns-wasms to avoid circular dependency as a temporary workaround.
 /// Avoid using outside of tests and the functions in this file.
 /// Specifies the version of an SNS.
@@ -0,0 +1,53 @@
+//! The interface that treasury managers implement, so that SNS governance can deposit
+//! treasury funds into them (DepositToTreasuryManager proposals) and withdraw the funds
+//! again (WithdrawFromTreasuryManager proposals). The interface does not depend on how the
+//! funds are managed, e.g. on which DEX they are provided as liquidity.
+//!
+//! The candid interface is described in `canister/treasury_manager.did`.
+
+use candid::{CandidType, Deserialize};
+use icrc_ledger_types::icrc1::account::Account;
+
+/// The accounts to which a treasury manager must return the funds it manages when they are
+/// withdrawn, i.e. the SNS treasury accounts.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct WithdrawAccounts {
+    pub icp: Account,
+    pub sns_token: Account,
+}
+
+/// The argument of the treasury manager's `deposit` method. Governance calls it after
+/// transferring the amounts to the treasury manager's default account.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DepositRequest {
+    pub icp_amount_e8s: u64,
+    pub sns_token_amount_e8s: u64,
+    pub withdraw_accounts: WithdrawAccounts,
+}
+
+/// The argument of the treasury manager's `withdraw` method. All managed funds are returned.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct WithdrawRequest {
+    pub withdraw_accounts: WithdrawAccounts,
+}
+
+/// An operation performed by a treasury manager, e.g. a swap or a liquidity change on a DEX.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AuditTrailEvent {
+    pub timestamp_seconds: u64,
+    pub human_readable: String,
+}
+
+/// What a treasury manager reports back to governance after a deposit or a withdrawal: the
+/// balances it manages on behalf of the SNS, and the operations it performed. Governance only
+/// records the operations that are newer than the ones it has already recorded, so a
+/// treasury manager may report its full audit trail each time.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TreasuryManagerReport {
+    pub icp_balance_e8s: u64,
+    pub sns_token_balance_e8s: u64,
+    pub audit_trail: Vec<AuditTrailEvent>,
+}
+
+/// The result of the treasury manager's `deposit` and `withdraw` methods.
+pub type TreasuryManagerResult = Result<TreasuryManagerReport, String>;
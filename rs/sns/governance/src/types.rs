@@ -3,8 +3,8 @@ use crate::{
     logs::INFO,
     pb::{
         sns_root_types::{
-            set_dapp_controllers_request::CanisterIds, RegisterDappCanistersRequest,
-            SetDappControllersRequest,
+            set_dapp_controllers_request::CanisterIds, ManageDappCanisterSettingsRequest,
+            RegisterDappCanistersRequest, SetDappControllersRequest,
         },
         v1::{
             claim_swap_neurons_request::NeuronParameters,
@@ -21,13 +21,15 @@ use crate::{
             nervous_system_function::FunctionType,
             neuron::Followees,
             proposal::Action,
-            ClaimSwapNeuronsError, ClaimSwapNeuronsResponse, ClaimedSwapNeuronStatus,
-            DefaultFollowees, DeregisterDappCanisters, Empty, ExecuteGenericNervousSystemFunction,
-            GovernanceError, ManageNeuronResponse, MintSnsTokens, Motion, NervousSystemFunction,
-            NervousSystemParameters, Neuron, NeuronId, NeuronPermission, NeuronPermissionList,
-            NeuronPermissionType, ProposalId, RegisterDappCanisters, RewardEvent,
-            TransferSnsTreasuryFunds, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote,
-            VotingRewardsParameters,
+            AdvanceSnsTargetVersion, ClaimSwapNeuronsError, ClaimSwapNeuronsResponse,
+            ClaimedSwapNeuronStatus, DefaultFollowees, DepositToTreasuryManager,
+            DeregisterDappCanisters, Empty, ExecuteGenericNervousSystemFunction, GovernanceError,
+            ManageDappCanisterSettings, ManageLedgerParameters, ManageNeuronResponse,
+            MintSnsTokens, Motion, NervousSystemFunction, NervousSystemParameters, Neuron,
+            NeuronId, NeuronPermission, NeuronPermissionList, NeuronPermissionType, ProposalId,
+            RegisterDappCanisters, RewardEvent, TransferSnsTreasuryFunds,
+            TreasuryManagerParameters, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote,
+            VotingRewardsParameters, WithdrawFromTreasuryManager,
         },
     },
     proposal::ValidGenericNervousSystemFunction,
@@ -104,6 +106,21 @@ pub mod native_action_ids {
 
     /// MintSnsTokens
     pub const MINT_SNS_TOKENS: u64 = 12;
+
+    /// ManageLedgerParameters Action.
+    pub const MANAGE_LEDGER_PARAMETERS: u64 = 13;
+
+    /// ManageDappCanisterSettings Action.
+    pub const MANAGE_DAPP_CANISTER_SETTINGS: u64 = 14;
+
+    /// AdvanceSnsTargetVersion Action.
+    pub const ADVANCE_SNS_TARGET_VERSION: u64 = 15;
+
+    /// DepositToTreasuryManager Action.
+    pub const DEPOSIT_TO_TREASURY_MANAGER: u64 = 16;
+
+    /// WithdrawFromTreasuryManager Action.
+    pub const WITHDRAW_FROM_TREASURY_MANAGER: u64 = 17;
 }
 
 impl governance::Mode {
@@ -229,6 +246,24 @@ impl governance::Mode {
                 )
             )),
 
+            Action::ManageLedgerParameters(_) => Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "ManageLedgerParameters proposals are not allowed while \
+                        governance is in PreInitializationSwap mode: {:#?}",
+                    action
+                )
+            )),
+
+            Action::DepositToTreasuryManager(_) => Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "DepositToTreasuryManager proposals are not allowed while \
+                        governance is in PreInitializationSwap mode: {:#?}",
+                    action
+                )
+            )),
+
             _ => Ok(()),
         }
     }
@@ -308,6 +343,44 @@ impl Default for &NervousSystemParameters {
     }
 }
 
+impl TreasuryManagerParameters {
+    /// Returns the reasons (if any) why `deposit` may not be executed under
+    /// these parameters: the target must be an approved treasury manager, and
+    /// neither amount may exceed its per-proposal limit (an unset limit allows
+    /// no deposits of that token).
+    pub(crate) fn deposit_defects(&self, deposit: &DepositToTreasuryManager) -> Vec<String> {
+        let mut defects = vec![];
+
+        if let Some(canister_id) = deposit.treasury_manager_canister_id {
+            if !self.approved_canister_ids.contains(&canister_id) {
+                defects.push(format!(
+                    "The treasury manager {} is not approved to receive treasury deposits.",
+                    canister_id
+                ));
+            }
+        }
+
+        let max_icp_amount_e8s = self.max_icp_amount_e8s_per_proposal.unwrap_or_default();
+        if deposit.icp_amount_e8s > max_icp_amount_e8s {
+            defects.push(format!(
+                "The ICP amount {} e8s exceeds the per-proposal limit of {} e8s.",
+                deposit.icp_amount_e8s, max_icp_amount_e8s
+            ));
+        }
+        let max_sns_token_amount_e8s = self
+            .max_sns_token_amount_e8s_per_proposal
+            .unwrap_or_default();
+        if deposit.sns_token_amount_e8s > max_sns_token_amount_e8s {
+            defects.push(format!(
+                "The SNS token amount {} e8s exceeds the per-proposal limit of {} e8s.",
+                deposit.sns_token_amount_e8s, max_sns_token_amount_e8s
+            ));
+        }
+
+        defects
+    }
+}
+
 /// Some constants that define upper bound (ceiling) and lower bounds (floor) for some of
 /// the nervous system parameters as well as the default values for the nervous system
 /// parameters (until we initialize them). We can't implement Default since it conflicts
@@ -360,6 +433,10 @@ impl NervousSystemParameters {
     /// to an over-concentration of voting power. The value used by the NNS is 25.
     pub const MAX_AGE_BONUS_PERCENTAGE_CEILING: u64 = 400;
 
+    /// This is an upper bound for the number of canisters in
+    /// `treasury_manager_parameters.approved_canister_ids`.
+    pub const MAX_APPROVED_TREASURY_MANAGERS_CEILING: usize = 10;
+
     /// These are the permissions that must be present in
     /// `neuron_claimer_permissions`.
     /// Permissions not in this list can be added after the SNS is created via a
@@ -418,6 +495,9 @@ impl NervousSystemParameters {
             max_dissolve_delay_bonus_percentage: Some(100),
             max_age_bonus_percentage: Some(25),
             maturity_modulation_disabled: Some(false),
+            // No canister may receive treasury deposits until the SNS
+            // explicitly approves it.
+            treasury_manager_parameters: Some(TreasuryManagerParameters::default()),
         }
     }
 
@@ -486,6 +566,10 @@ impl NervousSystemParameters {
             maturity_modulation_disabled: self
                 .maturity_modulation_disabled
                 .or(base.maturity_modulation_disabled),
+            treasury_manager_parameters: self
+                .treasury_manager_parameters
+                .clone()
+                .or_else(|| base.treasury_manager_parameters.clone()),
         }
     }
 
@@ -510,6 +594,7 @@ impl NervousSystemParameters {
         self.validate_voting_rewards_parameters()?;
         self.validate_max_dissolve_delay_bonus_percentage()?;
         self.validate_max_age_bonus_percentage()?;
+        self.validate_treasury_manager_parameters()?;
 
         Ok(())
     }
@@ -859,6 +944,44 @@ impl NervousSystemParameters {
         }
     }
 
+    /// Validates that the nervous system parameter treasury_manager_parameters
+    /// is well-formed. SNSes created before this field existed do not have it
+    /// set, which is equivalent to not approving any treasury managers.
+    fn validate_treasury_manager_parameters(&self) -> Result<(), String> {
+        let Some(treasury_manager_parameters) = self.treasury_manager_parameters.as_ref() else {
+            return Ok(());
+        };
+
+        let approved_canister_ids = &treasury_manager_parameters.approved_canister_ids;
+        if approved_canister_ids.len() > Self::MAX_APPROVED_TREASURY_MANAGERS_CEILING {
+            return Err(format!(
+                "NervousSystemParameters.treasury_manager_parameters.approved_canister_ids \
+                 must not contain more than {} entries",
+                Self::MAX_APPROVED_TREASURY_MANAGERS_CEILING
+            ));
+        }
+
+        let mut seen = BTreeSet::new();
+        for canister_id in approved_canister_ids {
+            if *canister_id == PrincipalId::new_anonymous() {
+                return Err(
+                    "NervousSystemParameters.treasury_manager_parameters.approved_canister_ids \
+                     must not contain the anonymous principal"
+                        .to_string(),
+                );
+            }
+            if !seen.insert(*canister_id) {
+                return Err(format!(
+                    "NervousSystemParameters.treasury_manager_parameters.approved_canister_ids \
+                     contains {} more than once",
+                    canister_id
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Given a NeuronPermissionList, check whether the provided list can be
     /// granted given the `NervousSystemParameters::neuron_grantable_permissions`.
     /// Format a useful error if not.
@@ -1094,6 +1217,49 @@ impl From<Action> for NervousSystemFunction {
                 ),
                 function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
             },
+            Action::ManageLedgerParameters(_) => NervousSystemFunction {
+                id: native_action_ids::MANAGE_LEDGER_PARAMETERS,
+                name: "Manage ledger parameters".to_string(),
+                description: Some(
+                    "Proposal to change some parameters in the ledger canister.".to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
+            Action::ManageDappCanisterSettings(_) => NervousSystemFunction {
+                id: native_action_ids::MANAGE_DAPP_CANISTER_SETTINGS,
+                name: "Manage dapp canister settings".to_string(),
+                description: Some(
+                    "Proposal to change the settings of registered dapp canisters.".to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
+            Action::AdvanceSnsTargetVersion(_) => NervousSystemFunction {
+                id: native_action_ids::ADVANCE_SNS_TARGET_VERSION,
+                name: "Advance SNS target version".to_string(),
+                description: Some(
+                    "Proposal to set the version that the SNS is automatically upgraded to."
+                        .to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
+            Action::DepositToTreasuryManager(_) => NervousSystemFunction {
+                id: native_action_ids::DEPOSIT_TO_TREASURY_MANAGER,
+                name: "Deposit to treasury manager".to_string(),
+                description: Some(
+                    "Proposal to deposit SNS treasury funds into a treasury manager.".to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
+            Action::WithdrawFromTreasuryManager(_) => NervousSystemFunction {
+                id: native_action_ids::WITHDRAW_FROM_TREASURY_MANAGER,
+                name: "Withdraw from treasury manager".to_string(),
+                description: Some(
+                    "Proposal to withdraw the funds managed by a treasury manager back to the \
+                     SNS treasury."
+                        .to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
         }
     }
 }
@@ -1424,6 +1590,7 @@ impl Action {
             Action::UpgradeSnsControlledCanister(_) => true,
             // Due to possible need of an emergency upgrade of the SNS
             Action::UpgradeSnsToNextVersion(_) => true,
+            Action::AdvanceSnsTargetVersion(_) => true,
             // Due to possible need of emergency functions defined as
             // GenericNervousSystemFunctions
             Action::ExecuteGenericNervousSystemFunction(_) => true,
@@ -1465,7 +1632,8 @@ impl Action {
         match self {
             Action::DeregisterDappCanisters(_)
             | Action::TransferSnsTreasuryFunds(_)
-            | Action::MintSnsTokens(_) => {
+            | Action::MintSnsTokens(_)
+            | Action::DepositToTreasuryManager(_) => {
                 NervousSystemParameters::CRITICAL_MINIMUM_YES_PROPORTION_OF_TOTAL_VOTING_POWER
             }
             _ => NervousSystemParameters::DEFAULT_MINIMUM_YES_PROPORTION_OF_TOTAL_VOTING_POWER,
@@ -1478,7 +1646,8 @@ impl Action {
         match self {
             Action::DeregisterDappCanisters(_)
             | Action::TransferSnsTreasuryFunds(_)
-            | Action::MintSnsTokens(_) => {
+            | Action::MintSnsTokens(_)
+            | Action::DepositToTreasuryManager(_) => {
                 NervousSystemParameters::CRITICAL_MINIMUM_YES_PROPORTION_OF_EXERCISED_VOTING_POWER
             }
             _ => NervousSystemParameters::DEFAULT_MINIMUM_YES_PROPORTION_OF_EXERCISED_VOTING_POWER,
@@ -1578,6 +1747,15 @@ impl From<&Action> for u64 {
             Action::ManageSnsMetadata(_) => native_action_ids::MANAGE_SNS_METADATA,
             Action::TransferSnsTreasuryFunds(_) => native_action_ids::TRANSFER_SNS_TREASURY_FUNDS,
             Action::MintSnsTokens(_) => native_action_ids::MINT_SNS_TOKENS,
+            Action::ManageLedgerParameters(_) => native_action_ids::MANAGE_LEDGER_PARAMETERS,
+            Action::ManageDappCanisterSettings(_) => {
+                native_action_ids::MANAGE_DAPP_CANISTER_SETTINGS
+            }
+            Action::AdvanceSnsTargetVersion(_) => native_action_ids::ADVANCE_SNS_TARGET_VERSION,
+            Action::DepositToTreasuryManager(_) => native_action_ids::DEPOSIT_TO_TREASURY_MANAGER,
+            Action::WithdrawFromTreasuryManager(_) => {
+                native_action_ids::WITHDRAW_FROM_TREASURY_MANAGER
+            }
         }
     }
 }
@@ -2004,6 +2182,27 @@ impl From<DeregisterDappCanisters> for SetDappControllersRequest {
     }
 }
 
+impl From<ManageDappCanisterSettings> for ManageDappCanisterSettingsRequest {
+    fn from(
+        manage_dapp_canister_settings: ManageDappCanisterSettings,
+    ) -> ManageDappCanisterSettingsRequest {
+        let ManageDappCanisterSettings {
+            canister_ids,
+            compute_allocation,
+            memory_allocation,
+            freezing_threshold,
+            reserved_cycles_limit,
+        } = manage_dapp_canister_settings;
+        ManageDappCanisterSettingsRequest {
+            canister_ids,
+            compute_allocation,
+            memory_allocation,
+            freezing_threshold,
+            reserved_cycles_limit,
+        }
+    }
+}
+
 impl Motion {
     pub fn new(text: &str) -> Self {
         Motion {
@@ -2070,6 +2269,36 @@ impl From<MintSnsTokens> for Action {
     }
 }
 
+impl From<ManageLedgerParameters> for Action {
+    fn from(manage_ledger_parameters: ManageLedgerParameters) -> Action {
+        Action::ManageLedgerParameters(manage_ledger_parameters)
+    }
+}
+
+impl From<ManageDappCanisterSettings> for Action {
+    fn from(manage_dapp_canister_settings: ManageDappCanisterSettings) -> Action {
+        Action::ManageDappCanisterSettings(manage_dapp_canister_settings)
+    }
+}
+
+impl From<AdvanceSnsTargetVersion> for Action {
+    fn from(advance_sns_target_version: AdvanceSnsTargetVersion) -> Action {
+        Action::AdvanceSnsTargetVersion(advance_sns_target_version)
+    }
+}
+
+impl From<DepositToTreasuryManager> for Action {
+    fn from(deposit_to_treasury_manager: DepositToTreasuryManager) -> Action {
+        Action::DepositToTreasuryManager(deposit_to_treasury_manager)
+    }
+}
+
+impl From<WithdrawFromTreasuryManager> for Action {
+    fn from(withdraw_from_treasury_manager: WithdrawFromTreasuryManager) -> Action {
+        Action::WithdrawFromTreasuryManager(withdraw_from_treasury_manager)
+    }
+}
+
 pub mod test_helpers {
     use super::*;
     use ic_crypto_sha2::Sha256;
@@ -2622,11 +2851,15 @@ pub(crate) mod tests {
                 Action::UpgradeSnsControlledCanister       (Default::default()),
                 Action::AddGenericNervousSystemFunction    (Default::default()),
                 Action::RemoveGenericNervousSystemFunction (Default::default()),
+                Action::ManageDappCanisterSettings         (Default::default()),
+                Action::WithdrawFromTreasuryManager        (Default::default()),
             ];
 
             let disallowed_in_pre_initialization_swap = vec! [
                 Action::ManageNervousSystemParameters(Default::default()),
-                Action::TransferSnsTreasuryFunds(Default::default())
+                Action::TransferSnsTreasuryFunds(Default::default()),
+                Action::ManageLedgerParameters(Default::default()),
+                Action::DepositToTreasuryManager(Default::default()),
             ];
 
             // Conditionally allow: No targeting SNS canisters.
@@ -3088,6 +3321,35 @@ pub(crate) mod tests {
         }
     }
 
+    #[test]
+    fn test_nervous_system_parameters_validate_treasury_manager_parameters() {
+        let canister_id = CanisterId::from_u64(1000).get();
+        let mut parameters = NervousSystemParameters::with_default_values();
+        parameters.treasury_manager_parameters = Some(TreasuryManagerParameters {
+            approved_canister_ids: vec![canister_id],
+            ..Default::default()
+        });
+        parameters.validate().unwrap();
+
+        // Unset parameters (as in SNSes that predate them) are valid.
+        parameters.treasury_manager_parameters = None;
+        parameters.validate().unwrap();
+
+        for approved_canister_ids in [
+            vec![canister_id, canister_id],
+            vec![PrincipalId::new_anonymous()],
+            (0..=NervousSystemParameters::MAX_APPROVED_TREASURY_MANAGERS_CEILING as u64)
+                .map(|i| CanisterId::from_u64(i).get())
+                .collect(),
+        ] {
+            parameters.treasury_manager_parameters = Some(TreasuryManagerParameters {
+                approved_canister_ids,
+                ..Default::default()
+            });
+            parameters.validate().unwrap_err();
+        }
+    }
+
     #[test]
     fn test_validate_logo_lets_base64_through() {
         SnsMetadata::validate_logo("data:image/png;base64,aGVsbG8gZnJvbSBkZmluaXR5IQ==").unwrap();
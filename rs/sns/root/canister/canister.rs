@@ -17,6 +17,7 @@ use ic_sns_root::{
     logs::{ERROR, INFO},
     pb::v1::{
         CanisterCallError, ListSnsCanistersRequest, ListSnsCanistersResponse,
+        ManageDappCanisterSettingsRequest, ManageDappCanisterSettingsResponse,
         RegisterDappCanisterRequest, RegisterDappCanisterResponse, RegisterDappCanistersRequest,
         RegisterDappCanistersResponse, SetDappControllersRequest, SetDappControllersResponse,
         SnsRootCanister,
@@ -306,6 +307,25 @@ async fn set_dapp_controllers(request: SetDappControllersRequest) -> SetDappCont
     .await
 }
 
+/// Changes the settings (other than controllers) of registered dapp canisters.
+///
+/// Caller must be the Governance canister. Otherwise, the request will be
+/// rejected. All canisters in the request must be registered dapp canisters.
+#[candid_method(update)]
+#[update]
+async fn manage_dapp_canister_settings(
+    request: ManageDappCanisterSettingsRequest,
+) -> ManageDappCanisterSettingsResponse {
+    log!(INFO, "manage_dapp_canister_settings");
+    SnsRootCanister::manage_dapp_canister_settings(
+        &STATE,
+        &ManagementCanisterClientImpl::<CanisterRuntime>::new(None),
+        PrincipalId(ic_cdk::api::caller()),
+        request,
+    )
+    .await
+}
+
 fn assert_state_is_valid(state: &SnsRootCanister) {
     assert!(state.governance_canister_id.is_some());
     assert!(state.ledger_canister_id.is_some());
@@ -2,6 +2,7 @@ use crate::{
     logs::{ERROR, INFO},
     pb::v1::{
         set_dapp_controllers_response, CanisterCallError, ListSnsCanistersResponse,
+        ManageDappCanisterSettingsRequest, ManageDappCanisterSettingsResponse,
         RegisterDappCanistersRequest, RegisterDappCanistersResponse, SetDappControllersRequest,
         SetDappControllersResponse, SnsRootCanister,
     },
@@ -599,6 +600,87 @@ impl SnsRootCanister {
         SetDappControllersResponse { failed_updates }
     }
 
+    /// Changes the settings (other than controllers) of registered dapp
+    /// canisters. Only the settings that are set in the request are changed.
+    ///
+    /// Caller must be the Governance canister, and all canisters in the
+    /// request must be registered dapp canisters. Otherwise, no changes are
+    /// made and the reason is reported in the response.
+    ///
+    /// Since the settings of several canisters cannot be updated atomically,
+    /// all canisters are attempted, and failures are reported together.
+    pub async fn manage_dapp_canister_settings(
+        self_ref: &'static LocalKey<RefCell<Self>>,
+        management_canister_client: &impl ManagementCanisterClient,
+        caller: PrincipalId,
+        request: ManageDappCanisterSettingsRequest,
+    ) -> ManageDappCanisterSettingsResponse {
+        let is_authorized =
+            self_ref.with(|self_ref| caller == self_ref.borrow().governance_canister_id());
+        if !is_authorized {
+            return ManageDappCanisterSettingsResponse {
+                failure_reason: Some(format!("Caller ({caller}) is not authorized.")),
+            };
+        }
+
+        let unregistered_canister_ids = self_ref.with(|self_ref| {
+            let self_ref = self_ref.borrow();
+            request
+                .canister_ids
+                .iter()
+                .filter(|canister_id| !self_ref.dapp_canister_ids.contains(canister_id))
+                .map(|canister_id| canister_id.to_string())
+                .collect::<Vec<_>>()
+        });
+        if !unregistered_canister_ids.is_empty() {
+            return ManageDappCanisterSettingsResponse {
+                failure_reason: Some(format!(
+                    "Operation aborted; no changes have been made: the following canisters \
+                     are not registered dapp canisters: {}",
+                    unregistered_canister_ids.join(", ")
+                )),
+            };
+        }
+
+        let settings = CanisterSettings {
+            controllers: None,
+            compute_allocation: request.compute_allocation.map(candid::Nat::from),
+            memory_allocation: request.memory_allocation.map(candid::Nat::from),
+            freezing_threshold: request.freezing_threshold.map(candid::Nat::from),
+            reserved_cycles_limit: request.reserved_cycles_limit.map(candid::Nat::from),
+        };
+
+        let mut failures = vec![];
+        for dapp_canister_id in &request.canister_ids {
+            let update_settings = UpdateSettings {
+                canister_id: *dapp_canister_id,
+                settings: settings.clone(),
+                sender_canister_version: management_canister_client.canister_version(),
+            };
+            if let Err(err) = management_canister_client
+                .update_settings(update_settings)
+                .await
+            {
+                log!(
+                    ERROR,
+                    "Unable to update the settings of {dapp_canister_id}: {err:#?}"
+                );
+                failures.push(format!(
+                    "Unable to update the settings of {dapp_canister_id}: code {}: {}",
+                    err.0, err.1
+                ));
+            }
+        }
+
+        ManageDappCanisterSettingsResponse {
+            failure_reason: if failures.is_empty() {
+                None
+            } else {
+                Some(failures.join("\n"))
+            },
+        }
+    }
+
     /// Runs periodic tasks that are not directly triggered by user input.
     pub async fn heartbeat(
         self_ref: &'static LocalKey<RefCell<Self>>,
@@ -2113,6 +2195,135 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_manage_dapp_canister_settings() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static STATE: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
+                governance_canister_id: Some(PrincipalId::new_user_test_id(1)),
+                ledger_canister_id: Some(PrincipalId::new_user_test_id(2)),
+                swap_canister_id: Some(PrincipalId::new_user_test_id(99)),
+                dapp_canister_ids: vec![PrincipalId::new_user_test_id(3), PrincipalId::new_user_test_id(4)],
+                ..Default::default()
+            });
+        }
+
+        // Step 1.1: Prepare helpers.
+        let management_canister_client = MockManagementCanisterClient::new(vec![
+            MockManagementCanisterClientReply::UpdateSettings(Ok(())),
+            MockManagementCanisterClientReply::UpdateSettings(Err((
+                5,
+                "Canister out of cycles".to_string(),
+            ))),
+        ]);
+
+        // Step 2: Run code under test.
+        let response = SnsRootCanister::manage_dapp_canister_settings(
+            &STATE,
+            &management_canister_client,
+            STATE.with(|state| state.borrow().governance_canister_id.unwrap()),
+            ManageDappCanisterSettingsRequest {
+                canister_ids: vec![
+                    PrincipalId::new_user_test_id(3),
+                    PrincipalId::new_user_test_id(4),
+                ],
+                compute_allocation: Some(10),
+                memory_allocation: None,
+                freezing_threshold: Some(2_592_000),
+                reserved_cycles_limit: Some(1_000_000_000_000),
+            },
+        )
+        .await;
+
+        // Step 3: Inspect results.
+        let failure_reason = response.failure_reason.unwrap();
+        assert!(
+            failure_reason.contains(&PrincipalId::new_user_test_id(4).to_string()),
+            "{failure_reason}"
+        );
+        assert!(
+            !failure_reason.contains(&PrincipalId::new_user_test_id(3).to_string()),
+            "{failure_reason}"
+        );
+
+        let expected_settings = CanisterSettings {
+            controllers: None,
+            compute_allocation: Some(candid::Nat::from(10_u64)),
+            memory_allocation: None,
+            freezing_threshold: Some(candid::Nat::from(2_592_000_u64)),
+            reserved_cycles_limit: Some(candid::Nat::from(1_000_000_000_000_u64)),
+        };
+        let actual_management_canister_calls = management_canister_client.get_calls_snapshot();
+        let expected_management_canister_calls = vec![
+            MockManagementCanisterClientCall::UpdateSettings(UpdateSettings {
+                canister_id: PrincipalId::new_user_test_id(3),
+                settings: expected_settings.clone(),
+                sender_canister_version: None,
+            }),
+            MockManagementCanisterClientCall::UpdateSettings(UpdateSettings {
+                canister_id: PrincipalId::new_user_test_id(4),
+                settings: expected_settings,
+                sender_canister_version: None,
+            }),
+        ];
+        assert_eq!(
+            actual_management_canister_calls,
+            expected_management_canister_calls
+        );
+    }
+
+    #[tokio::test]
+    async fn test_manage_dapp_canister_settings_rejects_unregistered_canisters() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static STATE: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
+                governance_canister_id: Some(PrincipalId::new_user_test_id(1)),
+                ledger_canister_id: Some(PrincipalId::new_user_test_id(2)),
+                swap_canister_id: Some(PrincipalId::new_user_test_id(99)),
+                dapp_canister_ids: vec![PrincipalId::new_user_test_id(3)],
+                ..Default::default()
+            });
+        }
+        let management_canister_client = MockManagementCanisterClient::new(vec![]);
+        let request = ManageDappCanisterSettingsRequest {
+            canister_ids: vec![
+                PrincipalId::new_user_test_id(3),
+                PrincipalId::new_user_test_id(2),
+            ],
+            compute_allocation: Some(10),
+            ..Default::default()
+        };
+
+        // Step 2: Run code under test, both as a non-governance caller and
+        // with a canister that is not a registered dapp.
+        let unauthorized_response = SnsRootCanister::manage_dapp_canister_settings(
+            &STATE,
+            &management_canister_client,
+            PrincipalId::new_user_test_id(99),
+            request.clone(),
+        )
+        .await;
+        let unregistered_response = SnsRootCanister::manage_dapp_canister_settings(
+            &STATE,
+            &management_canister_client,
+            PrincipalId::new_user_test_id(1),
+            request,
+        )
+        .await;
+
+        // Step 3: Inspect results. No calls to the management canister were made.
+        assert!(unauthorized_response
+            .failure_reason
+            .unwrap()
+            .contains("not authorized"));
+        let failure_reason = unregistered_response.failure_reason.unwrap();
+        assert!(
+            failure_reason.contains(&PrincipalId::new_user_test_id(2).to_string()),
+            "{failure_reason}"
+        );
+        assert_eq!(management_canister_client.get_calls_snapshot(), vec![]);
+    }
+
     #[test]
     fn test_list_sns_canisters() {
         let state = SnsRootCanister {
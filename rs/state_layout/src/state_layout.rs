@@ -15,9 +15,13 @@ use ic_protobuf::{
 use ic_replicated_state::{
     canister_state::{
         execution_state::{NextScheduledMethod, WasmMetadata},
-        system_state::{wasm_chunk_store::WasmChunkStoreMetadata, CanisterHistory, CyclesUseCase},
+        system_state::{
+            wasm_chunk_store::WasmChunkStoreMetadata, AutoTopUp, CanisterHistory, CyclesUseCase,
+            InstallCodeProgress, MethodStats,
+        },
     },
     CallContextManager, CanisterStatus, ExecutionTask, ExportedFunctions, Global, NumWasmPages,
+    QueueLatencyStats,
 };
 use ic_sys::mmap::ScopedMmap;
 use ic_types::{
@@ -161,6 +165,17 @@ pub struct CanisterStateBits {
     pub canister_history: CanisterHistory,
     pub wasm_chunk_store_metadata: WasmChunkStoreMetadata,
     pub total_query_stats: TotalQueryStats,
+    pub query_cache_max_staleness: NumSeconds,
+    pub auto_top_up: Option<AutoTopUp>,
+    pub auto_top_up_beneficiaries: BTreeSet<CanisterId>,
+    pub time_of_last_auto_top_up_nanos: u64,
+    pub extended_install_code_limit: bool,
+    pub install_code_progress: Option<InstallCodeProgress>,
+    pub method_stats: BTreeMap<String, MethodStats>,
+    pub input_wait_start_nanos: Option<u64>,
+    pub response_wait_start_round: Option<ExecutionRound>,
+    pub queue_latency_stats: QueueLatencyStats,
+    pub migrating: bool,
 }
 
 #[derive(Clone)]
@@ -1766,6 +1781,28 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
             canister_history: Some((&item.canister_history).into()),
             wasm_chunk_store_metadata: Some((&item.wasm_chunk_store_metadata).into()),
             total_query_stats: Some((&item.total_query_stats).into()),
+            query_cache_max_staleness: item.query_cache_max_staleness.get(),
+            auto_top_up: item.auto_top_up.as_ref().map(|v| v.into()),
+            auto_top_up_beneficiaries: item
+                .auto_top_up_beneficiaries
+                .iter()
+                .map(|canister_id| (*canister_id).into())
+                .collect(),
+            time_of_last_auto_top_up_nanos: item.time_of_last_auto_top_up_nanos,
+            extended_install_code_limit: item.extended_install_code_limit,
+            install_code_progress: item.install_code_progress.as_ref().map(|v| v.into()),
+            method_stats: item
+                .method_stats
+                .iter()
+                .map(|(method_name, stats)| pb_canister_state_bits::MethodStats {
+                    method_name: method_name.clone(),
+                    ..stats.into()
+                })
+                .collect(),
+            input_wait_start_nanos: item.input_wait_start_nanos,
+            response_wait_start_round: item.response_wait_start_round.map(|round| round.get()),
+            queue_latency_stats: Some((&item.queue_latency_stats).into()),
+            migrating: item.migrating,
         }
     }
 }
@@ -1796,6 +1833,11 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
             controllers.insert(PrincipalId::try_from(controller)?);
         }
 
+        let mut auto_top_up_beneficiaries = BTreeSet::new();
+        for beneficiary in value.auto_top_up_beneficiaries.into_iter() {
+            auto_top_up_beneficiaries.insert(CanisterId::try_from(beneficiary)?);
+        }
+
         let cycles_balance =
             try_from_option_field(value.cycles_balance, "CanisterStateBits::cycles_balance")?;
 
@@ -1887,6 +1929,24 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
                 "CanisterStateBits::total_query_stats",
             )
             .unwrap_or_default(),
+            query_cache_max_staleness: NumSeconds::from(value.query_cache_max_staleness),
+            auto_top_up: value.auto_top_up.map(|v| v.try_into()).transpose()?,
+            auto_top_up_beneficiaries,
+            time_of_last_auto_top_up_nanos: value.time_of_last_auto_top_up_nanos,
+            extended_install_code_limit: value.extended_install_code_limit,
+            install_code_progress: value.install_code_progress.map(|v| v.into()),
+            method_stats: value
+                .method_stats
+                .into_iter()
+                .map(|stats| (stats.method_name.clone(), stats.into()))
+                .collect(),
+            input_wait_start_nanos: value.input_wait_start_nanos,
+            response_wait_start_round: value.response_wait_start_round.map(|round| round.into()),
+            queue_latency_stats: value
+                .queue_latency_stats
+                .map(|stats| stats.into())
+                .unwrap_or_default(),
+            migrating: value.migrating,
         })
     }
 }
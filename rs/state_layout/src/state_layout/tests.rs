@@ -5,6 +5,7 @@ use ic_ic00_types::{
 };
 use ic_replicated_state::canister_state::system_state::CanisterHistory;
 use ic_replicated_state::metadata_state::subnet_call_context_manager::InstallCodeCallId;
+use ic_replicated_state::CyclesLedgerAccount;
 use ic_test_utilities::types::ids::user_test_id;
 use ic_test_utilities::{
     mock_time,
@@ -16,10 +17,12 @@ use ic_test_utilities::{
 use ic_test_utilities_logger::with_test_replica_logger;
 use ic_test_utilities_tmpdir::tmpdir;
 use ic_types::messages::{CanisterCall, CanisterMessage, CanisterMessageOrTask};
+use ic_types::Time;
 use itertools::Itertools;
 use proptest::prelude::*;
 use std::fs::File;
 use std::sync::Arc;
+use std::time::Duration;
 
 fn default_canister_state_bits() -> CanisterStateBits {
     CanisterStateBits {
@@ -53,6 +56,17 @@ fn default_canister_state_bits() -> CanisterStateBits {
         canister_history: CanisterHistory::default(),
         wasm_chunk_store_metadata: WasmChunkStoreMetadata::default(),
         total_query_stats: TotalQueryStats::default(),
+        query_cache_max_staleness: NumSeconds::from(0),
+        auto_top_up: None,
+        auto_top_up_beneficiaries: BTreeSet::new(),
+        time_of_last_auto_top_up_nanos: 0,
+        extended_install_code_limit: false,
+        install_code_progress: None,
+        method_stats: BTreeMap::new(),
+        input_wait_start_nanos: None,
+        response_wait_start_round: None,
+        queue_latency_stats: QueueLatencyStats::default(),
+        migrating: false,
     }
 }
 
@@ -180,6 +194,12 @@ fn test_encode_decode_non_empty_history() {
         CanisterChangeOrigin::from_canister(canister_test_id(123).get(), None),
         CanisterChangeDetails::controllers_change(vec![]),
     ));
+    canister_history.add_canister_change(CanisterChange::new(
+        555,
+        7,
+        CanisterChangeOrigin::from_canister(canister_test_id(456).get(), None),
+        CanisterChangeDetails::auto_top_up(canister_test_id(456).get(), u128::MAX),
+    ));
 
     // A canister state with non-empty history.
     let canister_state_bits = CanisterStateBits {
@@ -193,6 +213,147 @@ fn test_encode_decode_non_empty_history() {
     assert_eq!(canister_state_bits.canister_history, canister_history);
 }
 
+#[test]
+fn test_encode_decode_auto_top_up() {
+    let auto_top_up = AutoTopUp {
+        funding_canister: canister_test_id(456),
+        threshold: Cycles::new(1_000_000),
+        amount: Cycles::new(u128::MAX),
+        funding_account: None,
+    };
+    let beneficiaries: BTreeSet<_> = [canister_test_id(1), canister_test_id(2)].into();
+
+    let canister_state_bits = CanisterStateBits {
+        auto_top_up: Some(auto_top_up),
+        auto_top_up_beneficiaries: beneficiaries.clone(),
+        time_of_last_auto_top_up_nanos: 42,
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(canister_state_bits.auto_top_up, Some(auto_top_up));
+    assert_eq!(canister_state_bits.auto_top_up_beneficiaries, beneficiaries);
+    assert_eq!(canister_state_bits.time_of_last_auto_top_up_nanos, 42);
+}
+
+#[test]
+fn test_encode_decode_auto_top_up_from_cycles_ledger_account() {
+    let auto_top_up = AutoTopUp {
+        funding_canister: canister_test_id(456),
+        threshold: Cycles::new(1_000_000),
+        amount: Cycles::new(2_000_000),
+        funding_account: Some(CyclesLedgerAccount {
+            owner: user_test_id(7).get(),
+            subaccount: Some([3; 32]),
+        }),
+    };
+
+    let canister_state_bits = CanisterStateBits {
+        auto_top_up: Some(auto_top_up),
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(canister_state_bits.auto_top_up, Some(auto_top_up));
+}
+
+#[test]
+fn test_encode_decode_install_code_progress() {
+    let install_code_progress = InstallCodeProgress {
+        start_time: Time::from_nanos_since_unix_epoch(1_000),
+        executed_instructions: NumInstructions::from(42_000_000_000),
+        num_restarts: 2,
+    };
+
+    let canister_state_bits = CanisterStateBits {
+        extended_install_code_limit: true,
+        install_code_progress: Some(install_code_progress),
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert!(canister_state_bits.extended_install_code_limit);
+    assert_eq!(
+        canister_state_bits.install_code_progress,
+        Some(install_code_progress)
+    );
+}
+
+#[test]
+fn test_encode_decode_method_stats() {
+    let transfer_stats = MethodStats {
+        num_calls: 10,
+        num_traps: 1,
+        instructions_executed: NumInstructions::from(5_000_000),
+        dirty_pages: 12,
+        num_responses: 9,
+        total_latency: Duration::from_secs(18),
+    };
+    let method_stats = BTreeMap::from([
+        ("transfer".to_string(), transfer_stats),
+        ("canister_heartbeat".to_string(), MethodStats::default()),
+    ]);
+    let canister_state_bits = CanisterStateBits {
+        method_stats: method_stats.clone(),
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(canister_state_bits.method_stats, method_stats);
+    assert_eq!(
+        canister_state_bits.method_stats["transfer"].average_latency(),
+        Duration::from_secs(2)
+    );
+}
+
+#[test]
+fn test_encode_decode_queue_latency_stats() {
+    let queue_latency_stats = QueueLatencyStats {
+        num_waits: 4,
+        total_wait_nanos: 8_000_000_000,
+        max_wait_nanos: 5_000_000_000,
+        rounds_with_input: 8,
+        rounds_executed: 4,
+    };
+    let canister_state_bits = CanisterStateBits {
+        input_wait_start_nanos: Some(mock_time().as_nanos_since_unix_epoch()),
+        response_wait_start_round: Some(ExecutionRound::from(42)),
+        queue_latency_stats: queue_latency_stats.clone(),
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(
+        canister_state_bits.input_wait_start_nanos,
+        Some(mock_time().as_nanos_since_unix_epoch())
+    );
+    assert_eq!(
+        canister_state_bits.response_wait_start_round,
+        Some(ExecutionRound::from(42))
+    );
+    assert_eq!(canister_state_bits.queue_latency_stats, queue_latency_stats);
+    assert_eq!(
+        canister_state_bits.queue_latency_stats.average_wait(),
+        Duration::from_secs(2)
+    );
+    assert_eq!(
+        canister_state_bits
+            .queue_latency_stats
+            .scheduling_share_percent(),
+        50
+    );
+}
+
 #[test]
 fn test_encode_decode_task_queue() {
     let ingress = Arc::new(IngressBuilder::new().method_name("test_ingress").build());
@@ -130,7 +130,7 @@ use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use std::io::stderr;
 use std::ops::RangeInclusive;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::string::ToString;
 use std::sync::Arc;
@@ -565,6 +565,7 @@ pub struct StateMachineBuilder {
     features: SubnetFeatures,
     runtime: Option<Arc<Runtime>>,
     registry_data_provider: Arc<ProtoRegistryDataProvider>,
+    wasm_function_profile_dir: Option<PathBuf>,
 }
 
 impl StateMachineBuilder {
@@ -595,6 +596,7 @@ impl StateMachineBuilder {
             },
             runtime: None,
             registry_data_provider: Arc::new(ProtoRegistryDataProvider::new()),
+            wasm_function_profile_dir: None,
         }
     }
 
@@ -731,6 +733,15 @@ impl StateMachineBuilder {
         }
     }
 
+    /// Profiles the instructions executed in each Wasm function and appends
+    /// the profiles in the folded stack format to `<dir>/<canister_id>.folded`.
+    pub fn with_wasm_function_profile_dir(self, dir: PathBuf) -> Self {
+        Self {
+            wasm_function_profile_dir: Some(dir),
+            ..self
+        }
+    }
+
     pub fn build(self) -> StateMachine {
         let mut routing_table = self.routing_table;
         if routing_table.is_empty() {
@@ -746,11 +757,27 @@ impl StateMachineBuilder {
                 self.registry_data_provider.clone(),
             );
         }
+        let config = match self.wasm_function_profile_dir {
+            Some(dir) => {
+                let mut config = self.config.unwrap_or_else(|| {
+                    StateMachineConfig::new(
+                        SubnetConfig::new(self.subnet_type),
+                        HypervisorConfig::default(),
+                    )
+                });
+                config
+                    .hypervisor_config
+                    .embedders_config
+                    .wasm_function_profile_dir = Some(dir);
+                Some(config)
+            }
+            None => self.config,
+        };
         StateMachine::setup_from_dir(
             self.state_dir,
             self.nonce,
             self.time,
-            self.config,
+            config,
             self.checkpoints_enabled,
             self.subnet_type,
             self.subnet_size,
@@ -402,6 +402,14 @@ pub fn load_canister_state<P: ReadPolicy>(
         canister_state_bits.canister_history,
         wasm_chunk_store_data,
         canister_state_bits.wasm_chunk_store_metadata,
+        canister_state_bits.query_cache_max_staleness,
+        canister_state_bits.auto_top_up,
+        canister_state_bits.auto_top_up_beneficiaries,
+        Time::from_nanos_since_unix_epoch(canister_state_bits.time_of_last_auto_top_up_nanos),
+        canister_state_bits.extended_install_code_limit,
+        canister_state_bits.install_code_progress,
+        canister_state_bits.method_stats,
+        canister_state_bits.migrating,
     );
 
     let canister_state = CanisterState {
@@ -421,6 +429,11 @@ pub fn load_canister_state<P: ReadPolicy>(
                 canister_state_bits.time_of_last_allocation_charge_nanos,
             ),
             total_query_stats: canister_state_bits.total_query_stats,
+            input_wait_start: canister_state_bits
+                .input_wait_start_nanos
+                .map(Time::from_nanos_since_unix_epoch),
+            response_wait_start_round: canister_state_bits.response_wait_start_round,
+            queue_latency_stats: canister_state_bits.queue_latency_stats,
         },
     };
 
@@ -624,6 +624,26 @@ fn serialize_canister_to_tip(
                 .metadata()
                 .clone(),
             total_query_stats: canister_state.scheduler_state.total_query_stats.clone(),
+            query_cache_max_staleness: canister_state.system_state.query_cache_max_staleness,
+            auto_top_up: canister_state.system_state.auto_top_up,
+            auto_top_up_beneficiaries: canister_state
+                .system_state
+                .auto_top_up_beneficiaries
+                .clone(),
+            time_of_last_auto_top_up_nanos: canister_state
+                .system_state
+                .time_of_last_auto_top_up
+                .as_nanos_since_unix_epoch(),
+            extended_install_code_limit: canister_state.system_state.extended_install_code_limit,
+            install_code_progress: canister_state.system_state.install_code_progress,
+            method_stats: canister_state.system_state.method_stats.clone(),
+            input_wait_start_nanos: canister_state
+                .scheduler_state
+                .input_wait_start
+                .map(|time| time.as_nanos_since_unix_epoch()),
+            response_wait_start_round: canister_state.scheduler_state.response_wait_start_round,
+            queue_latency_stats: canister_state.scheduler_state.queue_latency_stats.clone(),
+            migrating: canister_state.system_state.migrating,
         }
         .into(),
     )?;
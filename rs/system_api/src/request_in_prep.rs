@@ -47,6 +47,9 @@ pub struct RequestInPrep {
     /// them up creating tricky bugs. Storing this an integer means that the two
     /// limits are stored as different types and are more difficult to mix up.
     multiplier_max_size_local_subnet: u64,
+    /// Whether the call is executed synchronously as a replicated query call
+    /// if possible.
+    replicated_query: bool,
 }
 
 impl RequestInPrep {
@@ -110,6 +113,7 @@ impl RequestInPrep {
             method_payload: Vec::new(),
             max_size_remote_subnet,
             multiplier_max_size_local_subnet,
+            replicated_query: false,
         })
     }
 
@@ -125,6 +129,10 @@ impl RequestInPrep {
         }
     }
 
+    pub(crate) fn set_replicated_query(&mut self) {
+        self.replicated_query = true;
+    }
+
     pub(crate) fn take_cycles(self) -> Cycles {
         self.cycles
     }
@@ -178,6 +186,7 @@ pub(crate) fn into_request(
         method_payload,
         max_size_remote_subnet,
         multiplier_max_size_local_subnet,
+        replicated_query,
     }: RequestInPrep,
     call_context_id: CallContextId,
     sandbox_safe_system_state: &mut SandboxSafeSystemState,
@@ -203,7 +212,7 @@ pub(crate) fn into_request(
     let prepayment_for_response_transmission =
         sandbox_safe_system_state.prepayment_for_response_transmission();
 
-    let callback_id = sandbox_safe_system_state.register_callback(Callback::new(
+    let mut callback = Callback::new(
         call_context_id,
         Some(sender),
         Some(destination_canister),
@@ -213,7 +222,9 @@ pub(crate) fn into_request(
         on_reply,
         on_reject,
         on_cleanup,
-    ))?;
+    );
+    callback.replicated_query = replicated_query;
+    let callback_id = sandbox_safe_system_state.register_callback(callback)?;
 
     let req = Request {
         sender,
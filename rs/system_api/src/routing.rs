@@ -5,12 +5,13 @@ use ic_base_types::{CanisterId, PrincipalId, SubnetId};
 use ic_btc_interface::NetworkInRequest as BitcoinNetwork;
 use ic_error_types::UserError;
 use ic_ic00_types::{
-    BitcoinGetBalanceArgs, BitcoinGetCurrentFeePercentilesArgs, BitcoinGetUtxosArgs,
-    BitcoinSendTransactionArgs, CanisterIdRecord, CanisterInfoRequest, ClearChunkStoreArgs,
-    ComputeInitialEcdsaDealingsArgs, ECDSAPublicKeyArgs, EcdsaKeyId, InstallChunkedCodeArgs,
-    InstallCodeArgsV2, Method as Ic00Method, NodeMetricsHistoryArgs, Payload,
-    ProvisionalTopUpCanisterArgs, SignWithECDSAArgs, StoredChunksArgs, UninstallCodeArgs,
-    UpdateSettingsArgs, UploadChunkArgs,
+    AbortCanisterMigrationArgs, BitcoinGetBalanceArgs, BitcoinGetCurrentFeePercentilesArgs,
+    BitcoinGetUtxosArgs, BitcoinSendTransactionArgs, CanisterHistoryArgs, CanisterIdRecord,
+    CanisterInfoRequest, ClearChunkStoreArgs, ComputeInitialEcdsaDealingsArgs, ECDSAPublicKeyArgs,
+    EcdsaKeyId, FinishCanisterMigrationArgs, InstallChunkedCodeArgs, InstallCodeArgsV2,
+    Method as Ic00Method, NodeMetricsHistoryArgs, Payload, ProvisionalTopUpCanisterArgs,
+    ReadCanisterMigrationDataArgs, RemoveMigratedCanisterArgs, SignWithECDSAArgs, StoredChunksArgs,
+    UninstallCodeArgs, UpdateSettingsArgs, UploadChunkArgs, WriteCanisterMigrationDataArgs,
 };
 use ic_replicated_state::NetworkTopology;
 
@@ -93,6 +94,9 @@ pub(super) fn resolve_destination(
         Ok(Ic00Method::CanisterStatus)
         | Ok(Ic00Method::StartCanister)
         | Ok(Ic00Method::StopCanister)
+        | Ok(Ic00Method::AbortInstallCode)
+        | Ok(Ic00Method::CanisterMethodStats)
+        | Ok(Ic00Method::ResetCanisterMethodStats)
         | Ok(Ic00Method::DeleteCanister)
         | Ok(Ic00Method::DepositCycles) => {
             let args = CanisterIdRecord::decode(payload)?;
@@ -116,6 +120,17 @@ pub(super) fn resolve_destination(
                     ResolveDestinationError::SubnetNotFound(canister_id, method.unwrap())
                 })
         }
+        Ok(Ic00Method::CanisterHistory) => {
+            let args = CanisterHistoryArgs::decode(payload)?;
+            let canister_id = args.canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or_else(|| {
+                    ResolveDestinationError::SubnetNotFound(canister_id, method.unwrap())
+                })
+        }
         Ok(Ic00Method::UninstallCode) => {
             let args = UninstallCodeArgs::decode(payload)?;
             let canister_id = args.get_canister_id();
@@ -177,6 +192,24 @@ pub(super) fn resolve_destination(
         Ok(Ic00Method::NodeMetricsHistory) => {
             Ok(NodeMetricsHistoryArgs::decode(payload)?.subnet_id)
         }
+        // During a migration the canister exists on both the source and the
+        // target subnet, so these messages are routed to the subnet given in
+        // the payload rather than by the routing table.
+        Ok(Ic00Method::ReadCanisterMigrationData) => {
+            Ok(ReadCanisterMigrationDataArgs::decode(payload)?.subnet_id)
+        }
+        Ok(Ic00Method::WriteCanisterMigrationData) => {
+            Ok(WriteCanisterMigrationDataArgs::decode(payload)?.subnet_id)
+        }
+        Ok(Ic00Method::RemoveMigratedCanister) => {
+            Ok(RemoveMigratedCanisterArgs::decode(payload)?.subnet_id)
+        }
+        Ok(Ic00Method::FinishCanisterMigration) => {
+            Ok(FinishCanisterMigrationArgs::decode(payload)?.subnet_id)
+        }
+        Ok(Ic00Method::AbortCanisterMigration) => {
+            Ok(AbortCanisterMigrationArgs::decode(payload)?.subnet_id)
+        }
         Ok(Ic00Method::ECDSAPublicKey) => {
             let key_id = ECDSAPublicKeyArgs::decode(payload)?.key_id;
             route_ecdsa_message(
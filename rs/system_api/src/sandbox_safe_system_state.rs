@@ -228,6 +228,10 @@ impl SystemStateChanges {
             | Ok(Ic00Method::CanisterInfo)
             | Ok(Ic00Method::StartCanister)
             | Ok(Ic00Method::StopCanister)
+            | Ok(Ic00Method::AbortInstallCode)
+            | Ok(Ic00Method::CanisterMethodStats)
+            | Ok(Ic00Method::ResetCanisterMethodStats)
+            | Ok(Ic00Method::CanisterHistory)
             | Ok(Ic00Method::DeleteCanister)
             | Ok(Ic00Method::RawRand)
             | Ok(Ic00Method::DepositCycles)
@@ -246,7 +250,12 @@ impl SystemStateChanges {
             | Ok(Ic00Method::UploadChunk)
             | Ok(Ic00Method::StoredChunks)
             | Ok(Ic00Method::DeleteChunks)
-            | Ok(Ic00Method::ClearChunkStore) => Ok(None),
+            | Ok(Ic00Method::ClearChunkStore)
+            | Ok(Ic00Method::ReadCanisterMigrationData)
+            | Ok(Ic00Method::WriteCanisterMigrationData)
+            | Ok(Ic00Method::RemoveMigratedCanister)
+            | Ok(Ic00Method::FinishCanisterMigration)
+            | Ok(Ic00Method::AbortCanisterMigration) => Ok(None),
             Err(_) => Err(UserError::new(
                 ErrorCode::CanisterMethodNotFound,
                 format!("Management canister has no method '{}'", msg.method_name),
@@ -1156,7 +1156,7 @@ fn test_canister_balance() {
             Time::from_nanos_since_unix_epoch(0),
         );
 
-    let api = get_system_api(
+    let mut api = get_system_api(
         ApiTypeBuilder::build_update_api(),
         &system_state,
         cycles_account_manager,
@@ -1184,7 +1184,7 @@ fn test_canister_cycle_balance() {
             Time::from_nanos_since_unix_epoch(0),
         );
 
-    let api = get_system_api(
+    let mut api = get_system_api(
         ApiTypeBuilder::build_update_api(),
         &system_state,
         cycles_account_manager,
@@ -1297,7 +1297,7 @@ fn certified_data_set() {
 fn data_certificate_copy() {
     let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
     let system_state = SystemStateBuilder::default().build();
-    let api = get_system_api(
+    let mut api = get_system_api(
         ApiType::replicated_query(
             mock_time(),
             vec![],
@@ -20,10 +20,10 @@ use ic_execution_environment::{
     InternalHttpQueryHandler, RoundInstructions, RoundLimits,
 };
 use ic_ic00_types::{
-    CanisterIdRecord, CanisterInstallMode, CanisterInstallModeV2, CanisterSettingsArgs,
-    CanisterSettingsArgsBuilder, CanisterStatusType, EcdsaKeyId, EmptyBlob, InstallCodeArgs,
-    InstallCodeArgsV2, Method, Payload, ProvisionalCreateCanisterWithCyclesArgs, SkipPreUpgrade,
-    UpdateSettingsArgs,
+    CanisterHistoryArgs, CanisterIdRecord, CanisterInstallMode, CanisterInstallModeV2,
+    CanisterSettingsArgs, CanisterSettingsArgsBuilder, CanisterStatusType, EcdsaKeyId, EmptyBlob,
+    InstallCodeArgs, InstallCodeArgsV2, Method, Payload, ProvisionalCreateCanisterWithCyclesArgs,
+    SkipPreUpgrade, UpdateSettingsArgs,
 };
 use ic_interfaces::execution_environment::{
     ExecutionMode, IngressHistoryWriter, QueryHandler, RegistryExecutionSettings,
@@ -55,7 +55,8 @@ use ic_types::{
         AnonymousQuery, CallbackId, CanisterCall, CanisterMessage, CanisterTask, MessageId,
         RequestOrResponse, Response, UserQuery, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES,
     },
-    CanisterId, Cycles, Height, NumInstructions, NumPages, QueryStatsEpoch, Time, UserId,
+    CanisterId, Cycles, ExecutionRound, Height, NumInstructions, NumPages, QueryStatsEpoch, Time,
+    UserId,
 };
 use ic_types_test_utils::ids::{node_test_id, subnet_test_id, user_test_id};
 use ic_universal_canister::UNIVERSAL_CANISTER_WASM;
@@ -65,6 +66,7 @@ use ic_config::embedders::MeteringType;
 use maplit::{btreemap, btreeset};
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryFrom;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::{os::unix::prelude::FileExt, str::FromStr};
 use tempfile::NamedTempFile;
@@ -560,6 +562,23 @@ impl ExecutionTest {
         self.subnet_message(Method::UpdateSettings, payload)
     }
 
+    /// Updates the query cache staleness window of the given canister.
+    pub fn canister_update_query_cache_max_staleness(
+        &mut self,
+        canister_id: CanisterId,
+        query_cache_max_staleness: std::time::Duration,
+    ) -> Result<WasmResult, UserError> {
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgsBuilder::new()
+                .with_query_cache_max_staleness(query_cache_max_staleness.as_secs())
+                .build(),
+            sender_canister_version: None,
+        }
+        .encode();
+        self.subnet_message(Method::UpdateSettings, payload)
+    }
+
     /// Sends an `install_code` message to the IC management canister.
     /// Consider using higher-level helpers like `canister_from_wat()`.
     pub fn install_code(&mut self, args: InstallCodeArgs) -> Result<WasmResult, UserError> {
@@ -579,6 +598,12 @@ impl ExecutionTest {
         message_id
     }
 
+    /// Aborts the pending `install_code` execution of the given canister.
+    pub fn abort_install_code(&mut self, canister_id: CanisterId) -> Result<WasmResult, UserError> {
+        let payload = CanisterIdRecord::from(canister_id).encode();
+        self.subnet_message(Method::AbortInstallCode, payload)
+    }
+
     /// Sends an `uninstall_code` message to the IC management canister.
     pub fn uninstall_code(&mut self, canister_id: CanisterId) -> Result<WasmResult, UserError> {
         let payload = CanisterIdRecord::from(canister_id).encode();
@@ -614,6 +639,36 @@ impl ExecutionTest {
         self.subnet_message(Method::CanisterStatus, payload)
     }
 
+    /// Sends a `canister_method_stats` message to the IC management canister.
+    pub fn canister_method_stats(
+        &mut self,
+        canister_id: CanisterId,
+    ) -> Result<WasmResult, UserError> {
+        let payload = CanisterIdRecord::from(canister_id).encode();
+        self.subnet_message(Method::CanisterMethodStats, payload)
+    }
+
+    /// Sends a `reset_canister_method_stats` message to the IC management
+    /// canister.
+    pub fn reset_canister_method_stats(
+        &mut self,
+        canister_id: CanisterId,
+    ) -> Result<WasmResult, UserError> {
+        let payload = CanisterIdRecord::from(canister_id).encode();
+        self.subnet_message(Method::ResetCanisterMethodStats, payload)
+    }
+
+    /// Sends a `canister_history` message to the IC management canister.
+    pub fn canister_history(
+        &mut self,
+        canister_id: CanisterId,
+        start: Option<u64>,
+        max_results: Option<u64>,
+    ) -> Result<WasmResult, UserError> {
+        let payload = CanisterHistoryArgs::new(canister_id, start, max_results).encode();
+        self.subnet_message(Method::CanisterHistory, payload)
+    }
+
     /// Updates the freezing threshold of the given canister.
     pub fn update_freezing_threshold(
         &mut self,
@@ -1354,6 +1409,18 @@ impl ExecutionTest {
     /// `self.xnet_messages`.
     pub fn induct_messages(&mut self) {
         let mut state = self.state.take().unwrap();
+        let mut round_limits = RoundLimits {
+            instructions: RoundInstructions::from(i64::MAX),
+            subnet_available_memory: self.subnet_available_memory,
+            compute_allocation_used: state.total_compute_allocation(),
+        };
+        self.exec_env.execute_replicated_query_calls(
+            &mut state,
+            ExecutionRound::new(0),
+            self.instruction_limit_without_dts,
+            &mut round_limits,
+            self.subnet_size(),
+        );
         let mut subnet_available_memory = self.subnet_available_memory.get_message_memory();
         let output_messages = get_output_messages(&mut state);
         let mut canisters = state.take_canister_states();
@@ -1704,6 +1771,12 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn with_extended_install_code_instruction_limit(mut self, limit: u64) -> Self {
+        self.execution_config
+            .max_instructions_per_extended_install_code = NumInstructions::from(limit);
+        self
+    }
+
     pub fn with_initial_canister_cycles(self, initial_canister_cycles: u128) -> Self {
         Self {
             initial_canister_cycles: Cycles::new(initial_canister_cycles),
@@ -1789,6 +1862,11 @@ impl ExecutionTestBuilder {
         self
     }
 
+    pub fn with_replicated_query_calls(mut self) -> Self {
+        self.execution_config.replicated_query_calls = FlagStatus::Enabled;
+        self
+    }
+
     pub fn with_query_caching(mut self) -> Self {
         self.execution_config.query_caching = FlagStatus::Enabled;
         self
@@ -1874,6 +1952,13 @@ impl ExecutionTestBuilder {
         self
     }
 
+    pub fn with_wasm_function_profile_dir(mut self, dir: PathBuf) -> Self {
+        self.execution_config
+            .embedders_config
+            .wasm_function_profile_dir = Some(dir);
+        self
+    }
+
     pub fn with_wasm_chunk_store(mut self) -> Self {
         self.execution_config.wasm_chunk_store = FlagStatus::Enabled;
         self
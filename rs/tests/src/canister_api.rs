@@ -731,6 +731,7 @@ impl ListNnsNeuronsRequest {
             payload: ListNnsNeuronsReq {
                 neuron_ids,
                 include_neurons_readable_by_caller,
+                include_public_neurons_in_full_neurons: None,
             },
         }
     }
@@ -996,6 +996,8 @@ pub fn create_canister_on_specific_subnet_type(env: TestEnv) {
                 None,
                 Some(SubnetSelection::Filter(SubnetFilter {
                     subnet_type: Some(type1),
+                    geography: None,
+                    min_subnet_size: None,
                 })),
             )
             .await
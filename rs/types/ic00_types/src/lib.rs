@@ -57,6 +57,10 @@ pub enum Method {
     ECDSAPublicKey,
     InstallCode,
     InstallChunkedCode,
+    AbortInstallCode,
+    CanisterMethodStats,
+    ResetCanisterMethodStats,
+    CanisterHistory,
     RawRand,
     SetupInitialDKG,
     SignWithECDSA,
@@ -87,6 +91,14 @@ pub enum Method {
     StoredChunks,
     DeleteChunks,
     ClearChunkStore,
+
+    // Private APIs used exclusively by the registry canister to migrate a
+    // canister to another subnet.
+    ReadCanisterMigrationData,
+    WriteCanisterMigrationData,
+    RemoveMigratedCanister,
+    FinishCanisterMigration,
+    AbortCanisterMigration,
 }
 
 fn candid_error_to_user_error(err: candid::Error) -> UserError {
@@ -252,6 +264,98 @@ impl CanisterControllersChangeRecord {
     }
 }
 
+/// `CandidType` for `CanisterAutoTopUpRecord`
+/// ```text
+/// record {
+///   funding_canister : principal;
+///   amount : nat;
+/// }
+/// ```
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanisterAutoTopUpRecord {
+    funding_canister: PrincipalId,
+    amount: u128,
+}
+
+impl CanisterAutoTopUpRecord {
+    pub fn funding_canister(&self) -> PrincipalId {
+        self.funding_canister
+    }
+
+    pub fn amount(&self) -> u128 {
+        self.amount
+    }
+}
+
+/// `CandidType` for `CanisterSettingsChangeRecord`
+/// ```text
+/// record {
+///   freezing_threshold : opt nat64;
+///   compute_allocation : opt nat64;
+///   memory_allocation : opt nat64;
+/// }
+/// ```
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanisterSettingsChangeRecord {
+    freezing_threshold: Option<u64>,
+    compute_allocation: Option<u64>,
+    memory_allocation: Option<u64>,
+}
+
+impl CanisterSettingsChangeRecord {
+    pub fn freezing_threshold(&self) -> Option<u64> {
+        self.freezing_threshold
+    }
+
+    pub fn compute_allocation(&self) -> Option<u64> {
+        self.compute_allocation
+    }
+
+    pub fn memory_allocation(&self) -> Option<u64> {
+        self.memory_allocation
+    }
+}
+
+/// `CandidType` for `CanisterCyclesDepositRecord`
+/// ```text
+/// record {
+///   amount : nat;
+/// }
+/// ```
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanisterCyclesDepositRecord {
+    amount: u128,
+}
+
+impl CanisterCyclesDepositRecord {
+    pub fn amount(&self) -> u128 {
+        self.amount
+    }
+}
+
+/// `CandidType` for `CanisterCyclesWithdrawalRecord`
+/// ```text
+/// record {
+///   receiver : principal;
+///   amount : nat;
+/// }
+/// ```
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanisterCyclesWithdrawalRecord {
+    receiver: PrincipalId,
+    amount: u128,
+}
+
+impl CanisterCyclesWithdrawalRecord {
+    pub fn receiver(&self) -> PrincipalId {
+        self.receiver
+    }
+
+    pub fn amount(&self) -> u128 {
+        self.amount
+    }
+}
+
 /// `CandidType` for `CanisterChangeDetails`
 /// ```text
 /// variant {
@@ -266,6 +370,22 @@ impl CanisterControllersChangeRecord {
 ///   controllers_change : record {
 ///     controllers : vec principal;
 ///   };
+///   auto_top_up : record {
+///     funding_canister : principal;
+///     amount : nat;
+///   };
+///   settings_change : record {
+///     freezing_threshold : opt nat64;
+///     compute_allocation : opt nat64;
+///     memory_allocation : opt nat64;
+///   };
+///   cycles_deposit : record {
+///     amount : nat;
+///   };
+///   cycles_withdrawal : record {
+///     receiver : principal;
+///     amount : nat;
+///   };
 /// }
 /// ```
 #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -278,6 +398,14 @@ pub enum CanisterChangeDetails {
     CanisterCodeDeployment(CanisterCodeDeploymentRecord),
     #[serde(rename = "controllers_change")]
     CanisterControllersChange(CanisterControllersChangeRecord),
+    #[serde(rename = "auto_top_up")]
+    CanisterAutoTopUp(CanisterAutoTopUpRecord),
+    #[serde(rename = "settings_change")]
+    CanisterSettingsChange(CanisterSettingsChangeRecord),
+    #[serde(rename = "cycles_deposit")]
+    CanisterCyclesDeposit(CanisterCyclesDepositRecord),
+    #[serde(rename = "cycles_withdrawal")]
+    CanisterCyclesWithdrawal(CanisterCyclesWithdrawalRecord),
 }
 
 impl CanisterChangeDetails {
@@ -300,9 +428,55 @@ impl CanisterChangeDetails {
             controllers,
         })
     }
+
+    pub fn auto_top_up(funding_canister: PrincipalId, amount: u128) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterAutoTopUp(CanisterAutoTopUpRecord {
+            funding_canister,
+            amount,
+        })
+    }
+
+    pub fn settings_change(
+        freezing_threshold: Option<u64>,
+        compute_allocation: Option<u64>,
+        memory_allocation: Option<u64>,
+    ) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterSettingsChange(CanisterSettingsChangeRecord {
+            freezing_threshold,
+            compute_allocation,
+            memory_allocation,
+        })
+    }
+
+    pub fn cycles_deposit(amount: u128) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterCyclesDeposit(CanisterCyclesDepositRecord { amount })
+    }
+
+    pub fn cycles_withdrawal(receiver: PrincipalId, amount: u128) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterCyclesWithdrawal(CanisterCyclesWithdrawalRecord {
+            receiver,
+            amount,
+        })
+    }
+
+    /// Returns whether the change is only returned by `canister_history` and
+    /// not by `canister_info`.
+    pub fn is_audit_change(&self) -> bool {
+        match self {
+            CanisterChangeDetails::CanisterCreation(_)
+            | CanisterChangeDetails::CanisterCodeUninstall
+            | CanisterChangeDetails::CanisterCodeDeployment(_)
+            | CanisterChangeDetails::CanisterControllersChange(_)
+            | CanisterChangeDetails::CanisterAutoTopUp(_) => false,
+            CanisterChangeDetails::CanisterSettingsChange(_)
+            | CanisterChangeDetails::CanisterCyclesDeposit(_)
+            | CanisterChangeDetails::CanisterCyclesWithdrawal(_) => true,
+        }
+    }
 }
 
-/// Every canister change (canister creation, code uninstallation, code deployment, or controllers change) consists of
+/// Every canister change (canister creation, code uninstallation, code deployment, controllers change, or automatic
+/// top-up) consists of
 ///
 /// 1. the system timestamp (in nanoseconds since Unix Epoch) at which the change was performed,
 /// 2. the canister version after performing the change,
@@ -318,6 +492,13 @@ impl CanisterChangeDetails {
 ///
 /// Controllers changes are described by the full new set of the canister controllers after the change.
 ///
+/// Automatic top-ups are described by the funding canister and the number of transferred cycles.
+///
+/// The following changes are only returned by `canister_history`:
+/// settings changes are described by the new values of the changed settings,
+/// cycles deposits by the number of deposited cycles, and cycles withdrawals
+/// by the receiver and the number of withdrawn cycles.
+///
 /// `CandidType` for `CanisterChange`
 /// ```text
 /// record {
@@ -350,6 +531,14 @@ impl CanisterChange {
         }
     }
 
+    pub fn timestamp_nanos(&self) -> u64 {
+        self.timestamp_nanos
+    }
+
+    pub fn details(&self) -> &CanisterChangeDetails {
+        &self.details
+    }
+
     /// Returns the number of bytes to represent a canister change in memory.
     /// The vector of controllers in `CanisterCreation` and `CanisterControllersChange`
     /// is counted separately because the controllers are stored on heap
@@ -363,7 +552,11 @@ impl CanisterChange {
                 std::mem::size_of_val(canister_controllers_change.controllers())
             }
             CanisterChangeDetails::CanisterCodeDeployment(_)
-            | CanisterChangeDetails::CanisterCodeUninstall => 0,
+            | CanisterChangeDetails::CanisterCodeUninstall
+            | CanisterChangeDetails::CanisterAutoTopUp(_)
+            | CanisterChangeDetails::CanisterSettingsChange(_)
+            | CanisterChangeDetails::CanisterCyclesDeposit(_)
+            | CanisterChangeDetails::CanisterCyclesWithdrawal(_) => 0,
         };
         NumBytes::from((size_of::<CanisterChange>() + controllers_memory_size) as u64)
     }
@@ -452,6 +645,138 @@ impl CanisterInfoResponse {
 
 impl Payload<'_> for CanisterInfoResponse {}
 
+/// `CandidType` for `CanisterHistoryArgs`
+/// ```text
+/// record {
+///   canister_id : principal;
+///   start : opt nat64;
+///   max_results : opt nat64;
+/// }
+/// ```
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanisterHistoryArgs {
+    canister_id: PrincipalId,
+    start: Option<u64>,
+    max_results: Option<u64>,
+}
+
+impl CanisterHistoryArgs {
+    pub fn new(canister_id: CanisterId, start: Option<u64>, max_results: Option<u64>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            start,
+            max_results,
+        }
+    }
+
+    pub fn canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    /// The sequence number from which on the retained changes are returned.
+    /// Sequence numbers do not change when older changes are dropped.
+    pub fn start(&self) -> Option<u64> {
+        self.start
+    }
+
+    pub fn max_results(&self) -> Option<u64> {
+        self.max_results
+    }
+}
+
+impl Payload<'_> for CanisterHistoryArgs {}
+
+/// `CandidType` for `CanisterHistoryEntry`
+/// ```text
+/// record {
+///   sequence_number : nat64;
+///   change : change;
+/// }
+/// ```
+///
+/// The sequence number of a change is the number of changes of all kinds
+/// recorded before it. A gap between the sequence numbers of consecutive
+/// entries means that the changes in between are no longer retained.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanisterHistoryEntry {
+    sequence_number: u64,
+    change: CanisterChange,
+}
+
+impl CanisterHistoryEntry {
+    pub fn new(sequence_number: u64, change: CanisterChange) -> Self {
+        Self {
+            sequence_number,
+            change,
+        }
+    }
+
+    pub fn sequence_number(&self) -> u64 {
+        self.sequence_number
+    }
+
+    pub fn change(&self) -> &CanisterChange {
+        &self.change
+    }
+}
+
+/// `CandidType` for `CanisterHistoryResponse`
+/// ```text
+/// record {
+///   total_num_changes : nat64;
+///   num_retained_changes : nat64;
+///   changes : vec canister_history_entry;
+///   next_start : opt nat64;
+/// }
+/// ```
+///
+/// Snapshot operations are not part of the history, as canister snapshots
+/// are not supported.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanisterHistoryResponse {
+    total_num_changes: u64,
+    num_retained_changes: u64,
+    changes: Vec<CanisterHistoryEntry>,
+    next_start: Option<u64>,
+}
+
+impl CanisterHistoryResponse {
+    pub fn new(
+        total_num_changes: u64,
+        num_retained_changes: u64,
+        changes: Vec<CanisterHistoryEntry>,
+        next_start: Option<u64>,
+    ) -> Self {
+        Self {
+            total_num_changes,
+            num_retained_changes,
+            changes,
+            next_start,
+        }
+    }
+
+    /// The number of changes of all kinds ever recorded, including the ones
+    /// that are no longer retained.
+    pub fn total_num_changes(&self) -> u64 {
+        self.total_num_changes
+    }
+
+    pub fn num_retained_changes(&self) -> u64 {
+        self.num_retained_changes
+    }
+
+    pub fn changes(&self) -> Vec<CanisterHistoryEntry> {
+        self.changes.clone()
+    }
+
+    /// The `start` of the next page or `None` if this is the last page.
+    pub fn next_start(&self) -> Option<u64> {
+        self.next_start
+    }
+}
+
+impl Payload<'_> for CanisterHistoryResponse {}
+
 impl From<&CanisterChangeOrigin> for pb_canister_state_bits::canister_change::ChangeOrigin {
     fn from(item: &CanisterChangeOrigin) -> Self {
         match item {
@@ -535,6 +860,44 @@ impl From<&CanisterChangeDetails> for pb_canister_state_bits::canister_change::C
                     },
                 )
             }
+            CanisterChangeDetails::CanisterAutoTopUp(canister_auto_top_up) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterAutoTopUp(
+                    pb_canister_state_bits::CanisterAutoTopUp {
+                        funding_canister: Some(canister_auto_top_up.funding_canister.into()),
+                        amount: Some(pb_canister_state_bits::Unsigned128 {
+                            raw: canister_auto_top_up.amount.to_le_bytes().to_vec(),
+                        }),
+                    },
+                )
+            }
+            CanisterChangeDetails::CanisterSettingsChange(canister_settings_change) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterSettingsChange(
+                    pb_canister_state_bits::CanisterSettingsChange {
+                        freezing_threshold: canister_settings_change.freezing_threshold,
+                        compute_allocation: canister_settings_change.compute_allocation,
+                        memory_allocation: canister_settings_change.memory_allocation,
+                    },
+                )
+            }
+            CanisterChangeDetails::CanisterCyclesDeposit(canister_cycles_deposit) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterCyclesDeposit(
+                    pb_canister_state_bits::CanisterCyclesDeposit {
+                        amount: Some(pb_canister_state_bits::Unsigned128 {
+                            raw: canister_cycles_deposit.amount.to_le_bytes().to_vec(),
+                        }),
+                    },
+                )
+            }
+            CanisterChangeDetails::CanisterCyclesWithdrawal(canister_cycles_withdrawal) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterCyclesWithdrawal(
+                    pb_canister_state_bits::CanisterCyclesWithdrawal {
+                        receiver: Some(canister_cycles_withdrawal.receiver.into()),
+                        amount: Some(pb_canister_state_bits::Unsigned128 {
+                            raw: canister_cycles_withdrawal.amount.to_le_bytes().to_vec(),
+                        }),
+                    },
+                )
+            }
         }
     }
 }
@@ -578,10 +941,57 @@ impl TryFrom<pb_canister_state_bits::canister_change::ChangeDetails> for Caniste
                     .map(TryInto::try_into)
                     .collect::<Result<Vec<PrincipalId>, _>>()?,
             )),
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterAutoTopUp(
+                canister_auto_top_up,
+            ) => Ok(CanisterChangeDetails::auto_top_up(
+                try_from_option_field(canister_auto_top_up.funding_canister, "funding_canister")?,
+                try_decode_cycles_amount(canister_auto_top_up.amount, "CanisterAutoTopUp::amount")?,
+            )),
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterSettingsChange(
+                canister_settings_change,
+            ) => Ok(CanisterChangeDetails::settings_change(
+                canister_settings_change.freezing_threshold,
+                canister_settings_change.compute_allocation,
+                canister_settings_change.memory_allocation,
+            )),
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterCyclesDeposit(
+                canister_cycles_deposit,
+            ) => Ok(CanisterChangeDetails::cycles_deposit(
+                try_decode_cycles_amount(
+                    canister_cycles_deposit.amount,
+                    "CanisterCyclesDeposit::amount",
+                )?,
+            )),
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterCyclesWithdrawal(
+                canister_cycles_withdrawal,
+            ) => Ok(CanisterChangeDetails::cycles_withdrawal(
+                try_from_option_field(canister_cycles_withdrawal.receiver, "receiver")?,
+                try_decode_cycles_amount(
+                    canister_cycles_withdrawal.amount,
+                    "CanisterCyclesWithdrawal::amount",
+                )?,
+            )),
         }
     }
 }
 
+/// Decodes a number of cycles stored as a little-endian `Unsigned128`.
+fn try_decode_cycles_amount(
+    amount: Option<pb_canister_state_bits::Unsigned128>,
+    typ: &'static str,
+) -> Result<u128, ProxyDecodeError> {
+    let amount: pb_canister_state_bits::Unsigned128 = try_from_option_field(amount, "amount")?;
+    let amount: [u8; 16] =
+        amount
+            .raw
+            .try_into()
+            .map_err(|raw: Vec<u8>| ProxyDecodeError::ValueOutOfRange {
+                typ,
+                err: format!("expected 16 bytes, got {}", raw.len()),
+            })?;
+    Ok(u128::from_le_bytes(amount))
+}
+
 impl From<&CanisterChange> for pb_canister_state_bits::CanisterChange {
     fn from(item: &CanisterChange) -> Self {
         Self {
@@ -645,6 +1055,8 @@ impl Payload<'_> for UninstallCodeArgs {}
 ///     memory_allocation: nat;
 ///     freezing_threshold: nat;
 ///     reserved_cycles_limit: nat;
+///     auto_top_up: opt auto_top_up_args;
+///     extended_install_code_limit: bool;
 /// })`
 #[derive(CandidType, Clone, Deserialize, Debug, Eq, PartialEq)]
 pub struct DefiniteCanisterSettingsArgs {
@@ -654,6 +1066,8 @@ pub struct DefiniteCanisterSettingsArgs {
     memory_allocation: candid::Nat,
     freezing_threshold: candid::Nat,
     reserved_cycles_limit: candid::Nat,
+    auto_top_up: Option<AutoTopUpArgs>,
+    extended_install_code_limit: bool,
 }
 
 impl DefiniteCanisterSettingsArgs {
@@ -674,6 +1088,8 @@ impl DefiniteCanisterSettingsArgs {
             memory_allocation,
             freezing_threshold: candid::Nat::from(freezing_threshold),
             reserved_cycles_limit,
+            auto_top_up: None,
+            extended_install_code_limit: false,
         }
     }
 
@@ -684,6 +1100,14 @@ impl DefiniteCanisterSettingsArgs {
     pub fn reserved_cycles_limit(&self) -> candid::Nat {
         self.reserved_cycles_limit.clone()
     }
+
+    pub fn auto_top_up(&self) -> Option<AutoTopUpArgs> {
+        self.auto_top_up.clone()
+    }
+
+    pub fn extended_install_code_limit(&self) -> bool {
+        self.extended_install_code_limit
+    }
 }
 
 impl Payload<'_> for DefiniteCanisterSettingsArgs {}
@@ -768,7 +1192,9 @@ pub struct QueryStats {
 ///         num_instructions: nat;
 ///         ingress_payload_size: nat;
 ///         egress_payload_size: nat;
-///     }
+///     };
+///     install_code_progress: opt install_code_progress;
+///     queue_latency: opt queue_latency;
 /// })`
 #[derive(CandidType, Debug, Deserialize, Eq, PartialEq)]
 pub struct CanisterStatusResultV2 {
@@ -784,6 +1210,80 @@ pub struct CanisterStatusResultV2 {
     idle_cycles_burned_per_day: candid::Nat,
     reserved_cycles: candid::Nat,
     query_stats: QueryStats,
+    install_code_progress: Option<InstallCodeProgressRecord>,
+    queue_latency: Option<QueueLatencyRecord>,
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     start_time_nanos: nat64;
+///     executed_instructions: nat;
+///     num_restarts: nat64;
+/// })`
+///
+/// Describes an `install_code` execution of the canister that is sliced
+/// across rounds and has not finished yet.
+#[derive(CandidType, Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct InstallCodeProgressRecord {
+    start_time_nanos: u64,
+    executed_instructions: candid::Nat,
+    num_restarts: u64,
+}
+
+impl InstallCodeProgressRecord {
+    pub fn start_time_nanos(&self) -> u64 {
+        self.start_time_nanos
+    }
+
+    pub fn executed_instructions(&self) -> u64 {
+        self.executed_instructions.0.to_u64().unwrap()
+    }
+
+    pub fn num_restarts(&self) -> u64 {
+        self.num_restarts
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     average_wait_nanos: nat64;
+///     max_wait_nanos: nat64;
+///     rounds_with_input: nat64;
+///     rounds_executed: nat64;
+///     scheduling_share_percent: nat64;
+/// })`
+///
+/// Describes how long the input messages of the canister wait for execution
+/// and in how many of the rounds with pending input the canister got executed.
+#[derive(CandidType, Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct QueueLatencyRecord {
+    average_wait_nanos: u64,
+    max_wait_nanos: u64,
+    rounds_with_input: u64,
+    rounds_executed: u64,
+    scheduling_share_percent: u64,
+}
+
+impl QueueLatencyRecord {
+    pub fn average_wait_nanos(&self) -> u64 {
+        self.average_wait_nanos
+    }
+
+    pub fn max_wait_nanos(&self) -> u64 {
+        self.max_wait_nanos
+    }
+
+    pub fn rounds_with_input(&self) -> u64 {
+        self.rounds_with_input
+    }
+
+    pub fn rounds_executed(&self) -> u64 {
+        self.rounds_executed
+    }
+
+    pub fn scheduling_share_percent(&self) -> u64 {
+        self.scheduling_share_percent
+    }
 }
 
 impl CanisterStatusResultV2 {
@@ -832,6 +1332,8 @@ impl CanisterStatusResultV2 {
                 request_payload_bytes_total: candid::Nat::from(query_ingress_payload_size),
                 response_payload_bytes_total: candid::Nat::from(query_egress_payload_size),
             },
+            install_code_progress: None,
+            queue_latency: None,
         }
     }
 
@@ -882,6 +1384,87 @@ impl CanisterStatusResultV2 {
     pub fn settings(&self) -> DefiniteCanisterSettingsArgs {
         self.settings.clone()
     }
+
+    /// Sets the automatic top-up settings reported in `settings`.
+    pub fn with_auto_top_up(
+        mut self,
+        funding_canister: PrincipalId,
+        threshold: u128,
+        amount: u128,
+    ) -> Self {
+        self.settings.auto_top_up = Some(AutoTopUpArgs {
+            funding_canister,
+            threshold: candid::Nat::from(threshold),
+            amount: candid::Nat::from(amount),
+            funding_account: None,
+        });
+        self
+    }
+
+    /// Sets the cycles ledger account of the automatic top-up settings
+    /// reported in `settings`. Has no effect without `with_auto_top_up()`.
+    pub fn with_auto_top_up_funding_account(
+        mut self,
+        owner: PrincipalId,
+        subaccount: Option<[u8; 32]>,
+    ) -> Self {
+        if let Some(auto_top_up) = self.settings.auto_top_up.as_mut() {
+            auto_top_up.funding_account = Some(CyclesLedgerAccountArgs {
+                owner,
+                subaccount: subaccount.map(|subaccount| subaccount.to_vec()),
+            });
+        }
+        self
+    }
+
+    /// Sets whether the extended `install_code` limit is reported as enabled
+    /// in `settings`.
+    pub fn with_extended_install_code_limit(mut self, extended_install_code_limit: bool) -> Self {
+        self.settings.extended_install_code_limit = extended_install_code_limit;
+        self
+    }
+
+    /// Sets the progress of the pending `install_code` execution.
+    pub fn with_install_code_progress(
+        mut self,
+        start_time_nanos: u64,
+        executed_instructions: u64,
+        num_restarts: u64,
+    ) -> Self {
+        self.install_code_progress = Some(InstallCodeProgressRecord {
+            start_time_nanos,
+            executed_instructions: candid::Nat::from(executed_instructions),
+            num_restarts,
+        });
+        self
+    }
+
+    pub fn install_code_progress(&self) -> Option<InstallCodeProgressRecord> {
+        self.install_code_progress.clone()
+    }
+
+    /// Sets the queue latency statistics of the canister.
+    pub fn with_queue_latency(
+        mut self,
+        average_wait_nanos: u64,
+        max_wait_nanos: u64,
+        rounds_with_input: u64,
+        rounds_executed: u64,
+        scheduling_share_percent: u64,
+    ) -> Self {
+        self.queue_latency = Some(QueueLatencyRecord {
+            average_wait_nanos,
+            max_wait_nanos,
+            rounds_with_input,
+            rounds_executed,
+            scheduling_share_percent,
+        });
+        self
+    }
+
+    pub fn queue_latency(&self) -> Option<QueueLatencyRecord> {
+        self.queue_latency.clone()
+    }
 }
 
 /// Indicates whether the canister is running, stopping, or stopped.
@@ -1428,6 +2011,10 @@ fn verify_max_bounded_controllers_length() {
 ///     memory_allocation: opt nat;
 ///     freezing_threshold: opt nat;
 ///     reserved_cycles_limit: opt nat;
+///     query_cache_max_staleness: opt nat;
+///     auto_top_up: opt auto_top_up_args;
+///     extended_install_code_limit: opt bool;
+///     auto_top_up_beneficiaries: opt vec principal;
 /// })`
 #[derive(Default, Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
 pub struct CanisterSettingsArgs {
@@ -1438,10 +2025,69 @@ pub struct CanisterSettingsArgs {
     pub memory_allocation: Option<candid::Nat>,
     pub freezing_threshold: Option<candid::Nat>,
     pub reserved_cycles_limit: Option<candid::Nat>,
+    /// The maximum age in seconds of a query cache entry that may be served
+    /// even though the time or the canister balance have changed since.
+    pub query_cache_max_staleness: Option<candid::Nat>,
+    /// Automatically top up the canister from a funding canister on the
+    /// same subnet once its balance drops below a threshold.
+    pub auto_top_up: Option<AutoTopUpArgs>,
+    /// Run `install_code` messages of the canister with the much larger
+    /// extended instruction limit. The execution is sliced across rounds. It
+    /// starts in the first round after a checkpoint and is rejected if it does
+    /// not finish before the next checkpoint.
+    pub extended_install_code_limit: Option<bool>,
+    /// The canisters that may automatically top themselves up with cycles
+    /// of this canister. Replaces the previous list.
+    pub auto_top_up_beneficiaries: Option<Vec<PrincipalId>>,
 }
 
 impl Payload<'_> for CanisterSettingsArgs {}
 
+/// Struct used for encoding/decoding
+/// `(record {
+///     funding_canister: principal;
+///     threshold: nat;
+///     amount: nat;
+///     funding_account: opt cycles_ledger_account;
+/// })`
+///
+/// Setting `amount` to zero disables automatic top-ups.
+///
+/// If `funding_account` is set, `funding_canister` is the cycles ledger and
+/// the cycles are withdrawn from the given account on the ledger instead.
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct AutoTopUpArgs {
+    pub funding_canister: PrincipalId,
+    pub threshold: candid::Nat,
+    pub amount: candid::Nat,
+    pub funding_account: Option<CyclesLedgerAccountArgs>,
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     owner: principal;
+///     subaccount: opt blob;
+/// })`
+///
+/// The owner of the account must approve the subnet of the topped up
+/// canister as spender, with the topped up canister as spender subaccount
+/// (see `auto_top_up_spender_subaccount()`).
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct CyclesLedgerAccountArgs {
+    pub owner: PrincipalId,
+    pub subaccount: Option<Vec<u8>>,
+}
+
+/// Returns the subaccount of the subnet that an account owner must approve
+/// on the cycles ledger to fund automatic top-ups of `canister_id`.
+pub fn auto_top_up_spender_subaccount(canister_id: &CanisterId) -> [u8; 32] {
+    let bytes = canister_id.get_ref().as_slice();
+    let mut subaccount = [0; 32];
+    subaccount[0] = bytes.len() as u8;
+    subaccount[1..1 + bytes.len()].copy_from_slice(bytes);
+    subaccount
+}
+
 impl CanisterSettingsArgs {
     pub fn new(
         controllers: Option<Vec<PrincipalId>>,
@@ -1457,6 +2103,10 @@ impl CanisterSettingsArgs {
             memory_allocation: memory_allocation.map(candid::Nat::from),
             freezing_threshold: freezing_threshold.map(candid::Nat::from),
             reserved_cycles_limit: reserved_cycles_limit.map(candid::Nat::from),
+            query_cache_max_staleness: None,
+            auto_top_up: None,
+            extended_install_code_limit: None,
+            auto_top_up_beneficiaries: None,
         }
     }
 
@@ -1473,6 +2123,10 @@ pub struct CanisterSettingsArgsBuilder {
     memory_allocation: Option<candid::Nat>,
     freezing_threshold: Option<candid::Nat>,
     reserved_cycles_limit: Option<candid::Nat>,
+    query_cache_max_staleness: Option<candid::Nat>,
+    auto_top_up: Option<AutoTopUpArgs>,
+    extended_install_code_limit: Option<bool>,
+    auto_top_up_beneficiaries: Option<Vec<PrincipalId>>,
 }
 
 #[allow(dead_code)]
@@ -1489,6 +2143,10 @@ impl CanisterSettingsArgsBuilder {
             memory_allocation: self.memory_allocation,
             freezing_threshold: self.freezing_threshold,
             reserved_cycles_limit: self.reserved_cycles_limit,
+            query_cache_max_staleness: self.query_cache_max_staleness,
+            auto_top_up: self.auto_top_up,
+            extended_install_code_limit: self.extended_install_code_limit,
+            auto_top_up_beneficiaries: self.auto_top_up_beneficiaries,
         }
     }
 
@@ -1556,6 +2214,75 @@ impl CanisterSettingsArgsBuilder {
             ..self
         }
     }
+
+    /// Sets the query cache max staleness in seconds.
+    pub fn with_query_cache_max_staleness(self, query_cache_max_staleness: u64) -> Self {
+        Self {
+            query_cache_max_staleness: Some(candid::Nat::from(query_cache_max_staleness)),
+            ..self
+        }
+    }
+
+    /// Sets the funding canister and the threshold and amount in cycles
+    /// for automatic top-ups. An amount of zero disables them.
+    pub fn with_auto_top_up(
+        self,
+        funding_canister: PrincipalId,
+        threshold: u128,
+        amount: u128,
+    ) -> Self {
+        Self {
+            auto_top_up: Some(AutoTopUpArgs {
+                funding_canister,
+                threshold: candid::Nat::from(threshold),
+                amount: candid::Nat::from(amount),
+                funding_account: None,
+            }),
+            ..self
+        }
+    }
+
+    /// Sets the account on the given cycles ledger and the threshold and
+    /// amount in cycles for automatic top-ups.
+    pub fn with_auto_top_up_from_account(
+        self,
+        cycles_ledger: PrincipalId,
+        owner: PrincipalId,
+        subaccount: Option<[u8; 32]>,
+        threshold: u128,
+        amount: u128,
+    ) -> Self {
+        Self {
+            auto_top_up: Some(AutoTopUpArgs {
+                funding_canister: cycles_ledger,
+                threshold: candid::Nat::from(threshold),
+                amount: candid::Nat::from(amount),
+                funding_account: Some(CyclesLedgerAccountArgs {
+                    owner,
+                    subaccount: subaccount.map(|subaccount| subaccount.to_vec()),
+                }),
+            }),
+            ..self
+        }
+    }
+
+    /// Sets the canisters that may automatically top themselves up with
+    /// cycles of this canister.
+    pub fn with_auto_top_up_beneficiaries(self, beneficiaries: Vec<PrincipalId>) -> Self {
+        Self {
+            auto_top_up_beneficiaries: Some(beneficiaries),
+            ..self
+        }
+    }
+
+    /// Sets whether `install_code` messages of the canister run with the
+    /// extended instruction limit.
+    pub fn with_extended_install_code_limit(self, extended_install_code_limit: bool) -> Self {
+        Self {
+            extended_install_code_limit: Some(extended_install_code_limit),
+            ..self
+        }
+    }
 }
 
 /// Struct used for encoding/decoding
@@ -2366,3 +3093,316 @@ impl StoredChunksArgs {
 pub struct StoredChunksReply(pub Vec<serde_bytes::ByteBuf>);
 
 impl Payload<'_> for StoredChunksReply {}
+
+/// Execution statistics of a canister method aggregated over its calls.
+/// The executions of callbacks are attributed to the method that made the
+/// outgoing call.
+/// `(record {
+///     method_name: text;
+///     num_calls: nat64;
+///     num_traps: nat64;
+///     instructions_executed: nat;
+///     dirty_pages: nat64;
+///     num_responses: nat64;
+///     average_latency_nanos: nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct MethodStatsRecord {
+    pub method_name: String,
+    pub num_calls: u64,
+    pub num_traps: u64,
+    pub instructions_executed: candid::Nat,
+    pub dirty_pages: u64,
+    pub num_responses: u64,
+    pub average_latency_nanos: u64,
+}
+
+/// Struct to be returned by `canister_method_stats`.
+/// `(record {
+///     method_stats: vec method_stats_record;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterMethodStatsReply {
+    pub method_stats: Vec<MethodStatsRecord>,
+}
+
+impl Payload<'_> for CanisterMethodStatsReply {}
+
+/// Selects the part of a canister's state that is read from the source subnet
+/// of a canister migration.
+/// `(variant {
+///     metadata;
+///     wasm_module: record { offset: nat64 };
+///     wasm_memory: record { start_page: nat64 };
+///     stable_memory: record { start_page: nat64 };
+///     wasm_chunk_store: record { index: nat64 };
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub enum CanisterMigrationSegment {
+    #[serde(rename = "metadata")]
+    Metadata,
+    #[serde(rename = "wasm_module")]
+    WasmModule { offset: u64 },
+    #[serde(rename = "wasm_memory")]
+    WasmMemory { start_page: u64 },
+    #[serde(rename = "stable_memory")]
+    StableMemory { start_page: u64 },
+    #[serde(rename = "wasm_chunk_store")]
+    WasmChunkStore { index: u64 },
+}
+
+/// The value of an exported global of a migrated canister.
+/// `(variant {
+///     i32: int32;
+///     i64: int64;
+///     f32: float32;
+///     f64: float64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq)]
+pub enum MigratedGlobal {
+    #[serde(rename = "i32")]
+    I32(i32),
+    #[serde(rename = "i64")]
+    I64(i64),
+    #[serde(rename = "f32")]
+    F32(f32),
+    #[serde(rename = "f64")]
+    F64(f64),
+}
+
+/// Describes the code and the memories of a migrated canister.
+/// `(record {
+///     wasm_module_hash: blob;
+///     wasm_module_size: nat64;
+///     chunk_hashes_list: vec blob;
+///     exported_globals: vec migrated_global;
+///     wasm_memory_size: nat64;
+///     stable_memory_size: nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq)]
+pub struct MigratedCode {
+    #[serde(with = "serde_bytes")]
+    pub wasm_module_hash: Vec<u8>,
+    pub wasm_module_size: u64,
+    /// The hashes of the chunks of the Wasm module in the order in which
+    /// they have to be concatenated.
+    pub chunk_hashes_list: Vec<serde_bytes::ByteBuf>,
+    pub exported_globals: Vec<MigratedGlobal>,
+    /// The size of the Wasm memory in Wasm pages.
+    pub wasm_memory_size: u64,
+    /// The size of the stable memory in Wasm pages.
+    pub stable_memory_size: u64,
+}
+
+/// The state of a migrated canister that is not stored in its Wasm module or
+/// memories.
+/// `(record {
+///     settings: canister_settings;
+///     certified_data: blob;
+///     canister_version: nat64;
+///     global_timer_nanos: opt nat64;
+///     recent_changes: vec change;
+///     total_num_changes: nat64;
+///     code: opt migrated_code;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq)]
+pub struct CanisterMigrationMetadata {
+    pub settings: CanisterSettingsArgs,
+    #[serde(with = "serde_bytes")]
+    pub certified_data: Vec<u8>,
+    pub canister_version: u64,
+    pub global_timer_nanos: Option<u64>,
+    pub recent_changes: Vec<CanisterChange>,
+    pub total_num_changes: u64,
+    pub code: Option<MigratedCode>,
+}
+
+/// A chunk of the Wasm module of a migrated canister.
+/// `(record {
+///     offset: nat64;
+///     chunk: blob;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct MigratedWasmChunk {
+    pub offset: u64,
+    #[serde(with = "serde_bytes")]
+    pub chunk: Vec<u8>,
+}
+
+/// A non-zero page of a memory of a migrated canister.
+/// `(record {
+///     index: nat64;
+///     contents: blob;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct MigratedPage {
+    pub index: u64,
+    #[serde(with = "serde_bytes")]
+    pub contents: Vec<u8>,
+}
+
+/// A chunk in the Wasm chunk store of a migrated canister.
+/// `(record {
+///     index: nat64;
+///     chunk: blob;
+///     next_index: opt nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct MigratedStoredChunk {
+    pub index: u64,
+    /// The contents of the chunk, empty if the chunk store is empty.
+    #[serde(with = "serde_bytes")]
+    pub chunk: Vec<u8>,
+    /// The chunk to continue reading from or `None` if all chunks were read.
+    pub next_index: Option<u64>,
+}
+
+/// A batch of pages of a memory of a migrated canister.
+/// `(record {
+///     pages: vec migrated_page;
+///     next_page: opt nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct MigratedPages {
+    pub pages: Vec<MigratedPage>,
+    /// The page to continue reading from or `None` if all pages were read.
+    pub next_page: Option<u64>,
+}
+
+/// A part of the state of a migrated canister.
+/// `(variant {
+///     metadata: canister_migration_metadata;
+///     wasm_module: migrated_wasm_chunk;
+///     wasm_memory: migrated_pages;
+///     stable_memory: migrated_pages;
+///     wasm_chunk_store: migrated_stored_chunk;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq)]
+pub enum CanisterMigrationData {
+    #[serde(rename = "metadata")]
+    Metadata(CanisterMigrationMetadata),
+    #[serde(rename = "wasm_module")]
+    WasmModule(MigratedWasmChunk),
+    #[serde(rename = "wasm_memory")]
+    WasmMemory(MigratedPages),
+    #[serde(rename = "stable_memory")]
+    StableMemory(MigratedPages),
+    #[serde(rename = "wasm_chunk_store")]
+    WasmChunkStore(MigratedStoredChunk),
+}
+
+impl Payload<'_> for CanisterMigrationData {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     subnet_id: principal;
+///     canister_id: principal;
+///     segment: canister_migration_segment;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct ReadCanisterMigrationDataArgs {
+    pub subnet_id: PrincipalId,
+    pub canister_id: PrincipalId,
+    pub segment: CanisterMigrationSegment,
+}
+
+impl Payload<'_> for ReadCanisterMigrationDataArgs {}
+
+impl ReadCanisterMigrationDataArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     subnet_id: principal;
+///     canister_id: principal;
+///     data: canister_migration_data;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct WriteCanisterMigrationDataArgs {
+    pub subnet_id: PrincipalId,
+    pub canister_id: PrincipalId,
+    pub data: CanisterMigrationData,
+}
+
+impl Payload<'_> for WriteCanisterMigrationDataArgs {}
+
+impl WriteCanisterMigrationDataArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     subnet_id: principal;
+///     canister_id: principal;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct RemoveMigratedCanisterArgs {
+    pub subnet_id: PrincipalId,
+    pub canister_id: PrincipalId,
+}
+
+impl Payload<'_> for RemoveMigratedCanisterArgs {}
+
+impl RemoveMigratedCanisterArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     subnet_id: principal;
+///     canister_id: principal;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct AbortCanisterMigrationArgs {
+    pub subnet_id: PrincipalId,
+    pub canister_id: PrincipalId,
+}
+
+impl Payload<'_> for AbortCanisterMigrationArgs {}
+
+impl AbortCanisterMigrationArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+}
+
+/// Struct to be returned when removing a migrated canister. Contains the
+/// cycles to be credited to the canister on the target subnet, i.e. its
+/// balance minus the migration fee.
+/// `(record {
+///     cycles: nat;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct RemoveMigratedCanisterReply {
+    pub cycles: candid::Nat,
+}
+
+impl Payload<'_> for RemoveMigratedCanisterReply {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     subnet_id: principal;
+///     canister_id: principal;
+///     cycles: nat;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct FinishCanisterMigrationArgs {
+    pub subnet_id: PrincipalId,
+    pub canister_id: PrincipalId,
+    pub cycles: candid::Nat,
+}
+
+impl Payload<'_> for FinishCanisterMigrationArgs {}
+
+impl FinishCanisterMigrationArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+}
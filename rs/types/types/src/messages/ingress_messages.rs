@@ -11,9 +11,9 @@ use crate::{
 };
 use ic_error_types::{ErrorCode, UserError};
 use ic_ic00_types::{
-    CanisterIdRecord, CanisterInfoRequest, ClearChunkStoreArgs, InstallChunkedCodeArgs,
-    InstallCodeArgsV2, Method, Payload, StoredChunksArgs, UpdateSettingsArgs, UploadChunkArgs,
-    IC_00,
+    CanisterHistoryArgs, CanisterIdRecord, CanisterInfoRequest, ClearChunkStoreArgs,
+    InstallChunkedCodeArgs, InstallCodeArgsV2, Method, Payload, StoredChunksArgs,
+    UpdateSettingsArgs, UploadChunkArgs, IC_00,
 };
 use ic_protobuf::{
     log::ingress_message_log_entry::v1::IngressMessageLogEntry,
@@ -477,6 +477,9 @@ pub fn extract_effective_canister_id(
         }
         Ok(Method::StartCanister)
         | Ok(Method::CanisterStatus)
+        | Ok(Method::AbortInstallCode)
+        | Ok(Method::CanisterMethodStats)
+        | Ok(Method::ResetCanisterMethodStats)
         | Ok(Method::DeleteCanister)
         | Ok(Method::UninstallCode)
         | Ok(Method::StopCanister) => match CanisterIdRecord::decode(ingress.arg()) {
@@ -487,6 +490,10 @@ pub fn extract_effective_canister_id(
             Ok(record) => Ok(Some(record.canister_id())),
             Err(err) => Err(ParseIngressError::InvalidSubnetPayload(err.to_string())),
         },
+        Ok(Method::CanisterHistory) => match CanisterHistoryArgs::decode(ingress.arg()) {
+            Ok(record) => Ok(Some(record.canister_id())),
+            Err(err) => Err(ParseIngressError::InvalidSubnetPayload(err.to_string())),
+        },
         Ok(Method::UpdateSettings) => match UpdateSettingsArgs::decode(ingress.arg()) {
             Ok(record) => Ok(Some(record.get_canister_id())),
             Err(err) => Err(ParseIngressError::InvalidSubnetPayload(err.to_string())),
@@ -526,7 +533,12 @@ pub fn extract_effective_canister_id(
         | Ok(Method::BitcoinSendTransactionInternal)
         | Ok(Method::BitcoinGetSuccessors)
         | Ok(Method::BitcoinGetCurrentFeePercentiles)
-        | Ok(Method::NodeMetricsHistory) => {
+        | Ok(Method::NodeMetricsHistory)
+        | Ok(Method::ReadCanisterMigrationData)
+        | Ok(Method::WriteCanisterMigrationData)
+        | Ok(Method::RemoveMigratedCanister)
+        | Ok(Method::FinishCanisterMigration)
+        | Ok(Method::AbortCanisterMigration) => {
             // Subnet method not allowed for ingress.
             Err(ParseIngressError::SubnetMethodNotAllowed)
         }
@@ -3,9 +3,9 @@ use ic_error_types::{RejectCode, TryFromError, UserError};
 #[cfg(test)]
 use ic_exhaustive_derive::ExhaustiveSet;
 use ic_ic00_types::{
-    CanisterIdRecord, CanisterInfoRequest, ClearChunkStoreArgs, InstallChunkedCodeArgs,
-    InstallCodeArgsV2, Method, Payload as _, ProvisionalTopUpCanisterArgs, StoredChunksArgs,
-    UpdateSettingsArgs, UploadChunkArgs,
+    CanisterHistoryArgs, CanisterIdRecord, CanisterInfoRequest, ClearChunkStoreArgs,
+    InstallChunkedCodeArgs, InstallCodeArgsV2, Method, Payload as _, ProvisionalTopUpCanisterArgs,
+    StoredChunksArgs, UpdateSettingsArgs, UploadChunkArgs,
 };
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -98,6 +98,9 @@ impl Request {
             Ok(Method::ProvisionalCreateCanisterWithCycles) => None,
             Ok(Method::StartCanister)
             | Ok(Method::CanisterStatus)
+            | Ok(Method::AbortInstallCode)
+            | Ok(Method::CanisterMethodStats)
+            | Ok(Method::ResetCanisterMethodStats)
             | Ok(Method::DeleteCanister)
             | Ok(Method::UninstallCode)
             | Ok(Method::DepositCycles)
@@ -109,6 +112,12 @@ impl Request {
                 Ok(record) => Some(record.canister_id()),
                 Err(_) => None,
             },
+            Ok(Method::CanisterHistory) => {
+                match CanisterHistoryArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.canister_id()),
+                    Err(_) => None,
+                }
+            }
             Ok(Method::UpdateSettings) => match UpdateSettingsArgs::decode(&self.method_payload) {
                 Ok(record) => Some(record.get_canister_id()),
                 Err(_) => None,
@@ -157,7 +166,12 @@ impl Request {
             | Ok(Method::BitcoinSendTransactionInternal)
             | Ok(Method::BitcoinGetSuccessors)
             | Ok(Method::BitcoinGetCurrentFeePercentiles)
-            | Ok(Method::NodeMetricsHistory) => {
+            | Ok(Method::NodeMetricsHistory)
+            | Ok(Method::ReadCanisterMigrationData)
+            | Ok(Method::WriteCanisterMigrationData)
+            | Ok(Method::RemoveMigratedCanister)
+            | Ok(Method::FinishCanisterMigration)
+            | Ok(Method::AbortCanisterMigration) => {
                 // No effective canister id.
                 None
             }
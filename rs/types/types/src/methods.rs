@@ -248,6 +248,10 @@ pub struct Callback {
     /// An optional closure to be executed if the execution of `on_reply` or
     /// `on_reject` traps.
     pub on_cleanup: Option<WasmClosure>,
+    /// Whether the caller opted in to executing the call synchronously as a
+    /// replicated query call via `ic0.call_replicated_query`.
+    #[serde(default)]
+    pub replicated_query: bool,
 }
 
 impl Callback {
@@ -272,6 +276,7 @@ impl Callback {
             on_reply,
             on_reject,
             on_cleanup,
+            replicated_query: false,
         }
     }
 }
@@ -307,6 +312,7 @@ impl From<&Callback> for pb::Callback {
                 func_idx: on_cleanup.func_idx,
                 env: on_cleanup.env,
             }),
+            replicated_query: item.replicated_query,
         }
     }
 }
@@ -351,6 +357,7 @@ impl TryFrom<pb::Callback> for Callback {
                 func_idx: on_cleanup.func_idx,
                 env: on_cleanup.env,
             }),
+            replicated_query: value.replicated_query,
         })
     }
 }
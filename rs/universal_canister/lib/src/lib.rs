@@ -358,6 +358,19 @@ impl PayloadBuilder {
         self
     }
 
+    /// Pushes the current time (as a 64-bit integer) on top of the stack.
+    pub fn time(mut self) -> Self {
+        self.0.push(Ops::Time as u8);
+        self
+    }
+
+    /// Pushes the canister cycles balance (as a 64-bit integer) on top of
+    /// the stack.
+    pub fn cycles_balance(mut self) -> Self {
+        self.0.push(Ops::CyclesBalance as u8);
+        self
+    }
+
     /// Store data (in a global variable) on the heap.
     /// NOTE: This does _not_ correspond to a Wasm global.
     pub fn set_global_data(mut self, data: &[u8]) -> Self {